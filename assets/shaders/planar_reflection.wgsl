@@ -0,0 +1,19 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::utils
+
+@group(1) @binding(0)
+var reflection: texture_2d<f32>;
+@group(1) @binding(1)
+var reflection_sampler: sampler;
+
+@fragment
+fn fragment(
+    @builtin(position) position: vec4<f32>,
+    #import bevy_pbr::mesh_vertex_output
+) -> @location(0) vec4<f32> {
+    let uv = coords_to_viewport_uv(position.xy, view.viewport);
+    // The camera rendering the reflection is mirrored, which flips its image horizontally
+    let color = textureSample(reflection, reflection_sampler, vec2<f32>(1.0 - uv.x, uv.y));
+    // A slightly tinted mirror
+    return vec4<f32>(color.rgb * vec3<f32>(0.85, 0.9, 0.95), 1.0);
+}
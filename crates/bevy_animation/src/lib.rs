@@ -2,7 +2,7 @@
 
 #![warn(missing_docs)]
 
-use std::ops::Deref;
+use std::{ops::Deref, time::Duration};
 
 use bevy_app::{App, CoreStage, Plugin};
 use bevy_asset::{AddAsset, Assets, Handle};
@@ -18,6 +18,7 @@ use bevy_ecs::{
 use bevy_hierarchy::Children;
 use bevy_math::{Quat, Vec3};
 use bevy_reflect::{FromReflect, Reflect, TypeUuid};
+use bevy_render::mesh::morph::{inherit_morph_weights, MorphWeights};
 use bevy_time::Time;
 use bevy_transform::{prelude::Transform, TransformSystem};
 use bevy_utils::{tracing::warn, HashMap};
@@ -26,11 +27,12 @@ use bevy_utils::{tracing::warn, HashMap};
 pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
-        AnimationClip, AnimationPlayer, AnimationPlugin, EntityPath, Keyframes, VariableCurve,
+        AnimationBlendMode, AnimationClip, AnimationLayer, AnimationMask, AnimationPlayer,
+        AnimationPlugin, EntityPath, Keyframes, VariableCurve,
     };
 }
 
-/// List of keyframes for one of the attribute of a [`Transform`].
+/// List of keyframes for one of the attribute of a [`Transform`], or for the [`MorphWeights`].
 #[derive(Reflect, FromReflect, Clone, Debug)]
 pub enum Keyframes {
     /// Keyframes for rotation.
@@ -39,9 +41,13 @@ pub enum Keyframes {
     Translation(Vec<Vec3>),
     /// Keyframes for scale.
     Scale(Vec<Vec3>),
+    /// Keyframes for the weights of the morph targets of the meshes of an entity.
+    ///
+    /// The weights of all the targets are given for each keyframe, one keyframe after the other.
+    Weights(Vec<f32>),
 }
 
-/// Describes how an attribute of a [`Transform`] should be animated.
+/// Describes how an attribute of a [`Transform`], or the [`MorphWeights`], should be animated.
 ///
 /// `keyframe_timestamps` and `keyframes` should have the same length.
 #[derive(Reflect, FromReflect, Clone, Debug)]
@@ -90,21 +96,62 @@ impl AnimationClip {
     }
 }
 
-/// Animation controls
-#[derive(Component, Reflect)]
-#[reflect(Component)]
-pub struct AnimationPlayer {
-    paused: bool,
+/// Whether an [`EntityPath`] of an animation is in the subtree of one of a list of paths, to only
+/// apply an [`AnimationLayer`] to part of a hierarchy, for example the bones of the upper body of
+/// a character.
+#[derive(Reflect, FromReflect, Clone, Debug, Default)]
+pub struct AnimationMask {
+    roots: Vec<EntityPath>,
+}
+
+impl AnimationMask {
+    /// Creates a mask without any path, which contains no entity.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the entity at this `path` and all its descendants to the mask.
+    ///
+    /// The path starts with the name of the entity of the [`AnimationPlayer`], like the paths of
+    /// the curves of an [`AnimationClip`].
+    pub fn add_subtree(&mut self, path: EntityPath) -> &mut Self {
+        self.roots.push(path);
+        self
+    }
+
+    /// Whether the entity at this `path` is in the mask.
+    pub fn contains(&self, path: &EntityPath) -> bool {
+        self.roots
+            .iter()
+            .any(|root| path.parts.starts_with(&root.parts))
+    }
+}
+
+/// How an [`AnimationLayer`] is combined with the animations below it.
+#[derive(Reflect, FromReflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationBlendMode {
+    /// The animated properties are interpolated towards the values of the layer by its weight.
+    #[default]
+    Override,
+    /// The difference between the values of the layer and its first keyframes is added to the
+    /// animated properties, scaled by its weight.
+    ///
+    /// Rotations are composed instead of added, and scales multiplied.
+    Additive,
+}
+
+/// The playback state of an animation.
+#[derive(Reflect, FromReflect)]
+struct PlayingAnimation {
     repeat: bool,
     speed: f32,
     elapsed: f32,
     animation_clip: Handle<AnimationClip>,
 }
 
-impl Default for AnimationPlayer {
+impl Default for PlayingAnimation {
     fn default() -> Self {
         Self {
-            paused: false,
             repeat: false,
             speed: 1.0,
             elapsed: 0.0,
@@ -113,37 +160,162 @@ impl Default for AnimationPlayer {
     }
 }
 
+impl PlayingAnimation {
+    fn new(handle: Handle<AnimationClip>) -> Self {
+        Self {
+            animation_clip: handle,
+            ..Default::default()
+        }
+    }
+}
+
+/// An animation fading out after the [`AnimationPlayer`] switched to another one with
+/// [`AnimationPlayer::play_with_transition`].
+#[derive(Reflect, FromReflect)]
+struct AnimationTransition {
+    /// The weight the animation is blended with, decreasing from `1.0` to `0.0`.
+    current_weight: f32,
+    weight_decline_per_sec: f32,
+    animation: PlayingAnimation,
+}
+
+/// An animation played over the main animation of an [`AnimationPlayer`], added with
+/// [`AnimationPlayer::add_layer`].
+///
+/// Layers are applied in the order they were added, each one blending its clip into the
+/// properties animated by the previous ones according to its `weight`, its `blend_mode` and its
+/// `mask`.
+#[derive(Reflect, FromReflect)]
+pub struct AnimationLayer {
+    /// How much the layer affects the animated properties, usually from `0.0` to `1.0`.
+    pub weight: f32,
+    /// How the layer is combined with the animations below it.
+    pub blend_mode: AnimationBlendMode,
+    /// The entities the layer animates, all of them if `None`.
+    pub mask: Option<AnimationMask>,
+    animation: PlayingAnimation,
+}
+
+impl AnimationLayer {
+    /// Creates a layer overriding the animations below it with this clip, with a weight of
+    /// `1.0` and no mask.
+    pub fn new(handle: Handle<AnimationClip>) -> Self {
+        Self {
+            weight: 1.0,
+            blend_mode: AnimationBlendMode::Override,
+            mask: None,
+            animation: PlayingAnimation::new(handle),
+        }
+    }
+
+    /// The clip played by the layer
+    pub fn animation_clip(&self) -> &Handle<AnimationClip> {
+        &self.animation.animation_clip
+    }
+
+    /// Set the animation of the layer to repeat
+    pub fn repeat(&mut self) -> &mut Self {
+        self.animation.repeat = true;
+        self
+    }
+
+    /// Stop the animation of the layer from repeating
+    pub fn stop_repeating(&mut self) -> &mut Self {
+        self.animation.repeat = false;
+        self
+    }
+
+    /// Speed of the animation playback of the layer
+    pub fn speed(&self) -> f32 {
+        self.animation.speed
+    }
+
+    /// Set the speed of the animation playback of the layer
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        self.animation.speed = speed;
+        self
+    }
+
+    /// Time elapsed playing the animation of the layer
+    pub fn elapsed(&self) -> f32 {
+        self.animation.elapsed
+    }
+
+    /// Seek to a specific time in the animation of the layer
+    pub fn set_elapsed(&mut self, elapsed: f32) -> &mut Self {
+        self.animation.elapsed = elapsed;
+        self
+    }
+}
+
+/// Animation controls
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct AnimationPlayer {
+    paused: bool,
+    animation: PlayingAnimation,
+    transitions: Vec<AnimationTransition>,
+    layers: Vec<AnimationLayer>,
+}
+
 impl AnimationPlayer {
     /// Start playing an animation, resetting state of the player
+    ///
+    /// This also stops the transitions from the previous animations, but keeps the layers.
     pub fn start(&mut self, handle: Handle<AnimationClip>) -> &mut Self {
-        *self = Self {
-            animation_clip: handle,
-            ..Default::default()
-        };
+        self.paused = false;
+        self.animation = PlayingAnimation::new(handle);
+        self.transitions.clear();
         self
     }
 
     /// Start playing an animation, resetting state of the player, unless the requested animation is already playing.
     pub fn play(&mut self, handle: Handle<AnimationClip>) -> &mut Self {
-        if self.animation_clip != handle || self.is_paused() {
+        if self.animation.animation_clip != handle || self.is_paused() {
             self.start(handle);
         }
         self
     }
 
+    /// Start playing an animation, cross-fading from the current one over `transition_duration`,
+    /// unless the requested animation is already playing.
+    ///
+    /// The previous animations keep playing until they are faded out.
+    pub fn play_with_transition(
+        &mut self,
+        handle: Handle<AnimationClip>,
+        transition_duration: Duration,
+    ) -> &mut Self {
+        if self.animation.animation_clip != handle || self.is_paused() {
+            let animation = std::mem::replace(&mut self.animation, PlayingAnimation::new(handle));
+            self.paused = false;
+            self.transitions.push(AnimationTransition {
+                current_weight: 1.0,
+                weight_decline_per_sec: 1.0 / transition_duration.as_secs_f32().max(f32::EPSILON),
+                animation,
+            });
+        }
+        self
+    }
+
+    /// Whether the player is fading out previous animations
+    pub fn is_in_transition(&self) -> bool {
+        !self.transitions.is_empty()
+    }
+
     /// Set the animation to repeat
     pub fn repeat(&mut self) -> &mut Self {
-        self.repeat = true;
+        self.animation.repeat = true;
         self
     }
 
     /// Stop the animation from repeating
     pub fn stop_repeating(&mut self) -> &mut Self {
-        self.repeat = false;
+        self.animation.repeat = false;
         self
     }
 
-    /// Pause the animation
+    /// Pause the animation, and those of the transitions and layers
     pub fn pause(&mut self) {
         self.paused = true;
     }
@@ -160,25 +332,55 @@ impl AnimationPlayer {
 
     /// Speed of the animation playback
     pub fn speed(&self) -> f32 {
-        self.speed
+        self.animation.speed
     }
 
     /// Set the speed of the animation playback
     pub fn set_speed(&mut self, speed: f32) -> &mut Self {
-        self.speed = speed;
+        self.animation.speed = speed;
         self
     }
 
     /// Time elapsed playing the animation
     pub fn elapsed(&self) -> f32 {
-        self.elapsed
+        self.animation.elapsed
     }
 
     /// Seek to a specific time in the animation
     pub fn set_elapsed(&mut self, elapsed: f32) -> &mut Self {
-        self.elapsed = elapsed;
+        self.animation.elapsed = elapsed;
         self
     }
+
+    /// Add a layer played over the animation and the previous layers, returning its index.
+    pub fn add_layer(&mut self, layer: AnimationLayer) -> usize {
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    /// Remove the layer at this index, shifting the index of the following layers down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no layer at this index.
+    pub fn remove_layer(&mut self, index: usize) -> AnimationLayer {
+        self.layers.remove(index)
+    }
+
+    /// The layer at this index
+    pub fn layer(&self, index: usize) -> Option<&AnimationLayer> {
+        self.layers.get(index)
+    }
+
+    /// The layer at this index, to modify
+    pub fn layer_mut(&mut self, index: usize) -> Option<&mut AnimationLayer> {
+        self.layers.get_mut(index)
+    }
+
+    /// The layers, in the order they are applied
+    pub fn layers(&self) -> &[AnimationLayer] {
+        &self.layers
+    }
 }
 
 /// System that will play all animations, using any entity with a [`AnimationPlayer`]
@@ -189,111 +391,268 @@ pub fn animation_player(
     mut animation_players: Query<(Entity, &mut AnimationPlayer)>,
     names: Query<&Name>,
     mut transforms: Query<&mut Transform>,
+    mut morph_weights: Query<&mut MorphWeights>,
     children: Query<&Children>,
 ) {
     for (entity, mut player) in &mut animation_players {
-        if let Some(animation_clip) = animations.get(&player.animation_clip) {
-            // Continue if paused unless the `AnimationPlayer` was changed
-            // This allow the animation to still be updated if the player.elapsed field was manually updated in pause
-            if player.paused && !player.is_changed() {
-                continue;
-            }
-            if !player.paused {
-                player.elapsed += time.delta_seconds() * player.speed;
-            }
-            let mut elapsed = player.elapsed;
-            if player.repeat {
-                elapsed %= animation_clip.duration;
+        // Continue if paused unless the `AnimationPlayer` was changed
+        // This allow the animation to still be updated if the player.elapsed field was manually updated in pause
+        if player.paused && !player.is_changed() {
+            continue;
+        }
+        let delta = if player.paused {
+            0.0
+        } else {
+            time.delta_seconds()
+        };
+        let AnimationPlayer {
+            animation,
+            transitions,
+            layers,
+            ..
+        } = &mut *player;
+
+        // Apply the main animation
+        if let Some((animation_clip, elapsed)) = advance_animation(animation, delta, &animations) {
+            apply_animation_clip(
+                animation_clip,
+                elapsed,
+                1.0,
+                AnimationBlendMode::Override,
+                None,
+                entity,
+                &names,
+                &children,
+                &mut transforms,
+                &mut morph_weights,
+            );
+        }
+
+        // Fade out the previous animations over it
+        for transition in transitions.iter_mut() {
+            let animation = &mut transition.animation;
+            if let Some((animation_clip, elapsed)) =
+                advance_animation(animation, delta, &animations)
+            {
+                apply_animation_clip(
+                    animation_clip,
+                    elapsed,
+                    transition.current_weight,
+                    AnimationBlendMode::Override,
+                    None,
+                    entity,
+                    &names,
+                    &children,
+                    &mut transforms,
+                    &mut morph_weights,
+                );
             }
-            if elapsed < 0.0 {
-                elapsed += animation_clip.duration;
+            transition.current_weight -= transition.weight_decline_per_sec * delta;
+        }
+        transitions.retain(|transition| transition.current_weight > 0.0);
+
+        // Apply the layers over both
+        for layer in layers.iter_mut() {
+            let animation = &mut layer.animation;
+            if let Some((animation_clip, elapsed)) =
+                advance_animation(animation, delta, &animations)
+            {
+                apply_animation_clip(
+                    animation_clip,
+                    elapsed,
+                    layer.weight,
+                    layer.blend_mode,
+                    layer.mask.as_ref(),
+                    entity,
+                    &names,
+                    &children,
+                    &mut transforms,
+                    &mut morph_weights,
+                );
             }
-            'entity: for (path, curves) in &animation_clip.curves {
-                // PERF: finding the target entity can be optimised
-                let mut current_entity = entity;
-                // Ignore the first name, it is the root node which we already have
-                for part in path.parts.iter().skip(1) {
-                    let mut found = false;
-                    if let Ok(children) = children.get(current_entity) {
-                        for child in children.deref() {
-                            if let Ok(name) = names.get(*child) {
-                                if name == part {
-                                    // Found a children with the right name, continue to the next part
-                                    current_entity = *child;
-                                    found = true;
-                                    break;
-                                }
-                            }
-                        }
+        }
+    }
+}
+
+/// Advances the elapsed time of an animation by `delta` seconds, returning its clip and the time
+/// to sample it at, or `None` if the clip isn't loaded.
+fn advance_animation<'a>(
+    animation: &mut PlayingAnimation,
+    delta: f32,
+    animations: &'a Assets<AnimationClip>,
+) -> Option<(&'a AnimationClip, f32)> {
+    let animation_clip = animations.get(&animation.animation_clip)?;
+    animation.elapsed += delta * animation.speed;
+    let mut elapsed = animation.elapsed;
+    if animation.repeat {
+        elapsed %= animation_clip.duration;
+    }
+    if elapsed < 0.0 {
+        elapsed += animation_clip.duration;
+    }
+    Some((animation_clip, elapsed))
+}
+
+/// Finds the target entity of an [`EntityPath`] by the names of the descendants of the `root`.
+fn find_target(
+    root: Entity,
+    path: &EntityPath,
+    names: &Query<&Name>,
+    children: &Query<&Children>,
+) -> Option<Entity> {
+    // PERF: finding the target entity can be optimised
+    let mut current_entity = root;
+    // Ignore the first name, it is the root node which we already have
+    for part in path.parts.iter().skip(1) {
+        let mut found = false;
+        if let Ok(children) = children.get(current_entity) {
+            for child in children.deref() {
+                if let Ok(name) = names.get(*child) {
+                    if name == part {
+                        // Found a children with the right name, continue to the next part
+                        current_entity = *child;
+                        found = true;
+                        break;
                     }
-                    if !found {
-                        warn!("Entity not found for path {:?} on part {:?}", path, part);
-                        continue 'entity;
+                }
+            }
+        }
+        if !found {
+            warn!("Entity not found for path {:?} on part {:?}", path, part);
+            return None;
+        }
+    }
+    Some(current_entity)
+}
+
+/// Samples the curves of a clip at `elapsed`, and blends them into the components of the
+/// entities it targets from the `root` by `weight` and `blend_mode`.
+#[allow(clippy::too_many_arguments)]
+fn apply_animation_clip(
+    animation_clip: &AnimationClip,
+    elapsed: f32,
+    weight: f32,
+    blend_mode: AnimationBlendMode,
+    mask: Option<&AnimationMask>,
+    root: Entity,
+    names: &Query<&Name>,
+    children: &Query<&Children>,
+    transforms: &mut Query<&mut Transform>,
+    morph_weights: &mut Query<&mut MorphWeights>,
+) {
+    for (path, curves) in &animation_clip.curves {
+        if mask.map_or(false, |mask| !mask.contains(path)) {
+            continue;
+        }
+        let Some(current_entity) = find_target(root, path, names, children) else {
+            continue;
+        };
+        let mut transform = transforms.get_mut(current_entity).ok();
+        let mut morph_weights = morph_weights.get_mut(current_entity).ok();
+        if transform.is_none() && morph_weights.is_none() {
+            continue;
+        }
+        for curve in curves {
+            let Some((step_start, step_end, lerp)) = find_keyframes(curve, elapsed) else {
+                continue;
+            };
+
+            // Apply the keyframe
+            match (&curve.keyframes, &mut transform, &mut morph_weights) {
+                (Keyframes::Rotation(keyframes), Some(transform), _) => {
+                    let rot_start = keyframes[step_start];
+                    let mut rot_end = keyframes[step_end];
+                    // Choose the smallest angle for the rotation
+                    if rot_end.dot(rot_start) < 0.0 {
+                        rot_end = -rot_end;
                     }
+                    // Rotations are using a spherical linear interpolation
+                    let rotation = rot_start.normalize().slerp(rot_end.normalize(), lerp);
+                    transform.rotation = match blend_mode {
+                        AnimationBlendMode::Override => transform.rotation.slerp(rotation, weight),
+                        AnimationBlendMode::Additive => {
+                            let difference = keyframes[0].normalize().inverse() * rotation;
+                            transform.rotation * Quat::IDENTITY.slerp(difference, weight)
+                        }
+                    };
                 }
-                if let Ok(mut transform) = transforms.get_mut(current_entity) {
-                    for curve in curves {
-                        // Some curves have only one keyframe used to set a transform
-                        if curve.keyframe_timestamps.len() == 1 {
-                            match &curve.keyframes {
-                                Keyframes::Rotation(keyframes) => transform.rotation = keyframes[0],
-                                Keyframes::Translation(keyframes) => {
-                                    transform.translation = keyframes[0];
-                                }
-                                Keyframes::Scale(keyframes) => transform.scale = keyframes[0],
-                            }
-                            continue;
+                (Keyframes::Translation(keyframes), Some(transform), _) => {
+                    let translation_start = keyframes[step_start];
+                    let translation_end = keyframes[step_end];
+                    let result = translation_start.lerp(translation_end, lerp);
+                    transform.translation = match blend_mode {
+                        AnimationBlendMode::Override => transform.translation.lerp(result, weight),
+                        AnimationBlendMode::Additive => {
+                            transform.translation + (result - keyframes[0]) * weight
                         }
-
-                        // Find the current keyframe
-                        // PERF: finding the current keyframe can be optimised
-                        let step_start = match curve
-                            .keyframe_timestamps
-                            .binary_search_by(|probe| probe.partial_cmp(&elapsed).unwrap())
-                        {
-                            Ok(n) if n >= curve.keyframe_timestamps.len() - 1 => continue, // this curve is finished
-                            Ok(i) => i,
-                            Err(0) => continue, // this curve isn't started yet
-                            Err(n) if n > curve.keyframe_timestamps.len() - 1 => continue, // this curve is finished
-                            Err(i) => i - 1,
-                        };
-                        let ts_start = curve.keyframe_timestamps[step_start];
-                        let ts_end = curve.keyframe_timestamps[step_start + 1];
-                        let lerp = (elapsed - ts_start) / (ts_end - ts_start);
-
-                        // Apply the keyframe
-                        match &curve.keyframes {
-                            Keyframes::Rotation(keyframes) => {
-                                let rot_start = keyframes[step_start];
-                                let mut rot_end = keyframes[step_start + 1];
-                                // Choose the smallest angle for the rotation
-                                if rot_end.dot(rot_start) < 0.0 {
-                                    rot_end = -rot_end;
-                                }
-                                // Rotations are using a spherical linear interpolation
-                                transform.rotation =
-                                    rot_start.normalize().slerp(rot_end.normalize(), lerp);
-                            }
-                            Keyframes::Translation(keyframes) => {
-                                let translation_start = keyframes[step_start];
-                                let translation_end = keyframes[step_start + 1];
-                                let result = translation_start.lerp(translation_end, lerp);
-                                transform.translation = result;
+                    };
+                }
+                (Keyframes::Scale(keyframes), Some(transform), _) => {
+                    let scale_start = keyframes[step_start];
+                    let scale_end = keyframes[step_end];
+                    let result = scale_start.lerp(scale_end, lerp);
+                    transform.scale = match blend_mode {
+                        AnimationBlendMode::Override => transform.scale.lerp(result, weight),
+                        AnimationBlendMode::Additive => {
+                            transform.scale * Vec3::ONE.lerp(result / keyframes[0], weight)
+                        }
+                    };
+                }
+                (Keyframes::Weights(keyframes), _, Some(morph_weights)) => {
+                    // The keyframes hold the weights of every target one after the other
+                    let target_count = keyframes.len() / curve.keyframe_timestamps.len();
+                    let weights_start = &keyframes[step_start * target_count..];
+                    let weights_end = &keyframes[step_end * target_count..];
+                    for (((morph_weight, start), end), first) in morph_weights
+                        .weights_mut()
+                        .iter_mut()
+                        .zip(&weights_start[..target_count])
+                        .zip(&weights_end[..target_count])
+                        .zip(&keyframes[..target_count])
+                    {
+                        let result = start + (end - start) * lerp;
+                        *morph_weight = match blend_mode {
+                            AnimationBlendMode::Override => {
+                                *morph_weight + (result - *morph_weight) * weight
                             }
-                            Keyframes::Scale(keyframes) => {
-                                let scale_start = keyframes[step_start];
-                                let scale_end = keyframes[step_start + 1];
-                                let result = scale_start.lerp(scale_end, lerp);
-                                transform.scale = result;
+                            AnimationBlendMode::Additive => {
+                                *morph_weight + (result - first) * weight
                             }
-                        }
+                        };
                     }
                 }
+                _ => {}
             }
         }
     }
 }
 
+/// Finds the keyframes of a curve around `elapsed`, and how far it is between them, or `None` if
+/// the curve isn't started yet or is finished.
+fn find_keyframes(curve: &VariableCurve, elapsed: f32) -> Option<(usize, usize, f32)> {
+    // Some curves have only one keyframe used to set a transform or weights
+    if curve.keyframe_timestamps.len() == 1 {
+        return Some((0, 0, 0.0));
+    }
+
+    // Find the current keyframe
+    // PERF: finding the current keyframe can be optimised
+    let step_start = match curve
+        .keyframe_timestamps
+        .binary_search_by(|probe| probe.partial_cmp(&elapsed).unwrap())
+    {
+        Ok(n) if n >= curve.keyframe_timestamps.len() - 1 => return None, // this curve is finished
+        Ok(i) => i,
+        Err(0) => return None, // this curve isn't started yet
+        Err(n) if n > curve.keyframe_timestamps.len() - 1 => return None, // this curve is finished
+        Err(i) => i - 1,
+    };
+    let ts_start = curve.keyframe_timestamps[step_start];
+    let ts_end = curve.keyframe_timestamps[step_start + 1];
+    let lerp = (elapsed - ts_start) / (ts_end - ts_start);
+    Some((step_start, step_start + 1, lerp))
+}
+
 /// Adds animation support to an app
 #[derive(Default)]
 pub struct AnimationPlugin {}
@@ -305,7 +664,286 @@ impl Plugin for AnimationPlugin {
             .register_type::<AnimationPlayer>()
             .add_system_to_stage(
                 CoreStage::PostUpdate,
-                animation_player.before(TransformSystem::TransformPropagate),
+                animation_player
+                    .before(TransformSystem::TransformPropagate)
+                    .before(inherit_morph_weights),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_app::App;
+    use bevy_hierarchy::BuildWorldChildren;
+
+    fn path(parts: &[&'static str]) -> EntityPath {
+        EntityPath {
+            parts: parts.iter().map(|part| Name::new(*part)).collect(),
+        }
+    }
+
+    fn translation_curve(timestamps: &[f32], translations: &[Vec3]) -> VariableCurve {
+        VariableCurve {
+            keyframe_timestamps: timestamps.to_vec(),
+            keyframes: Keyframes::Translation(translations.to_vec()),
+        }
+    }
+
+    fn translation_clip(curves: Vec<(EntityPath, VariableCurve)>) -> AnimationClip {
+        let mut clip = AnimationClip::default();
+        for (path, curve) in curves {
+            clip.add_curve_to_path(path, curve);
+        }
+        clip
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        let mut time = Time::default();
+        time.update();
+        app.add_plugin(bevy_core::CorePlugin::default())
+            .add_plugin(bevy_asset::AssetPlugin::default())
+            .add_asset::<AnimationClip>()
+            .insert_resource(time)
+            .add_system(animation_player);
+        app
+    }
+
+    /// Runs a frame lasting `seconds`.
+    fn update(app: &mut App, seconds: f32) {
+        let mut time = app.world.resource_mut::<Time>();
+        let last_update = time.last_update().unwrap();
+        time.update_with_instant(last_update + Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn translation(app: &App, entity: Entity) -> Vec3 {
+        app.world.get::<Transform>(entity).unwrap().translation
+    }
+
+    #[test]
+    fn find_keyframes_boundaries() {
+        let curve = translation_curve(&[1.0, 2.0, 4.0], &[Vec3::ZERO; 3]);
+        assert_eq!(find_keyframes(&curve, 0.5), None);
+        assert_eq!(find_keyframes(&curve, 1.0), Some((0, 1, 0.0)));
+        assert_eq!(find_keyframes(&curve, 1.5), Some((0, 1, 0.5)));
+        assert_eq!(find_keyframes(&curve, 2.0), Some((1, 2, 0.0)));
+        assert_eq!(find_keyframes(&curve, 3.0), Some((1, 2, 0.5)));
+        assert_eq!(find_keyframes(&curve, 4.0), None);
+        assert_eq!(find_keyframes(&curve, 5.0), None);
+
+        // a single keyframe applies at any time
+        let curve = translation_curve(&[1.0], &[Vec3::ZERO]);
+        assert_eq!(find_keyframes(&curve, 0.0), Some((0, 0, 0.0)));
+        assert_eq!(find_keyframes(&curve, 10.0), Some((0, 0, 0.0)));
+    }
+
+    #[test]
+    fn mask_contains_subtrees() {
+        let mut mask = AnimationMask::new();
+        assert!(!mask.contains(&path(&["root"])));
+
+        mask.add_subtree(path(&["root", "arm"]));
+        assert!(mask.contains(&path(&["root", "arm"])));
+        assert!(mask.contains(&path(&["root", "arm", "hand"])));
+        assert!(!mask.contains(&path(&["root"])));
+        assert!(!mask.contains(&path(&["root", "leg"])));
+        assert!(!mask.contains(&path(&["other", "arm"])));
+    }
+
+    #[test]
+    fn layer_controls() {
+        let mut player = AnimationPlayer::default();
+        let mut layer = AnimationLayer::new(Handle::default());
+        assert_eq!(layer.weight, 1.0);
+        assert_eq!(layer.blend_mode, AnimationBlendMode::Override);
+        assert!(layer.mask.is_none());
+        layer.repeat().set_speed(2.0).set_elapsed(0.5);
+        assert_eq!(layer.speed(), 2.0);
+        assert_eq!(layer.elapsed(), 0.5);
+
+        assert_eq!(player.add_layer(layer), 0);
+        assert_eq!(player.add_layer(AnimationLayer::new(Handle::default())), 1);
+        player.layer_mut(1).unwrap().weight = 0.5;
+        assert_eq!(player.layers().len(), 2);
+
+        assert_eq!(player.remove_layer(0).speed(), 2.0);
+        assert_eq!(player.layer(0).unwrap().weight, 0.5);
+        assert!(player.layer(1).is_none());
+
+        // starting another animation keeps the layers
+        player.start(Handle::default());
+        assert_eq!(player.layers().len(), 1);
+    }
+
+    #[test]
+    fn transition_weight_ramps_down() {
+        let mut app = app();
+        let mut clips = app.world.resource_mut::<Assets<AnimationClip>>();
+        let from = clips.add(translation_clip(vec![(
+            path(&["root"]),
+            translation_curve(&[0.0], &[Vec3::ZERO]),
+        )]));
+        let to = clips.add(translation_clip(vec![(
+            path(&["root"]),
+            translation_curve(&[0.0], &[Vec3::X * 10.0]),
+        )]));
+
+        let mut player = AnimationPlayer::default();
+        player.play(from.clone());
+        let root = app
+            .world
+            .spawn((Name::new("root"), Transform::default(), player))
+            .id();
+        update(&mut app, 0.25);
+        assert_eq!(translation(&app, root), Vec3::ZERO);
+
+        let mut player = app.world.get_mut::<AnimationPlayer>(root).unwrap();
+        player.play_with_transition(to.clone(), Duration::from_secs(1));
+        assert!(player.is_in_transition());
+        // playing the same animation again doesn't start another transition
+        player.play_with_transition(to, Duration::from_secs(1));
+        assert_eq!(player.transitions.len(), 1);
+
+        // the previous animation is blended over the new one with a decreasing weight
+        for expected in [0.0, 2.5, 5.0, 7.5] {
+            update(&mut app, 0.25);
+            assert!(translation(&app, root).abs_diff_eq(Vec3::X * expected, 1e-5));
+        }
+        assert!(!app
+            .world
+            .get::<AnimationPlayer>(root)
+            .unwrap()
+            .is_in_transition());
+        update(&mut app, 0.25);
+        assert_eq!(translation(&app, root), Vec3::X * 10.0);
+    }
+
+    /// Plays a layer over a constant base animation, returning the resulting translation.
+    fn layered_translation(weight: f32, blend_mode: AnimationBlendMode) -> Vec3 {
+        let mut app = app();
+        let mut clips = app.world.resource_mut::<Assets<AnimationClip>>();
+        let base = clips.add(translation_clip(vec![(
+            path(&["root"]),
+            translation_curve(&[0.0], &[Vec3::X]),
+        )]));
+        let layer = clips.add(translation_clip(vec![(
+            path(&["root"]),
+            translation_curve(&[0.0, 1.0], &[Vec3::Y, Vec3::Y * 3.0]),
+        )]));
+
+        let mut player = AnimationPlayer::default();
+        player.play(base);
+        let mut layer = AnimationLayer::new(layer);
+        layer.weight = weight;
+        layer.blend_mode = blend_mode;
+        player.add_layer(layer);
+        let root = app
+            .world
+            .spawn((Name::new("root"), Transform::default(), player))
+            .id();
+        update(&mut app, 0.5);
+        translation(&app, root)
+    }
+
+    #[test]
+    fn override_and_additive_layers() {
+        use AnimationBlendMode::*;
+
+        // the layer samples (0, 2, 0) half-way through its clip
+        assert_eq!(layered_translation(1.0, Override), Vec3::Y * 2.0);
+        assert_eq!(layered_translation(0.5, Override), Vec3::new(0.5, 1.0, 0.0));
+        assert_eq!(layered_translation(0.0, Override), Vec3::X);
+
+        // only the difference from its first keyframe is added
+        assert_eq!(layered_translation(1.0, Additive), Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(layered_translation(0.5, Additive), Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(layered_translation(0.0, Additive), Vec3::X);
+    }
+
+    #[test]
+    fn additive_rotation_and_scale() {
+        let mut app = app();
+        let mut clip = AnimationClip::default();
+        clip.add_curve_to_path(
+            path(&["root"]),
+            VariableCurve {
+                keyframe_timestamps: vec![0.0, 1.0],
+                keyframes: Keyframes::Rotation(vec![
+                    Quat::from_rotation_z(0.5),
+                    Quat::from_rotation_z(1.5),
+                ]),
+            },
+        );
+        clip.add_curve_to_path(
+            path(&["root"]),
+            VariableCurve {
+                keyframe_timestamps: vec![0.0, 1.0],
+                keyframes: Keyframes::Scale(vec![Vec3::splat(2.0), Vec3::splat(6.0)]),
+            },
+        );
+        let clip = app.world.resource_mut::<Assets<AnimationClip>>().add(clip);
+
+        let mut player = AnimationPlayer::default();
+        let mut layer = AnimationLayer::new(clip);
+        layer.blend_mode = AnimationBlendMode::Additive;
+        player.add_layer(layer);
+        let root = app
+            .world
+            .spawn((
+                Name::new("root"),
+                Transform::from_rotation(Quat::from_rotation_z(1.0)).with_scale(Vec3::splat(3.0)),
+                player,
+            ))
+            .id();
+        update(&mut app, 0.5);
+
+        // half-way, the layer is rotated by 0.5 and scaled by 2 from its first keyframe
+        let transform = app.world.get::<Transform>(root).unwrap();
+        assert!(transform
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_z(1.5), 1e-5));
+        assert!(transform.scale.abs_diff_eq(Vec3::splat(6.0), 1e-5));
+    }
+
+    #[test]
+    fn mask_filters_layer() {
+        let mut app = app();
+        let clip = app
+            .world
+            .resource_mut::<Assets<AnimationClip>>()
+            .add(translation_clip(vec![
+                (
+                    path(&["root", "arm"]),
+                    translation_curve(&[0.0], &[Vec3::Y]),
+                ),
+                (
+                    path(&["root", "leg"]),
+                    translation_curve(&[0.0], &[Vec3::Y]),
+                ),
+            ]));
+
+        let mut player = AnimationPlayer::default();
+        let mut layer = AnimationLayer::new(clip);
+        let mut mask = AnimationMask::new();
+        mask.add_subtree(path(&["root", "arm"]));
+        layer.mask = Some(mask);
+        player.add_layer(layer);
+        let arm = app
+            .world
+            .spawn((Name::new("arm"), Transform::default()))
+            .id();
+        let leg = app
+            .world
+            .spawn((Name::new("leg"), Transform::default()))
+            .id();
+        app.world
+            .spawn((Name::new("root"), Transform::default(), player))
+            .push_children(&[arm, leg]);
+        update(&mut app, 0.25);
+
+        assert_eq!(translation(&app, arm), Vec3::Y);
+        assert_eq!(translation(&app, leg), Vec3::ZERO);
+    }
+}
@@ -100,7 +100,9 @@ fn downsample_prefilter(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
 
     var o: vec4<f32> = sample_13_tap(uv, scale);
 
-    o = quadratic_threshold(o, uniforms.threshold, curve);
+    if uniforms.threshold > 0.0 {
+        o = quadratic_threshold(o, uniforms.threshold, curve);
+    }
     o = max(o, vec4<f32>(0.00001));
 
     return o;
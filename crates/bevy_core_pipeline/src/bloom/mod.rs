@@ -4,7 +4,7 @@ use bevy_asset::{load_internal_asset, HandleUntyped};
 use bevy_ecs::{
     prelude::{Component, Entity},
     query::{QueryItem, QueryState, With},
-    system::{Commands, Query, Res, ResMut, Resource},
+    system::{Commands, Query, Res, Resource},
     world::{FromWorld, World},
 };
 use bevy_math::UVec2;
@@ -15,7 +15,7 @@ use bevy_render::{
         ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
         UniformComponentPlugin,
     },
-    prelude::Camera,
+    prelude::{Camera, Color},
     render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
     render_phase::TrackedRenderPass,
     render_resource::*,
@@ -27,7 +27,6 @@ use bevy_render::{
 #[cfg(feature = "trace")]
 use bevy_utils::tracing::info_span;
 use bevy_utils::HashMap;
-use std::num::NonZeroU32;
 
 const BLOOM_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 929599476923908);
@@ -38,7 +37,8 @@ impl Plugin for BloomPlugin {
     fn build(&self, app: &mut App) {
         load_internal_asset!(app, BLOOM_SHADER_HANDLE, "bloom.wgsl", Shader::from_wgsl);
 
-        app.register_type::<BloomSettings>();
+        app.register_type::<BloomSettings>()
+            .register_type::<BloomCompositeMode>();
         app.add_plugin(ExtractComponentPlugin::<BloomSettings>::default());
         app.add_plugin(UniformComponentPlugin::<BloomUniform>::default());
 
@@ -118,7 +118,10 @@ impl Plugin for BloomPlugin {
 pub struct BloomSettings {
     /// Baseline of the threshold curve (default: 1.0).
     ///
-    /// RGB values under the threshold curve will not have bloom applied.
+    /// RGB values under the threshold curve will not have bloom applied. No threshold is applied
+    /// when it is `0.0` or less, which is what real lenses do: all the light is scattered, not
+    /// only the brightest. This is usually combined with
+    /// [`BloomCompositeMode::EnergyConserving`].
     pub threshold: f32,
 
     /// Knee of the threshold curve (default: 0.1).
@@ -129,6 +132,9 @@ pub struct BloomSettings {
 
     /// Intensity of the bloom effect (default: 0.3).
     pub intensity: f32,
+
+    /// How the bloom is combined with the image (default: [`BloomCompositeMode::Additive`]).
+    pub composite_mode: BloomCompositeMode,
 }
 
 impl Default for BloomSettings {
@@ -138,15 +144,32 @@ impl Default for BloomSettings {
             knee: 0.1,
             scale: 1.0,
             intensity: 0.3,
+            composite_mode: BloomCompositeMode::Additive,
         }
     }
 }
 
+/// How the bloom of a [`BloomSettings`] is combined with the image of its camera.
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BloomCompositeMode {
+    /// The image is blended with its bloom by the [`intensity`](BloomSettings::intensity), so
+    /// that the light spread around is taken from where it comes from instead of being added.
+    ///
+    /// Without a [`threshold`](BloomSettings::threshold), this keeps the overall brightness of
+    /// the image.
+    EnergyConserving,
+    /// The bloom scaled by the [`intensity`](BloomSettings::intensity) is added to the image,
+    /// which makes it brighter. Use it with a [`threshold`](BloomSettings::threshold) to only make
+    /// bright areas glow.
+    #[default]
+    Additive,
+}
+
 impl ExtractComponent for BloomSettings {
     type Query = (&'static Self, &'static Camera);
 
     type Filter = ();
-    type Out = BloomUniform;
+    type Out = (BloomUniform, BloomCompositeMode);
 
     fn extract_component((settings, camera): QueryItem<'_, Self::Query>) -> Option<Self::Out> {
         if !(camera.is_active && camera.hdr) {
@@ -158,12 +181,15 @@ impl ExtractComponent for BloomSettings {
             let mip_count = calculate_mip_count(min_view);
             let scale = (min_view / 2u32.pow(mip_count)) as f32 / 8.0;
 
-            BloomUniform {
-                threshold: settings.threshold,
-                knee: settings.knee,
-                scale: settings.scale * scale,
-                intensity: settings.intensity,
-            }
+            (
+                BloomUniform {
+                    threshold: settings.threshold,
+                    knee: settings.knee,
+                    scale: settings.scale * scale,
+                    intensity: settings.intensity,
+                },
+                settings.composite_mode,
+            )
         })
     }
 }
@@ -174,6 +200,8 @@ pub struct BloomNode {
         &'static ViewTarget,
         &'static BloomTextures,
         &'static BloomBindGroups,
+        &'static BloomUniform,
+        &'static BloomCompositeMode,
         &'static DynamicUniformIndex<BloomUniform>,
     )>,
 }
@@ -209,7 +237,7 @@ impl Node for BloomNode {
         let pipelines = world.resource::<BloomPipelines>();
         let pipeline_cache = world.resource::<PipelineCache>();
         let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
-        let (camera, view_target, textures, bind_groups, uniform_index) =
+        let (camera, view_target, textures, bind_groups, uniform, composite_mode, uniform_index) =
             match self.view_query.get_manual(world, view_entity) {
                 Ok(result) => result,
                 _ => return Ok(()),
@@ -230,7 +258,7 @@ impl Node for BloomNode {
         };
 
         {
-            let view = &BloomTextures::texture_view(&textures.texture_a, 0);
+            let view = BloomTextures::texture_view(&textures.texture_a, 0);
             let mut prefilter_pass =
                 TrackedRenderPass::new(render_context.command_encoder.begin_render_pass(
                     &RenderPassDescriptor {
@@ -256,7 +284,7 @@ impl Node for BloomNode {
         }
 
         for mip in 1..textures.mip_count {
-            let view = &BloomTextures::texture_view(&textures.texture_a, mip);
+            let view = BloomTextures::texture_view(&textures.texture_a, mip);
             let mut downsampling_pass =
                 TrackedRenderPass::new(render_context.command_encoder.begin_render_pass(
                     &RenderPassDescriptor {
@@ -282,7 +310,7 @@ impl Node for BloomNode {
         }
 
         for mip in (1..textures.mip_count).rev() {
-            let view = &BloomTextures::texture_view(&textures.texture_b, mip - 1);
+            let view = BloomTextures::texture_view(&textures.texture_b, mip - 1);
             let mut upsampling_pass =
                 TrackedRenderPass::new(render_context.command_encoder.begin_render_pass(
                     &RenderPassDescriptor {
@@ -322,6 +350,17 @@ impl Node for BloomNode {
                     },
                 ));
             upsampling_final_pass.set_render_pipeline(upsampling_final_pipeline);
+            // The image is scaled by one minus the blend constant before the bloom is added
+            let image_weight_loss = match composite_mode {
+                BloomCompositeMode::EnergyConserving => uniform.intensity,
+                BloomCompositeMode::Additive => 0.0,
+            };
+            upsampling_final_pass.set_blend_constant(Color::rgba_linear(
+                image_weight_loss,
+                image_weight_loss,
+                image_weight_loss,
+                image_weight_loss,
+            ));
             upsampling_final_pass.set_bind_group(
                 0,
                 &bind_groups.upsampling_final_bind_group,
@@ -449,6 +488,7 @@ impl FromWorld for BloomPipelines {
             pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
                 label: Some("bloom_downsampling_prefilter_pipeline".into()),
                 layout: Some(vec![downsampling_bind_group_layout.clone()]),
+                push_constant_ranges: Vec::new(),
                 vertex: fullscreen_shader_vertex_state(),
                 fragment: Some(FragmentState {
                     shader: BLOOM_SHADER_HANDLE.typed::<Shader>(),
@@ -469,6 +509,7 @@ impl FromWorld for BloomPipelines {
             pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
                 label: Some("bloom_downsampling_pipeline".into()),
                 layout: Some(vec![downsampling_bind_group_layout.clone()]),
+                push_constant_ranges: Vec::new(),
                 vertex: fullscreen_shader_vertex_state(),
                 fragment: Some(FragmentState {
                     shader: BLOOM_SHADER_HANDLE.typed::<Shader>(),
@@ -488,6 +529,7 @@ impl FromWorld for BloomPipelines {
         let upsampling_pipeline = pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
             label: Some("bloom_upsampling_pipeline".into()),
             layout: Some(vec![upsampling_bind_group_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: BLOOM_SHADER_HANDLE.typed::<Shader>(),
@@ -508,6 +550,7 @@ impl FromWorld for BloomPipelines {
             pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
                 label: Some("bloom_upsampling_final_pipeline".into()),
                 layout: Some(vec![downsampling_bind_group_layout.clone()]),
+                push_constant_ranges: Vec::new(),
                 vertex: fullscreen_shader_vertex_state(),
                 fragment: Some(FragmentState {
                     shader: BLOOM_SHADER_HANDLE.typed::<Shader>(),
@@ -516,9 +559,10 @@ impl FromWorld for BloomPipelines {
                     targets: vec![Some(ColorTargetState {
                         format: ViewTarget::TEXTURE_FORMAT_HDR,
                         blend: Some(BlendState {
+                            // The bloom is already scaled by the intensity in the shader
                             color: BlendComponent {
                                 src_factor: BlendFactor::One,
-                                dst_factor: BlendFactor::One,
+                                dst_factor: BlendFactor::OneMinusConstant,
                                 operation: BlendOperation::Add,
                             },
                             alpha: BlendComponent::REPLACE,
@@ -543,26 +587,26 @@ impl FromWorld for BloomPipelines {
     }
 }
 
+/// The mip chains the bloom is downsampled and upsampled through.
+///
+/// Each level is a separate texture rather than a mip level of a single texture, as some backends
+/// can't render to a mip level of a texture while sampling another level of it.
 #[derive(Component)]
 struct BloomTextures {
-    texture_a: CachedTexture,
-    texture_b: CachedTexture,
+    texture_a: Box<[CachedTexture]>,
+    texture_b: Box<[CachedTexture]>,
     mip_count: u32,
 }
 
 impl BloomTextures {
-    fn texture_view(texture: &CachedTexture, base_mip_level: u32) -> TextureView {
-        texture.texture.create_view(&TextureViewDescriptor {
-            base_mip_level,
-            mip_level_count: Some(unsafe { NonZeroU32::new_unchecked(1) }),
-            ..Default::default()
-        })
+    fn texture_view(textures: &[CachedTexture], mip: u32) -> &TextureView {
+        &textures[mip as usize].default_view
     }
 }
 
 fn prepare_bloom_textures(
     mut commands: Commands,
-    mut texture_cache: ResMut<TextureCache>,
+    texture_cache: Res<TextureCache>,
     render_device: Res<RenderDevice>,
     views: Query<(Entity, &ExtractedCamera), With<BloomUniform>>,
 ) {
@@ -577,30 +621,37 @@ fn prepare_bloom_textures(
             let min_view = width.min(height) / 2;
             let mip_count = calculate_mip_count(min_view);
 
-            let mut texture_descriptor = TextureDescriptor {
-                label: None,
-                size: Extent3d {
-                    width: (width / 2).max(1),
-                    height: (height / 2).max(1),
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: mip_count,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: ViewTarget::TEXTURE_FORMAT_HDR,
-                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            let mip_chain = |label| -> Box<[CachedTexture]> {
+                (0..mip_count)
+                    .map(|mip| {
+                        texture_cache.get(
+                            &render_device,
+                            TextureDescriptor {
+                                label: Some(label),
+                                size: Extent3d {
+                                    width: ((width / 2) >> mip).max(1),
+                                    height: ((height / 2) >> mip).max(1),
+                                    depth_or_array_layers: 1,
+                                },
+                                mip_level_count: 1,
+                                sample_count: 1,
+                                dimension: TextureDimension::D2,
+                                format: ViewTarget::TEXTURE_FORMAT_HDR,
+                                usage: TextureUsages::RENDER_ATTACHMENT
+                                    | TextureUsages::TEXTURE_BINDING,
+                            },
+                        )
+                    })
+                    .collect()
             };
 
-            texture_descriptor.label = Some("bloom_texture_a");
             let texture_a = texture_as
                 .entry(camera.target.clone())
-                .or_insert_with(|| texture_cache.get(&render_device, texture_descriptor.clone()))
+                .or_insert_with(|| mip_chain("bloom_texture_a"))
                 .clone();
-
-            texture_descriptor.label = Some("bloom_texture_b");
             let texture_b = texture_bs
                 .entry(camera.target.clone())
-                .or_insert_with(|| texture_cache.get(&render_device, texture_descriptor))
+                .or_insert_with(|| mip_chain("bloom_texture_b"))
                 .clone();
 
             commands.entity(entity).insert(BloomTextures {
@@ -669,7 +720,7 @@ fn queue_bloom_bind_groups(
                     entries: &[
                         BindGroupEntry {
                             binding: 0,
-                            resource: BindingResource::TextureView(&BloomTextures::texture_view(
+                            resource: BindingResource::TextureView(BloomTextures::texture_view(
                                 &textures.texture_a,
                                 mip - 1,
                             )),
@@ -706,7 +757,7 @@ fn queue_bloom_bind_groups(
                     entries: &[
                         BindGroupEntry {
                             binding: 0,
-                            resource: BindingResource::TextureView(&org),
+                            resource: BindingResource::TextureView(org),
                         },
                         BindGroupEntry {
                             binding: 1,
@@ -718,7 +769,7 @@ fn queue_bloom_bind_groups(
                         },
                         BindGroupEntry {
                             binding: 3,
-                            resource: BindingResource::TextureView(&up),
+                            resource: BindingResource::TextureView(up),
                         },
                     ],
                 });
@@ -733,7 +784,7 @@ fn queue_bloom_bind_groups(
                     entries: &[
                         BindGroupEntry {
                             binding: 0,
-                            resource: BindingResource::TextureView(&BloomTextures::texture_view(
+                            resource: BindingResource::TextureView(BloomTextures::texture_view(
                                 &textures.texture_b,
                                 0,
                             )),
@@ -4,12 +4,22 @@ use bevy_reflect::{Reflect, ReflectDeserialize, ReflectSerialize};
 use bevy_render::{color::Color, extract_resource::ExtractResource};
 use serde::{Deserialize, Serialize};
 
+/// How a camera clears its render target before drawing, set per camera with
+/// [`Camera2d::clear_color`](crate::core_2d::Camera2d::clear_color) and
+/// [`Camera3d::clear_color`](crate::core_3d::Camera3d::clear_color).
+///
+/// The cameras drawing to the same target with the same `hdr` setting share its textures, so a
+/// camera drawn after another one with [`ClearColorConfig::None`] draws over its output, like a UI
+/// camera over a 3d scene.
 #[derive(Reflect, Serialize, Deserialize, Clone, Debug, Default)]
 #[reflect(Serialize, Deserialize)]
 pub enum ClearColorConfig {
+    /// Clears with the color of the [`ClearColor`] resource.
     #[default]
     Default,
+    /// Clears with this color instead of the [`ClearColor`] resource.
     Custom(Color),
+    /// Doesn't clear, keeping what was drawn to the target before.
     None,
 }
 
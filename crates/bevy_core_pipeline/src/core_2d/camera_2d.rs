@@ -12,6 +12,7 @@ use bevy_transform::prelude::{GlobalTransform, Transform};
 #[derive(Component, Default, Reflect, Clone)]
 #[reflect(Component)]
 pub struct Camera2d {
+    /// The clear color operation to perform for the main 2d pass.
     pub clear_color: ClearColorConfig,
 }
 
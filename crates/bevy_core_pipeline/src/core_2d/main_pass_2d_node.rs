@@ -87,6 +87,9 @@ impl Node for MainPass2dNode {
             let mut tracked_pass = TrackedRenderPass::new(render_pass);
             if let Some(viewport) = camera.viewport.as_ref() {
                 tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
             }
             for item in &transparent_phase.items {
                 let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
@@ -8,6 +8,7 @@ pub mod graph {
     }
     pub mod node {
         pub const MAIN_PASS: &str = "main_pass";
+        pub const LIGHT_2D: &str = "light_2d";
         pub const BLOOM: &str = "bloom";
         pub const TONEMAPPING: &str = "tonemapping";
         pub const FXAA: &str = "fxaa";
@@ -26,8 +27,8 @@ use bevy_render::{
     extract_component::ExtractComponentPlugin,
     render_graph::{EmptyNode, RenderGraph, SlotInfo, SlotType},
     render_phase::{
-        batch_phase_system, sort_phase_system, BatchedPhaseItem, CachedRenderPipelinePhaseItem,
-        DrawFunctionId, DrawFunctions, EntityPhaseItem, PhaseItem, RenderPhase,
+        batch_phase_system, sort_phase_system, AddRenderPhase, BatchedPhaseItem,
+        CachedRenderPipelinePhaseItem, DrawFunctionId, EntityPhaseItem, PhaseItem, RenderPhase,
     },
     render_resource::CachedRenderPipelineId,
     Extract, RenderApp, RenderStage,
@@ -50,9 +51,8 @@ impl Plugin for Core2dPlugin {
         };
 
         render_app
-            .init_resource::<DrawFunctions<Transparent2d>>()
+            .add_render_phase::<Transparent2d>()
             .add_system_to_stage(RenderStage::Extract, extract_core_2d_camera_phases)
-            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<Transparent2d>)
             .add_system_to_stage(
                 RenderStage::PhaseSort,
                 batch_phase_system::<Transparent2d>.after(sort_phase_system::<Transparent2d>),
@@ -1,15 +1,19 @@
 use crate::{
     clear_color::{ClearColor, ClearColorConfig},
-    core_3d::{AlphaMask3d, Camera3d, Opaque3d, Transparent3d},
+    core_3d::{AlphaMask3d, Camera3d, Decal3d, Opaque3d, Transparent3d},
+    prepass::ViewPrepassTextures,
+    skybox::{SkyboxBindGroup, SkyboxPipelineId},
 };
 use bevy_ecs::prelude::*;
 use bevy_render::{
     camera::ExtractedCamera,
     render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
     render_phase::{DrawFunctions, RenderPhase, TrackedRenderPass},
-    render_resource::{LoadOp, Operations, RenderPassDepthStencilAttachment, RenderPassDescriptor},
+    render_resource::{
+        LoadOp, Operations, PipelineCache, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+    },
     renderer::RenderContext,
-    view::{ExtractedView, ViewDepthTexture, ViewTarget},
+    view::{ExtractedView, ViewDepthTexture, ViewTarget, ViewUniformOffset},
 };
 #[cfg(feature = "trace")]
 use bevy_utils::tracing::info_span;
@@ -21,9 +25,13 @@ pub struct MainPass3dNode {
             &'static RenderPhase<Opaque3d>,
             &'static RenderPhase<AlphaMask3d>,
             &'static RenderPhase<Transparent3d>,
+            Option<&'static RenderPhase<Decal3d>>,
             &'static Camera3d,
             &'static ViewTarget,
             &'static ViewDepthTexture,
+            &'static ViewUniformOffset,
+            Option<(&'static SkyboxPipelineId, &'static SkyboxBindGroup)>,
+            Option<&'static ViewPrepassTextures>,
         ),
         With<ExtractedView>,
     >,
@@ -55,13 +63,24 @@ impl Node for MainPass3dNode {
         world: &World,
     ) -> Result<(), NodeRunError> {
         let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
-        let (camera, opaque_phase, alpha_mask_phase, transparent_phase, camera_3d, target, depth) =
-            match self.query.get_manual(world, view_entity) {
-                Ok(query) => query,
-                Err(_) => {
-                    return Ok(());
-                } // No window
-            };
+        let (
+            camera,
+            opaque_phase,
+            alpha_mask_phase,
+            transparent_phase,
+            decal_phase,
+            camera_3d,
+            target,
+            depth,
+            view_uniform_offset,
+            skybox,
+            prepass_textures,
+        ) = match self.query.get_manual(world, view_entity) {
+            Ok(query) => query,
+            Err(_) => {
+                return Ok(());
+            } // No window
+        };
 
         // Always run opaque pass to ensure screen is cleared
         {
@@ -87,8 +106,15 @@ impl Node for MainPass3dNode {
                     view: &depth.view,
                     // NOTE: The opaque main pass loads the depth buffer and possibly overwrites it
                     depth_ops: Some(Operations {
+                        // NOTE: The prepass already cleared the depth buffer and wrote the depth of
+                        // the opaque meshes, which fail the `GreaterEqual` depth test everywhere
+                        // but on their visible fragments.
                         // NOTE: 0.0 is the far plane due to bevy's use of reverse-z projections.
-                        load: camera_3d.depth_load_op.clone().into(),
+                        load: if prepass_textures.is_some() {
+                            LoadOp::Load
+                        } else {
+                            camera_3d.depth_load_op.clone().into()
+                        },
                         store: true,
                     }),
                     stencil_ops: None,
@@ -104,6 +130,9 @@ impl Node for MainPass3dNode {
             let mut tracked_pass = TrackedRenderPass::new(render_pass);
             if let Some(viewport) = camera.viewport.as_ref() {
                 tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
             }
             for item in &opaque_phase.items {
                 let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
@@ -143,6 +172,9 @@ impl Node for MainPass3dNode {
             let mut tracked_pass = TrackedRenderPass::new(render_pass);
             if let Some(viewport) = camera.viewport.as_ref() {
                 tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
             }
             for item in &alpha_mask_phase.items {
                 let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
@@ -150,6 +182,94 @@ impl Node for MainPass3dNode {
             }
         }
 
+        // Draw the skybox where the opaque and alpha mask passes left the depth buffer cleared,
+        // before the transparent pass blends over it
+        let skybox_pipeline = skybox.and_then(|(pipeline_id, bind_group)| {
+            let pipeline_cache = world.resource::<PipelineCache>();
+            Some((
+                pipeline_cache.get_render_pipeline(pipeline_id.0)?,
+                bind_group,
+            ))
+        });
+        if let Some((pipeline, bind_group)) = skybox_pipeline {
+            #[cfg(feature = "trace")]
+            let _main_skybox_pass_3d_span = info_span!("main_skybox_pass_3d").entered();
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("main_skybox_pass_3d"),
+                color_attachments: &[Some(target.get_color_attachment(Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                }))],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &depth.view,
+                    // NOTE: The skybox pass only tests against the depth buffer, it keeps the
+                    // depth of the far plane where it is drawn.
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            };
+
+            let render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
+            }
+            tracked_pass.set_render_pipeline(pipeline);
+            tracked_pass.set_bind_group(0, &bind_group.0, &[view_uniform_offset.offset]);
+            tracked_pass.draw(0..3, 0..1);
+        }
+
+        if let Some(decal_phase) = decal_phase.filter(|phase| !phase.items.is_empty()) {
+            // Run the decal pass, sorted back-to-front
+            // NOTE: Scoped to drop the mutable borrow of render_context
+            #[cfg(feature = "trace")]
+            let _main_decal_pass_3d_span = info_span!("main_decal_pass_3d").entered();
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("main_decal_pass_3d"),
+                // NOTE: The decal pass loads the color buffer as well as blending over it.
+                color_attachments: &[Some(target.get_color_attachment(Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                }))],
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &depth.view,
+                    // NOTE: The decals only test against the depth buffer, but store is set to
+                    // `true` so that wgpu does not clear it for the transparent pass.
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            };
+
+            let draw_functions = world.resource::<DrawFunctions<Decal3d>>();
+
+            let render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+            let mut draw_functions = draw_functions.write();
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
+            }
+            for item in &decal_phase.items {
+                let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+                draw_function.draw(world, &mut tracked_pass, view_entity, item);
+            }
+        }
+
         if !transparent_phase.items.is_empty() {
             // Run the transparent pass, sorted back-to-front
             // NOTE: Scoped to drop the mutable borrow of render_context
@@ -187,6 +307,9 @@ impl Node for MainPass3dNode {
             let mut tracked_pass = TrackedRenderPass::new(render_pass);
             if let Some(viewport) = camera.viewport.as_ref() {
                 tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
             }
             for item in &transparent_phase.items {
                 let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
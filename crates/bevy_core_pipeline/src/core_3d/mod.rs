@@ -7,7 +7,11 @@ pub mod graph {
         pub const VIEW_ENTITY: &str = "view_entity";
     }
     pub mod node {
+        pub const PREPASS: &str = "prepass";
         pub const MAIN_PASS: &str = "main_pass";
+        pub const TAA: &str = "taa";
+        pub const MOTION_BLUR: &str = "motion_blur";
+        pub const DEPTH_OF_FIELD: &str = "depth_of_field";
         pub const BLOOM: &str = "bloom";
         pub const TONEMAPPING: &str = "tonemapping";
         pub const FXAA: &str = "fxaa";
@@ -16,20 +20,21 @@ pub mod graph {
     }
 }
 
-use std::cmp::Reverse;
+use std::{cmp::Reverse, ops::Range};
 
 pub use camera_3d::*;
 pub use main_pass_3d_node::*;
 
 use bevy_app::{App, Plugin};
 use bevy_ecs::prelude::*;
+use bevy_math::Mat4;
 use bevy_render::{
     camera::{Camera, ExtractedCamera},
-    extract_component::ExtractComponentPlugin,
+    extract_component::{ExtractComponentPlugin, UniformComponentPlugin},
     prelude::Msaa,
     render_graph::{EmptyNode, RenderGraph, SlotInfo, SlotType},
     render_phase::{
-        sort_phase_system, CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions,
+        AddRenderPhase, BatchedPhaseItem, CachedRenderPipelinePhaseItem, DrawFunctionId,
         EntityPhaseItem, PhaseItem, RenderPhase,
     },
     render_resource::{
@@ -41,9 +46,18 @@ use bevy_render::{
     view::ViewDepthTexture,
     Extract, RenderApp, RenderStage,
 };
-use bevy_utils::{FloatOrd, HashMap};
-
-use crate::{tonemapping::TonemappingNode, upscaling::UpscalingNode};
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::{FloatOrd, HashMap, HashSet};
+
+use crate::{
+    prepass::{
+        DepthPrepass, MotionVectorPrepass, NormalPrepass, Opaque3dPrepass, PrepassNode,
+        PreviousViewProjection, ViewPrepassTextures, DEPTH_PREPASS_FORMAT,
+        MOTION_VECTOR_PREPASS_FORMAT, NORMAL_PREPASS_FORMAT,
+    },
+    tonemapping::TonemappingNode,
+    upscaling::UpscalingNode,
+};
 
 pub struct Core3dPlugin;
 
@@ -51,7 +65,11 @@ impl Plugin for Core3dPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Camera3d>()
             .register_type::<Camera3dDepthLoadOp>()
-            .add_plugin(ExtractComponentPlugin::<Camera3d>::default());
+            .register_type::<DepthPrepass>()
+            .register_type::<NormalPrepass>()
+            .register_type::<MotionVectorPrepass>()
+            .add_plugin(ExtractComponentPlugin::<Camera3d>::default())
+            .add_plugin(UniformComponentPlugin::<PreviousViewProjection>::default());
 
         let render_app = match app.get_sub_app_mut(RenderApp) {
             Ok(render_app) => render_app,
@@ -59,21 +77,24 @@ impl Plugin for Core3dPlugin {
         };
 
         render_app
-            .init_resource::<DrawFunctions<Opaque3d>>()
-            .init_resource::<DrawFunctions<AlphaMask3d>>()
-            .init_resource::<DrawFunctions<Transparent3d>>()
+            .add_render_phase::<Opaque3d>()
+            .add_render_phase::<AlphaMask3d>()
+            .add_render_phase::<Decal3d>()
+            .add_render_phase::<Transparent3d>()
+            .add_render_phase::<Opaque3dPrepass>()
             .add_system_to_stage(RenderStage::Extract, extract_core_3d_camera_phases)
+            .add_system_to_stage(RenderStage::Extract, extract_previous_view_projections)
             .add_system_to_stage(RenderStage::Prepare, prepare_core_3d_depth_textures)
-            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<Opaque3d>)
-            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<AlphaMask3d>)
-            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<Transparent3d>);
+            .add_system_to_stage(RenderStage::Prepare, prepare_prepass_textures);
 
+        let prepass_node = PrepassNode::new(&mut render_app.world);
         let pass_node_3d = MainPass3dNode::new(&mut render_app.world);
         let tonemapping = TonemappingNode::new(&mut render_app.world);
         let upscaling = UpscalingNode::new(&mut render_app.world);
         let mut graph = render_app.world.resource_mut::<RenderGraph>();
 
         let mut draw_3d_graph = RenderGraph::default();
+        draw_3d_graph.add_node(graph::node::PREPASS, prepass_node);
         draw_3d_graph.add_node(graph::node::MAIN_PASS, pass_node_3d);
         draw_3d_graph.add_node(graph::node::TONEMAPPING, tonemapping);
         draw_3d_graph.add_node(graph::node::END_MAIN_PASS_POST_PROCESSING, EmptyNode);
@@ -82,6 +103,12 @@ impl Plugin for Core3dPlugin {
             graph::input::VIEW_ENTITY,
             SlotType::Entity,
         )]);
+        draw_3d_graph.add_slot_edge(
+            input_node_id,
+            graph::input::VIEW_ENTITY,
+            graph::node::PREPASS,
+            PrepassNode::IN_VIEW,
+        );
         draw_3d_graph.add_slot_edge(
             input_node_id,
             graph::input::VIEW_ENTITY,
@@ -100,6 +127,7 @@ impl Plugin for Core3dPlugin {
             graph::node::UPSCALING,
             UpscalingNode::IN_VIEW,
         );
+        draw_3d_graph.add_node_edge(graph::node::PREPASS, graph::node::MAIN_PASS);
         draw_3d_graph.add_node_edge(graph::node::MAIN_PASS, graph::node::TONEMAPPING);
         draw_3d_graph.add_node_edge(
             graph::node::TONEMAPPING,
@@ -118,6 +146,8 @@ pub struct Opaque3d {
     pub pipeline: CachedRenderPipelineId,
     pub entity: Entity,
     pub draw_function: DrawFunctionId,
+    /// Range in the instance buffer of this item
+    pub batch_range: Option<Range<u32>>,
 }
 
 impl PhaseItem for Opaque3d {
@@ -141,6 +171,18 @@ impl PhaseItem for Opaque3d {
     }
 }
 
+impl Opaque3d {
+    /// Sorts the items by pipeline and draw function, and then front to back, which minimizes the
+    /// state changes between draws at the cost of more overdraw than the default front to back
+    /// sort. It is used in place of [`PhaseItem::sort`] by calling
+    /// `render_app.set_phase_sort::<Opaque3d>(Opaque3d::sort_by_pipeline)`.
+    pub fn sort_by_pipeline(items: &mut [Self]) {
+        // Stable, so the items sharing a pipeline stay sorted front to back
+        radsort::sort_by_key(items, |item| -item.distance);
+        items.sort_by_key(|item| (item.pipeline, item.draw_function));
+    }
+}
+
 impl EntityPhaseItem for Opaque3d {
     #[inline]
     fn entity(&self) -> Entity {
@@ -155,11 +197,23 @@ impl CachedRenderPipelinePhaseItem for Opaque3d {
     }
 }
 
+impl BatchedPhaseItem for Opaque3d {
+    fn batch_range(&self) -> &Option<Range<u32>> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+        &mut self.batch_range
+    }
+}
+
 pub struct AlphaMask3d {
     pub distance: f32,
     pub pipeline: CachedRenderPipelineId,
     pub entity: Entity,
     pub draw_function: DrawFunctionId,
+    /// Range in the instance buffer of this item
+    pub batch_range: Option<Range<u32>>,
 }
 
 impl PhaseItem for AlphaMask3d {
@@ -183,6 +237,15 @@ impl PhaseItem for AlphaMask3d {
     }
 }
 
+impl AlphaMask3d {
+    /// Sorts the items by pipeline and draw function, and then front to back, like
+    /// [`Opaque3d::sort_by_pipeline`].
+    pub fn sort_by_pipeline(items: &mut [Self]) {
+        radsort::sort_by_key(items, |item| -item.distance);
+        items.sort_by_key(|item| (item.pipeline, item.draw_function));
+    }
+}
+
 impl EntityPhaseItem for AlphaMask3d {
     #[inline]
     fn entity(&self) -> Entity {
@@ -197,20 +260,84 @@ impl CachedRenderPipelinePhaseItem for AlphaMask3d {
     }
 }
 
+impl BatchedPhaseItem for AlphaMask3d {
+    fn batch_range(&self) -> &Option<Range<u32>> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+        &mut self.batch_range
+    }
+}
+
+/// Decals projected onto the opaque meshes of a view, drawn after them and before the
+/// [`Transparent3d`] phase.
+///
+/// The decals read the depth of the meshes they are projected onto from the prepass, so only the
+/// views with a [`DepthPrepass`] have this phase.
+pub struct Decal3d {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+}
+
+impl PhaseItem for Decal3d {
+    // NOTE: Values increase towards the camera. Back-to-front ordering for decals means we need an ascending sort.
+    // Like transparent meshes, decals at the same distance are ordered by entity so that those
+    // overlapping don't flicker.
+    type SortKey = (FloatOrd, u64);
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        (FloatOrd(self.distance), self.entity.to_bits())
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn sort(items: &mut [Self]) {
+        // Radix sorts are stable, so sorting by entity first breaks ties between distances
+        radsort::sort_by_key(items, |item| item.entity.to_bits());
+        radsort::sort_by_key(items, |item| item.distance);
+    }
+}
+
+impl EntityPhaseItem for Decal3d {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for Decal3d {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
 pub struct Transparent3d {
     pub distance: f32,
     pub pipeline: CachedRenderPipelineId,
     pub entity: Entity,
     pub draw_function: DrawFunctionId,
+    /// Range in the instance buffer of this item
+    pub batch_range: Option<Range<u32>>,
 }
 
 impl PhaseItem for Transparent3d {
     // NOTE: Values increase towards the camera. Back-to-front ordering for transparent means we need an ascending sort.
-    type SortKey = FloatOrd;
+    // Items at the same distance are ordered by entity, as the order they are queued in can change
+    // from frame to frame, which would make overlapping transparent meshes flicker.
+    type SortKey = (FloatOrd, u64);
 
     #[inline]
     fn sort_key(&self) -> Self::SortKey {
-        FloatOrd(self.distance)
+        (FloatOrd(self.distance), self.entity.to_bits())
     }
 
     #[inline]
@@ -220,6 +347,8 @@ impl PhaseItem for Transparent3d {
 
     #[inline]
     fn sort(items: &mut [Self]) {
+        // Radix sorts are stable, so sorting by entity first breaks ties between distances
+        radsort::sort_by_key(items, |item| item.entity.to_bits());
         radsort::sort_by_key(items, |item| item.distance);
     }
 }
@@ -238,28 +367,92 @@ impl CachedRenderPipelinePhaseItem for Transparent3d {
     }
 }
 
+impl BatchedPhaseItem for Transparent3d {
+    fn batch_range(&self) -> &Option<Range<u32>> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+        &mut self.batch_range
+    }
+}
+
 pub fn extract_core_3d_camera_phases(
     mut commands: Commands,
-    cameras_3d: Extract<Query<(Entity, &Camera), With<Camera3d>>>,
+    cameras_3d: Extract<
+        Query<
+            (
+                Entity,
+                &Camera,
+                Option<&DepthPrepass>,
+                Option<&NormalPrepass>,
+                Option<&MotionVectorPrepass>,
+            ),
+            With<Camera3d>,
+        >,
+    >,
 ) {
-    for (entity, camera) in &cameras_3d {
+    for (entity, camera, depth_prepass, normal_prepass, motion_vector_prepass) in &cameras_3d {
         if camera.is_active {
-            commands.get_or_spawn(entity).insert((
+            let mut entity = commands.get_or_spawn(entity);
+            entity.insert((
                 RenderPhase::<Opaque3d>::default(),
                 RenderPhase::<AlphaMask3d>::default(),
                 RenderPhase::<Transparent3d>::default(),
             ));
+            if depth_prepass.is_some()
+                || normal_prepass.is_some()
+                || motion_vector_prepass.is_some()
+            {
+                entity.insert(RenderPhase::<Opaque3dPrepass>::default());
+            }
+            if let Some(depth_prepass) = depth_prepass {
+                entity.insert((*depth_prepass, RenderPhase::<Decal3d>::default()));
+            }
+            if let Some(normal_prepass) = normal_prepass {
+                entity.insert(*normal_prepass);
+            }
+            if let Some(motion_vector_prepass) = motion_vector_prepass {
+                entity.insert(*motion_vector_prepass);
+            }
+        }
+    }
+}
+
+/// Extracts the [`PreviousViewProjection`] of the cameras with a [`MotionVectorPrepass`].
+pub fn extract_previous_view_projections(
+    mut commands: Commands,
+    mut view_projections: Local<HashMap<Entity, Mat4>>,
+    cameras_3d: Extract<
+        Query<(Entity, &Camera, &GlobalTransform), (With<Camera3d>, With<MotionVectorPrepass>)>,
+    >,
+) {
+    // Only the cameras that are still rendered keep their view-projection for the next frame
+    let mut last_frame_view_projections = std::mem::take(&mut *view_projections);
+    for (entity, camera, transform) in &cameras_3d {
+        if !camera.is_active {
+            continue;
         }
+        let view_proj = camera.projection_matrix() * transform.compute_matrix().inverse();
+        let previous_view_proj = last_frame_view_projections
+            .remove(&entity)
+            .unwrap_or(view_proj);
+        view_projections.insert(entity, view_proj);
+        commands
+            .get_or_spawn(entity)
+            .insert(PreviousViewProjection {
+                view_proj: previous_view_proj,
+            });
     }
 }
 
 pub fn prepare_core_3d_depth_textures(
     mut commands: Commands,
-    mut texture_cache: ResMut<TextureCache>,
+    texture_cache: Res<TextureCache>,
     msaa: Res<Msaa>,
     render_device: Res<RenderDevice>,
     views_3d: Query<
-        (Entity, &ExtractedCamera),
+        (Entity, &ExtractedCamera, Option<&DepthPrepass>),
         (
             With<RenderPhase<Opaque3d>>,
             With<RenderPhase<AlphaMask3d>>,
@@ -267,8 +460,15 @@ pub fn prepare_core_3d_depth_textures(
         ),
     >,
 ) {
+    // The depth prepass is copied out of the depth texture shared by the views of a target
+    let copied_targets: HashSet<_> = views_3d
+        .iter()
+        .filter(|(_, _, depth_prepass)| depth_prepass.is_some())
+        .map(|(_, camera, _)| camera.target.clone())
+        .collect();
+
     let mut textures = HashMap::default();
-    for (entity, camera) in &views_3d {
+    for (entity, camera, _) in &views_3d {
         if let Some(physical_target_size) = camera.physical_target_size {
             let cached_texture = textures
                 .entry(camera.target.clone())
@@ -287,7 +487,11 @@ pub fn prepare_core_3d_depth_textures(
                             dimension: TextureDimension::D2,
                             format: TextureFormat::Depth32Float, /* PERF: vulkan docs recommend using 24
                                                                   * bit depth for better performance */
-                            usage: TextureUsages::RENDER_ATTACHMENT,
+                            usage: if copied_targets.contains(&camera.target) {
+                                TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC
+                            } else {
+                                TextureUsages::RENDER_ATTACHMENT
+                            },
                         },
                     )
                 })
@@ -299,3 +503,72 @@ pub fn prepare_core_3d_depth_textures(
         }
     }
 }
+
+pub fn prepare_prepass_textures(
+    mut commands: Commands,
+    texture_cache: Res<TextureCache>,
+    msaa: Res<Msaa>,
+    render_device: Res<RenderDevice>,
+    views_3d: Query<
+        (
+            Entity,
+            &ExtractedCamera,
+            Option<&DepthPrepass>,
+            Option<&NormalPrepass>,
+            Option<&MotionVectorPrepass>,
+        ),
+        With<RenderPhase<Opaque3dPrepass>>,
+    >,
+) {
+    for (entity, camera, depth_prepass, normal_prepass, motion_vector_prepass) in &views_3d {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+        let size = Extent3d {
+            depth_or_array_layers: 1,
+            width: physical_target_size.x,
+            height: physical_target_size.y,
+        };
+        let texture = |label, format, usage| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: msaa.samples,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: usage | TextureUsages::TEXTURE_BINDING,
+                },
+            )
+        };
+        let depth = depth_prepass.map(|_| {
+            texture(
+                "prepass_depth_texture",
+                DEPTH_PREPASS_FORMAT,
+                TextureUsages::COPY_DST,
+            )
+        });
+        let normal = normal_prepass.map(|_| {
+            texture(
+                "prepass_normal_texture",
+                NORMAL_PREPASS_FORMAT,
+                TextureUsages::RENDER_ATTACHMENT,
+            )
+        });
+        let motion_vectors = motion_vector_prepass.map(|_| {
+            texture(
+                "prepass_motion_vectors_texture",
+                MOTION_VECTOR_PREPASS_FORMAT,
+                TextureUsages::RENDER_ATTACHMENT,
+            )
+        });
+        commands.entity(entity).insert(ViewPrepassTextures {
+            depth,
+            normal,
+            motion_vectors,
+            size,
+        });
+    }
+}
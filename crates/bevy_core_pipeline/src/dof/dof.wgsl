@@ -0,0 +1,96 @@
+// Reference: https://blog.voxagon.se/2018/05/04/bokeh-depth-of-field-in-single-pass.html
+
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+// The number of pixels each pixel gathers the color of
+let SAMPLE_COUNT: u32 = 64u;
+let GOLDEN_ANGLE: f32 = 2.39996323;
+
+struct View {
+    view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+    // viewport(x_origin, y_origin, width, height)
+    viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
+};
+
+struct DepthOfFieldSettings {
+    focal_distance: f32,
+    circle_of_confusion_scale: f32,
+    max_circle_of_confusion_diameter: f32,
+};
+
+@group(0) @binding(0)
+var view_target: texture_2d<f32>;
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var prepass_depth: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(1)
+var prepass_depth: texture_2d<f32>;
+#endif
+@group(0) @binding(2)
+var<uniform> view: View;
+@group(0) @binding(3)
+var<uniform> settings: DepthOfFieldSettings;
+@group(0) @binding(4)
+var view_target_sampler: sampler;
+
+// Returns the distance from the camera plane of the opaque mesh drawn at a position in pixels
+fn view_distance(position: vec2<f32>) -> f32 {
+    let texture_size = vec2<f32>(textureDimensions(prepass_depth));
+    let pixel = vec2<i32>(clamp(position, vec2<f32>(0.0), texture_size - 1.0));
+    // NOTE: The far plane of an infinite reverse-z projection is at a depth of 0.0, which would
+    // be at an infinite distance
+    let depth = max(textureLoad(prepass_depth, pixel, 0).r, 0.0000001);
+    let view_position = view.inverse_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    return -view_position.z / view_position.w;
+}
+
+// Returns the radius, in pixels, of the circle of confusion of a point at a distance
+fn circle_of_confusion_radius(distance: f32) -> f32 {
+    let diameter = abs(distance - settings.focal_distance) / distance * settings.circle_of_confusion_scale;
+    return 0.5 * min(diameter, settings.max_circle_of_confusion_diameter);
+}
+
+@fragment
+fn dof(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let texture_size = vec2<f32>(textureDimensions(view_target));
+    let center_color = textureSample(view_target, view_target_sampler, in.uv);
+    let center_distance = view_distance(in.position.xy);
+    let center_radius = circle_of_confusion_radius(center_distance);
+
+    // The samples are spread in a Vogel disk the size of the largest circle of confusion, as
+    // blurry pixels around an in focus one can cover it
+    let max_radius = 0.5 * settings.max_circle_of_confusion_diameter;
+    let sample_spacing = max_radius / sqrt(f32(SAMPLE_COUNT));
+
+    var color = center_color.rgb;
+    var total = 1.0;
+    for (var i = 0u; i < SAMPLE_COUNT; i += 1u) {
+        let radius = max_radius * sqrt((f32(i) + 0.5) / f32(SAMPLE_COUNT));
+        let angle = f32(i) * GOLDEN_ANGLE;
+        let offset = vec2<f32>(cos(angle), sin(angle)) * radius;
+        let sample_color = textureSample(view_target, view_target_sampler, in.uv + offset / texture_size).rgb;
+        let sample_distance = view_distance(in.position.xy + offset);
+        var sample_radius = circle_of_confusion_radius(sample_distance);
+        // An in focus pixel hides the blur of the pixels behind it
+        if sample_distance > center_distance {
+            sample_radius = min(sample_radius, center_radius * 2.0);
+        }
+        // The samples whose circle of confusion doesn't cover the pixel are replaced by the
+        // average so far, which keeps it unchanged
+        let coverage = saturate((sample_radius - radius) / sample_spacing + 0.5);
+        color += mix(color / total, sample_color, coverage);
+        total += 1.0;
+    }
+
+    return vec4<f32>(color / total, center_color.a);
+}
@@ -0,0 +1,328 @@
+//! Depth of field, which blurs the parts of the image of a 3D camera that are out of focus, like a
+//! physical lens does.
+//!
+//! The blur of each pixel, its circle of confusion, is computed from its depth in the
+//! [`DepthPrepass`] with the thin lens model. The pixel then gathers the colors of the pixels
+//! around it whose circles of confusion cover it, which gives bokeh shaped disks to the bright
+//! spots and lets blurry foreground meshes spread over the background.
+
+mod node;
+
+pub use node::DepthOfFieldNode;
+
+use crate::{
+    core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state, prepass::DepthPrepass,
+};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
+use bevy_render::{
+    camera::Camera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    render_graph::RenderGraph,
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, Msaa, ViewTarget, ViewUniform},
+    RenderApp, RenderStage,
+};
+
+const DOF_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 12748362814563020547);
+
+pub struct DepthOfFieldPlugin;
+
+impl Plugin for DepthOfFieldPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, DOF_SHADER_HANDLE, "dof.wgsl", Shader::from_wgsl);
+
+        app.register_type::<DepthOfFieldSettings>()
+            .add_plugin(ExtractComponentPlugin::<DepthOfFieldSettings>::default())
+            .add_plugin(UniformComponentPlugin::<DepthOfFieldUniform>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<DepthOfFieldPipeline>()
+            .init_resource::<SpecializedRenderPipelines<DepthOfFieldPipeline>>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_dof_pipelines);
+
+        let dof_node = DepthOfFieldNode::new(&mut render_app.world);
+        let mut binding = render_app.world.resource_mut::<RenderGraph>();
+        let graph = binding.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+
+        graph.add_node(core_3d::graph::node::DEPTH_OF_FIELD, dof_node);
+        graph.add_slot_edge(
+            graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            core_3d::graph::node::DEPTH_OF_FIELD,
+            DepthOfFieldNode::IN_VIEW,
+        );
+        // MAIN_PASS -> TAA -> DEPTH_OF_FIELD -> BLOOM -> TONEMAPPING
+        // NOTE: The blur runs after TAA, which would otherwise accumulate it with a jitter
+        graph.add_node_edge(
+            core_3d::graph::node::MAIN_PASS,
+            core_3d::graph::node::DEPTH_OF_FIELD,
+        );
+        graph.add_node_edge(
+            core_3d::graph::node::TAA,
+            core_3d::graph::node::DEPTH_OF_FIELD,
+        );
+        graph.add_node_edge(
+            core_3d::graph::node::DEPTH_OF_FIELD,
+            core_3d::graph::node::BLOOM,
+        );
+        graph.add_node_edge(
+            core_3d::graph::node::DEPTH_OF_FIELD,
+            core_3d::graph::node::TONEMAPPING,
+        );
+    }
+}
+
+/// Adds depth of field to a [`Camera3d`](crate::core_3d::Camera3d), along with the
+/// [`DepthPrepass`] it needs.
+#[derive(Bundle, Default)]
+pub struct DepthOfFieldBundle {
+    pub settings: DepthOfFieldSettings,
+    pub depth_prepass: DepthPrepass,
+}
+
+/// Settings of the depth of field of a camera with a perspective projection, which needs the
+/// [`DepthPrepass`] component too. See [`DepthOfFieldBundle`].
+///
+/// The camera is modeled as a thin lens focused at [`focal_distance`](Self::focal_distance),
+/// whose focal length is that of a sensor of [`sensor_height`](Self::sensor_height) with the field
+/// of view of the projection. The meshes that aren't in the prepass, such as transparent ones, are
+/// blurred as if they were at the depth of the opaque meshes behind them.
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component, Default)]
+pub struct DepthOfFieldSettings {
+    /// The distance from the camera, in world units, at which the image is sharp (default: `5.0`).
+    pub focal_distance: f32,
+    /// The f-number of the lens, which is its focal length divided by the diameter of its
+    /// aperture (default: `1.0`). The lower it is, the blurrier the out of focus areas are.
+    pub aperture_f_stops: f32,
+    /// The height of the image sensor, in world units (default: `0.01866`, a Super 35 film frame
+    /// in meters).
+    pub sensor_height: f32,
+    /// The largest diameter, in pixels, of the circle of confusion (default: `32.0`).
+    ///
+    /// The pixels gather the colors of their neighbors within this diameter, so larger values are
+    /// more expensive and spread their samples further apart.
+    pub max_circle_of_confusion_diameter: f32,
+}
+
+impl Default for DepthOfFieldSettings {
+    fn default() -> Self {
+        Self {
+            focal_distance: 5.0,
+            aperture_f_stops: 1.0,
+            sensor_height: 0.01866,
+            max_circle_of_confusion_diameter: 32.0,
+        }
+    }
+}
+
+/// The [`DepthOfFieldSettings`] of a view, in the form its shader uses them.
+#[derive(Component, ShaderType, Clone)]
+pub struct DepthOfFieldUniform {
+    focal_distance: f32,
+    /// The diameter, in pixels, of the circle of confusion of a point infinitely far away. The
+    /// diameter of a point at a distance `d` is `abs(d - focal_distance) / d` times this.
+    circle_of_confusion_scale: f32,
+    max_circle_of_confusion_diameter: f32,
+}
+
+impl ExtractComponent for DepthOfFieldSettings {
+    type Query = (&'static Self, &'static Camera);
+    type Filter = With<DepthPrepass>;
+    type Out = DepthOfFieldUniform;
+
+    fn extract_component((settings, camera): QueryItem<Self::Query>) -> Option<Self::Out> {
+        if !camera.is_active {
+            return None;
+        }
+        let viewport_height = camera.physical_viewport_size()?.y as f32;
+
+        // The focal length of a sensor of `sensor_height` with the vertical field of view of the
+        // projection, whose `y_axis.y` is `1.0 / tan(fov / 2.0)`
+        let focal_length = 0.5 * settings.sensor_height * camera.projection_matrix().y_axis.y;
+        // Thin lens model: https://en.wikipedia.org/wiki/Circle_of_confusion#Determining_a_circle_of_confusion_diameter_from_the_object_field
+        let sensor_scale = focal_length * focal_length
+            / (settings.aperture_f_stops * (settings.focal_distance - focal_length));
+        Some(DepthOfFieldUniform {
+            focal_distance: settings.focal_distance,
+            circle_of_confusion_scale: sensor_scale.max(0.0) * viewport_height
+                / settings.sensor_height,
+            max_circle_of_confusion_diameter: settings.max_circle_of_confusion_diameter,
+        })
+    }
+}
+
+#[derive(Resource)]
+pub struct DepthOfFieldPipeline {
+    layout: BindGroupLayout,
+    multisampled_layout: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl FromWorld for DepthOfFieldPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    // View target
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // Prepass depth
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                    // View
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(ViewUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    // Settings
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(DepthOfFieldUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("dof_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        });
+
+        DepthOfFieldPipeline {
+            layout: layout("dof_bind_group_layout", false),
+            multisampled_layout: layout("dof_multisampled_bind_group_layout", true),
+            sampler,
+        }
+    }
+}
+
+impl DepthOfFieldPipeline {
+    fn layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled {
+            &self.multisampled_layout
+        } else {
+            &self.layout
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct DepthOfFieldPipelineKey {
+    hdr: bool,
+    /// Whether the prepass depth texture is multisampled.
+    multisampled: bool,
+}
+
+impl SpecializedRenderPipeline for DepthOfFieldPipeline {
+    type Key = DepthOfFieldPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("dof_pipeline".into()),
+            layout: Some(vec![self.layout(key.multisampled).clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: DOF_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "dof".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DepthOfFieldPipelineId {
+    pub id: CachedRenderPipelineId,
+    multisampled: bool,
+}
+
+fn prepare_dof_pipelines(
+    mut commands: Commands,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<DepthOfFieldPipeline>>,
+    dof_pipeline: Res<DepthOfFieldPipeline>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedView), With<DepthOfFieldUniform>>,
+) {
+    for (entity, view) in &views {
+        let key = DepthOfFieldPipelineKey {
+            hdr: view.hdr,
+            multisampled: msaa.is_enabled(),
+        };
+        let id = pipelines.specialize(&mut pipeline_cache, &dof_pipeline, key);
+        commands.entity(entity).insert(DepthOfFieldPipelineId {
+            id,
+            multisampled: key.multisampled,
+        });
+    }
+}
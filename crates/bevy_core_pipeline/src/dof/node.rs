@@ -0,0 +1,133 @@
+use crate::{
+    dof::{DepthOfFieldPipeline, DepthOfFieldPipelineId, DepthOfFieldUniform},
+    prepass::ViewPrepassTextures,
+};
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryState;
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_resource::{
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::{ExtractedView, ViewTarget, ViewUniformOffset, ViewUniforms},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// Blurs the out of focus parts of the main pass of a view, from the depth of its prepass.
+pub struct DepthOfFieldNode {
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            &'static ViewPrepassTextures,
+            &'static DepthOfFieldPipelineId,
+            &'static ViewUniformOffset,
+            &'static DynamicUniformIndex<DepthOfFieldUniform>,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl DepthOfFieldNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for DepthOfFieldNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((view_target, prepass_textures, pipeline_id, view_uniform_offset, settings_index)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+        let (Some(pipeline), Some(depth), Some(view_uniforms), Some(settings_uniforms)) = (
+            world
+                .resource::<PipelineCache>()
+                .get_render_pipeline(pipeline_id.id),
+            &prepass_textures.depth,
+            world.resource::<ViewUniforms>().uniforms.cached_binding(),
+            world
+                .resource::<ComponentUniforms<DepthOfFieldUniform>>()
+                .cached_binding(),
+        ) else {
+            return Ok(());
+        };
+        let dof_pipeline = world.resource::<DepthOfFieldPipeline>();
+
+        #[cfg(feature = "trace")]
+        let _dof_span = info_span!("depth_of_field").entered();
+
+        let post_process = view_target.post_process_write();
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            Some("dof_bind_group"),
+            dof_pipeline.layout(pipeline_id.multisampled),
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(post_process.source),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::TextureView(&depth.default_view),
+                },
+                CachedBindGroupEntry {
+                    binding: 2,
+                    resource: view_uniforms,
+                },
+                CachedBindGroupEntry {
+                    binding: 3,
+                    resource: settings_uniforms,
+                },
+                CachedBindGroupEntry {
+                    binding: 4,
+                    resource: CachedBindingResource::Sampler(&dof_pipeline.sampler),
+                },
+            ],
+        );
+
+        let mut render_pass =
+            render_context
+                .command_encoder
+                .begin_render_pass(&RenderPassDescriptor {
+                    label: Some("dof_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: post_process.destination,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                    })],
+                    depth_stencil_attachment: None,
+                });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(
+            0,
+            &bind_group,
+            &[view_uniform_offset.offset, settings_index.index()],
+        );
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
@@ -1,7 +1,6 @@
 use crate::{core_2d, core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state};
 use bevy_app::prelude::*;
 use bevy_asset::{load_internal_asset, HandleUntyped};
-use bevy_derive::Deref;
 use bevy_ecs::{prelude::*, query::QueryItem};
 use bevy_reflect::TypeUuid;
 use bevy_render::{
@@ -14,6 +13,7 @@ use bevy_render::{
     view::{ExtractedView, ViewTarget},
     RenderApp, RenderStage,
 };
+use bevy_utils::default;
 
 mod node;
 
@@ -145,16 +145,17 @@ impl Plugin for FxaaPlugin {
     }
 }
 
-#[derive(Resource, Deref)]
+#[derive(Resource)]
 pub struct FxaaPipeline {
     texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
 }
 
 impl FromWorld for FxaaPipeline {
     fn from_world(render_world: &mut World) -> Self {
-        let texture_bind_group = render_world
-            .resource::<RenderDevice>()
-            .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        let render_device = render_world.resource::<RenderDevice>();
+        let texture_bind_group =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 label: Some("fxaa_texture_bind_group_layout"),
                 entries: &[
                     BindGroupLayoutEntry {
@@ -176,7 +177,17 @@ impl FromWorld for FxaaPipeline {
                 ],
             });
 
-        FxaaPipeline { texture_bind_group }
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mipmap_filter: FilterMode::Linear,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        FxaaPipeline {
+            texture_bind_group,
+            sampler,
+        }
     }
 }
 
@@ -199,6 +210,7 @@ impl SpecializedRenderPipeline for FxaaPipeline {
         RenderPipelineDescriptor {
             label: Some("fxaa".into()),
             layout: Some(vec![self.texture_bind_group.clone()]),
+            push_constant_ranges: Vec::new(),
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: FXAA_SHADER_HANDLE.typed(),
@@ -1,19 +1,15 @@
-use std::sync::Mutex;
-
 use crate::fxaa::{CameraFxaaPipeline, Fxaa, FxaaPipeline};
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::QueryState;
 use bevy_render::{
     render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
     render_resource::{
-        BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, FilterMode, Operations,
-        PipelineCache, RenderPassColorAttachment, RenderPassDescriptor, SamplerDescriptor,
-        TextureViewId,
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor,
     },
     renderer::RenderContext,
     view::{ExtractedView, ViewTarget},
 };
-use bevy_utils::default;
 
 pub struct FxaaNode {
     query: QueryState<
@@ -24,7 +20,6 @@ pub struct FxaaNode {
         ),
         With<ExtractedView>,
     >,
-    cached_texture_bind_group: Mutex<Option<(TextureViewId, BindGroup)>>,
 }
 
 impl FxaaNode {
@@ -33,7 +28,6 @@ impl FxaaNode {
     pub fn new(world: &mut World) -> Self {
         Self {
             query: QueryState::new(world),
-            cached_texture_bind_group: Mutex::new(None),
         }
     }
 }
@@ -66,48 +60,28 @@ impl Node for FxaaNode {
             return Ok(());
         };
 
-        let pipeline = pipeline_cache
-            .get_render_pipeline(pipeline.pipeline_id)
-            .unwrap();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline.pipeline_id) else {
+            return Ok(());
+        };
 
         let post_process = target.post_process_write();
         let source = post_process.source;
         let destination = post_process.destination;
-        let mut cached_bind_group = self.cached_texture_bind_group.lock().unwrap();
-        let bind_group = match &mut *cached_bind_group {
-            Some((id, bind_group)) if source.id() == *id => bind_group,
-            cached_bind_group => {
-                let sampler = render_context
-                    .render_device
-                    .create_sampler(&SamplerDescriptor {
-                        mipmap_filter: FilterMode::Linear,
-                        mag_filter: FilterMode::Linear,
-                        min_filter: FilterMode::Linear,
-                        ..default()
-                    });
-
-                let bind_group =
-                    render_context
-                        .render_device
-                        .create_bind_group(&BindGroupDescriptor {
-                            label: None,
-                            layout: &fxaa_pipeline.texture_bind_group,
-                            entries: &[
-                                BindGroupEntry {
-                                    binding: 0,
-                                    resource: BindingResource::TextureView(source),
-                                },
-                                BindGroupEntry {
-                                    binding: 1,
-                                    resource: BindingResource::Sampler(&sampler),
-                                },
-                            ],
-                        });
-
-                let (_, bind_group) = cached_bind_group.insert((source.id(), bind_group));
-                bind_group
-            }
-        };
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            None,
+            &fxaa_pipeline.texture_bind_group,
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(source),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::Sampler(&fxaa_pipeline.sampler),
+                },
+            ],
+        );
 
         let pass_descriptor = RenderPassDescriptor {
             label: Some("fxaa_pass"),
@@ -124,7 +98,7 @@ impl Node for FxaaNode {
             .begin_render_pass(&pass_descriptor);
 
         render_pass.set_pipeline(pipeline);
-        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(0, &bind_group, &[]);
         render_pass.draw(0..3, 0..1);
 
         Ok(())
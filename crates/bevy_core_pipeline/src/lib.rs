@@ -2,8 +2,13 @@ pub mod bloom;
 pub mod clear_color;
 pub mod core_2d;
 pub mod core_3d;
+pub mod dof;
 pub mod fullscreen_vertex_shader;
 pub mod fxaa;
+pub mod motion_blur;
+pub mod prepass;
+pub mod skybox;
+pub mod taa;
 pub mod tonemapping;
 pub mod upscaling;
 
@@ -21,8 +26,12 @@ use crate::{
     clear_color::{ClearColor, ClearColorConfig},
     core_2d::Core2dPlugin,
     core_3d::Core3dPlugin,
+    dof::DepthOfFieldPlugin,
     fullscreen_vertex_shader::FULLSCREEN_SHADER_HANDLE,
     fxaa::FxaaPlugin,
+    motion_blur::MotionBlurPlugin,
+    skybox::SkyboxPlugin,
+    taa::TemporalAntiAliasPlugin,
     tonemapping::TonemappingPlugin,
     upscaling::UpscalingPlugin,
 };
@@ -48,9 +57,13 @@ impl Plugin for CorePipelinePlugin {
             .add_plugin(ExtractResourcePlugin::<ClearColor>::default())
             .add_plugin(Core2dPlugin)
             .add_plugin(Core3dPlugin)
+            .add_plugin(SkyboxPlugin)
             .add_plugin(TonemappingPlugin)
             .add_plugin(UpscalingPlugin)
             .add_plugin(BloomPlugin)
-            .add_plugin(FxaaPlugin);
+            .add_plugin(FxaaPlugin)
+            .add_plugin(TemporalAntiAliasPlugin)
+            .add_plugin(DepthOfFieldPlugin)
+            .add_plugin(MotionBlurPlugin);
     }
 }
@@ -0,0 +1,297 @@
+//! Motion blur, which blurs the meshes of a 3D camera along their motion on screen, like the
+//! exposure of a physical camera does.
+//!
+//! Each pixel averages the colors along its motion vector from the [`MotionVectorPrepass`],
+//! which combines the motion of the camera with the motion of the mesh drawn at the pixel.
+
+mod node;
+
+pub use node::MotionBlurNode;
+
+use crate::{
+    core_3d,
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prepass::{DepthPrepass, MotionVectorPrepass},
+};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
+use bevy_render::{
+    camera::Camera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin},
+    render_graph::RenderGraph,
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, Msaa, ViewTarget},
+    RenderApp, RenderStage,
+};
+
+const MOTION_BLUR_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9832645231463259387);
+
+pub struct MotionBlurPlugin;
+
+impl Plugin for MotionBlurPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            MOTION_BLUR_SHADER_HANDLE,
+            "motion_blur.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<MotionBlurSettings>()
+            .add_plugin(ExtractComponentPlugin::<MotionBlurSettings>::default())
+            .add_plugin(UniformComponentPlugin::<MotionBlurUniform>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<MotionBlurPipeline>()
+            .init_resource::<SpecializedRenderPipelines<MotionBlurPipeline>>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_motion_blur_pipelines);
+
+        let motion_blur_node = MotionBlurNode::new(&mut render_app.world);
+        let mut binding = render_app.world.resource_mut::<RenderGraph>();
+        let graph = binding.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+
+        graph.add_node(core_3d::graph::node::MOTION_BLUR, motion_blur_node);
+        graph.add_slot_edge(
+            graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            core_3d::graph::node::MOTION_BLUR,
+            MotionBlurNode::IN_VIEW,
+        );
+        // MAIN_PASS -> TAA -> MOTION_BLUR -> DEPTH_OF_FIELD -> BLOOM -> TONEMAPPING
+        graph.add_node_edge(
+            core_3d::graph::node::MAIN_PASS,
+            core_3d::graph::node::MOTION_BLUR,
+        );
+        graph.add_node_edge(core_3d::graph::node::TAA, core_3d::graph::node::MOTION_BLUR);
+        graph.add_node_edge(
+            core_3d::graph::node::MOTION_BLUR,
+            core_3d::graph::node::DEPTH_OF_FIELD,
+        );
+        graph.add_node_edge(
+            core_3d::graph::node::MOTION_BLUR,
+            core_3d::graph::node::BLOOM,
+        );
+        graph.add_node_edge(
+            core_3d::graph::node::MOTION_BLUR,
+            core_3d::graph::node::TONEMAPPING,
+        );
+    }
+}
+
+/// Adds motion blur to a [`Camera3d`](crate::core_3d::Camera3d), along with the
+/// [`MotionVectorPrepass`] and [`DepthPrepass`] it needs.
+#[derive(Bundle, Default)]
+pub struct MotionBlurBundle {
+    pub settings: MotionBlurSettings,
+    pub motion_vector_prepass: MotionVectorPrepass,
+    pub depth_prepass: DepthPrepass,
+}
+
+/// Settings of the motion blur of a camera, which needs the [`MotionVectorPrepass`] and
+/// [`DepthPrepass`] components too. See [`MotionBlurBundle`].
+///
+/// The meshes that aren't in the prepass, such as transparent ones, are blurred with the motion of
+/// the opaque meshes behind them, and the pixels where no mesh was drawn aren't blurred.
+#[derive(Component, Reflect, Clone)]
+#[reflect(Component, Default)]
+pub struct MotionBlurSettings {
+    /// The fraction of the time between two frames the shutter is open for (default: `0.5`).
+    ///
+    /// The meshes are blurred along the distance they move while the shutter is open, so `1.0`
+    /// blurs them along their whole motion since the previous frame, and `0.0` disables the blur.
+    /// It is usually expressed in degrees, in which case `0.5` is a shutter angle of 180°.
+    pub shutter_angle: f32,
+    /// The number of colors each pixel averages along its motion (default: `8`).
+    ///
+    /// More samples give a smoother blur, especially for fast motions, but are more expensive.
+    pub samples: u32,
+}
+
+impl Default for MotionBlurSettings {
+    fn default() -> Self {
+        Self {
+            shutter_angle: 0.5,
+            samples: 8,
+        }
+    }
+}
+
+/// The [`MotionBlurSettings`] of a view, in the form its shader uses them.
+#[derive(Component, ShaderType, Clone)]
+pub struct MotionBlurUniform {
+    shutter_angle: f32,
+    samples: u32,
+}
+
+impl ExtractComponent for MotionBlurSettings {
+    type Query = (&'static Self, &'static Camera);
+    type Filter = (With<MotionVectorPrepass>, With<DepthPrepass>);
+    type Out = MotionBlurUniform;
+
+    fn extract_component((settings, camera): QueryItem<Self::Query>) -> Option<Self::Out> {
+        if !camera.is_active || settings.shutter_angle <= 0.0 || settings.samples == 0 {
+            return None;
+        }
+        Some(MotionBlurUniform {
+            shutter_angle: settings.shutter_angle,
+            samples: settings.samples,
+        })
+    }
+}
+
+#[derive(Resource)]
+pub struct MotionBlurPipeline {
+    layout: BindGroupLayout,
+    multisampled_layout: BindGroupLayout,
+    sampler: Sampler,
+}
+
+impl FromWorld for MotionBlurPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let texture_entry = |binding, filterable, multisampled| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable },
+                view_dimension: TextureViewDimension::D2,
+                multisampled,
+            },
+            count: None,
+        };
+        let layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    // View target
+                    texture_entry(0, true, false),
+                    // Prepass motion vectors
+                    texture_entry(1, false, multisampled),
+                    // Prepass depth
+                    texture_entry(2, false, multisampled),
+                    // Settings
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(MotionBlurUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("motion_blur_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        });
+
+        MotionBlurPipeline {
+            layout: layout("motion_blur_bind_group_layout", false),
+            multisampled_layout: layout("motion_blur_multisampled_bind_group_layout", true),
+            sampler,
+        }
+    }
+}
+
+impl MotionBlurPipeline {
+    fn layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled {
+            &self.multisampled_layout
+        } else {
+            &self.layout
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct MotionBlurPipelineKey {
+    hdr: bool,
+    /// Whether the prepass textures are multisampled.
+    multisampled: bool,
+}
+
+impl SpecializedRenderPipeline for MotionBlurPipeline {
+    type Key = MotionBlurPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("motion_blur_pipeline".into()),
+            layout: Some(vec![self.layout(key.multisampled).clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: MOTION_BLUR_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "motion_blur".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MotionBlurPipelineId {
+    pub id: CachedRenderPipelineId,
+    multisampled: bool,
+}
+
+fn prepare_motion_blur_pipelines(
+    mut commands: Commands,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<MotionBlurPipeline>>,
+    motion_blur_pipeline: Res<MotionBlurPipeline>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ExtractedView), With<MotionBlurUniform>>,
+) {
+    for (entity, view) in &views {
+        let key = MotionBlurPipelineKey {
+            hdr: view.hdr,
+            multisampled: msaa.is_enabled(),
+        };
+        let id = pipelines.specialize(&mut pipeline_cache, &motion_blur_pipeline, key);
+        commands.entity(entity).insert(MotionBlurPipelineId {
+            id,
+            multisampled: key.multisampled,
+        });
+    }
+}
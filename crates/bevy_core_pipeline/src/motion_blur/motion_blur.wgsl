@@ -0,0 +1,69 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct MotionBlurSettings {
+    shutter_angle: f32,
+    samples: u32,
+};
+
+@group(0) @binding(0)
+var view_target: texture_2d<f32>;
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var prepass_motion_vectors: texture_multisampled_2d<f32>;
+@group(0) @binding(2)
+var prepass_depth: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(1)
+var prepass_motion_vectors: texture_2d<f32>;
+@group(0) @binding(2)
+var prepass_depth: texture_2d<f32>;
+#endif
+@group(0) @binding(3)
+var<uniform> settings: MotionBlurSettings;
+@group(0) @binding(4)
+var view_target_sampler: sampler;
+
+// Returns the UV offset a pixel moves by while the shutter is open
+fn load_exposure_motion(pixel: vec2<i32>) -> vec2<f32> {
+    return textureLoad(prepass_motion_vectors, pixel, 0).rg * settings.shutter_angle;
+}
+
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    return textureLoad(prepass_depth, pixel, 0).r;
+}
+
+@fragment
+fn motion_blur(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let texture_size = vec2<f32>(textureDimensions(view_target));
+    let max_pixel = vec2<i32>(texture_size) - vec2<i32>(1);
+    let pixel = vec2<i32>(in.position.xy);
+    // NOTE: The samples are taken at level 0, as they aren't in uniform control flow
+    let center_color = textureSampleLevel(view_target, view_target_sampler, in.uv, 0.0);
+    let motion = load_exposure_motion(pixel);
+    if settings.samples < 2u || all(abs(motion * texture_size) < vec2<f32>(0.5)) {
+        return center_color;
+    }
+    let center_depth = load_depth(pixel);
+
+    var color = center_color.rgb;
+    var total = 1.0;
+    for (var i = 0u; i < settings.samples; i += 1u) {
+        // The samples span the motion while the shutter is open, centered on the current frame
+        let offset = motion * (f32(i) / f32(settings.samples - 1u) - 0.5);
+        let uv = in.uv + offset;
+        let sample_pixel = clamp(vec2<i32>(uv * texture_size), vec2<i32>(0), max_pixel);
+
+        // A mesh in front of the pixel only blurs over it where its own motion reaches it, which
+        // keeps the still meshes in front of a moving background sharp
+        var weight = 1.0;
+        if load_depth(sample_pixel) > center_depth {
+            let sample_reach = 0.5 * length(load_exposure_motion(sample_pixel) * texture_size);
+            weight = saturate(sample_reach - length(offset * texture_size) + 1.0);
+        }
+
+        color += textureSampleLevel(view_target, view_target_sampler, uv, 0.0).rgb * weight;
+        total += weight;
+    }
+
+    return vec4<f32>(color / total, center_color.a);
+}
@@ -0,0 +1,128 @@
+use crate::{
+    motion_blur::{MotionBlurPipeline, MotionBlurPipelineId, MotionBlurUniform},
+    prepass::ViewPrepassTextures,
+};
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryState;
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_resource::{
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::{ExtractedView, ViewTarget},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// Blurs the main pass of a view along the motion vectors of its prepass.
+pub struct MotionBlurNode {
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            &'static ViewPrepassTextures,
+            &'static MotionBlurPipelineId,
+            &'static DynamicUniformIndex<MotionBlurUniform>,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl MotionBlurNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for MotionBlurNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((view_target, prepass_textures, pipeline_id, settings_index)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+        let (Some(pipeline), Some(motion_vectors), Some(depth), Some(settings_uniforms)) = (
+            world
+                .resource::<PipelineCache>()
+                .get_render_pipeline(pipeline_id.id),
+            &prepass_textures.motion_vectors,
+            &prepass_textures.depth,
+            world
+                .resource::<ComponentUniforms<MotionBlurUniform>>()
+                .cached_binding(),
+        ) else {
+            return Ok(());
+        };
+        let motion_blur_pipeline = world.resource::<MotionBlurPipeline>();
+
+        #[cfg(feature = "trace")]
+        let _motion_blur_span = info_span!("motion_blur").entered();
+
+        let post_process = view_target.post_process_write();
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            Some("motion_blur_bind_group"),
+            motion_blur_pipeline.layout(pipeline_id.multisampled),
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(post_process.source),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::TextureView(&motion_vectors.default_view),
+                },
+                CachedBindGroupEntry {
+                    binding: 2,
+                    resource: CachedBindingResource::TextureView(&depth.default_view),
+                },
+                CachedBindGroupEntry {
+                    binding: 3,
+                    resource: settings_uniforms,
+                },
+                CachedBindGroupEntry {
+                    binding: 4,
+                    resource: CachedBindingResource::Sampler(&motion_blur_pipeline.sampler),
+                },
+            ],
+        );
+
+        let mut render_pass =
+            render_context
+                .command_encoder
+                .begin_render_pass(&RenderPassDescriptor {
+                    label: Some("motion_blur_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: post_process.destination,
+                        resolve_target: None,
+                        ops: Operations::default(),
+                    })],
+                    depth_stencil_attachment: None,
+                });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
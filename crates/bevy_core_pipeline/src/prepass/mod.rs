@@ -0,0 +1,144 @@
+//! Renders the opaque meshes seen by a 3D camera into a depth texture, and optionally normal and
+//! motion vector textures, before its main pass.
+//!
+//! Add [`DepthPrepass`], [`NormalPrepass`] and/or [`MotionVectorPrepass`] to a
+//! [`Camera3d`](crate::core_3d::Camera3d) to run the prepass. Its textures are stored in the [`ViewPrepassTextures`] of the view in the
+//! render world, for render graph nodes like ambient occlusion, soft particles or decals to
+//! sample.
+//!
+//! The main opaque pass loads the depth written by the prepass instead of clearing it, so the
+//! fragments of meshes that are hidden behind others are rejected by the early depth test and
+//! never shaded.
+
+mod node;
+
+pub use node::*;
+
+use std::{cmp::Reverse, ops::Range};
+
+use bevy_ecs::prelude::*;
+use bevy_math::Mat4;
+use bevy_reflect::Reflect;
+use bevy_render::{
+    render_phase::{
+        BatchedPhaseItem, CachedRenderPipelinePhaseItem, DrawFunctionId, EntityPhaseItem, PhaseItem,
+    },
+    render_resource::{CachedRenderPipelineId, Extent3d, ShaderType, TextureFormat},
+    texture::CachedTexture,
+};
+use bevy_utils::FloatOrd;
+
+/// The format of the depth texture written by the prepass, which is also the format of the
+/// [`ViewDepthTexture`](bevy_render::view::ViewDepthTexture) it is copied from.
+pub const DEPTH_PREPASS_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/// The format of the normal texture written by the prepass.
+pub const NORMAL_PREPASS_FORMAT: TextureFormat = TextureFormat::Rgb10a2Unorm;
+/// The format of the motion vector texture written by the prepass.
+pub const MOTION_VECTOR_PREPASS_FORMAT: TextureFormat = TextureFormat::Rg16Float;
+
+/// Add this component to a [`Camera3d`](crate::core_3d::Camera3d) to write the depth of its
+/// opaque meshes into [`ViewPrepassTextures::depth`] before its main pass.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct DepthPrepass;
+
+/// Add this component to a [`Camera3d`](crate::core_3d::Camera3d) to write the world space
+/// normals of its opaque meshes into [`ViewPrepassTextures::normal`] before its main pass.
+///
+/// The normals are remapped from `[-1, 1]` to `[0, 1]`, and the alpha channel is `1.0` where a
+/// mesh was drawn and `0.0` elsewhere.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct NormalPrepass;
+
+/// Add this component to a [`Camera3d`](crate::core_3d::Camera3d) to write the screen space
+/// motion of its opaque meshes since the previous frame into
+/// [`ViewPrepassTextures::motion_vectors`] before its main pass.
+///
+/// The motion vectors are the offset in UV space from where each fragment was on the previous
+/// frame to where it is now, so `uv - motion_vector` is its UV on the previous frame. They combine
+/// the motion of the camera with the motion of the mesh, but skinned meshes only move with their
+/// transform.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct MotionVectorPrepass;
+
+/// The unjittered view-projection matrix of a view on the previous frame, for the
+/// [`MotionVectorPrepass`] to compute motion vectors from.
+///
+/// It is the same as the current one on the first frame the view is rendered with motion vectors.
+#[derive(Component, ShaderType, Clone)]
+pub struct PreviousViewProjection {
+    pub view_proj: Mat4,
+}
+
+/// The textures written by the prepass of a view, which has them if its camera has at least one
+/// of the [`DepthPrepass`], [`NormalPrepass`] and [`MotionVectorPrepass`] components.
+///
+/// They are multisampled when [`Msaa`](bevy_render::view::Msaa) is enabled.
+#[derive(Component)]
+pub struct ViewPrepassTextures {
+    /// The depth of the opaque meshes, with a [`DEPTH_PREPASS_FORMAT`] format.
+    pub depth: Option<CachedTexture>,
+    /// The world space normals of the opaque meshes, with a [`NORMAL_PREPASS_FORMAT`] format.
+    pub normal: Option<CachedTexture>,
+    /// The motion vectors of the opaque meshes, with a [`MOTION_VECTOR_PREPASS_FORMAT`] format.
+    pub motion_vectors: Option<CachedTexture>,
+    /// The size of the textures.
+    pub size: Extent3d,
+}
+
+/// Opaque meshes drawn into the prepass textures of a view, sorted front-to-back.
+pub struct Opaque3dPrepass {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+    /// Range in the instance buffer of this item
+    pub batch_range: Option<Range<u32>>,
+}
+
+impl PhaseItem for Opaque3dPrepass {
+    // NOTE: Values increase towards the camera. Front-to-back ordering for opaque means we need a descending sort.
+    type SortKey = Reverse<FloatOrd>;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        Reverse(FloatOrd(self.distance))
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn sort(items: &mut [Self]) {
+        // Key negated to match reversed SortKey ordering
+        radsort::sort_by_key(items, |item| -item.distance);
+    }
+}
+
+impl EntityPhaseItem for Opaque3dPrepass {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for Opaque3dPrepass {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+impl BatchedPhaseItem for Opaque3dPrepass {
+    fn batch_range(&self) -> &Option<Range<u32>> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+        &mut self.batch_range
+    }
+}
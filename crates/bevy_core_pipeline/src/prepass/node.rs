@@ -0,0 +1,145 @@
+use crate::{
+    core_3d::Camera3d,
+    prepass::{Opaque3dPrepass, ViewPrepassTextures},
+};
+use bevy_ecs::prelude::*;
+use bevy_render::{
+    camera::ExtractedCamera,
+    color::Color,
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_phase::{DrawFunctions, RenderPhase, TrackedRenderPass},
+    render_resource::{
+        LoadOp, Operations, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+        RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    texture::CachedTexture,
+    view::{ExtractedView, ViewDepthTexture},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// Draws the [`Opaque3dPrepass`] phase of a view into its [`ViewDepthTexture`] and the normal and
+/// motion vector textures of its [`ViewPrepassTextures`], then copies the depth into
+/// [`ViewPrepassTextures::depth`].
+pub struct PrepassNode {
+    query: QueryState<
+        (
+            &'static ExtractedCamera,
+            &'static RenderPhase<Opaque3dPrepass>,
+            &'static Camera3d,
+            &'static ViewDepthTexture,
+            &'static ViewPrepassTextures,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl PrepassNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: world.query_filtered(),
+        }
+    }
+}
+
+impl Node for PrepassNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let (camera, opaque_prepass_phase, camera_3d, view_depth_texture, view_prepass_textures) =
+            match self.query.get_manual(world, view_entity) {
+                Ok(query) => query,
+                Err(_) => {
+                    return Ok(());
+                } // The camera doesn't have a prepass
+            };
+
+        {
+            // Run the prepass, sorted front-to-back
+            // NOTE: Scoped to drop the mutable borrow of render_context
+            #[cfg(feature = "trace")]
+            let _prepass_span = info_span!("prepass").entered();
+            // NOTE: The normals are written to location 0 and the motion vectors to location 1,
+            // whether or not the other one is written
+            let color_attachments = match (
+                prepass_color_attachment(&view_prepass_textures.normal),
+                prepass_color_attachment(&view_prepass_textures.motion_vectors),
+            ) {
+                (None, None) => Vec::new(),
+                (normal, None) => vec![normal],
+                (normal, motion_vectors) => vec![normal, motion_vectors],
+            };
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("prepass"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &view_depth_texture.view,
+                    // NOTE: The prepass is the first pass to write the depth buffer, the main
+                    // passes load it afterwards
+                    depth_ops: Some(Operations {
+                        // NOTE: 0.0 is the far plane due to bevy's use of reverse-z projections.
+                        load: camera_3d.depth_load_op.clone().into(),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            };
+
+            let draw_functions = world.resource::<DrawFunctions<Opaque3dPrepass>>();
+
+            let render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+            let mut draw_functions = draw_functions.write();
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                tracked_pass.set_camera_viewport(viewport);
+                if let Some(target_size) = camera.physical_target_size {
+                    tracked_pass.set_camera_scissor_rect(viewport, target_size);
+                }
+            }
+            for item in &opaque_prepass_phase.items {
+                let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+                draw_function.draw(world, &mut tracked_pass, view_entity, item);
+            }
+        }
+
+        // The main passes keep writing to the view depth texture, so the prepass depth is copied to
+        // its own texture for later passes to sample
+        if let Some(prepass_depth) = &view_prepass_textures.depth {
+            render_context.command_encoder.copy_texture_to_texture(
+                view_depth_texture.texture.as_image_copy(),
+                prepass_depth.texture.as_image_copy(),
+                view_prepass_textures.size,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn prepass_color_attachment(texture: &Option<CachedTexture>) -> Option<RenderPassColorAttachment> {
+    texture.as_ref().map(|texture| RenderPassColorAttachment {
+        view: &texture.default_view,
+        resolve_target: None,
+        ops: Operations {
+            load: LoadOp::Clear(Color::NONE.into()),
+            store: true,
+        },
+    })
+}
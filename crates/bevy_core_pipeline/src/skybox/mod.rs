@@ -0,0 +1,295 @@
+use crate::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::TypeUuid;
+use bevy_render::{
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Image},
+    render_asset::RenderAssets,
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::BevyDefault,
+    view::{ExtractedView, Msaa, ViewTarget, ViewUniform, ViewUniforms},
+    RenderApp, RenderStage,
+};
+
+const SKYBOX_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 730488942062131617);
+
+/// Draws an image around a 3D camera, behind everything rendered by its main pass, instead
+/// of leaving the clear color in the background.
+///
+/// The skybox is drawn after the opaque and alpha masked meshes, only where nothing has been
+/// drawn yet, and before the transparent meshes.
+#[derive(Component, Clone)]
+pub enum Skybox {
+    /// A cube map, whose [`texture_view_descriptor`](Image::texture_view_descriptor) has a
+    /// [`TextureViewDimension::Cube`] dimension, like the cube maps loaded from `.ktx2` files.
+    Cubemap(Handle<Image>),
+    /// A 2D panorama with an equirectangular projection, like most `.hdr` environment maps.
+    ///
+    /// Its center faces the `+X` axis and its top the `+Y` axis.
+    Equirectangular(Handle<Image>),
+}
+
+impl Skybox {
+    /// The image drawn around the camera.
+    pub fn image(&self) -> &Handle<Image> {
+        match self {
+            Skybox::Cubemap(image) | Skybox::Equirectangular(image) => image,
+        }
+    }
+}
+
+impl ExtractComponent for Skybox {
+    type Query = &'static Self;
+    type Filter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self> {
+        Some(item.clone())
+    }
+}
+
+/// Adds support for drawing a [`Skybox`] around 3D cameras.
+pub struct SkyboxPlugin;
+
+impl Plugin for SkyboxPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, SKYBOX_SHADER_HANDLE, "skybox.wgsl", Shader::from_wgsl);
+
+        app.add_plugin(ExtractComponentPlugin::<Skybox>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<SkyboxPipeline>()
+            .init_resource::<SpecializedRenderPipelines<SkyboxPipeline>>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_skybox_pipelines)
+            .add_system_to_stage(RenderStage::Queue, queue_skybox_bind_groups);
+    }
+}
+
+#[derive(Resource)]
+pub struct SkyboxPipeline {
+    /// The bind group layouts, indexed by [`SkyboxPipeline::layout_index`].
+    layouts: [BindGroupLayout; 4],
+    /// Used instead of the sampler of images whose format can't be filtered, like the
+    /// `Rgba32Float` images loaded from `.hdr` files.
+    non_filtering_sampler: Sampler,
+}
+
+impl SkyboxPipeline {
+    fn layout_index(equirectangular: bool, filterable: bool) -> usize {
+        usize::from(equirectangular) * 2 + usize::from(filterable)
+    }
+
+    fn layout(&self, equirectangular: bool, filterable: bool) -> &BindGroupLayout {
+        &self.layouts[Self::layout_index(equirectangular, filterable)]
+    }
+}
+
+impl FromWorld for SkyboxPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+        let layout = |index| {
+            let equirectangular = index >= 2;
+            let filterable = index % 2 == 1;
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("skybox_bind_group_layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(ViewUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable },
+                            view_dimension: if equirectangular {
+                                TextureViewDimension::D2
+                            } else {
+                                TextureViewDimension::Cube
+                            },
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(if filterable {
+                            SamplerBindingType::Filtering
+                        } else {
+                            SamplerBindingType::NonFiltering
+                        }),
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        SkyboxPipeline {
+            layouts: [layout(0), layout(1), layout(2), layout(3)],
+            non_filtering_sampler: render_device.create_sampler(&SamplerDescriptor {
+                label: Some("skybox_non_filtering_sampler"),
+                address_mode_u: AddressMode::Repeat,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Whether images of this `format` can be sampled with a linear filter.
+fn is_filterable(format: TextureFormat) -> bool {
+    matches!(
+        format.describe().sample_type,
+        TextureSampleType::Float { filterable: true }
+    )
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct SkyboxPipelineKey {
+    equirectangular: bool,
+    filterable: bool,
+    samples: u32,
+    texture_format: TextureFormat,
+}
+
+impl SpecializedRenderPipeline for SkyboxPipeline {
+    type Key = SkyboxPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.equirectangular {
+            shader_defs.push("EQUIRECTANGULAR".into());
+        }
+        RenderPipelineDescriptor {
+            label: Some("skybox_pipeline".into()),
+            layout: Some(vec![self
+                .layout(key.equirectangular, key.filterable)
+                .clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: SKYBOX_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            // The fullscreen triangle is on the far plane, at a depth of 0.0 due to bevy's use of
+            // reverse-z projections, so it is only drawn where the depth buffer was left cleared.
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.samples,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct SkyboxPipelineId(pub CachedRenderPipelineId);
+
+pub fn prepare_skybox_pipelines(
+    mut commands: Commands,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<SkyboxPipeline>>,
+    skybox_pipeline: Res<SkyboxPipeline>,
+    msaa: Res<Msaa>,
+    images: Res<RenderAssets<Image>>,
+    views: Query<(Entity, &ExtractedView, &Skybox)>,
+) {
+    for (entity, view, skybox) in &views {
+        // The skybox isn't drawn until its image is loaded
+        let Some(image) = images.get(skybox.image()) else {
+            continue;
+        };
+        let pipeline_id = pipelines.specialize(
+            &mut pipeline_cache,
+            &skybox_pipeline,
+            SkyboxPipelineKey {
+                equirectangular: matches!(skybox, Skybox::Equirectangular(_)),
+                filterable: is_filterable(image.texture_format),
+                samples: msaa.samples,
+                texture_format: if view.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+            },
+        );
+
+        commands
+            .entity(entity)
+            .insert(SkyboxPipelineId(pipeline_id));
+    }
+}
+
+#[derive(Component)]
+pub struct SkyboxBindGroup(pub BindGroup);
+
+pub fn queue_skybox_bind_groups(
+    mut commands: Commands,
+    skybox_pipeline: Res<SkyboxPipeline>,
+    view_uniforms: Res<ViewUniforms>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &Skybox)>,
+) {
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    for (entity, skybox) in &views {
+        let Some(image) = images.get(skybox.image()) else {
+            continue;
+        };
+        let filterable = is_filterable(image.texture_format);
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("skybox_bind_group"),
+            layout: skybox_pipeline
+                .layout(matches!(skybox, Skybox::Equirectangular(_)), filterable),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: view_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&image.texture_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(if filterable {
+                        &image.sampler
+                    } else {
+                        &skybox_pipeline.non_filtering_sampler
+                    }),
+                },
+            ],
+        });
+
+        commands.entity(entity).insert(SkyboxBindGroup(bind_group));
+    }
+}
@@ -0,0 +1,51 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct View {
+    view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+    // viewport(x_origin, y_origin, width, height)
+    viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> view: View;
+#ifdef EQUIRECTANGULAR
+@group(0) @binding(1)
+var skybox_texture: texture_2d<f32>;
+#else
+@group(0) @binding(1)
+var skybox_texture: texture_cube<f32>;
+#endif
+@group(0) @binding(2)
+var skybox_sampler: sampler;
+
+let PI: f32 = 3.141592653589793;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // The direction from the camera through the pixel, in world space
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let view_position = view.inverse_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let direction = normalize((view.view * vec4<f32>(view_position.xyz / view_position.w, 0.0)).xyz);
+
+#ifdef EQUIRECTANGULAR
+    let uv = vec2<f32>(
+        atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+        acos(clamp(direction.y, -1.0, 1.0)) / PI,
+    );
+    // The derivatives of the coordinates are discontinuous at the seam of the panorama, so
+    // always sample the first mip level.
+    return textureSampleLevel(skybox_texture, skybox_sampler, uv, 0.0);
+#else
+    // Cube maps are sampled with a left-handed coordinate system
+    return textureSample(skybox_texture, skybox_sampler, direction * vec3<f32>(1.0, 1.0, -1.0));
+#endif
+}
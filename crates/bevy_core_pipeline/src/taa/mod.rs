@@ -0,0 +1,351 @@
+//! Temporal anti-aliasing, which smooths the edges of the meshes and the specular highlights of a
+//! 3D camera by blending each frame with the previous ones.
+//!
+//! The projection of the camera is jittered by a different subpixel offset every frame, so that
+//! successive frames sample different points within each pixel. The motion vectors of the
+//! [`MotionVectorPrepass`] then find where each pixel was on the previous frame, to accumulate its
+//! samples over time.
+
+mod node;
+
+pub use node::TemporalAntiAliasNode;
+
+use crate::{
+    core_3d, fullscreen_vertex_shader::fullscreen_shader_vertex_state, prepass::MotionVectorPrepass,
+};
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_core::FrameCount;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::Vec2;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
+use bevy_render::{
+    camera::{Camera, ExtractedCamera, TemporalJitter},
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    render_graph::RenderGraph,
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ExtractedView, Msaa, ViewTarget},
+    RenderApp, RenderStage,
+};
+use bevy_utils::tracing::warn;
+
+const TAA_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5732873232519206380);
+
+/// The format of the history textures, which also keep how confident the accumulated color of
+/// each pixel is in their alpha channel.
+const TAA_HISTORY_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// The subpixel offsets the projection is jittered by, in turn: the first 8 points of the
+/// Halton(2, 3) sequence, centered on the pixel.
+const HALTON_SEQUENCE: [Vec2; 8] = [
+    Vec2::new(0.0, -0.16666667),
+    Vec2::new(-0.25, 0.16666667),
+    Vec2::new(0.25, -0.3888889),
+    Vec2::new(-0.375, -0.055555556),
+    Vec2::new(0.125, 0.2777778),
+    Vec2::new(-0.125, -0.2777778),
+    Vec2::new(0.375, 0.055555556),
+    Vec2::new(-0.4375, 0.3888889),
+];
+
+pub struct TemporalAntiAliasPlugin;
+
+impl Plugin for TemporalAntiAliasPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, TAA_SHADER_HANDLE, "taa.wgsl", Shader::from_wgsl);
+
+        app.register_type::<TemporalAntiAliasSettings>()
+            .add_plugin(ExtractComponentPlugin::<TemporalAntiAliasSettings>::default())
+            .add_system_to_stage(CoreStage::First, clear_taa_resets)
+            .add_system_to_stage(CoreStage::PostUpdate, update_taa_jitter);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<TaaPipeline>()
+            .init_resource::<SpecializedRenderPipelines<TaaPipeline>>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_taa_history_textures)
+            .add_system_to_stage(RenderStage::Prepare, prepare_taa_pipelines);
+
+        let taa_node = TemporalAntiAliasNode::new(&mut render_app.world);
+        let mut binding = render_app.world.resource_mut::<RenderGraph>();
+        let graph = binding.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+
+        graph.add_node(core_3d::graph::node::TAA, taa_node);
+        graph.add_slot_edge(
+            graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            core_3d::graph::node::TAA,
+            TemporalAntiAliasNode::IN_VIEW,
+        );
+        // NOTE: TAA runs on the HDR colors of the main pass, before they are spread by bloom
+        graph.add_node_edge(core_3d::graph::node::MAIN_PASS, core_3d::graph::node::TAA);
+        graph.add_node_edge(core_3d::graph::node::TAA, core_3d::graph::node::BLOOM);
+        graph.add_node_edge(core_3d::graph::node::TAA, core_3d::graph::node::TONEMAPPING);
+    }
+}
+
+/// Adds temporal anti-aliasing to a [`Camera3d`](crate::core_3d::Camera3d), along with the
+/// [`TemporalJitter`] and [`MotionVectorPrepass`] it needs.
+///
+/// Temporal anti-aliasing is skipped while [`Msaa`] is enabled.
+#[derive(Bundle, Default)]
+pub struct TemporalAntiAliasBundle {
+    pub settings: TemporalAntiAliasSettings,
+    pub jitter: TemporalJitter,
+    pub motion_vector_prepass: MotionVectorPrepass,
+}
+
+/// Settings of the temporal anti-aliasing of a camera, which needs the [`TemporalJitter`] and
+/// [`MotionVectorPrepass`] components too. See [`TemporalAntiAliasBundle`].
+///
+/// Unlike MSAA, temporal anti-aliasing also smooths the aliasing within meshes, such as small
+/// specular highlights, but it blurs the image slightly and can leave ghosts behind the meshes
+/// that move fast or aren't in the prepass, such as transparent ones.
+#[derive(Component, Reflect, Clone, Default)]
+#[reflect(Component, Default)]
+pub struct TemporalAntiAliasSettings {
+    /// Discards the colors accumulated over the previous frames.
+    ///
+    /// Set this when the history no longer matches the current frame, such as after a camera
+    /// cut, to avoid ghosting. It is set back to `false` at the start of the next frame.
+    pub reset: bool,
+}
+
+impl ExtractComponent for TemporalAntiAliasSettings {
+    type Query = (&'static Self, &'static Camera);
+    type Filter = (With<TemporalJitter>, With<MotionVectorPrepass>);
+    type Out = Self;
+
+    fn extract_component((settings, camera): QueryItem<Self::Query>) -> Option<Self> {
+        camera.is_active.then(|| settings.clone())
+    }
+}
+
+fn clear_taa_resets(mut query: Query<&mut TemporalAntiAliasSettings>) {
+    for mut settings in &mut query {
+        if settings.reset {
+            settings.reset = false;
+        }
+    }
+}
+
+fn update_taa_jitter(
+    frame_count: Res<FrameCount>,
+    mut query: Query<&mut TemporalJitter, With<TemporalAntiAliasSettings>>,
+) {
+    let offset = HALTON_SEQUENCE[frame_count.0 as usize % HALTON_SEQUENCE.len()];
+    for mut jitter in &mut query {
+        jitter.offset = offset;
+    }
+}
+
+#[derive(Resource)]
+pub struct TaaPipeline {
+    layout: BindGroupLayout,
+    nearest_sampler: Sampler,
+    linear_sampler: Sampler,
+}
+
+impl FromWorld for TaaPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+        };
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("taa_bind_group_layout"),
+            entries: &[
+                // View target
+                texture_entry(0),
+                // History
+                texture_entry(1),
+                // Motion vectors
+                texture_entry(2),
+                sampler_entry(3),
+                sampler_entry(4),
+            ],
+        });
+
+        let nearest_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("taa_nearest_sampler"),
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..SamplerDescriptor::default()
+        });
+        let linear_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("taa_linear_sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        });
+
+        TaaPipeline {
+            layout,
+            nearest_sampler,
+            linear_sampler,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct TaaPipelineKey {
+    hdr: bool,
+    reset: bool,
+}
+
+impl SpecializedRenderPipeline for TaaPipeline {
+    type Key = TaaPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.reset {
+            shader_defs.push("RESET".into());
+        }
+
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("taa_pipeline".into()),
+            layout: Some(vec![self.layout.clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: TAA_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "taa".into(),
+                targets: vec![
+                    Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    }),
+                    Some(ColorTargetState {
+                        format: TAA_HISTORY_FORMAT,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    }),
+                ],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+/// The history textures of the temporal anti-aliasing of a view, which swap roles every frame.
+#[derive(Component)]
+pub struct TemporalAntiAliasHistoryTextures {
+    /// The colors accumulated up to the current frame are written to this texture.
+    pub write: CachedTexture,
+    /// The colors accumulated up to the previous frame are read from this texture.
+    pub read: CachedTexture,
+}
+
+fn prepare_taa_history_textures(
+    mut commands: Commands,
+    texture_cache: Res<TextureCache>,
+    render_device: Res<RenderDevice>,
+    frame_count: Res<FrameCount>,
+    views: Query<(Entity, &ExtractedCamera), With<TemporalAntiAliasSettings>>,
+) {
+    for (entity, camera) in &views {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+        let texture = |label| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    // NOTE: The labels tell the two textures apart in the cache, so that each one
+                    // is given back the history written to it
+                    label: Some(label),
+                    size: Extent3d {
+                        depth_or_array_layers: 1,
+                        width: physical_target_size.x,
+                        height: physical_target_size.y,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TAA_HISTORY_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                },
+            )
+        };
+        let history_1 = texture("taa_history_1_texture");
+        let history_2 = texture("taa_history_2_texture");
+
+        let textures = if frame_count.0 % 2 == 0 {
+            TemporalAntiAliasHistoryTextures {
+                write: history_1,
+                read: history_2,
+            }
+        } else {
+            TemporalAntiAliasHistoryTextures {
+                write: history_2,
+                read: history_1,
+            }
+        };
+        commands.entity(entity).insert(textures);
+    }
+}
+
+#[derive(Component)]
+pub struct TemporalAntiAliasPipelineId(pub CachedRenderPipelineId);
+
+fn prepare_taa_pipelines(
+    mut commands: Commands,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<TaaPipeline>>,
+    taa_pipeline: Res<TaaPipeline>,
+    msaa: Res<Msaa>,
+    mut warned_about_msaa: Local<bool>,
+    views: Query<(Entity, &ExtractedView, &TemporalAntiAliasSettings)>,
+) {
+    if msaa.samples > 1 {
+        if !views.is_empty() && !*warned_about_msaa {
+            warn!("Temporal anti-aliasing is skipped while MSAA is enabled");
+            *warned_about_msaa = true;
+        }
+        return;
+    }
+
+    for (entity, view, settings) in &views {
+        let pipeline_id = pipelines.specialize(
+            &mut pipeline_cache,
+            &taa_pipeline,
+            TaaPipelineKey {
+                hdr: view.hdr,
+                reset: settings.reset,
+            },
+        );
+        commands
+            .entity(entity)
+            .insert(TemporalAntiAliasPipelineId(pipeline_id));
+    }
+}
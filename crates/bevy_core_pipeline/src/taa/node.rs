@@ -0,0 +1,133 @@
+use crate::{
+    prepass::ViewPrepassTextures,
+    taa::{TaaPipeline, TemporalAntiAliasHistoryTextures, TemporalAntiAliasPipelineId},
+};
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryState;
+use bevy_render::{
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_resource::{
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::{ExtractedView, ViewTarget},
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+/// Blends the main pass of a view with the colors accumulated over the previous frames, writing
+/// the result to both the view target and the history texture of the next frame.
+pub struct TemporalAntiAliasNode {
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            &'static TemporalAntiAliasHistoryTextures,
+            &'static ViewPrepassTextures,
+            &'static TemporalAntiAliasPipelineId,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl TemporalAntiAliasNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for TemporalAntiAliasNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((view_target, history_textures, prepass_textures, pipeline_id)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+        let (Some(pipeline), Some(motion_vectors)) = (
+            world
+                .resource::<PipelineCache>()
+                .get_render_pipeline(pipeline_id.0),
+            &prepass_textures.motion_vectors,
+        ) else {
+            return Ok(());
+        };
+        let taa_pipeline = world.resource::<TaaPipeline>();
+
+        #[cfg(feature = "trace")]
+        let _taa_span = info_span!("taa").entered();
+
+        let post_process = view_target.post_process_write();
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            Some("taa_bind_group"),
+            &taa_pipeline.layout,
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(post_process.source),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::TextureView(
+                        &history_textures.read.default_view,
+                    ),
+                },
+                CachedBindGroupEntry {
+                    binding: 2,
+                    resource: CachedBindingResource::TextureView(&motion_vectors.default_view),
+                },
+                CachedBindGroupEntry {
+                    binding: 3,
+                    resource: CachedBindingResource::Sampler(&taa_pipeline.nearest_sampler),
+                },
+                CachedBindGroupEntry {
+                    binding: 4,
+                    resource: CachedBindingResource::Sampler(&taa_pipeline.linear_sampler),
+                },
+            ],
+        );
+
+        let mut render_pass =
+            render_context
+                .command_encoder
+                .begin_render_pass(&RenderPassDescriptor {
+                    label: Some("taa_pass"),
+                    color_attachments: &[
+                        Some(RenderPassColorAttachment {
+                            view: post_process.destination,
+                            resolve_target: None,
+                            ops: Operations::default(),
+                        }),
+                        Some(RenderPassColorAttachment {
+                            view: &history_textures.write.default_view,
+                            resolve_target: None,
+                            ops: Operations::default(),
+                        }),
+                    ],
+                    depth_stencil_attachment: None,
+                });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
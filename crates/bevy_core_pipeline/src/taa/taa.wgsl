@@ -0,0 +1,165 @@
+// References:
+// https://www.elopezr.com/temporal-aa-and-the-quest-for-the-holy-trail
+// http://behindthepixels.io/assets/files/TemporalAA.pdf
+// https://advances.realtimerendering.com/s2014/index.html#_HIGH-QUALITY_TEMPORAL_SUPERSAMPLING
+
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+// The blend rate of the current frame when the history is new
+let DEFAULT_HISTORY_BLEND_RATE: f32 = 0.1;
+// The lowest blend rate of the current frame, once the history of a still pixel is confident
+let MIN_HISTORY_BLEND_RATE: f32 = 0.015;
+
+@group(0) @binding(0)
+var view_target: texture_2d<f32>;
+@group(0) @binding(1)
+var history: texture_2d<f32>;
+@group(0) @binding(2)
+var motion_vectors: texture_2d<f32>;
+@group(0) @binding(3)
+var nearest_sampler: sampler;
+@group(0) @binding(4)
+var linear_sampler: sampler;
+
+struct Output {
+    @location(0) view_target: vec4<f32>,
+    // The alpha channel is the confidence in the accumulated color
+    @location(1) history: vec4<f32>,
+};
+
+fn max3(x: vec3<f32>) -> f32 {
+    return max(x.r, max(x.g, x.b));
+}
+
+// Blending tonemapped colors keeps a few very bright samples from dominating the others, which
+// would make them flicker. The colors are tonemapped with a reversible operator, and the result
+// is mapped back to the colors of the view target.
+// https://gpuopen.com/learn/optimized-reversible-tonemapper-for-resolve
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    return color / (max3(color) + 1.0);
+}
+
+fn reverse_tonemap(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 - max3(color));
+}
+
+// https://github.com/playdeadgames/temporal/blob/master/Assets/Shaders/TemporalReprojection.shader
+fn rgb_to_ycocg(rgb: vec3<f32>) -> vec3<f32> {
+    let y = (rgb.r / 4.0) + (rgb.g / 2.0) + (rgb.b / 4.0);
+    let co = (rgb.r / 2.0) - (rgb.b / 2.0);
+    let cg = (-rgb.r / 4.0) + (rgb.g / 2.0) - (rgb.b / 4.0);
+    return vec3<f32>(y, co, cg);
+}
+
+fn ycocg_to_rgb(ycocg: vec3<f32>) -> vec3<f32> {
+    let r = ycocg.x + ycocg.y - ycocg.z;
+    let g = ycocg.x + ycocg.z;
+    let b = ycocg.x - ycocg.y - ycocg.z;
+    return saturate(vec3<f32>(r, g, b));
+}
+
+// Moves the history color towards the center of the box until it is inside it
+fn clip_towards_aabb_center(history_color: vec3<f32>, aabb_min: vec3<f32>, aabb_max: vec3<f32>) -> vec3<f32> {
+    let center = 0.5 * (aabb_max + aabb_min);
+    let extents = 0.5 * (aabb_max - aabb_min) + 0.00000001;
+    let offset = history_color - center;
+    let max_unit_offset = max3(abs(offset / extents));
+    if max_unit_offset > 1.0 {
+        return center + offset / max_unit_offset;
+    }
+    return history_color;
+}
+
+fn sample_history(u: f32, v: f32) -> vec3<f32> {
+    return textureSample(history, linear_sampler, vec2<f32>(u, v)).rgb;
+}
+
+fn sample_view_target(uv: vec2<f32>) -> vec3<f32> {
+    let sample = textureSample(view_target, nearest_sampler, uv).rgb;
+    return rgb_to_ycocg(tonemap(sample));
+}
+
+@fragment
+fn taa(@location(0) uv: vec2<f32>) -> Output {
+    let texture_size = vec2<f32>(textureDimensions(view_target));
+    let texel_size = 1.0 / texture_size;
+
+    let original_color = textureSample(view_target, nearest_sampler, uv);
+    var current_color = tonemap(original_color.rgb);
+
+#ifdef RESET
+    let history_confidence = 1.0;
+#else
+    // Find the UV of the pixel on the previous frame
+    let motion_vector = textureSample(motion_vectors, nearest_sampler, uv).rg;
+    let history_uv = uv - motion_vector;
+
+    // Sample the history with a 5-tap Catmull-Rom filter, which blurs it less than a bilinear one
+    // https://gist.github.com/TheRealMJP/c83b8c0f46b63f3a88a5986f4fa982b1
+    // The corner taps are skipped, which changes the result very little:
+    // https://www.activision.com/cdn/research/Dynamic_Temporal_Antialiasing_and_Upsampling_in_Call_of_Duty_v4.pdf#page=68
+    let sample_position = history_uv * texture_size;
+    let texel_center = floor(sample_position - 0.5) + 0.5;
+    let f = sample_position - texel_center;
+    let w0 = f * (-0.5 + f * (1.0 - 0.5 * f));
+    let w1 = 1.0 + f * f * (-2.5 + 1.5 * f);
+    let w2 = f * (0.5 + f * (2.0 - 1.5 * f));
+    let w3 = f * f * (-0.5 + 0.5 * f);
+    let w12 = w1 + w2;
+    let texel_position_0 = (texel_center - 1.0) * texel_size;
+    let texel_position_3 = (texel_center + 2.0) * texel_size;
+    let texel_position_12 = (texel_center + (w2 / w12)) * texel_size;
+    var history_color = sample_history(texel_position_12.x, texel_position_0.y) * w12.x * w0.y;
+    history_color += sample_history(texel_position_0.x, texel_position_12.y) * w0.x * w12.y;
+    history_color += sample_history(texel_position_12.x, texel_position_12.y) * w12.x * w12.y;
+    history_color += sample_history(texel_position_3.x, texel_position_12.y) * w3.x * w12.y;
+    history_color += sample_history(texel_position_12.x, texel_position_3.y) * w12.x * w3.y;
+
+    // Clip the history to the colors around the pixel on the current frame, which rejects the
+    // history of the pixels that were hidden or changed since
+    // Variance clipping: https://developer.download.nvidia.com/gameworks/events/GDC2016/msalvi_temporal_supersampling.pdf
+    var moment_1 = vec3<f32>(0.0);
+    var moment_2 = vec3<f32>(0.0);
+    for (var y = -1.0; y <= 1.0; y += 1.0) {
+        for (var x = -1.0; x <= 1.0; x += 1.0) {
+            let sample = sample_view_target(uv + vec2<f32>(x, y) * texel_size);
+            moment_1 += sample;
+            moment_2 += sample * sample;
+        }
+    }
+    let mean = moment_1 / 9.0;
+    let variance = moment_2 / 9.0 - mean * mean;
+    let std_deviation = sqrt(max(variance, vec3<f32>(0.0)));
+    history_color = rgb_to_ycocg(history_color);
+    history_color = clip_towards_aabb_center(history_color, mean - std_deviation, mean + std_deviation);
+    history_color = ycocg_to_rgb(history_color);
+
+    // The history of a still pixel is more and more representative of it, so less of the current
+    // frame is blended in over time, which removes more noise
+    // https://hhoppe.com/supersample.pdf, section 4.1
+    // NOTE: The history is loaded rather than sampled with the nearest sampler, as WebGL can't
+    // use a texture with more than one sampler
+    let previous_history_confidence = textureLoad(history, vec2<i32>(uv * texture_size), 0).a;
+    var history_confidence = previous_history_confidence;
+    let pixel_motion_vector = abs(motion_vector) * texture_size;
+    if pixel_motion_vector.x < 0.01 && pixel_motion_vector.y < 0.01 {
+        history_confidence += 10.0;
+    } else {
+        history_confidence = 1.0;
+    }
+    var current_color_factor = clamp(1.0 / history_confidence, MIN_HISTORY_BLEND_RATE, DEFAULT_HISTORY_BLEND_RATE);
+
+    // The history is empty on the first frame, and unknown where the pixel was off screen
+    if previous_history_confidence == 0.0 || any(saturate(history_uv) != history_uv) {
+        current_color_factor = 1.0;
+        history_confidence = 1.0;
+    }
+
+    current_color = mix(history_color, current_color, current_color_factor);
+#endif
+
+    return Output(
+        vec4<f32>(reverse_tonemap(current_color), original_color.a),
+        vec4<f32>(current_color, history_confidence),
+    );
+}
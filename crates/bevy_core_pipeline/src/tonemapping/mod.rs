@@ -3,11 +3,13 @@ use bevy_app::prelude::*;
 use bevy_asset::{load_internal_asset, HandleUntyped};
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::QueryItem;
-use bevy_reflect::{Reflect, TypeUuid};
+use bevy_reflect::{FromReflect, Reflect, TypeUuid};
 use bevy_render::camera::Camera;
 use bevy_render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy_render::render_asset::RenderAssets;
 use bevy_render::renderer::RenderDevice;
-use bevy_render::view::ViewTarget;
+use bevy_render::texture::Image;
+use bevy_render::view::{ColorGrading, ViewTarget, ViewUniform};
 use bevy_render::{render_resource::*, RenderApp, RenderStage};
 
 mod node;
@@ -37,7 +39,8 @@ impl Plugin for TonemappingPlugin {
             Shader::from_wgsl
         );
 
-        app.register_type::<Tonemapping>();
+        app.register_type::<Tonemapping>()
+            .register_type::<TonemappingMethod>();
 
         app.add_plugin(ExtractComponentPlugin::<Tonemapping>::default());
 
@@ -53,24 +56,34 @@ impl Plugin for TonemappingPlugin {
 #[derive(Resource)]
 pub struct TonemappingPipeline {
     texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
+    color_grading_lut_sampler: Sampler,
+    /// Bound in place of the [`ColorGrading::lut`] of the views without one.
+    fallback_color_grading_lut: TextureView,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct TonemappingPipelineKey {
     deband_dither: bool,
+    method: TonemappingMethod,
+    color_grading_lut: bool,
 }
 
 impl SpecializedRenderPipeline for TonemappingPipeline {
     type Key = TonemappingPipelineKey;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let mut shader_defs = Vec::new();
+        let mut shader_defs = vec![key.method.shader_def().into()];
         if key.deband_dither {
             shader_defs.push("DEBAND_DITHER".into());
         }
+        if key.color_grading_lut {
+            shader_defs.push("COLOR_GRADING_LUT".into());
+        }
         RenderPipelineDescriptor {
             label: Some("tonemapping pipeline".into()),
             layout: Some(vec![self.texture_bind_group.clone()]),
+            push_constant_ranges: Vec::new(),
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: TONEMAPPING_SHADER_HANDLE.typed(),
@@ -91,9 +104,9 @@ impl SpecializedRenderPipeline for TonemappingPipeline {
 
 impl FromWorld for TonemappingPipeline {
     fn from_world(render_world: &mut World) -> Self {
-        let tonemap_texture_bind_group = render_world
-            .resource::<RenderDevice>()
-            .create_bind_group_layout(&BindGroupLayoutDescriptor {
+        let render_device = render_world.resource::<RenderDevice>();
+        let tonemap_texture_bind_group =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 label: Some("tonemapping_hdr_texture_bind_group_layout"),
                 entries: &[
                     BindGroupLayoutEntry {
@@ -112,11 +125,68 @@ impl FromWorld for TonemappingPipeline {
                         ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
                         count: None,
                     },
+                    // View
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(ViewUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    // Color grading lookup table
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D3,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
                 ],
             });
 
+        let color_grading_lut_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("color_grading_lut_sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let fallback_color_grading_lut = render_device
+            .create_texture(&TextureDescriptor {
+                label: Some("fallback_color_grading_lut"),
+                size: Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D3,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING,
+            })
+            .create_view(&TextureViewDescriptor::default());
+
         TonemappingPipeline {
             texture_bind_group: tonemap_texture_bind_group,
+            sampler: render_device.create_sampler(&SamplerDescriptor::default()),
+            color_grading_lut_sampler,
+            fallback_color_grading_lut,
         }
     }
 }
@@ -129,12 +199,21 @@ pub fn queue_view_tonemapping_pipelines(
     mut pipeline_cache: ResMut<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<TonemappingPipeline>>,
     upscaling_pipeline: Res<TonemappingPipeline>,
-    view_targets: Query<(Entity, &Tonemapping)>,
+    images: Res<RenderAssets<Image>>,
+    view_targets: Query<(Entity, &Tonemapping, Option<&ColorGrading>)>,
 ) {
-    for (entity, tonemapping) in view_targets.iter() {
-        if let Tonemapping::Enabled { deband_dither } = tonemapping {
+    for (entity, tonemapping, color_grading) in view_targets.iter() {
+        if let Tonemapping::Enabled {
+            deband_dither,
+            method,
+        } = tonemapping
+        {
             let key = TonemappingPipelineKey {
                 deband_dither: *deband_dither,
+                method: *method,
+                color_grading_lut: color_grading
+                    .and_then(|color_grading| color_grading.lut.as_ref())
+                    .map_or(false, |lut| images.contains_key(lut)),
             };
             let pipeline = pipelines.specialize(&mut pipeline_cache, &upscaling_pipeline, key);
 
@@ -152,9 +231,35 @@ pub enum Tonemapping {
     Disabled,
     Enabled {
         deband_dither: bool,
+        method: TonemappingMethod,
     },
 }
 
+/// The operator used to map HDR colors to the displayable range when [`Tonemapping`] is enabled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Reflect, FromReflect)]
+pub enum TonemappingMethod {
+    /// Reinhard applied to each RGB channel. Simple, but oversaturates bright colors.
+    Reinhard,
+    /// Reinhard applied to the luminance of the color, which preserves its hue and saturation.
+    #[default]
+    ReinhardLuminance,
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve. Higher contrast, and desaturates very
+    /// bright colors towards white.
+    Aces,
+}
+
+impl TonemappingMethod {
+    /// Returns the shader def selecting this method in the `tonemap` function of the
+    /// `bevy_core_pipeline::tonemapping` shader import.
+    pub fn shader_def(&self) -> &'static str {
+        match self {
+            TonemappingMethod::Reinhard => "TONEMAP_METHOD_REINHARD",
+            TonemappingMethod::ReinhardLuminance => "TONEMAP_METHOD_REINHARD_LUMINANCE",
+            TonemappingMethod::Aces => "TONEMAP_METHOD_ACES",
+        }
+    }
+}
+
 impl Tonemapping {
     pub fn is_enabled(&self) -> bool {
         matches!(self, Tonemapping::Enabled { .. })
@@ -1,22 +1,28 @@
-use std::sync::Mutex;
-
 use crate::tonemapping::{TonemappingPipeline, ViewTonemappingPipeline};
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::QueryState;
 use bevy_render::{
+    render_asset::RenderAssets,
     render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
     render_resource::{
-        BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, LoadOp, Operations,
-        PipelineCache, RenderPassColorAttachment, RenderPassDescriptor, SamplerDescriptor,
-        TextureViewId,
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, LoadOp, Operations,
+        PipelineCache, RenderPassColorAttachment, RenderPassDescriptor,
     },
     renderer::RenderContext,
-    view::{ExtractedView, ViewTarget},
+    texture::Image,
+    view::{ColorGrading, ExtractedView, ViewTarget, ViewUniformOffset, ViewUniforms},
 };
 
 pub struct TonemappingNode {
-    query: QueryState<(&'static ViewTarget, &'static ViewTonemappingPipeline), With<ExtractedView>>,
-    cached_texture_bind_group: Mutex<Option<(TextureViewId, BindGroup)>>,
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            &'static ViewTonemappingPipeline,
+            &'static ViewUniformOffset,
+            Option<&'static ColorGrading>,
+        ),
+        With<ExtractedView>,
+    >,
 }
 
 impl TonemappingNode {
@@ -25,7 +31,6 @@ impl TonemappingNode {
     pub fn new(world: &mut World) -> Self {
         Self {
             query: QueryState::new(world),
-            cached_texture_bind_group: Mutex::new(None),
         }
     }
 }
@@ -49,10 +54,11 @@ impl Node for TonemappingNode {
         let pipeline_cache = world.resource::<PipelineCache>();
         let tonemapping_pipeline = world.resource::<TonemappingPipeline>();
 
-        let (target, tonemapping) = match self.query.get_manual(world, view_entity) {
-            Ok(result) => result,
-            Err(_) => return Ok(()),
-        };
+        let (target, tonemapping, view_uniform_offset, color_grading) =
+            match self.query.get_manual(world, view_entity) {
+                Ok(result) => result,
+                Err(_) => return Ok(()),
+            };
 
         if !target.is_hdr() {
             return Ok(());
@@ -63,40 +69,52 @@ impl Node for TonemappingNode {
             None => return Ok(()),
         };
 
+        let view_uniforms_binding = match world.resource::<ViewUniforms>().uniforms.cached_binding()
+        {
+            Some(binding) => binding,
+            None => return Ok(()),
+        };
+
+        let color_grading_lut = color_grading
+            .and_then(|color_grading| color_grading.lut.as_ref())
+            .and_then(|lut| world.resource::<RenderAssets<Image>>().get(lut))
+            .map_or(&tonemapping_pipeline.fallback_color_grading_lut, |lut| {
+                &lut.texture_view
+            });
+
         let post_process = target.post_process_write();
         let source = post_process.source;
         let destination = post_process.destination;
 
-        let mut cached_bind_group = self.cached_texture_bind_group.lock().unwrap();
-        let bind_group = match &mut *cached_bind_group {
-            Some((id, bind_group)) if source.id() == *id => bind_group,
-            cached_bind_group => {
-                let sampler = render_context
-                    .render_device
-                    .create_sampler(&SamplerDescriptor::default());
-
-                let bind_group =
-                    render_context
-                        .render_device
-                        .create_bind_group(&BindGroupDescriptor {
-                            label: None,
-                            layout: &tonemapping_pipeline.texture_bind_group,
-                            entries: &[
-                                BindGroupEntry {
-                                    binding: 0,
-                                    resource: BindingResource::TextureView(source),
-                                },
-                                BindGroupEntry {
-                                    binding: 1,
-                                    resource: BindingResource::Sampler(&sampler),
-                                },
-                            ],
-                        });
-
-                let (_, bind_group) = cached_bind_group.insert((source.id(), bind_group));
-                bind_group
-            }
-        };
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            None,
+            &tonemapping_pipeline.texture_bind_group,
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(source),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::Sampler(&tonemapping_pipeline.sampler),
+                },
+                CachedBindGroupEntry {
+                    binding: 2,
+                    resource: view_uniforms_binding,
+                },
+                CachedBindGroupEntry {
+                    binding: 3,
+                    resource: CachedBindingResource::TextureView(color_grading_lut),
+                },
+                CachedBindGroupEntry {
+                    binding: 4,
+                    resource: CachedBindingResource::Sampler(
+                        &tonemapping_pipeline.color_grading_lut_sampler,
+                    ),
+                },
+            ],
+        );
 
         let pass_descriptor = RenderPassDescriptor {
             label: Some("tonemapping_pass"),
@@ -116,7 +134,7 @@ impl Node for TonemappingNode {
             .begin_render_pass(&pass_descriptor);
 
         render_pass.set_pipeline(pipeline);
-        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(0, &bind_group, &[view_uniform_offset.offset]);
         render_pass.draw(0..3, 0..1);
 
         Ok(())
@@ -1,16 +1,50 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader
 #import bevy_core_pipeline::tonemapping
 
+struct View {
+    view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+    // viewport(x_origin, y_origin, width, height)
+    viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
+};
+
 @group(0) @binding(0)
 var hdr_texture: texture_2d<f32>;
 @group(0) @binding(1)
 var hdr_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> view: View;
+@group(0) @binding(3)
+var color_grading_lut: texture_3d<f32>;
+@group(0) @binding(4)
+var color_grading_lut_sampler: sampler;
+
+// Remaps a tonemapped color with the lookup table, which is indexed by sRGB encoded colors
+fn apply_lut(color: vec3<f32>) -> vec3<f32> {
+    let srgb_color = pow(saturate(color), vec3<f32>(1.0 / 2.2));
+    // Sample between the centers of the first and last texels, which hold the colors of 0 and 1
+    let lut_size = vec3<f32>(textureDimensions(color_grading_lut));
+    let uvw = (srgb_color * (lut_size - 1.0) + 0.5) / lut_size;
+    return textureSample(color_grading_lut, color_grading_lut_sampler, uvw).rgb;
+}
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     let hdr_color = textureSample(hdr_texture, hdr_sampler, in.uv);
 
-    var output_rgb = reinhard_luminance(hdr_color.rgb);
+    var output_rgb = tonemap(color_grade(hdr_color.rgb, view.color_grading));
+
+#ifdef COLOR_GRADING_LUT
+    output_rgb = apply_lut(output_rgb);
+#endif
 
 #ifdef DEBAND_DITHER
     output_rgb = pow(output_rgb.rgb, vec3<f32>(1.0 / 2.2));
@@ -28,6 +28,43 @@ fn reinhard_luminance(color: vec3<f32>) -> vec3<f32> {
     return tonemapping_change_luminance(color, l_new);
 }
 
+// ACES filmic curve fit by Krzysztof Narkowicz
+// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+fn tonemapping_aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Applies the tonemapping operator selected with the TONEMAP_METHOD_* shader defs,
+// falling back to reinhard_luminance.
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+#ifdef TONEMAP_METHOD_REINHARD
+    return tonemapping_reinhard(color);
+#else
+#ifdef TONEMAP_METHOD_ACES
+    return tonemapping_aces(color);
+#else
+    return reinhard_luminance(color);
+#endif
+#endif
+}
+
+// The brightness that contrast is applied around, so that it keeps the same brightness
+let COLOR_GRADING_MIDDLE_GREY: f32 = 0.18;
+
+// Applies the exposure (in stops), contrast and saturation of a `ColorGrading`, packed in that
+// order, to a linear HDR color before it is tonemapped.
+fn color_grade(color: vec3<f32>, color_grading: vec3<f32>) -> vec3<f32> {
+    var graded = max(color * exp2(color_grading.x), vec3<f32>(0.0));
+    graded = COLOR_GRADING_MIDDLE_GREY * pow(graded / COLOR_GRADING_MIDDLE_GREY, vec3<f32>(color_grading.y));
+    let luminance = tonemapping_luminance(graded);
+    return max(mix(vec3<f32>(luminance), graded, color_grading.z), vec3<f32>(0.0));
+}
+
 // Source: Advanced VR Rendering, GDC 2015, Alex Vlachos, Valve, Slide 49
 // https://media.steampowered.com/apps/valve/2015/Alex_Vlachos_Advanced_VR_Rendering_GDC2015.pdf
 fn screen_space_dither(frag_coord: vec2<f32>) -> vec3<f32> {
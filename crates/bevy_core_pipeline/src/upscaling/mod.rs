@@ -37,6 +37,7 @@ impl Plugin for UpscalingPlugin {
 #[derive(Resource)]
 pub struct UpscalingPipeline {
     texture_bind_group: BindGroupLayout,
+    sampler: Sampler,
 }
 
 impl FromWorld for UpscalingPipeline {
@@ -66,7 +67,12 @@ impl FromWorld for UpscalingPipeline {
                 ],
             });
 
-        UpscalingPipeline { texture_bind_group }
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        UpscalingPipeline {
+            texture_bind_group,
+            sampler,
+        }
     }
 }
 
@@ -89,6 +95,7 @@ impl SpecializedRenderPipeline for UpscalingPipeline {
         RenderPipelineDescriptor {
             label: Some("upscaling pipeline".into()),
             layout: Some(vec![self.texture_bind_group.clone()]),
+            push_constant_ranges: Vec::new(),
             vertex: fullscreen_shader_vertex_state(),
             fragment: Some(FragmentState {
                 shader: UPSCALING_SHADER_HANDLE.typed(),
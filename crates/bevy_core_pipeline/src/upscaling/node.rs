@@ -1,13 +1,10 @@
-use std::sync::Mutex;
-
 use bevy_ecs::prelude::*;
 use bevy_ecs::query::QueryState;
 use bevy_render::{
     render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
     render_resource::{
-        BindGroup, BindGroupDescriptor, BindGroupEntry, BindingResource, LoadOp, Operations,
-        PipelineCache, RenderPassColorAttachment, RenderPassDescriptor, SamplerDescriptor,
-        TextureViewId,
+        BindGroupCache, CachedBindGroupEntry, CachedBindingResource, LoadOp, Operations,
+        PipelineCache, RenderPassColorAttachment, RenderPassDescriptor,
     },
     renderer::RenderContext,
     view::{ExtractedView, ViewTarget},
@@ -17,7 +14,6 @@ use super::{UpscalingPipeline, ViewUpscalingPipeline};
 
 pub struct UpscalingNode {
     query: QueryState<(&'static ViewTarget, &'static ViewUpscalingPipeline), With<ExtractedView>>,
-    cached_texture_bind_group: Mutex<Option<(TextureViewId, BindGroup)>>,
 }
 
 impl UpscalingNode {
@@ -26,7 +22,6 @@ impl UpscalingNode {
     pub fn new(world: &mut World) -> Self {
         Self {
             query: QueryState::new(world),
-            cached_texture_bind_group: Mutex::new(None),
         }
     }
 }
@@ -58,36 +53,21 @@ impl Node for UpscalingNode {
 
         let upscaled_texture = target.main_texture();
 
-        let mut cached_bind_group = self.cached_texture_bind_group.lock().unwrap();
-        let bind_group = match &mut *cached_bind_group {
-            Some((id, bind_group)) if upscaled_texture.id() == *id => bind_group,
-            cached_bind_group => {
-                let sampler = render_context
-                    .render_device
-                    .create_sampler(&SamplerDescriptor::default());
-
-                let bind_group =
-                    render_context
-                        .render_device
-                        .create_bind_group(&BindGroupDescriptor {
-                            label: None,
-                            layout: &upscaling_pipeline.texture_bind_group,
-                            entries: &[
-                                BindGroupEntry {
-                                    binding: 0,
-                                    resource: BindingResource::TextureView(upscaled_texture),
-                                },
-                                BindGroupEntry {
-                                    binding: 1,
-                                    resource: BindingResource::Sampler(&sampler),
-                                },
-                            ],
-                        });
-
-                let (_, bind_group) = cached_bind_group.insert((upscaled_texture.id(), bind_group));
-                bind_group
-            }
-        };
+        let bind_group = world.resource::<BindGroupCache>().get_or_create(
+            &render_context.render_device,
+            None,
+            &upscaling_pipeline.texture_bind_group,
+            &[
+                CachedBindGroupEntry {
+                    binding: 0,
+                    resource: CachedBindingResource::TextureView(upscaled_texture),
+                },
+                CachedBindGroupEntry {
+                    binding: 1,
+                    resource: CachedBindingResource::Sampler(&upscaling_pipeline.sampler),
+                },
+            ],
+        );
 
         let pipeline = match pipeline_cache.get_render_pipeline(upscaling_target.0) {
             Some(pipeline) => pipeline,
@@ -112,7 +92,7 @@ impl Node for UpscalingNode {
             .begin_render_pass(&pass_descriptor);
 
         render_pass.set_pipeline(pipeline);
-        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.set_bind_group(0, &bind_group, &[]);
         render_pass.draw(0..3, 0..1);
 
         Ok(())
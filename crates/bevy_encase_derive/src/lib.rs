@@ -1,5 +1,8 @@
 use bevy_macro_utils::BevyManifest;
-use encase_derive_impl::{implement, syn};
+use encase_derive_impl::syn::{
+    self, parse_macro_input, spanned::Spanned, Data, DataStruct, DeriveInput, Fields, Path, Type,
+};
+use quote::{quote, quote_spanned};
 
 const BEVY: &str = "bevy";
 const BEVY_RENDER: &str = "bevy_render";
@@ -37,4 +40,134 @@ fn bevy_encase_path() -> syn::Path {
         .unwrap_or_else(|| bevy_manifest.get_path(ENCASE))
 }
 
-implement!(bevy_encase_path());
+/// Implements `ShaderType` for a struct, laying it out like WGSL does.
+///
+/// Structs only used in uniform buffers can be marked with `#[shader_type(uniform)]`, which
+/// checks at compile time that their layout follows the stricter rules of the uniform address
+/// space: structs and arrays need to be 16 bytes aligned, the elements of arrays need to be 16
+/// bytes apart, and runtime-sized arrays can't be stored. These would otherwise only be caught
+/// when the struct is first written into a buffer.
+#[proc_macro_derive(ShaderType, attributes(align, size, shader_type))]
+pub fn derive_shader_type(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let root = bevy_encase_path();
+    let uniform_layout_check =
+        uniform_layout_check(&input, &root).unwrap_or_else(syn::Error::into_compile_error);
+    let mut expanded = encase_derive_impl::derive_shader_type(input, &root);
+    expanded.extend(uniform_layout_check);
+    proc_macro::TokenStream::from(expanded)
+}
+
+/// Whether the struct is marked with `#[shader_type(uniform)]`.
+fn is_uniform(input: &DeriveInput) -> syn::Result<bool> {
+    let mut uniform = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("shader_type"))
+    {
+        let address_space: syn::Ident = attr.parse_args()?;
+        if address_space != "uniform" {
+            return Err(syn::Error::new(
+                address_space.span(),
+                "expected `uniform`, the only address space with layout checks",
+            ));
+        }
+        uniform = true;
+    }
+    Ok(uniform)
+}
+
+/// A constant evaluating the layout rules of the uniform address space on the fields of a struct
+/// marked with `#[shader_type(uniform)]`, which fails to compile when they are broken.
+fn uniform_layout_check(input: &DeriveInput, root: &Path) -> syn::Result<proc_macro2::TokenStream> {
+    if !is_uniform(input)? {
+        return Ok(quote! {});
+    }
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "`#[shader_type(uniform)]` is not supported on generic structs",
+        ));
+    }
+    // Other structs are reported by the `ShaderType` implementation
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Ok(quote! {});
+    };
+
+    let root = quote!(#root::private);
+    let name = &input.ident;
+    let mut checks = Vec::new();
+    let mut previous: Option<(&Type, String)> = None;
+    for (i, field) in fields.named.iter().enumerate() {
+        let ty = &field.ty;
+        let field_name = field.ident.as_ref().unwrap().to_string();
+        let runtime_sized = field.attrs.iter().any(|attr| {
+            attr.path.is_ident("size")
+                && attr
+                    .parse_args::<syn::Ident>()
+                    .map_or(false, |size| size == "runtime")
+        });
+        if runtime_sized {
+            return Err(syn::Error::new(
+                field.span(),
+                "runtime-sized arrays can't be stored in uniform buffers",
+            ));
+        }
+
+        checks.push(quote_spanned! {ty.span()=>
+            if let ::core::option::Option::Some(min_alignment) =
+                <#ty as #root::ShaderType>::METADATA.uniform_min_alignment()
+            {
+                let offset = <#name as #root::ShaderType>::METADATA.offset(#i);
+                #root::concat_assert!(
+                    min_alignment.is_aligned(offset),
+                    "in uniform buffers, the offset of field '", #field_name,
+                    "' must be a multiple of ", min_alignment.get(), " (currently: ", offset, ")"
+                );
+            }
+        });
+        if let Some((previous_ty, previous_name)) = previous {
+            let previous_index = i - 1;
+            checks.push(quote_spanned! {ty.span()=>
+                if let ::core::option::Option::Some(min_alignment) =
+                    <#previous_ty as #root::ShaderType>::METADATA.uniform_min_alignment()
+                {
+                    let distance = <#name as #root::ShaderType>::METADATA.offset(#i)
+                        - <#name as #root::ShaderType>::METADATA.offset(#previous_index);
+                    let size = min_alignment
+                        .round_up(<#previous_ty as #root::ShaderSize>::SHADER_SIZE.get());
+                    #root::concat_assert!(
+                        distance >= size,
+                        "in uniform buffers, field '", #field_name, "' must start at least ",
+                        size, " bytes after field '", #previous_name, "' (currently: ", distance, ")"
+                    );
+                }
+            });
+        }
+        if let Type::Array(array) = ty {
+            let element = &array.elem;
+            checks.push(quote_spanned! {ty.span()=>
+                let stride = <#element as #root::ShaderType>::METADATA
+                    .alignment()
+                    .round_up(<#element as #root::ShaderSize>::SHADER_SIZE.get());
+                #root::concat_assert!(
+                    stride % 16 == 0,
+                    "in uniform buffers, the elements of array field '", #field_name,
+                    "' must be a multiple of 16 bytes apart (currently: ", stride, ")"
+                );
+            });
+        }
+        previous = Some((ty, field_name));
+    }
+
+    Ok(quote! {
+        const _: () = {
+            #(#checks)*
+        };
+    })
+}
@@ -0,0 +1,218 @@
+use std::f32::consts::TAU;
+
+use bevy_ecs::{
+    prelude::*,
+    system::{ResMut, SystemParam},
+};
+use bevy_math::{Quat, Vec2, Vec3};
+use bevy_render::{color::Color, primitives::Aabb};
+use bevy_transform::prelude::{GlobalTransform, Transform};
+use bytemuck::{Pod, Zeroable};
+
+/// The number of line segments the circles and spheres are drawn with.
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub(crate) struct LineGizmoVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+/// The lines drawn with [`Gizmos`] during the current frame.
+///
+/// It is cleared at the start of every frame, after the lines drawn during the previous one have
+/// been extracted for rendering.
+#[derive(Resource, Default)]
+pub struct GizmoStorage {
+    /// The ends of the lines, two by two.
+    pub(crate) vertices: Vec<LineGizmoVertex>,
+}
+
+impl GizmoStorage {
+    /// The number of lines drawn so far this frame.
+    pub fn len(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+}
+
+/// A [`SystemParam`] for drawing debug lines in world space, without spawning meshes.
+///
+/// The shapes drawn with it are only rendered for the current frame, so systems draw them again
+/// every frame they should stay visible:
+///
+/// ```rust
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_gizmos::prelude::*;
+/// # use bevy_math::Vec3;
+/// # use bevy_render::prelude::Color;
+/// fn draw_ray(mut gizmos: Gizmos) {
+///     gizmos.ray(Vec3::ZERO, Vec3::new(1.0, 2.0, 0.0), Color::RED);
+/// }
+/// # bevy_ecs::system::assert_is_system(draw_ray);
+/// ```
+///
+/// They are drawn by every 2D and 3D camera, after the opaque and transparent meshes, and the
+/// [`GizmoConfig`](crate::GizmoConfig) resource controls whether meshes hide them.
+#[derive(SystemParam)]
+pub struct Gizmos<'w> {
+    storage: ResMut<'w, GizmoStorage>,
+}
+
+impl<'w> Gizmos<'w> {
+    /// Draws a line from `start` to `end`.
+    #[inline]
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: Color) {
+        self.line_gradient(start, end, color, color);
+    }
+
+    /// Draws a line from `start` to `end`, whose color fades from `start_color` to `end_color`.
+    #[inline]
+    pub fn line_gradient(&mut self, start: Vec3, end: Vec3, start_color: Color, end_color: Color) {
+        self.storage.vertices.extend([
+            LineGizmoVertex {
+                position: start.to_array(),
+                color: start_color.as_linear_rgba_f32(),
+            },
+            LineGizmoVertex {
+                position: end.to_array(),
+                color: end_color.as_linear_rgba_f32(),
+            },
+        ]);
+    }
+
+    /// Draws lines joining each of the `positions` to the next one.
+    pub fn linestrip(&mut self, positions: impl IntoIterator<Item = Vec3>, color: Color) {
+        let mut positions = positions.into_iter();
+        let Some(mut start) = positions.next() else {
+            return;
+        };
+        for end in positions {
+            self.line(start, end, color);
+            start = end;
+        }
+    }
+
+    /// Draws a line from `start` along `vector`, which ends at `start + vector`.
+    #[inline]
+    pub fn ray(&mut self, start: Vec3, vector: Vec3, color: Color) {
+        self.line(start, start + vector, color);
+    }
+
+    /// Draws the edges of a cube with sides of length `1.0`, centered at the origin and moved into
+    /// world space by the `transform`.
+    ///
+    /// Scale the `transform` to draw any other box.
+    pub fn cuboid(&mut self, transform: impl Into<GlobalTransform>, color: Color) {
+        let transform = transform.into();
+        let corners = [
+            Vec3::new(-0.5, -0.5, -0.5),
+            Vec3::new(0.5, -0.5, -0.5),
+            Vec3::new(0.5, 0.5, -0.5),
+            Vec3::new(-0.5, 0.5, -0.5),
+            Vec3::new(-0.5, -0.5, 0.5),
+            Vec3::new(0.5, -0.5, 0.5),
+            Vec3::new(0.5, 0.5, 0.5),
+            Vec3::new(-0.5, 0.5, 0.5),
+        ]
+        .map(|corner| transform.transform_point(corner));
+
+        for i in 0..4 {
+            let next = (i + 1) % 4;
+            // The back face, the front face, and the edges between them
+            self.line(corners[i], corners[next], color);
+            self.line(corners[i + 4], corners[next + 4], color);
+            self.line(corners[i], corners[i + 4], color);
+        }
+    }
+
+    /// Draws the edges of the [`Aabb`] of an entity with this `transform`, such as the ones
+    /// computed for the frustum culling of meshes.
+    pub fn aabb(&mut self, aabb: &Aabb, transform: &GlobalTransform, color: Color) {
+        let local = Transform::from_translation(aabb.center.into())
+            .with_scale((aabb.half_extents * 2.0).into());
+        self.cuboid(transform.mul_transform(local), color);
+    }
+
+    /// Draws a circle of this `radius` around `position`, in the plane facing `normal`.
+    pub fn circle(&mut self, position: Vec3, normal: Vec3, radius: f32, color: Color) {
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal.normalize());
+        self.linestrip(
+            circle_positions(radius).map(|point| position + rotation * point.extend(0.0)),
+            color,
+        );
+    }
+
+    /// Draws a sphere of this `radius` around `position`, as three circles around the axes of
+    /// its `rotation`.
+    pub fn sphere(&mut self, position: Vec3, rotation: Quat, radius: f32, color: Color) {
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.circle(position, rotation * axis, radius, color);
+        }
+    }
+
+    /// Draws the `X`, `Y` and `Z` axes of the `transform` in red, green and blue, with lines of
+    /// this `length` before scaling.
+    pub fn axes(&mut self, transform: impl Into<GlobalTransform>, length: f32) {
+        let transform = transform.into();
+        let origin = transform.translation();
+        for (axis, color) in [
+            (Vec3::X, Color::RED),
+            (Vec3::Y, Color::GREEN),
+            (Vec3::Z, Color::BLUE),
+        ] {
+            self.line(origin, transform.transform_point(axis * length), color);
+        }
+    }
+
+    /// Draws a line from `start` to `end` in the `XY` plane, as seen by 2D cameras.
+    #[inline]
+    pub fn line_2d(&mut self, start: Vec2, end: Vec2, color: Color) {
+        self.line(start.extend(0.0), end.extend(0.0), color);
+    }
+
+    /// Draws a line from `start` along `vector` in the `XY` plane.
+    #[inline]
+    pub fn ray_2d(&mut self, start: Vec2, vector: Vec2, color: Color) {
+        self.line_2d(start, start + vector, color);
+    }
+
+    /// Draws a circle of this `radius` around `position` in the `XY` plane.
+    pub fn circle_2d(&mut self, position: Vec2, radius: f32, color: Color) {
+        self.linestrip(
+            circle_positions(radius).map(|point| (position + point).extend(0.0)),
+            color,
+        );
+    }
+
+    /// Draws a rectangle of this `size` around `position` in the `XY` plane, rotated by
+    /// `rotation` radians counterclockwise.
+    pub fn rect_2d(&mut self, position: Vec2, rotation: f32, size: Vec2, color: Color) {
+        let rotation = Vec2::from_angle(rotation);
+        let half_size = size / 2.0;
+        let corners = [
+            Vec2::new(-half_size.x, -half_size.y),
+            Vec2::new(half_size.x, -half_size.y),
+            Vec2::new(half_size.x, half_size.y),
+            Vec2::new(-half_size.x, half_size.y),
+            Vec2::new(-half_size.x, -half_size.y),
+        ];
+        self.linestrip(
+            corners.map(|corner| (position + rotation.rotate(corner)).extend(0.0)),
+            color,
+        );
+    }
+}
+
+/// The points of a closed circle of this `radius` around the origin, the first one repeated at
+/// the end.
+fn circle_positions(radius: f32) -> impl Iterator<Item = Vec2> {
+    (0..=CIRCLE_SEGMENTS).map(move |i| {
+        let angle = i as f32 * TAU / CIRCLE_SEGMENTS as f32;
+        Vec2::from_angle(angle) * radius
+    })
+}
@@ -0,0 +1,174 @@
+//! Immediate mode drawing of debug lines, for visualizing things like raycasts, bounding boxes or
+//! the axes of entities without creating meshes.
+//!
+//! Draw with the [`Gizmos`] system parameter from any system, every frame the lines should be
+//! visible.
+
+mod gizmos;
+mod render;
+
+pub use gizmos::*;
+pub use render::*;
+
+pub mod prelude {
+    #[doc(hidden)]
+    pub use crate::{GizmoConfig, Gizmos};
+}
+
+pub mod graph {
+    pub mod node {
+        pub const LINE_GIZMO: &str = "line_gizmo";
+    }
+}
+
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_core_pipeline::{
+    core_2d::{self, Camera2d},
+    core_3d::{self, Camera3d},
+};
+use bevy_ecs::prelude::*;
+use bevy_reflect::TypeUuid;
+use bevy_render::{
+    camera::Camera,
+    render_graph::RenderGraph,
+    render_phase::{AddRenderCommand, DrawFunctions, RenderPhase},
+    render_resource::{Shader, SpecializedRenderPipelines},
+    Extract, RenderApp, RenderStage,
+};
+
+pub const LINE_GIZMO_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7414812689238026784);
+
+/// Adds the [`Gizmos`] system parameter, and draws the lines it records with every 2D and 3D
+/// camera.
+pub struct GizmoPlugin;
+
+impl Plugin for GizmoPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            LINE_GIZMO_SHADER_HANDLE,
+            "line_gizmo.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.init_resource::<GizmoConfig>()
+            .init_resource::<GizmoStorage>()
+            .add_system_to_stage(CoreStage::First, clear_gizmos);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .init_resource::<ExtractedGizmos>()
+            .init_resource::<LineGizmoPipeline>()
+            .init_resource::<SpecializedRenderPipelines<LineGizmoPipeline>>()
+            .init_resource::<LineGizmoMeta>()
+            .init_resource::<DrawFunctions<LineGizmo>>()
+            .add_render_command::<LineGizmo, DrawLineGizmo>()
+            .add_system_to_stage(RenderStage::Extract, extract_gizmos)
+            .add_system_to_stage(RenderStage::Extract, extract_line_gizmo_phases)
+            .add_system_to_stage(RenderStage::Prepare, prepare_line_gizmos)
+            .add_system_to_stage(RenderStage::Queue, queue_line_gizmos);
+
+        let line_gizmo_node_2d = LineGizmoNode::new(&mut render_app.world);
+        let line_gizmo_node_3d = LineGizmoNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+
+        if let Some(graph_2d) = graph.get_sub_graph_mut(core_2d::graph::NAME) {
+            graph_2d.add_node(graph::node::LINE_GIZMO, line_gizmo_node_2d);
+            graph_2d.add_slot_edge(
+                graph_2d.input_node().id,
+                core_2d::graph::input::VIEW_ENTITY,
+                graph::node::LINE_GIZMO,
+                LineGizmoNode::IN_VIEW,
+            );
+            // MAIN_PASS -> LINE_GIZMO -> BLOOM -> TONEMAPPING
+            graph_2d.add_node_edge(core_2d::graph::node::MAIN_PASS, graph::node::LINE_GIZMO);
+            graph_2d.add_node_edge(graph::node::LINE_GIZMO, core_2d::graph::node::BLOOM);
+            graph_2d.add_node_edge(graph::node::LINE_GIZMO, core_2d::graph::node::TONEMAPPING);
+        }
+
+        if let Some(graph_3d) = graph.get_sub_graph_mut(core_3d::graph::NAME) {
+            graph_3d.add_node(graph::node::LINE_GIZMO, line_gizmo_node_3d);
+            graph_3d.add_slot_edge(
+                graph_3d.input_node().id,
+                core_3d::graph::input::VIEW_ENTITY,
+                graph::node::LINE_GIZMO,
+                LineGizmoNode::IN_VIEW,
+            );
+            // MAIN_PASS -> LINE_GIZMO -> TAA -> ... -> TONEMAPPING
+            // NOTE: The lines are drawn before the post-processing, like the meshes they
+            // visualize, so that they are antialiased and tonemapped the same way.
+            graph_3d.add_node_edge(core_3d::graph::node::MAIN_PASS, graph::node::LINE_GIZMO);
+            graph_3d.add_node_edge(graph::node::LINE_GIZMO, core_3d::graph::node::TAA);
+            graph_3d.add_node_edge(graph::node::LINE_GIZMO, core_3d::graph::node::TONEMAPPING);
+        }
+    }
+}
+
+/// Configures how the lines drawn with [`Gizmos`] are rendered.
+#[derive(Resource, Clone, Debug)]
+pub struct GizmoConfig {
+    /// Whether the lines are rendered at all (default: `true`).
+    ///
+    /// The [`Gizmos`] still record them when disabled, so they can be toggled without changing
+    /// the systems drawing them.
+    pub enabled: bool,
+    /// Whether the lines are hidden behind the meshes of 3D views (default: `true`).
+    ///
+    /// Lines drawn by 2D views are always drawn over the sprites and 2D meshes.
+    pub depth_test: bool,
+}
+
+impl Default for GizmoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            depth_test: true,
+        }
+    }
+}
+
+/// Clears the [`GizmoStorage`] at the start of a frame, as the lines of the previous frame have
+/// been extracted for rendering at its end.
+pub fn clear_gizmos(mut storage: ResMut<GizmoStorage>) {
+    storage.vertices.clear();
+}
+
+/// The lines of the [`GizmoStorage`] the render world draws this frame.
+#[derive(Resource, Default)]
+pub struct ExtractedGizmos {
+    vertices: Vec<LineGizmoVertex>,
+    depth_test: bool,
+}
+
+pub fn extract_gizmos(
+    mut extracted_gizmos: ResMut<ExtractedGizmos>,
+    storage: Extract<Res<GizmoStorage>>,
+    config: Extract<Res<GizmoConfig>>,
+) {
+    extracted_gizmos.vertices.clear();
+    if config.enabled {
+        extracted_gizmos
+            .vertices
+            .extend_from_slice(&storage.vertices);
+    }
+    extracted_gizmos.depth_test = config.depth_test;
+}
+
+pub fn extract_line_gizmo_phases(
+    mut commands: Commands,
+    cameras: Extract<Query<(Entity, &Camera), Or<(With<Camera2d>, With<Camera3d>)>>>,
+) {
+    for (entity, camera) in &cameras {
+        if camera.is_active {
+            commands
+                .get_or_spawn(entity)
+                .insert(RenderPhase::<LineGizmo>::default());
+        }
+    }
+}
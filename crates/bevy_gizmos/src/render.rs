@@ -0,0 +1,398 @@
+use bevy_ecs::{
+    prelude::*,
+    system::{lifetimeless::*, SystemParamItem},
+};
+use bevy_render::{
+    camera::ExtractedCamera,
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_phase::{
+        CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, EntityPhaseItem,
+        EntityRenderCommand, PhaseItem, RenderCommandResult, RenderPhase, SetItemPipeline,
+        TrackedRenderPass,
+    },
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::BevyDefault,
+    view::{
+        ExtractedView, Msaa, ViewDepthTexture, ViewTarget, ViewUniform, ViewUniformOffset,
+        ViewUniforms,
+    },
+};
+use std::ops::Range;
+
+use crate::{gizmos::LineGizmoVertex, ExtractedGizmos, LINE_GIZMO_SHADER_HANDLE};
+
+#[derive(Resource)]
+pub struct LineGizmoPipeline {
+    pub view_layout: BindGroupLayout,
+}
+
+impl FromWorld for LineGizmoPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(ViewUniform::min_size()),
+                },
+                count: None,
+            }],
+            label: Some("line_gizmo_view_layout"),
+        });
+
+        LineGizmoPipeline { view_layout }
+    }
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub struct LineGizmoPipelineKey {
+    pub hdr: bool,
+    pub samples: u32,
+    /// Whether the view has a depth buffer the pass attaches, which 2D views don't have.
+    pub view_depth: bool,
+    /// Whether the lines are hidden behind the meshes in that depth buffer.
+    pub depth_test: bool,
+}
+
+impl SpecializedRenderPipeline for LineGizmoPipeline {
+    type Key = LineGizmoPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let vertex_layout = VertexBufferLayout::from_vertex_formats(
+            VertexStepMode::Vertex,
+            vec![
+                // position
+                VertexFormat::Float32x3,
+                // color
+                VertexFormat::Float32x4,
+            ],
+        );
+
+        RenderPipelineDescriptor {
+            vertex: VertexState {
+                shader: LINE_GIZMO_SHADER_HANDLE.typed::<Shader>(),
+                entry_point: "vertex".into(),
+                shader_defs: Vec::new(),
+                buffers: vec![vertex_layout],
+            },
+            fragment: Some(FragmentState {
+                shader: LINE_GIZMO_SHADER_HANDLE.typed::<Shader>(),
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: if key.hdr {
+                        ViewTarget::TEXTURE_FORMAT_HDR
+                    } else {
+                        TextureFormat::bevy_default()
+                    },
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            layout: Some(vec![self.view_layout.clone()]),
+            push_constant_ranges: Vec::new(),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // NOTE: The lines never write to the depth buffer, so that they don't hide each other.
+            depth_stencil: key.view_depth.then(|| DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                // NOTE: Values increase towards the camera, due to bevy's use of reverse-z
+                // projections.
+                depth_compare: if key.depth_test {
+                    CompareFunction::GreaterEqual
+                } else {
+                    CompareFunction::Always
+                },
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.samples,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            label: Some("line_gizmo_pipeline".into()),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct LineGizmoMeta {
+    vertices: BufferVec<LineGizmoVertex>,
+    view_bind_group: Option<BindGroup>,
+}
+
+impl Default for LineGizmoMeta {
+    fn default() -> Self {
+        Self {
+            vertices: BufferVec::new(BufferUsages::VERTEX),
+            view_bind_group: None,
+        }
+    }
+}
+
+/// The range of the [`LineGizmoMeta`] vertex buffer drawn by a [`LineGizmo`] phase item.
+#[derive(Component)]
+pub struct LineGizmoBatch {
+    pub range: Range<u32>,
+}
+
+pub fn prepare_line_gizmos(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut line_gizmo_meta: ResMut<LineGizmoMeta>,
+    extracted_gizmos: Res<ExtractedGizmos>,
+) {
+    line_gizmo_meta.vertices.clear();
+    if extracted_gizmos.vertices.is_empty() {
+        return;
+    }
+
+    for vertex in &extracted_gizmos.vertices {
+        line_gizmo_meta.vertices.push(*vertex);
+    }
+    // All the lines are drawn at once, with a single draw call per view
+    commands.spawn(LineGizmoBatch {
+        range: 0..extracted_gizmos.vertices.len() as u32,
+    });
+
+    line_gizmo_meta
+        .vertices
+        .write_buffer(&render_device, &render_queue);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_line_gizmos(
+    draw_functions: Res<DrawFunctions<LineGizmo>>,
+    render_device: Res<RenderDevice>,
+    mut line_gizmo_meta: ResMut<LineGizmoMeta>,
+    view_uniforms: Res<ViewUniforms>,
+    line_gizmo_pipeline: Res<LineGizmoPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<LineGizmoPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    extracted_gizmos: Res<ExtractedGizmos>,
+    batches: Query<Entity, With<LineGizmoBatch>>,
+    mut views: Query<(
+        &ExtractedView,
+        Option<&ViewDepthTexture>,
+        &mut RenderPhase<LineGizmo>,
+    )>,
+) {
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    line_gizmo_meta.view_bind_group = Some(render_device.create_bind_group(&BindGroupDescriptor {
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: view_binding,
+        }],
+        label: Some("line_gizmo_view_bind_group"),
+        layout: &line_gizmo_pipeline.view_layout,
+    }));
+
+    let draw_line_gizmo = draw_functions.read().id::<DrawLineGizmo>();
+    for (view, depth, mut line_gizmo_phase) in &mut views {
+        let pipeline = pipelines.specialize(
+            &mut pipeline_cache,
+            &line_gizmo_pipeline,
+            LineGizmoPipelineKey {
+                hdr: view.hdr,
+                samples: msaa.samples,
+                view_depth: depth.is_some(),
+                depth_test: extracted_gizmos.depth_test,
+            },
+        );
+        for entity in &batches {
+            line_gizmo_phase.add(LineGizmo {
+                entity,
+                pipeline,
+                draw_function: draw_line_gizmo,
+            });
+        }
+    }
+}
+
+/// The render phase of the lines drawn with [`Gizmos`](crate::Gizmos), which run after the main
+/// pass of each 2D and 3D view.
+pub struct LineGizmo {
+    pub entity: Entity,
+    pub pipeline: CachedRenderPipelineId,
+    pub draw_function: DrawFunctionId,
+}
+
+impl PhaseItem for LineGizmo {
+    // NOTE: Lines are blended in the order they are drawn in, so they aren't sorted.
+    type SortKey = ();
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {}
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn sort(_items: &mut [Self]) {}
+}
+
+impl EntityPhaseItem for LineGizmo {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for LineGizmo {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+pub type DrawLineGizmo = (
+    SetItemPipeline,
+    SetLineGizmoViewBindGroup<0>,
+    DrawLineGizmoBatch,
+);
+
+pub struct SetLineGizmoViewBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetLineGizmoViewBindGroup<I> {
+    type Param = (SRes<LineGizmoMeta>, SQuery<Read<ViewUniformOffset>>);
+
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        (line_gizmo_meta, view_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let view_uniform = view_query.get(view).unwrap();
+        pass.set_bind_group(
+            I,
+            line_gizmo_meta
+                .into_inner()
+                .view_bind_group
+                .as_ref()
+                .unwrap(),
+            &[view_uniform.offset],
+        );
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawLineGizmoBatch;
+impl EntityRenderCommand for DrawLineGizmoBatch {
+    type Param = (SRes<LineGizmoMeta>, SQuery<Read<LineGizmoBatch>>);
+
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (line_gizmo_meta, query_batch): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let batch = query_batch.get(item).unwrap();
+        let Some(vertices) = line_gizmo_meta.into_inner().vertices.buffer() else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, vertices.slice(..));
+        pass.draw(batch.range.clone(), 0..1);
+        RenderCommandResult::Success
+    }
+}
+
+/// Draws the [`LineGizmo`] phase of a view over its main texture, testing the lines against its
+/// depth buffer when it has one.
+pub struct LineGizmoNode {
+    query: QueryState<
+        (
+            &'static ExtractedCamera,
+            &'static RenderPhase<LineGizmo>,
+            &'static ViewTarget,
+            Option<&'static ViewDepthTexture>,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl LineGizmoNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: world.query_filtered(),
+        }
+    }
+}
+
+impl Node for LineGizmoNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(LineGizmoNode::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((camera, line_gizmo_phase, target, depth)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+        if line_gizmo_phase.items.is_empty() {
+            return Ok(());
+        }
+
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("line_gizmo_pass"),
+            color_attachments: &[Some(target.get_color_attachment(Operations {
+                load: LoadOp::Load,
+                store: true,
+            }))],
+            depth_stencil_attachment: depth.map(|depth| RenderPassDepthStencilAttachment {
+                view: &depth.view,
+                // NOTE: The lines only test against the depth buffer, but store is set to `true`
+                // so that wgpu does not clear it for the passes after this one.
+                depth_ops: Some(Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        };
+
+        let draw_functions = world.resource::<DrawFunctions<LineGizmo>>();
+
+        let render_pass = render_context
+            .command_encoder
+            .begin_render_pass(&pass_descriptor);
+
+        let mut draw_functions = draw_functions.write();
+        let mut tracked_pass = TrackedRenderPass::new(render_pass);
+        if let Some(viewport) = camera.viewport.as_ref() {
+            tracked_pass.set_camera_viewport(viewport);
+        }
+        for item in &line_gizmo_phase.items {
+            let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+            draw_function.draw(world, &mut tracked_pass, view_entity, item);
+        }
+        Ok(())
+    }
+}
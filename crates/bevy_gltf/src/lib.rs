@@ -29,18 +29,33 @@ impl Plugin for GltfPlugin {
 }
 
 /// Representation of a loaded glTF file.
+///
+/// Every part of the file is also a labeled sub-asset, which can be loaded on its own, for example
+/// a single primitive with `asset_server.load("models/house.gltf#Mesh0/Primitive0")`. The labels
+/// are the kind of the part followed by its index in the file: `Scene0`, `Node0`, `Mesh0`,
+/// `Mesh0/Primitive0`, `Material0` (or `MaterialDefault` for primitives without a material),
+/// `Texture0`, `Skin0` and `Animation0`.
+///
+/// Loading the file itself, without a label, gives this asset, whose maps find the handles of the
+/// parts of the file by their name instead.
 #[derive(Debug, TypeUuid)]
 #[uuid = "5c7d5f8a-f7b0-4e45-a09e-406c0372fea2"]
 pub struct Gltf {
+    /// The scenes of the file, labeled `Scene{index}`.
     pub scenes: Vec<Handle<Scene>>,
     pub named_scenes: HashMap<String, Handle<Scene>>,
+    /// The meshes of the file, labeled `Mesh{index}`.
     pub meshes: Vec<Handle<GltfMesh>>,
     pub named_meshes: HashMap<String, Handle<GltfMesh>>,
+    /// The materials of the file, labeled `Material{index}`.
     pub materials: Vec<Handle<StandardMaterial>>,
     pub named_materials: HashMap<String, Handle<StandardMaterial>>,
+    /// The nodes of the file, labeled `Node{index}`.
     pub nodes: Vec<Handle<GltfNode>>,
     pub named_nodes: HashMap<String, Handle<GltfNode>>,
+    /// The scene the file specifies to display by default, if any.
     pub default_scene: Option<Handle<Scene>>,
+    /// The animations of the file, labeled `Animation{index}`.
     #[cfg(feature = "bevy_animation")]
     pub animations: Vec<Handle<AnimationClip>>,
     #[cfg(feature = "bevy_animation")]
@@ -19,6 +19,7 @@ use bevy_render::{
     },
     color::Color,
     mesh::{
+        morph::{MeshMorphWeights, MorphAttributes, MorphWeights},
         skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
         Indices, Mesh, VertexAttributeValues,
     },
@@ -26,7 +27,9 @@ use bevy_render::{
     primitives::{Aabb, Frustum},
     render_resource::{AddressMode, Face, FilterMode, PrimitiveTopology, SamplerDescriptor},
     renderer::RenderDevice,
-    texture::{CompressedImageFormats, Image, ImageSampler, ImageType, TextureError},
+    texture::{
+        CompressedImageFormats, DefaultImageMipmaps, Image, ImageSampler, ImageType, TextureError,
+    },
     view::VisibleEntities,
 };
 use bevy_scene::Scene;
@@ -68,11 +71,22 @@ pub enum GltfError {
     MissingAnimationSampler(usize),
     #[error("failed to generate tangents: {0}")]
     GenerateTangentsError(#[from] bevy_render::mesh::GenerateTangentsError),
+    #[error("unsupported required extension: {0}, the file needs to be decompressed with a tool like gltf-transform")]
+    UnsupportedRequiredExtension(String),
 }
 
+/// Extensions compressing the vertex data of the meshes, which can't be loaded without decoding.
+///
+/// Files using them without requiring them also contain the uncompressed data, which is loaded
+/// instead.
+// TODO: decode KHR_draco_mesh_compression once a Rust Draco decoder is available
+const UNSUPPORTED_COMPRESSION_EXTENSIONS: &[&str] =
+    &["KHR_draco_mesh_compression", "EXT_meshopt_compression"];
+
 /// Loads glTF files with all of their data as their corresponding bevy representations.
 pub struct GltfLoader {
     supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
 }
 
 impl AssetLoader for GltfLoader {
@@ -82,7 +96,13 @@ impl AssetLoader for GltfLoader {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<()>> {
         Box::pin(async move {
-            Ok(load_gltf(bytes, load_context, self.supported_compressed_formats).await?)
+            Ok(load_gltf(
+                bytes,
+                load_context,
+                self.supported_compressed_formats,
+                self.generate_mipmaps,
+            )
+            .await?)
         })
     }
 
@@ -100,6 +120,9 @@ impl FromWorld for GltfLoader {
         };
         Self {
             supported_compressed_formats,
+            generate_mipmaps: world
+                .get_resource::<DefaultImageMipmaps>()
+                .map_or(false, |generate_mipmaps| **generate_mipmaps),
         }
     }
 }
@@ -109,8 +132,10 @@ async fn load_gltf<'a, 'b>(
     bytes: &'a [u8],
     load_context: &'a mut LoadContext<'b>,
     supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
 ) -> Result<(), GltfError> {
-    let gltf = gltf::Gltf::from_slice(bytes)?;
+    let gltf = gltf::Gltf::from_slice_without_validation(bytes)?;
+    validate(&gltf)?;
     let buffer_data = load_buffers(&gltf, load_context, load_context.path()).await?;
 
     let mut materials = vec![];
@@ -156,8 +181,12 @@ async fn load_gltf<'a, 'b>(
         for animation in gltf.animations() {
             let mut animation_clip = bevy_animation::AnimationClip::default();
             for channel in animation.channels() {
-                match channel.sampler().interpolation() {
+                let interpolation = channel.sampler().interpolation();
+                match interpolation {
                     gltf::animation::Interpolation::Linear => (),
+                    gltf::animation::Interpolation::CubicSpline => warn!(
+                        "Animation interpolation CubicSpline is not supported, will use linear between the keyframe values"
+                    ),
                     other => warn!(
                         "Animation interpolation {:?} is not supported, will use linear",
                         other
@@ -191,15 +220,19 @@ async fn load_gltf<'a, 'b>(
                         gltf::animation::util::ReadOutputs::Scales(scale) => {
                             bevy_animation::Keyframes::Scale(scale.map(Vec3::from).collect())
                         }
-                        gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => {
-                            warn!("Morph animation property not yet supported");
-                            continue;
+                        gltf::animation::util::ReadOutputs::MorphTargetWeights(weights) => {
+                            bevy_animation::Keyframes::Weights(weights.into_f32().collect())
                         }
                     }
                 } else {
                     warn!("Animations without a sampler output are not supported");
                     return Err(GltfError::MissingAnimationSampler(animation.index()));
                 };
+                let keyframes = if interpolation == gltf::animation::Interpolation::CubicSpline {
+                    cubic_spline_values(keyframes, keyframe_timestamps.len())
+                } else {
+                    keyframes
+                };
 
                 if let Some((root_index, path)) = paths.get(&node.index()) {
                     animation_roots.insert(root_index);
@@ -263,6 +296,13 @@ async fn load_gltf<'a, 'b>(
                 mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vertex_attribute);
             }
 
+            if let Some(vertex_attribute) = reader
+                .read_tex_coords(1)
+                .map(|v| VertexAttributeValues::Float32x2(v.into_f32().collect()))
+            {
+                mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, vertex_attribute);
+            }
+
             if let Some(vertex_attribute) = reader
                 .read_colors(0)
                 .map(|v| VertexAttributeValues::Float32x4(v.into_rgba_f32().collect()))
@@ -286,6 +326,36 @@ async fn load_gltf<'a, 'b>(
                 mesh.set_indices(Some(Indices::U32(indices.into_u32().collect())));
             };
 
+            let morph_targets = reader.read_morph_targets();
+            if morph_targets.len() > 0 {
+                let vertex_count = mesh.count_vertices();
+                let morph_targets = morph_targets.map(|(positions, normals, tangents)| {
+                    let mut attributes = vec![MorphAttributes::default(); vertex_count];
+                    for (attribute, position) in
+                        attributes.iter_mut().zip(positions.into_iter().flatten())
+                    {
+                        attribute.position = position.into();
+                    }
+                    for (attribute, normal) in
+                        attributes.iter_mut().zip(normals.into_iter().flatten())
+                    {
+                        attribute.normal = normal.into();
+                    }
+                    for (attribute, tangent) in
+                        attributes.iter_mut().zip(tangents.into_iter().flatten())
+                    {
+                        attribute.tangent = tangent.into();
+                    }
+                    attributes
+                });
+                if let Err(err) = mesh.set_morph_targets(morph_targets) {
+                    warn!(
+                        "Failed to load the morph targets of {}: {}",
+                        primitive_label, err
+                    );
+                }
+            }
+
             if mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_none()
                 && matches!(mesh.primitive_topology(), PrimitiveTopology::TriangleList)
             {
@@ -402,6 +472,7 @@ async fn load_gltf<'a, 'b>(
                 &linear_textures,
                 load_context,
                 supported_compressed_formats,
+                generate_mipmaps,
             )
             .await?;
             load_context.set_labeled_asset(&label, LoadedAsset::new(texture));
@@ -421,6 +492,7 @@ async fn load_gltf<'a, 'b>(
                             linear_textures,
                             load_context,
                             supported_compressed_formats,
+                            generate_mipmaps,
                         )
                         .await
                     });
@@ -574,6 +646,7 @@ async fn load_texture<'a>(
     linear_textures: &HashSet<usize>,
     load_context: &LoadContext<'a>,
     supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
 ) -> Result<(Image, String), GltfError> {
     let is_srgb = !linear_textures.contains(&gltf_texture.index());
     let mut texture = match gltf_texture.source().source() {
@@ -615,6 +688,7 @@ async fn load_texture<'a>(
         }
     };
     texture.sampler_descriptor = ImageSampler::Descriptor(texture_sampler(&gltf_texture));
+    texture.generate_mipmaps = generate_mipmaps;
 
     Ok((texture, texture_label(&gltf_texture)))
 }
@@ -657,7 +731,11 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         load_context.get_handle(path)
     });
 
-    let emissive = material.emissive_factor();
+    // KHR_materials_emissive_strength scales the emissive factor above 1.0
+    let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+    let emissive = material
+        .emissive_factor()
+        .map(|factor| factor * emissive_strength);
     let emissive_texture = material.emissive_texture().map(|info| {
         // TODO: handle occlusion_texture.tex_coord() (the *set* index for the right texcoords)
         // TODO: handle occlusion_texture.strength() (a scalar multiplier for occlusion strength)
@@ -666,6 +744,46 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         load_context.get_handle(path)
     });
 
+    let transmission = material.transmission().map_or(0.0, |transmission| {
+        if transmission.transmission_texture().is_some() {
+            warn!("Transmission textures are not supported, only the transmission factor is used");
+        }
+        transmission.transmission_factor()
+    });
+
+    // Only the reflectance of dielectrics depends on their index of refraction, as transmitted
+    // light isn't refracted
+    let reflectance = material
+        .ior()
+        .map_or(0.5, |ior| (ior - 1.0).abs() / (ior + 1.0) / 0.4);
+
+    // KHR_materials_clearcoat isn't parsed by the gltf crate
+    let (clearcoat, clearcoat_perceptual_roughness) = material
+        .extension_value("KHR_materials_clearcoat")
+        .map_or((0.0, 0.5), |clearcoat| {
+            if [
+                "clearcoatTexture",
+                "clearcoatRoughnessTexture",
+                "clearcoatNormalTexture",
+            ]
+            .iter()
+            .any(|texture| clearcoat.get(texture).is_some())
+            {
+                warn!("Clearcoat textures are not supported, only the clearcoat factors are used");
+            }
+            let factor = |name| clearcoat.get(name).and_then(|value| value.as_f64());
+            (
+                factor("clearcoatFactor").unwrap_or(0.0) as f32,
+                factor("clearcoatRoughnessFactor").unwrap_or(0.0) as f32,
+            )
+        });
+
+    // Transmitted light only shows what is behind the material when it is blended with it
+    let alpha_mode = match alpha_mode(material) {
+        AlphaMode::Opaque if transmission > 0.0 => AlphaMode::Premultiplied,
+        alpha_mode => alpha_mode,
+    };
+
     load_context.set_labeled_asset(
         &material_label,
         LoadedAsset::new(StandardMaterial {
@@ -685,7 +803,11 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
             emissive: Color::rgb_linear(emissive[0], emissive[1], emissive[2]),
             emissive_texture,
             unlit: material.unlit(),
-            alpha_mode: alpha_mode(material),
+            alpha_mode,
+            reflectance,
+            clearcoat,
+            clearcoat_perceptual_roughness,
+            transmission,
             ..Default::default()
         }),
     )
@@ -760,6 +882,36 @@ fn load_node(
         *active_camera_found = true;
     }
 
+    // The weights of the morph targets of the primitives, which are animated on the node
+    let morph_weights = gltf_node.mesh().and_then(|mesh| {
+        let target_count = mesh
+            .primitives()
+            .map(|primitive| primitive.morph_targets().len())
+            .max()
+            .unwrap_or(0);
+        if target_count == 0 {
+            return None;
+        }
+        let weights = gltf_node
+            .weights()
+            .or_else(|| mesh.weights())
+            .map_or_else(|| vec![0.0; target_count], <[f32]>::to_vec);
+        match MorphWeights::new(weights) {
+            Ok(weights) => Some(weights),
+            Err(err) => {
+                warn!(
+                    "Failed to load the morph weights of node {}: {}",
+                    gltf_node.index(),
+                    err
+                );
+                None
+            }
+        }
+    });
+    if let Some(morph_weights) = &morph_weights {
+        node.insert(morph_weights.clone());
+    }
+
     // Map node index to entity
     node_index_to_entity_map.insert(gltf_node.index(), node.id());
 
@@ -802,6 +954,13 @@ fn load_node(
                 if let Some(name) = mesh.name() {
                     mesh_entity.insert(Name::new(name.to_string()));
                 }
+                if let Some(morph_weights) = &morph_weights {
+                    if primitive.morph_targets().len() > 0 {
+                        mesh_entity.insert(
+                            MeshMorphWeights::new(morph_weights.weights().to_vec()).unwrap(),
+                        );
+                    }
+                }
                 // Mark for adding skinned mesh
                 if let Some(skin) = gltf_node.skin() {
                     entity_to_skin_index_map.insert(mesh_entity.id(), skin.index());
@@ -941,6 +1100,41 @@ fn scene_label(scene: &gltf::Scene) -> String {
     format!("Scene{}", scene.index())
 }
 
+/// Keeps the values of cubic spline keyframes, which are stored between their in and out
+/// tangents.
+#[cfg(feature = "bevy_animation")]
+fn cubic_spline_values(
+    keyframes: bevy_animation::Keyframes,
+    keyframe_count: usize,
+) -> bevy_animation::Keyframes {
+    fn values<T: Copy>(keyframes: Vec<T>, keyframe_count: usize) -> Vec<T> {
+        // Morph target weights have a value per target in each keyframe
+        let per_keyframe = keyframes.len() / (keyframe_count * 3).max(1);
+        if per_keyframe == 0 {
+            return keyframes;
+        }
+        keyframes
+            .chunks_exact(per_keyframe * 3)
+            .flat_map(|keyframe| keyframe[per_keyframe..per_keyframe * 2].iter().copied())
+            .collect()
+    }
+
+    match keyframes {
+        bevy_animation::Keyframes::Rotation(rotations) => {
+            bevy_animation::Keyframes::Rotation(values(rotations, keyframe_count))
+        }
+        bevy_animation::Keyframes::Translation(translations) => {
+            bevy_animation::Keyframes::Translation(values(translations, keyframe_count))
+        }
+        bevy_animation::Keyframes::Scale(scales) => {
+            bevy_animation::Keyframes::Scale(values(scales, keyframe_count))
+        }
+        bevy_animation::Keyframes::Weights(weights) => {
+            bevy_animation::Keyframes::Weights(values(weights, keyframe_count))
+        }
+    }
+}
+
 fn skin_label(skin: &gltf::Skin) -> String {
     format!("Skin{}", skin.index())
 }
@@ -1061,6 +1255,32 @@ async fn load_buffers(
     Ok(buffer_data)
 }
 
+/// Validates the glTF file like [`gltf::Gltf::from_slice`], first rejecting the
+/// [`UNSUPPORTED_COMPRESSION_EXTENSIONS`] with a more helpful error than the `gltf` crate's.
+fn validate(gltf: &gltf::Gltf) -> Result<(), GltfError> {
+    use gltf::json::validation::Validate;
+
+    if let Some(extension) = gltf
+        .extensions_required()
+        .find(|extension| UNSUPPORTED_COMPRESSION_EXTENSIONS.contains(extension))
+    {
+        return Err(GltfError::UnsupportedRequiredExtension(
+            extension.to_string(),
+        ));
+    }
+
+    let root = gltf.document.as_json();
+    let mut errors = Vec::new();
+    root.validate(root, gltf::json::Path::new, &mut |path, error| {
+        errors.push((path(), error));
+    });
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(GltfError::Gltf(gltf::Error::Validation(errors)))
+    }
+}
+
 fn resolve_node_hierarchy(
     nodes_intermediate: Vec<(String, GltfNode, Vec<usize>)>,
     asset_path: &Path,
@@ -1157,7 +1377,7 @@ impl<'a> DataUri<'a> {
 mod test {
     use std::path::PathBuf;
 
-    use super::resolve_node_hierarchy;
+    use super::{resolve_node_hierarchy, validate, GltfError};
     use crate::GltfNode;
 
     impl GltfNode {
@@ -1274,4 +1494,25 @@ mod test {
         assert_eq!(result[0].0, "l2");
         assert_eq!(result[0].1.children.len(), 0);
     }
+
+    fn gltf_requiring(extension: &str) -> gltf::Gltf {
+        let json = format!(
+            r#"{{"asset":{{"version":"2.0"}},"extensionsUsed":["{extension}"],"extensionsRequired":["{extension}"]}}"#
+        );
+        gltf::Gltf::from_slice_without_validation(json.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn validate_required_extensions() {
+        for extension in ["KHR_draco_mesh_compression", "EXT_meshopt_compression"] {
+            assert!(matches!(
+                validate(&gltf_requiring(extension)),
+                Err(GltfError::UnsupportedRequiredExtension(name)) if name == extension
+            ));
+        }
+        assert!(matches!(
+            validate(&gltf_requiring("EXT_unknown")),
+            Err(GltfError::Gltf(gltf::Error::Validation(_)))
+        ));
+    }
 }
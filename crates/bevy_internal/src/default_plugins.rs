@@ -14,11 +14,13 @@ use bevy_app::{PluginGroup, PluginGroupBuilder};
 /// * [`RenderPlugin`](crate::render::RenderPlugin) - with feature `bevy_render`
 /// * [`SpritePlugin`](crate::sprite::SpritePlugin) - with feature `bevy_sprite`
 /// * [`PbrPlugin`](crate::pbr::PbrPlugin) - with feature `bevy_pbr`
+/// * [`GizmoPlugin`](crate::gizmos::GizmoPlugin) - with feature `bevy_gizmos`
 /// * [`UiPlugin`](crate::ui::UiPlugin) - with feature `bevy_ui`
 /// * [`TextPlugin`](crate::text::TextPlugin) - with feature `bevy_text`
 /// * [`AudioPlugin`](crate::audio::AudioPlugin) - with feature `bevy_audio`
 /// * [`GilrsPlugin`](crate::gilrs::GilrsPlugin) - with feature `bevy_gilrs`
 /// * [`GltfPlugin`](crate::gltf::GltfPlugin) - with feature `bevy_gltf`
+/// * [`ObjPlugin`](crate::obj::ObjPlugin) - with feature `bevy_obj`
 /// * [`WinitPlugin`](crate::winit::WinitPlugin) - with feature `bevy_winit`
 ///
 /// See also [`MinimalPlugins`] for a slimmed down option
@@ -91,6 +93,11 @@ impl PluginGroup for DefaultPlugins {
             group = group.add(bevy_pbr::PbrPlugin::default());
         }
 
+        #[cfg(feature = "bevy_gizmos")]
+        {
+            group = group.add(bevy_gizmos::GizmoPlugin);
+        }
+
         // NOTE: Load this after renderer initialization so that it knows about the supported
         // compressed texture formats
         #[cfg(feature = "bevy_gltf")]
@@ -98,6 +105,11 @@ impl PluginGroup for DefaultPlugins {
             group = group.add(bevy_gltf::GltfPlugin::default());
         }
 
+        #[cfg(feature = "bevy_obj")]
+        {
+            group = group.add(bevy_obj::ObjPlugin);
+        }
+
         #[cfg(feature = "bevy_audio")]
         {
             group = group.add(bevy_audio::AudioPlugin::default());
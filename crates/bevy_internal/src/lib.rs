@@ -121,12 +121,24 @@ pub mod gilrs {
     pub use bevy_gilrs::*;
 }
 
+#[cfg(feature = "bevy_gizmos")]
+pub mod gizmos {
+    //! Immediate mode drawing of debug lines.
+    pub use bevy_gizmos::*;
+}
+
 #[cfg(feature = "bevy_gltf")]
 pub mod gltf {
     //! Support for GLTF file loading.
     pub use bevy_gltf::*;
 }
 
+#[cfg(feature = "bevy_obj")]
+pub mod obj {
+    //! Support for OBJ file loading.
+    pub use bevy_obj::*;
+}
+
 #[cfg(feature = "bevy_pbr")]
 pub mod pbr {
     //! Physically based rendering.
@@ -138,7 +150,7 @@ pub mod render {
     //! Cameras, meshes, textures, shaders, and pipelines.
     //! Use [`RenderDevice::features`](crate::render::renderer::RenderDevice::features),
     //! [`RenderDevice::limits`](crate::render::renderer::RenderDevice::limits), and the
-    //! [`WgpuAdapterInfo`](crate::render::render_resource::WgpuAdapterInfo) resource to
+    //! [`RenderAdapterInfo`](crate::render::renderer::RenderAdapterInfo) resource to
     //! get runtime information about the actual adapter, backend, features, and limits.
     pub use bevy_render::*;
 }
@@ -0,0 +1,62 @@
+use bevy_utils::HashMap;
+
+mod loader;
+mod parse;
+pub use loader::*;
+pub use parse::ParseError;
+
+use bevy_app::prelude::*;
+use bevy_asset::{AddAsset, Handle};
+use bevy_pbr::StandardMaterial;
+use bevy_reflect::TypeUuid;
+use bevy_render::mesh::Mesh;
+use bevy_scene::Scene;
+
+/// Adds support for loading `.obj` files, and the `.mtl` files of their materials, to the app.
+#[derive(Default)]
+pub struct ObjPlugin;
+
+impl Plugin for ObjPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset_loader::<ObjLoader>()
+            .add_asset::<Obj>()
+            .add_asset::<ObjMesh>();
+    }
+}
+
+/// Representation of a loaded OBJ file.
+///
+/// Every part of the file is also a labeled sub-asset, which can be loaded on its own: `Scene`
+/// spawns all the meshes with their materials, `Mesh0` is the first object or group of faces of
+/// the file as an [`ObjMesh`], `Mesh0/Primitive0` the [`Mesh`] of its faces with the first
+/// material they use, and `Material0` the first material of the `.mtl` files of the file.
+///
+/// Loading the file itself, without a label, gives this asset, whose maps find the handles of the
+/// parts of the file by their name instead.
+#[derive(Debug, TypeUuid)]
+#[uuid = "55d04513-5ed3-4a33-a090-879c9d584e6b"]
+pub struct Obj {
+    /// The scene of the file, labeled `Scene`.
+    pub scene: Handle<Scene>,
+    /// The objects and groups of faces of the file, labeled `Mesh{index}`.
+    pub meshes: Vec<Handle<ObjMesh>>,
+    pub named_meshes: HashMap<String, Handle<ObjMesh>>,
+    /// The materials of the `.mtl` files of the file, labeled `Material{index}`.
+    pub materials: Vec<Handle<StandardMaterial>>,
+    pub named_materials: HashMap<String, Handle<StandardMaterial>>,
+}
+
+/// An object or group of faces of an OBJ file, with an [`ObjPrimitive`] per material its faces
+/// use.
+#[derive(Debug, Clone, TypeUuid)]
+#[uuid = "9c78325b-5c0b-4469-a152-289800700c86"]
+pub struct ObjMesh {
+    pub primitives: Vec<ObjPrimitive>,
+}
+
+/// Part of an [`ObjMesh`] that consists of a [`Mesh`] and an optional [`StandardMaterial`].
+#[derive(Debug, Clone)]
+pub struct ObjPrimitive {
+    pub mesh: Handle<Mesh>,
+    pub material: Option<Handle<StandardMaterial>>,
+}
@@ -0,0 +1,334 @@
+use std::path::Path;
+
+use anyhow::Result;
+use bevy_asset::{AssetIoError, AssetLoader, BoxedFuture, Handle, LoadContext, LoadedAsset};
+use bevy_core::Name;
+use bevy_ecs::{prelude::FromWorld, world::World};
+use bevy_hierarchy::BuildWorldChildren;
+use bevy_log::warn;
+use bevy_pbr::{AlphaMode, PbrBundle, StandardMaterial};
+use bevy_render::{
+    color::Color,
+    mesh::{Indices, Mesh},
+    prelude::SpatialBundle,
+    render_resource::{AddressMode, FilterMode, PrimitiveTopology, SamplerDescriptor},
+    renderer::RenderDevice,
+    texture::{CompressedImageFormats, DefaultImageMipmaps, Image, ImageSampler, ImageType},
+};
+use bevy_scene::Scene;
+use bevy_utils::HashMap;
+use thiserror::Error;
+
+use crate::{
+    parse::{parse_mtl, parse_obj, MtlMaterial, ObjPrimitiveData},
+    Obj, ObjMesh, ObjPrimitive, ParseError,
+};
+
+/// An error that occurs when loading an OBJ file.
+#[derive(Error, Debug)]
+pub enum ObjError {
+    #[error("invalid OBJ file: {0}")]
+    InvalidObj(ParseError),
+    #[error("invalid MTL file {path}: {error}")]
+    InvalidMtl { path: String, error: ParseError },
+    #[error("the file is not valid UTF-8")]
+    InvalidUtf8(#[from] std::str::Utf8Error),
+    #[error("failed to load an asset path: {0}")]
+    AssetIoError(#[from] AssetIoError),
+}
+
+/// Loads OBJ files with their materials as their corresponding bevy representations.
+pub struct ObjLoader {
+    supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
+}
+
+impl AssetLoader for ObjLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        Box::pin(async move {
+            Ok(load_obj(
+                bytes,
+                load_context,
+                self.supported_compressed_formats,
+                self.generate_mipmaps,
+            )
+            .await?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+}
+
+impl FromWorld for ObjLoader {
+    fn from_world(world: &mut World) -> Self {
+        let supported_compressed_formats = match world.get_resource::<RenderDevice>() {
+            Some(render_device) => CompressedImageFormats::from_features(render_device.features()),
+
+            None => CompressedImageFormats::all(),
+        };
+        Self {
+            supported_compressed_formats,
+            generate_mipmaps: world
+                .get_resource::<DefaultImageMipmaps>()
+                .map_or(false, |generate_mipmaps| **generate_mipmaps),
+        }
+    }
+}
+
+/// Loads an entire OBJ file.
+async fn load_obj<'a, 'b>(
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext<'b>,
+    supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
+) -> Result<(), ObjError> {
+    let obj = parse_obj(std::str::from_utf8(bytes)?).map_err(ObjError::InvalidObj)?;
+    let directory = load_context.path().parent().unwrap().to_owned();
+
+    let mut mtl_materials = Vec::new();
+    for library in &obj.material_libraries {
+        // Many files reference material libraries they aren't distributed with, which leaves
+        // their meshes with the default material
+        let bytes = match load_context.read_asset_bytes(directory.join(library)).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!("Failed to load the material library {}: {}", library, err);
+                continue;
+            }
+        };
+        let materials =
+            parse_mtl(std::str::from_utf8(&bytes)?).map_err(|error| ObjError::InvalidMtl {
+                path: library.clone(),
+                error,
+            })?;
+        mtl_materials.extend(materials);
+    }
+
+    let mut textures = TextureLoader {
+        directory: &directory,
+        supported_compressed_formats,
+        generate_mipmaps,
+        handles: HashMap::default(),
+    };
+    let mut materials = vec![];
+    let mut named_materials = HashMap::default();
+    for (index, mtl_material) in mtl_materials.iter().enumerate() {
+        let material = load_material(mtl_material, &mut textures, load_context).await;
+        let handle =
+            load_context.set_labeled_asset(&format!("Material{index}"), LoadedAsset::new(material));
+        named_materials.insert(mtl_material.name.clone(), handle.clone());
+        materials.push(handle);
+    }
+
+    let mut meshes = vec![];
+    let mut named_meshes = HashMap::default();
+    let mut world = World::default();
+    let mut root = world.spawn(SpatialBundle::VISIBLE_IDENTITY);
+    for (mesh_index, obj_mesh) in obj.meshes.into_iter().enumerate() {
+        let mut primitives = vec![];
+        for (primitive_index, obj_primitive) in obj_mesh.primitives.into_iter().enumerate() {
+            let material = obj_primitive.material.as_ref().and_then(|name| {
+                let material = named_materials.get(name);
+                if material.is_none() {
+                    warn!("Material {} not found, using the default material", name);
+                }
+                material.cloned()
+            });
+            let has_normal_map = obj_primitive
+                .material
+                .as_ref()
+                .and_then(|name| mtl_materials.iter().find(|material| &material.name == name))
+                .map_or(false, |material| material.normal_texture.is_some());
+            let mesh = load_mesh(obj_primitive, has_normal_map);
+            let mesh = load_context.set_labeled_asset(
+                &format!("Mesh{mesh_index}/Primitive{primitive_index}"),
+                LoadedAsset::new(mesh),
+            );
+            primitives.push(ObjPrimitive { mesh, material });
+        }
+
+        root.with_children(|parent| {
+            let mut entity = parent.spawn(SpatialBundle::VISIBLE_IDENTITY);
+            if let Some(name) = &obj_mesh.name {
+                entity.insert(Name::new(name.clone()));
+            }
+            entity.with_children(|parent| {
+                for primitive in &primitives {
+                    parent.spawn(PbrBundle {
+                        mesh: primitive.mesh.clone(),
+                        material: primitive.material.clone().unwrap_or_default(),
+                        ..Default::default()
+                    });
+                }
+            });
+        });
+
+        let handle = load_context.set_labeled_asset(
+            &format!("Mesh{mesh_index}"),
+            LoadedAsset::new(ObjMesh { primitives }),
+        );
+        if let Some(name) = obj_mesh.name {
+            named_meshes.insert(name, handle.clone());
+        }
+        meshes.push(handle);
+    }
+
+    let scene = load_context.set_labeled_asset("Scene", LoadedAsset::new(Scene::new(world)));
+    load_context.set_default_asset(LoadedAsset::new(Obj {
+        scene,
+        meshes,
+        named_meshes,
+        materials,
+        named_materials,
+    }));
+
+    Ok(())
+}
+
+/// Creates the [`Mesh`] of the faces of an [`ObjPrimitiveData`].
+fn load_mesh(primitive: ObjPrimitiveData, has_normal_map: bool) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, primitive.positions);
+    if let Some(uvs) = primitive.uvs {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    }
+    let has_normals = primitive.normals.is_some();
+    if let Some(normals) = primitive.normals {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    }
+    mesh.set_indices(Some(Indices::U32(primitive.indices)));
+
+    if !has_normals {
+        bevy_log::debug!("Missing vertex normals in indexed geometry, computing them as flat.");
+        mesh.duplicate_vertices();
+        mesh.compute_flat_normals();
+    }
+
+    if has_normal_map && mesh.contains_attribute(Mesh::ATTRIBUTE_UV_0) {
+        bevy_log::debug!("Missing vertex tangents, computing them using the mikktspace algorithm");
+        if let Err(err) = mesh.generate_tangents() {
+            warn!("Failed to generate vertex tangents using the mikktspace algorithm: {err}");
+        }
+    }
+
+    mesh
+}
+
+/// Loads the textures of the materials as labeled sub-assets, once per file and color space.
+struct TextureLoader<'a> {
+    directory: &'a Path,
+    supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
+    handles: HashMap<(String, bool), Handle<Image>>,
+}
+
+impl<'a> TextureLoader<'a> {
+    async fn load(
+        &mut self,
+        path: &str,
+        is_srgb: bool,
+        load_context: &mut LoadContext<'_>,
+    ) -> Option<Handle<Image>> {
+        let key = (path.to_string(), is_srgb);
+        if let Some(handle) = self.handles.get(&key) {
+            return Some(handle.clone());
+        }
+
+        let bytes = match load_context
+            .read_asset_bytes(self.directory.join(path))
+            .await
+        {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!("Failed to load the texture {}: {}", path, err);
+                return None;
+            }
+        };
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        let mut image = match Image::from_buffer(
+            &bytes,
+            ImageType::Extension(extension),
+            self.supported_compressed_formats,
+            is_srgb,
+        ) {
+            Ok(image) => image,
+            Err(err) => {
+                warn!("Failed to load the texture {}: {}", path, err);
+                return None;
+            }
+        };
+        // The UVs of OBJ files commonly tile their textures
+        image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        image.generate_mipmaps = self.generate_mipmaps;
+
+        let handle = load_context.set_labeled_asset(
+            &format!("Texture{}", self.handles.len()),
+            LoadedAsset::new(image),
+        );
+        self.handles.insert(key, handle.clone());
+        Some(handle)
+    }
+}
+
+/// Converts an MTL material into a [`StandardMaterial`].
+async fn load_material(
+    material: &MtlMaterial,
+    textures: &mut TextureLoader<'_>,
+    load_context: &mut LoadContext<'_>,
+) -> StandardMaterial {
+    let [r, g, b] = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
+    let alpha = material.dissolve.unwrap_or(1.0);
+    let emissive = material.emissive.unwrap_or([0.0, 0.0, 0.0]);
+
+    // Converts the Phong exponent to the roughness of the GGX distribution matching it best,
+    // see http://simonstechblog.blogspot.com/2011/12/microfacet-brdf.html
+    let perceptual_roughness = material.roughness.unwrap_or_else(|| {
+        let exponent = material.specular_exponent.unwrap_or(0.0).max(0.0);
+        (2.0 / (exponent + 2.0)).sqrt().sqrt()
+    });
+
+    let mut base_color_texture = None;
+    if let Some(path) = &material.diffuse_texture {
+        base_color_texture = textures.load(path, true, load_context).await;
+    }
+    let mut emissive_texture = None;
+    if let Some(path) = &material.emissive_texture {
+        emissive_texture = textures.load(path, true, load_context).await;
+    }
+    let mut normal_map_texture = None;
+    if let Some(path) = &material.normal_texture {
+        normal_map_texture = textures.load(path, false, load_context).await;
+    }
+
+    StandardMaterial {
+        base_color: Color::rgba(r, g, b, alpha),
+        base_color_texture,
+        emissive: Color::rgb(emissive[0], emissive[1], emissive[2]),
+        emissive_texture,
+        perceptual_roughness,
+        metallic: material.metallic.unwrap_or(0.0),
+        normal_map_texture,
+        alpha_mode: if alpha < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        ..Default::default()
+    }
+}
@@ -0,0 +1,425 @@
+//! Parsers of the text of `.obj` and `.mtl` files, independent of the assets they are loaded as.
+
+use bevy_utils::HashMap;
+use thiserror::Error;
+
+/// An error in the text of an `.obj` or `.mtl` file.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("line {line}: {message}")]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// The meshes of an `.obj` file, and the material libraries they reference.
+#[derive(Debug, Default)]
+pub struct ObjData {
+    pub material_libraries: Vec<String>,
+    pub meshes: Vec<ObjMeshData>,
+}
+
+/// The faces of an object or group of an `.obj` file, split by material.
+#[derive(Debug, Default)]
+pub struct ObjMeshData {
+    pub name: Option<String>,
+    pub primitives: Vec<ObjPrimitiveData>,
+}
+
+/// Indexed triangles with the same material.
+///
+/// `normals` and `uvs` are `None` unless every vertex of the faces has one.
+#[derive(Debug, Default)]
+pub struct ObjPrimitiveData {
+    pub material: Option<String>,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub uvs: Option<Vec<[f32; 2]>>,
+    pub indices: Vec<u32>,
+}
+
+/// The indices of the position, UV and normal of a vertex of a face, which it is deduplicated by.
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct PrimitiveBuilder {
+    material: Option<String>,
+    vertices: HashMap<VertexKey, u32>,
+    keys: Vec<VertexKey>,
+    indices: Vec<u32>,
+}
+
+impl PrimitiveBuilder {
+    fn new(material: Option<String>) -> Self {
+        Self {
+            material,
+            ..Default::default()
+        }
+    }
+
+    fn vertex(&mut self, key: VertexKey) -> u32 {
+        let keys = &mut self.keys;
+        *self.vertices.entry(key).or_insert_with(|| {
+            keys.push(key);
+            keys.len() as u32 - 1
+        })
+    }
+
+    fn build(
+        self,
+        positions: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) -> ObjPrimitiveData {
+        // Vertices without an UV or a normal leave the whole primitive without them
+        let uvs = self
+            .keys
+            .iter()
+            .map(|(_, uv, _)| uv.map(|uv| uvs[uv]))
+            .collect();
+        let normals = self
+            .keys
+            .iter()
+            .map(|(_, _, normal)| normal.map(|normal| normals[normal]))
+            .collect();
+        ObjPrimitiveData {
+            material: self.material,
+            positions: self
+                .keys
+                .iter()
+                .map(|(position, _, _)| positions[*position])
+                .collect(),
+            normals,
+            uvs,
+            indices: self.indices,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    name: Option<String>,
+    primitives: Vec<PrimitiveBuilder>,
+}
+
+impl MeshBuilder {
+    fn is_empty(&self) -> bool {
+        self.primitives
+            .iter()
+            .all(|primitive| primitive.indices.is_empty())
+    }
+
+    /// The primitive the faces with this material are added to.
+    fn primitive(&mut self, material: &Option<String>) -> &mut PrimitiveBuilder {
+        let index = match self
+            .primitives
+            .iter()
+            .position(|primitive| &primitive.material == material)
+        {
+            Some(index) => index,
+            None => {
+                self.primitives
+                    .push(PrimitiveBuilder::new(material.clone()));
+                self.primitives.len() - 1
+            }
+        };
+        &mut self.primitives[index]
+    }
+
+    fn build(self, positions: &[[f32; 3]], uvs: &[[f32; 2]], normals: &[[f32; 3]]) -> ObjMeshData {
+        ObjMeshData {
+            name: self.name,
+            primitives: self
+                .primitives
+                .into_iter()
+                .filter(|primitive| !primitive.indices.is_empty())
+                .map(|primitive| primitive.build(positions, uvs, normals))
+                .collect(),
+        }
+    }
+}
+
+/// Splits the lines of a file into their statement keyword and its arguments, skipping comments
+/// and empty lines, along with their line number.
+fn statements(text: &str) -> impl Iterator<Item = (usize, &str, &str)> {
+    text.lines().enumerate().filter_map(|(index, line)| {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            return None;
+        }
+        let (keyword, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        Some((index + 1, keyword, arguments.trim()))
+    })
+}
+
+fn parse_floats<const N: usize>(
+    line: usize,
+    arguments: &str,
+    required: usize,
+    default: f32,
+) -> Result<[f32; N], ParseError> {
+    let mut values = [default; N];
+    let mut count = 0;
+    for argument in arguments.split_whitespace().take(N) {
+        values[count] = argument
+            .parse()
+            .map_err(|_| ParseError::new(line, format!("invalid number `{argument}`")))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(ParseError::new(
+            line,
+            format!("expected at least {required} numbers, found {count}"),
+        ));
+    }
+    Ok(values)
+}
+
+/// Resolves a 1-based index, or a negative index relative to the end, into a 0-based index.
+fn parse_index(line: usize, index: &str, len: usize) -> Result<usize, ParseError> {
+    let invalid = || ParseError::new(line, format!("invalid index `{index}`"));
+    let value: isize = index.parse().map_err(|_| invalid())?;
+    let resolved = match value {
+        0 => return Err(invalid()),
+        value if value > 0 => value as usize - 1,
+        value => len.checked_sub(value.unsigned_abs()).ok_or_else(invalid)?,
+    };
+    if resolved < len {
+        Ok(resolved)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Parses the text of an `.obj` file.
+///
+/// Faces are triangulated as fans, and split into a mesh per object or group, with a primitive
+/// per material. Points, lines and free-form geometry are ignored.
+pub fn parse_obj(text: &str) -> Result<ObjData, ParseError> {
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut data = ObjData::default();
+    let mut meshes = Vec::new();
+    let mut mesh = MeshBuilder::default();
+    let mut material = None;
+
+    for (line, keyword, arguments) in statements(text) {
+        match keyword {
+            "v" => positions.push(parse_floats(line, arguments, 3, 0.0)?),
+            "vt" => {
+                let [u, v] = parse_floats(line, arguments, 1, 0.0)?;
+                // The origin of the UVs of OBJ files is the bottom left of the images
+                uvs.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(parse_floats(line, arguments, 3, 0.0)?),
+            "f" => {
+                let mut face = Vec::new();
+                for vertex in arguments.split_whitespace() {
+                    let mut indices = vertex.split('/');
+                    let position = parse_index(line, indices.next().unwrap(), positions.len())?;
+                    let uv = match indices.next() {
+                        None | Some("") => None,
+                        Some(uv) => Some(parse_index(line, uv, uvs.len())?),
+                    };
+                    let normal = match indices.next() {
+                        None | Some("") => None,
+                        Some(normal) => Some(parse_index(line, normal, normals.len())?),
+                    };
+                    face.push(mesh.primitive(&material).vertex((position, uv, normal)));
+                }
+                if face.len() < 3 {
+                    return Err(ParseError::new(line, "faces need at least 3 vertices"));
+                }
+                let primitive = mesh.primitive(&material);
+                for i in 1..face.len() - 1 {
+                    primitive
+                        .indices
+                        .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            "o" | "g" => {
+                if !mesh.is_empty() {
+                    meshes.push(std::mem::take(&mut mesh));
+                }
+                mesh.name = (!arguments.is_empty()).then(|| arguments.to_string());
+            }
+            "usemtl" => material = (!arguments.is_empty()).then(|| arguments.to_string()),
+            "mtllib" => data
+                .material_libraries
+                .extend(arguments.split_whitespace().map(str::to_string)),
+            _ => {}
+        }
+    }
+    if !mesh.is_empty() {
+        meshes.push(mesh);
+    }
+
+    data.meshes = meshes
+        .into_iter()
+        .map(|mesh| mesh.build(&positions, &uvs, &normals))
+        .collect();
+    Ok(data)
+}
+
+/// A material of an `.mtl` file, with the statements this loader supports.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MtlMaterial {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Option<[f32; 3]>,
+    /// `Ke`
+    pub emissive: Option<[f32; 3]>,
+    /// `Ns`, the Phong exponent of the specular highlights
+    pub specular_exponent: Option<f32>,
+    /// `d`, or `1.0 - Tr`
+    pub dissolve: Option<f32>,
+    /// `Pr`, from the PBR extension of the format
+    pub roughness: Option<f32>,
+    /// `Pm`, from the PBR extension of the format
+    pub metallic: Option<f32>,
+    /// `map_Kd`
+    pub diffuse_texture: Option<String>,
+    /// `map_Ke`
+    pub emissive_texture: Option<String>,
+    /// `norm`, or `map_Bump` and `bump` which exporters use for normal maps too
+    pub normal_texture: Option<String>,
+}
+
+/// The file of a texture map statement, ignoring its options like `-bm 1.0`.
+fn texture_path(arguments: &str) -> Option<String> {
+    arguments.split_whitespace().last().map(str::to_string)
+}
+
+/// Parses the text of an `.mtl` file.
+pub fn parse_mtl(text: &str) -> Result<Vec<MtlMaterial>, ParseError> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+    for (line, keyword, arguments) in statements(text) {
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial {
+                name: arguments.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            return Err(ParseError::new(
+                line,
+                format!("`{keyword}` before `newmtl`"),
+            ));
+        };
+        let [value] = match keyword {
+            "Ns" | "d" | "Tr" | "Pr" | "Pm" => parse_floats(line, arguments, 1, 0.0)?,
+            _ => [0.0],
+        };
+        match keyword {
+            "Kd" => material.diffuse = Some(parse_floats(line, arguments, 3, 0.0)?),
+            "Ke" => material.emissive = Some(parse_floats(line, arguments, 3, 0.0)?),
+            "Ns" => material.specular_exponent = Some(value),
+            "d" => material.dissolve = Some(value),
+            "Tr" => material.dissolve = Some(1.0 - value),
+            "Pr" => material.roughness = Some(value),
+            "Pm" => material.metallic = Some(value),
+            "map_Kd" => material.diffuse_texture = texture_path(arguments),
+            "map_Ke" => material.emissive_texture = texture_path(arguments),
+            "norm" | "map_Bump" | "map_bump" | "bump" => {
+                material.normal_texture = texture_path(arguments);
+            }
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obj_faces_are_triangulated_and_deduplicated() {
+        let data = parse_obj(
+            "# a quad
+            mtllib quad.mtl
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 1
+            o quad
+            usemtl red
+            f 1/1 2/1 3/2 -1/2
+            usemtl blue
+            f 1/1 3/2 4/2",
+        )
+        .unwrap();
+        assert_eq!(data.material_libraries, ["quad.mtl"]);
+        assert_eq!(data.meshes.len(), 1);
+        let mesh = &data.meshes[0];
+        assert_eq!(mesh.name.as_deref(), Some("quad"));
+        assert_eq!(mesh.primitives.len(), 2);
+
+        let red = &mesh.primitives[0];
+        assert_eq!(red.material.as_deref(), Some("red"));
+        assert_eq!(red.positions.len(), 4);
+        assert_eq!(red.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(red.uvs.as_ref().unwrap()[0], [0.0, 1.0]);
+        assert!(red.normals.is_none());
+
+        let blue = &mesh.primitives[1];
+        assert_eq!(blue.positions.len(), 3);
+        assert_eq!(blue.indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn obj_invalid_indices() {
+        assert_eq!(
+            parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3").unwrap_err(),
+            ParseError::new(3, "invalid index `3`")
+        );
+        assert_eq!(
+            parse_obj("v 0 0 0\nf 1 0 1").unwrap_err(),
+            ParseError::new(2, "invalid index `0`")
+        );
+    }
+
+    #[test]
+    fn mtl_materials() {
+        let materials = parse_mtl(
+            "newmtl red
+            Kd 1 0 0
+            Tr 0.25
+            map_Bump -bm 0.5 normal.png
+            newmtl glow
+            Ke 0 1 0",
+        )
+        .unwrap();
+        assert_eq!(
+            materials,
+            [
+                MtlMaterial {
+                    name: "red".to_string(),
+                    diffuse: Some([1.0, 0.0, 0.0]),
+                    dissolve: Some(0.75),
+                    normal_texture: Some("normal.png".to_string()),
+                    ..Default::default()
+                },
+                MtlMaterial {
+                    name: "glow".to_string(),
+                    emissive: Some([0.0, 1.0, 0.0]),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(parse_mtl("Kd 1 1 1").is_err());
+    }
+}
@@ -23,6 +23,18 @@ pub enum AlphaMode {
     /// Standard alpha-blending is used to blend the fragment's color
     /// with the color behind it.
     Blend,
+    /// Similar to [`AlphaMode::Blend`], but the color is assumed to already be multiplied by
+    /// its alpha value: the color is added to the color behind it, which is first dimmed by the
+    /// alpha value.
+    ///
+    /// This lets a single material both occlude and add light, like a flame with a dark
+    /// smoky edge.
+    Premultiplied,
+    /// The color, multiplied by its alpha value, is added to the color behind it, without
+    /// dimming it.
+    ///
+    /// Useful for effects that only add light, like fire, holograms or particles.
+    Add,
 }
 
 impl Eq for AlphaMode {}
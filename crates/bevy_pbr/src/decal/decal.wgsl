@@ -0,0 +1,88 @@
+#import bevy_pbr::mesh_view_types
+
+@group(0) @binding(0)
+var<uniform> view: View;
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var depth_prepass_texture: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(1)
+var depth_prepass_texture: texture_2d<f32>;
+#endif
+
+struct DecalMaterial {
+    base_color: vec4<f32>,
+    cos_max_angle: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: DecalMaterial;
+@group(1) @binding(1)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+
+struct Decal {
+    transform: mat4x4<f32>,
+    inverse_transform: mat4x4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> decal: Decal;
+
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // The corners of the two triangles of each face, counter-clockwise seen from outside of the
+    // cube, with the bits of their index being their x, y and z coordinates
+    var corners = array<u32, 36>(
+        0u, 4u, 6u, 0u, 6u, 2u,
+        1u, 3u, 7u, 1u, 7u, 5u,
+        0u, 1u, 5u, 0u, 5u, 4u,
+        2u, 6u, 7u, 2u, 7u, 3u,
+        0u, 2u, 3u, 0u, 3u, 1u,
+        4u, 5u, 7u, 4u, 7u, 6u,
+    );
+    let corner = corners[vertex_index];
+    let position = vec3<f32>(
+        f32(corner & 1u),
+        f32((corner >> 1u) & 1u),
+        f32((corner >> 2u) & 1u),
+    ) - 0.5;
+    return view.view_proj * decal.transform * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fragment(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    let depth = textureLoad(depth_prepass_texture, vec2<i32>(frag_coord.xy), 0).r;
+
+    // The world position of the surface under the pixel, from its depth
+    let uv = (frag_coord.xy - view.viewport.xy) / view.viewport.zw;
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let world_position = view.inverse_view_proj * ndc;
+    let position = world_position.xyz / world_position.w;
+    let local_position = (decal.inverse_transform * vec4<f32>(position, 1.0)).xyz;
+
+    // The normal of the surface, facing the camera, from the positions of the neighboring pixels
+    let normal = normalize(cross(dpdy(position), dpdx(position)));
+    let facing = dot(normal, normalize(decal.transform[1].xyz));
+    let fade = smoothstep(
+        material.cos_max_angle,
+        mix(material.cos_max_angle, 1.0, 0.25),
+        facing,
+    );
+
+    // Projected along the negative local Y axis, with the top of the texture towards negative Z
+    var color = material.base_color
+        * textureSample(base_color_texture, base_color_sampler, local_position.xz + 0.5);
+    color.a = color.a * fade;
+#ifdef DECAL_MULTIPLY
+    color = vec4<f32>(mix(vec3<f32>(1.0), color.rgb, color.a), 1.0);
+#endif
+
+    // NOTE: Discarding after the texture is sampled keeps the derivatives in uniform control flow
+    // Nothing was drawn where the depth is 0.0, at the far plane with a reversed depth
+    if (depth == 0.0 || any(abs(local_position) > vec3<f32>(0.5))) {
+        discard;
+    }
+    return color;
+}
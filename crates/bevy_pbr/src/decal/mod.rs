@@ -0,0 +1,631 @@
+//! Decals projected onto the opaque meshes, like bullet holes or blood splats, without modifying
+//! the meshes themselves.
+//!
+//! Add a [`DecalBundle`] to project its [`DecalMaterial`] onto the meshes inside of the unit cube
+//! around its [`GlobalTransform`], along the negative local Y axis: the material covers the
+//! surfaces of the meshes as if it was stamped onto them from above the entity.
+//!
+//! The decals of a view are drawn into its [`Decal3d`] phase, after the opaque meshes and before
+//! the transparent ones. The position of the surfaces under each pixel is read back from the
+//! depth prepass, so only the cameras with a [`DepthPrepass`](bevy_core_pipeline::prepass::DepthPrepass)
+//! draw decals.
+
+use std::f32::consts::PI;
+
+use crate::{MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, AddAsset, Handle, HandleUntyped};
+use bevy_core_pipeline::{core_3d::Decal3d, prepass::ViewPrepassTextures};
+use bevy_ecs::{
+    prelude::*,
+    system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+};
+use bevy_math::{Mat4, Vec3, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect, TypeUuid};
+use bevy_render::{
+    color::Color,
+    extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+    primitives::Aabb,
+    render_asset::{
+        PrepareAssetError, PrepareAssetLabel, RenderAsset, RenderAssetPlugin, RenderAssets,
+    },
+    render_phase::{
+        AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
+        SetItemPipeline, TrackedRenderPass,
+    },
+    render_resource::*,
+    renderer::RenderDevice,
+    texture::{BevyDefault, FallbackImage, Image},
+    view::{
+        ComputedVisibility, ExtractedView, Msaa, ViewTarget, ViewUniform, ViewUniformOffset,
+        ViewUniforms, Visibility, VisibleEntities,
+    },
+    Extract, RenderApp, RenderStage,
+};
+use bevy_transform::components::{GlobalTransform, Transform};
+
+const DECAL_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5797931344805984055);
+
+/// Draws the decals of the entities with a [`Handle<DecalMaterial>`].
+pub struct DecalPlugin;
+
+impl Plugin for DecalPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, DECAL_SHADER_HANDLE, "decal.wgsl", Shader::from_wgsl);
+
+        app.add_asset::<DecalMaterial>()
+            .register_type::<DecalMaterial>()
+            .register_type::<DecalBlendMode>()
+            .add_plugin(UniformComponentPlugin::<DecalUniform>::default())
+            .add_plugin(
+                RenderAssetPlugin::<DecalMaterial>::with_prepare_asset_label(
+                    PrepareAssetLabel::PostAssetPrepare,
+                ),
+            );
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_render_command::<Decal3d, DrawDecal>()
+                .init_resource::<DecalPipeline>()
+                .init_resource::<SpecializedRenderPipelines<DecalPipeline>>()
+                .init_resource::<DecalBindGroup>()
+                .add_system_to_stage(RenderStage::Extract, extract_decals)
+                .add_system_to_stage(RenderStage::Queue, queue_decal_bind_groups)
+                .add_system_to_stage(RenderStage::Queue, queue_decals);
+        }
+    }
+}
+
+/// How the color of a [`DecalMaterial`] is combined with the color of the surfaces it is projected
+/// onto.
+#[derive(Debug, Default, Reflect, FromReflect, Copy, Clone, PartialEq, Eq, Hash)]
+#[reflect(Default, Debug)]
+pub enum DecalBlendMode {
+    /// The color is blended over the surfaces with its alpha value.
+    #[default]
+    Blend,
+    /// The surfaces are multiplied by the color, faded towards white by its alpha value.
+    ///
+    /// The decals aren't lit, so this suits those that darken the surfaces, like bullet holes or
+    /// blood splats, which then keep the lighting of the surfaces under them.
+    Multiply,
+}
+
+/// The material of a decal, projected onto the surfaces of the meshes without lighting.
+#[derive(AsBindGroup, Reflect, FromReflect, Debug, Clone, TypeUuid)]
+#[uuid = "7d06d518-bc93-4524-b7e4-5504cd744cb0"]
+#[uniform(0, DecalMaterialUniform)]
+#[reflect(Default, Debug)]
+pub struct DecalMaterial {
+    /// The color of the decal, multiplied by the `base_color_texture` when it has one.
+    ///
+    /// Defaults to [`Color::WHITE`].
+    pub base_color: Color,
+    /// The texture of the decal, whose top is towards the negative local Z axis of the decal.
+    #[texture(1)]
+    #[sampler(2)]
+    pub base_color_texture: Option<Handle<Image>>,
+    pub blend_mode: DecalBlendMode,
+    /// The largest angle, in radians, between the surfaces the decal is projected onto and the
+    /// plane of the decal. The decal fades out on the surfaces approaching that angle, so that
+    /// it doesn't stretch along surfaces parallel to its projection.
+    ///
+    /// Defaults to 60 degrees.
+    pub max_angle: f32,
+}
+
+impl Default for DecalMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            blend_mode: DecalBlendMode::Blend,
+            max_angle: PI / 3.0,
+        }
+    }
+}
+
+impl From<Handle<Image>> for DecalMaterial {
+    fn from(texture: Handle<Image>) -> Self {
+        DecalMaterial {
+            base_color_texture: Some(texture),
+            ..Default::default()
+        }
+    }
+}
+
+/// The GPU representation of the uniform data of a [`DecalMaterial`].
+#[derive(Clone, Default, ShaderType)]
+pub struct DecalMaterialUniform {
+    pub base_color: Vec4,
+    /// The cosine of [`DecalMaterial::max_angle`].
+    pub cos_max_angle: f32,
+}
+
+impl AsBindGroupShaderType<DecalMaterialUniform> for DecalMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> DecalMaterialUniform {
+        DecalMaterialUniform {
+            base_color: self.base_color.as_linear_rgba_f32().into(),
+            // The decal fades out over a range of angles, which needs a non-zero angle
+            cos_max_angle: self.max_angle.clamp(0.01, PI).cos(),
+        }
+    }
+}
+
+/// A [`DecalMaterial`] ready to be drawn.
+pub struct GpuDecalMaterial {
+    pub bind_group: BindGroup,
+    pub blend_mode: DecalBlendMode,
+}
+
+impl RenderAsset for DecalMaterial {
+    type ExtractedAsset = DecalMaterial;
+    type PreparedAsset = GpuDecalMaterial;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<DecalPipeline>,
+        SRes<RenderAssets<Image>>,
+        SRes<FallbackImage>,
+    );
+
+    fn extract_asset(&self) -> Self::ExtractedAsset {
+        self.clone()
+    }
+
+    fn prepare_asset(
+        material: Self::ExtractedAsset,
+        (render_device, decal_pipeline, images, fallback_image): &mut SystemParamItem<Self::Param>,
+    ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
+        match material.as_bind_group(
+            &decal_pipeline.material_layout,
+            render_device,
+            images,
+            fallback_image,
+        ) {
+            Ok(prepared) => Ok(GpuDecalMaterial {
+                bind_group: prepared.bind_group,
+                blend_mode: material.blend_mode,
+            }),
+            Err(AsBindGroupError::RetryNextUpdate) => {
+                Err(PrepareAssetError::RetryNextUpdate(material))
+            }
+        }
+    }
+}
+
+/// A decal projecting its [`DecalMaterial`] onto the meshes inside the unit cube around its
+/// [`Transform`], which can be scaled to change the size of the decal and how deep it is
+/// projected.
+#[derive(Bundle, Clone)]
+pub struct DecalBundle {
+    pub material: Handle<DecalMaterial>,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// The bounds of the unit cube the decal is projected in, for frustum culling.
+    pub aabb: Aabb,
+    /// User indication of whether the decal is visible
+    pub visibility: Visibility,
+    /// Algorithmically-computed indication of whether the decal is visible and should be drawn
+    pub computed_visibility: ComputedVisibility,
+}
+
+impl Default for DecalBundle {
+    fn default() -> Self {
+        Self {
+            material: Default::default(),
+            transform: Default::default(),
+            global_transform: Default::default(),
+            aabb: Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5)),
+            visibility: Default::default(),
+            computed_visibility: Default::default(),
+        }
+    }
+}
+
+/// The transforms of a visible decal, extracted to the render world.
+#[derive(Component, Clone, ShaderType)]
+pub struct DecalUniform {
+    pub transform: Mat4,
+    pub inverse_transform: Mat4,
+}
+
+pub fn extract_decals(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    decals: Extract<
+        Query<(
+            Entity,
+            &Handle<DecalMaterial>,
+            &GlobalTransform,
+            &ComputedVisibility,
+        )>,
+    >,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, material, transform, visibility) in &decals {
+        if !visibility.is_visible() {
+            continue;
+        }
+        let transform = transform.compute_matrix();
+        values.push((
+            entity,
+            (
+                material.clone_weak(),
+                DecalUniform {
+                    transform,
+                    inverse_transform: transform.inverse(),
+                },
+            ),
+        ));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+/// The layouts of the bind groups of the decal pipelines.
+#[derive(Resource)]
+pub struct DecalPipeline {
+    /// The view uniform and the depth prepass of the view.
+    pub view_layout: BindGroupLayout,
+    /// The view uniform and the multisampled depth prepass of the view.
+    pub view_multisampled_layout: BindGroupLayout,
+    pub material_layout: BindGroupLayout,
+    pub decal_layout: BindGroupLayout,
+}
+
+impl DecalPipeline {
+    fn view_layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled {
+            &self.view_multisampled_layout
+        } else {
+            &self.view_layout
+        }
+    }
+}
+
+impl FromWorld for DecalPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let view_layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    // View
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(ViewUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                    // Prepass depth
+                    // NOTE: Bound as a float texture, like for the soft particles
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+        let decal_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("decal_layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(DecalUniform::min_size()),
+                },
+                count: None,
+            }],
+        });
+
+        DecalPipeline {
+            view_layout: view_layout("decal_view_layout", false),
+            view_multisampled_layout: view_layout("decal_view_multisampled_layout", true),
+            material_layout: DecalMaterial::bind_group_layout(render_device),
+            decal_layout,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct DecalPipelineKey {
+    pub hdr: bool,
+    pub msaa_samples: u32,
+    pub blend_mode: DecalBlendMode,
+}
+
+impl SpecializedRenderPipeline for DecalPipeline {
+    type Key = DecalPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // The shader imports the `View` from `bevy_pbr::mesh_view_types`
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt(
+                "MAX_DIRECTIONAL_LIGHTS".to_string(),
+                MAX_DIRECTIONAL_LIGHTS as u32,
+            ),
+            ShaderDefVal::UInt(
+                "MAX_CASCADES_PER_LIGHT".to_string(),
+                MAX_CASCADES_PER_LIGHT as u32,
+            ),
+        ];
+        let multisampled = key.msaa_samples > 1;
+        if multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+        let blend = match key.blend_mode {
+            DecalBlendMode::Blend => BlendState::ALPHA_BLENDING,
+            DecalBlendMode::Multiply => {
+                shader_defs.push("DECAL_MULTIPLY".into());
+                BlendState {
+                    color: BlendComponent {
+                        src_factor: BlendFactor::Dst,
+                        dst_factor: BlendFactor::Zero,
+                        operation: BlendOperation::Add,
+                    },
+                    alpha: BlendComponent {
+                        src_factor: BlendFactor::Zero,
+                        dst_factor: BlendFactor::One,
+                        operation: BlendOperation::Add,
+                    },
+                }
+            }
+        };
+
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("decal_pipeline".into()),
+            layout: Some(vec![
+                self.view_layout(multisampled).clone(),
+                self.material_layout.clone(),
+                self.decal_layout.clone(),
+            ]),
+            push_constant_ranges: Vec::new(),
+            vertex: VertexState {
+                shader: DECAL_SHADER_HANDLE.typed(),
+                shader_defs: shader_defs.clone(),
+                entry_point: "vertex".into(),
+                buffers: Vec::new(),
+            },
+            fragment: Some(FragmentState {
+                shader: DECAL_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            // The back faces of the cube are drawn, so that the decal is still drawn when the
+            // camera is inside of it
+            primitive: PrimitiveState {
+                cull_mode: Some(Face::Front),
+                ..Default::default()
+            },
+            // NOTE: 0.0 is the far plane due to bevy's use of reverse-z projections.
+            // The back faces only pass the depth test where the surfaces are in front of them,
+            // which skips the pixels where the surfaces are behind the decal
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.msaa_samples,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The bind group of the [`DecalUniform`]s.
+#[derive(Resource, Default)]
+pub struct DecalBindGroup(pub Option<BindGroup>);
+
+/// The bind group of the view uniform and of the depth prepass of a view, for the decals.
+#[derive(Component)]
+pub struct DecalViewBindGroup(pub BindGroup);
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_decal_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    decal_pipeline: Res<DecalPipeline>,
+    decal_uniforms: Res<ComponentUniforms<DecalUniform>>,
+    view_uniforms: Res<ViewUniforms>,
+    msaa: Res<Msaa>,
+    mut decal_bind_group: ResMut<DecalBindGroup>,
+    views: Query<(Entity, &ViewPrepassTextures), With<RenderPhase<Decal3d>>>,
+) {
+    decal_bind_group.0 = decal_uniforms.binding().map(|binding| {
+        render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("decal_bind_group"),
+            layout: &decal_pipeline.decal_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: binding,
+            }],
+        })
+    });
+
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    for (entity, prepass_textures) in &views {
+        let Some(depth) = &prepass_textures.depth else {
+            continue;
+        };
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("decal_view_bind_group"),
+            layout: decal_pipeline.view_layout(msaa.samples > 1),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: view_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&depth.default_view),
+                },
+            ],
+        });
+        commands
+            .entity(entity)
+            .insert(DecalViewBindGroup(bind_group));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_decals(
+    decal_draw_functions: Res<DrawFunctions<Decal3d>>,
+    decal_pipeline: Res<DecalPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<DecalPipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    materials: Res<RenderAssets<DecalMaterial>>,
+    decals: Query<(&Handle<DecalMaterial>, &DecalUniform)>,
+    mut views: Query<(&ExtractedView, &VisibleEntities, &mut RenderPhase<Decal3d>)>,
+) {
+    let draw_decal = decal_draw_functions.read().id::<DrawDecal>();
+
+    for (view, visible_entities, mut decal_phase) in &mut views {
+        let rangefinder = view.rangefinder3d();
+
+        for visible_entity in &visible_entities.entities {
+            let Ok((material_handle, decal_uniform)) = decals.get(*visible_entity) else {
+                continue;
+            };
+            let Some(material) = materials.get(material_handle) else {
+                continue;
+            };
+            let pipeline = pipelines.specialize(
+                &mut pipeline_cache,
+                &decal_pipeline,
+                DecalPipelineKey {
+                    hdr: view.hdr,
+                    msaa_samples: msaa.samples,
+                    blend_mode: material.blend_mode,
+                },
+            );
+            decal_phase.add(Decal3d {
+                distance: rangefinder.distance(&decal_uniform.transform),
+                pipeline,
+                entity: *visible_entity,
+                draw_function: draw_decal,
+            });
+        }
+    }
+}
+
+/// Draws a decal, as the back faces of its unit cube.
+pub type DrawDecal = (
+    SetItemPipeline,
+    SetDecalViewBindGroup<0>,
+    SetDecalMaterialBindGroup<1>,
+    SetDecalBindGroup<2>,
+    DrawDecalCube,
+);
+
+pub struct SetDecalViewBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetDecalViewBindGroup<I> {
+    type Param = SQuery<(Read<DecalViewBindGroup>, Read<ViewUniformOffset>)>;
+    #[inline]
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        view_query: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Ok((bind_group, view_uniform)) = view_query.get_inner(view) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, &bind_group.0, &[view_uniform.offset]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetDecalMaterialBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetDecalMaterialBindGroup<I> {
+    type Param = (
+        SRes<RenderAssets<DecalMaterial>>,
+        SQuery<Read<Handle<DecalMaterial>>>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (materials, query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Ok(material_handle) = query.get(item) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(material) = materials.into_inner().get(material_handle) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, &material.bind_group, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetDecalBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetDecalBindGroup<I> {
+    type Param = (
+        SRes<DecalBindGroup>,
+        SQuery<Read<DynamicUniformIndex<DecalUniform>>>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        (bind_group, query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let (Some(bind_group), Ok(index)) = (&bind_group.into_inner().0, query.get(item)) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, bind_group, &[index.index()]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawDecalCube;
+impl EntityRenderCommand for DrawDecalCube {
+    type Param = ();
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        _item: Entity,
+        _param: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        // The vertices of the 12 triangles of the cube are generated by the vertex shader
+        pass.draw(0..36, 0..1);
+        RenderCommandResult::Success
+    }
+}
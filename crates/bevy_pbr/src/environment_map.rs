@@ -0,0 +1,47 @@
+use bevy_asset::Handle;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::Reflect;
+use bevy_render::{
+    extract_component::ExtractComponent, prelude::Camera, render_asset::RenderAssets,
+    texture::Image,
+};
+
+/// Add this component to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) to light the
+/// [`StandardMaterial`](crate::StandardMaterial)s it renders with an environment, on top of the
+/// [`AmbientLight`](crate::AmbientLight).
+///
+/// The environment has to be prefiltered offline into two cube maps, like the ones generated by
+/// the [glTF IBL Sampler](https://github.com/KhronosGroup/glTF-IBL-Sampler) and saved as `.ktx2`
+/// files:
+/// * [`diffuse_map`](EnvironmentMapLight::diffuse_map) holds the irradiance of the environment,
+///   the light received by a rough surface facing each direction.
+/// * [`specular_map`](EnvironmentMapLight::specular_map) holds the light reflected towards each
+///   direction, with one mip level for each roughness: its first level reflects the environment
+///   like a mirror, and its last level for a perceptual roughness of `1.0`.
+///
+/// Both images need a [`TextureViewDimension::Cube`](bevy_render::render_resource::TextureViewDimension::Cube)
+/// view dimension and a format that can be filtered, like `Rgba16Float` or `Rg11b10Float`.
+/// The environment doesn't light anything until both of them are loaded.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component)]
+pub struct EnvironmentMapLight {
+    pub diffuse_map: Handle<Image>,
+    pub specular_map: Handle<Image>,
+}
+
+impl EnvironmentMapLight {
+    /// Whether both maps of the environment have been uploaded to the GPU.
+    pub fn is_loaded(&self, images: &RenderAssets<Image>) -> bool {
+        images.get(&self.diffuse_map).is_some() && images.get(&self.specular_map).is_some()
+    }
+}
+
+impl ExtractComponent for EnvironmentMapLight {
+    type Query = &'static Self;
+    type Filter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self::Out> {
+        Some(item.clone())
+    }
+}
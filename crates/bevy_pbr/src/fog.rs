@@ -0,0 +1,241 @@
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_core_pipeline::core_3d::Transparent3d;
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::{Vec2, Vec3, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect, TypeUuid};
+use bevy_render::{
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Color},
+    render_phase::RenderPhase,
+    render_resource::{DynamicUniformBuffer, Shader, ShaderType},
+    renderer::{RenderDevice, StagingBelt},
+    Extract, RenderApp, RenderStage,
+};
+
+pub const FOG_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 4913569193382610166);
+
+/// Adds distance and height fog to the [`StandardMaterial`](crate::StandardMaterial)s, configured
+/// with [`FogSettings`].
+pub struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, FOG_SHADER_HANDLE, "render/fog.wgsl", Shader::from_wgsl);
+
+        app.register_type::<FogSettings>()
+            .register_type::<FogFalloff>()
+            .register_type::<HeightFog>()
+            .add_plugin(ExtractComponentPlugin::<FogSettings>::default());
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<FogMeta>()
+                .add_system_to_stage(RenderStage::Extract, extract_fog_settings)
+                .add_system_to_stage(RenderStage::Prepare, prepare_fog);
+        }
+    }
+}
+
+/// Fades the meshes a 3D camera renders into a color as they get further away from it, like
+/// the atmosphere does with the horizon of outdoor scenes.
+///
+/// Insert it as a resource to set the fog of every camera, or add it to a
+/// [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) to override that resource for this camera.
+/// There is no fog when neither is present.
+///
+/// The fog is applied to the [`StandardMaterial`](crate::StandardMaterial)s that have
+/// [`fog_enabled`](crate::StandardMaterial::fog_enabled) set, in their fragment shader, and
+/// doesn't cover the parts of the screen where no mesh is drawn, which usually need a clear color
+/// or skybox matching the fog color.
+#[derive(Resource, Component, Reflect, Clone, Debug)]
+#[reflect(Resource, Component, Default)]
+pub struct FogSettings {
+    /// The color of the fog (default: a light grey).
+    ///
+    /// Its alpha is the opacity of the fog at the distance where it is the densest, so meshes
+    /// can be kept visible through the thickest fog with an alpha lower than `1.0`.
+    pub color: Color,
+    /// How the fog gets denser with the distance to the camera.
+    pub falloff: FogFalloff,
+    /// An extra fog lying close to the ground, which gets thinner with altitude (default: `None`).
+    pub height_fog: Option<HeightFog>,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            color: Color::rgba(0.75, 0.8, 0.85, 1.0),
+            falloff: FogFalloff::default(),
+            height_fog: None,
+        }
+    }
+}
+
+/// How the [`FogSettings`] get denser with the distance `d` from the camera to a mesh, in world
+/// units.
+#[derive(Clone, Copy, Debug, Reflect, FromReflect)]
+pub enum FogFalloff {
+    /// The fog opacity rises linearly from `0.0` at the `start` distance to its maximum at the
+    /// `end` distance.
+    ///
+    /// Its sharp boundaries make it easy to control, for example to hide the meshes past the far
+    /// end of a level.
+    Linear { start: f32, end: f32 },
+    /// The fog is uniformly dense, and lets `exp(-density * d)` of the light of the meshes
+    /// through, like a real atmosphere does.
+    Exponential { density: f32 },
+    /// The fog lets `exp(-(density * d)²)` of the light of the meshes through, which keeps the
+    /// meshes close to the camera clearer than [`FogFalloff::Exponential`], but fades them out
+    /// faster past a distance of `1.0 / density`.
+    ExponentialSquared { density: f32 },
+}
+
+impl Default for FogFalloff {
+    fn default() -> Self {
+        FogFalloff::Linear {
+            start: 0.0,
+            end: 100.0,
+        }
+    }
+}
+
+/// A fog which is the densest at ground level, and gets exponentially thinner above it, like mist
+/// filling a valley. See [`FogSettings::height_fog`].
+///
+/// It is integrated along the line of sight from the camera to each mesh, so a camera below the
+/// fog sees the sky through it, and a camera above it sees the ground through it.
+#[derive(Clone, Copy, Debug, Reflect, FromReflect)]
+#[reflect(Default)]
+pub struct HeightFog {
+    /// The density of the fog at the `height`, as for [`FogFalloff::Exponential`] (default: `0.1`).
+    pub density: f32,
+    /// How fast the fog gets thinner with altitude, per world unit (default: `0.5`).
+    ///
+    /// The density is divided by `e` every `1.0 / falloff` world units above the `height`, and is
+    /// multiplied by it every `1.0 / falloff` world units below it.
+    pub falloff: f32,
+    /// The altitude of the ground level of the fog, in world units (default: `0.0`).
+    pub height: f32,
+}
+
+impl Default for HeightFog {
+    fn default() -> Self {
+        Self {
+            density: 0.1,
+            falloff: 0.5,
+            height: 0.0,
+        }
+    }
+}
+
+impl ExtractComponent for FogSettings {
+    type Query = &'static Self;
+    type Filter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self::Out> {
+        Some(item.clone())
+    }
+}
+
+/// Extracts the [`FogSettings`] resource, which unlike most resources is optional.
+pub fn extract_fog_settings(
+    mut commands: Commands,
+    fog_settings: Extract<Option<Res<FogSettings>>>,
+) {
+    match &*fog_settings {
+        Some(fog_settings) => commands.insert_resource(FogSettings::clone(fog_settings)),
+        None => commands.remove_resource::<FogSettings>(),
+    }
+}
+
+// NOTE: These must match the constants of bevy_pbr/src/render/mesh_view_types.wgsl
+const FOG_MODE_OFF: u32 = 0;
+const FOG_MODE_LINEAR: u32 = 1;
+const FOG_MODE_EXPONENTIAL: u32 = 2;
+const FOG_MODE_EXPONENTIAL_SQUARED: u32 = 3;
+
+/// The [`FogSettings`] of a view, in the form the shaders use them.
+#[derive(ShaderType, Clone, Default)]
+pub struct GpuFog {
+    /// The fog color in linear RGB, and its maximum opacity.
+    color: Vec4,
+    /// The `start` and `end` of a linear falloff, or the density of an exponential one in `x`.
+    falloff: Vec2,
+    mode: u32,
+    /// The density, falloff and height of the height fog, which is disabled with a density of `0.0`.
+    height_fog: Vec3,
+}
+
+impl From<&FogSettings> for GpuFog {
+    fn from(fog_settings: &FogSettings) -> Self {
+        let (mode, falloff) = match fog_settings.falloff {
+            FogFalloff::Linear { start, end } => (FOG_MODE_LINEAR, Vec2::new(start, end)),
+            FogFalloff::Exponential { density } => (FOG_MODE_EXPONENTIAL, Vec2::new(density, 0.0)),
+            FogFalloff::ExponentialSquared { density } => {
+                (FOG_MODE_EXPONENTIAL_SQUARED, Vec2::new(density, 0.0))
+            }
+        };
+        let height_fog = fog_settings
+            .height_fog
+            .map(|height_fog| Vec3::new(height_fog.density, height_fog.falloff, height_fog.height))
+            .unwrap_or(Vec3::ZERO);
+        Self {
+            color: fog_settings.color.as_linear_rgba_f32().into(),
+            falloff,
+            mode,
+            height_fog,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct FogMeta {
+    pub gpu_fogs: DynamicUniformBuffer<GpuFog>,
+}
+
+impl FromWorld for FogMeta {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            gpu_fogs: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+        }
+    }
+}
+
+/// The offset of the [`GpuFog`] of a view in the [`FogMeta`] buffer.
+#[derive(Component)]
+pub struct ViewFogUniformOffset {
+    pub offset: u32,
+}
+
+/// Writes the fog of each 3D view, from its own [`FogSettings`] or else the [`FogSettings`]
+/// resource.
+pub fn prepare_fog(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    staging_belt: Res<StagingBelt>,
+    mut fog_meta: ResMut<FogMeta>,
+    global_fog_settings: Option<Res<FogSettings>>,
+    views: Query<(Entity, Option<&FogSettings>), With<RenderPhase<Transparent3d>>>,
+) {
+    fog_meta.gpu_fogs.clear();
+
+    for (entity, fog_settings) in &views {
+        let gpu_fog = match fog_settings.or(global_fog_settings.as_deref()) {
+            Some(fog_settings) => GpuFog::from(fog_settings),
+            None => GpuFog {
+                mode: FOG_MODE_OFF,
+                ..Default::default()
+            },
+        };
+        commands.entity(entity).insert(ViewFogUniformOffset {
+            offset: fog_meta.gpu_fogs.push(gpu_fog),
+        });
+    }
+
+    fog_meta
+        .gpu_fogs
+        .write_buffer_staged(&render_device, &staging_belt);
+}
@@ -2,17 +2,38 @@ pub mod wireframe;
 
 mod alpha;
 mod bundle;
+mod decal;
+mod environment_map;
+mod fog;
 mod light;
 mod material;
+mod particles;
 mod pbr_material;
+mod picking;
+mod placeholder;
+mod prepass;
+pub mod procedural_sky;
+mod reflection_probe;
 mod render;
+mod ssao;
+mod terrain;
 
 pub use alpha::*;
 pub use bundle::*;
+pub use decal::*;
+pub use environment_map::*;
+pub use fog::*;
 pub use light::*;
 pub use material::*;
+pub use particles::*;
 pub use pbr_material::*;
+pub use picking::*;
+pub use placeholder::*;
+pub use prepass::*;
+pub use reflection_probe::*;
 pub use render::*;
+pub use ssao::*;
+pub use terrain::*;
 
 use bevy_window::ModifiesWindows;
 
@@ -24,15 +45,45 @@ pub mod prelude {
             DirectionalLightBundle, MaterialMeshBundle, PbrBundle, PointLightBundle,
             SpotLightBundle,
         },
+        decal::{DecalBlendMode, DecalBundle, DecalMaterial},
+        environment_map::EnvironmentMapLight,
+        fog::{FogFalloff, FogSettings},
         light::{AmbientLight, DirectionalLight, PointLight, SpotLight},
         material::{Material, MaterialPlugin},
+        particles::{ParticleEmitter, ParticleEmitterBundle},
         pbr_material::StandardMaterial,
+        procedural_sky::ProceduralSky,
+        reflection_probe::{
+            PlanarReflection, ReflectionProbe, ReflectionProbeBundle, ReflectionProbeRefresh,
+        },
+        ssao::{ScreenSpaceAmbientOcclusionBundle, ScreenSpaceAmbientOcclusionSettings},
+        terrain::{Terrain, TerrainBundle, TerrainMaterial},
     };
 }
 
 pub mod draw_3d_graph {
     pub mod node {
-        /// Label for the shadow pass node.
+        /// Label for the node running the [`shadow_graph`](crate::shadow_graph) for each light
+        /// view of the camera.
+        pub const SHADOW_PASS: &str = "shadow_pass";
+        /// Label for the screen space ambient occlusion node.
+        pub const SCREEN_SPACE_AMBIENT_OCCLUSION: &str = "screen_space_ambient_occlusion";
+        /// Label for the node baking the environment map of the procedural sky.
+        pub const PROCEDURAL_SKY_BAKE: &str = "procedural_sky_bake";
+        /// Label for the node drawing the [`PickingPass`](crate::PickingPass) of a view.
+        pub const PICKING_PASS: &str = "picking_pass";
+    }
+}
+
+/// The sub graph of the [`core_3d`](bevy_core_pipeline::core_3d::graph) graph drawing the shadow
+/// map of a light view, run for each of the light views of the camera.
+pub mod shadow_graph {
+    pub const NAME: &str = "shadow";
+    pub mod input {
+        pub const VIEW_LIGHT_ENTITY: &str = "view_light_entity";
+    }
+    pub mod node {
+        /// Label for the [`ShadowPassNode`](crate::ShadowPassNode).
         pub const SHADOW_PASS: &str = "shadow_pass";
     }
 }
@@ -43,11 +94,13 @@ use bevy_ecs::prelude::*;
 use bevy_reflect::TypeUuid;
 use bevy_render::{
     camera::CameraUpdateSystem,
+    extract_component::ExtractComponentPlugin,
     extract_resource::ExtractResourcePlugin,
     prelude::Color,
-    render_graph::RenderGraph,
-    render_phase::{sort_phase_system, AddRenderCommand, DrawFunctions},
+    render_graph::{RenderGraph, RunGraphOnSubViewsNode, SlotInfo, SlotType},
+    render_phase::{AddRenderCommand, AddRenderPhase},
     render_resource::{Shader, SpecializedMeshPipelines},
+    texture::TextureStreamingSystem,
     view::VisibilitySystems,
     RenderApp, RenderStage,
 };
@@ -71,10 +124,19 @@ pub const PBR_FUNCTIONS_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 16550102964439850292);
 pub const SHADOW_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1836745567947005696);
+pub const ENVIRONMENT_MAP_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6680651021341399386);
+pub const PREPASS_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 921124473254008983);
 
 /// Sets up the entire PBR infrastructure of bevy.
 #[derive(Default)]
-pub struct PbrPlugin;
+pub struct PbrPlugin {
+    /// Whether the meshes using a [`StandardMaterial`] are drawn with a grey placeholder while the
+    /// pipeline of their material is being created, see
+    /// [`MaterialPlugin::placeholder_enabled`]. Defaults to `false`.
+    pub placeholder_enabled: bool,
+}
 
 impl Plugin for PbrPlugin {
     fn build(&self, app: &mut App) {
@@ -122,6 +184,18 @@ impl Plugin for PbrPlugin {
             "render/depth.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            ENVIRONMENT_MAP_SHADER_HANDLE,
+            "render/environment_map.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            PREPASS_SHADER_HANDLE,
+            "render/prepass.wgsl",
+            Shader::from_wgsl
+        );
 
         app.register_type::<CubemapVisibleEntities>()
             .register_type::<DirectionalLight>()
@@ -130,17 +204,38 @@ impl Plugin for PbrPlugin {
             .register_asset_reflect::<StandardMaterial>()
             .register_type::<AmbientLight>()
             .register_type::<DirectionalLightShadowMap>()
+            .register_type::<CascadeShadowConfig>()
+            .register_type::<Cascades>()
+            .register_type::<CascadesVisibleEntities>()
             .register_type::<ClusterConfig>()
             .register_type::<ClusterZConfig>()
             .register_type::<ClusterFarZMode>()
             .register_type::<PointLightShadowMap>()
+            .register_type::<ShadowFilteringMethod>()
+            .register_type::<CascadeDebugVisualization>()
+            .register_type::<EnvironmentMapLight>()
             .add_plugin(MeshRenderPlugin)
-            .add_plugin(MaterialPlugin::<StandardMaterial>::default())
+            .add_plugin(PickingPlugin)
+            .add_plugin(ParticlePlugin)
+            .add_plugin(DecalPlugin)
+            .add_plugin(TerrainPlugin)
+            .add_plugin(ReflectionProbePlugin)
+            .add_plugin(PlaceholderPlugin)
+            .add_plugin(MaterialPlugin::<StandardMaterial> {
+                placeholder_enabled: self.placeholder_enabled,
+                ..Default::default()
+            })
+            .add_plugin(ScreenSpaceAmbientOcclusionPlugin)
+            .add_plugin(FogPlugin)
+            .add_plugin(procedural_sky::ProceduralSkyPlugin)
             .init_resource::<AmbientLight>()
             .init_resource::<GlobalVisiblePointLights>()
             .init_resource::<DirectionalLightShadowMap>()
             .init_resource::<PointLightShadowMap>()
             .add_plugin(ExtractResourcePlugin::<AmbientLight>::default())
+            .add_plugin(ExtractComponentPlugin::<ShadowFilteringMethod>::default())
+            .add_plugin(ExtractComponentPlugin::<CascadeDebugVisualization>::default())
+            .add_plugin(ExtractComponentPlugin::<EnvironmentMapLight>::default())
             .add_system_to_stage(
                 CoreStage::PostUpdate,
                 // NOTE: Clusters need to have been added before update_clusters is run so
@@ -158,10 +253,26 @@ impl Plugin for PbrPlugin {
                     .after(CameraUpdateSystem)
                     .after(ModifiesWindows),
             )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                request_standard_material_textures
+                    .after(VisibilitySystems::CheckVisibility)
+                    .after(VisibilitySystems::UpdateWorldBounds)
+                    .after(CameraUpdateSystem)
+                    .before(TextureStreamingSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_directional_light_cascades
+                    .label(SimulationLightSystems::UpdateDirectionalLightCascades)
+                    .after(TransformSystem::TransformPropagate)
+                    .after(CameraUpdateSystem),
+            )
             .add_system_to_stage(
                 CoreStage::PostUpdate,
                 update_directional_light_frusta
                     .label(SimulationLightSystems::UpdateLightFrusta)
+                    .after(SimulationLightSystems::UpdateDirectionalLightCascades)
                     // This must run after CheckVisibility because it relies on ComputedVisibility::is_visible()
                     .after(VisibilitySystems::CheckVisibility)
                     .after(TransformSystem::TransformPropagate)
@@ -242,20 +353,42 @@ impl Plugin for PbrPlugin {
                 render::queue_shadows.label(RenderLightSystems::QueueShadows),
             )
             .add_system_to_stage(RenderStage::Queue, render::queue_shadow_view_bind_group)
-            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<Shadow>)
+            .add_system_to_stage(
+                RenderStage::PhaseSort,
+                render::batch_shadows.after(render::prepare_mesh_instances),
+            )
             .init_resource::<ShadowPipeline>()
-            .init_resource::<DrawFunctions<Shadow>>()
+            .add_render_phase::<Shadow>()
             .init_resource::<LightMeta>()
             .init_resource::<GlobalLightMeta>()
             .init_resource::<SpecializedMeshPipelines<ShadowPipeline>>();
 
         let shadow_pass_node = ShadowPassNode::new(&mut render_app.world);
         render_app.add_render_command::<Shadow, DrawShadowMesh>();
+        let mut draw_shadow_graph = RenderGraph::default();
+        draw_shadow_graph.add_node(shadow_graph::node::SHADOW_PASS, shadow_pass_node);
+        let input_node_id = draw_shadow_graph.set_input(vec![SlotInfo::new(
+            shadow_graph::input::VIEW_LIGHT_ENTITY,
+            SlotType::Entity,
+        )]);
+        draw_shadow_graph.add_slot_edge(
+            input_node_id,
+            shadow_graph::input::VIEW_LIGHT_ENTITY,
+            shadow_graph::node::SHADOW_PASS,
+            ShadowPassNode::IN_VIEW_LIGHT,
+        );
+
         let mut graph = render_app.world.resource_mut::<RenderGraph>();
         let draw_3d_graph = graph
             .get_sub_graph_mut(bevy_core_pipeline::core_3d::graph::NAME)
             .unwrap();
-        draw_3d_graph.add_node(draw_3d_graph::node::SHADOW_PASS, shadow_pass_node);
+        draw_3d_graph.add_sub_graph(shadow_graph::NAME, draw_shadow_graph);
+        draw_3d_graph.add_node(
+            draw_3d_graph::node::SHADOW_PASS,
+            RunGraphOnSubViewsNode::<ViewLightEntities>::new(shadow_graph::NAME, |view_lights| {
+                &view_lights.lights
+            }),
+        );
         draw_3d_graph.add_node_edge(
             draw_3d_graph::node::SHADOW_PASS,
             bevy_core_pipeline::core_3d::graph::node::MAIN_PASS,
@@ -264,7 +397,7 @@ impl Plugin for PbrPlugin {
             draw_3d_graph.input_node().id,
             bevy_core_pipeline::core_3d::graph::input::VIEW_ENTITY,
             draw_3d_graph::node::SHADOW_PASS,
-            ShadowPassNode::IN_VIEW,
+            RunGraphOnSubViewsNode::<ViewLightEntities>::IN_VIEW,
         );
     }
 }
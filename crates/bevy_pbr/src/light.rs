@@ -1,24 +1,26 @@
 use std::collections::HashSet;
 
-use bevy_ecs::prelude::*;
+use bevy_ecs::{prelude::*, query::QueryItem};
 use bevy_math::{Mat4, UVec2, UVec3, Vec2, Vec3, Vec3A, Vec3Swizzles, Vec4, Vec4Swizzles};
 use bevy_reflect::prelude::*;
 use bevy_render::{
-    camera::{Camera, CameraProjection, OrthographicProjection},
+    camera::{Camera, CameraProjection, Projection},
     color::Color,
+    extract_component::ExtractComponent,
     extract_resource::ExtractResource,
-    primitives::{Aabb, CubemapFrusta, Frustum, Plane, Sphere},
+    primitives::{Aabb, CascadesFrusta, CubemapFrusta, Frustum, Plane, Sphere},
     render_resource::BufferBindingType,
     renderer::RenderDevice,
     view::{ComputedVisibility, RenderLayers, VisibleEntities},
 };
 use bevy_transform::{components::GlobalTransform, prelude::Transform};
-use bevy_utils::tracing::warn;
+use bevy_utils::{tracing::warn, HashMap};
 
 use crate::{
-    calculate_cluster_factors, spot_light_projection_matrix, spot_light_view_matrix, CubeMapFace,
-    CubemapVisibleEntities, ViewClusterBindings, CLUSTERED_FORWARD_STORAGE_BUFFER_COUNT,
-    CUBE_MAP_FACES, MAX_UNIFORM_BUFFER_POINT_LIGHTS, POINT_LIGHT_NEAR_Z,
+    calculate_cluster_factors, spot_light_projection_matrix, spot_light_view_matrix,
+    CascadesVisibleEntities, CubeMapFace, CubemapVisibleEntities, ViewClusterBindings,
+    CLUSTERED_FORWARD_STORAGE_BUFFER_COUNT, CUBE_MAP_FACES, MAX_CASCADES_PER_LIGHT,
+    MAX_UNIFORM_BUFFER_POINT_LIGHTS, POINT_LIGHT_NEAR_Z,
 };
 
 /// A light that emits light in all directions from a central point.
@@ -111,7 +113,7 @@ pub struct SpotLight {
     /// Angle defining the distance from the spot light direction to the inner limit
     /// of the light's cone of effect.
     /// Light is attenuated from `inner_angle` to `outer_angle` to give a smooth falloff.
-    /// `inner_angle` should be <= `outer_angle`
+    /// `inner_angle` should be <= `outer_angle`, larger values are clamped to `outer_angle`.
     pub inner_angle: f32,
 }
 
@@ -172,24 +174,16 @@ impl Default for SpotLight {
 ///
 /// To enable shadows, set the `shadows_enabled` property to `true`.
 ///
-/// While directional lights contribute to the illumination of meshes regardless
-/// of their (or the meshes') positions, currently only a limited region of the scene
-/// (the _shadow volume_) can cast and receive shadows for any given directional light.
+/// Shadows are produced via [cascaded shadow maps](https://developer.download.nvidia.com/SDK/10.5/opengl/src/cascaded_shadow_maps/doc/cascaded_shadow_maps.pdf).
 ///
-/// The shadow volume is a _rectangular cuboid_, with left/right/bottom/top/near/far
-/// planes controllable via the `shadow_projection` field. It is affected by the
-/// directional light entity's [`GlobalTransform`], and as such can be freely repositioned in the
-/// scene, (or even scaled!) without affecting illumination in any other way, by simply
-/// moving (or scaling) the entity around. The shadow volume is always oriented towards the
-/// light entity's forward direction.
+/// Only the part of the scene in front of each camera, up to a configurable maximum distance,
+/// casts and receives shadows. This volume is split into several _cascades_ along the camera's
+/// view direction, each rendered into its own shadow map, so that shadows close to the camera
+/// get more resolution than those far away. The number of cascades and their distances are
+/// controlled by the [`CascadeShadowConfig`] component on the light entity.
 ///
-/// For smaller scenes, a static directional light with a preset volume is typically
-/// sufficient. For larger scenes with movable cameras, you might want to introduce
-/// a system that dynamically repositions and scales the light entity (and therefore
-/// its shadow volume) based on the scene subject's position (e.g. a player character)
-/// and its relative distance to the camera.
+/// Only the rotation of the directional light entity's [`GlobalTransform`] affects its shadows.
 ///
-/// Shadows are produced via [shadow mapping](https://en.wikipedia.org/wiki/Shadow_mapping).
 /// To control the resolution of the shadow maps, use the [`DirectionalLightShadowMap`] resource:
 ///
 /// ```
@@ -201,8 +195,8 @@ impl Default for SpotLight {
 ///
 /// **Note:** Very large shadow map resolutions (> 4K) can have non-negligible performance and
 /// memory impact, and not work properly under mobile or lower-end hardware. To improve the visual
-/// fidelity of shadow maps, it's typically advisable to first reduce the `shadow_projection`
-/// left/right/top/bottom to a scene-appropriate size, before ramping up the shadow map
+/// fidelity of shadow maps, it's typically advisable to first reduce the maximum distance of the
+/// [`CascadeShadowConfig`] to a scene-appropriate size, before ramping up the shadow map
 /// resolution.
 #[derive(Component, Debug, Clone, Reflect)]
 #[reflect(Component, Default)]
@@ -211,30 +205,18 @@ pub struct DirectionalLight {
     /// Illuminance in lux
     pub illuminance: f32,
     pub shadows_enabled: bool,
-    /// A projection that controls the volume in which shadow maps are rendered
-    pub shadow_projection: OrthographicProjection,
     pub shadow_depth_bias: f32,
     /// A bias applied along the direction of the fragment's surface normal. It is scaled to the
-    /// shadow map's texel size so that it is automatically adjusted to the orthographic projection.
+    /// shadow map's texel size so that it is automatically adjusted to the size of each cascade.
     pub shadow_normal_bias: f32,
 }
 
 impl Default for DirectionalLight {
     fn default() -> Self {
-        let size = 100.0;
         DirectionalLight {
             color: Color::rgb(1.0, 1.0, 1.0),
             illuminance: 100000.0,
             shadows_enabled: false,
-            shadow_projection: OrthographicProjection {
-                left: -size,
-                right: size,
-                bottom: -size,
-                top: size,
-                near: -size,
-                far: size,
-                ..Default::default()
-            },
             shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
             shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
         }
@@ -262,6 +244,364 @@ impl Default for DirectionalLightShadowMap {
     }
 }
 
+/// Controls how cascaded shadow mapping works for a [`DirectionalLight`].
+///
+/// Use [`CascadeShadowConfigBuilder`] to construct a valid configuration.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct CascadeShadowConfig {
+    /// The (positive) distance to the far boundary of each cascade.
+    pub bounds: Vec<f32>,
+    /// The proportion of overlap each cascade has with the previous cascade.
+    pub overlap_proportion: f32,
+    /// The (positive) distance to the near boundary of the first cascade.
+    pub minimum_distance: f32,
+}
+
+impl Default for CascadeShadowConfig {
+    fn default() -> Self {
+        CascadeShadowConfigBuilder::default().into()
+    }
+}
+
+/// Returns the far bounds of `num_cascades` cascades, distributed so that the first cascade ends
+/// at `nearest_bound` and the last one at `shadow_maximum_distance`, with each cascade covering
+/// an exponentially larger distance than the previous one.
+fn calculate_cascade_bounds(
+    num_cascades: usize,
+    nearest_bound: f32,
+    shadow_maximum_distance: f32,
+) -> Vec<f32> {
+    if num_cascades == 1 {
+        return vec![shadow_maximum_distance];
+    }
+    let base = (shadow_maximum_distance / nearest_bound).powf(1.0 / (num_cascades - 1) as f32);
+    (0..num_cascades)
+        .map(|i| nearest_bound * base.powf(i as f32))
+        .collect()
+}
+
+/// Builder for [`CascadeShadowConfig`].
+#[derive(Clone, Debug)]
+pub struct CascadeShadowConfigBuilder {
+    /// The number of shadow cascades.
+    ///
+    /// More cascades increase shadow quality by mitigating perspective aliasing - a phenomenon
+    /// where areas nearer the camera are covered by fewer shadow map texels than areas further
+    /// from the camera, causing blocky looking shadows.
+    ///
+    /// This does come at the cost of increased rendering overhead, however this overhead is
+    /// still less than if you were to use fewer cascades and much larger shadow map textures to
+    /// achieve the same quality level.
+    ///
+    /// Defaults to 4 on native and 1 on WebGL, and can be at most [`MAX_CASCADES_PER_LIGHT`].
+    pub num_cascades: usize,
+    /// The minimum shadow distance, which can help improve the texel resolution of the first
+    /// cascade.
+    ///
+    /// Areas nearer to the camera than this will likely receive no shadows.
+    pub minimum_distance: f32,
+    /// The maximum shadow distance.
+    ///
+    /// Areas further from the camera than this will likely receive no shadows.
+    pub maximum_distance: f32,
+    /// Sets the far bound of the first cascade, relative to the view origin.
+    ///
+    /// In-between cascades will be exponentially spaced relative to the maximum shadow
+    /// distance. Has no effect if `num_cascades` is 1.
+    pub first_cascade_far_bound: f32,
+    /// Sets the overlap proportion between cascades.
+    ///
+    /// The overlap is used to make the transition from one cascade's shadow map to the next
+    /// less abrupt by blending between both shadow maps.
+    pub overlap_proportion: f32,
+}
+
+impl CascadeShadowConfigBuilder {
+    /// Returns the cascade config as specified by this builder.
+    pub fn build(&self) -> CascadeShadowConfig {
+        assert!(
+            self.num_cascades > 0,
+            "num_cascades must be positive, but was {}",
+            self.num_cascades
+        );
+        assert!(
+            self.num_cascades <= MAX_CASCADES_PER_LIGHT,
+            "num_cascades must be at most {}, but was {}",
+            MAX_CASCADES_PER_LIGHT,
+            self.num_cascades
+        );
+        assert!(
+            self.minimum_distance >= 0.0,
+            "minimum_distance must be non-negative, but was {}",
+            self.minimum_distance
+        );
+        assert!(
+            self.num_cascades == 1 || self.minimum_distance < self.first_cascade_far_bound,
+            "minimum_distance must be less than first_cascade_far_bound, but was {}",
+            self.minimum_distance
+        );
+        assert!(
+            self.maximum_distance > self.minimum_distance,
+            "maximum_distance must be greater than minimum_distance, but was {}",
+            self.maximum_distance
+        );
+        assert!(
+            (0.0..1.0).contains(&self.overlap_proportion),
+            "overlap_proportion must be in [0.0, 1.0) but was {}",
+            self.overlap_proportion
+        );
+        CascadeShadowConfig {
+            bounds: calculate_cascade_bounds(
+                self.num_cascades,
+                self.first_cascade_far_bound,
+                self.maximum_distance,
+            ),
+            overlap_proportion: self.overlap_proportion,
+            minimum_distance: self.minimum_distance,
+        }
+    }
+}
+
+impl Default for CascadeShadowConfigBuilder {
+    fn default() -> Self {
+        if cfg!(feature = "webgl") {
+            // Currently only support one cascade in webgl.
+            Self {
+                num_cascades: 1,
+                minimum_distance: 0.1,
+                maximum_distance: 100.0,
+                first_cascade_far_bound: 5.0,
+                overlap_proportion: 0.2,
+            }
+        } else {
+            Self {
+                num_cascades: 4,
+                minimum_distance: 0.1,
+                maximum_distance: 1000.0,
+                first_cascade_far_bound: 5.0,
+                overlap_proportion: 0.2,
+            }
+        }
+    }
+}
+
+impl From<CascadeShadowConfigBuilder> for CascadeShadowConfig {
+    fn from(builder: CascadeShadowConfigBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// The cascades of a [`DirectionalLight`] for each view, computed from its
+/// [`CascadeShadowConfig`] by [`update_directional_light_cascades`].
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Cascades {
+    /// Map from a view to the configuration of each of its [`Cascade`]s.
+    #[reflect(ignore)]
+    pub(crate) cascades: HashMap<Entity, Vec<Cascade>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cascade {
+    /// The transform of the light, i.e. the view to world matrix.
+    pub(crate) view_transform: Mat4,
+    /// The orthographic projection for this cascade.
+    pub(crate) projection: Mat4,
+    /// The view-projection matrix for this cascade, converting world space into light clip space.
+    /// Importantly, this is derived and stored separately from `view_transform` and `projection`
+    /// to improve shadow stability.
+    pub(crate) view_projection: Mat4,
+    /// Size of each shadow map texel in world units.
+    pub(crate) texel_size: f32,
+}
+
+pub fn update_directional_light_cascades(
+    directional_light_shadow_map: Res<DirectionalLightShadowMap>,
+    views: Query<(Entity, &GlobalTransform, &Projection, &Camera)>,
+    mut lights: Query<(
+        &GlobalTransform,
+        &DirectionalLight,
+        &CascadeShadowConfig,
+        &mut Cascades,
+    )>,
+) {
+    let views = views
+        .iter()
+        .filter_map(|(entity, transform, projection, camera)| {
+            if camera.is_active {
+                Some((entity, projection, transform.compute_matrix()))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (transform, directional_light, cascades_config, mut cascades) in &mut lights {
+        if !directional_light.shadows_enabled {
+            continue;
+        }
+
+        // It is very important to the numerical and thus visual stability of shadows that
+        // light_to_world has orthogonal upper-left 3x3 and zero translation.
+        // Even though only the direction (i.e. rotation) of the light matters, we don't constrain
+        // users to not change any other aspects of the transform - there's no guarantee
+        // `transform.compute_matrix()` will give us a matrix with our desired properties.
+        // Instead, we directly create a good matrix from just the rotation.
+        let light_to_world = Mat4::from_quat(transform.compute_transform().rotation);
+        let light_to_world_inverse = light_to_world.inverse();
+
+        cascades.cascades.clear();
+        for (view_entity, projection, view_to_world) in views.iter().copied() {
+            let camera_to_light_view = light_to_world_inverse * view_to_world;
+            let view_cascades = cascades_config
+                .bounds
+                .iter()
+                .enumerate()
+                .map(|(idx, far_bound)| {
+                    // Negate bounds as -z is camera forward direction.
+                    let z_near = if idx > 0 {
+                        (1.0 - cascades_config.overlap_proportion)
+                            * -cascades_config.bounds[idx - 1]
+                    } else {
+                        -cascades_config.minimum_distance
+                    };
+                    let z_far = -far_bound;
+
+                    let corners = projection.get_frustum_corners(z_near, z_far);
+
+                    calculate_cascade(
+                        corners,
+                        directional_light_shadow_map.size as f32,
+                        light_to_world,
+                        camera_to_light_view,
+                    )
+                })
+                .collect();
+            cascades.cascades.insert(view_entity, view_cascades);
+        }
+    }
+}
+
+fn calculate_cascade(
+    frustum_corners: [Vec3A; 8],
+    cascade_texture_size: f32,
+    light_to_world: Mat4,
+    camera_to_light: Mat4,
+) -> Cascade {
+    let mut min = Vec3A::splat(f32::MAX);
+    let mut max = Vec3A::splat(f32::MIN);
+    for corner_camera_view in frustum_corners {
+        let corner_light_view = camera_to_light.transform_point3a(corner_camera_view);
+        min = min.min(corner_light_view);
+        max = max.max(corner_light_view);
+    }
+
+    // NOTE: Use the larger of the frustum slice far plane diagonal and body diagonal lengths as this
+    //       will be the maximum possible projection size. Use the ceiling to get an integer which is
+    //       very important for floating point stability later. It is also important that these are
+    //       calculated using the original camera space corner positions for floating point precision
+    //       as even though the lengths using corner_light_view above should be the same, precision can
+    //       introduce small but significant differences.
+    // NOTE: The size remains the same unless the view frustum or cascade configuration is modified.
+    let cascade_diameter = (frustum_corners[0] - frustum_corners[6])
+        .length()
+        .max((frustum_corners[4] - frustum_corners[6]).length())
+        .ceil();
+
+    // NOTE: If we ensure that cascade_texture_size is a power of 2, then as we made cascade_diameter an
+    //       integer, cascade_texel_size is then an integer multiple of a power of 2 and can be
+    //       exactly represented in a floating point value.
+    let cascade_texel_size = cascade_diameter / cascade_texture_size;
+    // NOTE: For shadow stability it is very important that the near_plane_center is at integer
+    //       multiples of the texel size to be exactly representable in a floating point value.
+    let near_plane_center = Vec3A::new(
+        (0.5 * (min.x + max.x) / cascade_texel_size).floor() * cascade_texel_size,
+        (0.5 * (min.y + max.y) / cascade_texel_size).floor() * cascade_texel_size,
+        // NOTE: max.z is the near plane for right-handed y-up
+        max.z,
+    );
+
+    // It is critical for `world_to_cascade` to be stable. So rather than forming `cascade_to_world`
+    // and inverting it, which risks instability due to numerical precision, we directly form
+    // `world_to_cascade` from the transpose of the pure rotation `light_to_world`.
+    let light_to_world_transpose = light_to_world.transpose();
+    let world_to_cascade = Mat4::from_cols(
+        light_to_world_transpose.x_axis,
+        light_to_world_transpose.y_axis,
+        light_to_world_transpose.z_axis,
+        (-near_plane_center).extend(1.0),
+    );
+
+    // Right-handed orthographic projection, centered at `near_plane_center`.
+    // NOTE: This maps the near plane to a depth of 1.0 and the far plane to 0.0 as we use reverse Z.
+    let r = (max.z - min.z).recip();
+    let cascade_projection = Mat4::from_cols(
+        Vec4::new(2.0 / cascade_diameter, 0.0, 0.0, 0.0),
+        Vec4::new(0.0, 2.0 / cascade_diameter, 0.0, 0.0),
+        Vec4::new(0.0, 0.0, r, 0.0),
+        Vec4::new(0.0, 0.0, 1.0, 1.0),
+    );
+
+    let cascade_view_projection = cascade_projection * world_to_cascade;
+    Cascade {
+        view_transform: world_to_cascade.inverse(),
+        projection: cascade_projection,
+        view_projection: cascade_view_projection,
+        texel_size: cascade_texel_size,
+    }
+}
+
+/// Add this component to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d)
+/// to control how to anti-alias shadow edges.
+///
+/// The different modes use different approaches to
+/// [Percentage Closer Filtering](https://developer.nvidia.com/gpugems/gpugems/part-ii-lighting-and-shadows/chapter-11-shadow-map-antialiasing).
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect, FromReflect)]
+#[reflect(Component, Default)]
+pub enum ShadowFilteringMethod {
+    /// Hardware 2x2.
+    ///
+    /// Fast but poor quality.
+    #[default]
+    Hardware2x2,
+    /// Method by Ignacio Castaño for The Witness using 9 samples and smart
+    /// filtering to achieve the same as a regular 5x5 filter kernel.
+    ///
+    /// Good quality, good performance.
+    ///
+    /// Point light shadows are cube maps, so they use a 3x3 tent filter of hardware 2x2
+    /// lookups instead.
+    Castano13,
+}
+
+impl ExtractComponent for ShadowFilteringMethod {
+    type Query = &'static Self;
+    type Filter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self::Out> {
+        Some(*item)
+    }
+}
+
+/// Add this component to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) to tint what it
+/// sees with a different color for each [`Cascade`] of the directional lights, to check the
+/// boundaries of their cascades configured with a [`CascadeShadowConfig`].
+#[derive(Component, Clone, Copy, Debug, Default, Reflect, FromReflect)]
+#[reflect(Component, Default)]
+pub struct CascadeDebugVisualization;
+
+impl ExtractComponent for CascadeDebugVisualization {
+    type Query = &'static Self;
+    type Filter = With<Camera>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self::Out> {
+        Some(*item)
+    }
+}
+
 /// An ambient light, which lights the entire scene equally.
 #[derive(Resource, Clone, Debug, ExtractResource, Reflect)]
 #[reflect(Resource)]
@@ -293,6 +633,7 @@ pub struct NotShadowReceiver;
 pub enum SimulationLightSystems {
     AddClusters,
     AssignLightsToClusters,
+    UpdateDirectionalLightCascades,
     UpdateLightFrusta,
     CheckLightVisibility,
 }
@@ -339,8 +680,8 @@ impl Default for ClusterZConfig {
 }
 
 /// Configuration of the clustering strategy for clustered forward rendering
-#[derive(Debug, Copy, Clone, Component, Reflect)]
-#[reflect(Component)]
+#[derive(Debug, Copy, Clone, Component, Reflect, FromReflect)]
+#[reflect(Component, Default)]
 pub enum ClusterConfig {
     /// Disable light cluster calculations for this view
     None,
@@ -1462,19 +1803,18 @@ fn project_to_plane_y(y_light: Sphere, y_plane: Plane, is_orthographic: bool) ->
 pub fn update_directional_light_frusta(
     mut views: Query<
         (
-            &GlobalTransform,
+            &Cascades,
             &DirectionalLight,
-            &mut Frustum,
             &ComputedVisibility,
+            &mut CascadesFrusta,
         ),
         (
-            Or<(Changed<GlobalTransform>, Changed<DirectionalLight>)>,
             // Prevents this query from conflicting with camera queries.
             Without<Camera>,
         ),
     >,
 ) {
-    for (transform, directional_light, mut frustum, visibility) in &mut views {
+    for (cascades, directional_light, visibility, mut frusta) in &mut views {
         // The frustum is used for culling meshes to the light for shadow mapping
         // so if shadow mapping is disabled for this light, then the frustum is
         // not needed.
@@ -1482,14 +1822,28 @@ pub fn update_directional_light_frusta(
             continue;
         }
 
-        let view_projection = directional_light.shadow_projection.get_projection_matrix()
-            * transform.compute_matrix().inverse();
-        *frustum = Frustum::from_view_projection(
-            &view_projection,
-            &transform.translation(),
-            &transform.back(),
-            directional_light.shadow_projection.far(),
-        );
+        frusta.frusta = cascades
+            .cascades
+            .iter()
+            .map(|(view, cascades)| {
+                (
+                    *view,
+                    cascades
+                        .iter()
+                        .map(|cascade| {
+                            // The far plane of the orthographic cascade projection is at a depth
+                            // of 1 / r behind its near plane.
+                            Frustum::from_view_projection(
+                                &cascade.view_projection,
+                                &cascade.view_transform.w_axis.truncate(),
+                                &cascade.view_transform.z_axis.truncate(),
+                                cascade.projection.z_axis.z.recip(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
     }
 }
 
@@ -1591,8 +1945,8 @@ pub fn check_light_mesh_visibility(
     mut directional_lights: Query<
         (
             &DirectionalLight,
-            &Frustum,
-            &mut VisibleEntities,
+            &CascadesFrusta,
+            &mut CascadesVisibleEntities,
             Option<&RenderLayers>,
             &ComputedVisibility,
         ),
@@ -1628,13 +1982,35 @@ pub fn check_light_mesh_visibility(
     // Directional lights
     for (
         directional_light,
-        frustum,
+        frusta,
         mut visible_entities,
         maybe_view_mask,
         light_computed_visibility,
     ) in &mut directional_lights
     {
-        visible_entities.entities.clear();
+        // Re-use already allocated entries where possible.
+        let mut views_to_remove = Vec::new();
+        for (view, cascade_view_entities) in &mut visible_entities.entities {
+            match frusta.frusta.get(view) {
+                Some(view_frusta) => {
+                    cascade_view_entities.resize(view_frusta.len(), Default::default());
+                    cascade_view_entities
+                        .iter_mut()
+                        .for_each(|x| x.entities.clear());
+                }
+                None => views_to_remove.push(*view),
+            };
+        }
+        for (view, frusta) in &frusta.frusta {
+            visible_entities
+                .entities
+                .entry(*view)
+                .or_insert_with(|| vec![VisibleEntities::default(); frusta.len()]);
+        }
+
+        for v in views_to_remove {
+            visible_entities.entities.remove(&v);
+        }
 
         // NOTE: If shadow mapping is disabled for the light then it must have no visible entities
         if !directional_light.shadows_enabled || !light_computed_visibility.is_visible() {
@@ -1657,16 +2033,41 @@ pub fn check_light_mesh_visibility(
 
             // If we have an aabb and transform, do frustum culling
             if let (Some(aabb), Some(transform)) = (maybe_aabb, maybe_transform) {
-                if !frustum.intersects_obb(aabb, &transform.compute_matrix(), true) {
-                    continue;
+                let model_to_world = transform.compute_matrix();
+                for (view, view_frusta) in &frusta.frusta {
+                    let view_visible_entities = visible_entities
+                        .entities
+                        .get_mut(view)
+                        .expect("Per-view visible entities should have been inserted already");
+
+                    for (frustum, frustum_visible_entities) in
+                        view_frusta.iter().zip(view_visible_entities)
+                    {
+                        // Disable near-plane culling, as a shadow caster could lie before the near
+                        // plane: its shadow is clamped onto the near plane of the cascade.
+                        if !frustum.intersects_obb(aabb, &model_to_world, false, true) {
+                            continue;
+                        }
+
+                        computed_visibility.set_visible_in_view();
+                        frustum_visible_entities.entities.push(entity);
+                    }
+                }
+            } else {
+                computed_visibility.set_visible_in_view();
+                for view_visible_entities in visible_entities.entities.values_mut() {
+                    for frustum_visible_entities in view_visible_entities {
+                        frustum_visible_entities.entities.push(entity);
+                    }
                 }
             }
-
-            computed_visibility.set_visible_in_view();
-            visible_entities.entities.push(entity);
         }
 
-        shrink_entities(&mut visible_entities);
+        for view_visible_entities in visible_entities.entities.values_mut() {
+            for frustum_visible_entities in view_visible_entities {
+                shrink_entities(frustum_visible_entities);
+            }
+        }
     }
 
     for visible_lights in &visible_point_lights {
@@ -1724,7 +2125,7 @@ pub fn check_light_mesh_visibility(
                             .iter()
                             .zip(cubemap_visible_entities.iter_mut())
                         {
-                            if frustum.intersects_obb(aabb, &model_to_world, true) {
+                            if frustum.intersects_obb(aabb, &model_to_world, true, true) {
                                 computed_visibility.set_visible_in_view();
                                 visible_entities.entities.push(entity);
                             }
@@ -1784,7 +2185,7 @@ pub fn check_light_mesh_visibility(
                             continue;
                         }
 
-                        if frustum.intersects_obb(aabb, &model_to_world, true) {
+                        if frustum.intersects_obb(aabb, &model_to_world, true, true) {
                             computed_visibility.set_visible_in_view();
                             visible_entities.entities.push(entity);
                         }
@@ -1860,4 +2261,17 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn cascade_bounds_are_exponentially_spaced() {
+        let bounds = calculate_cascade_bounds(4, 5.0, 1000.0);
+        assert_eq!(bounds.len(), 4);
+        assert!((bounds[0] - 5.0).abs() < 1e-4);
+        assert!((bounds[3] - 1000.0).abs() < 1e-2);
+        let ratio = bounds[1] / bounds[0];
+        assert!((bounds[2] / bounds[1] - ratio).abs() < 1e-4);
+        assert!((bounds[3] / bounds[2] - ratio).abs() < 1e-4);
+
+        assert_eq!(calculate_cascade_bounds(1, 5.0, 100.0), vec![100.0]);
+    }
 }
@@ -1,8 +1,12 @@
 use crate::{
-    AlphaMode, DrawMesh, MeshPipeline, MeshPipelineKey, MeshUniform, SetMeshBindGroup,
-    SetMeshViewBindGroup,
+    batch_mesh_instances, is_skinned, prepare_mesh_instances,
+    procedural_sky::ProceduralSkyEnvironmentMap, AlphaMode, CascadeDebugVisualization,
+    DrawMeshInstanced, DrawPlaceholder, EnvironmentMapLight, MeshPipeline, MeshPipelineKey,
+    MeshUniform, NotShadowCaster, PickingMaterialPlugin, PlaceholderPipeline, PrepassPlugin,
+    ReflectionProbeMeta, ScreenSpaceAmbientOcclusionTextures, SetMeshBindGroup,
+    SetMeshViewBindGroup, ShadowFilteringMethod,
 };
-use bevy_app::{App, Plugin};
+use bevy_app::{App, CoreStage, Plugin};
 use bevy_asset::{AddAsset, AssetEvent, AssetServer, Assets, Handle};
 use bevy_core_pipeline::{
     core_3d::{AlphaMask3d, Opaque3d, Transparent3d},
@@ -13,6 +17,7 @@ use bevy_ecs::{
     entity::Entity,
     event::EventReader,
     prelude::World,
+    query::Added,
     schedule::IntoSystemDescriptor,
     system::{
         lifetimeless::{Read, SQuery, SRes},
@@ -23,7 +28,7 @@ use bevy_ecs::{
 use bevy_reflect::TypeUuid;
 use bevy_render::{
     extract_component::ExtractComponentPlugin,
-    mesh::{Mesh, MeshVertexBufferLayout},
+    mesh::{lod::LodFadeOut, Mesh, MeshVertexBufferLayout},
     prelude::Image,
     render_asset::{PrepareAssetLabel, RenderAssets},
     render_phase::{
@@ -110,6 +115,9 @@ use std::marker::PhantomData;
 pub trait Material: AsBindGroup + Send + Sync + Clone + TypeUuid + Sized + 'static {
     /// Returns this material's vertex shader. If [`ShaderRef::Default`] is returned, the default mesh vertex shader
     /// will be used.
+    ///
+    /// Meshes sharing the same mesh and material are only batched into instanced draws when the default
+    /// vertex shader is used, as custom vertex shaders read the transform from the mesh uniform.
     fn vertex_shader() -> ShaderRef {
         ShaderRef::Default
     }
@@ -150,21 +158,42 @@ pub trait Material: AsBindGroup + Send + Sync + Clone + TypeUuid + Sized + 'stat
 
 /// Adds the necessary ECS resources and render logic to enable rendering entities using the given [`Material`]
 /// asset type.
-pub struct MaterialPlugin<M: Material>(PhantomData<M>);
+pub struct MaterialPlugin<M: Material> {
+    /// Whether the opaque meshes using the material are drawn into the prepass of the cameras
+    /// that have one, see [`PrepassPlugin`]. Defaults to `true`.
+    pub prepass_enabled: bool,
+    /// Whether the meshes using the material are drawn with a grey placeholder while the pipeline
+    /// of their material is being created, instead of not being drawn until it is. See
+    /// [`PlaceholderPlugin`]. Pipelines are only created over several frames with
+    /// [`RenderPlugin::asynchronous_pipeline_compilation`](bevy_render::RenderPlugin::asynchronous_pipeline_compilation).
+    /// Defaults to `false`.
+    pub placeholder_enabled: bool,
+    pub _marker: PhantomData<M>,
+}
 
 impl<M: Material> Default for MaterialPlugin<M> {
     fn default() -> Self {
-        Self(Default::default())
+        Self {
+            prepass_enabled: true,
+            placeholder_enabled: false,
+            _marker: Default::default(),
+        }
     }
 }
 
+/// Exists in the render world when the [`MaterialPlugin`] of the material has
+/// [`placeholder_enabled`](MaterialPlugin::placeholder_enabled).
+#[derive(Resource)]
+pub struct MaterialPlaceholders<M: Material>(PhantomData<M>);
+
 impl<M: Material> Plugin for MaterialPlugin<M>
 where
     M::Data: PartialEq + Eq + Hash + Clone,
 {
     fn build(&self, app: &mut App) {
         app.add_asset::<M>()
-            .add_plugin(ExtractComponentPlugin::<Handle<M>>::extract_visible());
+            .add_plugin(ExtractComponentPlugin::<Handle<M>>::extract_visible())
+            .add_system_to_stage(CoreStage::PostUpdate, add_lod_fade_out_materials::<M>);
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
                 .add_render_command::<Transparent3d, DrawMaterial<M>>()
@@ -179,7 +208,35 @@ where
                     RenderStage::Prepare,
                     prepare_materials::<M>.after(PrepareAssetLabel::PreAssetPrepare),
                 )
-                .add_system_to_stage(RenderStage::Queue, queue_material_meshes::<M>);
+                .add_system_to_stage(RenderStage::Queue, queue_material_meshes::<M>)
+                .add_system_to_stage(
+                    RenderStage::PhaseSort,
+                    batch_material_meshes::<M>.after(prepare_mesh_instances),
+                );
+            if self.placeholder_enabled {
+                render_app.insert_resource(MaterialPlaceholders::<M>(PhantomData));
+            }
+        }
+
+        if self.prepass_enabled {
+            app.add_plugin(PrepassPlugin::<M>::default());
+        }
+        app.add_plugin(PickingMaterialPlugin::<M>::default());
+    }
+}
+
+/// Gives the [`LodFadeOut`] entities drawing the previous level of detail of a mesh the material
+/// of their source. They don't cast shadows, which the source already does.
+pub fn add_lod_fade_out_materials<M: Material>(
+    mut commands: Commands,
+    fade_outs: Query<(Entity, &LodFadeOut), Added<LodFadeOut>>,
+    materials: Query<&Handle<M>>,
+) {
+    for (entity, fade_out) in &fade_outs {
+        if let Ok(material) = materials.get(fade_out.source) {
+            commands
+                .entity(entity)
+                .insert((material.clone(), NotShadowCaster));
         }
     }
 }
@@ -233,6 +290,18 @@ pub struct MaterialPipeline<M: Material> {
     marker: PhantomData<M>,
 }
 
+impl<M: Material> Clone for MaterialPipeline<M> {
+    fn clone(&self) -> Self {
+        Self {
+            mesh_pipeline: self.mesh_pipeline.clone(),
+            material_layout: self.material_layout.clone(),
+            vertex_shader: self.vertex_shader.clone(),
+            fragment_shader: self.fragment_shader.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
 impl<M: Material> SpecializedMeshPipeline for MaterialPipeline<M>
 where
     M::Data: PartialEq + Eq + Hash + Clone,
@@ -291,7 +360,7 @@ type DrawMaterial<M> = (
     SetMeshViewBindGroup<0>,
     SetMaterialBindGroup<M, 1>,
     SetMeshBindGroup<2>,
-    DrawMesh,
+    DrawMeshInstanced,
 );
 
 /// Sets the bind group for a given [`Material`] at the configured `I` index.
@@ -319,14 +388,26 @@ pub fn queue_material_meshes<M: Material>(
     material_pipeline: Res<MaterialPipeline<M>>,
     mut pipelines: ResMut<SpecializedMeshPipelines<MaterialPipeline<M>>>,
     mut pipeline_cache: ResMut<PipelineCache>,
+    (placeholders, placeholder_pipeline, mut placeholder_pipelines): (
+        Option<Res<MaterialPlaceholders<M>>>,
+        Res<PlaceholderPipeline>,
+        ResMut<SpecializedMeshPipelines<PlaceholderPipeline>>,
+    ),
     msaa: Res<Msaa>,
     render_meshes: Res<RenderAssets<Mesh>>,
     render_materials: Res<RenderMaterials<M>>,
+    images: Res<RenderAssets<Image>>,
+    reflection_probe_meta: Res<ReflectionProbeMeta>,
     material_meshes: Query<(&Handle<M>, &Handle<Mesh>, &MeshUniform)>,
     mut views: Query<(
         &ExtractedView,
         &VisibleEntities,
         Option<&Tonemapping>,
+        Option<&ShadowFilteringMethod>,
+        Option<&CascadeDebugVisualization>,
+        Option<&EnvironmentMapLight>,
+        Option<&ProceduralSkyEnvironmentMap>,
+        Option<&ScreenSpaceAmbientOcclusionTextures>,
         &mut RenderPhase<Opaque3d>,
         &mut RenderPhase<AlphaMask3d>,
         &mut RenderPhase<Transparent3d>,
@@ -338,6 +419,11 @@ pub fn queue_material_meshes<M: Material>(
         view,
         visible_entities,
         tonemapping,
+        shadow_filtering_method,
+        cascade_debug_visualization,
+        environment_map,
+        procedural_sky_environment_map,
+        ssao_textures,
         mut opaque_phase,
         mut alpha_mask_phase,
         mut transparent_phase,
@@ -346,13 +432,41 @@ pub fn queue_material_meshes<M: Material>(
         let draw_opaque_pbr = opaque_draw_functions.read().id::<DrawMaterial<M>>();
         let draw_alpha_mask_pbr = alpha_mask_draw_functions.read().id::<DrawMaterial<M>>();
         let draw_transparent_pbr = transparent_draw_functions.read().id::<DrawMaterial<M>>();
+        let draw_placeholder = opaque_draw_functions.read().id::<DrawPlaceholder>();
 
         let mut view_key =
             MeshPipelineKey::from_msaa_samples(msaa.samples) | MeshPipelineKey::from_hdr(view.hdr);
 
-        if let Some(Tonemapping::Enabled { deband_dither }) = tonemapping {
+        if let Some(shadow_filtering_method) = shadow_filtering_method {
+            view_key |= MeshPipelineKey::from_shadow_filtering_method(*shadow_filtering_method);
+        }
+
+        if cascade_debug_visualization.is_some() {
+            view_key |= MeshPipelineKey::DEBUG_CASCADES;
+        }
+
+        if procedural_sky_environment_map.is_some()
+            || environment_map.map_or(false, |environment_map| environment_map.is_loaded(&images))
+        {
+            view_key |= MeshPipelineKey::ENVIRONMENT_MAP;
+        }
+
+        if reflection_probe_meta.has_probes() {
+            view_key |= MeshPipelineKey::REFLECTION_PROBES;
+        }
+
+        if ssao_textures.is_some() {
+            view_key |= MeshPipelineKey::SCREEN_SPACE_AMBIENT_OCCLUSION;
+        }
+
+        if let Some(Tonemapping::Enabled {
+            deband_dither,
+            method,
+        }) = tonemapping
+        {
             if !view.hdr {
                 view_key |= MeshPipelineKey::TONEMAP_IN_SHADER;
+                view_key |= MeshPipelineKey::from_tonemapping_method(*method);
 
                 if *deband_dither {
                     view_key |= MeshPipelineKey::DEBAND_DITHER;
@@ -371,9 +485,33 @@ pub fn queue_material_meshes<M: Material>(
                             MeshPipelineKey::from_primitive_topology(mesh.primitive_topology)
                                 | view_key;
                         let alpha_mode = material.properties.alpha_mode;
-                        if let AlphaMode::Blend = alpha_mode {
-                            mesh_key |= MeshPipelineKey::TRANSPARENT_MAIN_PASS;
+                        match alpha_mode {
+                            AlphaMode::Blend => {
+                                mesh_key |= MeshPipelineKey::TRANSPARENT_MAIN_PASS;
+                            }
+                            AlphaMode::Premultiplied | AlphaMode::Add => {
+                                mesh_key |= MeshPipelineKey::TRANSPARENT_MAIN_PASS
+                                    | MeshPipelineKey::BLEND_PREMULTIPLIED_ALPHA;
+                            }
+                            AlphaMode::Opaque | AlphaMode::Mask(_) => {}
+                        }
+                        if mesh.morph_targets.is_some() {
+                            mesh_key |= MeshPipelineKey::MORPH_TARGETS;
+                        }
+                        let cross_fade = mesh_uniform.lod_fade != 0.0;
+                        if cross_fade {
+                            mesh_key |= MeshPipelineKey::LOD_CROSS_FADE;
                         }
+                        // Custom vertex shaders don't read the per-instance transforms, and the
+                        // cross-fade is read from the mesh uniform
+                        let instanced = material_pipeline.vertex_shader.is_none()
+                            && !is_skinned(&mesh.layout)
+                            && mesh.morph_targets.is_none()
+                            && !cross_fade;
+                        if instanced {
+                            mesh_key |= MeshPipelineKey::INSTANCED;
+                        }
+                        let batch_range = instanced.then_some(0..1);
 
                         let pipeline_id = pipelines.specialize(
                             &mut pipeline_cache,
@@ -394,6 +532,33 @@ pub fn queue_material_meshes<M: Material>(
 
                         let distance = rangefinder.distance(&mesh_uniform.transform)
                             + material.properties.depth_bias;
+
+                        // The pipeline may still be created in the background
+                        if pipeline_cache.get_render_pipeline(pipeline_id).is_none() {
+                            if placeholders.is_none() {
+                                continue;
+                            }
+                            let placeholder_id = placeholder_pipelines.specialize(
+                                &mut pipeline_cache,
+                                &placeholder_pipeline,
+                                PlaceholderPipeline::placeholder_key(mesh_key),
+                                &mesh.layout,
+                            );
+                            match placeholder_id {
+                                Ok(id) if pipeline_cache.get_render_pipeline(id).is_some() => {
+                                    opaque_phase.add(Opaque3d {
+                                        entity: *visible_entity,
+                                        draw_function: draw_placeholder,
+                                        pipeline: id,
+                                        distance,
+                                        batch_range: None,
+                                    });
+                                }
+                                Ok(_) => {}
+                                Err(err) => error!("{}", err),
+                            }
+                            continue;
+                        }
                         match alpha_mode {
                             AlphaMode::Opaque => {
                                 opaque_phase.add(Opaque3d {
@@ -401,6 +566,7 @@ pub fn queue_material_meshes<M: Material>(
                                     draw_function: draw_opaque_pbr,
                                     pipeline: pipeline_id,
                                     distance,
+                                    batch_range,
                                 });
                             }
                             AlphaMode::Mask(_) => {
@@ -409,14 +575,16 @@ pub fn queue_material_meshes<M: Material>(
                                     draw_function: draw_alpha_mask_pbr,
                                     pipeline: pipeline_id,
                                     distance,
+                                    batch_range,
                                 });
                             }
-                            AlphaMode::Blend => {
+                            AlphaMode::Blend | AlphaMode::Premultiplied | AlphaMode::Add => {
                                 transparent_phase.add(Transparent3d {
                                     entity: *visible_entity,
                                     draw_function: draw_transparent_pbr,
                                     pipeline: pipeline_id,
                                     distance,
+                                    batch_range,
                                 });
                             }
                         }
@@ -427,6 +595,39 @@ pub fn queue_material_meshes<M: Material>(
     }
 }
 
+/// Merges consecutive phase items drawing the same mesh with the same [`Material`] instance into
+/// instanced draws.
+pub fn batch_material_meshes<M: Material>(
+    opaque_draw_functions: Res<DrawFunctions<Opaque3d>>,
+    alpha_mask_draw_functions: Res<DrawFunctions<AlphaMask3d>>,
+    transparent_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    material_meshes: Query<(&Handle<M>, &Handle<Mesh>, &MeshUniform)>,
+    mut views: Query<(
+        &mut RenderPhase<Opaque3d>,
+        &mut RenderPhase<AlphaMask3d>,
+        &mut RenderPhase<Transparent3d>,
+    )>,
+) {
+    let draw_opaque_pbr = opaque_draw_functions.read().id::<DrawMaterial<M>>();
+    let draw_alpha_mask_pbr = alpha_mask_draw_functions.read().id::<DrawMaterial<M>>();
+    let draw_transparent_pbr = transparent_draw_functions.read().id::<DrawMaterial<M>>();
+
+    // The mesh uniform of the first entity of a batch is bound for all instances, so the flags
+    // read by the fragment shader need to match as well
+    let compatible = |a, b| match (material_meshes.get(a), material_meshes.get(b)) {
+        (Ok((material_a, mesh_a, uniform_a)), Ok((material_b, mesh_b, uniform_b))) => {
+            material_a == material_b && mesh_a == mesh_b && uniform_a.flags == uniform_b.flags
+        }
+        _ => false,
+    };
+
+    for (mut opaque_phase, mut alpha_mask_phase, mut transparent_phase) in &mut views {
+        batch_mesh_instances(&mut opaque_phase, draw_opaque_pbr, compatible);
+        batch_mesh_instances(&mut alpha_mask_phase, draw_alpha_mask_pbr, compatible);
+        batch_mesh_instances(&mut transparent_phase, draw_transparent_pbr, compatible);
+    }
+}
+
 /// Common [`Material`] properties, calculated for a specific material instance.
 pub struct MaterialProperties {
     /// The [`AlphaMode`] of this material.
@@ -470,11 +671,14 @@ impl<T: Material> Default for RenderMaterials<T> {
 }
 
 /// This system extracts all created or modified assets of the corresponding [`Material`] type
-/// into the "render world".
+/// into the "render world", along with the ones bound to modified images.
 fn extract_materials<M: Material>(
     mut commands: Commands,
     mut events: Extract<EventReader<AssetEvent<M>>>,
+    mut image_events: Extract<EventReader<AssetEvent<Image>>>,
     assets: Extract<Res<Assets<M>>>,
+    images: Res<RenderAssets<Image>>,
+    render_materials: Res<RenderMaterials<M>>,
 ) {
     let mut changed_assets = HashSet::default();
     let mut removed = Vec::new();
@@ -490,6 +694,26 @@ fn extract_materials<M: Material>(
         }
     }
 
+    // Modified images, like streamed or reloaded ones, get new textures once they are prepared
+    // again, so the materials bound to their old textures need to be prepared again too
+    let modified_views: HashSet<_> = image_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => images.get(handle),
+            _ => None,
+        })
+        .map(|gpu_image| gpu_image.texture_view.id())
+        .collect();
+    if !modified_views.is_empty() {
+        for (handle, material) in render_materials.iter() {
+            if material.bindings.iter().any(|binding| {
+                matches!(binding, OwnedBindingResource::TextureView(view) if modified_views.contains(&view.id()))
+            }) {
+                changed_assets.insert(handle.clone_weak());
+            }
+        }
+    }
+
     let mut extracted_assets = Vec::new();
     for handle in changed_assets.drain() {
         if let Some(asset) = assets.get(&handle) {
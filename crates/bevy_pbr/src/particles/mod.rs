@@ -0,0 +1,887 @@
+//! Particles simulated on the GPU, and drawn as billboards facing the cameras.
+//!
+//! Add a [`ParticleEmitterBundle`] to spawn particles around its [`GlobalTransform`]. Each frame,
+//! a compute pass spawns the new particles of every emitter and moves the others, then each view
+//! draws the particles of the visible emitters in its transparent phase.
+//!
+//! The particles are spawned in world space, so they trail behind the emitters that move. Those of
+//! an emitter are drawn in the order they were spawned rather than sorted by depth, and they fade
+//! out where they get close to the meshes behind them when the view has a
+//! [`DepthPrepass`](bevy_core_pipeline::prepass::DepthPrepass).
+//!
+//! Particles need compute shaders and storage buffers, so they aren't drawn on the backends that
+//! don't support them, like the web with the `webgl` feature.
+
+use crate::{MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_core_pipeline::{core_3d::Transparent3d, prepass::ViewPrepassTextures};
+use bevy_ecs::{
+    prelude::*,
+    system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+};
+use bevy_math::{Mat4, Vec3, Vec4};
+use bevy_reflect::{Reflect, TypeUuid};
+use bevy_render::{
+    color::Color,
+    render_asset::RenderAssets,
+    render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+    render_phase::{
+        AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
+        SetItemPipeline, TrackedComputePass, TrackedRenderPass,
+    },
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::{BevyDefault, FallbackImage, Image},
+    view::{
+        ComputedVisibility, ExtractedView, Msaa, ViewTarget, ViewUniform, ViewUniformOffset,
+        ViewUniforms, Visibility, VisibleEntities,
+    },
+    Extract, RenderApp, RenderStage,
+};
+use bevy_time::Time;
+use bevy_transform::components::{GlobalTransform, Transform};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+use bevy_utils::{tracing::warn, HashMap};
+
+const PARTICLE_TYPES_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2090114929329395391);
+const PARTICLE_SIMULATION_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 15164095973410749013);
+const PARTICLES_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6363093092205740848);
+
+/// The name of the node of the main render graph simulating the particles, which runs before the
+/// cameras are rendered.
+pub const PARTICLE_SIMULATION_NODE: &str = "particle_simulation";
+
+/// The size of a particle in the storage buffer of its emitter: its position, age, velocity and
+/// lifetime.
+const PARTICLE_SIZE: u64 = 32;
+/// The number of particles simulated by each workgroup of the compute pass.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Simulates and draws the particles of the [`ParticleEmitter`]s.
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PARTICLE_TYPES_SHADER_HANDLE,
+            "particle_types.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            PARTICLE_SIMULATION_SHADER_HANDLE,
+            "particle_simulation.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            PARTICLES_SHADER_HANDLE,
+            "particles.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<ParticleEmitter>();
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        let limits = render_app.world.resource::<RenderDevice>().limits();
+        if limits.max_storage_buffers_per_shader_stage == 0
+            || limits.max_compute_workgroup_size_x < WORKGROUP_SIZE
+        {
+            warn!("Particles aren't supported by the current backend, they won't be drawn");
+            return;
+        }
+
+        render_app
+            .add_render_command::<Transparent3d, DrawParticles>()
+            .init_resource::<ParticlePipeline>()
+            .init_resource::<SpecializedRenderPipelines<ParticlePipeline>>()
+            .init_resource::<GpuParticleEmitters>()
+            .add_system_to_stage(RenderStage::Extract, extract_particle_emitters)
+            .add_system_to_stage(RenderStage::Prepare, prepare_particle_emitters)
+            .add_system_to_stage(RenderStage::Queue, queue_particle_bind_groups)
+            .add_system_to_stage(RenderStage::Queue, queue_particle_emitters);
+
+        let simulation_node = ParticleSimulationNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(PARTICLE_SIMULATION_NODE, simulation_node);
+        graph.add_node_edge(
+            PARTICLE_SIMULATION_NODE,
+            bevy_render::main_graph::node::CAMERA_DRIVER,
+        );
+    }
+}
+
+/// Spawns particles around the [`GlobalTransform`] of its entity.
+///
+/// The particles move from their initial `velocity`, changed by the `acceleration`, and their
+/// size and color change from the start to the end values over their `lifetime`. When more
+/// particles would be alive than the `capacity` of the emitter, the oldest ones are replaced.
+///
+/// The emitter keeps simulating its particles when it isn't visible, and stops spawning new ones
+/// when its `rate` is zero.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component)]
+pub struct ParticleEmitter {
+    /// The maximum number of particles alive at once. Changing it respawns all the particles.
+    pub capacity: u32,
+    /// The number of particles spawned per second.
+    pub rate: f32,
+    /// How long the particles live, in seconds.
+    pub lifetime: f32,
+    /// The radius of the sphere around the emitter the particles are spawned in.
+    pub spawn_radius: f32,
+    /// The velocity of the particles when they are spawned, in the space of the emitter.
+    pub velocity: Vec3,
+    /// The speed added to the velocity of each particle when it is spawned, in a random direction.
+    pub velocity_randomness: f32,
+    /// The acceleration of the particles in world space, like gravity.
+    pub acceleration: Vec3,
+    /// The width and height of the particles when they are spawned.
+    pub start_size: f32,
+    /// The width and height of the particles at the end of their lifetime.
+    pub end_size: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    /// The texture the color of the particles is multiplied with. The particles are soft discs
+    /// when there is none.
+    pub texture: Option<Handle<Image>>,
+    /// The distance in front of the meshes behind them over which the particles fade out, so
+    /// that they don't end in hard edges where they cross them. This needs a
+    /// [`DepthPrepass`](bevy_core_pipeline::prepass::DepthPrepass) on the camera.
+    pub soft_distance: f32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            rate: 64.0,
+            lifetime: 2.0,
+            spawn_radius: 0.1,
+            velocity: Vec3::Y,
+            velocity_randomness: 0.5,
+            acceleration: Vec3::ZERO,
+            start_size: 0.1,
+            end_size: 0.05,
+            start_color: Color::WHITE,
+            end_color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+            texture: None,
+            soft_distance: 0.2,
+        }
+    }
+}
+
+/// A component bundle for entities spawning particles.
+#[derive(Bundle, Clone, Default)]
+pub struct ParticleEmitterBundle {
+    pub emitter: ParticleEmitter,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether the particles are visible
+    pub visibility: Visibility,
+    /// Algorithmically-computed indication of whether the particles are visible and should be drawn
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// A [`ParticleEmitter`] extracted to the render world, along with its transform.
+#[derive(Component)]
+pub struct ExtractedParticleEmitter {
+    pub emitter: ParticleEmitter,
+    pub transform: GlobalTransform,
+    /// Whether the particles are drawn.
+    pub visible: bool,
+}
+
+pub fn extract_particle_emitters(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    emitters: Extract<
+        Query<(
+            Entity,
+            &ParticleEmitter,
+            &GlobalTransform,
+            &ComputedVisibility,
+        )>,
+    >,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, emitter, transform, visibility) in &emitters {
+        values.push((
+            entity,
+            ExtractedParticleEmitter {
+                emitter: emitter.clone(),
+                transform: *transform,
+                visible: visibility.is_visible(),
+            },
+        ));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+/// The parameters of a [`ParticleEmitter`] for the frame, read by the simulation and the drawing
+/// of its particles.
+#[derive(ShaderType)]
+pub struct ParticleEmitterUniform {
+    pub transform: Mat4,
+    pub start_color: Vec4,
+    pub end_color: Vec4,
+    pub velocity: Vec3,
+    pub velocity_randomness: f32,
+    pub acceleration: Vec3,
+    pub spawn_radius: f32,
+    pub lifetime: f32,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub soft_distance: f32,
+    pub capacity: u32,
+    /// The index of the first particle replaced by a new one this frame.
+    pub spawn_start: u32,
+    /// How many particles are spawned this frame, from `spawn_start` and wrapping around the
+    /// capacity.
+    pub spawn_count: u32,
+    /// Changes every frame, to spawn the particles with different random values.
+    pub seed: u32,
+    pub delta_seconds: f32,
+}
+
+/// The particles of an emitter on the GPU, kept across frames.
+struct GpuParticleEmitter {
+    particles: Buffer,
+    capacity: u32,
+    /// The index the next particle spawned replaces, as they are stored in a ring.
+    next_spawn: u32,
+    /// The fraction of a particle left to spawn from the previous frames.
+    spawn_remainder: f32,
+}
+
+/// The particles of every emitter, along with the [`ParticleEmitterUniform`]s of the frame.
+#[derive(Resource)]
+pub struct GpuParticleEmitters {
+    emitters: HashMap<Entity, GpuParticleEmitter>,
+    uniforms: DynamicUniformBuffer<ParticleEmitterUniform>,
+    frame: u32,
+}
+
+impl FromWorld for GpuParticleEmitters {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            emitters: HashMap::default(),
+            uniforms: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+            frame: 0,
+        }
+    }
+}
+
+/// The offset of the [`ParticleEmitterUniform`] of an emitter in the [`GpuParticleEmitters`].
+#[derive(Component)]
+pub struct ParticleEmitterUniformOffset(pub u32);
+
+pub fn prepare_particle_emitters(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    time: Res<Time>,
+    mut gpu_emitters: ResMut<GpuParticleEmitters>,
+    emitters: Query<(Entity, &ExtractedParticleEmitter)>,
+) {
+    let gpu_emitters = &mut *gpu_emitters;
+    gpu_emitters.uniforms.clear();
+    gpu_emitters.frame = gpu_emitters.frame.wrapping_add(1);
+    let delta_seconds = time.delta_seconds();
+
+    // The emitters that were despawned drop their particles
+    let mut previous_emitters = std::mem::take(&mut gpu_emitters.emitters);
+    for (entity, extracted) in &emitters {
+        let emitter = &extracted.emitter;
+        let capacity = emitter.capacity.max(1);
+        let gpu_emitter = match previous_emitters.remove(&entity) {
+            Some(gpu_emitter) if gpu_emitter.capacity == capacity => gpu_emitter,
+            // NOTE: Buffers are zeroed when created, which leaves every particle dead
+            _ => GpuParticleEmitter {
+                particles: render_device.create_buffer(&BufferDescriptor {
+                    label: Some("particle_buffer"),
+                    size: capacity as u64 * PARTICLE_SIZE,
+                    usage: BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                }),
+                capacity,
+                next_spawn: 0,
+                spawn_remainder: 0.0,
+            },
+        };
+        let gpu_emitter = gpu_emitters.emitters.entry(entity).or_insert(gpu_emitter);
+
+        let to_spawn = gpu_emitter.spawn_remainder + emitter.rate.max(0.0) * delta_seconds;
+        let spawn_count = (to_spawn as u32).min(capacity);
+        gpu_emitter.spawn_remainder = to_spawn.fract();
+        let spawn_start = gpu_emitter.next_spawn;
+        gpu_emitter.next_spawn = (spawn_start + spawn_count) % capacity;
+
+        let offset = gpu_emitters.uniforms.push(ParticleEmitterUniform {
+            transform: extracted.transform.compute_matrix(),
+            start_color: emitter.start_color.as_linear_rgba_f32().into(),
+            end_color: emitter.end_color.as_linear_rgba_f32().into(),
+            velocity: emitter.velocity,
+            velocity_randomness: emitter.velocity_randomness,
+            acceleration: emitter.acceleration,
+            spawn_radius: emitter.spawn_radius,
+            lifetime: emitter.lifetime,
+            start_size: emitter.start_size,
+            end_size: emitter.end_size,
+            soft_distance: emitter.soft_distance,
+            capacity,
+            spawn_start,
+            spawn_count,
+            seed: gpu_emitters.frame,
+            delta_seconds,
+        });
+        commands
+            .entity(entity)
+            .insert(ParticleEmitterUniformOffset(offset));
+    }
+
+    gpu_emitters
+        .uniforms
+        .write_buffer(&render_device, &render_queue);
+}
+
+/// The layouts of the bind groups of the particle pipelines.
+#[derive(Resource)]
+pub struct ParticlePipeline {
+    /// The particles of an emitter, written by the simulation, and its uniform.
+    pub simulation_layout: BindGroupLayout,
+    /// The particles of an emitter, read when drawing them, its uniform and its texture.
+    pub emitter_layout: BindGroupLayout,
+    /// The view uniform, for views without a depth prepass.
+    pub view_layout: BindGroupLayout,
+    /// The view uniform and the depth prepass of the view, for soft particles.
+    pub view_depth_layout: BindGroupLayout,
+    /// The view uniform and the multisampled depth prepass of the view, for soft particles.
+    pub view_multisampled_depth_layout: BindGroupLayout,
+    pub simulation_pipeline: CachedComputePipelineId,
+}
+
+impl ParticlePipeline {
+    fn view_layout(&self, soft: bool, multisampled: bool) -> &BindGroupLayout {
+        match (soft, multisampled) {
+            (false, _) => &self.view_layout,
+            (true, false) => &self.view_depth_layout,
+            (true, true) => &self.view_multisampled_depth_layout,
+        }
+    }
+}
+
+impl FromWorld for ParticlePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let particles_entry = |visibility, read_only| BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(PARTICLE_SIZE),
+            },
+            count: None,
+        };
+        let emitter_uniform_entry = |visibility| BindGroupLayoutEntry {
+            binding: 1,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(ParticleEmitterUniform::min_size()),
+            },
+            count: None,
+        };
+
+        let simulation_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("particle_simulation_layout"),
+                entries: &[
+                    particles_entry(ShaderStages::COMPUTE, false),
+                    emitter_uniform_entry(ShaderStages::COMPUTE),
+                ],
+            });
+        let emitter_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("particle_emitter_layout"),
+            entries: &[
+                particles_entry(ShaderStages::VERTEX, true),
+                emitter_uniform_entry(ShaderStages::VERTEX | ShaderStages::FRAGMENT),
+                // Texture
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // Sampler
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let view_entry = BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(ViewUniform::min_size()),
+            },
+            count: None,
+        };
+        let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("particle_view_layout"),
+            entries: &[view_entry],
+        });
+        let view_depth_layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    view_entry,
+                    // Prepass depth
+                    // NOTE: Bound as a float texture, as loading the texels of a depth texture
+                    // isn't supported by every backend
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+        let view_multisampled_depth_layout =
+            view_depth_layout("particle_view_multisampled_depth_layout", true);
+        let view_depth_layout = view_depth_layout("particle_view_depth_layout", false);
+
+        let simulation_pipeline = world
+            .resource_mut::<PipelineCache>()
+            .queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some("particle_simulation_pipeline".into()),
+                layout: Some(vec![simulation_layout.clone()]),
+                push_constant_ranges: Vec::new(),
+                shader: PARTICLE_SIMULATION_SHADER_HANDLE.typed(),
+                shader_defs: Vec::new(),
+                entry_point: "simulate".into(),
+            });
+
+        ParticlePipeline {
+            simulation_layout,
+            emitter_layout,
+            view_layout,
+            view_depth_layout,
+            view_multisampled_depth_layout,
+            simulation_pipeline,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ParticlePipelineKey {
+    pub hdr: bool,
+    pub msaa_samples: u32,
+    /// Whether the particles fade out close to the depth prepass of the view.
+    pub soft: bool,
+    pub textured: bool,
+}
+
+impl SpecializedRenderPipeline for ParticlePipeline {
+    type Key = ParticlePipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // The shader imports the `View` from `bevy_pbr::mesh_view_types`
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt(
+                "MAX_DIRECTIONAL_LIGHTS".to_string(),
+                MAX_DIRECTIONAL_LIGHTS as u32,
+            ),
+            ShaderDefVal::UInt(
+                "MAX_CASCADES_PER_LIGHT".to_string(),
+                MAX_CASCADES_PER_LIGHT as u32,
+            ),
+        ];
+        let multisampled = key.msaa_samples > 1;
+        if key.soft {
+            shader_defs.push("SOFT_PARTICLES".into());
+            if multisampled {
+                shader_defs.push("MULTISAMPLED".into());
+            }
+        }
+        if key.textured {
+            shader_defs.push("PARTICLE_TEXTURE".into());
+        }
+
+        let format = if key.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("particle_pipeline".into()),
+            layout: Some(vec![
+                self.view_layout(key.soft, multisampled).clone(),
+                self.emitter_layout.clone(),
+            ]),
+            push_constant_ranges: Vec::new(),
+            vertex: VertexState {
+                shader: PARTICLES_SHADER_HANDLE.typed(),
+                shader_defs: shader_defs.clone(),
+                entry_point: "vertex".into(),
+                buffers: Vec::new(),
+            },
+            fragment: Some(FragmentState {
+                shader: PARTICLES_SHADER_HANDLE.typed(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            // The particles are tested against the depth of the opaque meshes, without writing it
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.msaa_samples,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The bind groups of the particles of an emitter.
+#[derive(Component)]
+pub struct ParticleBindGroups {
+    pub simulation: BindGroup,
+    /// The bind group the particles are drawn with, which is `None` until the texture of the
+    /// emitter is loaded.
+    pub emitter: Option<BindGroup>,
+}
+
+/// The bind group of the view uniform, and of the depth prepass of views that have one, for the
+/// particles.
+#[derive(Component)]
+pub struct ParticleViewBindGroup(pub BindGroup);
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_particle_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    particle_pipeline: Res<ParticlePipeline>,
+    gpu_emitters: Res<GpuParticleEmitters>,
+    view_uniforms: Res<ViewUniforms>,
+    images: Res<RenderAssets<Image>>,
+    fallback_image: Res<FallbackImage>,
+    msaa: Res<Msaa>,
+    emitters: Query<(Entity, &ExtractedParticleEmitter)>,
+    views: Query<(Entity, Option<&ViewPrepassTextures>), With<RenderPhase<Transparent3d>>>,
+) {
+    let (Some(uniforms), Some(view_binding)) = (
+        gpu_emitters.uniforms.binding(),
+        view_uniforms.uniforms.binding(),
+    ) else {
+        return;
+    };
+
+    for (entity, extracted) in &emitters {
+        let Some(gpu_emitter) = gpu_emitters.emitters.get(&entity) else {
+            continue;
+        };
+        // The particles are drawn once their texture is loaded
+        let image = match &extracted.emitter.texture {
+            Some(texture) => images.get(texture),
+            None => Some(&**fallback_image),
+        };
+        let particles = gpu_emitter.particles.as_entire_binding();
+        let simulation = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("particle_simulation_bind_group"),
+            layout: &particle_pipeline.simulation_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: particles.clone(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: uniforms.clone(),
+                },
+            ],
+        });
+        let emitter = image.map(|image| {
+            render_device.create_bind_group(&BindGroupDescriptor {
+                label: Some("particle_emitter_bind_group"),
+                layout: &particle_pipeline.emitter_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: particles,
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: uniforms.clone(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: BindingResource::TextureView(&image.texture_view),
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: BindingResource::Sampler(&image.sampler),
+                    },
+                ],
+            })
+        });
+        commands.entity(entity).insert(ParticleBindGroups {
+            simulation,
+            emitter,
+        });
+    }
+
+    for (entity, prepass_textures) in &views {
+        let depth = prepass_textures.and_then(|textures| textures.depth.as_ref());
+        let layout = particle_pipeline.view_layout(depth.is_some(), msaa.samples > 1);
+        let bind_group = match depth {
+            Some(depth) => render_device.create_bind_group(&BindGroupDescriptor {
+                label: Some("particle_view_depth_bind_group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: view_binding.clone(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&depth.default_view),
+                    },
+                ],
+            }),
+            None => render_device.create_bind_group(&BindGroupDescriptor {
+                label: Some("particle_view_bind_group"),
+                layout,
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: view_binding.clone(),
+                }],
+            }),
+        };
+        commands
+            .entity(entity)
+            .insert(ParticleViewBindGroup(bind_group));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_particle_emitters(
+    transparent_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    particle_pipeline: Res<ParticlePipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<ParticlePipeline>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    images: Res<RenderAssets<Image>>,
+    emitters: Query<&ExtractedParticleEmitter>,
+    mut views: Query<(
+        &ExtractedView,
+        &VisibleEntities,
+        Option<&ViewPrepassTextures>,
+        &mut RenderPhase<Transparent3d>,
+    )>,
+) {
+    let draw_particles = transparent_draw_functions.read().id::<DrawParticles>();
+
+    for (view, visible_entities, prepass_textures, mut transparent_phase) in &mut views {
+        let soft = prepass_textures.map_or(false, |textures| textures.depth.is_some());
+        let rangefinder = view.rangefinder3d();
+
+        for visible_entity in &visible_entities.entities {
+            let Ok(extracted) = emitters.get(*visible_entity) else {
+                continue;
+            };
+            let texture = extracted.emitter.texture.as_ref();
+            if !extracted.visible || texture.map_or(false, |texture| !images.contains_key(texture))
+            {
+                continue;
+            }
+            let pipeline = pipelines.specialize(
+                &mut pipeline_cache,
+                &particle_pipeline,
+                ParticlePipelineKey {
+                    hdr: view.hdr,
+                    msaa_samples: msaa.samples,
+                    soft,
+                    textured: extracted.emitter.texture.is_some(),
+                },
+            );
+            transparent_phase.add(Transparent3d {
+                distance: rangefinder.distance(&extracted.transform.compute_matrix()),
+                pipeline,
+                entity: *visible_entity,
+                draw_function: draw_particles,
+                batch_range: None,
+            });
+        }
+    }
+}
+
+/// Draws the particles of an emitter, as one billboard instance per particle.
+pub type DrawParticles = (
+    SetItemPipeline,
+    SetParticleViewBindGroup<0>,
+    SetParticleEmitterBindGroup<1>,
+    DrawParticleInstances,
+);
+
+pub struct SetParticleViewBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetParticleViewBindGroup<I> {
+    type Param = SQuery<(Read<ParticleViewBindGroup>, Read<ViewUniformOffset>)>;
+    #[inline]
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        view_query: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Ok((bind_group, view_uniform)) = view_query.get_inner(view) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, &bind_group.0, &[view_uniform.offset]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct SetParticleEmitterBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetParticleEmitterBindGroup<I> {
+    type Param = SQuery<(Read<ParticleBindGroups>, Read<ParticleEmitterUniformOffset>)>;
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        emitter_query: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Ok((bind_groups, uniform_offset)) = emitter_query.get_inner(item) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(bind_group) = &bind_groups.emitter else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, bind_group, &[uniform_offset.0]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawParticleInstances;
+impl EntityRenderCommand for DrawParticleInstances {
+    type Param = SRes<GpuParticleEmitters>;
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: Entity,
+        gpu_emitters: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(gpu_emitter) = gpu_emitters.into_inner().emitters.get(&item) else {
+            return RenderCommandResult::Failure;
+        };
+        // The vertices of each billboard are generated by the vertex shader, and the dead
+        // particles are moved out of the view
+        pass.draw(0..6, 0..gpu_emitter.capacity);
+        RenderCommandResult::Success
+    }
+}
+
+/// Spawns and moves the particles of every emitter, before the views draw them.
+pub struct ParticleSimulationNode {
+    query: QueryState<(
+        Entity,
+        &'static ParticleBindGroups,
+        &'static ParticleEmitterUniformOffset,
+    )>,
+}
+
+impl ParticleSimulationNode {
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for ParticleSimulationNode {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let gpu_emitters = world.resource::<GpuParticleEmitters>();
+        let Some(pipeline) = pipeline_cache
+            .get_compute_pipeline(world.resource::<ParticlePipeline>().simulation_pipeline)
+        else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _particle_simulation_span = info_span!("particle_simulation").entered();
+
+        let mut pass = TrackedComputePass::new(render_context.command_encoder.begin_compute_pass(
+            &ComputePassDescriptor {
+                label: Some("particle_simulation_pass"),
+            },
+        ));
+        pass.set_compute_pipeline(pipeline);
+        for (entity, bind_groups, uniform_offset) in self.query.iter_manual(world) {
+            let Some(gpu_emitter) = gpu_emitters.emitters.get(&entity) else {
+                continue;
+            };
+            pass.set_bind_group(0, &bind_groups.simulation, &[uniform_offset.0]);
+            pass.dispatch_workgroups(
+                (gpu_emitter.capacity + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                1,
+                1,
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+#import bevy_pbr::particle_types
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1)
+var<uniform> emitter: ParticleEmitter;
+
+// PCG hash, from https://www.jcgt.org/published/0009/03/02/
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random_float(state: ptr<function, u32>) -> f32 {
+    *state = hash(*state);
+    return f32(*state) / 4294967295.0;
+}
+
+// A uniformly distributed direction
+fn random_direction(state: ptr<function, u32>) -> vec3<f32> {
+    let z = random_float(state) * 2.0 - 1.0;
+    let angle = random_float(state) * 6.283185307179586;
+    let radius = sqrt(1.0 - z * z);
+    return vec3<f32>(radius * cos(angle), radius * sin(angle), z);
+}
+
+@compute @workgroup_size(64, 1, 1)
+fn simulate(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let index = invocation_id.x;
+    if (index >= emitter.capacity) {
+        return;
+    }
+
+    var particle = particles[index];
+    let spawn_offset = (index + emitter.capacity - emitter.spawn_start) % emitter.capacity;
+    if (spawn_offset < emitter.spawn_count) {
+        var state = hash(index ^ hash(emitter.seed));
+        // Uniformly distributed in the sphere
+        let offset = random_direction(&state) * pow(random_float(&state), 1.0 / 3.0)
+            * emitter.spawn_radius;
+        particle.position = (emitter.transform * vec4<f32>(offset, 1.0)).xyz;
+        particle.velocity = (emitter.transform * vec4<f32>(emitter.velocity, 0.0)).xyz
+            + random_direction(&state) * emitter.velocity_randomness;
+        particle.lifetime = emitter.lifetime;
+        // The particles spawned during the frame are spread over it
+        particle.age = f32(spawn_offset) / f32(emitter.spawn_count) * emitter.delta_seconds;
+    } else if (particle.age < particle.lifetime) {
+        particle.velocity += emitter.acceleration * emitter.delta_seconds;
+        particle.position += particle.velocity * emitter.delta_seconds;
+        particle.age += emitter.delta_seconds;
+    }
+    particles[index] = particle;
+}
@@ -0,0 +1,29 @@
+#define_import_path bevy_pbr::particle_types
+
+struct Particle {
+    position: vec3<f32>,
+    // The particle is dead once its age reaches its lifetime
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+};
+
+struct ParticleEmitter {
+    transform: mat4x4<f32>,
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    velocity: vec3<f32>,
+    velocity_randomness: f32,
+    acceleration: vec3<f32>,
+    spawn_radius: f32,
+    lifetime: f32,
+    start_size: f32,
+    end_size: f32,
+    soft_distance: f32,
+    capacity: u32,
+    // The particles from spawn_start, wrapping around the capacity, are replaced by new ones
+    spawn_start: u32,
+    spawn_count: u32,
+    seed: u32,
+    delta_seconds: f32,
+};
@@ -0,0 +1,96 @@
+#import bevy_pbr::mesh_view_types
+#import bevy_pbr::particle_types
+
+@group(0) @binding(0)
+var<uniform> view: View;
+#ifdef SOFT_PARTICLES
+#ifdef MULTISAMPLED
+@group(0) @binding(1)
+var depth_prepass_texture: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(1)
+var depth_prepass_texture: texture_2d<f32>;
+#endif
+#endif
+
+@group(1) @binding(0)
+var<storage> particles: array<Particle>;
+@group(1) @binding(1)
+var<uniform> emitter: ParticleEmitter;
+@group(1) @binding(2)
+var particle_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var particle_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vertex(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let particle = particles[instance_index];
+    if (particle.age >= particle.lifetime) {
+        // Outside of the clip space, so that the billboard isn't drawn
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+        return out;
+    }
+
+    // Two triangles, from the bottom left corner of the billboard
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+    let t = particle.age / particle.lifetime;
+    let size = mix(emitter.start_size, emitter.end_size, t);
+
+    // The billboards face the camera, with the right and up axes of the view
+    let offset = (corner - 0.5) * size;
+    let world_position = particle.position + view.view[0].xyz * offset.x + view.view[1].xyz * offset.y;
+    out.clip_position = view.view_proj * vec4<f32>(world_position, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    out.color = mix(emitter.start_color, emitter.end_color, t);
+    return out;
+}
+
+#ifdef SOFT_PARTICLES
+// The distance from the camera to the depth of a fragment, along the view direction
+fn view_distance(depth: f32) -> f32 {
+    let view_position = view.inverse_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    return -view_position.z / view_position.w;
+}
+#endif
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = in.color;
+#ifdef PARTICLE_TEXTURE
+    color = color * textureSample(particle_texture, particle_sampler, in.uv);
+#else
+    // A disc fading out towards its edge
+    let distance = length(in.uv * 2.0 - 1.0);
+    color.a = color.a * (1.0 - smoothstep(0.5, 1.0, distance));
+#endif
+
+#ifdef SOFT_PARTICLES
+    let pixel = vec2<i32>(in.clip_position.xy);
+    let scene_depth = textureLoad(depth_prepass_texture, pixel, 0).r;
+    // Nothing was drawn behind the particle, which is at the far plane with a reversed depth
+    if (scene_depth > 0.0 && emitter.soft_distance > 0.0) {
+        let distance_in_front = view_distance(scene_depth) - view_distance(in.clip_position.z);
+        color.a = color.a * clamp(distance_in_front / emitter.soft_distance, 0.0, 1.0);
+    }
+#endif
+
+    return color;
+}
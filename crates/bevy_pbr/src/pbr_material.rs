@@ -1,11 +1,21 @@
-use crate::{AlphaMode, Material, MaterialPipeline, MaterialPipelineKey, PBR_SHADER_HANDLE};
-use bevy_asset::Handle;
-use bevy_math::Vec4;
+use crate::{
+    AlphaMode, Material, MaterialPipeline, MaterialPipelineKey, MeshPipelineKey, PBR_SHADER_HANDLE,
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::system::{Query, Res, ResMut};
+use bevy_math::{Affine2, Mat3, Vec3, Vec4};
 use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect, TypeUuid};
 use bevy_render::{
-    color::Color, mesh::MeshVertexBufferLayout, render_asset::RenderAssets, render_resource::*,
-    texture::Image,
+    camera::Camera,
+    color::Color,
+    mesh::MeshVertexBufferLayout,
+    primitives::WorldBounds,
+    render_asset::RenderAssets,
+    render_resource::*,
+    texture::{Image, TextureStreamingRequests, TextureStreamingSettings},
+    view::ComputedVisibility,
 };
+use bevy_transform::components::GlobalTransform;
 
 /// A material with "standard" properties used in PBR lighting
 /// Standard property values with pictures here
@@ -24,6 +34,9 @@ pub struct StandardMaterial {
     /// in between. If used together with a `base_color_texture`, this is factored into the final
     /// base color as `base_color * base_color_texture_value`
     ///
+    /// Meshes with a [`Mesh::ATTRIBUTE_COLOR`](bevy_render::mesh::Mesh::ATTRIBUTE_COLOR) also
+    /// multiply it by the color of their vertices, like vertex painted assets expect.
+    ///
     /// Defaults to [`Color::WHITE`].
     pub base_color: Color,
 
@@ -126,9 +139,39 @@ pub struct StandardMaterial {
     /// when `reflectance` is set to `1.0`.
     ///
     /// Defaults to `0.5` which is mapped to 4% reflectance in the shader.
+    ///
+    /// The reflectance of a dielectric with an index of refraction `ior` is
+    /// `(ior - 1.0) / (ior + 1.0) / 0.4`, which is `0.5` for the common `ior` of `1.5`.
     #[doc(alias = "specular_intensity")]
     pub reflectance: f32,
 
+    /// The strength of a clear and glossy coat over the material, like the varnish of car paint,
+    /// from `0.0` for none to `1.0`.
+    ///
+    /// The coat reflects 4% of the light at normal incidence, more at grazing angles, and dims
+    /// the light reflected by the material below it by as much.
+    ///
+    /// Defaults to `0.0`.
+    pub clearcoat: f32,
+
+    /// Linear perceptual roughness of the [`clearcoat`](StandardMaterial::clearcoat), clamped
+    /// like [`perceptual_roughness`](StandardMaterial::perceptual_roughness).
+    ///
+    /// Defaults to `0.5`.
+    pub clearcoat_perceptual_roughness: f32,
+
+    /// The fraction of the light that passes through the material instead of being diffused by
+    /// it, from `0.0` for none to `1.0`, like clear glass.
+    ///
+    /// The material is considered infinitely thin, so what is behind it is seen without
+    /// refraction. It is only seen with [`AlphaMode::Premultiplied`] or [`AlphaMode::Blend`], as
+    /// the transmitted fraction reduces the alpha of the color output by the material. Prefer
+    /// [`AlphaMode::Premultiplied`], with which the specular highlights aren't dimmed by it.
+    ///
+    /// Defaults to `0.0`.
+    #[doc(alias = "specular_transmission")]
+    pub transmission: f32,
+
     /// Used to fake the lighting of bumps and dents on a material.
     ///
     /// A typical usage would be faking cobblestones on a flat plane mesh in 3D.
@@ -169,6 +212,28 @@ pub struct StandardMaterial {
     #[sampler(8)]
     pub occlusion_texture: Option<Handle<Image>>,
 
+    /// The indirect light reaching the surface, baked ahead of time by a light baker, in linear
+    /// color.
+    ///
+    /// It is sampled with the second UV set of the mesh,
+    /// [`Mesh::ATTRIBUTE_UV_1`](bevy_render::mesh::Mesh::ATTRIBUTE_UV_1), which usually unwraps
+    /// the mesh without overlaps unlike the UVs of the other textures, and is added to the
+    /// ambient light diffused by the material. The [`StandardMaterial::uv_transform`] doesn't
+    /// apply to it.
+    ///
+    /// Baked lighting replaces the ambient light and the lights that don't move at a fraction of
+    /// their cost, so the baked lights can be removed from the scene, or kept for their specular
+    /// highlights with their shadows disabled.
+    #[texture(11)]
+    #[sampler(12)]
+    pub lightmap_texture: Option<Handle<Image>>,
+
+    /// Scales the light of the [`StandardMaterial::lightmap_texture`], for lightmaps baked with a
+    /// different exposure than the lights of the scene.
+    ///
+    /// Defaults to `1.0`.
+    pub lightmap_intensity: f32,
+
     /// Support two-sided lighting by automatically flipping the normals for "back" faces
     /// within the PBR lighting shader.
     ///
@@ -204,6 +269,12 @@ pub struct StandardMaterial {
     /// shadows, alpha mode and ambient light are ignored if this is set to `true`.
     pub unlit: bool,
 
+    /// Whether the [`FogSettings`](crate::FogSettings) of the camera are applied to this material.
+    ///
+    /// Defaults to `true`. Set it to `false` for meshes that should stay visible through the fog,
+    /// like distant landmarks or a skybox drawn as a mesh.
+    pub fog_enabled: bool,
+
     /// How to apply the alpha channel of the `base_color_texture`.
     ///
     /// See [`AlphaMode`] for details. Defaults to [`AlphaMode::Opaque`].
@@ -219,12 +290,33 @@ pub struct StandardMaterial {
     /// `depth_bias` will only have any effect if two materials are overlapping,
     /// which only serves as a [z-fighting] resolver.
     ///
-    /// `depth_bias` can however reorder [`AlphaMode::Blend`] materials.
+    /// `depth_bias` can however reorder [`AlphaMode::Blend`], [`AlphaMode::Premultiplied`] and
+    /// [`AlphaMode::Add`] materials.
     /// This is useful if your transparent materials are not rendering
     /// in the expected order.
     ///
     /// [z-fighting]: https://en.wikipedia.org/wiki/Z-fighting
     pub depth_bias: f32,
+
+    /// The transform applied to the UVs of the mesh before sampling any of the textures of the
+    /// material.
+    ///
+    /// Scaling tiles the textures, which then need a repeating
+    /// [`address_mode`](bevy_render::render_resource::SamplerDescriptor::address_mode_u) in
+    /// their sampler, and translating offsets them, for example to pick a region of a trim
+    /// sheet, without changing the UVs of the mesh:
+    ///
+    /// ```
+    /// # use bevy_math::{Affine2, Vec2};
+    /// # use bevy_pbr::StandardMaterial;
+    /// let tiled = StandardMaterial {
+    ///     uv_transform: Affine2::from_scale(Vec2::new(4.0, 4.0)),
+    ///     ..Default::default()
+    /// };
+    /// ```
+    ///
+    /// Defaults to [`Affine2::IDENTITY`].
+    pub uv_transform: Affine2,
 }
 
 impl Default for StandardMaterial {
@@ -248,14 +340,21 @@ impl Default for StandardMaterial {
             // Expressed in a linear scale and equivalent to 4% reflectance see
             // <https://google.github.io/filament/Material%20Properties.pdf>
             reflectance: 0.5,
+            clearcoat: 0.0,
+            clearcoat_perceptual_roughness: 0.5,
+            transmission: 0.0,
             occlusion_texture: None,
+            lightmap_texture: None,
+            lightmap_intensity: 1.0,
             normal_map_texture: None,
             flip_normal_map_y: false,
             double_sided: false,
             cull_mode: Some(Face::Back),
             unlit: false,
+            fog_enabled: true,
             alpha_mode: AlphaMode::Opaque,
             depth_bias: 0.0,
+            uv_transform: Affine2::IDENTITY,
         }
     }
 }
@@ -283,6 +382,77 @@ impl From<Handle<Image>> for StandardMaterial {
     }
 }
 
+/// Requests the textures of the [`StandardMaterial`]s of visible meshes to be streamed at the size
+/// the meshes are drawn at by the cameras, when [`TextureStreamingSettings::enabled`] is set.
+///
+/// The size of a mesh is the diameter of its bounding sphere on screen, which assumes its UVs
+/// cover the textures once, scaled by the [`StandardMaterial::uv_transform`].
+pub fn request_standard_material_textures(
+    settings: Res<TextureStreamingSettings>,
+    mut requests: ResMut<TextureStreamingRequests>,
+    materials: Res<Assets<StandardMaterial>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    meshes: Query<(&Handle<StandardMaterial>, &ComputedVisibility, &WorldBounds)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let views: Vec<_> = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .filter_map(|(camera, transform)| {
+            let projection = camera.projection_matrix();
+            Some((
+                projection * transform.compute_matrix().inverse(),
+                // The scale from a size in view space at a depth of 1 to a size in pixels
+                projection.y_axis.y * camera.physical_viewport_size()?.y as f32 / 2.0,
+            ))
+        })
+        .collect();
+
+    for (handle, visibility, bounds) in &meshes {
+        if !visibility.is_visible() {
+            continue;
+        }
+        let Some(material) = materials.get(handle) else {
+            continue;
+        };
+        let center = Vec3::from(bounds.sphere.center).extend(1.0);
+        let screen_size = views
+            .iter()
+            .map(|(view_projection, scale)| {
+                // The depth of the center for perspective projections, and 1 for orthographic ones
+                let depth = (*view_projection * center).w.max(0.001);
+                2.0 * bounds.sphere.radius * scale / depth
+            })
+            .fold(0.0, f32::max);
+
+        let uv_scale = material
+            .uv_transform
+            .matrix2
+            .x_axis
+            .length()
+            .max(material.uv_transform.matrix2.y_axis.length());
+        for texture in [
+            &material.base_color_texture,
+            &material.emissive_texture,
+            &material.metallic_roughness_texture,
+            &material.normal_map_texture,
+            &material.occlusion_texture,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            requests.request(texture, screen_size * uv_scale);
+        }
+        // Lightmaps are sampled with the second UVs, which aren't transformed
+        if let Some(lightmap) = &material.lightmap_texture {
+            requests.request(lightmap, screen_size);
+        }
+    }
+}
+
 // NOTE: These must match the bit flags in bevy_pbr/src/render/pbr_types.wgsl!
 bitflags::bitflags! {
     /// Bitflags info about the material a shader is currently rendering.
@@ -300,6 +470,10 @@ bitflags::bitflags! {
         const ALPHA_MODE_BLEND           = (1 << 8);
         const TWO_COMPONENT_NORMAL_MAP   = (1 << 9);
         const FLIP_NORMAL_MAP_Y          = (1 << 10);
+        const ALPHA_MODE_PREMULTIPLIED   = (1 << 11);
+        const ALPHA_MODE_ADD             = (1 << 12);
+        const FOG_ENABLED                = (1 << 13);
+        const LIGHTMAP_TEXTURE           = (1 << 14);
         const NONE                       = 0;
         const UNINITIALIZED              = 0xFFFF;
     }
@@ -307,6 +481,7 @@ bitflags::bitflags! {
 
 /// The GPU representation of the uniform data of a [`StandardMaterial`].
 #[derive(Clone, Default, ShaderType)]
+#[shader_type(uniform)]
 pub struct StandardMaterialUniform {
     /// Doubles as diffuse albedo for non-metallic, specular for metallic and a mix for everything
     /// in between.
@@ -327,6 +502,16 @@ pub struct StandardMaterialUniform {
     /// When the alpha mode mask flag is set, any base color alpha above this cutoff means fully opaque,
     /// and any below means fully transparent.
     pub alpha_cutoff: f32,
+    /// The [`StandardMaterial::uv_transform`], applied to UVs as homogeneous 2D coordinates.
+    pub uv_transform: Mat3,
+    /// From [0.0, 1.0], the strength of the clear coat
+    pub clearcoat: f32,
+    /// Linear perceptual roughness of the clear coat, clamped like `roughness`
+    pub clearcoat_roughness: f32,
+    /// From [0.0, 1.0], the fraction of the light passing through the material
+    pub transmission: f32,
+    /// The scale of the light of the lightmap
+    pub lightmap_intensity: f32,
 }
 
 impl AsBindGroupShaderType<StandardMaterialUniform> for StandardMaterial {
@@ -344,12 +529,18 @@ impl AsBindGroupShaderType<StandardMaterialUniform> for StandardMaterial {
         if self.occlusion_texture.is_some() {
             flags |= StandardMaterialFlags::OCCLUSION_TEXTURE;
         }
+        if self.lightmap_texture.is_some() {
+            flags |= StandardMaterialFlags::LIGHTMAP_TEXTURE;
+        }
         if self.double_sided {
             flags |= StandardMaterialFlags::DOUBLE_SIDED;
         }
         if self.unlit {
             flags |= StandardMaterialFlags::UNLIT;
         }
+        if self.fog_enabled {
+            flags |= StandardMaterialFlags::FOG_ENABLED;
+        }
         let has_normal_map = self.normal_map_texture.is_some();
         if has_normal_map {
             if let Some(texture) = images.get(self.normal_map_texture.as_ref().unwrap()) {
@@ -377,6 +568,8 @@ impl AsBindGroupShaderType<StandardMaterialUniform> for StandardMaterial {
                 flags |= StandardMaterialFlags::ALPHA_MODE_MASK;
             }
             AlphaMode::Blend => flags |= StandardMaterialFlags::ALPHA_MODE_BLEND,
+            AlphaMode::Premultiplied => flags |= StandardMaterialFlags::ALPHA_MODE_PREMULTIPLIED,
+            AlphaMode::Add => flags |= StandardMaterialFlags::ALPHA_MODE_ADD,
         };
 
         StandardMaterialUniform {
@@ -387,6 +580,11 @@ impl AsBindGroupShaderType<StandardMaterialUniform> for StandardMaterial {
             reflectance: self.reflectance,
             flags: flags.bits(),
             alpha_cutoff,
+            uv_transform: self.uv_transform.into(),
+            clearcoat: self.clearcoat,
+            clearcoat_roughness: self.clearcoat_perceptual_roughness,
+            transmission: self.transmission,
+            lightmap_intensity: self.lightmap_intensity,
         }
     }
 }
@@ -394,6 +592,9 @@ impl AsBindGroupShaderType<StandardMaterialUniform> for StandardMaterial {
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct StandardMaterialKey {
     normal_map: bool,
+    emissive_texture: bool,
+    occlusion_texture: bool,
+    lightmap_texture: bool,
     cull_mode: Option<Face>,
 }
 
@@ -401,6 +602,9 @@ impl From<&StandardMaterial> for StandardMaterialKey {
     fn from(material: &StandardMaterial) -> Self {
         StandardMaterialKey {
             normal_map: material.normal_map_texture.is_some(),
+            emissive_texture: material.emissive_texture.is_some(),
+            occlusion_texture: material.occlusion_texture.is_some(),
+            lightmap_texture: material.lightmap_texture.is_some(),
             cull_mode: material.cull_mode,
         }
     }
@@ -413,13 +617,30 @@ impl Material for StandardMaterial {
         _layout: &MeshVertexBufferLayout,
         key: MaterialPipelineKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
+        // The UVs are transformed once per vertex rather than once per fragment
+        descriptor
+            .vertex
+            .shader_defs
+            .push("STANDARDMATERIAL_UV_TRANSFORM".into());
+        // Textures that aren't set are neither sampled nor branched on in the shader
+        let shader_defs = &mut descriptor.fragment.as_mut().unwrap().shader_defs;
         if key.bind_group_data.normal_map {
-            descriptor
-                .fragment
-                .as_mut()
-                .unwrap()
-                .shader_defs
-                .push("STANDARDMATERIAL_NORMAL_MAP".into());
+            shader_defs.push("STANDARDMATERIAL_NORMAL_MAP".into());
+        }
+        if key.bind_group_data.emissive_texture {
+            shader_defs.push("STANDARDMATERIAL_EMISSIVE_TEXTURE".into());
+        }
+        if key.bind_group_data.occlusion_texture {
+            shader_defs.push("STANDARDMATERIAL_OCCLUSION_TEXTURE".into());
+        }
+        if key.bind_group_data.lightmap_texture {
+            shader_defs.push("STANDARDMATERIAL_LIGHTMAP_TEXTURE".into());
+        }
+        if key
+            .mesh_key
+            .contains(MeshPipelineKey::PICKING_PASS | MeshPipelineKey::ALPHA_MASK)
+        {
+            shader_defs.push("STANDARDMATERIAL_ALPHA_MASK".into());
         }
         descriptor.primitive.cull_mode = key.bind_group_data.cull_mode;
         if let Some(label) = &mut descriptor.label {
@@ -0,0 +1,887 @@
+//! Pixel-accurate picking of the meshes a 3D camera sees, by rendering the entity of each mesh
+//! into an integer texture and reading back the pixels under the cursor.
+//!
+//! Add a [`PickingPass`] to a [`Camera3d`] to render its picking texture, then call
+//! [`GpuPicking::pick`] to find which entity it draws at a position of its viewport:
+//!
+//! ```
+//! # use bevy_ecs::prelude::*;
+//! # use bevy_math::Vec2;
+//! # use bevy_pbr::{GpuPicking, Pick};
+//! fn pick_center(
+//!     camera: Query<Entity, With<bevy_pbr::PickingPass>>,
+//!     mut picking: ResMut<GpuPicking>,
+//!     mut pending: Local<Option<Pick>>,
+//! ) {
+//!     if let Some(entity) = pending.as_ref().and_then(|pick| pick.try_get()) {
+//!         println!("{entity:?} is at the center of the screen");
+//!         *pending = None;
+//!     }
+//!     if pending.is_none() {
+//!         *pending = Some(picking.pick(camera.single(), Vec2::new(640.0, 360.0)));
+//!     }
+//! }
+//! # bevy_ecs::system::assert_is_system(pick_center);
+//! ```
+//!
+//! The picking pass draws the meshes with their own transforms, skins and morph targets, and
+//! leaves out the fragments [`StandardMaterial`](crate::StandardMaterial)s with
+//! [`AlphaMode::Mask`] discard. Meshes with any other [`AlphaMode`] are picked wherever they are
+//! drawn, and the meshes of materials with a custom [`vertex_shader`](Material::vertex_shader)
+//! aren't drawn into it, like in the prepass.
+
+use std::{
+    cmp::Reverse,
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    draw_3d_graph, is_skinned, AlphaMode, DrawMesh, Material, MaterialPipeline,
+    MaterialPipelineKey, MeshPipeline, MeshPipelineKey, MeshUniform, RenderMaterials,
+    SetMaterialBindGroup, SetMeshBindGroup, MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS,
+};
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_core_pipeline::core_3d::{self, Camera3d};
+use bevy_ecs::{
+    prelude::*,
+    system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+};
+use bevy_math::{UVec2, Vec2};
+use bevy_reflect::{Reflect, TypeUuid};
+use bevy_render::{
+    camera::{Camera, ExtractedCamera},
+    color::Color,
+    mesh::{Mesh, MeshVertexBufferLayout},
+    render_asset::RenderAssets,
+    render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+    render_phase::{
+        AddRenderCommand, AddRenderPhase, CachedRenderPipelinePhaseItem, DrawFunctionId,
+        DrawFunctions, EntityPhaseItem, EntityRenderCommand, PhaseItem, RenderCommandResult,
+        RenderPhase, SetItemPipeline, TrackedRenderPass,
+    },
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::{CachedTexture, TextureCache, TextureFormatPixelInfo},
+    view::{ExtractedView, ViewUniform, ViewUniformOffset, ViewUniforms, VisibleEntities},
+    Extract, RenderApp, RenderStage,
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+use bevy_utils::{tracing::error, FloatOrd};
+
+pub const PICKING_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5803983836641958872);
+
+/// The format of the texture the [`PickingPass`] writes the entities into, as their index and
+/// generation.
+pub const PICKING_FORMAT: TextureFormat = TextureFormat::Rg32Uint;
+/// The format of the depth texture of the [`PickingPass`].
+pub const PICKING_DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// The value of the pixels of the picking texture no mesh was drawn on.
+const NO_ENTITY: u32 = u32::MAX;
+
+/// Adds support for the [`PickingPass`] of the 3D cameras and [`GpuPicking`].
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PICKING_SHADER_HANDLE,
+            "picking.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<PickingPass>()
+            .init_resource::<GpuPicking>()
+            .add_system_to_stage(CoreStage::First, clear_picking_requests);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .add_render_phase::<Picking3d>()
+            .init_resource::<PickingViewLayout>()
+            .init_resource::<PickingViewBindGroup>()
+            .init_resource::<ExtractedPickingRequests>()
+            .init_resource::<PendingPicks>()
+            .add_system_to_stage(RenderStage::Extract, extract_picking_cameras)
+            .add_system_to_stage(RenderStage::Extract, extract_picking_requests)
+            .add_system_to_stage(RenderStage::Prepare, prepare_picking_textures)
+            .add_system_to_stage(RenderStage::Queue, queue_picking_view_bind_group)
+            .add_system_to_stage(RenderStage::Cleanup, read_back_picks);
+
+        let picking_node = PickingNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        let draw_3d_graph = graph.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+        draw_3d_graph.add_node(draw_3d_graph::node::PICKING_PASS, picking_node);
+        draw_3d_graph.add_slot_edge(
+            draw_3d_graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            draw_3d_graph::node::PICKING_PASS,
+            PickingNode::IN_VIEW,
+        );
+    }
+}
+
+/// Draws the meshes using the [`Material`] `M` into the [`PickingPass`] of the cameras that have
+/// one.
+///
+/// It is added by the [`MaterialPlugin`](crate::MaterialPlugin) of `M`.
+pub struct PickingMaterialPlugin<M: Material>(PhantomData<M>);
+
+impl<M: Material> Default for PickingMaterialPlugin<M> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<M: Material> Plugin for PickingMaterialPlugin<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_render_command::<Picking3d, DrawPicking<M>>()
+                .init_resource::<PickingPipeline<M>>()
+                .init_resource::<SpecializedMeshPipelines<PickingPipeline<M>>>()
+                .add_system_to_stage(RenderStage::Queue, queue_picking_material_meshes::<M>);
+        }
+    }
+}
+
+/// Add this component to a [`Camera3d`] to render which entity is drawn at each pixel, for
+/// [`GpuPicking`] to read back.
+///
+/// The entities are written as their index and generation into a [`PICKING_FORMAT`] texture, stored
+/// in the [`ViewPickingTextures`] of the view in the render world. The pass isn't multisampled.
+#[derive(Component, Reflect, Clone, Copy, Default)]
+#[reflect(Component)]
+pub struct PickingPass;
+
+/// Finds the entities drawn by the [`PickingPass`] of cameras at positions of their viewport.
+///
+/// The pixels are read back from the GPU once the frame the pick was requested in has been
+/// rendered, so the result is available a frame or two later through the returned [`Pick`].
+#[derive(Resource, Default)]
+pub struct GpuPicking {
+    requests: Vec<PickingRequest>,
+}
+
+impl GpuPicking {
+    /// Finds the entity the [`PickingPass`] of `camera` draws at `viewport_position`, in logical
+    /// pixels from the bottom left of its viewport like
+    /// [`Window::cursor_position`](bevy_window::Window::cursor_position).
+    ///
+    /// The [`Pick`] resolves to `None` if no mesh is drawn there, if the position is outside the
+    /// viewport, or if the camera doesn't have an active [`PickingPass`]. The entity it resolves
+    /// to may have been despawned in the frames it took to read it back.
+    pub fn pick(&mut self, camera: Entity, viewport_position: Vec2) -> Pick {
+        let result = Arc::<Mutex<PickResult>>::default();
+        self.requests.push(PickingRequest {
+            camera,
+            viewport_position,
+            result: result.clone(),
+        });
+        Pick(result)
+    }
+}
+
+/// The entity found by [`GpuPicking::pick`], available once it has been read back from the GPU.
+///
+/// It can be awaited, or checked every frame with [`Pick::try_get`].
+pub struct Pick(Arc<Mutex<PickResult>>);
+
+impl Pick {
+    /// Returns `None` while the pick is being read back, then the entity that was found, if any.
+    pub fn try_get(&self) -> Option<Option<Entity>> {
+        self.0.lock().unwrap().entity
+    }
+}
+
+impl Future for Pick {
+    type Output = Option<Entity>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut result = self.0.lock().unwrap();
+        match result.entity {
+            Some(entity) => Poll::Ready(entity),
+            None => {
+                result.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct PickResult {
+    entity: Option<Option<Entity>>,
+    waker: Option<Waker>,
+}
+
+fn resolve_pick(result: &Mutex<PickResult>, entity: Option<Entity>) {
+    let mut result = result.lock().unwrap();
+    result.entity = Some(entity);
+    if let Some(waker) = result.waker.take() {
+        waker.wake();
+    }
+}
+
+#[derive(Clone)]
+struct PickingRequest {
+    camera: Entity,
+    viewport_position: Vec2,
+    result: Arc<Mutex<PickResult>>,
+}
+
+/// Clears the requests of the previous frame, which have been extracted to the render world.
+pub fn clear_picking_requests(mut picking: ResMut<GpuPicking>) {
+    picking.requests.clear();
+}
+
+/// Picking requests along with the pixel of the picking texture of their camera to read back.
+#[derive(Resource, Default)]
+struct ExtractedPickingRequests {
+    requests: Vec<(Entity, UVec2, Arc<Mutex<PickResult>>)>,
+}
+
+pub fn extract_picking_cameras(
+    mut commands: Commands,
+    cameras_3d: Extract<Query<(Entity, &Camera), (With<Camera3d>, With<PickingPass>)>>,
+) {
+    for (entity, camera) in &cameras_3d {
+        if camera.is_active {
+            commands
+                .get_or_spawn(entity)
+                .insert((PickingPass, RenderPhase::<Picking3d>::default()));
+        }
+    }
+}
+
+fn extract_picking_requests(
+    mut commands: Commands,
+    picking: Extract<Res<GpuPicking>>,
+    cameras: Extract<Query<&Camera>>,
+) {
+    let mut requests = Vec::new();
+    for request in &picking.requests {
+        let pixel = cameras.get(request.camera).ok().and_then(|camera| {
+            let (min, max) = camera.physical_viewport_rect()?;
+            let logical_size = camera.logical_viewport_size()?;
+            let physical_size = (max - min).as_vec2();
+            // Textures have their origin at the top left, viewport positions at the bottom left
+            let position = Vec2::new(
+                request.viewport_position.x,
+                logical_size.y - request.viewport_position.y,
+            ) * physical_size
+                / logical_size;
+            let inside = position.cmpge(Vec2::ZERO).all() && position.cmplt(physical_size).all();
+            inside.then(|| min + position.as_uvec2())
+        });
+        match pixel {
+            Some(pixel) => requests.push((request.camera, pixel, request.result.clone())),
+            None => resolve_pick(&request.result, None),
+        }
+    }
+    commands.insert_resource(ExtractedPickingRequests { requests });
+}
+
+/// The textures written by the [`PickingPass`] of a view.
+///
+/// They have the size of the render target of the view.
+#[derive(Component)]
+pub struct ViewPickingTextures {
+    /// The index and generation of the entity drawn at each pixel, or [`u32::MAX`] where no mesh
+    /// was drawn, with a [`PICKING_FORMAT`] format.
+    pub entities: CachedTexture,
+    /// The depth of the meshes, with a [`PICKING_DEPTH_FORMAT`] format.
+    pub depth: CachedTexture,
+}
+
+pub fn prepare_picking_textures(
+    mut commands: Commands,
+    texture_cache: Res<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views_3d: Query<(Entity, &ExtractedCamera), With<RenderPhase<Picking3d>>>,
+) {
+    for (entity, camera) in &views_3d {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+        let texture = |label, format, usage| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        depth_or_array_layers: 1,
+                        width: physical_target_size.x,
+                        height: physical_target_size.y,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: usage | TextureUsages::RENDER_ATTACHMENT,
+                },
+            )
+        };
+        commands.entity(entity).insert(ViewPickingTextures {
+            entities: texture(
+                "picking_entities_texture",
+                PICKING_FORMAT,
+                TextureUsages::COPY_SRC | TextureUsages::TEXTURE_BINDING,
+            ),
+            depth: texture(
+                "picking_depth_texture",
+                PICKING_DEPTH_FORMAT,
+                TextureUsages::empty(),
+            ),
+        });
+    }
+}
+
+/// The layout of the view bind group of the [`PickingPipeline`]s, which only binds the
+/// [`ViewUniform`].
+#[derive(Resource)]
+pub struct PickingViewLayout(pub BindGroupLayout);
+
+impl FromWorld for PickingViewLayout {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        Self(
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(ViewUniform::min_size()),
+                    },
+                    count: None,
+                }],
+                label: Some("picking_view_layout"),
+            }),
+        )
+    }
+}
+
+/// The bind group of the view uniforms, for the [`PickingPipeline`]s.
+#[derive(Resource, Default)]
+pub struct PickingViewBindGroup(pub Option<BindGroup>);
+
+pub fn queue_picking_view_bind_group(
+    render_device: Res<RenderDevice>,
+    picking_view_layout: Res<PickingViewLayout>,
+    view_uniforms: Res<ViewUniforms>,
+    mut picking_view_bind_group: ResMut<PickingViewBindGroup>,
+) {
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    picking_view_bind_group.0 = Some(render_device.create_bind_group(&BindGroupDescriptor {
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: view_binding,
+        }],
+        label: Some("picking_view_bind_group"),
+        layout: &picking_view_layout.0,
+    }));
+}
+
+/// Render pipeline data of the picking pass of a given [`Material`].
+#[derive(Resource)]
+pub struct PickingPipeline<M: Material> {
+    pub view_layout: BindGroupLayout,
+    pub mesh_layout: BindGroupLayout,
+    pub skinned_mesh_layout: BindGroupLayout,
+    pub morphed_mesh_layout: BindGroupLayout,
+    pub morphed_skinned_mesh_layout: BindGroupLayout,
+    /// The pipeline of the main passes, which is given to [`Material::specialize`].
+    pub material_pipeline: MaterialPipeline<M>,
+}
+
+impl<M: Material> FromWorld for PickingPipeline<M> {
+    fn from_world(world: &mut World) -> Self {
+        let mesh_pipeline = world.resource::<MeshPipeline>();
+
+        PickingPipeline {
+            view_layout: world.resource::<PickingViewLayout>().0.clone(),
+            mesh_layout: mesh_pipeline.mesh_layout.clone(),
+            skinned_mesh_layout: mesh_pipeline.skinned_mesh_layout.clone(),
+            morphed_mesh_layout: mesh_pipeline.morphed_mesh_layout.clone(),
+            morphed_skinned_mesh_layout: mesh_pipeline.morphed_skinned_mesh_layout.clone(),
+            material_pipeline: world.resource::<MaterialPipeline<M>>().clone(),
+        }
+    }
+}
+
+impl<M: Material> SpecializedMeshPipeline for PickingPipeline<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    type Key = MaterialPipelineKey<M>;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt(
+                "MAX_DIRECTIONAL_LIGHTS".to_string(),
+                MAX_DIRECTIONAL_LIGHTS as u32,
+            ),
+            ShaderDefVal::UInt(
+                "MAX_CASCADES_PER_LIGHT".to_string(),
+                MAX_CASCADES_PER_LIGHT as u32,
+            ),
+        ];
+        let mut vertex_attributes = vec![Mesh::ATTRIBUTE_POSITION.at_shader_location(0)];
+
+        // Only masked materials sample their textures
+        if key.mesh_key.contains(MeshPipelineKey::ALPHA_MASK)
+            && layout.contains(Mesh::ATTRIBUTE_UV_0)
+        {
+            shader_defs.push("VERTEX_UVS".into());
+            vertex_attributes.push(Mesh::ATTRIBUTE_UV_0.at_shader_location(2));
+        }
+
+        let mut bind_group_layout = vec![
+            self.view_layout.clone(),
+            self.material_pipeline.material_layout.clone(),
+        ];
+        let skinned = is_skinned(layout);
+        if skinned {
+            shader_defs.push("SKINNED".into());
+            vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_INDEX.at_shader_location(5));
+            vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_WEIGHT.at_shader_location(6));
+        }
+        let morphed = key.mesh_key.contains(MeshPipelineKey::MORPH_TARGETS);
+        if morphed {
+            shader_defs.push("MORPH_TARGETS".into());
+        }
+        bind_group_layout.push(match (skinned, morphed) {
+            (false, false) => self.mesh_layout.clone(),
+            (true, false) => self.skinned_mesh_layout.clone(),
+            (false, true) => self.morphed_mesh_layout.clone(),
+            (true, true) => self.morphed_skinned_mesh_layout.clone(),
+        });
+
+        let mut descriptor = RenderPipelineDescriptor {
+            vertex: VertexState {
+                shader: PICKING_SHADER_HANDLE.typed::<Shader>(),
+                entry_point: "vertex".into(),
+                shader_defs: shader_defs.clone(),
+                buffers: vec![layout.get_layout(&vertex_attributes)?],
+            },
+            fragment: Some(FragmentState {
+                shader: PICKING_SHADER_HANDLE.typed::<Shader>(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: PICKING_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            layout: Some(bind_group_layout),
+            push_constant_ranges: Vec::new(),
+            primitive: PrimitiveState {
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+                topology: key.mesh_key.primitive_topology(),
+                strip_index_format: None,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: PICKING_DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState {
+                    front: StencilFaceState::IGNORE,
+                    back: StencilFaceState::IGNORE,
+                    read_mask: 0,
+                    write_mask: 0,
+                },
+                bias: DepthBiasState {
+                    constant: 0,
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: MultisampleState::default(),
+            label: Some("picking_pipeline".into()),
+        };
+
+        // The material can change the culling of its meshes, and tell the shader how to mask them
+        M::specialize(&self.material_pipeline, &mut descriptor, layout, key)?;
+        Ok(descriptor)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_picking_material_meshes<M: Material>(
+    picking_draw_functions: Res<DrawFunctions<Picking3d>>,
+    picking_pipeline: Res<PickingPipeline<M>>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<PickingPipeline<M>>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    render_meshes: Res<RenderAssets<Mesh>>,
+    render_materials: Res<RenderMaterials<M>>,
+    material_meshes: Query<(&Handle<M>, &Handle<Mesh>, &MeshUniform)>,
+    mut views: Query<(
+        &ExtractedView,
+        &VisibleEntities,
+        &mut RenderPhase<Picking3d>,
+    )>,
+) where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    // Custom vertex shaders can move the vertices away from where the picking pass draws them
+    if picking_pipeline.material_pipeline.vertex_shader.is_some() {
+        return;
+    }
+    let draw_picking = picking_draw_functions.read().id::<DrawPicking<M>>();
+
+    for (view, visible_entities, mut picking_phase) in &mut views {
+        let rangefinder = view.rangefinder3d();
+
+        for visible_entity in &visible_entities.entities {
+            let Ok((material_handle, mesh_handle, mesh_uniform)) =
+                material_meshes.get(*visible_entity)
+            else {
+                continue;
+            };
+            // The previous level of a mesh cross-fading between levels of detail would be
+            // picked instead of the entity of the mesh
+            if mesh_uniform.lod_fade < 0.0 {
+                continue;
+            }
+            let (Some(material), Some(mesh)) = (
+                render_materials.get(material_handle),
+                render_meshes.get(mesh_handle),
+            ) else {
+                continue;
+            };
+
+            let mut mesh_key = MeshPipelineKey::from_primitive_topology(mesh.primitive_topology)
+                | MeshPipelineKey::PICKING_PASS;
+            if mesh.morph_targets.is_some() {
+                mesh_key |= MeshPipelineKey::MORPH_TARGETS;
+            }
+            if let AlphaMode::Mask(_) = material.properties.alpha_mode {
+                mesh_key |= MeshPipelineKey::ALPHA_MASK;
+            }
+
+            let pipeline_id = pipelines.specialize(
+                &mut pipeline_cache,
+                &picking_pipeline,
+                MaterialPipelineKey {
+                    mesh_key,
+                    bind_group_data: material.key.clone(),
+                },
+                &mesh.layout,
+            );
+            let pipeline_id = match pipeline_id {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+
+            picking_phase.add(Picking3d {
+                entity: *visible_entity,
+                draw_function: draw_picking,
+                pipeline: pipeline_id,
+                distance: rangefinder.distance(&mesh_uniform.transform)
+                    + material.properties.depth_bias,
+            });
+        }
+    }
+}
+
+/// Meshes drawn into the picking texture of a view, sorted front-to-back.
+pub struct Picking3d {
+    pub distance: f32,
+    pub pipeline: CachedRenderPipelineId,
+    pub entity: Entity,
+    pub draw_function: DrawFunctionId,
+}
+
+impl PhaseItem for Picking3d {
+    // NOTE: Values increase towards the camera. Front-to-back ordering means we need a descending sort.
+    type SortKey = Reverse<FloatOrd>;
+
+    #[inline]
+    fn sort_key(&self) -> Self::SortKey {
+        Reverse(FloatOrd(self.distance))
+    }
+
+    #[inline]
+    fn draw_function(&self) -> DrawFunctionId {
+        self.draw_function
+    }
+
+    #[inline]
+    fn sort(items: &mut [Self]) {
+        // Key negated to match reversed SortKey ordering
+        radsort::sort_by_key(items, |item| -item.distance);
+    }
+}
+
+impl EntityPhaseItem for Picking3d {
+    #[inline]
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl CachedRenderPipelinePhaseItem for Picking3d {
+    #[inline]
+    fn cached_pipeline(&self) -> CachedRenderPipelineId {
+        self.pipeline
+    }
+}
+
+pub type DrawPicking<M> = (
+    SetItemPipeline,
+    SetPickingViewBindGroup<0>,
+    SetMaterialBindGroup<M, 1>,
+    SetMeshBindGroup<2>,
+    DrawMesh,
+);
+
+pub struct SetPickingViewBindGroup<const I: usize>;
+impl<const I: usize> EntityRenderCommand for SetPickingViewBindGroup<I> {
+    type Param = (SRes<PickingViewBindGroup>, SQuery<Read<ViewUniformOffset>>);
+    #[inline]
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        (picking_view_bind_group, view_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let view_uniform_offset = view_query.get(view).unwrap();
+        pass.set_bind_group(
+            I,
+            picking_view_bind_group.into_inner().0.as_ref().unwrap(),
+            &[view_uniform_offset.offset],
+        );
+        RenderCommandResult::Success
+    }
+}
+
+/// Draws the [`Picking3d`] phase of a view into its [`ViewPickingTextures`].
+pub struct PickingNode {
+    query: QueryState<
+        (
+            &'static ExtractedCamera,
+            &'static RenderPhase<Picking3d>,
+            &'static ViewPickingTextures,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl PickingNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: world.query_filtered(),
+        }
+    }
+}
+
+impl Node for PickingNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((camera, picking_phase, picking_textures)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            // The camera doesn't have a picking pass
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _picking_pass_span = info_span!("picking_pass").entered();
+        // NOTE: The clear color is cast to the integers of the texture, saturating to `NO_ENTITY`
+        let no_entity = NO_ENTITY as f32;
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("picking_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &picking_textures.entities.default_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(
+                        Color::rgba_linear(no_entity, no_entity, no_entity, no_entity).into(),
+                    ),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &picking_textures.depth.default_view,
+                depth_ops: Some(Operations {
+                    // NOTE: 0.0 is the far plane due to bevy's use of reverse-z projections.
+                    load: LoadOp::Clear(0.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        };
+
+        let draw_functions = world.resource::<DrawFunctions<Picking3d>>();
+
+        let render_pass = render_context
+            .command_encoder
+            .begin_render_pass(&pass_descriptor);
+        let mut draw_functions = draw_functions.write();
+        let mut tracked_pass = TrackedRenderPass::new(render_pass);
+        if let Some(viewport) = camera.viewport.as_ref() {
+            tracked_pass.set_camera_viewport(viewport);
+            if let Some(target_size) = camera.physical_target_size {
+                tracked_pass.set_camera_scissor_rect(viewport, target_size);
+            }
+        }
+        for item in &picking_phase.items {
+            let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+            draw_function.draw(world, &mut tracked_pass, view_entity, item);
+        }
+
+        Ok(())
+    }
+}
+
+/// A copy of a pixel of a picking texture into a buffer, being mapped to be read on the CPU.
+struct PendingPick {
+    buffer: Buffer,
+    result: Arc<Mutex<PickResult>>,
+    map_result: Arc<Mutex<Option<bool>>>,
+}
+
+/// Picks whose pixel is being copied or mapped.
+#[derive(Resource, Default)]
+struct PendingPicks(Vec<PendingPick>);
+
+/// Copies the pixels of the requested picks once the frame has been rendered, and resolves the
+/// picks whose buffer finished mapping.
+fn read_back_picks(
+    extracted: Res<ExtractedPickingRequests>,
+    views: Query<&ViewPickingTextures>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut pending: ResMut<PendingPicks>,
+) {
+    render_device.poll(Maintain::Poll);
+    pending.0.retain(|pick| {
+        let Some(mapped) = pick.map_result.lock().unwrap().take() else {
+            return true;
+        };
+        let mut entity = None;
+        if mapped {
+            {
+                let data = pick.buffer.slice(..).get_mapped_range();
+                let [index, generation]: [u32; 2] =
+                    bytemuck::pod_read_unaligned(&data[..PICKING_FORMAT.pixel_size()]);
+                if index != NO_ENTITY {
+                    entity = Some(Entity::from_bits((generation as u64) << 32 | index as u64));
+                }
+            }
+            pick.buffer.unmap();
+        }
+        resolve_pick(&pick.result, entity);
+        false
+    });
+
+    if extracted.requests.is_empty() {
+        return;
+    }
+
+    let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("picking_readback_command_encoder"),
+    });
+    let mut new_picks = Vec::new();
+    for (camera, pixel, result) in &extracted.requests {
+        let Ok(picking_textures) = views.get(*camera) else {
+            resolve_pick(result, None);
+            continue;
+        };
+        let bytes_per_row = RenderDevice::align_copy_bytes_per_row(PICKING_FORMAT.pixel_size());
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("picking_readback_buffer"),
+            size: bytes_per_row as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        command_encoder.copy_texture_to_buffer(
+            ImageCopyTexture {
+                texture: &picking_textures.entities.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: pixel.x,
+                    y: pixel.y,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        new_picks.push(PendingPick {
+            buffer,
+            result: result.clone(),
+            map_result: Arc::default(),
+        });
+    }
+    render_queue.submit([command_encoder.finish()]);
+
+    for pick in new_picks {
+        let map_result = pick.map_result.clone();
+        render_device.map_buffer(&pick.buffer.slice(..), MapMode::Read, move |result| {
+            *map_result.lock().unwrap() = Some(result.is_ok());
+        });
+        pending.0.push(pick);
+    }
+}
@@ -0,0 +1,85 @@
+#import bevy_pbr::mesh_view_types
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+#ifdef STANDARDMATERIAL_ALPHA_MASK
+#import bevy_pbr::pbr_bindings
+#endif
+#ifdef STANDARDMATERIAL_UV_TRANSFORM
+#import bevy_pbr::pbr_bindings
+#endif
+#import bevy_pbr::mesh_bindings
+
+// NOTE: Bindings must come before functions that use them!
+#import bevy_pbr::mesh_functions
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+#ifdef VERTEX_UVS
+    @location(2) uv: vec2<f32>,
+#endif
+#ifdef SKINNED
+    @location(5) joint_indices: vec4<u32>,
+    @location(6) joint_weights: vec4<f32>,
+#endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+#ifdef VERTEX_UVS
+    @location(0) uv: vec2<f32>,
+#endif
+};
+
+@vertex
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#endif
+
+#ifdef SKINNED
+    var model = skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+    var model = mesh.model;
+#endif
+
+    var out: VertexOutput;
+    out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+#ifdef VERTEX_UVS
+#ifdef STANDARDMATERIAL_UV_TRANSFORM
+    out.uv = (material.uv_transform * vec3<f32>(vertex.uv, 1.0)).xy;
+#else
+    out.uv = vertex.uv;
+#endif
+#endif
+    return out;
+}
+
+struct FragmentInput {
+    @builtin(position) frag_coord: vec4<f32>,
+#ifdef VERTEX_UVS
+    @location(0) uv: vec2<f32>,
+#endif
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec2<u32> {
+#ifdef STANDARDMATERIAL_ALPHA_MASK
+    // Matches the alpha of the main pass, without the vertex colors
+    var alpha = material.base_color.a;
+#ifdef VERTEX_UVS
+    if ((material.flags & STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT) != 0u) {
+        alpha = alpha * textureSample(base_color_texture, base_color_sampler, in.uv).a;
+    }
+#endif
+    if (alpha < material.alpha_cutoff) {
+        discard;
+    }
+#endif
+    return mesh.entity;
+}
@@ -0,0 +1,90 @@
+use crate::{DrawMesh, MeshPipeline, MeshPipelineKey, SetMeshBindGroup, SetMeshViewBindGroup};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_core_pipeline::core_3d::Opaque3d;
+use bevy_ecs::{prelude::*, system::Resource};
+use bevy_reflect::TypeUuid;
+use bevy_render::{
+    mesh::MeshVertexBufferLayout,
+    render_phase::{AddRenderCommand, SetItemPipeline},
+    render_resource::{
+        RenderPipelineDescriptor, Shader, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+        SpecializedMeshPipelines,
+    },
+    RenderApp,
+};
+
+pub const PLACEHOLDER_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6305817621859327167);
+
+/// Adds the pipeline drawing the meshes whose material pipeline isn't created yet in a flat grey,
+/// for the [`MaterialPlugin`](crate::MaterialPlugin)s with
+/// [`placeholder_enabled`](crate::MaterialPlugin::placeholder_enabled).
+pub struct PlaceholderPlugin;
+
+impl Plugin for PlaceholderPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PLACEHOLDER_SHADER_HANDLE,
+            "render/placeholder.wgsl",
+            Shader::from_wgsl
+        );
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_render_command::<Opaque3d, DrawPlaceholder>()
+                .init_resource::<PlaceholderPipeline>()
+                .init_resource::<SpecializedMeshPipelines<PlaceholderPipeline>>();
+        }
+    }
+}
+
+/// The pipeline of the grey placeholder drawn in place of the materials still being compiled.
+#[derive(Resource)]
+pub struct PlaceholderPipeline {
+    mesh_pipeline: MeshPipeline,
+    shader: Handle<Shader>,
+}
+
+impl FromWorld for PlaceholderPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        PlaceholderPipeline {
+            mesh_pipeline: render_world.resource::<MeshPipeline>().clone(),
+            shader: PLACEHOLDER_SHADER_HANDLE.typed(),
+        }
+    }
+}
+
+impl PlaceholderPipeline {
+    /// Returns the key of the placeholder standing in for a material specialized with `key`. The
+    /// placeholders are opaque and drawn one mesh at a time.
+    pub fn placeholder_key(key: MeshPipelineKey) -> MeshPipelineKey {
+        key - MeshPipelineKey::TRANSPARENT_MAIN_PASS
+            - MeshPipelineKey::BLEND_PREMULTIPLIED_ALPHA
+            - MeshPipelineKey::INSTANCED
+    }
+}
+
+impl SpecializedMeshPipeline for PlaceholderPipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key, layout)?;
+        descriptor.label = Some("placeholder_pipeline".into());
+        descriptor.vertex.shader = self.shader.clone_weak();
+        descriptor.fragment.as_mut().unwrap().shader = self.shader.clone_weak();
+        Ok(descriptor)
+    }
+}
+
+pub type DrawPlaceholder = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    DrawMesh,
+);
@@ -0,0 +1,523 @@
+use crate::{
+    batch_mesh_instances, is_skinned, prepare_mesh_instances, AlphaMode, DrawMeshInstanced,
+    Material, MaterialPipeline, MaterialPipelineKey, MeshInstance, MeshPipeline, MeshPipelineKey,
+    MeshUniform, RenderMaterials, SetMaterialBindGroup, SetMeshBindGroup, MAX_CASCADES_PER_LIGHT,
+    MAX_DIRECTIONAL_LIGHTS, PREPASS_SHADER_HANDLE,
+};
+use bevy_app::{App, Plugin};
+use bevy_asset::Handle;
+use bevy_core_pipeline::prepass::{
+    Opaque3dPrepass, PreviousViewProjection, ViewPrepassTextures, DEPTH_PREPASS_FORMAT,
+    MOTION_VECTOR_PREPASS_FORMAT, NORMAL_PREPASS_FORMAT,
+};
+use bevy_ecs::{
+    prelude::*,
+    system::{
+        lifetimeless::{Read, SQuery, SRes},
+        SystemParamItem,
+    },
+};
+use bevy_render::{
+    extract_component::{ComponentUniforms, DynamicUniformIndex},
+    mesh::{Mesh, MeshVertexBufferLayout},
+    render_asset::RenderAssets,
+    render_phase::{
+        AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
+        SetItemPipeline, TrackedRenderPass,
+    },
+    render_resource::*,
+    renderer::RenderDevice,
+    view::{ExtractedView, Msaa, ViewUniform, ViewUniformOffset, ViewUniforms, VisibleEntities},
+    RenderApp, RenderStage,
+};
+use bevy_utils::tracing::error;
+use std::{hash::Hash, marker::PhantomData};
+
+/// Draws the opaque meshes using the [`Material`] `M` into the prepass of the cameras with a
+/// [`DepthPrepass`](bevy_core_pipeline::prepass::DepthPrepass),
+/// [`NormalPrepass`](bevy_core_pipeline::prepass::NormalPrepass) or
+/// [`MotionVectorPrepass`](bevy_core_pipeline::prepass::MotionVectorPrepass).
+///
+/// It is added by the [`MaterialPlugin`](crate::MaterialPlugin) of `M`, unless its
+/// [`prepass_enabled`](crate::MaterialPlugin::prepass_enabled) is `false`.
+///
+/// Only the vertex positions and normals of the meshes are used by the prepass, with its own
+/// shaders. Meshes aren't instanced in the prepass of views with motion vectors, which read the
+/// previous transform of each mesh from its [`MeshUniform`]. Because of that, meshes are left out of it if their [`AlphaMode`] isn't
+/// [`AlphaMode::Opaque`], or if `M` has a custom [`vertex_shader`](Material::vertex_shader) that
+/// could move them: they would otherwise be occluded by a depth that doesn't match theirs.
+pub struct PrepassPlugin<M: Material>(PhantomData<M>);
+
+impl<M: Material> Default for PrepassPlugin<M> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<M: Material> Plugin for PrepassPlugin<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    fn build(&self, app: &mut App) {
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .add_render_command::<Opaque3dPrepass, DrawPrepass<M>>()
+                .init_resource::<PrepassPipeline<M>>()
+                .init_resource::<PrepassViewBindGroup<M>>()
+                .init_resource::<SpecializedMeshPipelines<PrepassPipeline<M>>>()
+                .add_system_to_stage(RenderStage::Queue, queue_prepass_view_bind_group::<M>)
+                .add_system_to_stage(RenderStage::Queue, queue_prepass_material_meshes::<M>)
+                .add_system_to_stage(
+                    RenderStage::PhaseSort,
+                    batch_prepass_material_meshes::<M>.after(prepare_mesh_instances),
+                );
+        }
+    }
+}
+
+/// Render pipeline data of the prepass of a given [`Material`].
+#[derive(Resource)]
+pub struct PrepassPipeline<M: Material> {
+    pub view_layout: BindGroupLayout,
+    /// The layout of the view bind group of the prepasses that write motion vectors, which also
+    /// binds the [`PreviousViewProjection`].
+    pub view_layout_motion_vectors: BindGroupLayout,
+    pub mesh_layout: BindGroupLayout,
+    pub skinned_mesh_layout: BindGroupLayout,
+    pub morphed_mesh_layout: BindGroupLayout,
+    pub morphed_skinned_mesh_layout: BindGroupLayout,
+    /// The pipeline of the main passes, which is given to [`Material::specialize`].
+    pub material_pipeline: MaterialPipeline<M>,
+}
+
+impl<M: Material> FromWorld for PrepassPipeline<M> {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        // View
+        let view_entry = BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(ViewUniform::min_size()),
+            },
+            count: None,
+        };
+        let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[view_entry],
+            label: Some("prepass_view_layout"),
+        });
+        let view_layout_motion_vectors =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[
+                    view_entry,
+                    // Previous view projection
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(PreviousViewProjection::min_size()),
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("prepass_view_layout_motion_vectors"),
+            });
+
+        let mesh_pipeline = world.resource::<MeshPipeline>();
+
+        PrepassPipeline {
+            view_layout,
+            view_layout_motion_vectors,
+            mesh_layout: mesh_pipeline.mesh_layout.clone(),
+            skinned_mesh_layout: mesh_pipeline.skinned_mesh_layout.clone(),
+            morphed_mesh_layout: mesh_pipeline.morphed_mesh_layout.clone(),
+            morphed_skinned_mesh_layout: mesh_pipeline.morphed_skinned_mesh_layout.clone(),
+            material_pipeline: world.resource::<MaterialPipeline<M>>().clone(),
+        }
+    }
+}
+
+impl<M: Material> SpecializedMeshPipeline for PrepassPipeline<M>
+where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    type Key = MaterialPipelineKey<M>;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt(
+                "MAX_DIRECTIONAL_LIGHTS".to_string(),
+                MAX_DIRECTIONAL_LIGHTS as u32,
+            ),
+            ShaderDefVal::UInt(
+                "MAX_CASCADES_PER_LIGHT".to_string(),
+                MAX_CASCADES_PER_LIGHT as u32,
+            ),
+        ];
+        let mut vertex_attributes = vec![Mesh::ATTRIBUTE_POSITION.at_shader_location(0)];
+
+        let normal_prepass = key.mesh_key.contains(MeshPipelineKey::NORMAL_PREPASS);
+        if normal_prepass {
+            shader_defs.push("NORMAL_PREPASS".into());
+            if layout.contains(Mesh::ATTRIBUTE_NORMAL) {
+                shader_defs.push("VERTEX_NORMALS".into());
+                vertex_attributes.push(Mesh::ATTRIBUTE_NORMAL.at_shader_location(1));
+            }
+        }
+
+        let motion_vector_prepass = key
+            .mesh_key
+            .contains(MeshPipelineKey::MOTION_VECTOR_PREPASS);
+        if motion_vector_prepass {
+            shader_defs.push("MOTION_VECTOR_PREPASS".into());
+        }
+        if normal_prepass || motion_vector_prepass {
+            shader_defs.push("PREPASS_FRAGMENT".into());
+        }
+
+        let view_layout = if motion_vector_prepass {
+            self.view_layout_motion_vectors.clone()
+        } else {
+            self.view_layout.clone()
+        };
+        let mut bind_group_layout =
+            vec![view_layout, self.material_pipeline.material_layout.clone()];
+        let skinned = is_skinned(layout);
+        if skinned {
+            shader_defs.push("SKINNED".into());
+            vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_INDEX.at_shader_location(5));
+            vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_WEIGHT.at_shader_location(6));
+        }
+        let morphed = key.mesh_key.contains(MeshPipelineKey::MORPH_TARGETS);
+        if morphed {
+            shader_defs.push("MORPH_TARGETS".into());
+        }
+        if key.mesh_key.contains(MeshPipelineKey::LOD_CROSS_FADE) {
+            shader_defs.push("LOD_CROSS_FADE".into());
+        }
+        bind_group_layout.push(match (skinned, morphed) {
+            (false, false) => self.mesh_layout.clone(),
+            (true, false) => self.skinned_mesh_layout.clone(),
+            (false, true) => self.morphed_mesh_layout.clone(),
+            (true, true) => self.morphed_skinned_mesh_layout.clone(),
+        });
+
+        let mut vertex_buffer_layouts = vec![layout.get_layout(&vertex_attributes)?];
+        if key.mesh_key.contains(MeshPipelineKey::INSTANCED) {
+            shader_defs.push("MESH_INSTANCED".into());
+            vertex_buffer_layouts.push(MeshInstance::vertex_buffer_layout());
+        }
+
+        // NOTE: Without a texture to write to, the fragment shader has no outputs. It is kept
+        // anyway as materials are used to their main pass pipelines having one in
+        // `Material::specialize`.
+        let normal_target = normal_prepass.then_some(ColorTargetState {
+            format: NORMAL_PREPASS_FORMAT,
+            blend: Some(BlendState::REPLACE),
+            write_mask: ColorWrites::ALL,
+        });
+        let targets = if motion_vector_prepass {
+            // The motion vectors are written to location 1, whether or not normals are written
+            vec![
+                normal_target,
+                Some(ColorTargetState {
+                    format: MOTION_VECTOR_PREPASS_FORMAT,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                }),
+            ]
+        } else {
+            normal_target.into_iter().map(Some).collect()
+        };
+
+        let mut descriptor = RenderPipelineDescriptor {
+            vertex: VertexState {
+                shader: PREPASS_SHADER_HANDLE.typed::<Shader>(),
+                entry_point: "vertex".into(),
+                shader_defs: shader_defs.clone(),
+                buffers: vertex_buffer_layouts,
+            },
+            fragment: Some(FragmentState {
+                shader: PREPASS_SHADER_HANDLE.typed::<Shader>(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets,
+            }),
+            layout: Some(bind_group_layout),
+            push_constant_ranges: Vec::new(),
+            primitive: PrimitiveState {
+                front_face: FrontFace::Ccw,
+                cull_mode: Some(Face::Back),
+                unclipped_depth: false,
+                polygon_mode: PolygonMode::Fill,
+                conservative: false,
+                topology: key.mesh_key.primitive_topology(),
+                strip_index_format: None,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_PREPASS_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState {
+                    front: StencilFaceState::IGNORE,
+                    back: StencilFaceState::IGNORE,
+                    read_mask: 0,
+                    write_mask: 0,
+                },
+                bias: DepthBiasState {
+                    constant: 0,
+                    slope_scale: 0.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: MultisampleState {
+                count: key.mesh_key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            label: Some("prepass_pipeline".into()),
+        };
+
+        // The material can change the culling of its meshes, which the prepass needs to match
+        M::specialize(&self.material_pipeline, &mut descriptor, layout, key)?;
+        Ok(descriptor)
+    }
+}
+
+/// The bind group of the view uniforms, for the [`PrepassPipeline`] of a given [`Material`].
+#[derive(Resource)]
+pub struct PrepassViewBindGroup<M: Material> {
+    pub bind_group: Option<BindGroup>,
+    /// The bind group of the views with motion vectors, which also binds the
+    /// [`PreviousViewProjection`] uniforms.
+    pub motion_vectors: Option<BindGroup>,
+    marker: PhantomData<M>,
+}
+
+impl<M: Material> Default for PrepassViewBindGroup<M> {
+    fn default() -> Self {
+        Self {
+            bind_group: None,
+            motion_vectors: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+pub fn queue_prepass_view_bind_group<M: Material>(
+    render_device: Res<RenderDevice>,
+    prepass_pipeline: Res<PrepassPipeline<M>>,
+    view_uniforms: Res<ViewUniforms>,
+    previous_view_projection_uniforms: Res<ComponentUniforms<PreviousViewProjection>>,
+    mut prepass_view_bind_group: ResMut<PrepassViewBindGroup<M>>,
+) {
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    prepass_view_bind_group.bind_group =
+        Some(render_device.create_bind_group(&BindGroupDescriptor {
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: view_binding.clone(),
+            }],
+            label: Some("prepass_view_bind_group"),
+            layout: &prepass_pipeline.view_layout,
+        }));
+
+    if let Some(previous_view_projection_binding) =
+        previous_view_projection_uniforms.uniforms().binding()
+    {
+        prepass_view_bind_group.motion_vectors =
+            Some(render_device.create_bind_group(&BindGroupDescriptor {
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: view_binding,
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: previous_view_projection_binding,
+                    },
+                ],
+                label: Some("prepass_view_motion_vectors_bind_group"),
+                layout: &prepass_pipeline.view_layout_motion_vectors,
+            }));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_prepass_material_meshes<M: Material>(
+    opaque_prepass_draw_functions: Res<DrawFunctions<Opaque3dPrepass>>,
+    prepass_pipeline: Res<PrepassPipeline<M>>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<PrepassPipeline<M>>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    msaa: Res<Msaa>,
+    render_meshes: Res<RenderAssets<Mesh>>,
+    render_materials: Res<RenderMaterials<M>>,
+    material_meshes: Query<(&Handle<M>, &Handle<Mesh>, &MeshUniform)>,
+    mut views: Query<(
+        &ExtractedView,
+        &VisibleEntities,
+        &ViewPrepassTextures,
+        &mut RenderPhase<Opaque3dPrepass>,
+    )>,
+) where
+    M::Data: PartialEq + Eq + Hash + Clone,
+{
+    // Custom vertex shaders can move the vertices away from where the prepass draws them
+    if prepass_pipeline.material_pipeline.vertex_shader.is_some() {
+        return;
+    }
+    let draw_opaque_prepass = opaque_prepass_draw_functions.read().id::<DrawPrepass<M>>();
+
+    for (view, visible_entities, prepass_textures, mut opaque_prepass_phase) in &mut views {
+        let mut view_key =
+            MeshPipelineKey::from_msaa_samples(msaa.samples) | MeshPipelineKey::DEPTH_PREPASS;
+        if prepass_textures.normal.is_some() {
+            view_key |= MeshPipelineKey::NORMAL_PREPASS;
+        }
+        let motion_vector_prepass = prepass_textures.motion_vectors.is_some();
+        if motion_vector_prepass {
+            view_key |= MeshPipelineKey::MOTION_VECTOR_PREPASS;
+        }
+        let rangefinder = view.rangefinder3d();
+
+        for visible_entity in &visible_entities.entities {
+            let Ok((material_handle, mesh_handle, mesh_uniform)) =
+                material_meshes.get(*visible_entity)
+            else {
+                continue;
+            };
+            let (Some(material), Some(mesh)) = (
+                render_materials.get(material_handle),
+                render_meshes.get(mesh_handle),
+            ) else {
+                continue;
+            };
+            if !matches!(material.properties.alpha_mode, AlphaMode::Opaque) {
+                continue;
+            }
+
+            let mut mesh_key =
+                MeshPipelineKey::from_primitive_topology(mesh.primitive_topology) | view_key;
+            if mesh.morph_targets.is_some() {
+                mesh_key |= MeshPipelineKey::MORPH_TARGETS;
+            }
+            let cross_fade = mesh_uniform.lod_fade != 0.0;
+            if cross_fade {
+                mesh_key |= MeshPipelineKey::LOD_CROSS_FADE;
+            }
+            // The instance buffer doesn't have the previous transforms nor the cross-fade of the
+            // meshes
+            let instanced = !is_skinned(&mesh.layout)
+                && mesh.morph_targets.is_none()
+                && !motion_vector_prepass
+                && !cross_fade;
+            if instanced {
+                mesh_key |= MeshPipelineKey::INSTANCED;
+            }
+
+            let pipeline_id = pipelines.specialize(
+                &mut pipeline_cache,
+                &prepass_pipeline,
+                MaterialPipelineKey {
+                    mesh_key,
+                    bind_group_data: material.key.clone(),
+                },
+                &mesh.layout,
+            );
+            let pipeline_id = match pipeline_id {
+                Ok(id) => id,
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+
+            opaque_prepass_phase.add(Opaque3dPrepass {
+                entity: *visible_entity,
+                draw_function: draw_opaque_prepass,
+                pipeline: pipeline_id,
+                distance: rangefinder.distance(&mesh_uniform.transform)
+                    + material.properties.depth_bias,
+                batch_range: instanced.then_some(0..1),
+            });
+        }
+    }
+}
+
+/// Merges consecutive prepass items drawing the same mesh with the same [`Material`] instance
+/// into instanced draws.
+pub fn batch_prepass_material_meshes<M: Material>(
+    opaque_prepass_draw_functions: Res<DrawFunctions<Opaque3dPrepass>>,
+    material_meshes: Query<(&Handle<M>, &Handle<Mesh>)>,
+    mut views: Query<&mut RenderPhase<Opaque3dPrepass>>,
+) {
+    let draw_opaque_prepass = opaque_prepass_draw_functions.read().id::<DrawPrepass<M>>();
+
+    let compatible = |a, b| match (material_meshes.get(a), material_meshes.get(b)) {
+        (Ok((material_a, mesh_a)), Ok((material_b, mesh_b))) => {
+            material_a == material_b && mesh_a == mesh_b
+        }
+        _ => false,
+    };
+
+    for mut opaque_prepass_phase in &mut views {
+        batch_mesh_instances(&mut opaque_prepass_phase, draw_opaque_prepass, compatible);
+    }
+}
+
+pub type DrawPrepass<M> = (
+    SetItemPipeline,
+    SetPrepassViewBindGroup<M, 0>,
+    SetMaterialBindGroup<M, 1>,
+    SetMeshBindGroup<2>,
+    DrawMeshInstanced,
+);
+
+pub struct SetPrepassViewBindGroup<M: Material, const I: usize>(PhantomData<M>);
+impl<M: Material, const I: usize> EntityRenderCommand for SetPrepassViewBindGroup<M, I> {
+    type Param = (
+        SRes<PrepassViewBindGroup<M>>,
+        SQuery<(
+            Read<ViewUniformOffset>,
+            Option<Read<DynamicUniformIndex<PreviousViewProjection>>>,
+        )>,
+    );
+    #[inline]
+    fn render<'w>(
+        view: Entity,
+        _item: Entity,
+        (prepass_view_bind_group, view_query): SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let prepass_view_bind_group = prepass_view_bind_group.into_inner();
+        let (view_uniform_offset, previous_view_projection_index) = view_query.get(view).unwrap();
+        match previous_view_projection_index {
+            Some(previous_view_projection_index) => pass.set_bind_group(
+                I,
+                prepass_view_bind_group.motion_vectors.as_ref().unwrap(),
+                &[
+                    view_uniform_offset.offset,
+                    previous_view_projection_index.index(),
+                ],
+            ),
+            None => pass.set_bind_group(
+                I,
+                prepass_view_bind_group.bind_group.as_ref().unwrap(),
+                &[view_uniform_offset.offset],
+            ),
+        }
+
+        RenderCommandResult::Success
+    }
+}
@@ -0,0 +1,723 @@
+//! A procedural sky, which draws the color of a clear sky around a 3D camera according to the
+//! direction of the sun, with the [Preetham](https://www2.cs.duke.edu/courses/cps124/spring08/assign/07_papers/p91-preetham.pdf)
+//! analytic model of daylight.
+//!
+//! Add a [`ProceduralSky`] to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) to draw it
+//! behind the meshes, like a [`Skybox`]. The sun is the brightest visible [`DirectionalLight`],
+//! so the sky follows the time of day as the light rotates.
+//!
+//! With [`ProceduralSky::bake_environment_map`], the sky is also rendered every frame into cube
+//! maps that light the [`StandardMaterial`](crate::StandardMaterial)s of the camera like an
+//! [`EnvironmentMapLight`](crate::EnvironmentMapLight), so the ambient light matches the sky.
+
+use crate::{
+    draw_3d_graph, exposure, DirectionalLight, MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS,
+};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_core_pipeline::{
+    core_3d,
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    skybox::{Skybox, SkyboxBindGroup, SkyboxPipelineId},
+};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_math::{Vec3, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
+use bevy_render::{
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Color},
+    render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ComputedVisibility, ExtractedView, Msaa, ViewTarget, ViewUniform, ViewUniforms},
+    Extract, RenderApp, RenderStage,
+};
+use bevy_transform::components::GlobalTransform;
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+const PROCEDURAL_SKY_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2191887942013256434);
+
+/// The size of the faces of the baked diffuse cube map.
+const DIFFUSE_MAP_SIZE: u32 = 32;
+/// The size of the faces of the first mip level of the baked specular cube map.
+const SPECULAR_MAP_SIZE: u32 = 128;
+/// The number of mip levels of the baked specular cube map, down to faces of a single texel.
+const SPECULAR_MAP_MIP_LEVELS: u32 = SPECULAR_MAP_SIZE.trailing_zeros() + 1;
+const ENVIRONMENT_MAP_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+pub struct ProceduralSkyPlugin;
+
+impl Plugin for ProceduralSkyPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PROCEDURAL_SKY_SHADER_HANDLE,
+            "procedural_sky.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<ProceduralSky>()
+            .add_plugin(ExtractComponentPlugin::<ProceduralSky>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .init_resource::<ProceduralSkyPipeline>()
+            .init_resource::<SpecializedRenderPipelines<ProceduralSkyPipeline>>()
+            .init_resource::<ProceduralSkyMeta>()
+            .add_system_to_stage(RenderStage::Extract, extract_sun)
+            .add_system_to_stage(RenderStage::Prepare, prepare_procedural_skies)
+            .add_system_to_stage(RenderStage::Queue, queue_procedural_sky_bind_groups);
+
+        let bake_node = ProceduralSkyBakeNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        let draw_3d_graph = graph.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+        draw_3d_graph.add_node(draw_3d_graph::node::PROCEDURAL_SKY_BAKE, bake_node);
+        draw_3d_graph.add_slot_edge(
+            draw_3d_graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            draw_3d_graph::node::PROCEDURAL_SKY_BAKE,
+            ProceduralSkyBakeNode::IN_VIEW,
+        );
+        draw_3d_graph.add_node_edge(
+            draw_3d_graph::node::PROCEDURAL_SKY_BAKE,
+            core_3d::graph::node::MAIN_PASS,
+        );
+    }
+}
+
+/// Draws a clear sky lit by the sun around a 3D camera, behind everything rendered by its main
+/// pass. See the [module documentation](crate::procedural_sky).
+///
+/// It replaces the clear color like a [`Skybox`], and isn't drawn for the cameras which have a
+/// [`Skybox`] too.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct ProceduralSky {
+    /// The haziness of the atmosphere (default: `2.5`).
+    ///
+    /// A turbidity of `2.0` gives the deep blue of a very clear sky, and higher values add haze
+    /// which whitens the sky and makes the area around the sun brighter. The model is meant for
+    /// values from `2.0` to `10.0`.
+    pub turbidity: f32,
+    /// A scale factor multiplied with the physical luminance of the sky (default: `1.0`).
+    ///
+    /// At `1.0`, the sky is exposed like the [`DirectionalLight`]s are, so a sun of around
+    /// `100000.0` lux matches the brightness of the sky.
+    pub brightness: f32,
+    /// The fraction of the light of the horizon reflected by the ground seen below it
+    /// (default: a dark grey).
+    pub ground_albedo: Color,
+    /// The angular radius of the disk of the sun drawn in the sky, in radians
+    /// (default: `0.00465`, the one of the real sun).
+    ///
+    /// The disk has the color and illuminance of the sun, and can be hidden with a radius of `0.0`.
+    pub sun_disk_angular_radius: f32,
+    /// Whether to render the sky into an environment map every frame, to light the
+    /// [`StandardMaterial`](crate::StandardMaterial)s of the camera with it (default: `false`).
+    ///
+    /// It replaces the [`EnvironmentMapLight`](crate::EnvironmentMapLight) of the camera, if any.
+    /// The sun disk isn't baked into it, as the sun already lights the meshes directly.
+    pub bake_environment_map: bool,
+}
+
+impl Default for ProceduralSky {
+    fn default() -> Self {
+        Self {
+            turbidity: 2.5,
+            brightness: 1.0,
+            ground_albedo: Color::rgb(0.3, 0.3, 0.3),
+            sun_disk_angular_radius: 0.00465,
+            bake_environment_map: false,
+        }
+    }
+}
+
+impl ExtractComponent for ProceduralSky {
+    type Query = &'static Self;
+    type Filter = (With<Camera>, Without<Skybox>);
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self::Out> {
+        Some(item.clone())
+    }
+}
+
+/// The sun of the [`ProceduralSky`]s: the brightest visible [`DirectionalLight`].
+#[derive(Resource)]
+pub struct ExtractedSun {
+    direction_to_sun: Vec3,
+    /// The color of the sun multiplied by its illuminance, in lux.
+    illuminance: Vec3,
+}
+
+pub fn extract_sun(
+    mut commands: Commands,
+    directional_lights: Extract<Query<(&DirectionalLight, &GlobalTransform, &ComputedVisibility)>>,
+) {
+    let sun = directional_lights
+        .iter()
+        .filter(|(_, _, visibility)| visibility.is_visible())
+        .max_by(|(a, _, _), (b, _, _)| a.illuminance.total_cmp(&b.illuminance));
+    match sun {
+        Some((light, transform, _)) => commands.insert_resource(ExtractedSun {
+            direction_to_sun: transform.back(),
+            illuminance: Vec3::from_slice(&light.color.as_linear_rgba_f32()) * light.illuminance,
+        }),
+        None => commands.remove_resource::<ExtractedSun>(),
+    }
+}
+
+/// The [`ProceduralSky`] of a view lit by its sun, in the form its shader uses it.
+#[derive(ShaderType, Clone)]
+pub struct GpuProceduralSky {
+    /// The coefficients of the Perez distribution of the luminance and chromaticities of the sky,
+    /// in the columns of the `Y`, `x` and `y` rows.
+    perez_a: Vec3,
+    perez_b: Vec3,
+    perez_c: Vec3,
+    perez_d: Vec3,
+    perez_e: Vec3,
+    /// The `Y`, `x` and `y` of the zenith, divided by the Perez distribution of the zenith.
+    /// `Y` already has the exposure and brightness applied.
+    zenith: Vec3,
+    direction_to_sun: Vec3,
+    /// The cosine of the angular radius of the sun disk.
+    sun_disk_cos_angular_radius: f32,
+    /// The exposed luminance of the sun disk in linear RGB.
+    sun_disk_luminance: Vec3,
+    ground_albedo: Vec3,
+}
+
+impl GpuProceduralSky {
+    fn new(sky: &ProceduralSky, sun: Option<&ExtractedSun>) -> Self {
+        let t = sky.turbidity;
+        let direction_to_sun = sun.map_or(Vec3::Y, |sun| sun.direction_to_sun);
+        // The model only holds while the sun is above the horizon, past which the sky fades out
+        let sun_zenith_angle = direction_to_sun.y.clamp(0.0, 1.0).acos();
+        let twilight = (1.0 + direction_to_sun.y / 0.1).clamp(0.0, 1.0);
+
+        // The coefficients are fitted to the turbidity, see the appendix of the paper
+        let perez_a = Vec3::new(
+            0.1787 * t - 1.4630,
+            -0.0193 * t - 0.2592,
+            -0.0167 * t - 0.2608,
+        );
+        let perez_b = Vec3::new(
+            -0.3554 * t + 0.4275,
+            -0.0665 * t + 0.0008,
+            -0.0950 * t + 0.0092,
+        );
+        let perez_c = Vec3::new(
+            -0.0227 * t + 5.3251,
+            -0.0004 * t + 0.2125,
+            -0.0079 * t + 0.2102,
+        );
+        let perez_d = Vec3::new(
+            0.1206 * t - 2.5771,
+            -0.0641 * t - 0.8989,
+            -0.0441 * t - 1.6537,
+        );
+        let perez_e = Vec3::new(
+            -0.0670 * t + 0.3703,
+            -0.0033 * t + 0.0452,
+            -0.0109 * t + 0.0529,
+        );
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f32::consts::PI - 2.0 * sun_zenith_angle);
+        // In kcd/m²
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        // The chromaticities are polynomials of the sun zenith angle, with coefficients for each
+        // power of the turbidity
+        let theta = Vec4::new(
+            sun_zenith_angle.powi(3),
+            sun_zenith_angle.powi(2),
+            sun_zenith_angle,
+            1.0,
+        );
+        let zenith_chromaticity = |t2: Vec4, t1: Vec4, t0: Vec4| {
+            t * t * t2.dot(theta) + t * t1.dot(theta) + t0.dot(theta)
+        };
+        let zenith_x = zenith_chromaticity(
+            Vec4::new(0.00166, -0.00375, 0.00209, 0.0),
+            Vec4::new(-0.02903, 0.06377, -0.03202, 0.00394),
+            Vec4::new(0.11693, -0.21196, 0.06052, 0.25886),
+        );
+        let zenith_y = zenith_chromaticity(
+            Vec4::new(0.00275, -0.00610, 0.00317, 0.0),
+            Vec4::new(-0.04214, 0.08970, -0.04153, 0.00516),
+            Vec4::new(0.15346, -0.26756, 0.06670, 0.26688),
+        );
+
+        // The Perez distribution at the zenith, whose angle to the sun is the sun zenith angle
+        let perez_zenith = (Vec3::ONE + perez_a * perez_b.exp())
+            * (Vec3::ONE
+                + perez_c * (perez_d * sun_zenith_angle).exp()
+                + perez_e * sun_zenith_angle.cos().powi(2));
+        let zenith = Vec3::new(
+            zenith_luminance.max(0.0) * 1000.0 * exposure() * sky.brightness * twilight,
+            zenith_x,
+            zenith_y,
+        ) / perez_zenith;
+
+        let sun_solid_angle =
+            std::f32::consts::TAU * (1.0 - sky.sun_disk_angular_radius.cos()).max(f32::EPSILON);
+        let sun_disk_luminance = sun.map_or(Vec3::ZERO, |sun| {
+            sun.illuminance * exposure() / sun_solid_angle
+        });
+
+        Self {
+            perez_a,
+            perez_b,
+            perez_c,
+            perez_d,
+            perez_e,
+            zenith,
+            direction_to_sun,
+            sun_disk_cos_angular_radius: if sky.sun_disk_angular_radius > 0.0 {
+                sky.sun_disk_angular_radius.cos()
+            } else {
+                // No direction is inside the disk
+                2.0
+            },
+            sun_disk_luminance,
+            ground_albedo: Vec3::from_slice(&sky.ground_albedo.as_linear_rgba_f32()),
+        }
+    }
+}
+
+/// The face and roughness a baking pass renders the sky into.
+#[derive(ShaderType, Clone)]
+struct GpuBakeParams {
+    face: u32,
+    /// The perceptual roughness prefiltered into the mip level of the specular map.
+    perceptual_roughness: f32,
+    /// Whether the pass renders the diffuse map instead of the specular map.
+    diffuse: u32,
+}
+
+#[derive(Resource)]
+pub struct ProceduralSkyMeta {
+    pub gpu_skies: DynamicUniformBuffer<GpuProceduralSky>,
+}
+
+impl FromWorld for ProceduralSkyMeta {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            gpu_skies: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+        }
+    }
+}
+
+/// The offset of the [`GpuProceduralSky`] of a view in the [`ProceduralSkyMeta`] buffer.
+#[derive(Component)]
+pub struct ProceduralSkyUniformOffset {
+    pub offset: u32,
+}
+
+/// The environment map a [`ProceduralSky`] is baked into every frame.
+#[derive(Component)]
+pub struct ProceduralSkyEnvironmentMap {
+    pub diffuse_map: CachedTexture,
+    pub specular_map: CachedTexture,
+    /// The views of the maps as cube maps.
+    pub diffuse_map_view: TextureView,
+    pub specular_map_view: TextureView,
+}
+
+#[derive(Resource)]
+pub struct ProceduralSkyPipeline {
+    layout: BindGroupLayout,
+    bake_layout: BindGroupLayout,
+    bake_pipeline: CachedRenderPipelineId,
+    /// The [`GpuBakeParams`] of every baking pass, the diffuse faces first.
+    bake_params: DynamicUniformBuffer<GpuBakeParams>,
+    bake_params_offsets: Vec<u32>,
+}
+
+fn shader_defs() -> Vec<ShaderDefVal> {
+    vec![
+        ShaderDefVal::UInt(
+            "MAX_DIRECTIONAL_LIGHTS".to_string(),
+            MAX_DIRECTIONAL_LIGHTS as u32,
+        ),
+        ShaderDefVal::UInt(
+            "MAX_CASCADES_PER_LIGHT".to_string(),
+            MAX_CASCADES_PER_LIGHT as u32,
+        ),
+    ]
+}
+
+impl FromWorld for ProceduralSkyPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let render_queue = world.resource::<RenderQueue>();
+
+        let uniform_entry = |binding, min_binding_size, has_dynamic_offset| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset,
+                min_binding_size: Some(min_binding_size),
+            },
+            count: None,
+        };
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("procedural_sky_bind_group_layout"),
+            entries: &[
+                uniform_entry(0, ViewUniform::min_size(), true),
+                uniform_entry(1, GpuProceduralSky::min_size(), false),
+            ],
+        });
+        let bake_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("procedural_sky_bake_bind_group_layout"),
+            entries: &[
+                uniform_entry(1, GpuProceduralSky::min_size(), true),
+                uniform_entry(2, GpuBakeParams::min_size(), true),
+            ],
+        });
+
+        let mut bake_params = DynamicUniformBuffer::new(render_device);
+        let mut bake_params_offsets = Vec::new();
+        for face in 0..6 {
+            bake_params_offsets.push(bake_params.push(GpuBakeParams {
+                face,
+                perceptual_roughness: 1.0,
+                diffuse: 1,
+            }));
+        }
+        for mip_level in 0..SPECULAR_MAP_MIP_LEVELS {
+            for face in 0..6 {
+                bake_params_offsets.push(bake_params.push(GpuBakeParams {
+                    face,
+                    perceptual_roughness: mip_level as f32 / (SPECULAR_MAP_MIP_LEVELS - 1) as f32,
+                    diffuse: 0,
+                }));
+            }
+        }
+        bake_params.write_buffer(render_device, render_queue);
+
+        let bake_pipeline =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("procedural_sky_bake_pipeline".into()),
+                    layout: Some(vec![bake_layout.clone()]),
+                    push_constant_ranges: Vec::new(),
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: PROCEDURAL_SKY_SHADER_HANDLE.typed(),
+                        shader_defs: shader_defs(),
+                        entry_point: "bake".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: ENVIRONMENT_MAP_FORMAT,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                });
+
+        ProceduralSkyPipeline {
+            layout,
+            bake_layout,
+            bake_pipeline,
+            bake_params,
+            bake_params_offsets,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ProceduralSkyPipelineKey {
+    samples: u32,
+    texture_format: TextureFormat,
+}
+
+impl SpecializedRenderPipeline for ProceduralSkyPipeline {
+    type Key = ProceduralSkyPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("procedural_sky_pipeline".into()),
+            layout: Some(vec![self.layout.clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: PROCEDURAL_SKY_SHADER_HANDLE.typed(),
+                shader_defs: shader_defs(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            // Like the skybox, the sky is only drawn where the depth buffer was left cleared.
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.samples,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_procedural_skies(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    texture_cache: Res<TextureCache>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<ProceduralSkyPipeline>>,
+    procedural_sky_pipeline: Res<ProceduralSkyPipeline>,
+    mut procedural_sky_meta: ResMut<ProceduralSkyMeta>,
+    msaa: Res<Msaa>,
+    sun: Option<Res<ExtractedSun>>,
+    views: Query<(Entity, &ExtractedView, &ProceduralSky)>,
+) {
+    procedural_sky_meta.gpu_skies.clear();
+
+    for (entity, view, sky) in &views {
+        let pipeline_id = pipelines.specialize(
+            &mut pipeline_cache,
+            &procedural_sky_pipeline,
+            ProceduralSkyPipelineKey {
+                samples: msaa.samples,
+                texture_format: if view.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+            },
+        );
+        let offset = procedural_sky_meta
+            .gpu_skies
+            .push(GpuProceduralSky::new(sky, sun.as_deref()));
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            SkyboxPipelineId(pipeline_id),
+            ProceduralSkyUniformOffset { offset },
+        ));
+
+        if sky.bake_environment_map {
+            let cube_map = |label, size, mip_level_count| {
+                let texture = texture_cache.get(
+                    &render_device,
+                    TextureDescriptor {
+                        label: Some(label),
+                        size: Extent3d {
+                            width: size,
+                            height: size,
+                            depth_or_array_layers: 6,
+                        },
+                        mip_level_count,
+                        sample_count: 1,
+                        dimension: TextureDimension::D2,
+                        format: ENVIRONMENT_MAP_FORMAT,
+                        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    },
+                );
+                let cube_view = texture.texture.create_view(&TextureViewDescriptor {
+                    label: Some(label),
+                    dimension: Some(TextureViewDimension::Cube),
+                    ..Default::default()
+                });
+                (texture, cube_view)
+            };
+            let (diffuse_map, diffuse_map_view) =
+                cube_map("procedural_sky_diffuse_map", DIFFUSE_MAP_SIZE, 1);
+            let (specular_map, specular_map_view) = cube_map(
+                "procedural_sky_specular_map",
+                SPECULAR_MAP_SIZE,
+                SPECULAR_MAP_MIP_LEVELS,
+            );
+            entity_commands.insert(ProceduralSkyEnvironmentMap {
+                diffuse_map,
+                specular_map,
+                diffuse_map_view,
+                specular_map_view,
+            });
+        }
+    }
+
+    procedural_sky_meta
+        .gpu_skies
+        .write_buffer(&render_device, &render_queue);
+}
+
+/// The bind group of the passes baking the [`ProceduralSkyEnvironmentMap`] of a view.
+#[derive(Component)]
+pub struct ProceduralSkyBakeBindGroup(BindGroup);
+
+pub fn queue_procedural_sky_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    procedural_sky_pipeline: Res<ProceduralSkyPipeline>,
+    procedural_sky_meta: Res<ProceduralSkyMeta>,
+    view_uniforms: Res<ViewUniforms>,
+    views: Query<(
+        Entity,
+        &ProceduralSkyUniformOffset,
+        Option<&ProceduralSkyEnvironmentMap>,
+    )>,
+) {
+    let (Some(view_binding), Some(sky_buffer), Some(bake_params_binding)) = (
+        view_uniforms.uniforms.binding(),
+        procedural_sky_meta.gpu_skies.buffer(),
+        procedural_sky_pipeline.bake_params.binding(),
+    ) else {
+        return;
+    };
+
+    for (entity, sky_offset, environment_map) in &views {
+        // The main pass only has a dynamic offset for the view uniform, so the sky is bound at its
+        // own offset
+        let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("procedural_sky_bind_group"),
+            layout: &procedural_sky_pipeline.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: view_binding.clone(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Buffer(BufferBinding {
+                        buffer: sky_buffer,
+                        offset: sky_offset.offset as u64,
+                        size: Some(GpuProceduralSky::min_size()),
+                    }),
+                },
+            ],
+        });
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(SkyboxBindGroup(bind_group));
+
+        if environment_map.is_some() {
+            let bake_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+                label: Some("procedural_sky_bake_bind_group"),
+                layout: &procedural_sky_pipeline.bake_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: procedural_sky_meta.gpu_skies.binding().unwrap(),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: bake_params_binding.clone(),
+                    },
+                ],
+            });
+            entity_commands.insert(ProceduralSkyBakeBindGroup(bake_bind_group));
+        }
+    }
+}
+
+/// Bakes the [`ProceduralSkyEnvironmentMap`] of a view, before its main pass samples it.
+pub struct ProceduralSkyBakeNode {
+    query: QueryState<(
+        &'static ProceduralSkyEnvironmentMap,
+        &'static ProceduralSkyBakeBindGroup,
+        &'static ProceduralSkyUniformOffset,
+    )>,
+}
+
+impl ProceduralSkyBakeNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for ProceduralSkyBakeNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((environment_map, bind_group, sky_offset)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            // The camera doesn't bake a procedural sky
+            return Ok(());
+        };
+        let procedural_sky_pipeline = world.resource::<ProceduralSkyPipeline>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(procedural_sky_pipeline.bake_pipeline)
+        else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _procedural_sky_bake_span = info_span!("procedural_sky_bake").entered();
+
+        let diffuse_targets = (0..6).map(|face| (&environment_map.diffuse_map, face, 0));
+        let specular_targets = (0..SPECULAR_MAP_MIP_LEVELS).flat_map(|mip_level| {
+            (0..6).map(move |face| (&environment_map.specular_map, face, mip_level))
+        });
+        for ((texture, face, mip_level), bake_params_offset) in diffuse_targets
+            .chain(specular_targets)
+            .zip(&procedural_sky_pipeline.bake_params_offsets)
+        {
+            let target = texture.texture.create_view(&TextureViewDescriptor {
+                label: Some("procedural_sky_bake_target"),
+                dimension: Some(TextureViewDimension::D2),
+                base_mip_level: mip_level,
+                mip_level_count: std::num::NonZeroU32::new(1),
+                base_array_layer: face,
+                array_layer_count: std::num::NonZeroU32::new(1),
+                ..Default::default()
+            });
+            let mut render_pass =
+                render_context
+                    .command_encoder
+                    .begin_render_pass(&RenderPassDescriptor {
+                        label: Some("procedural_sky_bake_pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: &target,
+                            resolve_target: None,
+                            ops: Operations::default(),
+                        })],
+                        depth_stencil_attachment: None,
+                    });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group.0, &[sky_offset.offset, *bake_params_offset]);
+            render_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+#import bevy_pbr::mesh_view_types
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct ProceduralSky {
+    perez_a: vec3<f32>,
+    perez_b: vec3<f32>,
+    perez_c: vec3<f32>,
+    perez_d: vec3<f32>,
+    perez_e: vec3<f32>,
+    // The (Y, x, y) of the zenith, divided by the Perez distribution of the zenith
+    zenith: vec3<f32>,
+    direction_to_sun: vec3<f32>,
+    sun_disk_cos_angular_radius: f32,
+    sun_disk_luminance: vec3<f32>,
+    ground_albedo: vec3<f32>,
+};
+
+struct BakeParams {
+    face: u32,
+    perceptual_roughness: f32,
+    diffuse: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(0) @binding(1)
+var<uniform> sky: ProceduralSky;
+@group(0) @binding(2)
+var<uniform> bake_params: BakeParams;
+
+let PI: f32 = 3.141592653589793;
+
+// The Perez distribution of the (Y, x, y) of the sky, for a direction at `cos_theta` from the zenith
+// and `gamma` radians from the sun
+fn perez(cos_theta: f32, gamma: f32) -> vec3<f32> {
+    let cos_gamma = cos(gamma);
+    return (1.0 + sky.perez_a * exp(sky.perez_b / cos_theta))
+        * (1.0 + sky.perez_c * exp(sky.perez_d * gamma) + sky.perez_e * cos_gamma * cos_gamma);
+}
+
+fn xyY_to_linear_rgb(x: f32, y: f32, Y: f32) -> vec3<f32> {
+    let XYZ = vec3<f32>(x * Y / y, Y, (1.0 - x - y) * Y / y);
+    let XYZ_to_linear_rgb = mat3x3<f32>(
+        vec3<f32>(3.2406, -0.9689, 0.0557),
+        vec3<f32>(-1.5372, 1.8758, -0.2040),
+        vec3<f32>(-0.4986, 0.0415, 1.0570),
+    );
+    return max(XYZ_to_linear_rgb * XYZ, vec3<f32>(0.0));
+}
+
+// The radiance of the sky in a world space `direction`, without the sun disk
+fn sky_radiance(direction: vec3<f32>) -> vec3<f32> {
+    // The model doesn't hold below the horizon, where the ground reflects the light of the horizon
+    let above_horizon = normalize(vec3<f32>(direction.x, max(direction.y, 0.0), direction.z));
+    let cos_theta = max(above_horizon.y, 0.001);
+    let gamma = acos(clamp(dot(above_horizon, sky.direction_to_sun), -1.0, 1.0));
+    let Yxy = sky.zenith * perez(cos_theta, gamma);
+    let radiance = xyY_to_linear_rgb(Yxy.y, Yxy.z, Yxy.x);
+    return radiance * mix(vec3<f32>(1.0), sky.ground_albedo, smoothstep(0.0, 0.02, -direction.y));
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // The direction from the camera through the pixel, in world space
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let view_position = view.inverse_projection * vec4<f32>(ndc, 1.0, 1.0);
+    let direction = normalize((view.view * vec4<f32>(view_position.xyz / view_position.w, 0.0)).xyz);
+
+    var radiance = sky_radiance(direction);
+    if (dot(direction, sky.direction_to_sun) >= sky.sun_disk_cos_angular_radius && direction.y >= 0.0) {
+        radiance += sky.sun_disk_luminance;
+    }
+    return vec4<f32>(radiance, 1.0);
+}
+
+// The direction of the texel at `uv` of a cube map face, in the left-handed coordinate system cube
+// maps are sampled with
+fn cube_face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let s = uv.x * 2.0 - 1.0;
+    let t = uv.y * 2.0 - 1.0;
+    switch (face) {
+        case 0u: { return vec3<f32>(1.0, -t, -s); }
+        case 1u: { return vec3<f32>(-1.0, -t, s); }
+        case 2u: { return vec3<f32>(s, 1.0, t); }
+        case 3u: { return vec3<f32>(s, -1.0, -t); }
+        case 4u: { return vec3<f32>(s, -t, 1.0); }
+        default: { return vec3<f32>(-s, -t, -1.0); }
+    }
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+// An orthonormal basis whose third column is `n`
+fn tangent_frame(n: vec3<f32>) -> mat3x3<f32> {
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if (abs(n.y) > 0.999) {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+    return mat3x3<f32>(tangent, bitangent, n);
+}
+
+let DIFFUSE_SAMPLES: u32 = 64u;
+let SPECULAR_SAMPLES: u32 = 32u;
+
+// The cosine weighted average of the radiance of the sky around `n`
+fn prefilter_diffuse(n: vec3<f32>) -> vec3<f32> {
+    let frame = tangent_frame(n);
+    var irradiance = vec3<f32>(0.0);
+    for (var i = 0u; i < DIFFUSE_SAMPLES; i += 1u) {
+        let xi = hammersley(i, DIFFUSE_SAMPLES);
+        let phi = 2.0 * PI * xi.y;
+        let cos_theta = sqrt(1.0 - xi.x);
+        let sin_theta = sqrt(xi.x);
+        let l = vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        irradiance += sky_radiance(frame * l);
+    }
+    return irradiance / f32(DIFFUSE_SAMPLES);
+}
+
+// The radiance of the sky around `r` weighted by the GGX distribution of the roughness, assuming
+// that the view and normal directions are along `r`, like the split sum approximation does
+fn prefilter_specular(r: vec3<f32>, perceptual_roughness: f32) -> vec3<f32> {
+    let alpha = perceptual_roughness * perceptual_roughness;
+    let frame = tangent_frame(r);
+    var radiance = vec3<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = 0u; i < SPECULAR_SAMPLES; i += 1u) {
+        let xi = hammersley(i, SPECULAR_SAMPLES);
+        let phi = 2.0 * PI * xi.y;
+        let cos_theta = sqrt((1.0 - xi.x) / (1.0 + (alpha * alpha - 1.0) * xi.x));
+        let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        let h = frame * vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        let l = reflect(-r, h);
+        let n_dot_l = dot(r, l);
+        if (n_dot_l > 0.0) {
+            radiance += sky_radiance(l) * n_dot_l;
+            total_weight += n_dot_l;
+        }
+    }
+    return radiance / max(total_weight, 0.0001);
+}
+
+@fragment
+fn bake(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let direction = normalize(cube_face_direction(bake_params.face, in.uv) * vec3<f32>(1.0, 1.0, -1.0));
+    if (bake_params.diffuse != 0u) {
+        return vec4<f32>(prefilter_diffuse(direction), 1.0);
+    }
+    if (bake_params.perceptual_roughness == 0.0) {
+        return vec4<f32>(sky_radiance(direction), 1.0);
+    }
+    return vec4<f32>(prefilter_specular(direction, bake_params.perceptual_roughness), 1.0);
+}
@@ -0,0 +1,1069 @@
+//! Reflections of the surroundings of the meshes, captured from points of the scene instead of
+//! coming from a single [`EnvironmentMapLight`](crate::EnvironmentMapLight).
+//!
+//! A [`ReflectionProbe`] renders its surroundings into the six faces of a cube map, either once
+//! or on a schedule, which is then prefiltered like an environment map. The meshes drawn with the
+//! [`StandardMaterial`](crate::StandardMaterial) inside of the unit cube around the
+//! [`GlobalTransform`] of a probe are lit by it instead of the environment map of the view.
+//! When the influence boxes of probes overlap, the smaller probes are preferred, and the probes
+//! fade out near the faces of their boxes. With [`ReflectionProbe::parallax_correction`], the
+//! reflections are projected onto the faces of the box, which lines them up with the walls of a
+//! room captured by a probe in its middle.
+//!
+//! Each face is rendered by a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) spawned for the
+//! probe, marked with a [`ReflectionProbeCamera`] and only active on the frames the probe is
+//! captured.
+//!
+//! Flat reflections, like the ones of water or mirrors, are better rendered by a camera mirroring
+//! the main camera with a [`PlanarReflection`].
+
+use std::num::NonZeroU32;
+
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_asset::{load_internal_asset, Assets, Handle, HandleUntyped};
+use bevy_core_pipeline::{
+    core_3d::Camera3dBundle, fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    tonemapping::Tonemapping,
+};
+use bevy_ecs::prelude::*;
+use bevy_math::{Mat4, Vec3, Vec4Swizzles};
+use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect, TypeUuid};
+use bevy_render::{
+    camera::{
+        Camera, CameraClipPlane, CameraUpdateSystem, PerspectiveProjection, Projection,
+        RenderTarget,
+    },
+    render_asset::RenderAssets,
+    render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice, RenderQueue},
+    texture::{Image, ImageSampler},
+    view::{ComputedVisibility, Visibility, VisibilitySystems},
+    Extract, RenderApp, RenderStage,
+};
+use bevy_time::Time;
+use bevy_transform::{
+    components::{GlobalTransform, Transform},
+    TransformSystem,
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+use bevy_utils::HashMap;
+
+pub const REFLECTION_PROBE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2865904422584755836);
+const REFLECTION_PROBE_BAKE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7431285917294357001);
+
+/// The label of the node baking the captured [`ReflectionProbe`]s, in the main render graph.
+pub const REFLECTION_PROBE_BAKE_NODE: &str = "reflection_probe_bake";
+
+/// The largest number of [`ReflectionProbe`]s lighting the meshes at once.
+///
+/// NOTE: This must match the size of the `probes` array of `ReflectionProbes` in
+/// `bevy_pbr/src/render/mesh_view_types.wgsl`.
+pub const MAX_REFLECTION_PROBES: usize = 8;
+/// The number of cube maps the probes are baked into. Without array textures, a single cube map
+/// is bound, so only the first probe lights the meshes.
+#[cfg(not(feature = "webgl"))]
+const REFLECTION_PROBE_SLOTS: u32 = MAX_REFLECTION_PROBES as u32;
+#[cfg(feature = "webgl")]
+const REFLECTION_PROBE_SLOTS: u32 = 1;
+
+/// The size of the faces the probes are captured into, and of the first mip level of their
+/// specular cube maps.
+const CAPTURE_SIZE: u32 = 128;
+/// The size of the faces of the diffuse cube maps.
+const DIFFUSE_MAP_SIZE: u32 = 32;
+/// The number of mip levels of the specular cube maps, down to faces of a single texel.
+///
+/// NOTE: This must match `REFLECTION_PROBE_SPECULAR_MIP_LEVELS` in `reflection_probe.wgsl`.
+const SPECULAR_MAP_MIP_LEVELS: u32 = CAPTURE_SIZE.trailing_zeros() + 1;
+const REFLECTION_PROBE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// The direction each [`ReflectionProbeCamera`] looks at and its up direction, in the order of
+/// the faces of a cube map. Cube maps are sampled with a left-handed coordinate system, so the
+/// `+Z` face looks to the negative Z axis of the world.
+const FACE_ORIENTATIONS: [(Vec3, Vec3); 6] = [
+    (Vec3::X, Vec3::Y),
+    (Vec3::NEG_X, Vec3::Y),
+    (Vec3::Y, Vec3::Z),
+    (Vec3::NEG_Y, Vec3::NEG_Z),
+    (Vec3::NEG_Z, Vec3::Y),
+    (Vec3::Z, Vec3::Y),
+];
+
+pub struct ReflectionProbePlugin;
+
+impl Plugin for ReflectionProbePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            REFLECTION_PROBE_SHADER_HANDLE,
+            "reflection_probe.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            REFLECTION_PROBE_BAKE_SHADER_HANDLE,
+            "reflection_probe_bake.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<ReflectionProbe>()
+            .register_type::<ReflectionProbeRefresh>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_reflection_probe_cameras
+                    .before(CameraUpdateSystem)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_planar_reflections
+                    .after(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::UpdateProjectionFrusta),
+            );
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .init_resource::<ReflectionProbePipeline>()
+            .init_resource::<ReflectionProbeMeta>()
+            .add_system_to_stage(RenderStage::Extract, extract_reflection_probes)
+            .add_system_to_stage(RenderStage::Prepare, prepare_reflection_probes);
+
+        // The probes are baked after the cameras rendered their faces, so the meshes are lit by
+        // them from the next frame on
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(REFLECTION_PROBE_BAKE_NODE, ReflectionProbeBakeNode);
+        graph.add_node_edge(
+            bevy_render::main_graph::node::CAMERA_DRIVER,
+            REFLECTION_PROBE_BAKE_NODE,
+        );
+    }
+}
+
+/// When a [`ReflectionProbe`] captures its surroundings.
+#[derive(Debug, Default, Reflect, FromReflect, Copy, Clone, PartialEq)]
+#[reflect(Default, Debug)]
+pub enum ReflectionProbeRefresh {
+    /// The probe is captured once, then again each time [`ReflectionProbe::needs_capture`] is set.
+    #[default]
+    OnDemand,
+    /// The probe is captured every frame, which renders the scene six more times.
+    EveryFrame,
+    /// The probe is captured every given number of seconds.
+    Interval(f32),
+}
+
+/// Captures the surroundings of its [`GlobalTransform`] into a cube map, which lights the meshes
+/// inside of the unit cube around it.
+///
+/// The cube map is captured in the directions of the world, so rotating the probe only rotates
+/// its influence box.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default, Debug)]
+pub struct ReflectionProbe {
+    pub refresh: ReflectionProbeRefresh,
+    /// Whether the probe is captured on the next frame, reset once it is.
+    ///
+    /// Defaults to `true`, and is also how [`ReflectionProbeRefresh::OnDemand`] probes are
+    /// captured again after their surroundings changed.
+    pub needs_capture: bool,
+    /// The distance to the near plane of the cameras capturing the probe (default: `0.1`).
+    pub near: f32,
+    /// The fraction of the size of the influence box, from its faces inwards, over which the
+    /// probe fades out (default: `0.1`).
+    pub falloff: f32,
+    /// Whether the reflections are projected onto the faces of the influence box, as if the
+    /// captured surroundings were at its faces (default: `true`).
+    ///
+    /// This suits a box fitting the walls of a room, and should be disabled for probes of open
+    /// spaces, whose surroundings are far away.
+    pub parallax_correction: bool,
+    /// A scale factor multiplied with the light of the probe (default: `1.0`).
+    pub intensity: f32,
+}
+
+impl Default for ReflectionProbe {
+    fn default() -> Self {
+        Self {
+            refresh: ReflectionProbeRefresh::OnDemand,
+            needs_capture: true,
+            near: 0.1,
+            falloff: 0.1,
+            parallax_correction: true,
+            intensity: 1.0,
+        }
+    }
+}
+
+/// A [`ReflectionProbe`], whose [`Transform`] is scaled to the size of its influence box.
+#[derive(Bundle, Clone, Debug, Default)]
+pub struct ReflectionProbeBundle {
+    pub reflection_probe: ReflectionProbe,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether the probe lights the meshes, and is captured
+    pub visibility: Visibility,
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// A camera rendering a face of the cube map of a [`ReflectionProbe`].
+///
+/// The cameras are spawned with the probe, and can be customized after that, though their
+/// transforms and targets are kept up to date by the probe. Note that they draw the UI unless
+/// it is hidden from them.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ReflectionProbeCamera {
+    pub probe: Entity,
+    /// The index of the face, from `0` to `5` in the order `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z`
+    /// of the faces of a cube map.
+    pub face: usize,
+}
+
+/// The [`ReflectionProbeCamera`]s of a [`ReflectionProbe`] and the images they render into,
+/// added to the probe when its cameras are spawned.
+#[derive(Component, Clone, Debug)]
+pub struct ReflectionProbeFaces {
+    pub cameras: [Entity; 6],
+    pub images: [Handle<Image>; 6],
+    /// The time since the last capture of a [`ReflectionProbeRefresh::Interval`] probe.
+    elapsed: f32,
+    /// Whether the probe was captured at least once.
+    captured: bool,
+}
+
+/// Spawns the cameras of the new [`ReflectionProbe`]s, and activates them on the frames the
+/// probes are captured.
+pub fn update_reflection_probe_cameras(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut images: ResMut<Assets<Image>>,
+    mut probes: Query<(
+        Entity,
+        &mut ReflectionProbe,
+        &GlobalTransform,
+        &ComputedVisibility,
+        Option<&mut ReflectionProbeFaces>,
+    )>,
+    mut cameras: Query<(
+        Entity,
+        &ReflectionProbeCamera,
+        &mut Camera,
+        &mut Transform,
+        &mut Projection,
+    )>,
+) {
+    let mut captures = HashMap::default();
+    for (entity, mut probe, transform, visibility, faces) in &mut probes {
+        let Some(mut faces) = faces else {
+            // The cameras are captured from the next frame on, once they have been updated
+            let images = FACE_ORIENTATIONS.map(|_| {
+                let mut image = Image::new_fill(
+                    Extent3d {
+                        width: CAPTURE_SIZE,
+                        height: CAPTURE_SIZE,
+                        depth_or_array_layers: 1,
+                    },
+                    TextureDimension::D2,
+                    &[0; 8],
+                    REFLECTION_PROBE_FORMAT,
+                );
+                image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT;
+                images.add(image)
+            });
+            let mut face = 0;
+            let cameras = images.clone().map(|image| {
+                let camera = commands
+                    .spawn((
+                        Camera3dBundle {
+                            camera: Camera {
+                                target: RenderTarget::Image(image),
+                                is_active: false,
+                                hdr: true,
+                                // The faces are rendered before the views lit by the probe
+                                priority: -1,
+                                ..Default::default()
+                            },
+                            projection: Projection::Perspective(PerspectiveProjection {
+                                fov: std::f32::consts::FRAC_PI_2,
+                                aspect_ratio: 1.0,
+                                near: probe.near,
+                                ..Default::default()
+                            }),
+                            tonemapping: Tonemapping::Disabled,
+                            ..Default::default()
+                        },
+                        ReflectionProbeCamera {
+                            probe: entity,
+                            face,
+                        },
+                    ))
+                    .id();
+                face += 1;
+                camera
+            });
+            commands.entity(entity).insert(ReflectionProbeFaces {
+                cameras,
+                images,
+                elapsed: 0.0,
+                captured: false,
+            });
+            continue;
+        };
+
+        let mut capture = !faces.captured;
+        match probe.refresh {
+            ReflectionProbeRefresh::OnDemand => {}
+            ReflectionProbeRefresh::EveryFrame => capture = true,
+            ReflectionProbeRefresh::Interval(interval) => {
+                faces.elapsed += time.delta_seconds();
+                if faces.elapsed >= interval {
+                    faces.elapsed = 0.0;
+                    capture = true;
+                }
+            }
+        }
+        if probe.needs_capture {
+            probe.needs_capture = false;
+            capture = true;
+        }
+        // Hidden probes are captured once they are visible again
+        if !visibility.is_visible_in_hierarchy() {
+            probe.needs_capture |= capture;
+            capture = false;
+        }
+        faces.captured |= capture;
+        captures.insert(entity, (capture, transform.translation(), probe.near));
+    }
+
+    for (entity, probe_camera, mut camera, mut transform, mut projection) in &mut cameras {
+        let Some(&(capture, translation, near)) = captures.get(&probe_camera.probe) else {
+            if !probes.contains(probe_camera.probe) {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        if camera.is_active != capture {
+            camera.is_active = capture;
+        }
+        if capture {
+            let (forward, up) = FACE_ORIENTATIONS[probe_camera.face];
+            *transform = Transform::from_translation(translation).looking_to(forward, up);
+            if let Projection::Perspective(perspective) = &mut *projection {
+                perspective.near = near;
+            }
+        }
+    }
+}
+
+/// Mirrors the `source` camera through a plane, to render its reflection into the image the
+/// camera with this component renders into, like the reflection of a mirror or still water.
+///
+/// The plane goes through the [`GlobalTransform`] of the `plane` entity, and faces its local Y
+/// axis. The camera clips everything on the other side of the plane from the `source` camera with
+/// a [`CameraClipPlane`], and is given the mirrored transform after the transforms are propagated,
+/// so it shouldn't have a parent.
+///
+/// The camera should have the projection of the `source` camera and render into an image of the
+/// size of its viewport, with a lower [`Camera::priority`] to be rendered first. The surface of
+/// the mirror then samples the image at the screen-space position of its pixels, mirrored
+/// horizontally: at `(1.0 - u, v)`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PlanarReflection {
+    pub source: Entity,
+    pub plane: Entity,
+}
+
+/// Mirrors the cameras with a [`PlanarReflection`] through their planes.
+pub fn update_planar_reflections(
+    mut commands: Commands,
+    mut reflections: Query<(
+        Entity,
+        &PlanarReflection,
+        &mut Transform,
+        &mut GlobalTransform,
+        Option<&mut CameraClipPlane>,
+    )>,
+    transforms: Query<&GlobalTransform, Without<PlanarReflection>>,
+) {
+    for (entity, reflection, mut transform, mut global_transform, clip_plane) in &mut reflections {
+        let (Ok(source), Ok(plane)) = (
+            transforms.get(reflection.source),
+            transforms.get(reflection.plane),
+        ) else {
+            continue;
+        };
+        let normal = plane.up();
+        let point = plane.translation();
+        let mirror = |v: Vec3| v - 2.0 * normal.dot(v) * normal;
+
+        // Mirroring both the forward and up directions keeps the rotation proper, and flips the
+        // image horizontally instead
+        let position = source.translation();
+        let mirrored_position = position - 2.0 * normal.dot(position - point) * normal;
+        *transform = Transform::from_translation(mirrored_position)
+            .looking_to(mirror(source.forward()), mirror(source.up()));
+        *global_transform = GlobalTransform::from(*transform);
+
+        let visible_side = if normal.dot(position - point) >= 0.0 {
+            normal
+        } else {
+            -normal
+        };
+        let new_clip_plane = CameraClipPlane::new(point, visible_side);
+        match clip_plane {
+            Some(mut clip_plane) => *clip_plane = new_clip_plane,
+            None => {
+                commands.entity(entity).insert(new_clip_plane);
+            }
+        }
+    }
+}
+
+/// A [`ReflectionProbe`] which was captured at least once, extracted to the render world.
+#[derive(Component)]
+pub struct ExtractedReflectionProbe {
+    transform: Mat4,
+    falloff: f32,
+    intensity: f32,
+    parallax_correction: bool,
+    images: [Handle<Image>; 6],
+    /// Whether the faces were rendered this frame.
+    captured: bool,
+}
+
+pub fn extract_reflection_probes(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    probes: Extract<
+        Query<(
+            Entity,
+            &ReflectionProbe,
+            &ReflectionProbeFaces,
+            &GlobalTransform,
+            &ComputedVisibility,
+        )>,
+    >,
+    cameras: Extract<Query<&Camera, With<ReflectionProbeCamera>>>,
+) {
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, probe, faces, transform, visibility) in &probes {
+        if !faces.captured || !visibility.is_visible_in_hierarchy() {
+            continue;
+        }
+        values.push((
+            entity,
+            ExtractedReflectionProbe {
+                transform: transform.compute_matrix(),
+                falloff: probe.falloff,
+                intensity: probe.intensity,
+                parallax_correction: probe.parallax_correction,
+                images: faces.images.clone(),
+                captured: cameras
+                    .get(faces.cameras[0])
+                    .map_or(false, |camera| camera.is_active),
+            },
+        ));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+#[derive(ShaderType, Clone, Copy, Default)]
+pub struct GpuReflectionProbe {
+    inverse_transform: Mat4,
+    position: Vec3,
+    falloff: f32,
+    intensity: f32,
+    /// The index of the cube maps of the probe in the arrays of cube maps.
+    index: u32,
+    parallax_correction: u32,
+}
+
+/// The [`ReflectionProbe`]s lighting the meshes, the smaller ones first.
+#[derive(ShaderType, Clone, Default)]
+pub struct GpuReflectionProbes {
+    probes: [GpuReflectionProbe; MAX_REFLECTION_PROBES],
+    count: u32,
+}
+
+/// The face, mip level and roughness a baking pass renders into.
+#[derive(ShaderType, Clone)]
+struct GpuBakeParams {
+    face: u32,
+    mip_level: u32,
+    /// The perceptual roughness prefiltered into the mip level of the specular map.
+    perceptual_roughness: f32,
+    /// Whether the pass renders the diffuse map instead of the specular map.
+    diffuse: u32,
+}
+
+#[derive(Resource)]
+pub struct ReflectionProbePipeline {
+    downsample_layout: BindGroupLayout,
+    filter_layout: BindGroupLayout,
+    downsample_pipeline: CachedRenderPipelineId,
+    filter_pipeline: CachedRenderPipelineId,
+    sampler: Sampler,
+    /// The [`GpuBakeParams`] of every baking pass: the mip levels of the radiance map, then the
+    /// diffuse faces and the mip levels of the specular map.
+    bake_params: DynamicUniformBuffer<GpuBakeParams>,
+    bake_params_offsets: Vec<u32>,
+}
+
+impl FromWorld for ReflectionProbePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let render_queue = world.resource::<RenderQueue>();
+
+        let texture_entry = |binding, sample_type, view_dimension| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                multisampled: false,
+                sample_type,
+                view_dimension,
+            },
+            count: None,
+        };
+        let params_entry = BindGroupLayoutEntry {
+            binding: 6,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(GpuBakeParams::min_size()),
+            },
+            count: None,
+        };
+        let mut downsample_entries: Vec<_> = (0..6)
+            .map(|face| {
+                texture_entry(
+                    face,
+                    TextureSampleType::Float { filterable: false },
+                    TextureViewDimension::D2,
+                )
+            })
+            .collect();
+        downsample_entries.push(params_entry);
+        let downsample_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("reflection_probe_downsample_bind_group_layout"),
+                entries: &downsample_entries,
+            });
+        let filter_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("reflection_probe_filter_bind_group_layout"),
+            entries: &[
+                params_entry,
+                texture_entry(
+                    7,
+                    TextureSampleType::Float { filterable: true },
+                    TextureViewDimension::Cube,
+                ),
+                BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("reflection_probe_sampler"),
+            ..ImageSampler::linear_descriptor()
+        });
+
+        let mut bake_params = DynamicUniformBuffer::new(render_device);
+        let mut bake_params_offsets = Vec::new();
+        for mip_level in 0..SPECULAR_MAP_MIP_LEVELS {
+            for face in 0..6 {
+                bake_params_offsets.push(bake_params.push(GpuBakeParams {
+                    face,
+                    mip_level,
+                    perceptual_roughness: 0.0,
+                    diffuse: 0,
+                }));
+            }
+        }
+        for face in 0..6 {
+            bake_params_offsets.push(bake_params.push(GpuBakeParams {
+                face,
+                mip_level: 0,
+                perceptual_roughness: 1.0,
+                diffuse: 1,
+            }));
+        }
+        for mip_level in 0..SPECULAR_MAP_MIP_LEVELS {
+            for face in 0..6 {
+                bake_params_offsets.push(bake_params.push(GpuBakeParams {
+                    face,
+                    mip_level,
+                    perceptual_roughness: mip_level as f32 / (SPECULAR_MAP_MIP_LEVELS - 1) as f32,
+                    diffuse: 0,
+                }));
+            }
+        }
+        bake_params.write_buffer(render_device, render_queue);
+
+        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
+        let mut queue_pipeline = |label: &'static str, layout: &BindGroupLayout, entry_point| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some(label.into()),
+                layout: Some(vec![layout.clone()]),
+                push_constant_ranges: Vec::new(),
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: REFLECTION_PROBE_BAKE_SHADER_HANDLE.typed(),
+                    shader_defs: Vec::new(),
+                    entry_point,
+                    targets: vec![Some(ColorTargetState {
+                        format: REFLECTION_PROBE_FORMAT,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+            })
+        };
+        let downsample_pipeline = queue_pipeline(
+            "reflection_probe_downsample_pipeline",
+            &downsample_layout,
+            "downsample".into(),
+        );
+        let filter_pipeline = queue_pipeline(
+            "reflection_probe_filter_pipeline",
+            &filter_layout,
+            "prefilter".into(),
+        );
+
+        ReflectionProbePipeline {
+            downsample_layout,
+            filter_layout,
+            downsample_pipeline,
+            filter_pipeline,
+            sampler,
+            bake_params,
+            bake_params_offsets,
+        }
+    }
+}
+
+/// The textures the [`ReflectionProbe`]s are baked into, created with the first probe.
+pub struct ReflectionProbeTextures {
+    /// The mip levels of the faces of the probe being baked, which are sampled when filtering
+    /// them.
+    radiance: Texture,
+    diffuse: Texture,
+    specular: Texture,
+    /// The views of the diffuse and specular maps of all the probes, as cube map arrays.
+    pub diffuse_view: TextureView,
+    pub specular_view: TextureView,
+    filter_bind_group: BindGroup,
+}
+
+impl ReflectionProbeTextures {
+    fn new(render_device: &RenderDevice, pipeline: &ReflectionProbePipeline) -> Self {
+        let texture = |label, size, layers, mip_level_count, usage| {
+            render_device.create_texture(&TextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: layers,
+                },
+                mip_level_count,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: REFLECTION_PROBE_FORMAT,
+                usage,
+            })
+        };
+        let radiance = texture(
+            "reflection_probe_radiance",
+            CAPTURE_SIZE,
+            6,
+            SPECULAR_MAP_MIP_LEVELS,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let diffuse = texture(
+            "reflection_probe_diffuse_maps",
+            DIFFUSE_MAP_SIZE,
+            6 * REFLECTION_PROBE_SLOTS,
+            1,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+        let specular = texture(
+            "reflection_probe_specular_maps",
+            CAPTURE_SIZE,
+            6 * REFLECTION_PROBE_SLOTS,
+            SPECULAR_MAP_MIP_LEVELS,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        );
+
+        let cube_maps_view = |texture: &Texture| {
+            texture.create_view(&TextureViewDescriptor {
+                #[cfg(not(feature = "webgl"))]
+                dimension: Some(TextureViewDimension::CubeArray),
+                #[cfg(feature = "webgl")]
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            })
+        };
+        let diffuse_view = cube_maps_view(&diffuse);
+        let specular_view = cube_maps_view(&specular);
+
+        let filter_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("reflection_probe_filter_bind_group"),
+            layout: &pipeline.filter_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 6,
+                    resource: pipeline.bake_params.binding().unwrap(),
+                },
+                BindGroupEntry {
+                    binding: 7,
+                    resource: BindingResource::TextureView(&radiance.create_view(
+                        &TextureViewDescriptor {
+                            dimension: Some(TextureViewDimension::Cube),
+                            ..Default::default()
+                        },
+                    )),
+                },
+                BindGroupEntry {
+                    binding: 8,
+                    resource: BindingResource::Sampler(&pipeline.sampler),
+                },
+            ],
+        });
+
+        Self {
+            radiance,
+            diffuse,
+            specular,
+            diffuse_view,
+            specular_view,
+            filter_bind_group,
+        }
+    }
+}
+
+/// A probe whose faces are baked into the cube maps at `slot` this frame.
+struct ReflectionProbeBake {
+    slot: u32,
+    images: [Handle<Image>; 6],
+}
+
+#[derive(Resource)]
+pub struct ReflectionProbeMeta {
+    pub gpu_probes: UniformBuffer<GpuReflectionProbes>,
+    pub textures: Option<ReflectionProbeTextures>,
+    /// A black cube map array, bound in place of the probes until the first one is baked.
+    pub dummy_view: TextureView,
+    /// The slot of the cube maps of each probe.
+    slots: HashMap<Entity, u32>,
+    bakes: Vec<ReflectionProbeBake>,
+}
+
+impl ReflectionProbeMeta {
+    /// Whether any [`ReflectionProbe`] lights the meshes this frame.
+    pub fn has_probes(&self) -> bool {
+        self.gpu_probes.get().count > 0
+    }
+}
+
+impl FromWorld for ReflectionProbeMeta {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let render_queue = world.resource::<RenderQueue>();
+        let dummy_view = render_device
+            .create_texture_with_data(
+                render_queue,
+                &TextureDescriptor {
+                    label: Some("reflection_probe_dummy_cube_maps"),
+                    size: Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 6,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::Rgba8Unorm,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                },
+                &[0; 4 * 6],
+            )
+            .create_view(&TextureViewDescriptor {
+                #[cfg(not(feature = "webgl"))]
+                dimension: Some(TextureViewDimension::CubeArray),
+                #[cfg(feature = "webgl")]
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            });
+        Self {
+            gpu_probes: UniformBuffer::default(),
+            textures: None,
+            dummy_view,
+            slots: HashMap::default(),
+            bakes: Vec::new(),
+        }
+    }
+}
+
+/// Gives a slot of the cube map arrays to each probe, queues the bakes of the probes captured this
+/// frame or new to their slot, and writes the probes baked on the previous frames to their
+/// uniform buffer.
+pub fn prepare_reflection_probes(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    pipeline_cache: Res<PipelineCache>,
+    pipeline: Res<ReflectionProbePipeline>,
+    mut meta: ResMut<ReflectionProbeMeta>,
+    images: Res<RenderAssets<Image>>,
+    probes: Query<(Entity, &ExtractedReflectionProbe)>,
+) {
+    let meta = &mut *meta;
+    meta.bakes.clear();
+    let mut gpu_probes = GpuReflectionProbes::default();
+
+    let ready = pipeline_cache
+        .get_render_pipeline(pipeline.downsample_pipeline)
+        .is_some()
+        && pipeline_cache
+            .get_render_pipeline(pipeline.filter_pipeline)
+            .is_some();
+    let mut probes: Vec<_> = probes
+        .iter()
+        .filter(|(_, probe)| probe.images.iter().all(|image| images.contains_key(image)))
+        .collect();
+    if ready && !probes.is_empty() && meta.textures.is_none() {
+        meta.textures = Some(ReflectionProbeTextures::new(&render_device, &pipeline));
+    }
+    if !ready || meta.textures.is_none() {
+        probes.clear();
+    }
+
+    // The smaller probes are more specific to the meshes inside of them, and are preferred
+    let volume = |probe: &ExtractedReflectionProbe| {
+        let transform = probe.transform;
+        (transform.x_axis.xyz().cross(transform.y_axis.xyz()))
+            .dot(transform.z_axis.xyz())
+            .abs()
+    };
+    probes.sort_by(|(_, a), (_, b)| volume(a).total_cmp(&volume(b)));
+    probes.truncate(REFLECTION_PROBE_SLOTS as usize);
+    meta.slots
+        .retain(|entity, _| probes.iter().any(|(probe, _)| probe == entity));
+
+    for (entity, probe) in probes {
+        let slot = match meta.slots.get(&entity) {
+            Some(&slot) => {
+                // The probe has been baked on a previous frame, and is lit with meanwhile
+                let index = gpu_probes.count as usize;
+                gpu_probes.probes[index] = GpuReflectionProbe {
+                    inverse_transform: probe.transform.inverse(),
+                    position: probe.transform.w_axis.xyz(),
+                    falloff: probe.falloff,
+                    intensity: probe.intensity,
+                    index: slot,
+                    parallax_correction: probe.parallax_correction as u32,
+                };
+                gpu_probes.count += 1;
+                if !probe.captured {
+                    continue;
+                }
+                slot
+            }
+            None => {
+                let slot = (0..REFLECTION_PROBE_SLOTS)
+                    .find(|slot| !meta.slots.values().any(|used| used == slot))
+                    .unwrap();
+                meta.slots.insert(entity, slot);
+                slot
+            }
+        };
+        meta.bakes.push(ReflectionProbeBake {
+            slot,
+            images: probe.images.clone(),
+        });
+    }
+
+    meta.gpu_probes.set(gpu_probes);
+    meta.gpu_probes.write_buffer(&render_device, &render_queue);
+}
+
+/// Bakes the faces of the [`ReflectionProbe`]s captured this frame into their cube maps.
+///
+/// The faces are downsampled into the mip levels of a cube map, which is then prefiltered into
+/// the diffuse and specular maps of the probe.
+pub struct ReflectionProbeBakeNode;
+
+impl Node for ReflectionProbeBakeNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let meta = world.resource::<ReflectionProbeMeta>();
+        let (Some(textures), false) = (&meta.textures, meta.bakes.is_empty()) else {
+            return Ok(());
+        };
+        let pipeline = world.resource::<ReflectionProbePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (Some(downsample_pipeline), Some(filter_pipeline)) = (
+            pipeline_cache.get_render_pipeline(pipeline.downsample_pipeline),
+            pipeline_cache.get_render_pipeline(pipeline.filter_pipeline),
+        ) else {
+            return Ok(());
+        };
+        let images = world.resource::<RenderAssets<Image>>();
+
+        #[cfg(feature = "trace")]
+        let _reflection_probe_bake_span = info_span!("reflection_probe_bake").entered();
+
+        for bake in &meta.bakes {
+            let Some(faces) = bake
+                .images
+                .iter()
+                .map(|image| images.get(image))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let mut entries: Vec<_> = faces
+                .iter()
+                .enumerate()
+                .map(|(face, image)| BindGroupEntry {
+                    binding: face as u32,
+                    resource: BindingResource::TextureView(&image.texture_view),
+                })
+                .collect();
+            entries.push(BindGroupEntry {
+                binding: 6,
+                resource: pipeline.bake_params.binding().unwrap(),
+            });
+            let downsample_bind_group =
+                render_context
+                    .render_device
+                    .create_bind_group(&BindGroupDescriptor {
+                        label: Some("reflection_probe_downsample_bind_group"),
+                        layout: &pipeline.downsample_layout,
+                        entries: &entries,
+                    });
+
+            let mut bake_params_offsets = pipeline.bake_params_offsets.iter();
+            let mut pass = |texture: &Texture, layer, mip_level, render_pipeline, bind_group| {
+                let target = texture.create_view(&TextureViewDescriptor {
+                    label: Some("reflection_probe_bake_target"),
+                    dimension: Some(TextureViewDimension::D2),
+                    base_mip_level: mip_level,
+                    mip_level_count: NonZeroU32::new(1),
+                    base_array_layer: layer,
+                    array_layer_count: NonZeroU32::new(1),
+                    ..Default::default()
+                });
+                let mut render_pass =
+                    render_context
+                        .command_encoder
+                        .begin_render_pass(&RenderPassDescriptor {
+                            label: Some("reflection_probe_bake_pass"),
+                            color_attachments: &[Some(RenderPassColorAttachment {
+                                view: &target,
+                                resolve_target: None,
+                                ops: Operations::default(),
+                            })],
+                            depth_stencil_attachment: None,
+                        });
+                render_pass.set_pipeline(render_pipeline);
+                render_pass.set_bind_group(0, bind_group, &[*bake_params_offsets.next().unwrap()]);
+                render_pass.draw(0..3, 0..1);
+            };
+            for mip_level in 0..SPECULAR_MAP_MIP_LEVELS {
+                for face in 0..6 {
+                    pass(
+                        &textures.radiance,
+                        face,
+                        mip_level,
+                        downsample_pipeline,
+                        &downsample_bind_group,
+                    );
+                }
+            }
+            for face in 0..6 {
+                pass(
+                    &textures.diffuse,
+                    bake.slot * 6 + face,
+                    0,
+                    filter_pipeline,
+                    &textures.filter_bind_group,
+                );
+            }
+            for mip_level in 0..SPECULAR_MAP_MIP_LEVELS {
+                for face in 0..6 {
+                    pass(
+                        &textures.specular,
+                        bake.slot * 6 + face,
+                        mip_level,
+                        filter_pipeline,
+                        &textures.filter_bind_group,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The bind group layout entries of the [`ReflectionProbe`]s in the view bind group of the
+/// [`MeshPipeline`](crate::MeshPipeline), at `binding` and the two next bindings.
+pub(crate) fn reflection_probe_layout_entries(binding: u32) -> [BindGroupLayoutEntry; 3] {
+    let cube_maps_entry = |binding| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+            multisampled: false,
+            sample_type: TextureSampleType::Float { filterable: true },
+            #[cfg(not(feature = "webgl"))]
+            view_dimension: TextureViewDimension::CubeArray,
+            #[cfg(feature = "webgl")]
+            view_dimension: TextureViewDimension::Cube,
+        },
+        count: None,
+    };
+    [
+        BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: Some(GpuReflectionProbes::min_size()),
+            },
+            count: None,
+        },
+        cube_maps_entry(binding + 1),
+        cube_maps_entry(binding + 2),
+    ]
+}
+
+impl ReflectionProbeMeta {
+    /// The views of the diffuse and specular maps bound to the [`MeshPipeline`](crate::MeshPipeline).
+    pub(crate) fn cube_map_views(&self) -> (&TextureView, &TextureView) {
+        match &self.textures {
+            Some(textures) => (&textures.diffuse_view, &textures.specular_view),
+            None => (&self.dummy_view, &self.dummy_view),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+#define_import_path bevy_pbr::reflection_probe
+
+struct ReflectionProbeLight {
+    light: vec3<f32>,
+    // The fraction of the ambient light coming from the probes, the rest coming from the
+    // environment map of the view
+    weight: f32,
+};
+
+// NOTE: This must match SPECULAR_MAP_MIP_LEVELS in bevy_pbr/src/reflection_probe/mod.rs
+let REFLECTION_PROBE_SPECULAR_MIP_LEVELS: f32 = 8.0;
+
+fn sample_reflection_probe_diffuse(index: u32, direction: vec3<f32>) -> vec3<f32> {
+#ifdef NO_ARRAY_TEXTURES_SUPPORT
+    return textureSampleLevel(reflection_probe_diffuse, environment_map_sampler, direction, 0.0).rgb;
+#else
+    return textureSampleLevel(reflection_probe_diffuse, environment_map_sampler, direction, i32(index), 0.0).rgb;
+#endif
+}
+
+fn sample_reflection_probe_specular(index: u32, direction: vec3<f32>, perceptual_roughness: f32) -> vec3<f32> {
+    // The last mip level of the specular map is prefiltered for a perceptual roughness of 1.0
+    let level = perceptual_roughness * (REFLECTION_PROBE_SPECULAR_MIP_LEVELS - 1.0);
+#ifdef NO_ARRAY_TEXTURES_SUPPORT
+    return textureSampleLevel(reflection_probe_specular, environment_map_sampler, direction, level).rgb;
+#else
+    return textureSampleLevel(reflection_probe_specular, environment_map_sampler, direction, i32(index), level).rgb;
+#endif
+}
+
+// Samples the probes whose influence boxes contain `world_position`, given the ambient terms of
+// the split sum approximation like `environment_map_light`. The probes are sorted from the
+// smallest, which take precedence over the larger ones they overlap.
+fn reflection_probe_light(
+    world_position: vec3<f32>,
+    N: vec3<f32>,
+    R: vec3<f32>,
+    perceptual_roughness: f32,
+    clearcoat_perceptual_roughness: f32,
+    diffuse_ambient: vec3<f32>,
+    specular_ambient: vec3<f32>,
+    clearcoat_ambient: vec3<f32>,
+) -> ReflectionProbeLight {
+    var out: ReflectionProbeLight;
+    out.light = vec3<f32>(0.0);
+    out.weight = 0.0;
+    for (var i = 0u; i < reflection_probes.count; i = i + 1u) {
+        let probe = reflection_probes.probes[i];
+        let local_position = (probe.inverse_transform * vec4<f32>(world_position, 1.0)).xyz;
+        let distance_to_faces = 0.5 - max(max(abs(local_position.x), abs(local_position.y)), abs(local_position.z));
+        if (distance_to_faces <= 0.0) {
+            continue;
+        }
+        let fade = smoothstep(0.0, 1.0, distance_to_faces / max(probe.falloff * 0.5, 0.0001));
+        let weight = (1.0 - out.weight) * fade;
+
+        var specular_direction = R;
+        if (probe.parallax_correction != 0u) {
+            // The surroundings are assumed to lie on the faces of the box, so the reflection
+            // samples the direction from the probe to where R leaves the box
+            var local_direction = (probe.inverse_transform * vec4<f32>(R, 0.0)).xyz;
+            local_direction = select(local_direction, vec3<f32>(0.00001), abs(local_direction) < vec3<f32>(0.00001));
+            let to_faces = (sign(local_direction) * 0.5 - local_position) / local_direction;
+            let t = min(min(to_faces.x, to_faces.y), to_faces.z);
+            specular_direction = world_position + R * t - probe.position;
+        }
+
+        // Cube maps are sampled with a left-handed coordinate system
+        let flip = vec3<f32>(1.0, 1.0, -1.0);
+        let irradiance = sample_reflection_probe_diffuse(probe.index, N * flip);
+        let radiance = sample_reflection_probe_specular(probe.index, specular_direction * flip, perceptual_roughness);
+        let clearcoat_radiance = sample_reflection_probe_specular(probe.index, specular_direction * flip, clearcoat_perceptual_roughness);
+
+        out.light = out.light + weight * probe.intensity
+            * (diffuse_ambient * irradiance + specular_ambient * radiance + clearcoat_ambient * clearcoat_radiance);
+        out.weight = out.weight + weight;
+        if (out.weight >= 0.999) {
+            break;
+        }
+    }
+    return out;
+}
@@ -0,0 +1,156 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct BakeParams {
+    face: u32,
+    mip_level: u32,
+    perceptual_roughness: f32,
+    diffuse: u32,
+};
+
+// The captured faces, downsampled into the mip levels of the radiance map
+@group(0) @binding(0)
+var face_x: texture_2d<f32>;
+@group(0) @binding(1)
+var face_neg_x: texture_2d<f32>;
+@group(0) @binding(2)
+var face_y: texture_2d<f32>;
+@group(0) @binding(3)
+var face_neg_y: texture_2d<f32>;
+@group(0) @binding(4)
+var face_z: texture_2d<f32>;
+@group(0) @binding(5)
+var face_neg_z: texture_2d<f32>;
+@group(0) @binding(6)
+var<uniform> bake_params: BakeParams;
+// The radiance map, prefiltered into the diffuse and specular maps
+@group(0) @binding(7)
+var radiance: texture_cube<f32>;
+@group(0) @binding(8)
+var radiance_sampler: sampler;
+
+let PI: f32 = 3.141592653589793;
+// NOTE: This must match CAPTURE_SIZE in bevy_pbr/src/reflection_probe/mod.rs
+let CAPTURE_SIZE: f32 = 128.0;
+
+// The direction through the point at `uv` of a face of a cube map, in the coordinate system cube
+// maps are sampled with
+fn cube_face_direction(face: u32, uv: vec2<f32>) -> vec3<f32> {
+    let s = uv.x * 2.0 - 1.0;
+    let t = uv.y * 2.0 - 1.0;
+    switch (face) {
+        case 0u: { return vec3<f32>(1.0, -t, -s); }
+        case 1u: { return vec3<f32>(-1.0, -t, s); }
+        case 2u: { return vec3<f32>(s, 1.0, t); }
+        case 3u: { return vec3<f32>(s, -1.0, -t); }
+        case 4u: { return vec3<f32>(s, -t, 1.0); }
+        default: { return vec3<f32>(-s, -t, -1.0); }
+    }
+}
+
+fn load_face(texel: vec2<i32>) -> vec3<f32> {
+    switch (bake_params.face) {
+        case 0u: { return textureLoad(face_x, texel, 0).rgb; }
+        case 1u: { return textureLoad(face_neg_x, texel, 0).rgb; }
+        case 2u: { return textureLoad(face_y, texel, 0).rgb; }
+        case 3u: { return textureLoad(face_neg_y, texel, 0).rgb; }
+        case 4u: { return textureLoad(face_z, texel, 0).rgb; }
+        default: { return textureLoad(face_neg_z, texel, 0).rgb; }
+    }
+}
+
+// Averages the texels of the captured face covered by a texel of the mip level
+@fragment
+fn downsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let block = 1u << bake_params.mip_level;
+    let origin = vec2<u32>(in.position.xy) * block;
+    var sum = vec3<f32>(0.0);
+    for (var y = 0u; y < block; y += 1u) {
+        for (var x = 0u; x < block; x += 1u) {
+            sum += load_face(vec2<i32>(origin + vec2<u32>(x, y)));
+        }
+    }
+    return vec4<f32>(sum / f32(block * block), 1.0);
+}
+
+fn hammersley(i: u32, count: u32) -> vec2<f32> {
+    return vec2<f32>(f32(i) / f32(count), f32(reverseBits(i)) * 2.3283064365386963e-10);
+}
+
+// An orthonormal basis whose third column is `n`
+fn tangent_frame(n: vec3<f32>) -> mat3x3<f32> {
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if (abs(n.y) > 0.999) {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, n));
+    let bitangent = cross(n, tangent);
+    return mat3x3<f32>(tangent, bitangent, n);
+}
+
+let DIFFUSE_SAMPLES: u32 = 64u;
+let SPECULAR_SAMPLES: u32 = 32u;
+
+// The mip level of the radiance map to sample for a sample of probability density `pdf` out of
+// `count`, whose texels cover about the solid angle of the sample: filtered importance sampling
+// only needs a few samples without aliasing
+fn sample_level(pdf: f32, count: u32) -> f32 {
+    let texel_solid_angle = 4.0 * PI / (6.0 * CAPTURE_SIZE * CAPTURE_SIZE);
+    let sample_solid_angle = 1.0 / (f32(count) * max(pdf, 0.0001));
+    return max(0.5 * log2(sample_solid_angle / texel_solid_angle) + 1.0, 0.0);
+}
+
+// The cosine weighted average of the radiance around `n`
+fn prefilter_diffuse(n: vec3<f32>) -> vec3<f32> {
+    let frame = tangent_frame(n);
+    var irradiance = vec3<f32>(0.0);
+    for (var i = 0u; i < DIFFUSE_SAMPLES; i += 1u) {
+        let xi = hammersley(i, DIFFUSE_SAMPLES);
+        let phi = 2.0 * PI * xi.y;
+        let cos_theta = sqrt(1.0 - xi.x);
+        let sin_theta = sqrt(xi.x);
+        let l = vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        let level = sample_level(cos_theta / PI, DIFFUSE_SAMPLES);
+        irradiance += textureSampleLevel(radiance, radiance_sampler, frame * l, level).rgb;
+    }
+    return irradiance / f32(DIFFUSE_SAMPLES);
+}
+
+// The radiance around `r` weighted by the GGX distribution of the roughness, assuming that the
+// view and normal directions are along `r`, like the split sum approximation does
+fn prefilter_specular(r: vec3<f32>, perceptual_roughness: f32) -> vec3<f32> {
+    let alpha = perceptual_roughness * perceptual_roughness;
+    let alpha2 = alpha * alpha;
+    let frame = tangent_frame(r);
+    var prefiltered = vec3<f32>(0.0);
+    var total_weight = 0.0;
+    for (var i = 0u; i < SPECULAR_SAMPLES; i += 1u) {
+        let xi = hammersley(i, SPECULAR_SAMPLES);
+        let phi = 2.0 * PI * xi.y;
+        let cos_theta = sqrt((1.0 - xi.x) / (1.0 + (alpha2 - 1.0) * xi.x));
+        let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+        let h = frame * vec3<f32>(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta);
+        let l = reflect(-r, h);
+        let n_dot_l = dot(r, l);
+        if (n_dot_l > 0.0) {
+            // With the normal and view directions along `r`, the density of `l` is D(h) / 4
+            let d = (cos_theta * cos_theta) * (alpha2 - 1.0) + 1.0;
+            let pdf = alpha2 / (PI * d * d) / 4.0;
+            let level = sample_level(pdf, SPECULAR_SAMPLES);
+            prefiltered += textureSampleLevel(radiance, radiance_sampler, l, level).rgb * n_dot_l;
+            total_weight += n_dot_l;
+        }
+    }
+    return prefiltered / max(total_weight, 0.0001);
+}
+
+@fragment
+fn prefilter(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let direction = normalize(cube_face_direction(bake_params.face, in.uv));
+    if (bake_params.diffuse != 0u) {
+        return vec4<f32>(prefilter_diffuse(direction), 1.0);
+    }
+    if (bake_params.perceptual_roughness == 0.0) {
+        return vec4<f32>(textureSampleLevel(radiance, radiance_sampler, direction, 0.0).rgb, 1.0);
+    }
+    return vec4<f32>(prefilter_specular(direction, bake_params.perceptual_roughness), 1.0);
+}
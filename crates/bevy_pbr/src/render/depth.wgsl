@@ -12,6 +12,13 @@ var<uniform> mesh: Mesh;
 var<uniform> joint_matrices: SkinnedMesh;
 #import bevy_pbr::skinning
 #endif
+#ifdef MORPH_TARGETS
+@group(1) @binding(2)
+var<uniform> morph_weights: MorphWeights;
+@group(1) @binding(3)
+var morph_targets: texture_2d_array<f32>;
+#import bevy_pbr::morph
+#endif
 
 // NOTE: Bindings must come before functions that use them!
 #import bevy_pbr::mesh_functions
@@ -22,6 +29,15 @@ struct Vertex {
     @location(4) joint_indices: vec4<u32>,
     @location(5) joint_weights: vec4<f32>,
 #endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+#ifdef MESH_INSTANCED
+    @location(8) instance_model_0: vec4<f32>,
+    @location(9) instance_model_1: vec4<f32>,
+    @location(10) instance_model_2: vec4<f32>,
+    @location(11) instance_model_3: vec4<f32>,
+#endif
 };
 
 struct VertexOutput {
@@ -29,14 +45,33 @@ struct VertexOutput {
 };
 
 @vertex
-fn vertex(vertex: Vertex) -> VertexOutput {
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#endif
+
 #ifdef SKINNED
     let model = skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+#ifdef MESH_INSTANCED
+    let model = mat4x4<f32>(
+        vertex.instance_model_0,
+        vertex.instance_model_1,
+        vertex.instance_model_2,
+        vertex.instance_model_3
+    );
 #else
     let model = mesh.model;
+#endif
 #endif
 
     var out: VertexOutput;
     out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+#ifdef DEPTH_CLAMP_ORTHO
+    // Shadow casters in front of the near plane of a directional light cascade are flattened onto
+    // the near plane instead of being clipped, so that they still cast shadows into the cascade.
+    out.clip_position.z = min(out.clip_position.z, 1.0);
+#endif
     return out;
 }
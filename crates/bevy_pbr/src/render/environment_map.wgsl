@@ -0,0 +1,28 @@
+#define_import_path bevy_pbr::environment_map
+
+struct EnvironmentMapLight {
+    diffuse: vec3<f32>,
+    specular: vec3<f32>,
+};
+
+// Samples the prefiltered environment maps, given the diffuse and specular ambient terms of the
+// split sum approximation already weighted by `EnvBRDFApprox`.
+fn environment_map_light(
+    perceptual_roughness: f32,
+    N: vec3<f32>,
+    R: vec3<f32>,
+    diffuse_ambient: vec3<f32>,
+    specular_ambient: vec3<f32>,
+) -> EnvironmentMapLight {
+    // The last mip level of the specular map is prefiltered for a perceptual roughness of 1.0
+    let roughness_level = perceptual_roughness * f32(textureNumLevels(environment_map_specular) - 1);
+
+    // Cube maps are sampled with a left-handed coordinate system
+    let irradiance = textureSample(environment_map_diffuse, environment_map_sampler, N * vec3<f32>(1.0, 1.0, -1.0)).rgb;
+    let radiance = textureSampleLevel(environment_map_specular, environment_map_sampler, R * vec3<f32>(1.0, 1.0, -1.0), roughness_level).rgb;
+
+    var out: EnvironmentMapLight;
+    out.diffuse = diffuse_ambient * irradiance;
+    out.specular = specular_ambient * radiance;
+    return out;
+}
@@ -0,0 +1,52 @@
+#define_import_path bevy_pbr::fog
+
+// The fraction of the light of a mesh at `distance` from the camera the distance fog lets through
+fn distance_fog_transmittance(distance: f32) -> f32 {
+    if (fog.mode == FOG_MODE_LINEAR) {
+        let start = fog.falloff.x;
+        let end = fog.falloff.y;
+        return 1.0 - saturate((distance - start) / max(end - start, 0.0001));
+    } else if (fog.mode == FOG_MODE_EXPONENTIAL) {
+        return exp(-fog.falloff.x * distance);
+    } else if (fog.mode == FOG_MODE_EXPONENTIAL_SQUARED) {
+        let optical_depth = fog.falloff.x * distance;
+        return exp(-optical_depth * optical_depth);
+    }
+    return 1.0;
+}
+
+// The fraction of the light of a mesh the height fog lets through, from the density
+// `density * exp(-falloff * (y - height))` integrated along the line of sight
+fn height_fog_transmittance(camera_position: vec3<f32>, world_position: vec3<f32>) -> f32 {
+    let density = fog.height_fog.x;
+    if (density <= 0.0) {
+        return 1.0;
+    }
+    let falloff = fog.height_fog.y;
+    let height = fog.height_fog.z;
+
+    let distance = length(world_position - camera_position);
+    let camera_density = exp(-falloff * (camera_position.y - height));
+    let climb = falloff * (world_position.y - camera_position.y);
+    // The density falls from `camera_density` to `fragment_density` along the line of sight, and
+    // averages to their difference divided by `climb`, or `camera_density` along horizontal ones
+    var average_density = camera_density;
+    if (abs(climb) > 0.0001) {
+        let fragment_density = exp(-falloff * (world_position.y - height));
+        average_density = (camera_density - fragment_density) / climb;
+    }
+    let optical_depth = density * average_density * distance;
+    return exp(-optical_depth);
+}
+
+// Fades the color of a fragment at `world_position` into the fog of the view
+fn apply_fog(color: vec4<f32>, world_position: vec3<f32>, camera_position: vec3<f32>) -> vec4<f32> {
+    if (fog.mode == FOG_MODE_OFF) {
+        return color;
+    }
+    let distance = length(world_position - camera_position);
+    let transmittance = distance_fog_transmittance(distance)
+        * height_fog_transmittance(camera_position, world_position);
+    let fog_opacity = (1.0 - transmittance) * fog.color.a;
+    return vec4<f32>(mix(color.rgb, fog.color.rgb, fog_opacity), color.a);
+}
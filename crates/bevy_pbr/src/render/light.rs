@@ -1,8 +1,9 @@
 use crate::{
-    directional_light_order, point_light_order, AmbientLight, Clusters, CubemapVisibleEntities,
-    DirectionalLight, DirectionalLightShadowMap, DrawMesh, GlobalVisiblePointLights, MeshPipeline,
-    NotShadowCaster, PointLight, PointLightShadowMap, SetMeshBindGroup, SpotLight,
-    VisiblePointLights, SHADOW_SHADER_HANDLE,
+    batch_mesh_instances, directional_light_order, is_skinned, point_light_order, AmbientLight,
+    Cascade, CascadeShadowConfig, Cascades, CascadesVisibleEntities, Clusters,
+    CubemapVisibleEntities, DirectionalLight, DirectionalLightShadowMap, DrawMeshInstanced,
+    GlobalVisiblePointLights, MeshInstance, MeshPipeline, NotShadowCaster, PointLight,
+    PointLightShadowMap, SetMeshBindGroup, SpotLight, VisiblePointLights, SHADOW_SHADER_HANDLE,
 };
 use bevy_asset::Handle;
 use bevy_core_pipeline::core_3d::Transparent3d;
@@ -10,20 +11,20 @@ use bevy_ecs::{
     prelude::*,
     system::{lifetimeless::*, SystemParamItem},
 };
-use bevy_math::{Mat4, UVec3, UVec4, Vec2, Vec3, Vec3A, Vec3Swizzles, Vec4, Vec4Swizzles};
+use bevy_math::{Mat4, UVec3, UVec4, Vec2, Vec3, Vec3Swizzles, Vec4, Vec4Swizzles};
 use bevy_render::{
-    camera::{Camera, CameraProjection},
+    camera::{Camera, PhysicalCameraParameters},
     color::Color,
     mesh::{Mesh, MeshVertexBufferLayout},
     render_asset::RenderAssets,
     render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
     render_phase::{
-        CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions, EntityPhaseItem,
-        EntityRenderCommand, PhaseItem, RenderCommandResult, RenderPhase, SetItemPipeline,
-        TrackedRenderPass,
+        BatchedPhaseItem, CachedRenderPipelinePhaseItem, DrawFunctionId, DrawFunctions,
+        EntityPhaseItem, EntityRenderCommand, PhaseItem, RenderCommandResult, RenderPhase,
+        SetItemPipeline, TrackedRenderPass,
     },
     render_resource::*,
-    renderer::{RenderContext, RenderDevice, RenderQueue},
+    renderer::{RenderContext, RenderDevice, RenderQueue, StagingBelt},
     texture::*,
     view::{
         ComputedVisibility, ExtractedView, ViewUniform, ViewUniformOffset, ViewUniforms,
@@ -37,7 +38,10 @@ use bevy_utils::{
     tracing::{error, warn},
     HashMap,
 };
-use std::num::{NonZeroU32, NonZeroU64};
+use std::{
+    num::{NonZeroU32, NonZeroU64},
+    ops::Range,
+};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum RenderLightSystems {
@@ -67,10 +71,11 @@ pub struct ExtractedDirectionalLight {
     color: Color,
     illuminance: f32,
     transform: GlobalTransform,
-    projection: Mat4,
     shadows_enabled: bool,
     shadow_depth_bias: f32,
     shadow_normal_bias: f32,
+    cascade_shadow_config: CascadeShadowConfig,
+    cascades: HashMap<Entity, Vec<Cascade>>,
 }
 
 #[derive(Copy, Clone, ShaderType, Default, Debug)]
@@ -141,10 +146,14 @@ impl GpuPointLights {
         }
     }
 
-    fn write_buffer(&mut self, render_device: &RenderDevice, render_queue: &RenderQueue) {
+    fn write_buffer_staged(&mut self, render_device: &RenderDevice, staging_belt: &StagingBelt) {
         match self {
-            GpuPointLights::Uniform(buffer) => buffer.write_buffer(render_device, render_queue),
-            GpuPointLights::Storage(buffer) => buffer.write_buffer(render_device, render_queue),
+            GpuPointLights::Uniform(buffer) => {
+                buffer.write_buffer_staged(render_device, staging_belt);
+            }
+            GpuPointLights::Storage(buffer) => {
+                buffer.write_buffer_staged(render_device, staging_belt);
+            }
         }
     }
 
@@ -155,6 +164,13 @@ impl GpuPointLights {
         }
     }
 
+    pub fn cached_binding(&self) -> Option<CachedBindingResource> {
+        match self {
+            GpuPointLights::Uniform(buffer) => buffer.cached_binding(),
+            GpuPointLights::Storage(buffer) => buffer.cached_binding(),
+        }
+    }
+
     pub fn min_size(buffer_binding_type: BufferBindingType) -> NonZeroU64 {
         match buffer_binding_type {
             BufferBindingType::Storage { .. } => GpuPointLightsStorage::min_size(),
@@ -175,13 +191,23 @@ bitflags::bitflags! {
 }
 
 #[derive(Copy, Clone, ShaderType, Default, Debug)]
-pub struct GpuDirectionalLight {
+pub struct GpuDirectionalCascade {
     view_projection: Mat4,
+    texel_size: f32,
+    far_bound: f32,
+}
+
+#[derive(Copy, Clone, ShaderType, Default, Debug)]
+pub struct GpuDirectionalLight {
+    cascades: [GpuDirectionalCascade; MAX_CASCADES_PER_LIGHT],
     color: Vec4,
     dir_to_light: Vec3,
     flags: u32,
     shadow_depth_bias: f32,
     shadow_normal_bias: f32,
+    num_cascades: u32,
+    cascades_overlap_proportion: f32,
+    depth_texture_base_index: u32,
 }
 
 // NOTE: These must match the bit flags in bevy_pbr/src/render/mesh_view_types.wgsl!
@@ -195,6 +221,7 @@ bitflags::bitflags! {
 }
 
 #[derive(Copy, Clone, Debug, ShaderType)]
+#[shader_type(uniform)]
 pub struct GpuLights {
     directional_lights: [GpuDirectionalLight; MAX_DIRECTIONAL_LIGHTS],
     ambient_color: Vec4,
@@ -212,6 +239,10 @@ pub struct GpuLights {
 // NOTE: this must be kept in sync with the same constants in pbr.frag
 pub const MAX_UNIFORM_BUFFER_POINT_LIGHTS: usize = 256;
 pub const MAX_DIRECTIONAL_LIGHTS: usize = 10;
+#[cfg(not(feature = "webgl"))]
+pub const MAX_CASCADES_PER_LIGHT: usize = 4;
+#[cfg(feature = "webgl")]
+pub const MAX_CASCADES_PER_LIGHT: usize = 1;
 pub const SHADOW_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 
 #[derive(Resource)]
@@ -219,6 +250,8 @@ pub struct ShadowPipeline {
     pub view_layout: BindGroupLayout,
     pub mesh_layout: BindGroupLayout,
     pub skinned_mesh_layout: BindGroupLayout,
+    pub morphed_mesh_layout: BindGroupLayout,
+    pub morphed_skinned_mesh_layout: BindGroupLayout,
     pub point_light_sampler: Sampler,
     pub directional_light_sampler: Sampler,
 }
@@ -252,6 +285,8 @@ impl FromWorld for ShadowPipeline {
             view_layout,
             mesh_layout: mesh_pipeline.mesh_layout.clone(),
             skinned_mesh_layout,
+            morphed_mesh_layout: mesh_pipeline.morphed_mesh_layout.clone(),
+            morphed_skinned_mesh_layout: mesh_pipeline.morphed_skinned_mesh_layout.clone(),
             point_light_sampler: render_device.create_sampler(&SamplerDescriptor {
                 address_mode_u: AddressMode::ClampToEdge,
                 address_mode_v: AddressMode::ClampToEdge,
@@ -280,6 +315,9 @@ bitflags::bitflags! {
     #[repr(transparent)]
     pub struct ShadowPipelineKey: u32 {
         const NONE               = 0;
+        const DEPTH_CLAMP_ORTHO  = (1 << 0);
+        const INSTANCED          = (1 << 1);
+        const MORPH_TARGETS      = (1 << 2);
         const PRIMITIVE_TOPOLOGY_RESERVED_BITS = ShadowPipelineKey::PRIMITIVE_TOPOLOGY_MASK_BITS << ShadowPipelineKey::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
     }
 }
@@ -325,29 +363,48 @@ impl SpecializedMeshPipeline for ShadowPipeline {
             "MAX_DIRECTIONAL_LIGHTS".to_string(),
             MAX_DIRECTIONAL_LIGHTS as u32,
         ));
+        shader_defs.push(ShaderDefVal::UInt(
+            "MAX_CASCADES_PER_LIGHT".to_string(),
+            MAX_CASCADES_PER_LIGHT as u32,
+        ));
 
-        if layout.contains(Mesh::ATTRIBUTE_JOINT_INDEX)
-            && layout.contains(Mesh::ATTRIBUTE_JOINT_WEIGHT)
-        {
+        if key.contains(ShadowPipelineKey::DEPTH_CLAMP_ORTHO) {
+            shader_defs.push("DEPTH_CLAMP_ORTHO".into());
+        }
+
+        let skinned = is_skinned(layout);
+        if skinned {
             shader_defs.push("SKINNED".into());
             vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_INDEX.at_shader_location(4));
             vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_WEIGHT.at_shader_location(5));
-            bind_group_layout.push(self.skinned_mesh_layout.clone());
-        } else {
-            bind_group_layout.push(self.mesh_layout.clone());
         }
+        let morphed = key.contains(ShadowPipelineKey::MORPH_TARGETS);
+        if morphed {
+            shader_defs.push("MORPH_TARGETS".into());
+        }
+        bind_group_layout.push(match (skinned, morphed) {
+            (false, false) => self.mesh_layout.clone(),
+            (true, false) => self.skinned_mesh_layout.clone(),
+            (false, true) => self.morphed_mesh_layout.clone(),
+            (true, true) => self.morphed_skinned_mesh_layout.clone(),
+        });
 
-        let vertex_buffer_layout = layout.get_layout(&vertex_attributes)?;
+        let mut vertex_buffer_layouts = vec![layout.get_layout(&vertex_attributes)?];
+        if key.contains(ShadowPipelineKey::INSTANCED) {
+            shader_defs.push("MESH_INSTANCED".into());
+            vertex_buffer_layouts.push(MeshInstance::vertex_buffer_layout());
+        }
 
         Ok(RenderPipelineDescriptor {
             vertex: VertexState {
                 shader: SHADOW_SHADER_HANDLE.typed::<Shader>(),
                 entry_point: "vertex".into(),
                 shader_defs,
-                buffers: vec![vertex_buffer_layout],
+                buffers: vertex_buffer_layouts,
             },
             fragment: None,
             layout: Some(bind_group_layout),
+            push_constant_ranges: Vec::new(),
             primitive: PrimitiveState {
                 topology: key.primitive_topology(),
                 strip_index_format: None,
@@ -438,7 +495,9 @@ pub fn extract_lights(
             (
                 Entity,
                 &DirectionalLight,
-                &VisibleEntities,
+                &CascadesVisibleEntities,
+                &Cascades,
+                &CascadeShadowConfig,
                 &GlobalTransform,
                 &ComputedVisibility,
             ),
@@ -537,7 +596,12 @@ pub fn extract_lights(
                         shadow_normal_bias: spot_light.shadow_normal_bias
                             * texel_size
                             * std::f32::consts::SQRT_2,
-                        spot_light_angles: Some((spot_light.inner_angle, spot_light.outer_angle)),
+                        // An inner angle beyond the outer angle would collapse the falloff into a
+                        // hard edge, so it is clamped to the outer angle
+                        spot_light_angles: Some((
+                            spot_light.inner_angle.min(spot_light.outer_angle),
+                            spot_light.outer_angle,
+                        )),
                     },
                     render_visible_entities,
                 ),
@@ -547,22 +611,20 @@ pub fn extract_lights(
     *previous_spot_lights_len = spot_lights_values.len();
     commands.insert_or_spawn_batch(spot_lights_values);
 
-    for (entity, directional_light, visible_entities, transform, visibility) in
-        directional_lights.iter()
+    for (
+        entity,
+        directional_light,
+        visible_entities,
+        cascades,
+        cascade_config,
+        transform,
+        visibility,
+    ) in directional_lights.iter()
     {
         if !visibility.is_visible() {
             continue;
         }
 
-        // Calculate the directional light shadow map texel size using the scaled x,y length of
-        // the orthographic projection divided by the shadow map resolution
-        // NOTE: When using various PCF kernel sizes, this will need to be adjusted, according to:
-        // https://catlikecoding.com/unity/tutorials/custom-srp/directional-shadows/
-        let directional_light_texel_size = transform.radius_vec3a(Vec3A::new(
-            directional_light.shadow_projection.right - directional_light.shadow_projection.left,
-            directional_light.shadow_projection.top - directional_light.shadow_projection.bottom,
-            0.,
-        )) / directional_light_shadow_map.size as f32;
         // TODO: As above
         let render_visible_entities = visible_entities.clone();
         commands.get_or_spawn(entity).insert((
@@ -570,11 +632,13 @@ pub fn extract_lights(
                 color: directional_light.color,
                 illuminance: directional_light.illuminance,
                 transform: *transform,
-                projection: directional_light.shadow_projection.get_projection_matrix(),
                 shadows_enabled: directional_light.shadows_enabled,
                 shadow_depth_bias: directional_light.shadow_depth_bias,
-                shadow_normal_bias: directional_light.shadow_normal_bias
-                    * directional_light_texel_size,
+                // The factor of SQRT_2 is for the worst-case diagonal offset. The bias is scaled
+                // to the texel size of each cascade in the shader.
+                shadow_normal_bias: directional_light.shadow_normal_bias * std::f32::consts::SQRT_2,
+                cascade_shadow_config: cascade_config.clone(),
+                cascades: cascades.cascades.clone(),
             },
             render_visible_entities,
         ));
@@ -653,6 +717,15 @@ pub struct ViewLightEntities {
     pub lights: Vec<Entity>,
 }
 
+/// The factor applied to the physical light units of the lights, like the lux of the
+/// [`DirectionalLight`]s, to get the values the shaders work with.
+///
+/// This is the exposure of the default [`PhysicalCameraParameters`]. The cameras with other
+/// parameters apply the difference in their color grading, before tonemapping.
+pub(crate) fn exposure() -> f32 {
+    PhysicalCameraParameters::default().exposure()
+}
+
 #[derive(Component)]
 pub struct ViewLightsUniformOffset {
     pub offset: u32,
@@ -687,16 +760,26 @@ impl GlobalLightMeta {
     }
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct LightMeta {
     pub view_gpu_lights: DynamicUniformBuffer<GpuLights>,
     pub shadow_view_bind_group: Option<BindGroup>,
 }
 
+impl FromWorld for LightMeta {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            view_gpu_lights: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+            shadow_view_bind_group: None,
+        }
+    }
+}
+
 #[derive(Component)]
 pub enum LightEntity {
     Directional {
         light_entity: Entity,
+        cascade_index: usize,
     },
     Point {
         light_entity: Entity,
@@ -758,9 +841,9 @@ pub(crate) fn spot_light_projection_matrix(angle: f32) -> Mat4 {
 #[allow(clippy::too_many_arguments)]
 pub fn prepare_lights(
     mut commands: Commands,
-    mut texture_cache: ResMut<TextureCache>,
+    texture_cache: Res<TextureCache>,
     render_device: Res<RenderDevice>,
-    render_queue: Res<RenderQueue>,
+    staging_belt: Res<StagingBelt>,
     mut global_light_meta: ResMut<GlobalLightMeta>,
     mut light_meta: ResMut<LightMeta>,
     views: Query<
@@ -819,19 +902,6 @@ pub fn prepare_lights(
         .count()
         .min(max_texture_cubes);
 
-    let directional_shadow_maps_count = directional_lights
-        .iter()
-        .take(MAX_DIRECTIONAL_LIGHTS)
-        .filter(|(_, light)| light.shadows_enabled)
-        .count()
-        .min(max_texture_array_layers);
-
-    let spot_light_shadow_maps_count = point_lights
-        .iter()
-        .filter(|(_, light)| light.shadows_enabled && light.spot_light_angles.is_some())
-        .count()
-        .min(max_texture_array_layers - directional_shadow_maps_count);
-
     // Sort lights by
     // - point-light vs spot-light, so that we can iterate point lights and spot lights in contiguous blocks in the fragment shader,
     // - then those with shadows enabled first, so that the index can be used to render at most `point_light_shadow_maps_count`
@@ -863,6 +933,34 @@ pub fn prepare_lights(
         )
     });
 
+    // Each cascade of a directional light with shadows enabled uses one layer of the
+    // directional light shadow map texture array, so only enable shadows for as many lights as
+    // all of their cascades fit into the array.
+    let mut directional_shadow_enabled_count = 0;
+    let mut num_directional_cascades_enabled = 0;
+    for (_, light) in directional_lights
+        .iter()
+        .take(MAX_DIRECTIONAL_LIGHTS)
+        .filter(|(_, light)| light.shadows_enabled)
+    {
+        let num_cascades = light
+            .cascade_shadow_config
+            .bounds
+            .len()
+            .min(MAX_CASCADES_PER_LIGHT);
+        if num_directional_cascades_enabled + num_cascades > max_texture_array_layers {
+            break;
+        }
+        directional_shadow_enabled_count += 1;
+        num_directional_cascades_enabled += num_cascades;
+    }
+
+    let spot_light_shadow_maps_count = point_lights
+        .iter()
+        .filter(|(_, light)| light.shadows_enabled && light.spot_light_angles.is_some())
+        .count()
+        .min(max_texture_array_layers - num_directional_cascades_enabled);
+
     if global_light_meta.entity_to_index.capacity() < point_lights.len() {
         global_light_meta
             .entity_to_index
@@ -932,6 +1030,7 @@ pub fn prepare_lights(
     }
 
     let mut gpu_directional_lights = [GpuDirectionalLight::default(); MAX_DIRECTIONAL_LIGHTS];
+    let mut directional_depth_texture_base_index = 0;
 
     for (index, (_light_entity, light)) in directional_lights
         .iter()
@@ -939,49 +1038,45 @@ pub fn prepare_lights(
         .take(MAX_DIRECTIONAL_LIGHTS)
     {
         let mut flags = DirectionalLightFlags::NONE;
+        let num_cascades = light
+            .cascade_shadow_config
+            .bounds
+            .len()
+            .min(MAX_CASCADES_PER_LIGHT);
+        let depth_texture_base_index = directional_depth_texture_base_index;
 
         // Lights are sorted, shadow enabled lights are first
-        if light.shadows_enabled && (index < directional_shadow_maps_count) {
+        if light.shadows_enabled && (index < directional_shadow_enabled_count) {
             flags |= DirectionalLightFlags::SHADOWS_ENABLED;
+            directional_depth_texture_base_index += num_cascades;
         }
 
         // direction is negated to be ready for N.L
         let dir_to_light = light.transform.back();
 
         // convert from illuminance (lux) to candelas
-        //
-        // exposure is hard coded at the moment but should be replaced
-        // by values coming from the camera
-        // see: https://google.github.io/filament/Filament.html#imagingpipeline/physicallybasedcamera/exposuresettings
-        const APERTURE: f32 = 4.0;
-        const SHUTTER_SPEED: f32 = 1.0 / 250.0;
-        const SENSITIVITY: f32 = 100.0;
-        let ev100 = f32::log2(APERTURE * APERTURE / SHUTTER_SPEED) - f32::log2(SENSITIVITY / 100.0);
-        let exposure = 1.0 / (f32::powf(2.0, ev100) * 1.2);
-        let intensity = light.illuminance * exposure;
-
-        // NOTE: For the purpose of rendering shadow maps, we apply the directional light's transform to an orthographic camera
-        let view = light.transform.compute_matrix().inverse();
-        // NOTE: This orthographic projection defines the volume within which shadows from a directional light can be cast
-        let projection = light.projection;
+        let intensity = light.illuminance * exposure();
 
         gpu_directional_lights[index] = GpuDirectionalLight {
+            // Filled in later, as the cascades depend on the view.
+            cascades: [GpuDirectionalCascade::default(); MAX_CASCADES_PER_LIGHT],
             // premultiply color by intensity
             // we don't use the alpha at all, so no reason to multiply only [0..3]
             color: Vec4::from_slice(&light.color.as_linear_rgba_f32()) * intensity,
             dir_to_light,
-            // NOTE: * view is correct, it should not be view.inverse() here
-            view_projection: projection * view,
             flags: flags.bits,
             shadow_depth_bias: light.shadow_depth_bias,
             shadow_normal_bias: light.shadow_normal_bias,
+            num_cascades: num_cascades as u32,
+            cascades_overlap_proportion: light.cascade_shadow_config.overlap_proportion,
+            depth_texture_base_index: depth_texture_base_index as u32,
         };
     }
 
     global_light_meta.gpu_point_lights.set(gpu_point_lights);
     global_light_meta
         .gpu_point_lights
-        .write_buffer(&render_device, &render_queue);
+        .write_buffer_staged(&render_device, &staging_belt);
 
     // set up light data for each view
     for (entity, extracted_view, clusters) in &views {
@@ -1009,7 +1104,7 @@ pub fn prepare_lights(
                         .min(render_device.limits().max_texture_dimension_2d),
                     height: (directional_light_shadow_map.size as u32)
                         .min(render_device.limits().max_texture_dimension_2d),
-                    depth_or_array_layers: (directional_shadow_maps_count
+                    depth_or_array_layers: (num_directional_cascades_enabled
                         + spot_light_shadow_maps_count)
                         .max(1) as u32,
                 },
@@ -1032,7 +1127,7 @@ pub fn prepare_lights(
         );
 
         let n_clusters = clusters.dimensions.x * clusters.dimensions.y * clusters.dimensions.z;
-        let gpu_lights = GpuLights {
+        let mut gpu_lights = GpuLights {
             directional_lights: gpu_directional_lights,
             ambient_color: Vec4::from_slice(&ambient_light.color.as_linear_rgba_f32())
                 * ambient_light.brightness,
@@ -1044,10 +1139,10 @@ pub fn prepare_lights(
             ),
             cluster_dimensions: clusters.dimensions.extend(n_clusters),
             n_directional_lights: directional_lights.iter().len() as u32,
-            // spotlight shadow maps are stored in the directional light array, starting at directional_shadow_maps_count.
+            // spotlight shadow maps are stored in the directional light array, starting at num_directional_cascades_enabled.
             // the spot lights themselves start in the light array at point_light_count. so to go from light
             // index to shadow map index, we need to subtract point light count and add directional shadowmap count.
-            spot_light_shadowmap_offset: directional_shadow_maps_count as i32
+            spot_light_shadowmap_offset: num_directional_cascades_enabled as i32
                 - point_light_count as i32,
         };
 
@@ -1138,7 +1233,7 @@ pub fn prepare_lights(
                         aspect: TextureAspect::All,
                         base_mip_level: 0,
                         mip_level_count: None,
-                        base_array_layer: (directional_shadow_maps_count + light_index) as u32,
+                        base_array_layer: (num_directional_cascades_enabled + light_index) as u32,
                         array_layer_count: NonZeroU32::new(1),
                     });
 
@@ -1171,44 +1266,77 @@ pub fn prepare_lights(
         for (light_index, &(light_entity, light)) in directional_lights
             .iter()
             .enumerate()
-            .take(directional_shadow_maps_count)
+            .take(directional_shadow_enabled_count)
         {
-            let depth_texture_view =
-                directional_light_depth_texture
-                    .texture
-                    .create_view(&TextureViewDescriptor {
-                        label: Some("directional_light_shadow_map_texture_view"),
-                        format: None,
-                        dimension: Some(TextureViewDimension::D2),
-                        aspect: TextureAspect::All,
-                        base_mip_level: 0,
-                        mip_level_count: None,
-                        base_array_layer: light_index as u32,
-                        array_layer_count: NonZeroU32::new(1),
-                    });
+            let gpu_light = &mut gpu_lights.directional_lights[light_index];
+            let cascades = match light.cascades.get(&entity) {
+                Some(cascades) => cascades,
+                None => {
+                    // The cascades for this view have not been computed (yet), so it can't
+                    // receive shadows from this light.
+                    gpu_light.num_cascades = 0;
+                    continue;
+                }
+            };
 
-            let view_light_entity = commands
-                .spawn((
-                    ShadowView {
-                        depth_texture_view,
-                        pass_name: format!("shadow pass directional light {}", light_index),
-                    },
-                    ExtractedView {
-                        viewport: UVec4::new(
-                            0,
-                            0,
-                            directional_light_shadow_map.size as u32,
-                            directional_light_shadow_map.size as u32,
-                        ),
-                        transform: light.transform,
-                        projection: light.projection,
-                        hdr: false,
-                    },
-                    RenderPhase::<Shadow>::default(),
-                    LightEntity::Directional { light_entity },
-                ))
-                .id();
-            view_lights.push(view_light_entity);
+            let num_cascades = (gpu_light.num_cascades as usize).min(cascades.len());
+            gpu_light.num_cascades = num_cascades as u32;
+            for (cascade_index, (cascade, bound)) in cascades
+                .iter()
+                .zip(&light.cascade_shadow_config.bounds)
+                .take(num_cascades)
+                .enumerate()
+            {
+                gpu_light.cascades[cascade_index] = GpuDirectionalCascade {
+                    view_projection: cascade.view_projection,
+                    texel_size: cascade.texel_size,
+                    far_bound: *bound,
+                };
+
+                let depth_texture_view =
+                    directional_light_depth_texture
+                        .texture
+                        .create_view(&TextureViewDescriptor {
+                            label: Some("directional_light_shadow_map_texture_view"),
+                            format: None,
+                            dimension: Some(TextureViewDimension::D2),
+                            aspect: TextureAspect::All,
+                            base_mip_level: 0,
+                            mip_level_count: None,
+                            base_array_layer: gpu_light.depth_texture_base_index
+                                + cascade_index as u32,
+                            array_layer_count: NonZeroU32::new(1),
+                        });
+
+                let view_light_entity = commands
+                    .spawn((
+                        ShadowView {
+                            depth_texture_view,
+                            pass_name: format!(
+                                "shadow pass directional light {} cascade {}",
+                                light_index, cascade_index
+                            ),
+                        },
+                        ExtractedView {
+                            viewport: UVec4::new(
+                                0,
+                                0,
+                                directional_light_shadow_map.size as u32,
+                                directional_light_shadow_map.size as u32,
+                            ),
+                            transform: GlobalTransform::from(cascade.view_transform),
+                            projection: cascade.projection,
+                            hdr: false,
+                        },
+                        RenderPhase::<Shadow>::default(),
+                        LightEntity::Directional {
+                            light_entity,
+                            cascade_index,
+                        },
+                    ))
+                    .id();
+                view_lights.push(view_light_entity);
+            }
         }
 
         let point_light_depth_texture_view =
@@ -1261,7 +1389,7 @@ pub fn prepare_lights(
 
     light_meta
         .view_gpu_lights
-        .write_buffer(&render_device, &render_queue);
+        .write_buffer_staged(&render_device, &staging_belt);
 }
 
 // this must match CLUSTER_COUNT_SIZE in pbr.wgsl
@@ -1502,6 +1630,19 @@ impl ViewClusterBindings {
         }
     }
 
+    pub fn cached_light_index_lists_binding(&self) -> Option<CachedBindingResource> {
+        match &self.buffers {
+            ViewClusterBuffers::Uniform {
+                cluster_light_index_lists,
+                ..
+            } => cluster_light_index_lists.cached_binding(),
+            ViewClusterBuffers::Storage {
+                cluster_light_index_lists,
+                ..
+            } => cluster_light_index_lists.cached_binding(),
+        }
+    }
+
     pub fn offsets_and_counts_binding(&self) -> Option<BindingResource> {
         match &self.buffers {
             ViewClusterBuffers::Uniform {
@@ -1515,6 +1656,19 @@ impl ViewClusterBindings {
         }
     }
 
+    pub fn cached_offsets_and_counts_binding(&self) -> Option<CachedBindingResource> {
+        match &self.buffers {
+            ViewClusterBuffers::Uniform {
+                cluster_offsets_and_counts,
+                ..
+            } => cluster_offsets_and_counts.cached_binding(),
+            ViewClusterBuffers::Storage {
+                cluster_offsets_and_counts,
+                ..
+            } => cluster_offsets_and_counts.cached_binding(),
+        }
+    }
+
     pub fn min_size_cluster_light_index_lists(
         buffer_binding_type: BufferBindingType,
     ) -> NonZeroU64 {
@@ -1628,21 +1782,36 @@ pub fn queue_shadows(
     render_meshes: Res<RenderAssets<Mesh>>,
     mut pipelines: ResMut<SpecializedMeshPipelines<ShadowPipeline>>,
     mut pipeline_cache: ResMut<PipelineCache>,
-    view_lights: Query<&ViewLightEntities>,
+    view_lights: Query<(Entity, &ViewLightEntities)>,
     mut view_light_shadow_phases: Query<(&LightEntity, &mut RenderPhase<Shadow>)>,
     point_light_entities: Query<&CubemapVisibleEntities, With<ExtractedPointLight>>,
-    directional_light_entities: Query<&VisibleEntities, With<ExtractedDirectionalLight>>,
+    directional_light_entities: Query<&CascadesVisibleEntities, With<ExtractedDirectionalLight>>,
     spot_light_entities: Query<&VisibleEntities, With<ExtractedPointLight>>,
 ) {
-    for view_lights in &view_lights {
+    for (entity, view_lights) in &view_lights {
         let draw_shadow_mesh = shadow_draw_functions.read().id::<DrawShadowMesh>();
         for view_light_entity in view_lights.lights.iter().copied() {
             let (light_entity, mut shadow_phase) =
                 view_light_shadow_phases.get_mut(view_light_entity).unwrap();
+            let is_directional_light = matches!(light_entity, LightEntity::Directional { .. });
             let visible_entities = match light_entity {
-                LightEntity::Directional { light_entity } => directional_light_entities
-                    .get(*light_entity)
-                    .expect("Failed to get directional light visible entities"),
+                LightEntity::Directional {
+                    light_entity,
+                    cascade_index,
+                } => {
+                    // NOTE: A light may not have visible entities for a view that was only added
+                    // this frame, in which case there is nothing to queue for its cascades yet.
+                    match directional_light_entities
+                        .get(*light_entity)
+                        .expect("Failed to get directional light visible entities")
+                        .entities
+                        .get(&entity)
+                        .and_then(|view_entities| view_entities.get(*cascade_index))
+                    {
+                        Some(visible_entities) => visible_entities,
+                        None => continue,
+                    }
+                }
                 LightEntity::Point {
                     light_entity,
                     face_index,
@@ -1659,8 +1828,18 @@ pub fn queue_shadows(
             for entity in visible_entities.iter().copied() {
                 if let Ok(mesh_handle) = casting_meshes.get(entity) {
                     if let Some(mesh) = render_meshes.get(mesh_handle) {
-                        let key =
+                        let mut key =
                             ShadowPipelineKey::from_primitive_topology(mesh.primitive_topology);
+                        if is_directional_light {
+                            key |= ShadowPipelineKey::DEPTH_CLAMP_ORTHO;
+                        }
+                        if mesh.morph_targets.is_some() {
+                            key |= ShadowPipelineKey::MORPH_TARGETS;
+                        }
+                        let instanced = !is_skinned(&mesh.layout) && mesh.morph_targets.is_none();
+                        if instanced {
+                            key |= ShadowPipelineKey::INSTANCED;
+                        }
                         let pipeline_id = pipelines.specialize(
                             &mut pipeline_cache,
                             &shadow_pipeline,
@@ -1681,6 +1860,7 @@ pub fn queue_shadows(
                             pipeline: pipeline_id,
                             entity,
                             distance: 0.0, // TODO: sort back-to-front
+                            batch_range: instanced.then_some(0..1),
                         });
                     }
                 }
@@ -1694,6 +1874,8 @@ pub struct Shadow {
     pub entity: Entity,
     pub pipeline: CachedRenderPipelineId,
     pub draw_function: DrawFunctionId,
+    /// Range in the instance buffer of this item
+    pub batch_range: Option<Range<u32>>,
 }
 
 impl PhaseItem for Shadow {
@@ -1728,17 +1910,49 @@ impl CachedRenderPipelinePhaseItem for Shadow {
     }
 }
 
+impl BatchedPhaseItem for Shadow {
+    fn batch_range(&self) -> &Option<Range<u32>> {
+        &self.batch_range
+    }
+
+    fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+        &mut self.batch_range
+    }
+}
+
+/// Merges consecutive shadow casters sharing the same mesh into instanced draws.
+pub fn batch_shadows(
+    shadow_draw_functions: Res<DrawFunctions<Shadow>>,
+    casting_meshes: Query<&Handle<Mesh>>,
+    mut shadow_phases: Query<&mut RenderPhase<Shadow>>,
+) {
+    let draw_shadow_mesh = shadow_draw_functions.read().id::<DrawShadowMesh>();
+    for mut shadow_phase in &mut shadow_phases {
+        batch_mesh_instances(&mut shadow_phase, draw_shadow_mesh, |a, b| {
+            same_casting_mesh(&casting_meshes, a, b)
+        });
+    }
+}
+
+/// Whether both shadow casters were found and draw the same mesh.
+fn same_casting_mesh(casting_meshes: &Query<&Handle<Mesh>>, a: Entity, b: Entity) -> bool {
+    matches!(
+        (casting_meshes.get(a), casting_meshes.get(b)),
+        (Ok(mesh_a), Ok(mesh_b)) if mesh_a == mesh_b
+    )
+}
+
+/// Draws the [`Shadow`] phase of a light view into its shadow map. It is the node of the
+/// [`shadow_graph`](crate::shadow_graph), run for each light view casting shadows on a camera.
 pub struct ShadowPassNode {
-    main_view_query: QueryState<&'static ViewLightEntities>,
     view_light_query: QueryState<(&'static ShadowView, &'static RenderPhase<Shadow>)>,
 }
 
 impl ShadowPassNode {
-    pub const IN_VIEW: &'static str = "view";
+    pub const IN_VIEW_LIGHT: &'static str = "view_light";
 
     pub fn new(world: &mut World) -> Self {
         Self {
-            main_view_query: QueryState::new(world),
             view_light_query: QueryState::new(world),
         }
     }
@@ -1746,11 +1960,13 @@ impl ShadowPassNode {
 
 impl Node for ShadowPassNode {
     fn input(&self) -> Vec<SlotInfo> {
-        vec![SlotInfo::new(ShadowPassNode::IN_VIEW, SlotType::Entity)]
+        vec![SlotInfo::new(
+            ShadowPassNode::IN_VIEW_LIGHT,
+            SlotType::Entity,
+        )]
     }
 
     fn update(&mut self, world: &mut World) {
-        self.main_view_query.update_archetypes(world);
         self.view_light_query.update_archetypes(world);
     }
 
@@ -1760,42 +1976,38 @@ impl Node for ShadowPassNode {
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
-        if let Ok(view_lights) = self.main_view_query.get_manual(world, view_entity) {
-            for view_light_entity in view_lights.lights.iter().copied() {
-                let (view_light, shadow_phase) = self
-                    .view_light_query
-                    .get_manual(world, view_light_entity)
-                    .unwrap();
-
-                if shadow_phase.items.is_empty() {
-                    continue;
-                }
+        let view_light_entity = graph.get_input_entity(Self::IN_VIEW_LIGHT)?;
+        let Ok((view_light, shadow_phase)) =
+            self.view_light_query.get_manual(world, view_light_entity)
+        else {
+            return Ok(());
+        };
+        if shadow_phase.items.is_empty() {
+            return Ok(());
+        }
 
-                let pass_descriptor = RenderPassDescriptor {
-                    label: Some(&view_light.pass_name),
-                    color_attachments: &[],
-                    depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                        view: &view_light.depth_texture_view,
-                        depth_ops: Some(Operations {
-                            load: LoadOp::Clear(0.0),
-                            store: true,
-                        }),
-                        stencil_ops: None,
-                    }),
-                };
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some(&view_light.pass_name),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &view_light.depth_texture_view,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(0.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        };
 
-                let draw_functions = world.resource::<DrawFunctions<Shadow>>();
-                let render_pass = render_context
-                    .command_encoder
-                    .begin_render_pass(&pass_descriptor);
-                let mut draw_functions = draw_functions.write();
-                let mut tracked_pass = TrackedRenderPass::new(render_pass);
-                for item in &shadow_phase.items {
-                    let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
-                    draw_function.draw(world, &mut tracked_pass, view_light_entity, item);
-                }
-            }
+        let draw_functions = world.resource::<DrawFunctions<Shadow>>();
+        let render_pass = render_context
+            .command_encoder
+            .begin_render_pass(&pass_descriptor);
+        let mut draw_functions = draw_functions.write();
+        let mut tracked_pass = TrackedRenderPass::new(render_pass);
+        for item in &shadow_phase.items {
+            let draw_function = draw_functions.get_mut(item.draw_function).unwrap();
+            draw_function.draw(world, &mut tracked_pass, view_light_entity, item);
         }
 
         Ok(())
@@ -1806,7 +2018,7 @@ pub type DrawShadowMesh = (
     SetItemPipeline,
     SetShadowViewBindGroup<0>,
     SetMeshBindGroup<1>,
-    DrawMesh,
+    DrawMeshInstanced,
 );
 
 pub struct SetShadowViewBindGroup<const I: usize>;
@@ -1833,3 +2045,33 @@ impl<const I: usize> EntityRenderCommand for SetShadowViewBindGroup<I> {
         RenderCommandResult::Success
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::same_casting_mesh;
+    use bevy_asset::{Handle, HandleId};
+    use bevy_ecs::{
+        prelude::World,
+        system::{Query, SystemState},
+    };
+    use bevy_render::mesh::Mesh;
+
+    #[test]
+    fn casting_meshes_need_to_be_found() {
+        let mut world = World::new();
+        let mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let other_mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let a = world.spawn(mesh.clone()).id();
+        let b = world.spawn(mesh).id();
+        let c = world.spawn(other_mesh).id();
+        let missing = world.spawn_empty().id();
+        let other_missing = world.spawn_empty().id();
+
+        let mut state = SystemState::<Query<&Handle<Mesh>>>::new(&mut world);
+        let casting_meshes = state.get(&world);
+        assert!(same_casting_mesh(&casting_meshes, a, b));
+        assert!(!same_casting_mesh(&casting_meshes, a, c));
+        assert!(!same_casting_mesh(&casting_meshes, a, missing));
+        assert!(!same_casting_mesh(&casting_meshes, missing, other_missing));
+    }
+}
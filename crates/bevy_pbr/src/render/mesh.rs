@@ -1,25 +1,39 @@
 use crate::{
-    GlobalLightMeta, GpuLights, GpuPointLights, LightMeta, NotShadowCaster, NotShadowReceiver,
-    ShadowPipeline, ViewClusterBindings, ViewLightsUniformOffset, ViewShadowBindings,
-    CLUSTERED_FORWARD_STORAGE_BUFFER_COUNT, MAX_DIRECTIONAL_LIGHTS,
+    procedural_sky::ProceduralSkyEnvironmentMap, reflection_probe_layout_entries,
+    EnvironmentMapLight, FogMeta, GlobalLightMeta, GpuFog, GpuLights, GpuPointLights, LightMeta,
+    NotShadowCaster, NotShadowReceiver, ReflectionProbeMeta, ScreenSpaceAmbientOcclusionTextures,
+    Shadow, ShadowFilteringMethod, ShadowPipeline, ViewClusterBindings, ViewFogUniformOffset,
+    ViewLightsUniformOffset, ViewShadowBindings, CLUSTERED_FORWARD_STORAGE_BUFFER_COUNT,
+    MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS,
 };
 use bevy_app::Plugin;
 use bevy_asset::{load_internal_asset, Assets, Handle, HandleUntyped};
+use bevy_core_pipeline::{
+    core_3d::{AlphaMask3d, Opaque3d, Transparent3d},
+    prepass::Opaque3dPrepass,
+    tonemapping::TonemappingMethod,
+};
 use bevy_ecs::{
     prelude::*,
     system::{lifetimeless::*, SystemParamItem, SystemState},
 };
-use bevy_math::{Mat3A, Mat4, Vec2};
-use bevy_reflect::TypeUuid;
+use bevy_math::{Mat3, Mat3A, Mat4, UVec2, Vec2};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
 use bevy_render::{
     extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
+    extract_resource::{ExtractResource, ExtractResourcePlugin},
     globals::{GlobalsBuffer, GlobalsUniform},
     mesh::{
+        lod::LodCrossFade,
+        morph::{MeshMorphWeights, MAX_MORPH_WEIGHTS},
         skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
-        GpuBufferInfo, Mesh, MeshVertexBufferLayout,
+        GpuBufferInfo, GpuMesh, Mesh, MeshBufferArena, MeshBufferSlices, MeshVertexBufferLayout,
     },
     render_asset::RenderAssets,
-    render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
+    render_phase::{
+        sort_phase_system, BatchedPhaseItem, CachedRenderPipelinePhaseItem, DrawFunctionId,
+        EntityRenderCommand, RenderCommand, RenderCommandResult, RenderPhase, TrackedRenderPass,
+    },
     render_resource::*,
     renderer::{RenderDevice, RenderQueue},
     texture::{
@@ -29,6 +43,8 @@ use bevy_render::{
     Extract, RenderApp, RenderStage,
 };
 use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashMap;
+use bytemuck::{Pod, Zeroable};
 use std::num::NonZeroU64;
 
 #[derive(Default)]
@@ -37,6 +53,7 @@ pub struct MeshRenderPlugin;
 const MAX_JOINTS: usize = 256;
 const JOINT_SIZE: usize = std::mem::size_of::<Mat4>();
 pub(crate) const JOINT_BUFFER_SIZE: usize = MAX_JOINTS * JOINT_SIZE;
+const MORPH_WEIGHTS_BUFFER_SIZE: usize = MAX_MORPH_WEIGHTS * std::mem::size_of::<f32>();
 
 pub const MESH_VERTEX_OUTPUT: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2645551199423808407);
@@ -54,6 +71,8 @@ pub const MESH_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3252377289100772450);
 pub const SKINNING_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 13215291596265391738);
+pub const MORPH_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9768685958920817143);
 
 impl Plugin for MeshRenderPlugin {
     fn build(&self, app: &mut bevy_app::App) {
@@ -90,27 +109,228 @@ impl Plugin for MeshRenderPlugin {
         );
         load_internal_asset!(app, MESH_SHADER_HANDLE, "mesh.wgsl", Shader::from_wgsl);
         load_internal_asset!(app, SKINNING_HANDLE, "skinning.wgsl", Shader::from_wgsl);
+        load_internal_asset!(app, MORPH_HANDLE, "morph.wgsl", Shader::from_wgsl);
 
-        app.add_plugin(UniformComponentPlugin::<MeshUniform>::default());
+        app.register_type::<MeshDrawMode>()
+            .init_resource::<MeshDrawMode>()
+            .add_plugin(UniformComponentPlugin::<MeshUniform>::default())
+            .add_plugin(ExtractResourcePlugin::<MeshDrawMode>::default());
 
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
                 .init_resource::<MeshPipeline>()
                 .init_resource::<SkinnedMeshUniform>()
+                .init_resource::<MorphUniform>()
+                .init_resource::<MeshInstanceBuffer>()
+                .init_resource::<MeshIndirectBuffer>()
                 .add_system_to_stage(RenderStage::Extract, extract_meshes)
                 .add_system_to_stage(RenderStage::Extract, extract_skinned_meshes)
+                .add_system_to_stage(RenderStage::Extract, extract_morphs)
                 .add_system_to_stage(RenderStage::Prepare, prepare_skinned_meshes)
+                .add_system_to_stage(RenderStage::Prepare, prepare_morphs)
                 .add_system_to_stage(RenderStage::Queue, queue_mesh_bind_group)
-                .add_system_to_stage(RenderStage::Queue, queue_mesh_view_bind_groups);
+                .add_system_to_stage(RenderStage::Queue, queue_mesh_view_bind_groups)
+                .add_system_to_stage(
+                    RenderStage::PhaseSort,
+                    prepare_mesh_instances
+                        .after(sort_phase_system::<Opaque3d>)
+                        .after(sort_phase_system::<Opaque3dPrepass>)
+                        .after(sort_phase_system::<AlphaMask3d>)
+                        .after(sort_phase_system::<Transparent3d>)
+                        .after(sort_phase_system::<Shadow>),
+                );
         }
     }
 }
 
 #[derive(Component, ShaderType, Clone)]
+#[shader_type(uniform)]
 pub struct MeshUniform {
     pub transform: Mat4,
+    /// The transform of the mesh on the previous frame, which the motion vectors of the
+    /// [`MotionVectorPrepass`](bevy_core_pipeline::prepass::MotionVectorPrepass) are computed
+    /// from. It is the same as `transform` on the first frame the mesh is visible.
+    pub previous_transform: Mat4,
     pub inverse_transpose_model: Mat4,
     pub flags: u32,
+    /// The index and generation of the entity of the mesh, which the
+    /// [`PickingPass`](crate::PickingPass) writes to tell which entity is under each pixel.
+    pub entity: UVec2,
+    /// The [`LodCrossFade::shader_fade`] of the mesh, or `0.0` when it isn't cross-fading
+    /// between two levels of detail.
+    pub lod_fade: f32,
+}
+
+/// The first shader location of the per-instance attributes of a [`MeshInstance`].
+// NOTE: This must match the locations in bevy_pbr/src/render/mesh.wgsl and depth.wgsl!
+pub const MESH_INSTANCE_SHADER_LOCATION: u32 = 8;
+
+/// The per-instance data of an instanced mesh draw, read from the [`MeshInstanceBuffer`].
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct MeshInstance {
+    pub model: [f32; 16],
+    pub inverse_transpose_model: [f32; 9],
+}
+
+impl MeshInstance {
+    /// The layout of the per-instance vertex buffer bound at slot 1 of instanced pipelines.
+    pub fn vertex_buffer_layout() -> VertexBufferLayout {
+        let mut layout = VertexBufferLayout::from_vertex_formats(
+            VertexStepMode::Instance,
+            [
+                // model
+                VertexFormat::Float32x4,
+                VertexFormat::Float32x4,
+                VertexFormat::Float32x4,
+                VertexFormat::Float32x4,
+                // inverse_transpose_model
+                VertexFormat::Float32x3,
+                VertexFormat::Float32x3,
+                VertexFormat::Float32x3,
+            ],
+        );
+        for attribute in &mut layout.attributes {
+            attribute.shader_location += MESH_INSTANCE_SHADER_LOCATION;
+        }
+        layout
+    }
+}
+
+impl From<&MeshUniform> for MeshInstance {
+    fn from(mesh_uniform: &MeshUniform) -> Self {
+        Self {
+            model: mesh_uniform.transform.to_cols_array(),
+            inverse_transpose_model: Mat3::from_mat4(mesh_uniform.inverse_transpose_model)
+                .to_cols_array(),
+        }
+    }
+}
+
+/// Stores the [`MeshInstance`] of every instanced phase item queued this frame.
+#[derive(Resource)]
+pub struct MeshInstanceBuffer {
+    pub buffer: BufferVec<MeshInstance>,
+}
+
+impl Default for MeshInstanceBuffer {
+    fn default() -> Self {
+        Self {
+            buffer: BufferVec::new(BufferUsages::VERTEX),
+        }
+    }
+}
+
+/// Selects how the batches of instanced meshes are drawn.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, ExtractResource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum MeshDrawMode {
+    /// Each batch is drawn with a regular instanced draw call.
+    #[default]
+    Direct,
+    /// The draw arguments of every instance are written to the [`MeshIndirectBuffer`], and each
+    /// batch is drawn from it with a single multi-draw-indirect call, or one indirect draw per
+    /// instance if `MULTI_DRAW_INDIRECT` isn't supported.
+    ///
+    /// This falls back to [`MeshDrawMode::Direct`] if the device doesn't support the
+    /// `INDIRECT_FIRST_INSTANCE` feature.
+    Indirect,
+}
+
+/// The arguments of a non-indexed indirect draw.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+/// The arguments of an indexed indirect draw.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+/// Stores the indirect draw arguments of every instanced phase item queued this frame when
+/// [`MeshDrawMode::Indirect`] is used.
+///
+/// Every instance gets its own entry with an instance count of one. The buffer can also be bound
+/// as a storage buffer, so that a compute pass run before the main passes can for example cull
+/// instances by setting their instance count to zero.
+#[derive(Resource)]
+pub struct MeshIndirectBuffer {
+    /// Tightly packed [`DrawIndexedIndirectArgs`] or [`DrawIndirectArgs`], depending on whether
+    /// the mesh of the instance is indexed.
+    pub buffer: BufferVec<u32>,
+    /// The byte offset of the draw arguments of each instance in the `buffer`.
+    pub offsets: Vec<u64>,
+    supported: bool,
+    multi_draw: bool,
+}
+
+impl FromWorld for MeshIndirectBuffer {
+    fn from_world(world: &mut World) -> Self {
+        let features = world.resource::<RenderDevice>().features();
+        Self {
+            buffer: BufferVec::new(BufferUsages::INDIRECT | BufferUsages::STORAGE),
+            offsets: Vec::new(),
+            supported: features.contains(WgpuFeatures::INDIRECT_FIRST_INSTANCE),
+            multi_draw: features.contains(WgpuFeatures::MULTI_DRAW_INDIRECT),
+        }
+    }
+}
+
+impl MeshIndirectBuffer {
+    /// Returns `true` if the device supports drawing batches with [`MeshDrawMode::Indirect`].
+    #[inline]
+    pub fn is_supported(&self) -> bool {
+        self.supported
+    }
+
+    fn push(&mut self, mesh: Option<(&GpuMesh, MeshBufferSlices)>, instance: u32) {
+        self.offsets
+            .push((self.buffer.len() * std::mem::size_of::<u32>()) as u64);
+        match mesh.map(|(gpu_mesh, slices)| (&gpu_mesh.buffer_info, slices)) {
+            Some((GpuBufferInfo::Indexed { count, .. }, slices)) => {
+                let args = DrawIndexedIndirectArgs {
+                    index_count: *count,
+                    instance_count: 1,
+                    first_index: slices.first_index,
+                    // the vertex buffer is bound from the first vertex of the mesh
+                    base_vertex: 0,
+                    first_instance: instance,
+                };
+                self.buffer.extend(bytemuck::cast::<_, [u32; 5]>(args));
+            }
+            Some((GpuBufferInfo::NonIndexed { vertex_count }, _)) => {
+                // the vertex buffer is bound from the first vertex of the mesh
+                let args = DrawIndirectArgs {
+                    vertex_count: *vertex_count,
+                    instance_count: 1,
+                    first_vertex: 0,
+                    first_instance: instance,
+                };
+                self.buffer.extend(bytemuck::cast::<_, [u32; 4]>(args));
+            }
+            // the item fails to draw anyway
+            None => {}
+        }
+    }
+}
+
+/// Returns `true` if meshes with this `layout` are skinned, in which case they are never instanced.
+///
+/// Meshes with morph targets aren't instanced either, as their [`MeshMorphWeights`] differ per
+/// entity.
+pub fn is_skinned(layout: &MeshVertexBufferLayout) -> bool {
+    layout.contains(Mesh::ATTRIBUTE_JOINT_INDEX) && layout.contains(Mesh::ATTRIBUTE_JOINT_WEIGHT)
 }
 
 // NOTE: These must match the bit flags in bevy_pbr/src/render/mesh_types.wgsl!
@@ -130,6 +350,7 @@ pub fn extract_meshes(
     mut commands: Commands,
     mut prev_caster_commands_len: Local<usize>,
     mut prev_not_caster_commands_len: Local<usize>,
+    mut previous_transforms: Local<HashMap<Entity, Mat4>>,
     meshes_query: Extract<
         Query<(
             Entity,
@@ -138,14 +359,17 @@ pub fn extract_meshes(
             &Handle<Mesh>,
             Option<With<NotShadowReceiver>>,
             Option<With<NotShadowCaster>>,
+            Option<&LodCrossFade>,
         )>,
     >,
 ) {
     let mut caster_commands = Vec::with_capacity(*prev_caster_commands_len);
     let mut not_caster_commands = Vec::with_capacity(*prev_not_caster_commands_len);
     let visible_meshes = meshes_query.iter().filter(|(_, vis, ..)| vis.is_visible());
+    // Only the meshes that are still visible keep their transform for the next frame
+    let mut last_frame_transforms = std::mem::take(&mut *previous_transforms);
 
-    for (entity, _, transform, handle, not_receiver, not_caster) in visible_meshes {
+    for (entity, _, transform, handle, not_receiver, not_caster, cross_fade) in visible_meshes {
         let transform = transform.compute_matrix();
         let mut flags = if not_receiver.is_some() {
             MeshFlags::empty()
@@ -155,10 +379,15 @@ pub fn extract_meshes(
         if Mat3A::from_mat4(transform).determinant().is_sign_positive() {
             flags |= MeshFlags::SIGN_DETERMINANT_MODEL_3X3;
         }
+        let previous_transform = last_frame_transforms.remove(&entity).unwrap_or(transform);
+        previous_transforms.insert(entity, transform);
         let uniform = MeshUniform {
             flags: flags.bits,
             transform,
+            previous_transform,
             inverse_transpose_model: transform.inverse().transpose(),
+            entity: UVec2::new(entity.index(), entity.generation()),
+            lod_fade: cross_fade.map_or(0.0, LodCrossFade::shader_fade),
         };
         if not_caster.is_some() {
             not_caster_commands.push((entity, (handle.clone_weak(), uniform, NotShadowCaster)));
@@ -250,13 +479,80 @@ pub fn extract_skinned_meshes(
     commands.insert_or_spawn_batch(values);
 }
 
+/// The byte offset of the [`MeshMorphWeights`] of an entity in the [`MorphUniform`].
+#[derive(Component)]
+pub struct MorphIndex {
+    pub index: u32,
+}
+
+pub fn extract_morphs(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    mut uniform: ResMut<MorphUniform>,
+    query: Extract<
+        Query<(
+            Entity,
+            &ComputedVisibility,
+            &MeshMorphWeights,
+            &Handle<Mesh>,
+        )>,
+    >,
+    meshes: Extract<Res<Assets<Mesh>>>,
+) {
+    uniform.buffer.clear();
+    // The first weights are all zero, for the entities with a morphed mesh but no weights
+    uniform
+        .buffer
+        .extend(std::iter::repeat(0.0).take(MAX_MORPH_WEIGHTS));
+    let mut values = Vec::with_capacity(*previous_len);
+
+    for (entity, computed_visibility, morph_weights, mesh_handle) in &query {
+        if !computed_visibility.is_visible() {
+            continue;
+        }
+        let Some(morph_targets) = meshes.get(mesh_handle).and_then(Mesh::morph_targets) else {
+            continue;
+        };
+        let start = uniform.buffer.len();
+        // The vertex shaders blend as many targets as there are non-zero weights, so the weights
+        // without a target are zeroed. Every entity gets all of the weights, so its offset has
+        // the 256 byte alignment required for dynamic offsets.
+        let weights = &morph_weights.weights()[..morph_targets
+            .target_count()
+            .min(morph_weights.weights().len())];
+        uniform.buffer.extend(
+            weights
+                .iter()
+                .copied()
+                .chain(std::iter::repeat(0.0))
+                .take(MAX_MORPH_WEIGHTS),
+        );
+        values.push((
+            entity,
+            MorphIndex {
+                index: (start * std::mem::size_of::<f32>()) as u32,
+            },
+        ));
+    }
+
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
 #[derive(Resource, Clone)]
 pub struct MeshPipeline {
     pub view_layout: BindGroupLayout,
     pub mesh_layout: BindGroupLayout,
     pub skinned_mesh_layout: BindGroupLayout,
+    /// The layout of the bind group of meshes with morph targets, which also binds their
+    /// [`MeshMorphWeights`] and the texture of their targets.
+    pub morphed_mesh_layout: BindGroupLayout,
+    pub morphed_skinned_mesh_layout: BindGroupLayout,
     // This dummy white texture is to be used in place of optional StandardMaterial textures
     pub dummy_white_gpu_image: GpuImage,
+    // This dummy cube texture is bound in place of the maps of views without an EnvironmentMapLight
+    pub dummy_environment_map: TextureView,
+    pub environment_map_sampler: Sampler,
     pub clustered_forward_buffer_binding_type: BufferBindingType,
 }
 
@@ -271,6 +567,7 @@ impl FromWorld for MeshPipeline {
         let clustered_forward_buffer_binding_type = render_device
             .get_supported_read_only_binding_type(CLUSTERED_FORWARD_STORAGE_BUFFER_COUNT);
 
+        let reflection_probe_entries = reflection_probe_layout_entries(15);
         let view_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             entries: &[
                 // View
@@ -390,6 +687,61 @@ impl FromWorld for MeshPipeline {
                     },
                     count: None,
                 },
+                // Environment Map Diffuse Texture
+                BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                    },
+                    count: None,
+                },
+                // Environment Map Specular Texture
+                BindGroupLayoutEntry {
+                    binding: 11,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::Cube,
+                    },
+                    count: None,
+                },
+                // Environment Map Sampler
+                BindGroupLayoutEntry {
+                    binding: 12,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // Screen Space Ambient Occlusion Texture
+                BindGroupLayoutEntry {
+                    binding: 13,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                // Fog
+                BindGroupLayoutEntry {
+                    binding: 14,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(GpuFog::min_size()),
+                    },
+                    count: None,
+                },
+                // Reflection Probes, and their Diffuse and Specular Cube Map Arrays
+                reflection_probe_entries[0],
+                reflection_probe_entries[1],
+                reflection_probe_entries[2],
             ],
             label: Some("mesh_view_layout"),
         });
@@ -410,22 +762,58 @@ impl FromWorld for MeshPipeline {
             label: Some("mesh_layout"),
         });
 
+        let joints_binding = BindGroupLayoutEntry {
+            binding: 1,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: BufferSize::new(JOINT_BUFFER_SIZE as u64),
+            },
+            count: None,
+        };
+        let morph_weights_binding = BindGroupLayoutEntry {
+            binding: 2,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: BufferSize::new(MORPH_WEIGHTS_BUFFER_SIZE as u64),
+            },
+            count: None,
+        };
+        let morph_targets_binding = BindGroupLayoutEntry {
+            binding: 3,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2Array,
+                multisampled: false,
+            },
+            count: None,
+        };
+
         let skinned_mesh_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[mesh_binding, joints_binding],
+                label: Some("skinned_mesh_layout"),
+            });
+
+        let morphed_mesh_layout =
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                entries: &[mesh_binding, morph_weights_binding, morph_targets_binding],
+                label: Some("morphed_mesh_layout"),
+            });
+
+        let morphed_skinned_mesh_layout =
             render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
                 entries: &[
                     mesh_binding,
-                    BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: ShaderStages::VERTEX,
-                        ty: BindingType::Buffer {
-                            ty: BufferBindingType::Uniform,
-                            has_dynamic_offset: true,
-                            min_binding_size: BufferSize::new(JOINT_BUFFER_SIZE as u64),
-                        },
-                        count: None,
-                    },
+                    joints_binding,
+                    morph_weights_binding,
+                    morph_targets_binding,
                 ],
-                label: Some("skinned_mesh_layout"),
+                label: Some("morphed_skinned_mesh_layout"),
             });
 
         // A 1x1x1 'all 1.0' texture to use as a dummy texture to use in place of optional StandardMaterial textures
@@ -477,17 +865,59 @@ impl FromWorld for MeshPipeline {
             }
         };
 
+        // A 1x1 black cube texture
+        let dummy_environment_map = render_device
+            .create_texture_with_data(
+                &render_queue,
+                &TextureDescriptor {
+                    label: Some("dummy_environment_map"),
+                    size: Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 6,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::Rgba8Unorm,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                },
+                &[0; 4 * 6],
+            )
+            .create_view(&TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::Cube),
+                ..Default::default()
+            });
+        let environment_map_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("environment_map_sampler"),
+            ..ImageSampler::linear_descriptor()
+        });
+
         MeshPipeline {
             view_layout,
             mesh_layout,
             skinned_mesh_layout,
+            morphed_mesh_layout,
+            morphed_skinned_mesh_layout,
             clustered_forward_buffer_binding_type,
             dummy_white_gpu_image,
+            dummy_environment_map,
+            environment_map_sampler,
         }
     }
 }
 
 impl MeshPipeline {
+    /// Returns the layout of the mesh bind group of skinned meshes or meshes with morph targets.
+    pub fn get_mesh_layout(&self, skinned: bool, morphed: bool) -> &BindGroupLayout {
+        match (skinned, morphed) {
+            (false, false) => &self.mesh_layout,
+            (true, false) => &self.skinned_mesh_layout,
+            (false, true) => &self.morphed_mesh_layout,
+            (true, true) => &self.morphed_skinned_mesh_layout,
+        }
+    }
+
     pub fn get_image_texture<'a>(
         &'a self,
         gpu_images: &'a RenderAssets<Image>,
@@ -515,8 +945,39 @@ bitflags::bitflags! {
         const HDR                         = (1 << 1);
         const TONEMAP_IN_SHADER           = (1 << 2);
         const DEBAND_DITHER               = (1 << 3);
+        const SHADOW_FILTER_CASTANO_13    = (1 << 4);
+        const INSTANCED                   = (1 << 5);
+        const ENVIRONMENT_MAP             = (1 << 6);
+        const BLEND_PREMULTIPLIED_ALPHA   = (1 << 7);
+        /// Set on the keys of the pipelines that draw into the prepass of a view, for
+        /// [`Material::specialize`](crate::Material::specialize) to tell them apart.
+        const DEPTH_PREPASS               = (1 << 8);
+        /// Set alongside [`MeshPipelineKey::DEPTH_PREPASS`] when the prepass also writes normals.
+        const NORMAL_PREPASS              = (1 << 9);
+        const SCREEN_SPACE_AMBIENT_OCCLUSION = (1 << 10);
+        /// Set alongside [`MeshPipelineKey::DEPTH_PREPASS`] when the prepass also writes motion
+        /// vectors.
+        const MOTION_VECTOR_PREPASS       = (1 << 11);
+        /// The mesh has morph targets, see [`GpuMesh::morph_targets`].
+        const MORPH_TARGETS               = (1 << 12);
+        /// Set on the keys of the pipelines that draw into the [`PickingPass`](crate::PickingPass)
+        /// of a view, for [`Material::specialize`](crate::Material::specialize) to tell them apart.
+        const PICKING_PASS                = (1 << 13);
+        /// Set alongside [`MeshPipelineKey::PICKING_PASS`] when the material uses
+        /// [`AlphaMode::Mask`](crate::AlphaMode::Mask), to leave out the fragments it discards.
+        const ALPHA_MASK                  = (1 << 14);
+        /// The mesh is cross-fading between two levels of detail, see [`LodCrossFade`].
+        const LOD_CROSS_FADE              = (1 << 15);
+        /// The view is lit by [`ReflectionProbe`](crate::ReflectionProbe)s.
+        const REFLECTION_PROBES           = (1 << 16);
+        /// The view has a [`CascadeDebugVisualization`](crate::CascadeDebugVisualization).
+        const DEBUG_CASCADES              = (1 << 17);
         const MSAA_RESERVED_BITS          = Self::MSAA_MASK_BITS << Self::MSAA_SHIFT_BITS;
         const PRIMITIVE_TOPOLOGY_RESERVED_BITS = Self::PRIMITIVE_TOPOLOGY_MASK_BITS << Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
+        const TONEMAP_METHOD_RESERVED_BITS = Self::TONEMAP_METHOD_MASK_BITS << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD     = 0 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD_LUMINANCE = 1 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_ACES         = 2 << Self::TONEMAP_METHOD_SHIFT_BITS;
     }
 }
 
@@ -525,6 +986,9 @@ impl MeshPipelineKey {
     const MSAA_SHIFT_BITS: u32 = 32 - Self::MSAA_MASK_BITS.count_ones();
     const PRIMITIVE_TOPOLOGY_MASK_BITS: u32 = 0b111;
     const PRIMITIVE_TOPOLOGY_SHIFT_BITS: u32 = Self::MSAA_SHIFT_BITS - 3;
+    const TONEMAP_METHOD_MASK_BITS: u32 = 0b11;
+    const TONEMAP_METHOD_SHIFT_BITS: u32 =
+        Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS - Self::TONEMAP_METHOD_MASK_BITS.count_ones();
 
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
         let msaa_bits =
@@ -540,6 +1004,34 @@ impl MeshPipelineKey {
         }
     }
 
+    pub fn from_shadow_filtering_method(method: ShadowFilteringMethod) -> Self {
+        match method {
+            ShadowFilteringMethod::Hardware2x2 => MeshPipelineKey::NONE,
+            ShadowFilteringMethod::Castano13 => MeshPipelineKey::SHADOW_FILTER_CASTANO_13,
+        }
+    }
+
+    pub fn from_tonemapping_method(method: TonemappingMethod) -> Self {
+        match method {
+            TonemappingMethod::Reinhard => MeshPipelineKey::TONEMAP_METHOD_REINHARD,
+            TonemappingMethod::ReinhardLuminance => {
+                MeshPipelineKey::TONEMAP_METHOD_REINHARD_LUMINANCE
+            }
+            TonemappingMethod::Aces => MeshPipelineKey::TONEMAP_METHOD_ACES,
+        }
+    }
+
+    pub fn tonemapping_method(&self) -> TonemappingMethod {
+        let method = self.intersection(MeshPipelineKey::TONEMAP_METHOD_RESERVED_BITS);
+        if method == MeshPipelineKey::TONEMAP_METHOD_REINHARD {
+            TonemappingMethod::Reinhard
+        } else if method == MeshPipelineKey::TONEMAP_METHOD_ACES {
+            TonemappingMethod::Aces
+        } else {
+            TonemappingMethod::ReinhardLuminance
+        }
+    }
+
     pub fn msaa_samples(&self) -> u32 {
         1 << ((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS)
     }
@@ -590,12 +1082,21 @@ impl SpecializedMeshPipeline for MeshPipeline {
             "MAX_DIRECTIONAL_LIGHTS".to_string(),
             MAX_DIRECTIONAL_LIGHTS as u32,
         ));
+        shader_defs.push(ShaderDefVal::UInt(
+            "MAX_CASCADES_PER_LIGHT".to_string(),
+            MAX_CASCADES_PER_LIGHT as u32,
+        ));
 
         if layout.contains(Mesh::ATTRIBUTE_UV_0) {
             shader_defs.push("VERTEX_UVS".into());
             vertex_attributes.push(Mesh::ATTRIBUTE_UV_0.at_shader_location(2));
         }
 
+        if layout.contains(Mesh::ATTRIBUTE_UV_1) {
+            shader_defs.push("VERTEX_UVS_1".into());
+            vertex_attributes.push(Mesh::ATTRIBUTE_UV_1.at_shader_location(7));
+        }
+
         if layout.contains(Mesh::ATTRIBUTE_TANGENT) {
             shader_defs.push("VERTEX_TANGENTS".into());
             vertex_attributes.push(Mesh::ATTRIBUTE_TANGENT.at_shader_location(3));
@@ -607,23 +1108,38 @@ impl SpecializedMeshPipeline for MeshPipeline {
         }
 
         let mut bind_group_layout = vec![self.view_layout.clone()];
-        if layout.contains(Mesh::ATTRIBUTE_JOINT_INDEX)
-            && layout.contains(Mesh::ATTRIBUTE_JOINT_WEIGHT)
-        {
+        let skinned = is_skinned(layout);
+        if skinned {
             shader_defs.push("SKINNED".into());
             vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_INDEX.at_shader_location(5));
             vertex_attributes.push(Mesh::ATTRIBUTE_JOINT_WEIGHT.at_shader_location(6));
-            bind_group_layout.push(self.skinned_mesh_layout.clone());
-        } else {
-            bind_group_layout.push(self.mesh_layout.clone());
-        };
+        }
+        let morphed = key.contains(MeshPipelineKey::MORPH_TARGETS);
+        if morphed {
+            shader_defs.push("MORPH_TARGETS".into());
+        }
+        bind_group_layout.push(self.get_mesh_layout(skinned, morphed).clone());
+
+        if key.contains(MeshPipelineKey::LOD_CROSS_FADE) {
+            shader_defs.push("LOD_CROSS_FADE".into());
+        }
 
-        let vertex_buffer_layout = layout.get_layout(&vertex_attributes)?;
+        let mut vertex_buffer_layouts = vec![layout.get_layout(&vertex_attributes)?];
+        if key.contains(MeshPipelineKey::INSTANCED) {
+            shader_defs.push("MESH_INSTANCED".into());
+            vertex_buffer_layouts.push(MeshInstance::vertex_buffer_layout());
+        }
 
         let (label, blend, depth_write_enabled);
         if key.contains(MeshPipelineKey::TRANSPARENT_MAIN_PASS) {
             label = "transparent_mesh_pipeline".into();
-            blend = Some(BlendState::ALPHA_BLENDING);
+            if key.contains(MeshPipelineKey::BLEND_PREMULTIPLIED_ALPHA) {
+                // The fragment shader is responsible for outputting premultiplied colors
+                shader_defs.push("BLEND_PREMULTIPLIED_ALPHA".into());
+                blend = Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING);
+            } else {
+                blend = Some(BlendState::ALPHA_BLENDING);
+            }
             // For the transparent pass, fragments that are closer will be alpha blended
             // but their depth is not written to the depth buffer
             depth_write_enabled = false;
@@ -638,6 +1154,7 @@ impl SpecializedMeshPipeline for MeshPipeline {
 
         if key.contains(MeshPipelineKey::TONEMAP_IN_SHADER) {
             shader_defs.push("TONEMAP_IN_SHADER".into());
+            shader_defs.push(key.tonemapping_method().shader_def().into());
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
             if key.contains(MeshPipelineKey::DEBAND_DITHER) {
@@ -645,6 +1162,26 @@ impl SpecializedMeshPipeline for MeshPipeline {
             }
         }
 
+        if key.contains(MeshPipelineKey::SHADOW_FILTER_CASTANO_13) {
+            shader_defs.push("SHADOW_FILTER_METHOD_CASTANO_13".into());
+        }
+
+        if key.contains(MeshPipelineKey::ENVIRONMENT_MAP) {
+            shader_defs.push("ENVIRONMENT_MAP".into());
+        }
+
+        if key.contains(MeshPipelineKey::REFLECTION_PROBES) {
+            shader_defs.push("REFLECTION_PROBES".into());
+        }
+
+        if key.contains(MeshPipelineKey::DEBUG_CASCADES) {
+            shader_defs.push("DIRECTIONAL_LIGHT_SHADOW_MAP_DEBUG_CASCADES".into());
+        }
+
+        if key.contains(MeshPipelineKey::SCREEN_SPACE_AMBIENT_OCCLUSION) {
+            shader_defs.push("SCREEN_SPACE_AMBIENT_OCCLUSION".into());
+        }
+
         let format = match key.contains(MeshPipelineKey::HDR) {
             true => ViewTarget::TEXTURE_FORMAT_HDR,
             false => TextureFormat::bevy_default(),
@@ -655,7 +1192,7 @@ impl SpecializedMeshPipeline for MeshPipeline {
                 shader: MESH_SHADER_HANDLE.typed::<Shader>(),
                 entry_point: "vertex".into(),
                 shader_defs: shader_defs.clone(),
-                buffers: vec![vertex_buffer_layout],
+                buffers: vertex_buffer_layouts,
             },
             fragment: Some(FragmentState {
                 shader: MESH_SHADER_HANDLE.typed::<Shader>(),
@@ -668,6 +1205,7 @@ impl SpecializedMeshPipeline for MeshPipeline {
                 })],
             }),
             layout: Some(bind_group_layout),
+            push_constant_ranges: Vec::new(),
             primitive: PrimitiveState {
                 front_face: FrontFace::Ccw,
                 cull_mode: Some(Face::Back),
@@ -680,7 +1218,9 @@ impl SpecializedMeshPipeline for MeshPipeline {
             depth_stencil: Some(DepthStencilState {
                 format: TextureFormat::Depth32Float,
                 depth_write_enabled,
-                depth_compare: CompareFunction::Greater,
+                // NOTE: Fragments at the same depth pass, as the prepass of a view may already
+                // have written the depth of the opaque meshes
+                depth_compare: CompareFunction::GreaterEqual,
                 stencil: StencilState {
                     front: StencilFaceState::IGNORE,
                     back: StencilFaceState::IGNORE,
@@ -707,47 +1247,125 @@ impl SpecializedMeshPipeline for MeshPipeline {
 pub struct MeshBindGroup {
     pub normal: BindGroup,
     pub skinned: Option<BindGroup>,
+    /// The bind groups of the meshes with morph targets, which bind the texture of their targets.
+    pub morphed: HashMap<Handle<Mesh>, BindGroup>,
+    pub morphed_skinned: HashMap<Handle<Mesh>, BindGroup>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn queue_mesh_bind_group(
     mut commands: Commands,
     mesh_pipeline: Res<MeshPipeline>,
     render_device: Res<RenderDevice>,
+    bind_group_cache: Res<BindGroupCache>,
+    render_meshes: Res<RenderAssets<Mesh>>,
     mesh_uniforms: Res<ComponentUniforms<MeshUniform>>,
     skinned_mesh_uniform: Res<SkinnedMeshUniform>,
+    morph_uniform: Res<MorphUniform>,
 ) {
-    if let Some(mesh_binding) = mesh_uniforms.uniforms().binding() {
+    if let Some(mesh_binding) = mesh_uniforms.uniforms().cached_binding() {
         let mut mesh_bind_group = MeshBindGroup {
-            normal: render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[BindGroupEntry {
+            normal: bind_group_cache.get_or_create(
+                &render_device,
+                Some("mesh_bind_group"),
+                &mesh_pipeline.mesh_layout,
+                &[CachedBindGroupEntry {
                     binding: 0,
                     resource: mesh_binding.clone(),
                 }],
-                label: Some("mesh_bind_group"),
-                layout: &mesh_pipeline.mesh_layout,
-            }),
+            ),
             skinned: None,
+            morphed: HashMap::default(),
+            morphed_skinned: HashMap::default(),
         };
 
-        if let Some(skinned_joints_buffer) = skinned_mesh_uniform.buffer.buffer() {
-            mesh_bind_group.skinned = Some(render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[
-                    BindGroupEntry {
+        let skinned_joints_binding =
+            skinned_mesh_uniform
+                .buffer
+                .buffer()
+                .map(|buffer| CachedBindingResource::Buffer {
+                    buffer,
+                    offset: 0,
+                    size: Some(NonZeroU64::new(JOINT_BUFFER_SIZE as u64).unwrap()),
+                });
+        if let Some(skinned_joints_binding) = &skinned_joints_binding {
+            mesh_bind_group.skinned = Some(bind_group_cache.get_or_create(
+                &render_device,
+                Some("skinned_mesh_bind_group"),
+                &mesh_pipeline.skinned_mesh_layout,
+                &[
+                    CachedBindGroupEntry {
                         binding: 0,
-                        resource: mesh_binding,
+                        resource: mesh_binding.clone(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 1,
-                        resource: BindingResource::Buffer(BufferBinding {
-                            buffer: skinned_joints_buffer,
-                            offset: 0,
-                            size: Some(NonZeroU64::new(JOINT_BUFFER_SIZE as u64).unwrap()),
-                        }),
+                        resource: skinned_joints_binding.clone(),
                     },
                 ],
-                label: Some("skinned_mesh_bind_group"),
-                layout: &mesh_pipeline.skinned_mesh_layout,
-            }));
+            ));
+        }
+
+        if let Some(morph_weights_buffer) = morph_uniform.buffer.buffer() {
+            let morph_weights_binding = CachedBindingResource::Buffer {
+                buffer: morph_weights_buffer,
+                offset: 0,
+                size: Some(NonZeroU64::new(MORPH_WEIGHTS_BUFFER_SIZE as u64).unwrap()),
+            };
+            for (handle, gpu_mesh) in render_meshes.iter() {
+                let Some(morph_targets) = &gpu_mesh.morph_targets else {
+                    continue;
+                };
+                let morphed = bind_group_cache.get_or_create(
+                    &render_device,
+                    Some("morphed_mesh_bind_group"),
+                    &mesh_pipeline.morphed_mesh_layout,
+                    &[
+                        CachedBindGroupEntry {
+                            binding: 0,
+                            resource: mesh_binding.clone(),
+                        },
+                        CachedBindGroupEntry {
+                            binding: 2,
+                            resource: morph_weights_binding.clone(),
+                        },
+                        CachedBindGroupEntry {
+                            binding: 3,
+                            resource: CachedBindingResource::TextureView(morph_targets),
+                        },
+                    ],
+                );
+                mesh_bind_group.morphed.insert(handle.clone_weak(), morphed);
+
+                if let Some(skinned_joints_binding) = &skinned_joints_binding {
+                    let morphed_skinned = bind_group_cache.get_or_create(
+                        &render_device,
+                        Some("morphed_skinned_mesh_bind_group"),
+                        &mesh_pipeline.morphed_skinned_mesh_layout,
+                        &[
+                            CachedBindGroupEntry {
+                                binding: 0,
+                                resource: mesh_binding.clone(),
+                            },
+                            CachedBindGroupEntry {
+                                binding: 1,
+                                resource: skinned_joints_binding.clone(),
+                            },
+                            CachedBindGroupEntry {
+                                binding: 2,
+                                resource: morph_weights_binding.clone(),
+                            },
+                            CachedBindGroupEntry {
+                                binding: 3,
+                                resource: CachedBindingResource::TextureView(morph_targets),
+                            },
+                        ],
+                    );
+                    mesh_bind_group
+                        .morphed_skinned
+                        .insert(handle.clone_weak(), morphed_skinned);
+                }
+            }
         }
         commands.insert_resource(mesh_bind_group);
     }
@@ -788,6 +1406,36 @@ pub fn prepare_skinned_meshes(
         .write_buffer(&render_device, &render_queue);
 }
 
+/// The [`MeshMorphWeights`] of the visible entities, padded to [`MAX_MORPH_WEIGHTS`] each.
+#[derive(Resource)]
+pub struct MorphUniform {
+    pub buffer: BufferVec<f32>,
+}
+
+impl Default for MorphUniform {
+    fn default() -> Self {
+        Self {
+            buffer: BufferVec::new(BufferUsages::UNIFORM),
+        }
+    }
+}
+
+pub fn prepare_morphs(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut morph_uniform: ResMut<MorphUniform>,
+) {
+    if morph_uniform.buffer.is_empty() {
+        return;
+    }
+
+    let len = morph_uniform.buffer.len();
+    morph_uniform.buffer.reserve(len, &render_device);
+    morph_uniform
+        .buffer
+        .write_buffer(&render_device, &render_queue);
+}
+
 #[derive(Component)]
 pub struct MeshViewBindGroup {
     pub value: BindGroup,
@@ -797,73 +1445,164 @@ pub struct MeshViewBindGroup {
 pub fn queue_mesh_view_bind_groups(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
+    bind_group_cache: Res<BindGroupCache>,
     mesh_pipeline: Res<MeshPipeline>,
     shadow_pipeline: Res<ShadowPipeline>,
     light_meta: Res<LightMeta>,
     global_light_meta: Res<GlobalLightMeta>,
+    fog_meta: Res<FogMeta>,
+    reflection_probe_meta: Res<ReflectionProbeMeta>,
     view_uniforms: Res<ViewUniforms>,
-    views: Query<(Entity, &ViewShadowBindings, &ViewClusterBindings)>,
+    views: Query<(
+        Entity,
+        &ViewShadowBindings,
+        &ViewClusterBindings,
+        Option<&EnvironmentMapLight>,
+        Option<&ProceduralSkyEnvironmentMap>,
+        Option<&ScreenSpaceAmbientOcclusionTextures>,
+    )>,
     globals_buffer: Res<GlobalsBuffer>,
+    images: Res<RenderAssets<Image>>,
 ) {
-    if let (Some(view_binding), Some(light_binding), Some(point_light_binding), Some(globals)) = (
-        view_uniforms.uniforms.binding(),
-        light_meta.view_gpu_lights.binding(),
-        global_light_meta.gpu_point_lights.binding(),
-        globals_buffer.buffer.binding(),
+    if let (
+        Some(view_binding),
+        Some(light_binding),
+        Some(point_light_binding),
+        Some(globals),
+        Some(fog_binding),
+        Some(reflection_probes_binding),
+    ) = (
+        view_uniforms.uniforms.cached_binding(),
+        light_meta.view_gpu_lights.cached_binding(),
+        global_light_meta.gpu_point_lights.cached_binding(),
+        globals_buffer.buffer.cached_binding(),
+        fog_meta.gpu_fogs.cached_binding(),
+        reflection_probe_meta.gpu_probes.cached_binding(),
     ) {
-        for (entity, view_shadow_bindings, view_cluster_bindings) in &views {
-            let view_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[
-                    BindGroupEntry {
+        let (reflection_probe_diffuse_maps, reflection_probe_specular_maps) =
+            reflection_probe_meta.cube_map_views();
+        for (
+            entity,
+            view_shadow_bindings,
+            view_cluster_bindings,
+            environment_map,
+            procedural_sky_environment_map,
+            ssao_textures,
+        ) in &views
+        {
+            // A baked procedural sky replaces the environment map of the view
+            let environment_map = procedural_sky_environment_map
+                .map(|map| (&map.diffuse_map_view, &map.specular_map_view))
+                .or_else(|| {
+                    let map = environment_map?;
+                    Some((
+                        &images.get(&map.diffuse_map)?.texture_view,
+                        &images.get(&map.specular_map)?.texture_view,
+                    ))
+                });
+            let (diffuse_map, specular_map) = environment_map.unwrap_or((
+                &mesh_pipeline.dummy_environment_map,
+                &mesh_pipeline.dummy_environment_map,
+            ));
+            let ssao_texture = match ssao_textures {
+                Some(ssao_textures) => &ssao_textures.denoised.default_view,
+                None => &mesh_pipeline.dummy_white_gpu_image.texture_view,
+            };
+            let view_bind_group = bind_group_cache.get_or_create(
+                &render_device,
+                Some("mesh_view_bind_group"),
+                &mesh_pipeline.view_layout,
+                &[
+                    CachedBindGroupEntry {
                         binding: 0,
                         resource: view_binding.clone(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 1,
                         resource: light_binding.clone(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 2,
-                        resource: BindingResource::TextureView(
+                        resource: CachedBindingResource::TextureView(
                             &view_shadow_bindings.point_light_depth_texture_view,
                         ),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 3,
-                        resource: BindingResource::Sampler(&shadow_pipeline.point_light_sampler),
+                        resource: CachedBindingResource::Sampler(
+                            &shadow_pipeline.point_light_sampler,
+                        ),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 4,
-                        resource: BindingResource::TextureView(
+                        resource: CachedBindingResource::TextureView(
                             &view_shadow_bindings.directional_light_depth_texture_view,
                         ),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 5,
-                        resource: BindingResource::Sampler(
+                        resource: CachedBindingResource::Sampler(
                             &shadow_pipeline.directional_light_sampler,
                         ),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 6,
                         resource: point_light_binding.clone(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 7,
-                        resource: view_cluster_bindings.light_index_lists_binding().unwrap(),
+                        resource: view_cluster_bindings
+                            .cached_light_index_lists_binding()
+                            .unwrap(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 8,
-                        resource: view_cluster_bindings.offsets_and_counts_binding().unwrap(),
+                        resource: view_cluster_bindings
+                            .cached_offsets_and_counts_binding()
+                            .unwrap(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 9,
                         resource: globals.clone(),
                     },
+                    CachedBindGroupEntry {
+                        binding: 10,
+                        resource: CachedBindingResource::TextureView(diffuse_map),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 11,
+                        resource: CachedBindingResource::TextureView(specular_map),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 12,
+                        resource: CachedBindingResource::Sampler(
+                            &mesh_pipeline.environment_map_sampler,
+                        ),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 13,
+                        resource: CachedBindingResource::TextureView(ssao_texture),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 14,
+                        resource: fog_binding.clone(),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 15,
+                        resource: reflection_probes_binding.clone(),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 16,
+                        resource: CachedBindingResource::TextureView(reflection_probe_diffuse_maps),
+                    },
+                    CachedBindGroupEntry {
+                        binding: 17,
+                        resource: CachedBindingResource::TextureView(
+                            reflection_probe_specular_maps,
+                        ),
+                    },
                 ],
-                label: Some("mesh_view_bind_group"),
-                layout: &mesh_pipeline.view_layout,
-            });
+            );
 
             commands.entity(entity).insert(MeshViewBindGroup {
                 value: view_bind_group,
@@ -877,6 +1616,7 @@ impl<const I: usize> EntityRenderCommand for SetMeshViewBindGroup<I> {
     type Param = SQuery<(
         Read<ViewUniformOffset>,
         Read<ViewLightsUniformOffset>,
+        Read<ViewFogUniformOffset>,
         Read<MeshViewBindGroup>,
     )>;
     #[inline]
@@ -886,11 +1626,12 @@ impl<const I: usize> EntityRenderCommand for SetMeshViewBindGroup<I> {
         view_query: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let (view_uniform, view_lights, mesh_view_bind_group) = view_query.get_inner(view).unwrap();
+        let (view_uniform, view_lights, view_fog, mesh_view_bind_group) =
+            view_query.get_inner(view).unwrap();
         pass.set_bind_group(
             I,
             &mesh_view_bind_group.value,
-            &[view_uniform.offset, view_lights.offset],
+            &[view_uniform.offset, view_lights.offset, view_fog.offset],
         );
 
         RenderCommandResult::Success
@@ -904,6 +1645,8 @@ impl<const I: usize> EntityRenderCommand for SetMeshBindGroup<I> {
         SQuery<(
             Read<DynamicUniformIndex<MeshUniform>>,
             Option<Read<SkinnedMeshJoints>>,
+            Option<Read<MorphIndex>>,
+            Read<Handle<Mesh>>,
         )>,
     );
     #[inline]
@@ -913,19 +1656,29 @@ impl<const I: usize> EntityRenderCommand for SetMeshBindGroup<I> {
         (mesh_bind_group, mesh_query): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let (mesh_index, skinned_mesh_joints) = mesh_query.get(item).unwrap();
+        let (mesh_index, skinned_mesh_joints, morph_index, mesh_handle) =
+            mesh_query.get(item).unwrap();
+        let mesh_bind_group = mesh_bind_group.into_inner();
+        // Entities without weights use the zero weights at the start of the buffer
+        let morph_index = morph_index.map_or(0, |morph| morph.index);
         if let Some(joints) = skinned_mesh_joints {
-            pass.set_bind_group(
-                I,
-                mesh_bind_group.into_inner().skinned.as_ref().unwrap(),
-                &[mesh_index.index(), joints.index],
-            );
+            if let Some(bind_group) = mesh_bind_group.morphed_skinned.get(mesh_handle) {
+                pass.set_bind_group(
+                    I,
+                    bind_group,
+                    &[mesh_index.index(), joints.index, morph_index],
+                );
+            } else {
+                pass.set_bind_group(
+                    I,
+                    mesh_bind_group.skinned.as_ref().unwrap(),
+                    &[mesh_index.index(), joints.index],
+                );
+            }
+        } else if let Some(bind_group) = mesh_bind_group.morphed.get(mesh_handle) {
+            pass.set_bind_group(I, bind_group, &[mesh_index.index(), morph_index]);
         } else {
-            pass.set_bind_group(
-                I,
-                &mesh_bind_group.into_inner().normal,
-                &[mesh_index.index()],
-            );
+            pass.set_bind_group(I, &mesh_bind_group.normal, &[mesh_index.index()]);
         }
         RenderCommandResult::Success
     }
@@ -933,44 +1686,437 @@ impl<const I: usize> EntityRenderCommand for SetMeshBindGroup<I> {
 
 pub struct DrawMesh;
 impl EntityRenderCommand for DrawMesh {
-    type Param = (SRes<RenderAssets<Mesh>>, SQuery<Read<Handle<Mesh>>>);
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SRes<MeshBufferArena>,
+        SQuery<Read<Handle<Mesh>>>,
+    );
     #[inline]
     fn render<'w>(
         _view: Entity,
         item: Entity,
-        (meshes, mesh_query): SystemParamItem<'w, '_, Self::Param>,
+        (meshes, mesh_buffers, mesh_query): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         let mesh_handle = mesh_query.get(item).unwrap();
-        if let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) {
-            pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
-            match &gpu_mesh.buffer_info {
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(slices) = mesh_buffers.into_inner().get(&gpu_mesh.allocation) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_vertex_buffer(0, slices.vertex_slice());
+        match (&gpu_mesh.buffer_info, slices.index_buffer) {
+            (
                 GpuBufferInfo::Indexed {
-                    buffer,
                     index_format,
                     count,
-                } => {
-                    pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                    pass.draw_indexed(0..*count, 0, 0..1);
+                },
+                Some(index_buffer),
+            ) => {
+                pass.set_index_buffer(index_buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(slices.first_index..slices.first_index + count, 0, 0..1);
+            }
+            (GpuBufferInfo::NonIndexed { vertex_count }, _) => {
+                pass.draw(0..*vertex_count, 0..1);
+            }
+            _ => return RenderCommandResult::Failure,
+        }
+        RenderCommandResult::Success
+    }
+}
+
+/// Writes the [`MeshInstance`] of every instanced phase item to the [`MeshInstanceBuffer`], in
+/// sorted phase order, and points the item's batch range at it.
+///
+/// Consecutive items are merged into a single instanced draw afterwards, see
+/// [`batch_mesh_instances`].
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_mesh_instances(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    draw_mode: Res<MeshDrawMode>,
+    render_meshes: Res<RenderAssets<Mesh>>,
+    mesh_buffers: Res<MeshBufferArena>,
+    mut mesh_instances: ResMut<MeshInstanceBuffer>,
+    mut mesh_indirect: ResMut<MeshIndirectBuffer>,
+    mesh_uniforms: Query<(&MeshUniform, &Handle<Mesh>)>,
+    mut opaque_phases: Query<&mut RenderPhase<Opaque3d>>,
+    mut opaque_prepass_phases: Query<&mut RenderPhase<Opaque3dPrepass>>,
+    mut alpha_mask_phases: Query<&mut RenderPhase<AlphaMask3d>>,
+    mut transparent_phases: Query<&mut RenderPhase<Transparent3d>>,
+    mut shadow_phases: Query<&mut RenderPhase<Shadow>>,
+) {
+    let buffer = &mut mesh_instances.buffer;
+    buffer.clear();
+    mesh_indirect.buffer.clear();
+    mesh_indirect.offsets.clear();
+    let mut indirect = (*draw_mode == MeshDrawMode::Indirect && mesh_indirect.is_supported())
+        .then_some(mesh_indirect.as_mut());
+
+    for mut phase in &mut opaque_phases {
+        push_mesh_instances(
+            &mut phase.items,
+            &mesh_uniforms,
+            &render_meshes,
+            &mesh_buffers,
+            buffer,
+            &mut indirect,
+        );
+    }
+    for mut phase in &mut opaque_prepass_phases {
+        push_mesh_instances(
+            &mut phase.items,
+            &mesh_uniforms,
+            &render_meshes,
+            &mesh_buffers,
+            buffer,
+            &mut indirect,
+        );
+    }
+    for mut phase in &mut alpha_mask_phases {
+        push_mesh_instances(
+            &mut phase.items,
+            &mesh_uniforms,
+            &render_meshes,
+            &mesh_buffers,
+            buffer,
+            &mut indirect,
+        );
+    }
+    for mut phase in &mut transparent_phases {
+        push_mesh_instances(
+            &mut phase.items,
+            &mesh_uniforms,
+            &render_meshes,
+            &mesh_buffers,
+            buffer,
+            &mut indirect,
+        );
+    }
+    for mut phase in &mut shadow_phases {
+        push_mesh_instances(
+            &mut phase.items,
+            &mesh_uniforms,
+            &render_meshes,
+            &mesh_buffers,
+            buffer,
+            &mut indirect,
+        );
+    }
+    buffer.write_buffer(&render_device, &render_queue);
+    if let Some(indirect) = indirect {
+        indirect.buffer.write_buffer(&render_device, &render_queue);
+    }
+}
+
+fn push_mesh_instances<P: BatchedPhaseItem>(
+    items: &mut [P],
+    mesh_uniforms: &Query<(&MeshUniform, &Handle<Mesh>)>,
+    render_meshes: &RenderAssets<Mesh>,
+    mesh_buffers: &MeshBufferArena,
+    buffer: &mut BufferVec<MeshInstance>,
+    indirect: &mut Option<&mut MeshIndirectBuffer>,
+) {
+    for item in items {
+        if item.batch_range().is_none() {
+            continue;
+        }
+        if let Ok((mesh_uniform, mesh_handle)) = mesh_uniforms.get(item.entity()) {
+            let index = buffer.push(mesh_uniform.into()) as u32;
+            *item.batch_range_mut() = Some(index..index + 1);
+            if let Some(indirect) = indirect {
+                let mesh = render_meshes
+                    .get(mesh_handle)
+                    .and_then(|gpu_mesh| Some((gpu_mesh, mesh_buffers.get(&gpu_mesh.allocation)?)));
+                indirect.push(mesh, index);
+            }
+        }
+    }
+}
+
+/// Merges consecutive instanced items of the `phase` that use the `draw_function` and the same
+/// pipeline into a single instanced draw, if `compatible` returns `true` for their entities.
+///
+/// The first entity of a batch is used to look up the mesh and bind groups of the whole batch,
+/// so `compatible` must only accept entities for which those are identical.
+pub fn batch_mesh_instances<P: BatchedPhaseItem + CachedRenderPipelinePhaseItem>(
+    phase: &mut RenderPhase<P>,
+    draw_function: DrawFunctionId,
+    compatible: impl Fn(Entity, Entity) -> bool,
+) {
+    merge_instances(
+        phase,
+        |item| (item.draw_function() == draw_function).then(|| item.cached_pipeline()),
+        compatible,
+    );
+}
+
+/// Merges consecutive items of the `phase` with adjacent instance ranges and the same
+/// `batch_key`. Items without a key, or without a batch range, are never merged.
+fn merge_instances<P: BatchedPhaseItem, K: PartialEq>(
+    phase: &mut RenderPhase<P>,
+    batch_key: impl Fn(&P) -> Option<K>,
+    compatible: impl Fn(Entity, Entity) -> bool,
+) {
+    let mut items = std::mem::take(&mut phase.items).into_iter();
+    phase.items.reserve(items.len());
+
+    if let Some(mut current_batch) = items.next() {
+        for next_item in items {
+            let merged = matches!(
+                (batch_key(&current_batch), batch_key(&next_item)),
+                (Some(current), Some(next)) if current == next
+            ) && matches!(
+                (current_batch.batch_range(), next_item.batch_range()),
+                (Some(current), Some(next)) if current.end == next.start
+            ) && compatible(current_batch.entity(), next_item.entity());
+            if merged {
+                let next_end = next_item.batch_range().as_ref().unwrap().end;
+                current_batch.batch_range_mut().as_mut().unwrap().end = next_end;
+            } else {
+                phase.items.push(current_batch);
+                current_batch = next_item;
+            }
+        }
+        phase.items.push(current_batch);
+    }
+}
+
+/// Draws the mesh of the phase item. Items with a batch range are drawn as that range of
+/// instances from the [`MeshInstanceBuffer`], and must use a [`MeshPipelineKey::INSTANCED`]
+/// pipeline. See [`MeshDrawMode`] for how those draws are issued.
+pub struct DrawMeshInstanced;
+impl<P: BatchedPhaseItem> RenderCommand<P> for DrawMeshInstanced {
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SRes<MeshBufferArena>,
+        SRes<MeshInstanceBuffer>,
+        SRes<MeshIndirectBuffer>,
+        SQuery<Read<Handle<Mesh>>>,
+    );
+    #[inline]
+    fn render<'w>(
+        _view: Entity,
+        item: &P,
+        (meshes, mesh_buffers, mesh_instances, mesh_indirect, mesh_query): SystemParamItem<
+            'w,
+            '_,
+            Self::Param,
+        >,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let instances = match item.batch_range() {
+            Some(batch_range) => match mesh_instances.into_inner().buffer.buffer() {
+                Some(buffer) => {
+                    pass.set_vertex_buffer(1, buffer.slice(..));
+                    batch_range.clone()
+                }
+                None => return RenderCommandResult::Failure,
+            },
+            None => 0..1,
+        };
+        let mesh_indirect = mesh_indirect.into_inner();
+        // The indirect draw arguments are only written in `MeshDrawMode::Indirect`
+        let indirect = match (item.batch_range(), mesh_indirect.buffer.buffer()) {
+            (Some(_), Some(buffer)) => mesh_indirect
+                .offsets
+                .get(instances.start as usize..instances.end as usize)
+                .map(|offsets| (buffer, offsets)),
+            _ => None,
+        };
+        let mesh_handle = mesh_query.get(item.entity()).unwrap();
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(slices) = mesh_buffers.into_inner().get(&gpu_mesh.allocation) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_vertex_buffer(0, slices.vertex_slice());
+        match (&gpu_mesh.buffer_info, slices.index_buffer) {
+            (
+                GpuBufferInfo::Indexed {
+                    index_format,
+                    count,
+                },
+                Some(index_buffer),
+            ) => {
+                pass.set_index_buffer(index_buffer.slice(..), 0, *index_format);
+                if let Some((indirect_buffer, offsets)) = indirect {
+                    if mesh_indirect.multi_draw {
+                        pass.multi_draw_indexed_indirect(
+                            indirect_buffer,
+                            offsets[0],
+                            offsets.len() as u32,
+                        );
+                    } else {
+                        for offset in offsets {
+                            pass.draw_indexed_indirect(indirect_buffer, *offset);
+                        }
+                    }
+                } else {
+                    pass.draw_indexed(slices.first_index..slices.first_index + count, 0, instances);
                 }
-                GpuBufferInfo::NonIndexed { vertex_count } => {
-                    pass.draw(0..*vertex_count, 0..1);
+            }
+            (GpuBufferInfo::NonIndexed { vertex_count }, _) => {
+                if let Some((indirect_buffer, offsets)) = indirect {
+                    if mesh_indirect.multi_draw {
+                        pass.multi_draw_indirect(indirect_buffer, offsets[0], offsets.len() as u32);
+                    } else {
+                        for offset in offsets {
+                            pass.draw_indirect(indirect_buffer, *offset);
+                        }
+                    }
+                } else {
+                    pass.draw(0..*vertex_count, instances);
                 }
             }
-            RenderCommandResult::Success
-        } else {
-            RenderCommandResult::Failure
+            _ => return RenderCommandResult::Failure,
         }
+        RenderCommandResult::Success
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::MeshPipelineKey;
+    use super::{merge_instances, MeshPipelineKey};
+    use bevy_core_pipeline::tonemapping::TonemappingMethod;
+    use bevy_ecs::entity::Entity;
+    use bevy_render::{
+        mesh::PrimitiveTopology,
+        render_phase::{BatchedPhaseItem, DrawFunctionId, EntityPhaseItem, PhaseItem, RenderPhase},
+    };
+    use std::ops::Range;
+
     #[test]
     fn mesh_key_msaa_samples() {
         for i in [1, 2, 4, 8, 16, 32, 64, 128] {
             assert_eq!(MeshPipelineKey::from_msaa_samples(i).msaa_samples(), i);
         }
     }
+
+    #[test]
+    fn mesh_key_tonemapping_method() {
+        for method in [
+            TonemappingMethod::Reinhard,
+            TonemappingMethod::ReinhardLuminance,
+            TonemappingMethod::Aces,
+        ] {
+            let key = MeshPipelineKey::from_msaa_samples(4)
+                | MeshPipelineKey::from_primitive_topology(PrimitiveTopology::TriangleStrip)
+                | MeshPipelineKey::from_tonemapping_method(method);
+            assert_eq!(key.tonemapping_method(), method);
+            assert_eq!(key.msaa_samples(), 4);
+            assert_eq!(key.primitive_topology(), PrimitiveTopology::TriangleStrip);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct TestPhaseItem {
+        entity: Entity,
+        pipeline: Option<u32>,
+        batch_range: Option<Range<u32>>,
+    }
+
+    impl PhaseItem for TestPhaseItem {
+        type SortKey = ();
+
+        fn sort_key(&self) -> Self::SortKey {}
+
+        fn draw_function(&self) -> DrawFunctionId {
+            unimplemented!();
+        }
+    }
+
+    impl EntityPhaseItem for TestPhaseItem {
+        fn entity(&self) -> Entity {
+            self.entity
+        }
+    }
+
+    impl BatchedPhaseItem for TestPhaseItem {
+        fn batch_range(&self) -> &Option<Range<u32>> {
+            &self.batch_range
+        }
+
+        fn batch_range_mut(&mut self) -> &mut Option<Range<u32>> {
+            &mut self.batch_range
+        }
+    }
+
+    /// The mesh and material drawn by each entity, indexed by entity id.
+    const MESH_MATERIALS: [(u32, u32); 4] = [(0, 0), (0, 0), (1, 0), (0, 1)];
+
+    fn item(entity: u32, pipeline: Option<u32>, batch_range: Option<Range<u32>>) -> TestPhaseItem {
+        TestPhaseItem {
+            entity: Entity::from_raw(entity),
+            pipeline,
+            batch_range,
+        }
+    }
+
+    /// Merges the `items` of a phase, keyed by their pipeline, if their entities draw the same
+    /// mesh with the same material.
+    fn merge(items: Vec<TestPhaseItem>) -> Vec<TestPhaseItem> {
+        let mut phase = RenderPhase { items };
+        merge_instances(
+            &mut phase,
+            |item| item.pipeline,
+            |a, b| MESH_MATERIALS[a.index() as usize] == MESH_MATERIALS[b.index() as usize],
+        );
+        phase.items
+    }
+
+    #[test]
+    fn merge_same_mesh_material_and_pipeline() {
+        assert_eq!(
+            merge(vec![
+                item(0, Some(0), Some(0..1)),
+                item(1, Some(0), Some(1..2)),
+                item(0, Some(0), Some(2..4)),
+            ]),
+            [item(0, Some(0), Some(0..4))]
+        );
+    }
+
+    #[test]
+    fn split_different_mesh_or_material() {
+        let items = || {
+            vec![
+                item(0, Some(0), Some(0..1)),
+                item(2, Some(0), Some(1..2)),
+                item(3, Some(0), Some(2..3)),
+            ]
+        };
+        assert_eq!(merge(items()), items());
+    }
+
+    #[test]
+    fn split_different_pipeline_or_draw_function() {
+        let items = || {
+            vec![
+                item(0, Some(0), Some(0..1)),
+                item(1, Some(1), Some(1..2)),
+                // items drawn by another draw function have no batch key
+                item(0, None, Some(2..3)),
+                item(1, None, Some(3..4)),
+            ]
+        };
+        assert_eq!(merge(items()), items());
+    }
+
+    #[test]
+    fn split_skinned_or_non_adjacent_instances() {
+        let items = || {
+            vec![
+                item(0, Some(0), Some(0..1)),
+                // skinned meshes aren't instanced, so they don't have a batch range
+                item(1, Some(0), None),
+                item(0, Some(0), None),
+                item(1, Some(0), Some(1..2)),
+                item(0, Some(0), Some(3..4)),
+            ]
+        };
+        assert_eq!(merge(items()), items());
+    }
 }
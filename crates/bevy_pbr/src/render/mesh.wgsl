@@ -1,4 +1,7 @@
 #import bevy_pbr::mesh_view_bindings
+#ifdef STANDARDMATERIAL_UV_TRANSFORM
+#import bevy_pbr::pbr_bindings
+#endif
 #import bevy_pbr::mesh_bindings
 
 // NOTE: Bindings must come before functions that use them!
@@ -24,6 +27,21 @@ struct Vertex {
     @location(5) joint_indices: vec4<u32>,
     @location(6) joint_weights: vec4<f32>,
 #endif
+#ifdef VERTEX_UVS_1
+    @location(7) uv_1: vec2<f32>,
+#endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+#ifdef MESH_INSTANCED
+    @location(8) instance_model_0: vec4<f32>,
+    @location(9) instance_model_1: vec4<f32>,
+    @location(10) instance_model_2: vec4<f32>,
+    @location(11) instance_model_3: vec4<f32>,
+    @location(12) instance_inverse_transpose_model_0: vec3<f32>,
+    @location(13) instance_inverse_transpose_model_1: vec3<f32>,
+    @location(14) instance_inverse_transpose_model_2: vec3<f32>,
+#endif
 };
 
 struct VertexOutput {
@@ -32,22 +50,55 @@ struct VertexOutput {
 };
 
 @vertex
-fn vertex(vertex: Vertex) -> VertexOutput {
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+#ifdef VERTEX_POSITIONS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#endif
+#ifdef VERTEX_NORMALS
+    vertex.normal = morph_normal(vertex.index, vertex.normal);
+#endif
+#ifdef VERTEX_TANGENTS
+    vertex.tangent = morph_tangent(vertex.index, vertex.tangent);
+#endif
+#endif
+
     var out: VertexOutput;
 
 #ifdef SKINNED
     var model = skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+#ifdef MESH_INSTANCED
+    var model = mat4x4<f32>(
+        vertex.instance_model_0,
+        vertex.instance_model_1,
+        vertex.instance_model_2,
+        vertex.instance_model_3
+    );
 #else
     var model = mesh.model;
 #endif
+#endif
 
 #ifdef VERTEX_NORMALS
 #ifdef SKINNED
     out.world_normal = skin_normals(model, vertex.normal);
+#else
+#ifdef MESH_INSTANCED
+    out.world_normal = mesh_normal_local_to_world_with(
+        mat3x3<f32>(
+            vertex.instance_inverse_transpose_model_0,
+            vertex.instance_inverse_transpose_model_1,
+            vertex.instance_inverse_transpose_model_2
+        ),
+        vertex.normal
+    );
 #else
     out.world_normal = mesh_normal_local_to_world(vertex.normal);
 #endif
 #endif
+#endif
 
 #ifdef VERTEX_POSITIONS
     out.world_position = mesh_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
@@ -55,8 +106,16 @@ fn vertex(vertex: Vertex) -> VertexOutput {
 #endif
 
 #ifdef VERTEX_UVS
+#ifdef STANDARDMATERIAL_UV_TRANSFORM
+    out.uv = (material.uv_transform * vec3<f32>(vertex.uv, 1.0)).xy;
+#else
     out.uv = vertex.uv;
 #endif
+#endif
+
+#ifdef VERTEX_UVS_1
+    out.uv_1 = vertex.uv_1;
+#endif
 
 #ifdef VERTEX_TANGENTS
     out.world_tangent = mesh_tangent_local_to_world(model, vertex.tangent);
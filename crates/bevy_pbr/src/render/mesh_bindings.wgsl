@@ -9,3 +9,10 @@ var<uniform> mesh: Mesh;
 var<uniform> joint_matrices: SkinnedMesh;
 #import bevy_pbr::skinning
 #endif
+#ifdef MORPH_TARGETS
+@group(2) @binding(2)
+var<uniform> morph_weights: MorphWeights;
+@group(2) @binding(3)
+var morph_targets: texture_2d_array<f32>;
+#import bevy_pbr::morph
+#endif
@@ -16,19 +16,24 @@ fn mesh_position_local_to_clip(model: mat4x4<f32>, vertex_position: vec4<f32>) -
     return mesh_position_world_to_clip(world_position);
 }
 
-fn mesh_normal_local_to_world(vertex_normal: vec3<f32>) -> vec3<f32> {
+fn mesh_normal_local_to_world_with(inverse_transpose_model: mat3x3<f32>, vertex_normal: vec3<f32>) -> vec3<f32> {
     // NOTE: The mikktspace method of normal mapping requires that the world normal is
     // re-normalized in the vertex shader to match the way mikktspace bakes vertex tangents
     // and normal maps so that the exact inverse process is applied when shading. Blender, Unity,
     // Unreal Engine, Godot, and more all use the mikktspace method. Do not change this code
     // unless you really know what you are doing.
     // http://www.mikktspace.com/
-    return normalize(
+    return normalize(inverse_transpose_model * vertex_normal);
+}
+
+fn mesh_normal_local_to_world(vertex_normal: vec3<f32>) -> vec3<f32> {
+    return mesh_normal_local_to_world_with(
         mat3x3<f32>(
             mesh.inverse_transpose_model[0].xyz,
             mesh.inverse_transpose_model[1].xyz,
             mesh.inverse_transpose_model[2].xyz
-        ) * vertex_normal
+        ),
+        vertex_normal
     );
 }
 
@@ -61,3 +66,16 @@ fn mesh_tangent_local_to_world(model: mat4x4<f32>, vertex_tangent: vec4<f32>) ->
         vertex_tangent.w * sign_determinant_model_3x3()
     );
 }
+
+#ifdef LOD_CROSS_FADE
+// Whether the fragment of a mesh cross-fading between two levels of detail is discarded. The
+// previous and the new level use complementary patterns of interleaved gradient noise, so that
+// each pixel is drawn by exactly one of them.
+fn lod_cross_fade_discarded(frag_coord: vec2<f32>) -> bool {
+    let noise = fract(52.9829189 * fract(dot(frag_coord, vec2<f32>(0.06711056, 0.00583715))));
+    if (mesh.lod_fade > 0.0) {
+        return noise >= mesh.lod_fade;
+    }
+    return noise < -mesh.lod_fade;
+}
+#endif
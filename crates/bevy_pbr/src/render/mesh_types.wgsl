@@ -2,9 +2,14 @@
 
 struct Mesh {
     model: mat4x4<f32>,
+    previous_model: mat4x4<f32>,
     inverse_transpose_model: mat4x4<f32>,
     // 'flags' is a bit field indicating various options. u32 is 32 bits so we have up to 32 options.
     flags: u32,
+    // The index and generation of the entity of the mesh
+    entity: vec2<u32>,
+    // Set while the mesh cross-fades between two levels of detail, see lod_cross_fade_discard
+    lod_fade: f32,
 };
 
 #ifdef SKINNED
@@ -13,6 +18,13 @@ struct SkinnedMesh {
 };
 #endif
 
+#ifdef MORPH_TARGETS
+struct MorphWeights {
+    // The 64 weights, packed by 4 for the alignment of uniform arrays
+    weights: array<vec4<f32>, 16u>,
+};
+#endif
+
 let MESH_FLAGS_SHADOW_RECEIVER_BIT: u32 = 1u;
 // 2^31 - if the flag is set, the sign is positive, else it is negative
 let MESH_FLAGS_SIGN_DETERMINANT_MODEL_3X3_BIT: u32 = 2147483648u;
@@ -43,3 +43,30 @@ var<uniform> cluster_offsets_and_counts: ClusterOffsetsAndCounts;
 
 @group(0) @binding(9)
 var<uniform> globals: Globals;
+
+@group(0) @binding(10)
+var environment_map_diffuse: texture_cube<f32>;
+@group(0) @binding(11)
+var environment_map_specular: texture_cube<f32>;
+@group(0) @binding(12)
+var environment_map_sampler: sampler;
+
+@group(0) @binding(13)
+var screen_space_ambient_occlusion_texture: texture_2d<f32>;
+
+@group(0) @binding(14)
+var<uniform> fog: Fog;
+
+@group(0) @binding(15)
+var<uniform> reflection_probes: ReflectionProbes;
+#ifdef NO_ARRAY_TEXTURES_SUPPORT
+@group(0) @binding(16)
+var reflection_probe_diffuse: texture_cube<f32>;
+@group(0) @binding(17)
+var reflection_probe_specular: texture_cube<f32>;
+#else
+@group(0) @binding(16)
+var reflection_probe_diffuse: texture_cube_array<f32>;
+@group(0) @binding(17)
+var reflection_probe_specular: texture_cube_array<f32>;
+#endif
@@ -2,6 +2,7 @@
 
 struct View {
     view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
     inverse_view_proj: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
@@ -10,6 +11,8 @@ struct View {
     world_position: vec3<f32>,
     // viewport(x_origin, y_origin, width, height)
     viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
 };
 
 struct PointLight {
@@ -28,14 +31,23 @@ struct PointLight {
 let POINT_LIGHT_FLAGS_SHADOWS_ENABLED_BIT: u32   = 1u;
 let POINT_LIGHT_FLAGS_SPOT_LIGHT_Y_NEGATIVE: u32 = 2u;
 
-struct DirectionalLight {
+struct DirectionalCascade {
     view_projection: mat4x4<f32>,
+    texel_size: f32,
+    far_bound: f32,
+}
+
+struct DirectionalLight {
+    cascades: array<DirectionalCascade, #{MAX_CASCADES_PER_LIGHT}u>,
     color: vec4<f32>,
     direction_to_light: vec3<f32>,
     // 'flags' is a bit field indicating various options. u32 is 32 bits so we have up to 32 options.
     flags: u32,
     shadow_depth_bias: f32,
     shadow_normal_bias: f32,
+    num_cascades: u32,
+    cascades_overlap_proportion: f32,
+    depth_texture_base_index: u32,
 };
 
 let DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT: u32 = 1u;
@@ -100,3 +112,38 @@ struct Globals {
     _wasm_padding: f32
 #endif
 }
+
+struct Fog {
+    // The fog color in linear RGB, and its maximum opacity in `a`
+    color: vec4<f32>,
+    // Linear: (start, end), exponential and exponential squared: (density, unused)
+    falloff: vec2<f32>,
+    mode: u32,
+    // (density, falloff, height), the height fog is disabled with a density of 0.0
+    height_fog: vec3<f32>,
+};
+
+// NOTE: These must match the constants of bevy_pbr/src/fog.rs
+let FOG_MODE_OFF: u32                   = 0u;
+let FOG_MODE_LINEAR: u32                = 1u;
+let FOG_MODE_EXPONENTIAL: u32           = 2u;
+let FOG_MODE_EXPONENTIAL_SQUARED: u32   = 3u;
+
+struct ReflectionProbe {
+    // From world space to the unit cube of the influence box of the probe
+    inverse_transform: mat4x4<f32>,
+    position: vec3<f32>,
+    // The fraction of the size of the influence box over which the probe fades out
+    falloff: f32,
+    intensity: f32,
+    // The index of the cube maps of the probe in the arrays of cube maps
+    index: u32,
+    parallax_correction: u32,
+};
+
+struct ReflectionProbes {
+    // NOTE: this must be kept in sync with MAX_REFLECTION_PROBES in
+    // bevy_pbr/src/reflection_probe/mod.rs
+    probes: array<ReflectionProbe, 8u>,
+    count: u32,
+};
@@ -0,0 +1,57 @@
+// If using this WGSL snippet as an #import, dedicated "morph_weights" and "morph_targets"
+// bindings must be added in the main shader.
+
+#define_import_path bevy_pbr::morph
+
+// The maximum number of morph targets and weights of a mesh
+let MAX_MORPH_WEIGHTS: u32 = 64u;
+// The floats of the position, normal and tangent displacements of a vertex by a morph target
+let MORPH_COMPONENTS_PER_VERTEX: u32 = 9u;
+let MORPH_POSITION_OFFSET: u32 = 0u;
+let MORPH_NORMAL_OFFSET: u32 = 3u;
+let MORPH_TANGENT_OFFSET: u32 = 6u;
+
+fn morph_weight(target_index: u32) -> f32 {
+    return morph_weights.weights[target_index / 4u][target_index % 4u];
+}
+
+fn morph_component(vertex_index: u32, target_index: u32, component: u32) -> f32 {
+    let index = vertex_index * MORPH_COMPONENTS_PER_VERTEX + component;
+    let width = u32(textureDimensions(morph_targets).x);
+    let coords = vec2<i32>(i32(index % width), i32(index / width));
+    return textureLoad(morph_targets, coords, i32(target_index), 0).r;
+}
+
+// The displacement of this vertex by this target, of the attribute starting at this offset
+fn morph_displacement(vertex_index: u32, target_index: u32, offset: u32) -> vec3<f32> {
+    return vec3<f32>(
+        morph_component(vertex_index, target_index, offset),
+        morph_component(vertex_index, target_index, offset + 1u),
+        morph_component(vertex_index, target_index, offset + 2u)
+    );
+}
+
+// Adds the displacements of this vertex by every target, scaled by their weights.
+// NOTE: The weights after the last target of the mesh are zero, so the targets aren't counted.
+fn morph(vertex_index: u32, value: vec3<f32>, offset: u32) -> vec3<f32> {
+    var morphed = value;
+    for (var target_index = 0u; target_index < MAX_MORPH_WEIGHTS; target_index = target_index + 1u) {
+        let weight = morph_weight(target_index);
+        if weight != 0.0 {
+            morphed = morphed + weight * morph_displacement(vertex_index, target_index, offset);
+        }
+    }
+    return morphed;
+}
+
+fn morph_position(vertex_index: u32, position: vec3<f32>) -> vec3<f32> {
+    return morph(vertex_index, position, MORPH_POSITION_OFFSET);
+}
+
+fn morph_normal(vertex_index: u32, normal: vec3<f32>) -> vec3<f32> {
+    return normalize(morph(vertex_index, normal, MORPH_NORMAL_OFFSET));
+}
+
+fn morph_tangent(vertex_index: u32, tangent: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(normalize(morph(vertex_index, tangent.xyz, MORPH_TANGENT_OFFSET)), tangent.w);
+}
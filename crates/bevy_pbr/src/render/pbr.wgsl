@@ -1,12 +1,14 @@
 #import bevy_pbr::mesh_view_bindings
 #import bevy_pbr::pbr_bindings
 #import bevy_pbr::mesh_bindings
+#import bevy_pbr::mesh_functions
 
 #import bevy_pbr::utils
 #import bevy_pbr::clustered_forward
 #import bevy_pbr::lighting
 #import bevy_pbr::shadows
 #import bevy_pbr::pbr_functions
+#import bevy_pbr::fog
 
 struct FragmentInput {
     @builtin(front_facing) is_front: bool,
@@ -40,9 +42,9 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
         // TODO use .a for exposure compensation in HDR
         var emissive: vec4<f32> = material.emissive;
 #ifdef VERTEX_UVS
-        if ((material.flags & STANDARD_MATERIAL_FLAGS_EMISSIVE_TEXTURE_BIT) != 0u) {
-            emissive = vec4<f32>(emissive.rgb * textureSample(emissive_texture, emissive_sampler, in.uv).rgb, 1.0);
-        }
+#ifdef STANDARDMATERIAL_EMISSIVE_TEXTURE
+        emissive = vec4<f32>(emissive.rgb * textureSample(emissive_texture, emissive_sampler, in.uv).rgb, 1.0);
+#endif
 #endif
         pbr_input.material.emissive = emissive;
 
@@ -58,15 +60,26 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
 #endif
         pbr_input.material.metallic = metallic;
         pbr_input.material.perceptual_roughness = perceptual_roughness;
+        pbr_input.material.clearcoat = material.clearcoat;
+        pbr_input.material.clearcoat_perceptual_roughness = material.clearcoat_perceptual_roughness;
+        pbr_input.material.transmission = material.transmission;
 
         var occlusion: f32 = 1.0;
 #ifdef VERTEX_UVS
-        if ((material.flags & STANDARD_MATERIAL_FLAGS_OCCLUSION_TEXTURE_BIT) != 0u) {
-            occlusion = textureSample(occlusion_texture, occlusion_sampler, in.uv).r;
-        }
+#ifdef STANDARDMATERIAL_OCCLUSION_TEXTURE
+        occlusion = textureSample(occlusion_texture, occlusion_sampler, in.uv).r;
+#endif
 #endif
         pbr_input.occlusion = occlusion;
 
+        var lightmap = vec3<f32>(0.0);
+#ifdef VERTEX_UVS_1
+#ifdef STANDARDMATERIAL_LIGHTMAP_TEXTURE
+        lightmap = textureSample(lightmap_texture, lightmap_sampler, in.uv_1).rgb * material.lightmap_intensity;
+#endif
+#endif
+        pbr_input.lightmap = lightmap;
+
         pbr_input.frag_coord = in.frag_coord;
         pbr_input.world_position = in.world_position;
         pbr_input.world_normal = prepare_world_normal(
@@ -95,6 +108,10 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
         output_color = alpha_discard(material, output_color);
     }
 
+    if ((material.flags & STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT) != 0u) {
+        output_color = apply_fog(output_color, in.world_position.xyz, view.world_position.xyz);
+    }
+
 #ifdef TONEMAP_IN_SHADER
         output_color = tone_mapping(output_color);
 #endif
@@ -106,6 +123,19 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
     // SRGB; the GPU will assume our output is linear and will apply an SRGB conversion.
     output_rgb = pow(output_rgb, vec3<f32>(2.2));
     output_color = vec4(output_rgb, output_color.a);
+#endif
+#ifdef BLEND_PREMULTIPLIED_ALPHA
+    // `Premultiplied` colors are output as they are, while `Add` colors are premultiplied here
+    // and don't dim the color behind them
+    if ((material.flags & STANDARD_MATERIAL_FLAGS_ALPHA_MODE_ADD) != 0u) {
+        output_color = vec4<f32>(output_color.rgb * output_color.a, 0.0);
+    }
+#endif
+#ifdef LOD_CROSS_FADE
+    // NOTE: Discarding is left to the end, as textures can't be sampled after it
+    if (lod_cross_fade_discarded(in.frag_coord.xy)) {
+        discard;
+    }
 #endif
     return output_color;
 }
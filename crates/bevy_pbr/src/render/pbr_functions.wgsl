@@ -4,6 +4,18 @@
 #import bevy_core_pipeline::tonemapping
 #endif
 
+#ifdef ENVIRONMENT_MAP
+#import bevy_pbr::environment_map
+#endif
+
+#ifdef REFLECTION_PROBES
+#import bevy_pbr::reflection_probe
+#endif
+
+#ifdef SCREEN_SPACE_AMBIENT_OCCLUSION
+#import bevy_pbr::gtao_utils
+#endif
+
 
 fn alpha_discard(material: StandardMaterial, output_color: vec4<f32>) -> vec4<f32>{
     var color = output_color;
@@ -119,6 +131,8 @@ fn calculate_view(
 struct PbrInput {
     material: StandardMaterial,
     occlusion: f32,
+    // The baked indirect light reaching the surface, diffused by the material like the ambient light
+    lightmap: vec3<f32>,
     frag_coord: vec4<f32>,
     world_position: vec4<f32>,
     // Normalized world normal used for shadow mapping as normal-mapping is not used for shadow
@@ -138,6 +152,7 @@ fn pbr_input_new() -> PbrInput {
 
     pbr_input.material = standard_material_new();
     pbr_input.occlusion = 1.0;
+    pbr_input.lightmap = vec3<f32>(0.0);
 
     pbr_input.frag_coord = vec4<f32>(0.0, 0.0, 0.0, 1.0);
     pbr_input.world_position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
@@ -163,8 +178,12 @@ fn pbr(
     let metallic = in.material.metallic;
     let perceptual_roughness = in.material.perceptual_roughness;
     let roughness = perceptualRoughnessToRoughness(perceptual_roughness);
+    let clearcoat = in.material.clearcoat;
+    let clearcoat_perceptual_roughness = in.material.clearcoat_perceptual_roughness;
+    let clearcoat_roughness = perceptualRoughnessToRoughness(clearcoat_perceptual_roughness);
+    let transmission = in.material.transmission;
 
-    let occlusion = in.occlusion;
+    var occlusion = vec3<f32>(in.occlusion);
 
     output_color = alpha_discard(in.material, output_color);
 
@@ -176,11 +195,17 @@ fn pbr(
     let reflectance = in.material.reflectance;
     let F0 = 0.16 * reflectance * reflectance * (1.0 - metallic) + output_color.rgb * metallic;
 
-    // Diffuse strength inversely related to metallicity
-    let diffuse_color = output_color.rgb * (1.0 - metallic);
+    // Diffuse strength inversely related to metallicity, and to the light passing through
+    let diffuse_color = output_color.rgb * (1.0 - metallic) * (1.0 - transmission);
 
     let R = reflect(-in.V, in.N);
 
+#ifdef SCREEN_SPACE_AMBIENT_OCCLUSION
+    let ssao = textureLoad(screen_space_ambient_occlusion_texture, vec2<i32>(in.frag_coord.xy), 0).r;
+    // The occlusion baked in the material already accounts for some of what SSAO finds
+    occlusion = min(occlusion, gtao_multibounce(ssao, output_color.rgb));
+#endif
+
     // accumulate color
     var light_accum: vec3<f32> = vec3<f32>(0.0);
 
@@ -202,7 +227,7 @@ fn pbr(
                 && (light.flags & POINT_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
             shadow = fetch_point_shadow(light_id, in.world_position, in.world_normal);
         }
-        let light_contrib = point_light(in.world_position.xyz, light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color);
+        let light_contrib = point_light(in.world_position.xyz, light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color, clearcoat, clearcoat_roughness);
         light_accum = light_accum + light_contrib * shadow;
     }
 
@@ -215,7 +240,7 @@ fn pbr(
                 && (light.flags & POINT_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
             shadow = fetch_spot_shadow(light_id, in.world_position, in.world_normal);
         }
-        let light_contrib = spot_light(in.world_position.xyz, light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color);
+        let light_contrib = spot_light(in.world_position.xyz, light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color, clearcoat, clearcoat_roughness);
         light_accum = light_accum + light_contrib * shadow;
     }
 
@@ -225,20 +250,61 @@ fn pbr(
         var shadow: f32 = 1.0;
         if ((mesh.flags & MESH_FLAGS_SHADOW_RECEIVER_BIT) != 0u
                 && (light.flags & DIRECTIONAL_LIGHT_FLAGS_SHADOWS_ENABLED_BIT) != 0u) {
-            shadow = fetch_directional_shadow(i, in.world_position, in.world_normal);
+            shadow = fetch_directional_shadow(i, in.world_position, in.world_normal, view_z);
         }
-        let light_contrib = directional_light(light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color);
+        var light_contrib = directional_light(light, roughness, NdotV, in.N, in.V, R, F0, diffuse_color, clearcoat, clearcoat_roughness);
+#ifdef DIRECTIONAL_LIGHT_SHADOW_MAP_DEBUG_CASCADES
+        // NOTE: This debug mode tints each fragment with the color of the cascade it samples the
+        // shadow map of, to visualize the cascade boundaries.
+        light_contrib = cascade_debug_visualization(light_contrib, i, view_z);
+#endif
         light_accum = light_accum + light_contrib * shadow;
     }
 
-    let diffuse_ambient = EnvBRDFApprox(diffuse_color, 1.0, NdotV);
-    let specular_ambient = EnvBRDFApprox(F0, perceptual_roughness, NdotV);
+    // The clear coat dims the ambient light reflected by the base layer like the light of the lights
+    let clearcoat_fresnel = F_Schlick(0.04, 1.0, NdotV) * clearcoat;
+    let clearcoat_attenuation = 1.0 - clearcoat_fresnel;
+    let diffuse_ambient = EnvBRDFApprox(diffuse_color, 1.0, NdotV) * clearcoat_attenuation;
+    let specular_ambient = EnvBRDFApprox(F0, perceptual_roughness, NdotV) * clearcoat_attenuation * clearcoat_attenuation;
+    let clearcoat_ambient = vec3<f32>(clearcoat_fresnel);
+
+    // ambient light
+    var ambient_light = (diffuse_ambient + specular_ambient + clearcoat_ambient) * lights.ambient_color.rgb;
+    ambient_light = ambient_light + diffuse_ambient * in.lightmap;
+    // The reflection probes replace the environment map where they light the mesh
+    var environment_map_weight = 1.0;
+#ifdef REFLECTION_PROBES
+    let probe_light = reflection_probe_light(
+        in.world_position.xyz,
+        in.N,
+        R,
+        perceptual_roughness,
+        clearcoat_perceptual_roughness,
+        diffuse_ambient,
+        specular_ambient,
+        clearcoat_ambient,
+    );
+    ambient_light = ambient_light + probe_light.light;
+    environment_map_weight = 1.0 - probe_light.weight;
+#endif
+#ifdef ENVIRONMENT_MAP
+    let environment_light = environment_map_light(perceptual_roughness, in.N, R, diffuse_ambient, specular_ambient);
+    ambient_light = ambient_light + (environment_light.diffuse + environment_light.specular) * environment_map_weight;
+    let clearcoat_environment_light = environment_map_light(clearcoat_perceptual_roughness, in.N, R, vec3<f32>(0.0), clearcoat_ambient);
+    ambient_light = ambient_light + clearcoat_environment_light.specular * environment_map_weight;
+#endif
+
+    // The light passing through the material shows what is behind it, when it is blended with it
+    var alpha = output_color.a;
+    if ((in.material.flags & (STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND | STANDARD_MATERIAL_FLAGS_ALPHA_MODE_PREMULTIPLIED)) != 0u) {
+        alpha = alpha * (1.0 - transmission);
+    }
 
     output_color = vec4<f32>(
         light_accum +
-            (diffuse_ambient + specular_ambient) * lights.ambient_color.rgb * occlusion +
+            ambient_light * occlusion +
             emissive.rgb * output_color.a,
-        output_color.a);
+        alpha);
 
     output_color = cluster_debug_visualization(
         output_color,
@@ -254,7 +320,7 @@ fn pbr(
 #ifdef TONEMAP_IN_SHADER
 fn tone_mapping(in: vec4<f32>) -> vec4<f32> {
     // tone_mapping
-    return vec4<f32>(reinhard_luminance(in.rgb), in.a);
+    return vec4<f32>(tonemap(color_grade(in.rgb, view.color_grading)), in.a);
 
     // Gamma correction.
     // Not needed with sRGB buffer
@@ -110,6 +110,28 @@ fn specular(f0: vec3<f32>, roughness: f32, h: vec3<f32>, NoV: f32, NoL: f32,
     return (specularIntensity * D * V) * F;
 }
 
+// Clear coat BRDF
+// https://google.github.io/filament/Filament.html#materialsystem/clearcoatmodel
+// A specular lobe with a fixed f0 of 0.04 over the base layer, whose Fresnel term dims the light
+// reflected by the base layer
+// f(v,l) = f_d(v,l) (1 − F_c) + f_r(v,l) (1 − F_c)^2 + f_c(v,l)
+
+// Kelemen's visibility function, simpler than V_SmithGGXCorrelated as a coat is usually smooth
+// V(l,h) = 1 / { 4 (l⋅h)^2 }
+fn V_Kelemen(LoH: f32) -> f32 {
+    return 0.25 / max(LoH * LoH, 0.0001);
+}
+
+// Returns the specular light of the coat in x, and its Fresnel term F_c in y
+fn clearcoat_specular(clearcoat: f32, clearcoat_roughness: f32, h: vec3<f32>, NoH: f32, LoH: f32,
+              specularIntensity: f32) -> vec2<f32> {
+    let D = D_GGX(clearcoat_roughness, NoH, h);
+    let V = V_Kelemen(LoH);
+    let F = F_Schlick(0.04, 1.0, LoH) * clearcoat;
+
+    return vec2<f32>(specularIntensity * D * V * F, F);
+}
+
 // Diffuse BRDF
 // https://google.github.io/filament/Filament.html#materialsystem/diffusebrdf
 // fd(v,l) = σ/π * 1 / { |n⋅v||n⋅l| } ∫Ω D(m,α) G(v,l,m) (v⋅m) (l⋅m) dm
@@ -151,7 +173,7 @@ fn perceptualRoughnessToRoughness(perceptualRoughness: f32) -> f32 {
 
 fn point_light(
     world_position: vec3<f32>, light: PointLight, roughness: f32, NdotV: f32, N: vec3<f32>, V: vec3<f32>,
-    R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>
+    R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>, clearcoat: f32, clearcoat_roughness: f32
 ) -> vec3<f32> {
     let light_to_frag = light.position_radius.xyz - world_position.xyz;
     let distance_square = dot(light_to_frag, light_to_frag);
@@ -176,6 +198,9 @@ fn point_light(
 
     let specular_light = specular(F0, roughness, H, NdotV, NoL, NoH, LoH, specularIntensity);
 
+    let clearcoat_normalization = clearcoat_roughness / saturate(clearcoat_roughness + (light.position_radius.w * 0.5 * LspecLengthInverse));
+    let clearcoat_light = clearcoat_specular(clearcoat, clearcoat_roughness, H, NoH, LoH, clearcoat_normalization * clearcoat_normalization);
+
     // Diffuse.
     // Comes after specular since its NoL is used in the lighting equation.
     L = normalize(light_to_frag);
@@ -201,15 +226,18 @@ fn point_light(
 
     // TODO compensate for energy loss https://google.github.io/filament/Filament.html#materialsystem/improvingthebrdfs/energylossinspecularreflectance
 
-    return ((diffuse + specular_light) * light.color_inverse_square_range.rgb) * (rangeAttenuation * NoL);
+    let clearcoat_attenuation = 1.0 - clearcoat_light.y;
+    let color = (diffuse + specular_light * clearcoat_attenuation) * clearcoat_attenuation + clearcoat_light.x;
+
+    return (color * light.color_inverse_square_range.rgb) * (rangeAttenuation * NoL);
 }
 
 fn spot_light(
     world_position: vec3<f32>, light: PointLight, roughness: f32, NdotV: f32, N: vec3<f32>, V: vec3<f32>,
-    R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>
+    R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>, clearcoat: f32, clearcoat_roughness: f32
 ) -> vec3<f32> {
     // reuse the point light calculations
-    let point_light = point_light(world_position, light, roughness, NdotV, N, V, R, F0, diffuseColor);
+    let point_light = point_light(world_position, light, roughness, NdotV, N, V, R, F0, diffuseColor, clearcoat, clearcoat_roughness);
 
     // reconstruct spot dir from x/z and y-direction flag
     var spot_dir = vec3<f32>(light.light_custom_data.x, 0.0, light.light_custom_data.y);
@@ -229,7 +257,7 @@ fn spot_light(
     return point_light * spot_attenuation;
 }
 
-fn directional_light(light: DirectionalLight, roughness: f32, NdotV: f32, normal: vec3<f32>, view: vec3<f32>, R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>) -> vec3<f32> {
+fn directional_light(light: DirectionalLight, roughness: f32, NdotV: f32, normal: vec3<f32>, view: vec3<f32>, R: vec3<f32>, F0: vec3<f32>, diffuseColor: vec3<f32>, clearcoat: f32, clearcoat_roughness: f32) -> vec3<f32> {
     let incident_light = light.direction_to_light.xyz;
 
     let half_vector = normalize(incident_light + view);
@@ -240,6 +268,10 @@ fn directional_light(light: DirectionalLight, roughness: f32, NdotV: f32, normal
     let diffuse = diffuseColor * Fd_Burley(roughness, NdotV, NoL, LoH);
     let specularIntensity = 1.0;
     let specular_light = specular(F0, roughness, half_vector, NdotV, NoL, NoH, LoH, specularIntensity);
+    let clearcoat_light = clearcoat_specular(clearcoat, clearcoat_roughness, half_vector, NoH, LoH, specularIntensity);
+
+    let clearcoat_attenuation = 1.0 - clearcoat_light.y;
+    let color = (diffuse + specular_light * clearcoat_attenuation) * clearcoat_attenuation + clearcoat_light.x;
 
-    return (specular_light + diffuse) * light.color.rgb * NoL;
+    return color * light.color.rgb * NoL;
 }
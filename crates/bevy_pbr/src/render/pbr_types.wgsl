@@ -9,6 +9,11 @@ struct StandardMaterial {
     // 'flags' is a bit field indicating various options. u32 is 32 bits so we have up to 32 options.
     flags: u32,
     alpha_cutoff: f32,
+    uv_transform: mat3x3<f32>,
+    clearcoat: f32,
+    clearcoat_perceptual_roughness: f32,
+    transmission: f32,
+    lightmap_intensity: f32,
 };
 
 let STANDARD_MATERIAL_FLAGS_BASE_COLOR_TEXTURE_BIT: u32         = 1u;
@@ -22,6 +27,10 @@ let STANDARD_MATERIAL_FLAGS_ALPHA_MODE_MASK: u32                = 128u;
 let STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND: u32               = 256u;
 let STANDARD_MATERIAL_FLAGS_TWO_COMPONENT_NORMAL_MAP: u32       = 512u;
 let STANDARD_MATERIAL_FLAGS_FLIP_NORMAL_MAP_Y: u32              = 1024u;
+let STANDARD_MATERIAL_FLAGS_ALPHA_MODE_PREMULTIPLIED: u32       = 2048u;
+let STANDARD_MATERIAL_FLAGS_ALPHA_MODE_ADD: u32                 = 4096u;
+let STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT: u32                = 8192u;
+let STANDARD_MATERIAL_FLAGS_LIGHTMAP_TEXTURE_BIT: u32           = 16384u;
 
 // Creates a StandardMaterial with default values
 fn standard_material_new() -> StandardMaterial {
@@ -33,8 +42,13 @@ fn standard_material_new() -> StandardMaterial {
     material.perceptual_roughness = 0.089;
     material.metallic = 0.01;
     material.reflectance = 0.5;
-    material.flags = STANDARD_MATERIAL_FLAGS_ALPHA_MODE_OPAQUE;
+    material.flags = STANDARD_MATERIAL_FLAGS_ALPHA_MODE_OPAQUE | STANDARD_MATERIAL_FLAGS_FOG_ENABLED_BIT;
     material.alpha_cutoff = 0.5;
+    material.uv_transform = mat3x3<f32>(vec3<f32>(1.0, 0.0, 0.0), vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(0.0, 0.0, 1.0));
+    material.clearcoat = 0.0;
+    material.clearcoat_perceptual_roughness = 0.5;
+    material.transmission = 0.0;
+    material.lightmap_intensity = 1.0;
 
     return material;
 }
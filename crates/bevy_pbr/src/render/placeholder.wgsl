@@ -0,0 +1,59 @@
+#import bevy_pbr::mesh_types
+#import bevy_pbr::mesh_view_bindings
+
+@group(1) @binding(0)
+var<uniform> mesh: Mesh;
+
+#ifdef SKINNED
+@group(1) @binding(1)
+var<uniform> joint_matrices: SkinnedMesh;
+#import bevy_pbr::skinning
+#endif
+#ifdef MORPH_TARGETS
+@group(1) @binding(2)
+var<uniform> morph_weights: MorphWeights;
+@group(1) @binding(3)
+var morph_targets: texture_2d_array<f32>;
+#import bevy_pbr::morph
+#endif
+
+// NOTE: Bindings must come before functions that use them!
+#import bevy_pbr::mesh_functions
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+#ifdef SKINNED
+    @location(5) joint_indexes: vec4<u32>,
+    @location(6) joint_weights: vec4<f32>,
+#endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#endif
+
+#ifdef SKINNED
+    let model = skin_model(vertex.joint_indexes, vertex.joint_weights);
+#else
+    let model = mesh.model;
+#endif
+
+    var out: VertexOutput;
+    out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+    return out;
+}
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.5, 0.5, 0.5, 1.0);
+}
@@ -0,0 +1,178 @@
+#import bevy_pbr::mesh_view_types
+
+@group(0) @binding(0)
+var<uniform> view: View;
+#ifdef MOTION_VECTOR_PREPASS
+@group(0) @binding(1)
+var<uniform> previous_view_proj: mat4x4<f32>;
+#endif
+
+#import bevy_pbr::mesh_bindings
+
+// NOTE: Bindings must come before functions that use them!
+#import bevy_pbr::mesh_functions
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+#ifdef VERTEX_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#ifdef SKINNED
+    @location(5) joint_indices: vec4<u32>,
+    @location(6) joint_weights: vec4<f32>,
+#endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
+#endif
+#ifdef MESH_INSTANCED
+    @location(8) instance_model_0: vec4<f32>,
+    @location(9) instance_model_1: vec4<f32>,
+    @location(10) instance_model_2: vec4<f32>,
+    @location(11) instance_model_3: vec4<f32>,
+    @location(12) instance_inverse_transpose_model_0: vec3<f32>,
+    @location(13) instance_inverse_transpose_model_1: vec3<f32>,
+    @location(14) instance_inverse_transpose_model_2: vec3<f32>,
+#endif
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+#ifdef VERTEX_NORMALS
+    @location(0) world_normal: vec3<f32>,
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    @location(1) world_position: vec4<f32>,
+    @location(2) previous_world_position: vec4<f32>,
+#endif
+};
+
+@vertex
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#ifdef VERTEX_NORMALS
+    vertex.normal = morph_normal(vertex.index, vertex.normal);
+#endif
+#endif
+
+    var out: VertexOutput;
+
+#ifdef SKINNED
+    var model = skin_model(vertex.joint_indices, vertex.joint_weights);
+#else
+#ifdef MESH_INSTANCED
+    var model = mat4x4<f32>(
+        vertex.instance_model_0,
+        vertex.instance_model_1,
+        vertex.instance_model_2,
+        vertex.instance_model_3
+    );
+#else
+    var model = mesh.model;
+#endif
+#endif
+
+    // NOTE: This matches the computation of the main pass vertex shader, so that the main pass
+    // draws the visible fragments at exactly the depth written by the prepass.
+    out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+
+#ifdef VERTEX_NORMALS
+#ifdef SKINNED
+    out.world_normal = skin_normals(model, vertex.normal);
+#else
+#ifdef MESH_INSTANCED
+    out.world_normal = mesh_normal_local_to_world_with(
+        mat3x3<f32>(
+            vertex.instance_inverse_transpose_model_0,
+            vertex.instance_inverse_transpose_model_1,
+            vertex.instance_inverse_transpose_model_2
+        ),
+        vertex.normal
+    );
+#else
+    out.world_normal = mesh_normal_local_to_world(vertex.normal);
+#endif
+#endif
+#endif
+
+#ifdef MOTION_VECTOR_PREPASS
+    out.world_position = mesh_position_local_to_world(model, vec4<f32>(vertex.position, 1.0));
+#ifdef SKINNED
+    // The joints of the previous frame aren't known, so skinned meshes only move with the camera
+    out.previous_world_position = out.world_position;
+#else
+    out.previous_world_position = mesh_position_local_to_world(mesh.previous_model, vec4<f32>(vertex.position, 1.0));
+#endif
+#endif
+
+    return out;
+}
+
+#ifdef PREPASS_FRAGMENT
+struct FragmentInput {
+    @builtin(position) position: vec4<f32>,
+#ifdef VERTEX_NORMALS
+    @location(0) world_normal: vec3<f32>,
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    @location(1) world_position: vec4<f32>,
+    @location(2) previous_world_position: vec4<f32>,
+#endif
+};
+
+struct FragmentOutput {
+#ifdef NORMAL_PREPASS
+    @location(0) normal: vec4<f32>,
+#endif
+#ifdef MOTION_VECTOR_PREPASS
+    @location(1) motion_vector: vec2<f32>,
+#endif
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> FragmentOutput {
+#ifdef LOD_CROSS_FADE
+    if (lod_cross_fade_discarded(in.position.xy)) {
+        discard;
+    }
+#endif
+
+    var out: FragmentOutput;
+
+#ifdef NORMAL_PREPASS
+#ifdef VERTEX_NORMALS
+    out.normal = vec4<f32>(normalize(in.world_normal) * 0.5 + vec3<f32>(0.5), 1.0);
+#else
+    // Meshes without normals write a zero normal
+    out.normal = vec4<f32>(0.5, 0.5, 0.5, 1.0);
+#endif
+#endif
+
+#ifdef MOTION_VECTOR_PREPASS
+    // NOTE: The jitter of the projection is left out, so that a still mesh has no motion
+    let clip_position = view.unjittered_view_proj * in.world_position;
+    let previous_clip_position = previous_view_proj * in.previous_world_position;
+    let ndc_motion = clip_position.xy / clip_position.w
+        - previous_clip_position.xy / previous_clip_position.w;
+    // The V axis of the UVs points down
+    out.motion_vector = ndc_motion * vec2<f32>(0.5, -0.5);
+#endif
+
+    return out;
+}
+#else
+// Only the depth is written, by the fixed function pipeline
+#ifdef LOD_CROSS_FADE
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) {
+    if (lod_cross_fade_discarded(position.xy)) {
+        discard;
+    }
+}
+#else
+@fragment
+fn fragment() {
+}
+#endif
+#endif
@@ -1,5 +1,121 @@
 #define_import_path bevy_pbr::shadows
 
+fn sample_shadow_map_hardware(light_local: vec2<f32>, depth: f32, array_index: i32) -> f32 {
+    // do the lookup, using HW PCF and comparison
+    // NOTE: Due to non-uniform control flow in the callers, we must use the level variant of the
+    // texture sampler to avoid use of implicit derivatives causing possible undefined behavior.
+#ifdef NO_ARRAY_TEXTURES_SUPPORT
+    return textureSampleCompareLevel(directional_shadow_textures, directional_shadow_textures_sampler, light_local, depth);
+#else
+    return textureSampleCompareLevel(directional_shadow_textures, directional_shadow_textures_sampler, light_local, array_index, depth);
+#endif
+}
+
+// https://web.archive.org/web/20230210095515/http://the-witness.net/news/2013/09/shadow-mapping-summary-part-1
+// Uses 9 hardware 2x2 PCF lookups with weights chosen such that the result matches a 5x5 tent
+// filter kernel, which is equivalent to 13 individual samples.
+fn sample_shadow_map_castano_thirteen(light_local: vec2<f32>, depth: f32, array_index: i32) -> f32 {
+    let shadow_map_size = vec2<f32>(textureDimensions(directional_shadow_textures));
+    let inv_shadow_map_size = 1.0 / shadow_map_size;
+
+    let uv = light_local * shadow_map_size;
+    var base_uv = floor(uv + 0.5);
+    let s = (uv.x + 0.5 - base_uv.x);
+    let t = (uv.y + 0.5 - base_uv.y);
+    base_uv -= 0.5;
+    base_uv *= inv_shadow_map_size;
+
+    let uw0 = (4.0 - 3.0 * s);
+    let uw1 = 7.0;
+    let uw2 = (1.0 + 3.0 * s);
+
+    let u0 = (3.0 - 2.0 * s) / uw0 - 2.0;
+    let u1 = (3.0 + s) / uw1;
+    let u2 = s / uw2 + 2.0;
+
+    let vw0 = (4.0 - 3.0 * t);
+    let vw1 = 7.0;
+    let vw2 = (1.0 + 3.0 * t);
+
+    let v0 = (3.0 - 2.0 * t) / vw0 - 2.0;
+    let v1 = (3.0 + t) / vw1;
+    let v2 = t / vw2 + 2.0;
+
+    var sum = 0.0;
+
+    sum += uw0 * vw0 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u0, v0) * inv_shadow_map_size), depth, array_index);
+    sum += uw1 * vw0 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u1, v0) * inv_shadow_map_size), depth, array_index);
+    sum += uw2 * vw0 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u2, v0) * inv_shadow_map_size), depth, array_index);
+
+    sum += uw0 * vw1 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u0, v1) * inv_shadow_map_size), depth, array_index);
+    sum += uw1 * vw1 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u1, v1) * inv_shadow_map_size), depth, array_index);
+    sum += uw2 * vw1 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u2, v1) * inv_shadow_map_size), depth, array_index);
+
+    sum += uw0 * vw2 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u0, v2) * inv_shadow_map_size), depth, array_index);
+    sum += uw1 * vw2 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u1, v2) * inv_shadow_map_size), depth, array_index);
+    sum += uw2 * vw2 * sample_shadow_map_hardware(base_uv + (vec2<f32>(u2, v2) * inv_shadow_map_size), depth, array_index);
+
+    return sum * (1.0 / 144.0);
+}
+
+fn sample_shadow_map(light_local: vec2<f32>, depth: f32, array_index: i32) -> f32 {
+#ifdef SHADOW_FILTER_METHOD_CASTANO_13
+    return sample_shadow_map_castano_thirteen(light_local, depth, array_index);
+#else
+    return sample_shadow_map_hardware(light_local, depth, array_index);
+#endif
+}
+
+fn sample_point_shadow_map_hardware(light_local: vec3<f32>, depth: f32, light_id: u32) -> f32 {
+    // do the lookup, using HW PCF and comparison
+    // NOTE: Due to the non-uniform control flow in the callers, we must use the Level variant of
+    // textureSampleCompare to avoid undefined behaviour due to some of the fragments in
+    // a quad (2x2 fragments) being processed not being sampled, and this messing with
+    // mip-mapping functionality. The shadow maps have no mipmaps so Level just samples
+    // from LOD 0.
+#ifdef NO_ARRAY_TEXTURES_SUPPORT
+    return textureSampleCompare(point_shadow_textures, point_shadow_textures_sampler, light_local, depth);
+#else
+    return textureSampleCompareLevel(point_shadow_textures, point_shadow_textures_sampler, light_local, i32(light_id), depth);
+#endif
+}
+
+// Applies a 3x3 tent filter in the plane perpendicular to the lookup direction. Each tap is a
+// hardware 2x2 PCF lookup offset by one texel of the cube face the direction falls into.
+fn sample_point_shadow_map_tent(light_local: vec3<f32>, depth: f32, major_axis_magnitude: f32, light_id: u32) -> f32 {
+    // the cube faces span [-major_axis_magnitude, major_axis_magnitude] at the fragment's depth
+    let shadow_map_size = f32(textureDimensions(point_shadow_textures).x);
+    let texel_size = 2.0 * major_axis_magnitude / shadow_map_size;
+
+    // build an orthonormal basis around the lookup direction so a 2D kernel can be applied
+    let direction = normalize(light_local);
+    var up = vec3<f32>(0.0, 1.0, 0.0);
+    if (abs(direction.y) > 0.99) {
+        up = vec3<f32>(1.0, 0.0, 0.0);
+    }
+    let x_basis = normalize(cross(up, direction)) * texel_size;
+    let y_basis = cross(direction, x_basis);
+
+    var sum = 0.0;
+    for (var y = -1; y <= 1; y += 1) {
+        for (var x = -1; x <= 1; x += 1) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y)));
+            let offset = f32(x) * x_basis + f32(y) * y_basis;
+            sum += weight * sample_point_shadow_map_hardware(light_local + offset, depth, light_id);
+        }
+    }
+
+    return sum * (1.0 / 16.0);
+}
+
+fn sample_point_shadow_map(light_local: vec3<f32>, depth: f32, major_axis_magnitude: f32, light_id: u32) -> f32 {
+#ifdef SHADOW_FILTER_METHOD_CASTANO_13
+    return sample_point_shadow_map_tent(light_local, depth, major_axis_magnitude, light_id);
+#else
+    return sample_point_shadow_map_hardware(light_local, depth, light_id);
+#endif
+}
+
 fn fetch_point_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: vec3<f32>) -> f32 {
     let light = point_lights.data[light_id];
 
@@ -28,17 +144,7 @@ fn fetch_point_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: v
     let zw = -major_axis_magnitude * light.light_custom_data.xy + light.light_custom_data.zw;
     let depth = zw.x / zw.y;
 
-    // do the lookup, using HW PCF and comparison
-    // NOTE: Due to the non-uniform control flow above, we must use the Level variant of
-    // textureSampleCompare to avoid undefined behaviour due to some of the fragments in
-    // a quad (2x2 fragments) being processed not being sampled, and this messing with
-    // mip-mapping functionality. The shadow maps have no mipmaps so Level just samples
-    // from LOD 0.
-#ifdef NO_ARRAY_TEXTURES_SUPPORT
-    return textureSampleCompare(point_shadow_textures, point_shadow_textures_sampler, frag_ls, depth);
-#else
-    return textureSampleCompareLevel(point_shadow_textures, point_shadow_textures_sampler, frag_ls, i32(light_id), depth);
-#endif
+    return sample_point_shadow_map(frag_ls, depth, major_axis_magnitude, light_id);
 }
 
 fn fetch_spot_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: vec3<f32>) -> f32 {
@@ -89,24 +195,30 @@ fn fetch_spot_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: ve
     // 0.1 must match POINT_LIGHT_NEAR_Z
     let depth = 0.1 / -projected_position.z;
 
-    #ifdef NO_ARRAY_TEXTURES_SUPPORT
-        return textureSampleCompare(directional_shadow_textures, directional_shadow_textures_sampler, 
-            shadow_uv, depth);
-    #else
-        return textureSampleCompareLevel(directional_shadow_textures, directional_shadow_textures_sampler, 
-            shadow_uv, i32(light_id) + lights.spot_light_shadowmap_offset, depth);
-    #endif
+    return sample_shadow_map(shadow_uv, depth, i32(light_id) + lights.spot_light_shadowmap_offset);
 }
 
-fn fetch_directional_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: vec3<f32>) -> f32 {
-    let light = lights.directional_lights[light_id];
+fn get_cascade_index(light_id: u32, view_z: f32) -> u32 {
+    let light = &lights.directional_lights[light_id];
+
+    for (var i: u32 = 0u; i < (*light).num_cascades; i = i + 1u) {
+        if (-view_z < (*light).cascades[i].far_bound) {
+            return i;
+        }
+    }
+    return (*light).num_cascades;
+}
+
+fn sample_cascade(light_id: u32, cascade_index: u32, frag_position: vec4<f32>, surface_normal: vec3<f32>) -> f32 {
+    let light = &lights.directional_lights[light_id];
+    let cascade = &(*light).cascades[cascade_index];
 
     // The normal bias is scaled to the texel size.
-    let normal_offset = light.shadow_normal_bias * surface_normal.xyz;
-    let depth_offset = light.shadow_depth_bias * light.direction_to_light.xyz;
+    let normal_offset = (*light).shadow_normal_bias * (*cascade).texel_size * surface_normal.xyz;
+    let depth_offset = (*light).shadow_depth_bias * (*light).direction_to_light.xyz;
     let offset_position = vec4<f32>(frag_position.xyz + normal_offset + depth_offset, frag_position.w);
 
-    let offset_position_clip = light.view_projection * offset_position;
+    let offset_position_clip = (*cascade).view_projection * offset_position;
     if (offset_position_clip.w <= 0.0) {
         return 1.0;
     }
@@ -123,12 +235,42 @@ fn fetch_directional_shadow(light_id: u32, frag_position: vec4<f32>, surface_nor
     let light_local = offset_position_ndc.xy * flip_correction + vec2<f32>(0.5, 0.5);
 
     let depth = offset_position_ndc.z;
-    // do the lookup, using HW PCF and comparison
-    // NOTE: Due to non-uniform control flow above, we must use the level variant of the texture
-    // sampler to avoid use of implicit derivatives causing possible undefined behavior.
-#ifdef NO_ARRAY_TEXTURES_SUPPORT
-    return textureSampleCompareLevel(directional_shadow_textures, directional_shadow_textures_sampler, light_local, depth);
-#else
-    return textureSampleCompareLevel(directional_shadow_textures, directional_shadow_textures_sampler, light_local, i32(light_id), depth);
-#endif
+    let array_index = i32((*light).depth_texture_base_index + cascade_index);
+    return sample_shadow_map(light_local, depth, array_index);
+}
+
+fn fetch_directional_shadow(light_id: u32, frag_position: vec4<f32>, surface_normal: vec3<f32>, view_z: f32) -> f32 {
+    let light = &lights.directional_lights[light_id];
+    let cascade_index = get_cascade_index(light_id, view_z);
+
+    if (cascade_index >= (*light).num_cascades) {
+        return 1.0;
+    }
+
+    var shadow = sample_cascade(light_id, cascade_index, frag_position, surface_normal);
+
+    // Blend with the next cascade, if there is one, in the region where both overlap.
+    let next_cascade_index = cascade_index + 1u;
+    if (next_cascade_index < (*light).num_cascades) {
+        let this_far_bound = (*light).cascades[cascade_index].far_bound;
+        let next_near_bound = (1.0 - (*light).cascades_overlap_proportion) * this_far_bound;
+        if (-view_z >= next_near_bound) {
+            let next_shadow = sample_cascade(light_id, next_cascade_index, frag_position, surface_normal);
+            shadow = mix(shadow, next_shadow, (-view_z - next_near_bound) / (this_far_bound - next_near_bound));
+        }
+    }
+    return shadow;
+}
+
+fn cascade_debug_visualization(
+    output_color: vec3<f32>,
+    light_id: u32,
+    view_z: f32,
+) -> vec3<f32> {
+    let overlay_alpha = 0.95;
+    let cascade_index = get_cascade_index(light_id, view_z);
+    let cascade_color = hsv2rgb(f32(cascade_index) / f32(#{MAX_CASCADES_PER_LIGHT}u + 1u), 1.0, 0.5);
+    return vec3<f32>(
+        (1.0 - overlay_alpha) * output_color.rgb + overlay_alpha * cascade_color
+    );
 }
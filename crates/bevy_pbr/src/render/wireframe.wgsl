@@ -9,6 +9,13 @@ var<uniform> mesh: Mesh;
 var<uniform> joint_matrices: SkinnedMesh;
 #import bevy_pbr::skinning
 #endif
+#ifdef MORPH_TARGETS
+@group(1) @binding(2)
+var<uniform> morph_weights: MorphWeights;
+@group(1) @binding(3)
+var morph_targets: texture_2d_array<f32>;
+#import bevy_pbr::morph
+#endif
 
 // NOTE: Bindings must come before functions that use them!
 #import bevy_pbr::mesh_functions
@@ -16,8 +23,11 @@ var<uniform> joint_matrices: SkinnedMesh;
 struct Vertex {
     @location(0) position: vec3<f32>,
 #ifdef SKINNED
-    @location(4) joint_indexes: vec4<u32>,
-    @location(5) joint_weights: vec4<f32>,
+    @location(5) joint_indexes: vec4<u32>,
+    @location(6) joint_weights: vec4<f32>,
+#endif
+#ifdef MORPH_TARGETS
+    @builtin(vertex_index) index: u32,
 #endif
 };
 
@@ -26,7 +36,12 @@ struct VertexOutput {
 };
 
 @vertex
-fn vertex(vertex: Vertex) -> VertexOutput {
+fn vertex(vertex_no_morph: Vertex) -> VertexOutput {
+    var vertex = vertex_no_morph;
+#ifdef MORPH_TARGETS
+    vertex.position = morph_position(vertex.index, vertex.position);
+#endif
+
 #ifdef SKINNED
     let model = skin_model(vertex.joint_indexes, vertex.joint_weights);
 #else
@@ -0,0 +1,143 @@
+// Ground Truth-based Ambient Occlusion (GTAO)
+// Paper: https://www.activision.com/cdn/research/Practical_Real_Time_Strategies_for_Accurate_Indirect_Occlusion_NEW%20VERSION_COLOR.pdf
+// Presentation: https://blog.selfshadow.com/publications/s2016-shading-course/activision/s2016_pbs_activision_occlusion.pdf
+// Based on XeGTAO: https://github.com/GameTechDev/XeGTAO
+
+#import bevy_pbr::mesh_view_types
+
+#ifdef MULTISAMPLED
+@group(0) @binding(0)
+var prepass_depth: texture_multisampled_2d<f32>;
+@group(0) @binding(1)
+var prepass_normals: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(0)
+var prepass_depth: texture_2d<f32>;
+@group(0) @binding(1)
+var prepass_normals: texture_2d<f32>;
+#endif
+@group(0) @binding(2)
+var<uniform> view: View;
+
+#import bevy_pbr::ssao_utils
+
+let PI: f32 = 3.141592653589793;
+let HALF_PI: f32 = 1.5707963267948966;
+
+// The world space radius around a point within which other points occlude it
+let EFFECT_RADIUS: f32 = 0.5;
+// The distance over which the occlusion of farther points fades out, relative to EFFECT_RADIUS
+let FALLOFF_RANGE: f32 = 0.615;
+
+fn fast_acos(in_x: f32) -> f32 {
+    let x = abs(in_x);
+    let res = (-0.156583 * x + HALF_PI) * sqrt(1.0 - x);
+    return select(PI - res, res, in_x >= 0.0);
+}
+
+// Interleaved gradient noise, from "Next Generation Post Processing in Call of Duty: Advanced Warfare"
+fn interleaved_gradient_noise(pixel: vec2<f32>) -> f32 {
+    return fract(52.9829189 * fract(dot(pixel, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+fn load_and_reconstruct_view_space_position(uv: vec2<f32>) -> vec3<f32> {
+    let pixel = clamp_to_viewport(vec2<i32>(uv * view.viewport.zw + view.viewport.xy));
+    return reconstruct_view_space_position(load_depth(pixel), pixel_uv(pixel));
+}
+
+@fragment
+fn gtao(
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(position.xy);
+    let depth = load_depth(pixel);
+    let world_normal = load_normal(pixel);
+    // Nothing was drawn there
+    if depth == 0.0 || dot(world_normal, world_normal) == 0.0 {
+        return vec4<f32>(1.0);
+    }
+
+    let falloff_range = FALLOFF_RANGE * EFFECT_RADIUS;
+    let falloff_from = EFFECT_RADIUS * (1.0 - FALLOFF_RANGE);
+    let falloff_mul = -1.0 / falloff_range;
+    let falloff_add = falloff_from / falloff_range + 1.0;
+
+    let pixel_position = reconstruct_view_space_position(depth, uv);
+    let inverse_view = mat3x3<f32>(
+        view.inverse_view[0].xyz,
+        view.inverse_view[1].xyz,
+        view.inverse_view[2].xyz,
+    );
+    let pixel_normal = normalize(inverse_view * world_normal);
+    var view_vec = normalize(-pixel_position);
+    var distance_scale = -pixel_position.z;
+    if is_orthographic() {
+        view_vec = vec3<f32>(0.0, 0.0, 1.0);
+        distance_scale = 1.0;
+    }
+
+    let noise = vec2<f32>(
+        interleaved_gradient_noise(position.xy),
+        interleaved_gradient_noise(position.xy + vec2<f32>(47.0, 17.0)),
+    );
+    // The UV offset of a point EFFECT_RADIUS away from the pixel along each axis
+    let sample_scale = 0.5 * EFFECT_RADIUS * vec2<f32>(view.projection[0].x, view.projection[1].y) / distance_scale;
+
+    let slice_count = f32(#{SLICE_COUNT}u);
+    let samples_per_slice_side = f32(#{SAMPLES_PER_SLICE_SIDE}u);
+    var visibility = 0.0;
+    for (var slice_t = 0.0; slice_t < slice_count; slice_t += 1.0) {
+        let slice = slice_t + noise.x;
+        let phi = (PI / slice_count) * slice;
+        let omega = vec2<f32>(cos(phi), sin(phi));
+
+        let direction = vec3<f32>(omega, 0.0);
+        let orthographic_direction = direction - (dot(direction, view_vec) * view_vec);
+        let axis = cross(direction, view_vec);
+        let projected_normal = pixel_normal - axis * dot(pixel_normal, axis);
+        let projected_normal_length = length(projected_normal);
+
+        let sign_norm = sign(dot(orthographic_direction, projected_normal));
+        let cos_norm = saturate(dot(projected_normal, view_vec) / projected_normal_length);
+        let n = sign_norm * fast_acos(cos_norm);
+
+        let min_cos_horizon_1 = cos(n + HALF_PI);
+        let min_cos_horizon_2 = cos(n - HALF_PI);
+        var cos_horizon_1 = min_cos_horizon_1;
+        var cos_horizon_2 = min_cos_horizon_2;
+        // The V axis of the UVs points down
+        let sample_mul = vec2<f32>(omega.x, -omega.y) * sample_scale;
+        for (var sample_t = 0.0; sample_t < samples_per_slice_side; sample_t += 1.0) {
+            let sample_noise = fract(noise.y + (slice_t + sample_t * samples_per_slice_side) * 0.6180339887498948);
+            var s = (sample_t + sample_noise) / samples_per_slice_side;
+            // More samples close to the pixel, where the occlusion matters the most
+            s *= s;
+            let sample = s * sample_mul;
+
+            let sample_difference_1 = load_and_reconstruct_view_space_position(uv + sample) - pixel_position;
+            let sample_difference_2 = load_and_reconstruct_view_space_position(uv - sample) - pixel_position;
+            let sample_distance_1 = max(length(sample_difference_1), 0.0001);
+            let sample_distance_2 = max(length(sample_difference_2), 0.0001);
+            var sample_cos_horizon_1 = dot(sample_difference_1 / sample_distance_1, view_vec);
+            var sample_cos_horizon_2 = dot(sample_difference_2 / sample_distance_2, view_vec);
+
+            let weight_1 = saturate(sample_distance_1 * falloff_mul + falloff_add);
+            let weight_2 = saturate(sample_distance_2 * falloff_mul + falloff_add);
+            sample_cos_horizon_1 = mix(min_cos_horizon_1, sample_cos_horizon_1, weight_1);
+            sample_cos_horizon_2 = mix(min_cos_horizon_2, sample_cos_horizon_2, weight_2);
+
+            cos_horizon_1 = max(cos_horizon_1, sample_cos_horizon_1);
+            cos_horizon_2 = max(cos_horizon_2, sample_cos_horizon_2);
+        }
+
+        let horizon_1 = fast_acos(cos_horizon_1);
+        let horizon_2 = -fast_acos(cos_horizon_2);
+        let v1 = (cos_norm + 2.0 * horizon_1 * sin(n) - cos(2.0 * horizon_1 - n)) / 4.0;
+        let v2 = (cos_norm + 2.0 * horizon_2 * sin(n) - cos(2.0 * horizon_2 - n)) / 4.0;
+        visibility += projected_normal_length * (v1 + v2);
+    }
+    visibility /= slice_count;
+
+    return vec4<f32>(clamp(visibility, 0.03, 1.0));
+}
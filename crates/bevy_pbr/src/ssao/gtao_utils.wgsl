@@ -0,0 +1,12 @@
+#define_import_path bevy_pbr::gtao_utils
+
+// Approximates the light bouncing between the occluding surfaces, which makes the ambient
+// occlusion of bright surfaces lighter and tinted by their color.
+// From "Practical Real-Time Strategies for Accurate Indirect Occlusion", Jimenez et al. 2016
+fn gtao_multibounce(visibility: f32, base_color: vec3<f32>) -> vec3<f32> {
+    let a = 2.0404 * base_color - vec3<f32>(0.3324);
+    let b = -4.7951 * base_color + vec3<f32>(0.6417);
+    let c = 2.7552 * base_color + vec3<f32>(0.6903);
+    let x = vec3<f32>(visibility);
+    return max(x, ((x * a + b) * x + c) * x);
+}
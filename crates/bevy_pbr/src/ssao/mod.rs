@@ -0,0 +1,622 @@
+//! Screen space ambient occlusion, which darkens the ambient light received by the creases and
+//! corners of the meshes a 3D camera sees.
+//!
+//! Add a [`ScreenSpaceAmbientOcclusionBundle`] to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d)
+//! to enable it.
+//!
+//! The occlusion is computed from the depth and normal prepass of the view with
+//! [GTAO](https://www.activision.com/cdn/research/Practical_Real_Time_Strategies_for_Accurate_Indirect_Occlusion_NEW%20VERSION_COLOR.pdf),
+//! then blurred and stored in the [`ScreenSpaceAmbientOcclusionTextures`] of the view, before its
+//! main pass multiplies it into the ambient light and environment map lighting of the
+//! [`StandardMaterial`](crate::StandardMaterial)s it renders.
+
+use crate::{draw_3d_graph, MAX_CASCADES_PER_LIGHT, MAX_DIRECTIONAL_LIGHTS};
+use bevy_app::{App, Plugin};
+use bevy_asset::{load_internal_asset, HandleUntyped};
+use bevy_core_pipeline::{
+    core_3d,
+    fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prepass::{DepthPrepass, NormalPrepass, ViewPrepassTextures},
+};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_reflect::{Reflect, TypeUuid};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::{ExtractComponent, ExtractComponentPlugin},
+    prelude::{Camera, Color},
+    render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo, SlotType},
+    render_phase::TrackedRenderPass,
+    render_resource::*,
+    renderer::{RenderContext, RenderDevice},
+    texture::{CachedTexture, TextureCache},
+    view::{Msaa, ViewUniform, ViewUniformOffset, ViewUniforms},
+    RenderApp, RenderStage,
+};
+#[cfg(feature = "trace")]
+use bevy_utils::tracing::info_span;
+
+const GTAO_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1077592962430684432);
+const SPATIAL_DENOISE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 4305018245103263315);
+const SSAO_UTILS_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 13209617208550698724);
+pub const GTAO_UTILS_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7501956430974020552);
+
+/// The format of the textures the ambient occlusion is rendered to.
+pub const SCREEN_SPACE_AMBIENT_OCCLUSION_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+/// Adds support for [`ScreenSpaceAmbientOcclusionSettings`] to the 3D cameras.
+pub struct ScreenSpaceAmbientOcclusionPlugin;
+
+impl Plugin for ScreenSpaceAmbientOcclusionPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, GTAO_SHADER_HANDLE, "gtao.wgsl", Shader::from_wgsl);
+        load_internal_asset!(
+            app,
+            SPATIAL_DENOISE_SHADER_HANDLE,
+            "spatial_denoise.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            SSAO_UTILS_SHADER_HANDLE,
+            "ssao_utils.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            GTAO_UTILS_SHADER_HANDLE,
+            "gtao_utils.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<ScreenSpaceAmbientOcclusionSettings>()
+            .register_type::<ScreenSpaceAmbientOcclusionQualityLevel>()
+            .add_plugin(ExtractComponentPlugin::<ScreenSpaceAmbientOcclusionSettings>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .init_resource::<SsaoPipelines>()
+            .init_resource::<SpecializedRenderPipelines<SsaoPipelines>>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_ssao_textures)
+            .add_system_to_stage(RenderStage::Prepare, prepare_ssao_pipelines)
+            .add_system_to_stage(RenderStage::Queue, queue_ssao_bind_groups);
+
+        let ssao_node = ScreenSpaceAmbientOcclusionNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        let draw_3d_graph = graph.get_sub_graph_mut(core_3d::graph::NAME).unwrap();
+        draw_3d_graph.add_node(
+            draw_3d_graph::node::SCREEN_SPACE_AMBIENT_OCCLUSION,
+            ssao_node,
+        );
+        draw_3d_graph.add_slot_edge(
+            draw_3d_graph.input_node().id,
+            core_3d::graph::input::VIEW_ENTITY,
+            draw_3d_graph::node::SCREEN_SPACE_AMBIENT_OCCLUSION,
+            ScreenSpaceAmbientOcclusionNode::IN_VIEW,
+        );
+        // PREPASS -> SCREEN_SPACE_AMBIENT_OCCLUSION -> MAIN_PASS
+        draw_3d_graph.add_node_edge(
+            core_3d::graph::node::PREPASS,
+            draw_3d_graph::node::SCREEN_SPACE_AMBIENT_OCCLUSION,
+        );
+        draw_3d_graph.add_node_edge(
+            draw_3d_graph::node::SCREEN_SPACE_AMBIENT_OCCLUSION,
+            core_3d::graph::node::MAIN_PASS,
+        );
+    }
+}
+
+/// The components a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) needs for screen space
+/// ambient occlusion.
+#[derive(Bundle, Default)]
+pub struct ScreenSpaceAmbientOcclusionBundle {
+    pub settings: ScreenSpaceAmbientOcclusionSettings,
+    pub depth_prepass: DepthPrepass,
+    pub normal_prepass: NormalPrepass,
+}
+
+/// Add this component to a [`Camera3d`](bevy_core_pipeline::core_3d::Camera3d) to darken the
+/// ambient light received by the parts of its meshes that are occluded by nearby geometry.
+///
+/// The camera also needs the [`DepthPrepass`] and [`NormalPrepass`] components, which the
+/// [`ScreenSpaceAmbientOcclusionBundle`] adds. Remove this component to disable the effect.
+///
+/// Only the ambient light, from the [`AmbientLight`](crate::AmbientLight) and the
+/// [`EnvironmentMapLight`](crate::EnvironmentMapLight), is occluded: the direct lights have
+/// their own shadows.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct ScreenSpaceAmbientOcclusionSettings {
+    pub quality_level: ScreenSpaceAmbientOcclusionQualityLevel,
+}
+
+/// How many samples [`ScreenSpaceAmbientOcclusionSettings`] takes for each pixel, trading
+/// performance for less noise.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ScreenSpaceAmbientOcclusionQualityLevel {
+    Low,
+    Medium,
+    #[default]
+    High,
+    Ultra,
+    Custom {
+        /// The number of directions around each pixel the occlusion is searched in.
+        slice_count: u32,
+        /// The number of samples along each side of each direction.
+        samples_per_slice_side: u32,
+    },
+}
+
+impl ScreenSpaceAmbientOcclusionQualityLevel {
+    /// The number of directions around each pixel the occlusion is searched in.
+    pub fn slice_count(&self) -> u32 {
+        match self {
+            Self::Low => 1,
+            Self::Medium => 2,
+            Self::High => 3,
+            Self::Ultra => 9,
+            Self::Custom { slice_count, .. } => *slice_count,
+        }
+    }
+
+    /// The number of samples along each side of each direction.
+    pub fn samples_per_slice_side(&self) -> u32 {
+        match self {
+            Self::Low | Self::Medium => 2,
+            Self::High | Self::Ultra => 3,
+            Self::Custom {
+                samples_per_slice_side,
+                ..
+            } => *samples_per_slice_side,
+        }
+    }
+}
+
+impl ExtractComponent for ScreenSpaceAmbientOcclusionSettings {
+    type Query = (&'static Self, &'static Camera);
+    type Filter = (With<DepthPrepass>, With<NormalPrepass>);
+    type Out = Self;
+
+    fn extract_component((settings, camera): QueryItem<'_, Self::Query>) -> Option<Self::Out> {
+        camera.is_active.then(|| settings.clone())
+    }
+}
+
+/// The ambient occlusion of a view, which has them if its camera has a
+/// [`ScreenSpaceAmbientOcclusionSettings`].
+///
+/// They have the size of the render target of the view, with a
+/// [`SCREEN_SPACE_AMBIENT_OCCLUSION_FORMAT`] format.
+#[derive(Component)]
+pub struct ScreenSpaceAmbientOcclusionTextures {
+    /// The occlusion written by the GTAO pass, before the spatial denoising pass.
+    pub noisy: CachedTexture,
+    /// The denoised occlusion, sampled by the main pass.
+    pub denoised: CachedTexture,
+}
+
+fn prepare_ssao_textures(
+    mut commands: Commands,
+    texture_cache: Res<TextureCache>,
+    render_device: Res<RenderDevice>,
+    views: Query<(Entity, &ExtractedCamera), With<ScreenSpaceAmbientOcclusionSettings>>,
+) {
+    for (entity, camera) in &views {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+        let texture = |label| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: physical_target_size.x,
+                        height: physical_target_size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: SCREEN_SPACE_AMBIENT_OCCLUSION_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                },
+            )
+        };
+        let noisy = texture("ssao_noisy_texture");
+        let denoised = texture("ssao_denoised_texture");
+        commands
+            .entity(entity)
+            .insert(ScreenSpaceAmbientOcclusionTextures { noisy, denoised });
+    }
+}
+
+#[derive(Resource)]
+struct SsaoPipelines {
+    prepass_layout: BindGroupLayout,
+    multisampled_prepass_layout: BindGroupLayout,
+    noisy_layout: BindGroupLayout,
+}
+
+impl SsaoPipelines {
+    fn prepass_layout(&self, multisampled: bool) -> &BindGroupLayout {
+        if multisampled {
+            &self.multisampled_prepass_layout
+        } else {
+            &self.prepass_layout
+        }
+    }
+}
+
+impl FromWorld for SsaoPipelines {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let prepass_layout = |label, multisampled| {
+            render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &[
+                    // Prepass depth
+                    // NOTE: Bound as a float texture, as loading the texels of a depth texture
+                    // isn't supported by every backend
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                    // Prepass normals
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: false },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled,
+                        },
+                        count: None,
+                    },
+                    // View
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: Some(ViewUniform::min_size()),
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        };
+
+        let noisy_layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("ssao_noisy_layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        SsaoPipelines {
+            prepass_layout: prepass_layout("ssao_prepass_layout", false),
+            multisampled_prepass_layout: prepass_layout("ssao_multisampled_prepass_layout", true),
+            noisy_layout,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum SsaoPass {
+    Gtao {
+        slice_count: u32,
+        samples_per_slice_side: u32,
+    },
+    SpatialDenoise,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+struct SsaoPipelineKey {
+    pass: SsaoPass,
+    multisampled: bool,
+}
+
+impl SpecializedRenderPipeline for SsaoPipelines {
+    type Key = SsaoPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        // The shaders import the `View` from `bevy_pbr::mesh_view_types`
+        let mut shader_defs = vec![
+            ShaderDefVal::UInt(
+                "MAX_DIRECTIONAL_LIGHTS".to_string(),
+                MAX_DIRECTIONAL_LIGHTS as u32,
+            ),
+            ShaderDefVal::UInt(
+                "MAX_CASCADES_PER_LIGHT".to_string(),
+                MAX_CASCADES_PER_LIGHT as u32,
+            ),
+        ];
+        if key.multisampled {
+            shader_defs.push("MULTISAMPLED".into());
+        }
+        let mut layout = vec![self.prepass_layout(key.multisampled).clone()];
+
+        let (label, shader, entry_point) = match key.pass {
+            SsaoPass::Gtao {
+                slice_count,
+                samples_per_slice_side,
+            } => {
+                shader_defs.push(ShaderDefVal::UInt("SLICE_COUNT".to_string(), slice_count));
+                shader_defs.push(ShaderDefVal::UInt(
+                    "SAMPLES_PER_SLICE_SIDE".to_string(),
+                    samples_per_slice_side,
+                ));
+                ("ssao_gtao_pipeline", GTAO_SHADER_HANDLE, "gtao")
+            }
+            SsaoPass::SpatialDenoise => {
+                layout.push(self.noisy_layout.clone());
+                (
+                    "ssao_spatial_denoise_pipeline",
+                    SPATIAL_DENOISE_SHADER_HANDLE,
+                    "spatial_denoise",
+                )
+            }
+        };
+
+        RenderPipelineDescriptor {
+            label: Some(label.into()),
+            layout: Some(layout),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.typed(),
+                shader_defs,
+                entry_point: entry_point.into(),
+                targets: vec![Some(ColorTargetState {
+                    format: SCREEN_SPACE_AMBIENT_OCCLUSION_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct SsaoPipelineIds {
+    gtao: CachedRenderPipelineId,
+    spatial_denoise: CachedRenderPipelineId,
+}
+
+fn prepare_ssao_pipelines(
+    mut commands: Commands,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<SsaoPipelines>>,
+    ssao_pipelines: Res<SsaoPipelines>,
+    msaa: Res<Msaa>,
+    views: Query<(Entity, &ScreenSpaceAmbientOcclusionSettings)>,
+) {
+    let multisampled = msaa.samples > 1;
+    for (entity, settings) in &views {
+        let gtao = pipelines.specialize(
+            &mut pipeline_cache,
+            &ssao_pipelines,
+            SsaoPipelineKey {
+                pass: SsaoPass::Gtao {
+                    slice_count: settings.quality_level.slice_count(),
+                    samples_per_slice_side: settings.quality_level.samples_per_slice_side(),
+                },
+                multisampled,
+            },
+        );
+        let spatial_denoise = pipelines.specialize(
+            &mut pipeline_cache,
+            &ssao_pipelines,
+            SsaoPipelineKey {
+                pass: SsaoPass::SpatialDenoise,
+                multisampled,
+            },
+        );
+
+        commands.entity(entity).insert(SsaoPipelineIds {
+            gtao,
+            spatial_denoise,
+        });
+    }
+}
+
+#[derive(Component)]
+struct SsaoBindGroups {
+    prepass_bind_group: BindGroup,
+    noisy_bind_group: BindGroup,
+}
+
+fn queue_ssao_bind_groups(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    ssao_pipelines: Res<SsaoPipelines>,
+    view_uniforms: Res<ViewUniforms>,
+    msaa: Res<Msaa>,
+    views: Query<(
+        Entity,
+        &ViewPrepassTextures,
+        &ScreenSpaceAmbientOcclusionTextures,
+    )>,
+) {
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    for (entity, prepass_textures, ssao_textures) in &views {
+        let (Some(depth), Some(normal)) = (&prepass_textures.depth, &prepass_textures.normal)
+        else {
+            continue;
+        };
+
+        let prepass_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("ssao_prepass_bind_group"),
+            layout: ssao_pipelines.prepass_layout(msaa.samples > 1),
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&depth.default_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&normal.default_view),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: view_binding.clone(),
+                },
+            ],
+        });
+        let noisy_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+            label: Some("ssao_noisy_bind_group"),
+            layout: &ssao_pipelines.noisy_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&ssao_textures.noisy.default_view),
+            }],
+        });
+
+        commands.entity(entity).insert(SsaoBindGroups {
+            prepass_bind_group,
+            noisy_bind_group,
+        });
+    }
+}
+
+/// Renders the [`ScreenSpaceAmbientOcclusionTextures`] of a view from its prepass textures.
+pub struct ScreenSpaceAmbientOcclusionNode {
+    query: QueryState<(
+        &'static ExtractedCamera,
+        &'static SsaoPipelineIds,
+        &'static SsaoBindGroups,
+        &'static ScreenSpaceAmbientOcclusionTextures,
+        &'static ViewUniformOffset,
+    )>,
+}
+
+impl ScreenSpaceAmbientOcclusionNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for ScreenSpaceAmbientOcclusionNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((camera, pipeline_ids, bind_groups, textures, view_uniform_offset)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            // The camera doesn't have screen space ambient occlusion
+            return Ok(());
+        };
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let (Some(gtao_pipeline), Some(spatial_denoise_pipeline)) = (
+            pipeline_cache.get_render_pipeline(pipeline_ids.gtao),
+            pipeline_cache.get_render_pipeline(pipeline_ids.spatial_denoise),
+        ) else {
+            return Ok(());
+        };
+
+        #[cfg(feature = "trace")]
+        let _ssao_span = info_span!("screen_space_ambient_occlusion").entered();
+
+        {
+            let mut gtao_pass =
+                TrackedRenderPass::new(render_context.command_encoder.begin_render_pass(
+                    &RenderPassDescriptor {
+                        label: Some("ssao_gtao_pass"),
+                        color_attachments: &[Some(ssao_color_attachment(&textures.noisy))],
+                        depth_stencil_attachment: None,
+                    },
+                ));
+            gtao_pass.set_render_pipeline(gtao_pipeline);
+            gtao_pass.set_bind_group(
+                0,
+                &bind_groups.prepass_bind_group,
+                &[view_uniform_offset.offset],
+            );
+            if let Some(viewport) = camera.viewport.as_ref() {
+                gtao_pass.set_camera_viewport(viewport);
+            }
+            gtao_pass.draw(0..3, 0..1);
+        }
+
+        {
+            let mut spatial_denoise_pass =
+                TrackedRenderPass::new(render_context.command_encoder.begin_render_pass(
+                    &RenderPassDescriptor {
+                        label: Some("ssao_spatial_denoise_pass"),
+                        color_attachments: &[Some(ssao_color_attachment(&textures.denoised))],
+                        depth_stencil_attachment: None,
+                    },
+                ));
+            spatial_denoise_pass.set_render_pipeline(spatial_denoise_pipeline);
+            spatial_denoise_pass.set_bind_group(
+                0,
+                &bind_groups.prepass_bind_group,
+                &[view_uniform_offset.offset],
+            );
+            spatial_denoise_pass.set_bind_group(1, &bind_groups.noisy_bind_group, &[]);
+            if let Some(viewport) = camera.viewport.as_ref() {
+                spatial_denoise_pass.set_camera_viewport(viewport);
+            }
+            spatial_denoise_pass.draw(0..3, 0..1);
+        }
+
+        Ok(())
+    }
+}
+
+fn ssao_color_attachment(target: &CachedTexture) -> RenderPassColorAttachment {
+    RenderPassColorAttachment {
+        view: &target.default_view,
+        resolve_target: None,
+        ops: Operations {
+            // Outside of the viewport, nothing is occluded
+            load: LoadOp::Clear(Color::WHITE.into()),
+            store: true,
+        },
+    }
+}
@@ -0,0 +1,63 @@
+// Blurs the noisy ambient occlusion written by the GTAO pass, without blurring it across the
+// edges of the meshes
+
+#import bevy_pbr::mesh_view_types
+
+#ifdef MULTISAMPLED
+@group(0) @binding(0)
+var prepass_depth: texture_multisampled_2d<f32>;
+@group(0) @binding(1)
+var prepass_normals: texture_multisampled_2d<f32>;
+#else
+@group(0) @binding(0)
+var prepass_depth: texture_2d<f32>;
+@group(0) @binding(1)
+var prepass_normals: texture_2d<f32>;
+#endif
+@group(0) @binding(2)
+var<uniform> view: View;
+
+@group(1) @binding(0)
+var ambient_occlusion_noisy: texture_2d<f32>;
+
+#import bevy_pbr::ssao_utils
+
+// Samples closer to the pixel than this distance, relative to its own distance to the camera,
+// are blurred with it
+let DEPTH_TOLERANCE: f32 = 0.05;
+
+@fragment
+fn spatial_denoise(
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(position.xy);
+    let depth = load_depth(pixel);
+    if depth == 0.0 {
+        return vec4<f32>(1.0);
+    }
+    let pixel_z = reconstruct_view_space_position(depth, uv).z;
+    let pixel_normal = load_normal(pixel);
+
+    var visibility = 0.0;
+    var weight_sum = 0.0;
+    for (var y = -2; y <= 2; y += 1) {
+        for (var x = -2; x <= 2; x += 1) {
+            let sample_pixel = clamp_to_viewport(pixel + vec2<i32>(x, y));
+            let sample_depth = load_depth(sample_pixel);
+            let sample_z = reconstruct_view_space_position(sample_depth, pixel_uv(sample_pixel)).z;
+            let sample_normal = load_normal(sample_pixel);
+
+            let depth_weight = saturate(1.0 - abs(sample_z - pixel_z) / (abs(pixel_z) * DEPTH_TOLERANCE));
+            let normal_weight = pow(saturate(dot(sample_normal, pixel_normal)), 8.0);
+            let distance_weight = exp(-f32(x * x + y * y) / 4.0);
+            let weight = depth_weight * normal_weight * distance_weight;
+
+            visibility += textureLoad(ambient_occlusion_noisy, sample_pixel, 0).r * weight;
+            weight_sum += weight;
+        }
+    }
+
+    // The center pixel always has a weight of 1.0
+    return vec4<f32>(visibility / weight_sum);
+}
@@ -0,0 +1,37 @@
+#define_import_path bevy_pbr::ssao_utils
+
+// NOTE: Expects the `view`, `prepass_depth` and `prepass_normals` bindings to be declared before
+// this file is imported.
+
+fn clamp_to_viewport(pixel: vec2<i32>) -> vec2<i32> {
+    let viewport_min = vec2<i32>(view.viewport.xy);
+    let viewport_max = viewport_min + vec2<i32>(view.viewport.zw) - vec2<i32>(1);
+    return clamp(pixel, viewport_min, viewport_max);
+}
+
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    return textureLoad(prepass_depth, pixel, 0).r;
+}
+
+// Returns the world space normal of the mesh at a pixel, or zero where no mesh was drawn
+fn load_normal(pixel: vec2<i32>) -> vec3<f32> {
+    let normal = textureLoad(prepass_normals, pixel, 0);
+    return (normal.xyz * 2.0 - vec3<f32>(1.0)) * normal.a;
+}
+
+// Returns the UV of the center of a pixel, relative to the viewport
+fn pixel_uv(pixel: vec2<i32>) -> vec2<f32> {
+    return (vec2<f32>(pixel) + vec2<f32>(0.5) - view.viewport.xy) / view.viewport.zw;
+}
+
+fn reconstruct_view_space_position(depth: f32, uv: vec2<f32>) -> vec3<f32> {
+    let clip_xy = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - 2.0 * uv.y);
+    // NOTE: The far plane of an infinite reverse-z projection is at a depth of 0.0, which would
+    // reconstruct a point at infinity
+    let t = view.inverse_projection * vec4<f32>(clip_xy, max(depth, 0.0000001), 1.0);
+    return t.xyz / t.w;
+}
+
+fn is_orthographic() -> bool {
+    return view.projection[3].w == 1.0;
+}
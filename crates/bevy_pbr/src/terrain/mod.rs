@@ -0,0 +1,657 @@
+//! Terrains generated from heightmaps, split into chunks whose level of detail follows the
+//! cameras.
+//!
+//! Add a [`TerrainBundle`] to spawn a terrain centered on its [`Transform`], extending along its
+//! local X and Z axes. The terrain is a quadtree: a node is split into four smaller ones while a
+//! camera is close to it, and each node left is drawn by a child entity of the terrain with a
+//! [`TerrainChunk`]. The chunks have the same number of vertices whatever their size, so the
+//! farther ones are coarser, and they are frustum culled like any other mesh.
+//!
+//! Neighboring chunks of different sizes don't share all of their edge vertices, so each chunk
+//! has skirts hanging down from its edges to hide the cracks between them.
+
+use crate::{Material, MaterialMeshBundle, MaterialPlugin};
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_asset::{load_internal_asset, AssetEvent, Assets, Handle, HandleUntyped};
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::{BuildChildren, DespawnRecursiveExt};
+use bevy_math::{Vec2, Vec3};
+use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect, TypeUuid};
+use bevy_render::{
+    camera::Camera,
+    mesh::{Indices, Mesh},
+    render_asset::RenderAssets,
+    render_resource::{
+        AsBindGroup, AsBindGroupShaderType, PrimitiveTopology, Shader, ShaderRef, ShaderType,
+        TextureFormat,
+    },
+    texture::Image,
+    view::{ComputedVisibility, Visibility},
+};
+use bevy_transform::components::{GlobalTransform, Transform};
+use bevy_utils::{tracing::warn, HashMap, HashSet};
+
+const TERRAIN_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 11473002667997393510);
+
+/// Generates the chunks of the [`Terrain`]s, and draws them with their [`TerrainMaterial`].
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            TERRAIN_SHADER_HANDLE,
+            "terrain.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<Terrain>()
+            .register_type::<TerrainMaterial>()
+            .add_plugin(MaterialPlugin::<TerrainMaterial>::default())
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                // NOTE: Like `update_lods`, the chunks are spawned before the transforms are
+                // propagated and the visibility is checked, so that they are drawn this frame
+                update_terrain_chunks.at_start(),
+            );
+    }
+}
+
+/// A terrain whose heights are read from a heightmap, see the [module-level documentation](self).
+///
+/// Changing it, or modifying its heightmap, regenerates all of its chunks.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component, Default, Debug)]
+pub struct Terrain {
+    /// The image the heights are read from, in its first channel. The texels at its edges are
+    /// at the edges of the terrain.
+    ///
+    /// Its format must be [`TextureFormat::R8Unorm`], [`TextureFormat::Rgba8Unorm`],
+    /// [`TextureFormat::Rgba8UnormSrgb`], [`TextureFormat::R16Unorm`],
+    /// [`TextureFormat::R16Uint`] or [`TextureFormat::R32Float`]. The formats other than
+    /// [`TextureFormat::R32Float`] span the whole `height` of the terrain.
+    pub heightmap: Handle<Image>,
+    /// The extent of the terrain along its local X and Z axes.
+    ///
+    /// Defaults to 100 by 100.
+    pub size: Vec2,
+    /// The height of the texels of the heightmap with the largest value.
+    ///
+    /// Defaults to `10.0`.
+    pub height: f32,
+    /// The number of quads along each edge of a chunk.
+    ///
+    /// Defaults to `32`.
+    pub chunk_resolution: u32,
+    /// How many times the terrain can be split: each edge of the terrain is covered by at most
+    /// `2^max_depth` chunks.
+    ///
+    /// Defaults to `4`.
+    pub max_depth: u32,
+    /// A node of the quadtree is split while a camera is closer to its bounds than its size
+    /// times this factor. Larger factors keep more detail farther from the cameras.
+    ///
+    /// Defaults to `1.5`.
+    pub split_distance: f32,
+}
+
+impl Default for Terrain {
+    fn default() -> Self {
+        Self {
+            heightmap: Handle::default(),
+            size: Vec2::splat(100.0),
+            height: 10.0,
+            chunk_resolution: 32,
+            max_depth: 4,
+            split_distance: 1.5,
+        }
+    }
+}
+
+impl Terrain {
+    /// Selects the nodes of the quadtree drawn for cameras at `viewers`, in the local space of
+    /// the terrain. Without viewers, the whole terrain is a single node.
+    pub fn select_nodes(&self, viewers: &[Vec3]) -> Vec<TerrainNode> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![TerrainNode::ROOT];
+        while let Some(node) = stack.pop() {
+            let (min, max) = node.bounds(self.size);
+            let split_distance = (max.x - min.x).max(max.y - min.y) * self.split_distance;
+            let is_near = viewers.iter().any(|viewer| {
+                // The distance to the box covering the node, from the bottom to the top of the
+                // terrain
+                let closest = viewer.clamp(
+                    Vec3::new(min.x, 0.0, min.y),
+                    Vec3::new(max.x, self.height, max.y),
+                );
+                viewer.distance(closest) < split_distance
+            });
+            if node.depth < self.max_depth && is_near {
+                stack.extend(node.children());
+            } else {
+                nodes.push(node);
+            }
+        }
+        nodes
+    }
+
+    /// Returns the height of the terrain at `position`, along its local X and Z axes, or `None`
+    /// if the format of the `heightmap` isn't supported.
+    pub fn height_at(&self, heightmap: &Image, position: Vec2) -> Option<f32> {
+        let sampler = HeightmapSampler::new(heightmap)?;
+        Some(sampler.sample(self.uv(position)) * self.height)
+    }
+
+    /// Generates the mesh of the chunk of a node of the quadtree, in the local space of the
+    /// terrain, or returns `None` if the format of the `heightmap` isn't supported.
+    pub fn chunk_mesh(&self, heightmap: &Image, node: TerrainNode) -> Option<Mesh> {
+        let sampler = HeightmapSampler::new(heightmap)?;
+        let resolution = self.chunk_resolution.max(1);
+        let side = resolution + 1;
+        let (min, max) = node.bounds(self.size);
+        let quad_size = (max - min) / resolution as f32;
+        // The normals are computed between neighboring texels, whatever the size of the chunk,
+        // so that they match at the edges of the chunks
+        let texel_size = self.size / (sampler.size - 1.0).max(Vec2::ONE);
+
+        let vertex_count = (side * side + 4 * side) as usize;
+        let mut positions = Vec::with_capacity(vertex_count);
+        let mut normals = Vec::with_capacity(vertex_count);
+        let mut uvs = Vec::with_capacity(vertex_count);
+        for z in 0..side {
+            for x in 0..side {
+                let position = min + Vec2::new(x as f32, z as f32) * quad_size;
+                let height =
+                    |offset: Vec2| sampler.sample(self.uv(position + offset)) * self.height;
+                let slope = Vec2::new(
+                    height(Vec2::new(texel_size.x, 0.0)) - height(Vec2::new(-texel_size.x, 0.0)),
+                    height(Vec2::new(0.0, texel_size.y)) - height(Vec2::new(0.0, -texel_size.y)),
+                ) / (2.0 * texel_size);
+                positions.push([position.x, height(Vec2::ZERO), position.y]);
+                normals.push(Vec3::new(-slope.x, 1.0, -slope.y).normalize().to_array());
+                uvs.push(self.uv(position).to_array());
+            }
+        }
+
+        let mut indices =
+            Vec::with_capacity((resolution * resolution * 6 + 4 * resolution * 6) as usize);
+        for z in 0..resolution {
+            for x in 0..resolution {
+                let corner = z * side + x;
+                indices.extend([
+                    corner,
+                    corner + side,
+                    corner + 1,
+                    corner + 1,
+                    corner + side,
+                    corner + side + 1,
+                ]);
+            }
+        }
+
+        // The skirts hang below the edges by the largest height difference between neighboring
+        // vertices of the chunk, which is about how far a finer neighbor can deviate from them
+        let mut skirt_depth = quad_size.min_element() * 0.1;
+        for z in 0..side {
+            for x in 0..side {
+                let height = positions[(z * side + x) as usize][1];
+                if x > 0 {
+                    let left = positions[(z * side + x - 1) as usize][1];
+                    skirt_depth = skirt_depth.max((height - left).abs());
+                }
+                if z > 0 {
+                    let below = positions[((z - 1) * side + x) as usize][1];
+                    skirt_depth = skirt_depth.max((height - below).abs());
+                }
+            }
+        }
+        // The edge vertices of each skirt, walked so that its triangles face outwards
+        let edges: [Vec<u32>; 4] = [
+            (0..side).rev().collect(),
+            (0..side).map(|x| resolution * side + x).collect(),
+            (0..side).map(|z| z * side).collect(),
+            (0..side).rev().map(|z| z * side + resolution).collect(),
+        ];
+        for edge in edges {
+            let first_skirt_vertex = positions.len() as u32;
+            for &vertex in &edge {
+                let [x, y, z] = positions[vertex as usize];
+                positions.push([x, y - skirt_depth, z]);
+                normals.push(normals[vertex as usize]);
+                uvs.push(uvs[vertex as usize]);
+            }
+            for (i, pair) in edge.windows(2).enumerate() {
+                let skirt = first_skirt_vertex + i as u32;
+                indices.extend([pair[0], skirt, pair[1], pair[1], skirt, skirt + 1]);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        Some(mesh)
+    }
+
+    /// The coordinates of `position` on the heightmap and the splat map, from `0.0` to `1.0`.
+    fn uv(&self, position: Vec2) -> Vec2 {
+        position / self.size + 0.5
+    }
+}
+
+/// A node of the quadtree of a [`Terrain`]: one of the `2^depth` by `2^depth` squares the
+/// terrain is split into at `depth`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TerrainNode {
+    pub depth: u32,
+    /// The index of the node along the local X axis of the terrain.
+    pub x: u32,
+    /// The index of the node along the local Z axis of the terrain.
+    pub z: u32,
+}
+
+impl TerrainNode {
+    /// The node covering the whole terrain.
+    pub const ROOT: TerrainNode = TerrainNode {
+        depth: 0,
+        x: 0,
+        z: 0,
+    };
+
+    /// The four nodes this node is split into.
+    pub fn children(&self) -> [TerrainNode; 4] {
+        let child = |x, z| TerrainNode {
+            depth: self.depth + 1,
+            x: self.x * 2 + x,
+            z: self.z * 2 + z,
+        };
+        [child(0, 0), child(1, 0), child(0, 1), child(1, 1)]
+    }
+
+    /// The corners of the node with the smallest and largest coordinates along the local X and
+    /// Z axes of a terrain of `size`.
+    pub fn bounds(&self, size: Vec2) -> (Vec2, Vec2) {
+        let node_size = size / (1u32 << self.depth) as f32;
+        let min = Vec2::new(self.x as f32, self.z as f32) * node_size - size / 2.0;
+        (min, min + node_size)
+    }
+}
+
+/// A chunk of a [`Terrain`], spawned as a child of the terrain by [`update_terrain_chunks`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TerrainChunk {
+    pub node: TerrainNode,
+}
+
+/// The chunks of a [`Terrain`], by node of its quadtree.
+#[derive(Component, Default)]
+pub struct TerrainChunks(HashMap<TerrainNode, Entity>);
+
+/// A bundle for a [`Terrain`], drawn with a [`TerrainMaterial`].
+#[derive(Bundle, Clone, Default)]
+pub struct TerrainBundle {
+    pub terrain: Terrain,
+    pub material: Handle<TerrainMaterial>,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether the terrain is visible, inherited by its chunks.
+    pub visibility: Visibility,
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// Selects the nodes of the quadtree of each [`Terrain`] for the active cameras, then spawns
+/// the chunks of the new nodes and despawns those of the nodes that aren't drawn anymore.
+#[allow(clippy::type_complexity)]
+pub fn update_terrain_chunks(
+    mut commands: Commands,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut terrains: Query<(
+        Entity,
+        &Terrain,
+        ChangeTrackers<Terrain>,
+        &Handle<TerrainMaterial>,
+        ChangeTrackers<Handle<TerrainMaterial>>,
+        &GlobalTransform,
+        Option<&mut TerrainChunks>,
+    )>,
+) {
+    let modified_images: HashSet<_> = image_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => Some(handle.id()),
+            AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+    let camera_positions: Vec<_> = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .map(|(_, transform)| transform.translation())
+        .collect();
+
+    for (entity, terrain, terrain_tracker, material, material_tracker, transform, chunks) in
+        &mut terrains
+    {
+        let mut new_chunks = TerrainChunks::default();
+        let chunks = match chunks {
+            Some(chunks) => chunks.into_inner(),
+            None => &mut new_chunks,
+        };
+        let Some(heightmap) = images.get(&terrain.heightmap) else {
+            continue;
+        };
+        let regenerate = terrain_tracker.is_changed()
+            || material_tracker.is_changed()
+            || modified_images.contains(&terrain.heightmap.id());
+        if regenerate {
+            for (_, chunk) in chunks.0.drain() {
+                commands.entity(chunk).despawn_recursive();
+            }
+            if HeightmapSampler::new(heightmap).is_none() {
+                warn!(
+                    "The format {:?} of the heightmap of a terrain isn't supported",
+                    heightmap.texture_descriptor.format
+                );
+            }
+        }
+        if camera_positions.is_empty() && !chunks.0.is_empty() {
+            continue;
+        }
+
+        let local_from_world = transform.affine().inverse();
+        let viewers: Vec<_> = camera_positions
+            .iter()
+            .map(|position| local_from_world.transform_point3(*position))
+            .collect();
+        let nodes: HashSet<_> = terrain.select_nodes(&viewers).into_iter().collect();
+
+        chunks.0.retain(|node, chunk| {
+            let is_selected = nodes.contains(node);
+            if !is_selected {
+                commands.entity(*chunk).despawn_recursive();
+            }
+            is_selected
+        });
+        for node in nodes {
+            if chunks.0.contains_key(&node) {
+                continue;
+            }
+            let Some(mesh) = terrain.chunk_mesh(heightmap, node) else {
+                break;
+            };
+            let chunk = commands
+                .spawn((
+                    MaterialMeshBundle {
+                        mesh: meshes.add(mesh),
+                        material: material.clone(),
+                        ..Default::default()
+                    },
+                    TerrainChunk { node },
+                ))
+                .id();
+            commands.entity(entity).add_child(chunk);
+            chunks.0.insert(node, chunk);
+        }
+
+        if !new_chunks.0.is_empty() {
+            commands.entity(entity).insert(new_chunks);
+        }
+    }
+}
+
+/// Reads the heights of a heightmap, from `0.0` to `1.0` for the normalized formats.
+struct HeightmapSampler<'a> {
+    data: &'a [u8],
+    format: TextureFormat,
+    size: Vec2,
+    width: usize,
+}
+
+impl<'a> HeightmapSampler<'a> {
+    fn new(image: &'a Image) -> Option<Self> {
+        let format = image.texture_descriptor.format;
+        let texel_size = match format {
+            TextureFormat::R8Unorm => 1,
+            TextureFormat::R16Unorm | TextureFormat::R16Uint => 2,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::R32Float => {
+                4
+            }
+            _ => return None,
+        };
+        let size = image.texture_descriptor.size;
+        let texel_count = size.width as usize * size.height as usize;
+        if size.width == 0 || size.height == 0 || image.data.len() < texel_count * texel_size {
+            return None;
+        }
+        Some(Self {
+            data: &image.data,
+            format,
+            size: Vec2::new(size.width as f32, size.height as f32),
+            width: size.width as usize,
+        })
+    }
+
+    fn texel(&self, x: usize, y: usize) -> f32 {
+        let i = y * self.width + x;
+        match self.format {
+            TextureFormat::R8Unorm => self.data[i] as f32 / 255.0,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+                self.data[i * 4] as f32 / 255.0
+            }
+            TextureFormat::R16Unorm | TextureFormat::R16Uint => {
+                u16::from_le_bytes([self.data[i * 2], self.data[i * 2 + 1]]) as f32 / 65535.0
+            }
+            _ => f32::from_le_bytes(self.data[i * 4..i * 4 + 4].try_into().unwrap()),
+        }
+    }
+
+    /// Bilinearly samples the heightmap at `uv`, clamped to its edges.
+    fn sample(&self, uv: Vec2) -> f32 {
+        let max = self.size - 1.0;
+        let position = (uv * max).clamp(Vec2::ZERO, max);
+        let (x0, y0) = (position.x as usize, position.y as usize);
+        let (x1, y1) = ((x0 + 1).min(max.x as usize), (y0 + 1).min(max.y as usize));
+        let t = position.fract();
+        let top = self.texel(x0, y0) * (1.0 - t.x) + self.texel(x1, y0) * t.x;
+        let bottom = self.texel(x0, y1) * (1.0 - t.x) + self.texel(x1, y1) * t.x;
+        top * (1.0 - t.y) + bottom * t.y
+    }
+}
+
+/// The material of a [`Terrain`], blending up to four textures across it by the weights of a
+/// splat map.
+///
+/// The layer textures are repeated across the terrain, while the splat map covers all of it
+/// once, with the same orientation as the heightmap.
+#[derive(AsBindGroup, Reflect, FromReflect, Debug, Clone, TypeUuid)]
+#[uuid = "a1bd5d0e-6a43-4b6c-a8f2-1e8f5cbb1c47"]
+#[uniform(0, TerrainMaterialUniform)]
+#[reflect(Default, Debug)]
+pub struct TerrainMaterial {
+    /// The weights of the layers across the terrain: its red, green, blue and alpha channels
+    /// weight the first four layers of `layers`. The weights are normalized, and the terrain is
+    /// drawn with the first layer where they are all zero.
+    #[texture(1)]
+    #[sampler(2)]
+    pub splat_map: Handle<Image>,
+    /// An array texture with a layer per texture of the terrain. With fewer than four layers,
+    /// the weights of the missing ones are added to the last one. Its sampler should repeat
+    /// it, with [`AddressMode::Repeat`](bevy_render::render_resource::AddressMode::Repeat).
+    #[texture(3, dimension = "2d_array")]
+    #[sampler(4)]
+    pub layers: Handle<Image>,
+    /// The size of the terrain each layer texture covers before repeating, in world units.
+    ///
+    /// Defaults to `4.0`.
+    pub layer_size: f32,
+    /// Defaults to `0.9`, like the roughness of rock and soil.
+    pub perceptual_roughness: f32,
+}
+
+impl Default for TerrainMaterial {
+    fn default() -> Self {
+        Self {
+            splat_map: Handle::default(),
+            layers: Handle::default(),
+            layer_size: 4.0,
+            perceptual_roughness: 0.9,
+        }
+    }
+}
+
+/// The GPU representation of the uniform data of a [`TerrainMaterial`].
+#[derive(Clone, Default, ShaderType)]
+pub struct TerrainMaterialUniform {
+    pub layer_size: f32,
+    pub perceptual_roughness: f32,
+}
+
+impl AsBindGroupShaderType<TerrainMaterialUniform> for TerrainMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<Image>) -> TerrainMaterialUniform {
+        TerrainMaterialUniform {
+            layer_size: self.layer_size,
+            perceptual_roughness: self.perceptual_roughness,
+        }
+    }
+}
+
+impl Material for TerrainMaterial {
+    fn fragment_shader() -> ShaderRef {
+        TERRAIN_SHADER_HANDLE.typed().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_render::render_resource::{Extent3d, TextureDimension};
+
+    fn terrain() -> Terrain {
+        Terrain {
+            size: Vec2::splat(16.0),
+            height: 2.0,
+            chunk_resolution: 4,
+            max_depth: 2,
+            split_distance: 0.5,
+            ..Default::default()
+        }
+    }
+
+    fn slope_heightmap() -> Image {
+        // Rises from 0 on the left to 255 on the right
+        let data = (0..4).flat_map(|_| [0, 85, 170, 255]).collect();
+        Image::new(
+            Extent3d {
+                width: 4,
+                height: 4,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::R8Unorm,
+        )
+    }
+
+    #[test]
+    fn nodes_are_split_near_viewers() {
+        let terrain = terrain();
+        assert_eq!(terrain.select_nodes(&[]), vec![TerrainNode::ROOT]);
+
+        // Far above the terrain
+        assert_eq!(
+            terrain.select_nodes(&[Vec3::new(0.0, 100.0, 0.0)]),
+            vec![TerrainNode::ROOT]
+        );
+
+        // In a corner, where the nodes are split down to the maximum depth
+        let nodes = terrain.select_nodes(&[Vec3::new(-7.0, 1.0, -7.0)]);
+        assert!(nodes.contains(&TerrainNode {
+            depth: 2,
+            x: 0,
+            z: 0
+        }));
+        assert!(nodes.contains(&TerrainNode {
+            depth: 1,
+            x: 1,
+            z: 1
+        }));
+        assert!(nodes.iter().all(|node| node.depth <= terrain.max_depth));
+
+        // The nodes cover the terrain without overlapping
+        let area: f32 = nodes
+            .iter()
+            .map(|node| {
+                let (min, max) = node.bounds(terrain.size);
+                (max - min).x * (max - min).y
+            })
+            .sum();
+        assert_eq!(area, terrain.size.x * terrain.size.y);
+    }
+
+    #[test]
+    fn chunk_meshes_follow_the_heightmap() {
+        let terrain = terrain();
+        let heightmap = slope_heightmap();
+        assert_eq!(
+            terrain.height_at(&heightmap, Vec2::new(-8.0, 0.0)),
+            Some(0.0)
+        );
+        assert_eq!(
+            terrain.height_at(&heightmap, Vec2::new(8.0, 3.0)),
+            Some(2.0)
+        );
+
+        let node = TerrainNode {
+            depth: 1,
+            x: 1,
+            z: 0,
+        };
+        let mesh = terrain.chunk_mesh(&heightmap, node).unwrap();
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap();
+        // A 5 by 5 grid, and 4 skirts of 5 vertices
+        assert_eq!(positions.len(), 25 + 20);
+        assert_eq!(positions[0][0], 0.0);
+        assert_eq!(positions[0][2], -8.0);
+        assert_eq!(positions[24][0], 8.0);
+        assert_eq!(positions[24][1], 2.0);
+        assert_eq!(positions[24][2], 0.0);
+
+        // The skirts hang below the edges of the grid
+        for skirt in &positions[25..] {
+            assert!(positions[..25].iter().any(|vertex| vertex[0] == skirt[0]
+                && vertex[2] == skirt[2]
+                && vertex[1] > skirt[1]));
+        }
+
+        // The triangles of the grid face upwards
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("the chunk mesh should have u32 indices");
+        };
+        assert_eq!(indices.len(), (16 + 16) * 6);
+        for triangle in indices[..16 * 6].chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i] as usize]));
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+    }
+
+    #[test]
+    fn unsupported_heightmaps_generate_nothing() {
+        let terrain = terrain();
+        let heightmap = Image::new_fill(
+            Extent3d::default(),
+            TextureDimension::D2,
+            &[0; 8],
+            TextureFormat::Rg32Float,
+        );
+        assert!(terrain.chunk_mesh(&heightmap, TerrainNode::ROOT).is_none());
+        assert!(terrain.height_at(&heightmap, Vec2::ZERO).is_none());
+    }
+}
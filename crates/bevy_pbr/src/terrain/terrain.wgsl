@@ -0,0 +1,75 @@
+#import bevy_pbr::mesh_view_bindings
+#import bevy_pbr::mesh_bindings
+
+#import bevy_pbr::pbr_types
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::shadows
+#import bevy_pbr::pbr_functions
+#import bevy_pbr::fog
+
+struct TerrainMaterial {
+    layer_size: f32,
+    perceptual_roughness: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: TerrainMaterial;
+@group(1) @binding(1)
+var splat_map: texture_2d<f32>;
+@group(1) @binding(2)
+var splat_map_sampler: sampler;
+@group(1) @binding(3)
+var layers: texture_2d_array<f32>;
+@group(1) @binding(4)
+var layers_sampler: sampler;
+
+struct FragmentInput {
+    @builtin(front_facing) is_front: bool,
+    @builtin(position) frag_coord: vec4<f32>,
+    #import bevy_pbr::mesh_vertex_output
+};
+
+@fragment
+fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
+    // The splat map covers the whole terrain, while the layers repeat across the world
+    var weights = textureSample(splat_map, splat_map_sampler, in.uv);
+    let weight_sum = dot(weights, vec4<f32>(1.0));
+    if (weight_sum > 0.0) {
+        weights = weights / weight_sum;
+    } else {
+        weights = vec4<f32>(1.0, 0.0, 0.0, 0.0);
+    }
+    let layer_uv = in.world_position.xz / material.layer_size;
+    // NOTE: The layer indices past the last layer are clamped to it
+    var base_color = vec4<f32>(0.0);
+    for (var i = 0; i < 4; i = i + 1) {
+        base_color = base_color + weights[i] * textureSample(layers, layers_sampler, layer_uv, i);
+    }
+
+    var pbr_input: PbrInput = pbr_input_new();
+    pbr_input.material.base_color = vec4<f32>(base_color.rgb, 1.0);
+    pbr_input.material.perceptual_roughness = material.perceptual_roughness;
+
+    pbr_input.frag_coord = in.frag_coord;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = prepare_world_normal(in.world_normal, false, in.is_front);
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.V = calculate_view(in.world_position, pbr_input.is_orthographic);
+
+    var output_color = pbr(pbr_input);
+    output_color = apply_fog(output_color, in.world_position.xyz, view.world_position.xyz);
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color);
+#endif
+#ifdef DEBAND_DITHER
+    var output_rgb = output_color.rgb;
+    output_rgb = pow(output_rgb, vec3<f32>(1.0 / 2.2));
+    output_rgb = output_rgb + screen_space_dither(in.frag_coord.xy);
+    output_rgb = pow(output_rgb, vec3<f32>(2.2));
+    output_color = vec4(output_rgb, output_color.a);
+#endif
+    return output_color;
+}
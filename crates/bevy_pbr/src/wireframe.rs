@@ -14,16 +14,23 @@ use bevy_render::{
     render_phase::{AddRenderCommand, DrawFunctions, RenderPhase, SetItemPipeline},
     render_resource::{
         PipelineCache, PolygonMode, RenderPipelineDescriptor, Shader, SpecializedMeshPipeline,
-        SpecializedMeshPipelineError, SpecializedMeshPipelines,
+        SpecializedMeshPipelineError, SpecializedMeshPipelines, WgpuFeatures,
     },
+    renderer::RenderDevice,
     view::{ExtractedView, Msaa, VisibleEntities},
     RenderApp, RenderStage,
 };
-use bevy_utils::tracing::error;
+use bevy_utils::tracing::{error, warn};
 
 pub const WIREFRAME_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 192598014480025766);
 
+/// Draws the edges of the triangles of the meshes with a [`Wireframe`], or of every mesh with
+/// [`WireframeConfig::global`], on top of their material.
+///
+/// The [`WgpuFeatures::POLYGON_MODE_LINE`] feature has to be enabled in the
+/// [`WgpuSettings`](bevy_render::settings::WgpuSettings) of the `RenderPlugin`, without which no
+/// wireframe is drawn.
 #[derive(Debug, Default)]
 pub struct WireframePlugin;
 
@@ -42,6 +49,17 @@ impl Plugin for WireframePlugin {
             .add_plugin(ExtractResourcePlugin::<WireframeConfig>::default());
 
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            if !render_app
+                .world
+                .resource::<RenderDevice>()
+                .features()
+                .contains(WgpuFeatures::POLYGON_MODE_LINE)
+            {
+                warn!(
+                    "The wireframes aren't drawn, as the POLYGON_MODE_LINE feature isn't enabled"
+                );
+                return;
+            }
             render_app
                 .add_render_command::<Opaque3d, DrawWireframes>()
                 .init_resource::<WireframePipeline>()
@@ -125,8 +143,11 @@ fn queue_wireframes(
         let add_render_phase =
             |(entity, mesh_handle, mesh_uniform): (Entity, &Handle<Mesh>, &MeshUniform)| {
                 if let Some(mesh) = render_meshes.get(mesh_handle) {
-                    let key = view_key
+                    let mut key = view_key
                         | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology);
+                    if mesh.morph_targets.is_some() {
+                        key |= MeshPipelineKey::MORPH_TARGETS;
+                    }
                     let pipeline_id = pipelines.specialize(
                         &mut pipeline_cache,
                         &wireframe_pipeline,
@@ -145,6 +166,7 @@ fn queue_wireframes(
                         pipeline: pipeline_id,
                         draw_function: draw_custom,
                         distance: rangefinder.distance(&mesh_uniform.transform),
+                        batch_range: None,
                     });
                 }
             };
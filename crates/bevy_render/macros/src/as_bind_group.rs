@@ -11,6 +11,8 @@ use syn::{
 const UNIFORM_ATTRIBUTE_NAME: Symbol = Symbol("uniform");
 const TEXTURE_ATTRIBUTE_NAME: Symbol = Symbol("texture");
 const SAMPLER_ATTRIBUTE_NAME: Symbol = Symbol("sampler");
+const STORAGE_ATTRIBUTE_NAME: Symbol = Symbol("storage");
+const STORAGE_TEXTURE_ATTRIBUTE_NAME: Symbol = Symbol("storage_texture");
 const BIND_GROUP_DATA_ATTRIBUTE_NAME: Symbol = Symbol("bind_group_data");
 
 #[derive(Copy, Clone, Debug)]
@@ -18,6 +20,8 @@ enum BindingType {
     Uniform,
     Texture,
     Sampler,
+    Storage,
+    StorageTexture,
 }
 
 #[derive(Clone)]
@@ -126,6 +130,10 @@ pub fn derive_as_bind_group(ast: syn::DeriveInput) -> Result<TokenStream> {
                 BindingType::Texture
             } else if attr_ident == SAMPLER_ATTRIBUTE_NAME {
                 BindingType::Sampler
+            } else if attr_ident == STORAGE_ATTRIBUTE_NAME {
+                BindingType::Storage
+            } else if attr_ident == STORAGE_TEXTURE_ATTRIBUTE_NAME {
+                BindingType::StorageTexture
             } else {
                 continue;
             };
@@ -227,6 +235,43 @@ pub fn derive_as_bind_group(ast: syn::DeriveInput) -> Result<TokenStream> {
                         }
                     });
                 }
+                BindingType::StorageTexture => {
+                    let StorageTextureAttrs {
+                        dimension,
+                        image_format,
+                        access,
+                        visibility,
+                    } = get_storage_texture_binding_attr(nested_meta_items)?;
+
+                    let visibility =
+                        visibility.hygenic_quote(&quote! { #render_path::render_resource });
+
+                    // The fallback image can't be bound as a storage texture, so storage
+                    // textures wait for their image instead
+                    binding_impls.push(quote! {
+                        #render_path::render_resource::OwnedBindingResource::TextureView({
+                            let handle: Option<&#asset_path::Handle<#render_path::texture::Image>> = (&self.#field_name).into();
+                            handle
+                                .and_then(|handle| images.get(handle))
+                                .ok_or_else(|| #render_path::render_resource::AsBindGroupError::RetryNextUpdate)?
+                                .texture_view
+                                .clone()
+                        })
+                    });
+
+                    binding_layouts.push(quote! {
+                        #render_path::render_resource::BindGroupLayoutEntry {
+                            binding: #binding_index,
+                            visibility: #visibility,
+                            ty: #render_path::render_resource::BindingType::StorageTexture {
+                                access: #render_path::render_resource::#access,
+                                format: #render_path::render_resource::TextureFormat::#image_format,
+                                view_dimension: #render_path::render_resource::#dimension,
+                            },
+                            count: None,
+                        }
+                    });
+                }
                 BindingType::Sampler => {
                     let SamplerAttrs {
                         sampler_binding_type,
@@ -256,6 +301,54 @@ pub fn derive_as_bind_group(ast: syn::DeriveInput) -> Result<TokenStream> {
                         }
                     });
                 }
+                BindingType::Storage => {
+                    let StorageAttrs {
+                        visibility,
+                        read_only,
+                        buffer,
+                    } = get_storage_binding_attr(nested_meta_items)?;
+
+                    let visibility =
+                        visibility.hygenic_quote(&quote! { #render_path::render_resource });
+
+                    let field_ty = &field.ty;
+                    let min_binding_size = if buffer {
+                        binding_impls.push(quote! {
+                            #render_path::render_resource::OwnedBindingResource::Buffer(
+                                self.#field_name.clone()
+                            )
+                        });
+
+                        quote! { None }
+                    } else {
+                        binding_impls.push(quote! {{
+                            let mut buffer = #render_path::render_resource::encase::StorageBuffer::new(Vec::new());
+                            buffer.write(&self.#field_name).unwrap();
+                            #render_path::render_resource::OwnedBindingResource::Buffer(render_device.create_buffer_with_data(
+                                &#render_path::render_resource::BufferInitDescriptor {
+                                    label: None,
+                                    usage: #render_path::render_resource::BufferUsages::COPY_DST | #render_path::render_resource::BufferUsages::STORAGE,
+                                    contents: buffer.as_ref(),
+                                },
+                            ))
+                        }});
+
+                        quote! { Some(<#field_ty as #render_path::render_resource::ShaderType>::min_size()) }
+                    };
+
+                    binding_layouts.push(quote! {
+                        #render_path::render_resource::BindGroupLayoutEntry {
+                            binding: #binding_index,
+                            visibility: #visibility,
+                            ty: #render_path::render_resource::BindingType::Buffer {
+                                ty: #render_path::render_resource::BufferBindingType::Storage { read_only: #read_only },
+                                has_dynamic_offset: false,
+                                min_binding_size: #min_binding_size,
+                            },
+                            count: None,
+                        }
+                    });
+                }
             }
         }
     }
@@ -861,3 +954,195 @@ fn get_sampler_binding_type_value(lit_str: &LitStr) -> Result<SamplerBindingType
         )),
     }
 }
+
+#[derive(Default)]
+struct StorageAttrs {
+    visibility: ShaderStageVisibility,
+    read_only: bool,
+    buffer: bool,
+}
+
+const READ_ONLY: Symbol = Symbol("read_only");
+const BUFFER: Symbol = Symbol("buffer");
+
+fn get_storage_binding_attr(metas: Vec<NestedMeta>) -> Result<StorageAttrs> {
+    let mut visibility = ShaderStageVisibility::vertex_fragment();
+    let mut read_only = false;
+    let mut buffer = false;
+
+    for meta in metas {
+        use syn::{
+            Meta::{List, Path},
+            NestedMeta::Meta,
+        };
+        match meta {
+            // Parse #[storage(0, visibility(...))].
+            Meta(List(m)) if m.path == VISIBILITY => {
+                visibility = get_visibility_flag_value(&m.nested)?;
+            }
+            // Parse #[storage(0, read_only)].
+            Meta(Path(path)) if path == READ_ONLY => {
+                read_only = true;
+            }
+            // Parse #[storage(0, buffer)].
+            Meta(Path(path)) if path == BUFFER => {
+                buffer = true;
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "Not a valid attribute. Available attributes: `read_only`, `buffer`, `visibility`",
+                ));
+            }
+        }
+    }
+
+    Ok(StorageAttrs {
+        visibility,
+        read_only,
+        buffer,
+    })
+}
+
+enum StorageTextureAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl ToTokens for StorageTextureAccess {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            StorageTextureAccess::ReadOnly => quote! { StorageTextureAccess::ReadOnly },
+            StorageTextureAccess::WriteOnly => quote! { StorageTextureAccess::WriteOnly },
+            StorageTextureAccess::ReadWrite => quote! { StorageTextureAccess::ReadWrite },
+        });
+    }
+}
+
+struct StorageTextureAttrs {
+    dimension: BindingTextureDimension,
+    image_format: Ident,
+    access: StorageTextureAccess,
+    visibility: ShaderStageVisibility,
+}
+
+const IMAGE_FORMAT: Symbol = Symbol("image_format");
+const ACCESS: Symbol = Symbol("access");
+
+// Values for `access` attribute.
+const READ_ONLY_ACCESS: &str = "read_only";
+const WRITE_ONLY_ACCESS: &str = "write_only";
+const READ_WRITE_ACCESS: &str = "read_write";
+
+fn get_storage_texture_binding_attr(metas: Vec<NestedMeta>) -> Result<StorageTextureAttrs> {
+    let mut dimension = Default::default();
+    let mut image_format = Ident::new("Rgba8Unorm", Span::call_site());
+    let mut access = StorageTextureAccess::ReadWrite;
+    let mut visibility = ShaderStageVisibility::Flags(VisibilityFlags {
+        compute: true,
+        ..Default::default()
+    });
+
+    for meta in metas {
+        use syn::{
+            Meta::{List, NameValue},
+            NestedMeta::Meta,
+        };
+        match meta {
+            // Parse #[storage_texture(0, dimension = "...")].
+            Meta(NameValue(m)) if m.path == DIMENSION => {
+                let value = get_lit_str(DIMENSION, &m.lit)?;
+                dimension = get_texture_dimension_value(value)?;
+            }
+            // Parse #[storage_texture(0, image_format = "...")].
+            Meta(NameValue(m)) if m.path == IMAGE_FORMAT => {
+                let value = get_lit_str(IMAGE_FORMAT, &m.lit)?;
+                image_format = value.parse()?;
+            }
+            // Parse #[storage_texture(0, access = "...")].
+            Meta(NameValue(m)) if m.path == ACCESS => {
+                let value = get_lit_str(ACCESS, &m.lit)?;
+                access = get_storage_texture_access_value(value)?;
+            }
+            // Parse #[storage_texture(0, visibility(...))].
+            Meta(List(m)) if m.path == VISIBILITY => {
+                visibility = get_visibility_flag_value(&m.nested)?;
+            }
+            Meta(NameValue(m)) => {
+                return Err(Error::new_spanned(
+                    m.path,
+                    "Not a valid name. Available attributes: `dimension`, `image_format`, or `access`.",
+                ));
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "Not a name value pair: `foo = \"...\"`",
+                ));
+            }
+        }
+    }
+
+    Ok(StorageTextureAttrs {
+        dimension,
+        image_format,
+        access,
+        visibility,
+    })
+}
+
+fn get_storage_texture_access_value(lit_str: &LitStr) -> Result<StorageTextureAccess> {
+    match lit_str.value().as_str() {
+        READ_ONLY_ACCESS => Ok(StorageTextureAccess::ReadOnly),
+        WRITE_ONLY_ACCESS => Ok(StorageTextureAccess::WriteOnly),
+        READ_WRITE_ACCESS => Ok(StorageTextureAccess::ReadWrite),
+
+        _ => Err(Error::new_spanned(
+            lit_str,
+            "Not a valid access. Must be `read_only`, `write_only` or `read_write`.",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_binding_nested_attr, get_storage_binding_attr, StorageAttrs};
+    use quote::quote;
+    use syn::{parse_quote, Attribute, Result};
+
+    fn storage_attrs(attr: &Attribute) -> Result<(u32, StorageAttrs)> {
+        let (binding_index, nested_meta_items) = get_binding_nested_attr(attr)?;
+        Ok((binding_index, get_storage_binding_attr(nested_meta_items)?))
+    }
+
+    #[test]
+    fn storage_binding_defaults() {
+        let (binding_index, attrs) = storage_attrs(&parse_quote!(#[storage(3)])).unwrap();
+        assert_eq!(binding_index, 3);
+        assert!(!attrs.read_only);
+        assert!(!attrs.buffer);
+        assert_eq!(
+            attrs.visibility.hygenic_quote(&quote!(path)).to_string(),
+            quote!(path::ShaderStages::VERTEX | path::ShaderStages::FRAGMENT).to_string()
+        );
+    }
+
+    #[test]
+    fn storage_binding_options() {
+        let (binding_index, attrs) = storage_attrs(&parse_quote!(
+            #[storage(0, read_only, buffer, visibility(all))]
+        ))
+        .unwrap();
+        assert_eq!(binding_index, 0);
+        assert!(attrs.read_only);
+        assert!(attrs.buffer);
+        assert_eq!(
+            attrs.visibility.hygenic_quote(&quote!(path)).to_string(),
+            quote!(path::ShaderStages::all()).to_string()
+        );
+
+        assert!(storage_attrs(&parse_quote!(#[storage(0, writable)])).is_err());
+        assert!(storage_attrs(&parse_quote!(#[storage(0, read_only = "true")])).is_err());
+    }
+}
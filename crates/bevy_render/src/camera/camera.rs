@@ -1,9 +1,9 @@
 use crate::{
-    camera::CameraProjection,
+    camera::{CameraProjection, PerspectiveProjection, Projection},
     prelude::Image,
     render_asset::RenderAssets,
     render_resource::TextureView,
-    view::{ExtractedView, ExtractedWindows, VisibleEntities},
+    view::{ColorGrading, ExtractedView, ExtractedWindows, VisibleEntities},
     Extract,
 };
 use bevy_asset::{AssetEvent, Assets, Handle};
@@ -13,23 +13,26 @@ use bevy_ecs::{
     component::Component,
     entity::Entity,
     event::EventReader,
+    query::Changed,
     reflect::ReflectComponent,
-    system::{Commands, Query, Res},
+    system::{Commands, Query, Res, ResMut},
 };
-use bevy_math::{Mat4, Ray, UVec2, UVec4, Vec2, Vec3};
+use bevy_math::{Mat4, Ray, UVec2, UVec4, Vec2, Vec3, Vec4};
 use bevy_reflect::prelude::*;
 use bevy_reflect::FromReflect;
 use bevy_transform::components::GlobalTransform;
 use bevy_utils::HashSet;
 use bevy_window::{WindowCreated, WindowId, WindowResized, Windows};
 use std::{borrow::Cow, ops::Range};
-use wgpu::{Extent3d, TextureFormat};
+use wgpu::{Extent3d, TextureFormat, TextureUsages};
 
 /// Render viewport configuration for the [`Camera`] component.
 ///
 /// The viewport defines the area on the render target to which the camera renders its image.
 /// You can overlay multiple cameras in a single window using viewports to create effects like
 /// split screen, minimaps, and character viewers.
+/// Rendering in the core main passes is clipped to this area, so cameras sharing a target don't
+/// draw over each other.
 #[derive(Reflect, FromReflect, Debug, Clone)]
 #[reflect(Default)]
 pub struct Viewport {
@@ -304,6 +307,156 @@ impl CameraRenderGraph {
     }
 }
 
+/// A subpixel offset to jitter a [`Camera`]'s projection by, so that successive frames sample
+/// different points within each pixel.
+///
+/// This is used by temporal rendering techniques such as temporal anti-aliasing, which are
+/// responsible for updating the offset every frame.
+#[derive(Component, Clone, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct TemporalJitter {
+    /// The offset, in pixels, in the range `[-0.5, 0.5]`.
+    pub offset: Vec2,
+}
+
+impl TemporalJitter {
+    /// Offsets `projection` by [`Self::offset`] pixels of a view of size `view_size`.
+    pub fn jitter_projection(&self, projection: &mut Mat4, view_size: Vec2) {
+        let jitter = self.offset * Vec2::new(2.0, -2.0) / view_size;
+        if projection.w_axis.w == 1.0 {
+            // Orthographic projections don't divide by w, so the offset is a translation
+            projection.w_axis.x += jitter.x;
+            projection.w_axis.y += jitter.y;
+        } else {
+            // Perspective projections multiply z by -1.0 into w
+            projection.z_axis.x -= jitter.x;
+            projection.z_axis.y -= jitter.y;
+        }
+    }
+}
+
+/// A world-space plane that a camera clips everything behind, by tilting the near plane of its
+/// projection to lie on it.
+///
+/// This is how a camera rendering a reflection skips the geometry under the mirror or the water.
+/// Tilting the near plane costs some depth precision, far away from the camera.
+#[derive(Component, Clone, Copy, Default, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct CameraClipPlane {
+    /// The plane, as a normal in `xyz` and an offset in `w`, so that visible points `p` verify
+    /// `plane.xyz.dot(p) + plane.w >= 0.0`. The normal must be normalized.
+    pub plane: Vec4,
+}
+
+impl CameraClipPlane {
+    /// Creates a clip plane through `point`, keeping the side `normal` points to.
+    pub fn new(point: Vec3, normal: Vec3) -> Self {
+        let normal = normal.normalize();
+        Self {
+            plane: normal.extend(-normal.dot(point)),
+        }
+    }
+
+    /// Replaces the near plane of the perspective `projection` of a view at `view_transform` with
+    /// [`Self::plane`].
+    ///
+    /// Orthographic projections are left untouched.
+    pub fn clip_projection(&self, projection: &mut Mat4, view_transform: &GlobalTransform) {
+        if projection.w_axis.w == 1.0 {
+            return;
+        }
+        // The plane in view space, which the near plane lies on once the z row of the projection is
+        // the w row minus the plane. The projections are reverse z and infinite, so the depth of a
+        // point at a distance `d` from the plane at `-z` in front of the camera is
+        // `1.0 - d * SCALE / -z`, and scaling the plane by half keeps the points in the frustum
+        // from going behind the far plane for fields of view up to 120°.
+        const SCALE: f32 = 0.5;
+        let plane = view_transform.compute_matrix().transpose() * self.plane * SCALE;
+        let w_row = projection.row(3);
+        projection.x_axis.z = w_row.x - plane.x;
+        projection.y_axis.z = w_row.y - plane.y;
+        projection.z_axis.z = w_row.z - plane.z;
+        projection.w_axis.z = w_row.w - plane.w;
+    }
+}
+
+/// The physical parameters of a camera, which derive its field of view and its exposure like the
+/// settings of a real camera.
+///
+/// The vertical field of view of the [`PerspectiveProjection`] of the camera, or of its
+/// [`Projection`] when it is perspective, is set from the [`focal_length`](Self::focal_length) and
+/// the [`sensor_height`](Self::sensor_height) by [`physical_camera_system`].
+///
+/// The exposure of the camera is added to the one of its [`ColorGrading`], which is applied
+/// before tonemapping. The physical light units, like the lux of a `DirectionalLight`, are
+/// calibrated for the default parameters, so a scene looks the same with a wider aperture and a
+/// faster shutter speed keeping the same [EV100](Self::ev100).
+///
+/// [`PerspectiveProjection`]: crate::camera::PerspectiveProjection
+/// [`Projection`]: crate::camera::Projection
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct PhysicalCameraParameters {
+    /// The f-number of the aperture, the focal length divided by the diameter of the aperture.
+    /// Each doubling of the f-number divides the light reaching the sensor by four.
+    ///
+    /// Defaults to `4.0`.
+    pub aperture_f_stops: f32,
+    /// The time the sensor is exposed for, in seconds.
+    ///
+    /// Defaults to `1.0 / 250.0`.
+    pub shutter_speed_s: f32,
+    /// The ISO sensitivity of the sensor.
+    ///
+    /// Defaults to `100.0`.
+    pub sensitivity_iso: f32,
+    /// The focal length of the lens, in meters.
+    ///
+    /// Defaults to `0.05`, a 50mm lens.
+    pub focal_length: f32,
+    /// The height of the sensor, in meters.
+    ///
+    /// Defaults to `0.024`, the height of a full frame sensor.
+    pub sensor_height: f32,
+}
+
+impl Default for PhysicalCameraParameters {
+    fn default() -> Self {
+        Self {
+            aperture_f_stops: 4.0,
+            shutter_speed_s: 1.0 / 250.0,
+            sensitivity_iso: 100.0,
+            focal_length: 0.05,
+            sensor_height: 0.024,
+        }
+    }
+}
+
+impl PhysicalCameraParameters {
+    /// Returns the exposure value of the parameters at ISO 100, which is higher for the settings
+    /// letting less light in.
+    pub fn ev100(&self) -> f32 {
+        f32::log2(self.aperture_f_stops * self.aperture_f_stops / self.shutter_speed_s)
+            - f32::log2(self.sensitivity_iso / 100.0)
+    }
+
+    /// Returns the factor converting the luminance of the scene to the brightness it is exposed
+    /// to, see the [exposure settings of Filament](https://google.github.io/filament/Filament.html#imagingpipeline/physicallybasedcamera/exposuresettings).
+    pub fn exposure(&self) -> f32 {
+        1.0 / (f32::powf(2.0, self.ev100()) * 1.2)
+    }
+
+    /// Returns the exposure of these parameters relative to the default ones, in stops.
+    pub fn exposure_stops(&self) -> f32 {
+        Self::default().ev100() - self.ev100()
+    }
+
+    /// Returns the vertical field of view of the lens, in radians.
+    pub fn fov(&self) -> f32 {
+        2.0 * f32::atan(self.sensor_height / (2.0 * self.focal_length))
+    }
+}
+
 /// The "target" that a [`Camera`] will render to. For example, this could be a [`Window`](bevy_window::Window)
 /// swapchain or an [`Image`].
 #[derive(Debug, Clone, Reflect, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -311,6 +464,9 @@ pub enum RenderTarget {
     /// Window to which the camera's view is rendered.
     Window(WindowId),
     /// Image to which the camera's view is rendered.
+    ///
+    /// The image is given the texture usages needed to render to it, to sample it from materials and
+    /// to read it back with [`ImageReadbacks`](crate::texture::ImageReadbacks).
     Image(Handle<Image>),
 }
 
@@ -470,6 +626,54 @@ pub fn camera_system<T: CameraProjection + Component>(
     }
 }
 
+/// System that sets the field of view of the perspective projections of the cameras from their
+/// [`PhysicalCameraParameters`], when they change.
+pub fn physical_camera_system(
+    mut cameras: Query<
+        (
+            &PhysicalCameraParameters,
+            Option<&mut PerspectiveProjection>,
+            Option<&mut Projection>,
+        ),
+        Changed<PhysicalCameraParameters>,
+    >,
+) {
+    for (parameters, perspective, projection) in &mut cameras {
+        if let Some(mut perspective) = perspective {
+            perspective.fov = parameters.fov();
+        }
+        if let Some(mut projection) = projection {
+            if let Projection::Perspective(perspective) = &mut *projection {
+                perspective.fov = parameters.fov();
+            }
+        }
+    }
+}
+
+/// System that makes sure every [`Image`] used as a [`RenderTarget`] can be rendered to, then
+/// sampled or read back, by adding [`TextureUsages::RENDER_ATTACHMENT`],
+/// [`TextureUsages::TEXTURE_BINDING`] and [`TextureUsages::COPY_SRC`] to its texture descriptor
+/// when they are missing.
+pub fn camera_target_usage_system(cameras: Query<&Camera>, mut images: ResMut<Assets<Image>>) {
+    const TARGET_USAGES: TextureUsages = TextureUsages::RENDER_ATTACHMENT
+        .union(TextureUsages::TEXTURE_BINDING)
+        .union(TextureUsages::COPY_SRC);
+
+    for camera in &cameras {
+        let RenderTarget::Image(handle) = &camera.target else {
+            continue;
+        };
+        let has_usages = images.get(handle).map_or(true, |image| {
+            image.texture_descriptor.usage.contains(TARGET_USAGES)
+        });
+        if !has_usages {
+            if let Some(image) = images.get_mut(handle) {
+                image.texture_descriptor.usage |= TARGET_USAGES;
+            }
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct ExtractedCamera {
     pub target: RenderTarget,
@@ -489,10 +693,25 @@ pub fn extract_cameras(
             &CameraRenderGraph,
             &GlobalTransform,
             &VisibleEntities,
+            Option<&TemporalJitter>,
+            Option<&ColorGrading>,
+            Option<&CameraClipPlane>,
+            Option<&PhysicalCameraParameters>,
         )>,
     >,
 ) {
-    for (entity, camera, camera_render_graph, transform, visible_entities) in query.iter() {
+    for (
+        entity,
+        camera,
+        camera_render_graph,
+        transform,
+        visible_entities,
+        temporal_jitter,
+        color_grading,
+        clip_plane,
+        physical_parameters,
+    ) in query.iter()
+    {
         if !camera.is_active {
             continue;
         }
@@ -504,7 +723,12 @@ pub fn extract_cameras(
             if target_size.x == 0 || target_size.y == 0 {
                 continue;
             }
-            commands.get_or_spawn(entity).insert((
+            let mut projection = camera.projection_matrix();
+            if let Some(clip_plane) = clip_plane {
+                clip_plane.clip_projection(&mut projection, transform);
+            }
+            let mut camera_commands = commands.get_or_spawn(entity);
+            camera_commands.insert((
                 ExtractedCamera {
                     target: camera.target.clone(),
                     viewport: camera.viewport.clone(),
@@ -514,7 +738,7 @@ pub fn extract_cameras(
                     priority: camera.priority,
                 },
                 ExtractedView {
-                    projection: camera.projection_matrix(),
+                    projection,
                     transform: *transform,
                     hdr: camera.hdr,
                     viewport: UVec4::new(
@@ -526,6 +750,20 @@ pub fn extract_cameras(
                 },
                 visible_entities.clone(),
             ));
+            if let Some(temporal_jitter) = temporal_jitter {
+                camera_commands.insert(temporal_jitter.clone());
+            }
+            match (color_grading, physical_parameters) {
+                (color_grading, Some(physical_parameters)) => {
+                    let mut color_grading = color_grading.cloned().unwrap_or_default();
+                    color_grading.exposure += physical_parameters.exposure_stops();
+                    camera_commands.insert(color_grading);
+                }
+                (Some(color_grading), None) => {
+                    camera_commands.insert(color_grading.clone());
+                }
+                (None, None) => {}
+            }
         }
     }
 }
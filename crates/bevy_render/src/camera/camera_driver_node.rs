@@ -35,10 +35,13 @@ impl Node for CameraDriverNode {
             .iter_manual(world)
             .map(|(e, c)| (e, c.priority, c.target.clone()))
             .collect::<Vec<_>>();
-        // sort by priority and ensure within a priority, RenderTargets of the same type are packed together
-        sorted_cameras.sort_by(|(_, p1, t1), (_, p2, t2)| match p1.cmp(p2) {
-            std::cmp::Ordering::Equal => t1.cmp(t2),
-            ord => ord,
+        // sort by priority and ensure within a priority, RenderTargets of the same type are packed together.
+        // Image targets are rendered first, so that cameras rendering to windows can sample them this frame.
+        sorted_cameras.sort_by(|(_, p1, t1), (_, p2, t2)| {
+            let is_window = |target: &RenderTarget| matches!(target, RenderTarget::Window(_));
+            p1.cmp(p2)
+                .then_with(|| is_window(t1).cmp(&is_window(t2)))
+                .then_with(|| t1.cmp(t2))
         });
         let mut camera_windows = HashSet::new();
         let mut previous_priority_target = None;
@@ -8,7 +8,8 @@ pub use camera_driver_node::*;
 pub use projection::*;
 
 use crate::{render_graph::RenderGraph, RenderApp, RenderStage};
-use bevy_app::{App, Plugin};
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_ecs::schedule::IntoSystemDescriptor;
 
 #[derive(Default)]
 pub struct CameraPlugin;
@@ -22,9 +23,20 @@ impl Plugin for CameraPlugin {
             .register_type::<ScalingMode>()
             .register_type::<CameraRenderGraph>()
             .register_type::<RenderTarget>()
+            .register_type::<TemporalJitter>()
+            .register_type::<CameraClipPlane>()
+            .register_type::<PhysicalCameraParameters>()
             .add_plugin(CameraProjectionPlugin::<Projection>::default())
             .add_plugin(CameraProjectionPlugin::<OrthographicProjection>::default())
-            .add_plugin(CameraProjectionPlugin::<PerspectiveProjection>::default());
+            .add_plugin(CameraProjectionPlugin::<PerspectiveProjection>::default())
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                camera_target_usage_system.before(CameraUpdateSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                physical_camera_system.before(CameraUpdateSystem),
+            );
 
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app.add_system_to_stage(RenderStage::Extract, extract_cameras);
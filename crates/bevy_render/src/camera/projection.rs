@@ -1,16 +1,19 @@
 use std::marker::PhantomData;
 
+use crate::view::{update_frusta, VisibilitySystems};
 use bevy_app::{App, CoreStage, Plugin, StartupStage};
 use bevy_ecs::{prelude::*, reflect::ReflectComponent};
-use bevy_math::Mat4;
+use bevy_math::{Mat4, Vec3A};
 use bevy_reflect::{
     std_traits::ReflectDefault, FromReflect, GetTypeRegistration, Reflect, ReflectDeserialize,
     ReflectSerialize,
 };
+use bevy_transform::TransformSystem;
 use bevy_window::ModifiesWindows;
 use serde::{Deserialize, Serialize};
 
-/// Adds [`Camera`](crate::camera::Camera) driver systems for a given projection type.
+/// Adds [`Camera`](crate::camera::Camera) driver systems for a given projection type, updating the
+/// projection matrix of the cameras and their [`Frustum`](crate::primitives::Frustum).
 pub struct CameraProjectionPlugin<T: CameraProjection>(PhantomData<T>);
 
 impl<T: CameraProjection> Default for CameraProjectionPlugin<T> {
@@ -46,6 +49,17 @@ impl<T: CameraProjection + Component + GetTypeRegistration> Plugin for CameraPro
                     // so we can ignore ambiguities with all other monomorphizations.
                     // FIXME: Add an archetype invariant for this https://github.com/bevyengine/bevy/issues/1481.
                     .ambiguous_with(CameraUpdateSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_frusta::<T>
+                    .label(VisibilitySystems::UpdateProjectionFrusta)
+                    .after(crate::camera::camera_system::<T>)
+                    .after(TransformSystem::TransformPropagate)
+                    // We assume that no camera will have more than one projection component,
+                    // so these systems will run independently of one another.
+                    // FIXME: Add an archetype invariant for this https://github.com/bevyengine/bevy/issues/1481.
+                    .ambiguous_with(VisibilitySystems::UpdateProjectionFrusta),
             );
     }
 }
@@ -56,11 +70,25 @@ impl<T: CameraProjection + Component + GetTypeRegistration> Plugin for CameraPro
 /// to recompute the camera projection matrix of the [`Camera`] component attached to
 /// the same entity as the component implementing this trait.
 ///
+/// Custom projections, like off-axis or oblique ones, are added with a
+/// [`CameraProjectionPlugin`] for their type. Their matrix is used for the view uniform and to
+/// cull the entities outside of the view, so it must follow the conventions of the built-in
+/// projections: a right-handed view space looking down `-z`, and a reversed depth with the near
+/// plane at 1 and the far plane at 0.
+///
 /// [`Camera`]: crate::camera::Camera
 pub trait CameraProjection {
     fn get_projection_matrix(&self) -> Mat4;
     fn update(&mut self, width: f32, height: f32);
     fn far(&self) -> f32;
+    /// Returns the eight corners of the slice of the view frustum between the view-space
+    /// `z_near` and `z_far` planes.
+    ///
+    /// The corners are given in view space, with the camera looking down `-z`, so both
+    /// values are expected to be negative for points in front of the camera. They are
+    /// ordered bottom right, top right, top left and bottom left, first for the near plane
+    /// and then for the far plane.
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8];
 }
 
 /// A configurable [`CameraProjection`] that can select its projection type at runtime.
@@ -104,6 +132,13 @@ impl CameraProjection for Projection {
             Projection::Orthographic(projection) => projection.far(),
         }
     }
+
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8] {
+        match self {
+            Projection::Perspective(projection) => projection.get_frustum_corners(z_near, z_far),
+            Projection::Orthographic(projection) => projection.get_frustum_corners(z_near, z_far),
+        }
+    }
 }
 
 impl Default for Projection {
@@ -156,6 +191,23 @@ impl CameraProjection for PerspectiveProjection {
     fn far(&self) -> f32 {
         self.far
     }
+
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8] {
+        let tan_half_fov = (self.fov / 2.0).tan();
+        let a = z_near.abs() * tan_half_fov;
+        let b = z_far.abs() * tan_half_fov;
+        let aspect_ratio = self.aspect_ratio;
+        [
+            Vec3A::new(a * aspect_ratio, -a, z_near),  // bottom right
+            Vec3A::new(a * aspect_ratio, a, z_near),   // top right
+            Vec3A::new(-a * aspect_ratio, a, z_near),  // top left
+            Vec3A::new(-a * aspect_ratio, -a, z_near), // bottom left
+            Vec3A::new(b * aspect_ratio, -b, z_far),   // bottom right
+            Vec3A::new(b * aspect_ratio, b, z_far),    // top right
+            Vec3A::new(-b * aspect_ratio, b, z_far),   // top left
+            Vec3A::new(-b * aspect_ratio, -b, z_far),  // bottom left
+        ]
+    }
 }
 
 impl Default for PerspectiveProjection {
@@ -293,6 +345,23 @@ impl CameraProjection for OrthographicProjection {
     fn far(&self) -> f32 {
         self.far
     }
+
+    fn get_frustum_corners(&self, z_near: f32, z_far: f32) -> [Vec3A; 8] {
+        let left = self.left * self.scale;
+        let right = self.right * self.scale;
+        let bottom = self.bottom * self.scale;
+        let top = self.top * self.scale;
+        [
+            Vec3A::new(right, bottom, z_near), // bottom right
+            Vec3A::new(right, top, z_near),    // top right
+            Vec3A::new(left, top, z_near),     // top left
+            Vec3A::new(left, bottom, z_near),  // bottom left
+            Vec3A::new(right, bottom, z_far),  // bottom right
+            Vec3A::new(right, top, z_far),     // top right
+            Vec3A::new(left, top, z_far),      // top left
+            Vec3A::new(left, bottom, z_far),   // bottom left
+        ]
+    }
 }
 
 impl Default for OrthographicProjection {
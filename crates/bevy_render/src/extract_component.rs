@@ -1,6 +1,6 @@
 use crate::{
     render_resource::{encase::internal::WriteInto, DynamicUniformBuffer, ShaderType},
-    renderer::{RenderDevice, RenderQueue},
+    renderer::{RenderDevice, StagingBelt},
     view::ComputedVisibility,
     Extract, RenderApp, RenderStage,
 };
@@ -80,7 +80,7 @@ impl<C: Component + ShaderType + WriteInto + Clone> Plugin for UniformComponentP
     fn build(&self, app: &mut App) {
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
-                .insert_resource(ComponentUniforms::<C>::default())
+                .init_resource::<ComponentUniforms<C>>()
                 .add_system_to_stage(RenderStage::Prepare, prepare_uniform_components::<C>);
         }
     }
@@ -108,10 +108,10 @@ impl<C: Component + ShaderType> ComponentUniforms<C> {
     }
 }
 
-impl<C: Component + ShaderType> Default for ComponentUniforms<C> {
-    fn default() -> Self {
+impl<C: Component + ShaderType> FromWorld for ComponentUniforms<C> {
+    fn from_world(world: &mut World) -> Self {
         Self {
-            uniforms: Default::default(),
+            uniforms: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
         }
     }
 }
@@ -121,7 +121,7 @@ impl<C: Component + ShaderType> Default for ComponentUniforms<C> {
 fn prepare_uniform_components<C: Component>(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
-    render_queue: Res<RenderQueue>,
+    staging_belt: Res<StagingBelt>,
     mut component_uniforms: ResMut<ComponentUniforms<C>>,
     components: Query<(Entity, &C)>,
 ) where
@@ -144,7 +144,7 @@ fn prepare_uniform_components<C: Component>(
 
     component_uniforms
         .uniforms
-        .write_buffer(&render_device, &render_queue);
+        .write_buffer_staged(&render_device, &staging_belt);
 }
 
 /// This plugin extracts the components into the "render world".
@@ -1,7 +1,7 @@
 use crate::{
     extract_resource::ExtractResource,
     render_resource::{ShaderType, UniformBuffer},
-    renderer::{RenderDevice, RenderQueue},
+    renderer::{RenderDevice, StagingBelt},
     Extract, RenderApp, RenderStage,
 };
 use bevy_app::{App, Plugin};
@@ -38,6 +38,7 @@ fn extract_time(mut commands: Commands, time: Extract<Res<Time>>) {
 /// Currently only contains values related to time.
 #[derive(Default, Clone, Resource, ExtractResource, Reflect, ShaderType)]
 #[reflect(Resource)]
+#[shader_type(uniform)]
 pub struct GlobalsUniform {
     /// The time since startup in seconds.
     /// Wraps to 0 after 1 hour.
@@ -60,7 +61,7 @@ pub struct GlobalsBuffer {
 
 fn prepare_globals_buffer(
     render_device: Res<RenderDevice>,
-    render_queue: Res<RenderQueue>,
+    staging_belt: Res<StagingBelt>,
     mut globals_buffer: ResMut<GlobalsBuffer>,
     time: Res<Time>,
     frame_count: Res<FrameCount>,
@@ -72,5 +73,5 @@ fn prepare_globals_buffer(
 
     globals_buffer
         .buffer
-        .write_buffer(&render_device, &render_queue);
+        .write_buffer_staged(&render_device, &staging_belt);
 }
@@ -12,6 +12,7 @@ pub mod globals;
 pub mod mesh;
 pub mod primitives;
 pub mod rangefinder;
+pub mod ray_cast;
 pub mod render_asset;
 pub mod render_graph;
 pub mod render_phase;
@@ -44,12 +45,17 @@ pub use once_cell;
 use crate::{
     camera::CameraPlugin,
     mesh::MeshPlugin,
-    render_resource::{PipelineCache, Shader, ShaderLoader},
-    renderer::{render_system, RenderInstance},
+    ray_cast::RayCastPlugin,
+    render_resource::{
+        send_pipeline_compilation_events, send_shader_error_events, update_bind_group_cache_system,
+        BindGroupCache, PipelineCache, PipelineCompilationEvent, Shader, ShaderErrorEvent,
+        ShaderLoader,
+    },
+    renderer::{render_system, RenderInstance, StagingBelt},
     settings::WgpuSettings,
     view::{ViewPlugin, WindowRenderPlugin},
 };
-use bevy_app::{App, AppLabel, Plugin};
+use bevy_app::{App, AppLabel, CoreStage, Plugin};
 use bevy_asset::{AddAsset, AssetServer};
 use bevy_ecs::prelude::*;
 use bevy_utils::tracing::debug;
@@ -62,6 +68,12 @@ use std::{
 #[derive(Default)]
 pub struct RenderPlugin {
     pub wgpu_settings: WgpuSettings,
+    /// Whether the pipelines are created on the [`AsyncComputeTaskPool`](bevy_tasks::AsyncComputeTaskPool)
+    /// over the next frames, instead of right before the frame they are first drawn in.
+    /// Creating them asynchronously avoids stalling the frames new pipelines appear in, but the
+    /// draws using them are skipped until they are created, so that the first frames may not be
+    /// rendered completely. Defaults to `false`.
+    pub asynchronous_pipeline_compilation: bool,
 }
 
 /// The labels of the default App rendering stages.
@@ -166,7 +178,14 @@ impl Plugin for RenderPlugin {
                 .insert_resource(render_adapter.clone())
                 .init_resource::<ScratchMainWorld>();
 
-            let pipeline_cache = PipelineCache::new(device.clone());
+            let pipeline_cache =
+                PipelineCache::new(device.clone(), !self.asynchronous_pipeline_compilation);
+            app.insert_resource(pipeline_cache.completed_pipelines())
+                .insert_resource(pipeline_cache.shader_errors())
+                .add_event::<PipelineCompilationEvent>()
+                .add_event::<ShaderErrorEvent>()
+                .add_system_to_stage(CoreStage::PreUpdate, send_pipeline_compilation_events)
+                .add_system_to_stage(CoreStage::PreUpdate, send_shader_error_events);
             let asset_server = app.world.resource::<AssetServer>().clone();
 
             let mut render_app = App::empty();
@@ -198,8 +217,13 @@ impl Plugin for RenderPlugin {
                         .with_system(PipelineCache::process_pipeline_queue_system)
                         .with_system(render_system.at_end()),
                 )
-                .add_stage(RenderStage::Cleanup, SystemStage::parallel())
+                .add_stage(
+                    RenderStage::Cleanup,
+                    SystemStage::parallel().with_system(update_bind_group_cache_system),
+                )
                 .init_resource::<render_graph::RenderGraph>()
+                .init_resource::<BindGroupCache>()
+                .init_resource::<StagingBelt>()
                 .insert_resource(RenderInstance(instance))
                 .insert_resource(device)
                 .insert_resource(queue)
@@ -328,12 +352,15 @@ impl Plugin for RenderPlugin {
             .add_plugin(CameraPlugin)
             .add_plugin(ViewPlugin)
             .add_plugin(MeshPlugin)
-            .add_plugin(GlobalsPlugin);
+            .add_plugin(GlobalsPlugin)
+            .add_plugin(RayCastPlugin);
 
         app.register_type::<color::Color>()
             .register_type::<primitives::Aabb>()
             .register_type::<primitives::CubemapFrusta>()
-            .register_type::<primitives::Frustum>();
+            .register_type::<primitives::CascadesFrusta>()
+            .register_type::<primitives::Frustum>()
+            .register_type::<primitives::WorldBounds>();
     }
 }
 
@@ -0,0 +1,562 @@
+use crate::{
+    render_resource::{Buffer, BufferSlice},
+    renderer::{RenderDevice, RenderQueue},
+};
+use bevy_ecs::system::{Res, ResMut, Resource};
+use bevy_utils::HashMap;
+use parking_lot::Mutex;
+use std::{borrow::Cow, fmt, iter, ops::Range, sync::Arc};
+use wgpu::{BufferDescriptor, BufferUsages, CommandEncoderDescriptor, IndexFormat};
+
+/// The vertex and index buffers shared by the meshes, each [`GpuMesh`](super::GpuMesh) getting a
+/// range of them.
+///
+/// Meshes don't get a pair of buffers each, which would fragment video memory and change the bound
+/// buffers between every draw. The vertices of the meshes with the same vertex stride are stored
+/// together in slabs of up to [`MeshBufferArena::MAX_SLAB_SIZE`] bytes, and the indices of the
+/// same format are drawn from their [`first_index`](MeshBufferSlices::first_index).
+///
+/// The vertex buffer of a mesh is bound from its first vertex with
+/// [`MeshBufferSlices::vertex_slice`], so its draws use a base vertex and a first vertex of `0`.
+/// Drawing with a non-zero base vertex isn't supported by every device, like `WebGL2` ones, and the
+/// `vertex_index` seen by the shaders stays the index of the vertex in the mesh, which is what
+/// the vertex shaders of morph targets use to look up their deltas.
+///
+/// The ranges of a mesh are freed when its last [`MeshAllocation`] is dropped, along with its
+/// [`GpuMesh`](super::GpuMesh) when the mesh is unloaded or modified. The ranges of a slab are
+/// packed together again during the [`RenderStage::Prepare`](crate::RenderStage::Prepare) once
+/// a quarter of its space has been freed, which moves them: the draws look them up with
+/// [`MeshBufferArena::get`] every frame.
+#[derive(Resource, Default)]
+pub struct MeshBufferArena {
+    slabs: Vec<Slab>,
+    allocations: HashMap<u64, MeshRanges>,
+    next_id: u64,
+    freed: Arc<Mutex<Vec<u64>>>,
+}
+
+/// The ranges of a mesh in the [`MeshBufferArena`], freed once every clone of it is dropped.
+#[derive(Clone)]
+pub struct MeshAllocation(Arc<AllocationGuard>);
+
+struct AllocationGuard {
+    id: u64,
+    freed: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Drop for AllocationGuard {
+    fn drop(&mut self) {
+        self.freed.lock().push(self.id);
+    }
+}
+
+impl fmt::Debug for MeshAllocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MeshAllocation").field(&self.0.id).finish()
+    }
+}
+
+/// Where the data of a mesh currently is in the buffers of the [`MeshBufferArena`].
+#[derive(Debug, Clone, Copy)]
+pub struct MeshBufferSlices<'a> {
+    /// The buffer storing the vertices of the mesh.
+    pub vertex_buffer: &'a Buffer,
+    /// The offset in bytes of the first vertex of the mesh in the `vertex_buffer`.
+    pub vertex_offset: u64,
+    /// The buffer to bind as the index buffer of the mesh, from its start, if it has indices.
+    pub index_buffer: Option<&'a Buffer>,
+    /// The index of the first index of the mesh in the `index_buffer`.
+    pub first_index: u32,
+}
+
+impl<'a> MeshBufferSlices<'a> {
+    /// The vertex buffer to bind for the mesh, starting at its first vertex.
+    #[inline]
+    pub fn vertex_slice(&self) -> BufferSlice<'a> {
+        self.vertex_buffer.slice(self.vertex_offset..)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlabKind {
+    Vertex { stride: u64 },
+    Index(IndexFormat),
+}
+
+impl SlabKind {
+    fn element_size(self) -> u64 {
+        match self {
+            SlabKind::Vertex { stride } => stride,
+            SlabKind::Index(IndexFormat::Uint16) => 2,
+            SlabKind::Index(IndexFormat::Uint32) => 4,
+        }
+    }
+
+    /// The number of elements the ranges are a multiple of, for their offsets and sizes in bytes
+    /// to be multiples of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    fn alignment(self) -> u64 {
+        let size = self.element_size();
+        wgpu::COPY_BUFFER_ALIGNMENT / gcd(size, wgpu::COPY_BUFFER_ALIGNMENT)
+    }
+
+    fn usage(self) -> BufferUsages {
+        let usage = match self {
+            SlabKind::Vertex { .. } => BufferUsages::VERTEX,
+            SlabKind::Index(_) => BufferUsages::INDEX,
+        };
+        usage | BufferUsages::COPY_DST | BufferUsages::COPY_SRC
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SlabKind::Vertex { .. } => "mesh_vertex_slab",
+            SlabKind::Index(_) => "mesh_index_slab",
+        }
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A buffer storing the ranges of several meshes, one after another, in elements of its kind.
+struct Slab {
+    kind: SlabKind,
+    buffer: Option<Buffer>,
+    capacity: u64,
+    /// The end of the last range.
+    end: u64,
+    /// The number of elements of the freed ranges before the `end`.
+    freed: u64,
+}
+
+impl Slab {
+    fn new(kind: SlabKind) -> Self {
+        Self {
+            kind,
+            buffer: None,
+            capacity: 0,
+            end: 0,
+            freed: 0,
+        }
+    }
+
+    fn max_capacity(&self) -> u64 {
+        MeshBufferArena::MAX_SLAB_SIZE / self.kind.element_size()
+    }
+
+    /// Whether `count` more elements can be stored at the end of the slab. An empty slab can
+    /// store a range of any size, larger than the maximum size of a slab.
+    fn fits(&self, count: u64) -> bool {
+        self.end == 0 || self.end + count <= self.capacity.max(self.max_capacity())
+    }
+
+    fn create_buffer(&self, render_device: &RenderDevice, capacity: u64) -> Buffer {
+        render_device.create_buffer(&BufferDescriptor {
+            label: Some(self.kind.label()),
+            size: capacity * self.kind.element_size(),
+            usage: self.kind.usage(),
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Grows the capacity to store at least `capacity` elements, the buffer being resized by
+    /// [`Slab::sync_buffer`].
+    fn reserve(&mut self, capacity: u64) {
+        if capacity <= self.capacity {
+            return;
+        }
+        // The buffer at least doubles in size when it grows, up to the maximum size of a slab
+        let min_capacity = MeshBufferArena::MIN_SLAB_SIZE / self.kind.element_size();
+        self.capacity = (self.capacity * 2)
+            .max(min_capacity)
+            .min(self.max_capacity())
+            .max(capacity);
+    }
+
+    /// Creates a buffer of the capacity of the slab if it doesn't have one that large, copying the
+    /// ranges of the previous buffer to it.
+    fn sync_buffer(&mut self, render_device: &RenderDevice, render_queue: &RenderQueue) {
+        let size = self.capacity * self.kind.element_size();
+        if self
+            .buffer
+            .as_ref()
+            .map_or(false, |buffer| buffer.size() >= size)
+        {
+            return;
+        }
+        let buffer = self.create_buffer(render_device, self.capacity);
+        if let Some(old_buffer) = &self.buffer {
+            let mut command_encoder =
+                render_device.create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("mesh_slab_grow_encoder"),
+                });
+            command_encoder.copy_buffer_to_buffer(old_buffer, 0, &buffer, 0, old_buffer.size());
+            render_queue.submit([command_encoder.finish()]);
+        }
+        self.buffer = Some(buffer);
+    }
+}
+
+/// A range of elements of a slab.
+#[derive(Debug, Clone)]
+struct SlabRange {
+    slab: usize,
+    range: Range<u64>,
+}
+
+struct MeshRanges {
+    vertices: SlabRange,
+    indices: Option<SlabRange>,
+}
+
+/// The ranges of a slab moved to a new buffer of its capacity when it is compacted.
+struct SlabRelocation {
+    slab: usize,
+    /// The old range of the elements moved, and where they start in the new buffer.
+    moves: Vec<(Range<u64>, u64)>,
+}
+
+impl MeshBufferArena {
+    /// The size in bytes that slabs grow up to. A larger mesh gets a slab of its own.
+    pub const MAX_SLAB_SIZE: u64 = 64 * 1024 * 1024;
+    /// The size in bytes of the buffer first created for a slab.
+    pub const MIN_SLAB_SIZE: u64 = 1024 * 1024;
+
+    /// Stores the `vertex_data`, made of vertices of `vertex_stride` bytes, and the `indices`, if
+    /// there are any, in the slabs of the arena.
+    pub fn allocate(
+        &mut self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+        vertex_data: &[u8],
+        vertex_stride: u64,
+        indices: Option<(&[u8], IndexFormat)>,
+    ) -> MeshAllocation {
+        // Meshes without vertex attributes still get a range to bind
+        let vertex_stride = if vertex_stride == 0 { 4 } else { vertex_stride };
+        let allocation = self.allocate_ranges(
+            (
+                SlabKind::Vertex {
+                    stride: vertex_stride,
+                },
+                vertex_data.len() as u64,
+            ),
+            indices.map(|(data, format)| (SlabKind::Index(format), data.len() as u64)),
+        );
+
+        let ranges = &self.allocations[&allocation.0.id];
+        let writes = iter::once((ranges.vertices.clone(), vertex_data))
+            .chain(ranges.indices.clone().zip(indices.map(|(data, _)| data)));
+        for (range, data) in writes {
+            let slab = &mut self.slabs[range.slab];
+            slab.sync_buffer(render_device, render_queue);
+            if data.is_empty() {
+                continue;
+            }
+            // Writes are multiples of 4 bytes
+            let aligned_len = (data.len() + 3) / 4 * 4;
+            let data = if aligned_len == data.len() {
+                Cow::Borrowed(data)
+            } else {
+                let mut padded = data.to_vec();
+                padded.resize(aligned_len, 0);
+                Cow::Owned(padded)
+            };
+            render_queue.write_buffer(
+                slab.buffer.as_ref().unwrap(),
+                range.range.start * slab.kind.element_size(),
+                &data,
+            );
+        }
+        allocation
+    }
+
+    /// Reserves ranges of `vertices` and `indices` sizes in bytes in the slabs of their kinds.
+    fn allocate_ranges(
+        &mut self,
+        vertices: (SlabKind, u64),
+        indices: Option<(SlabKind, u64)>,
+    ) -> MeshAllocation {
+        let vertices = self.allocate_range(vertices.0, vertices.1);
+        let indices = indices.map(|(kind, size)| self.allocate_range(kind, size));
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.allocations
+            .insert(id, MeshRanges { vertices, indices });
+        MeshAllocation(Arc::new(AllocationGuard {
+            id,
+            freed: self.freed.clone(),
+        }))
+    }
+
+    fn allocate_range(&mut self, kind: SlabKind, size: u64) -> SlabRange {
+        let alignment = kind.alignment();
+        let count = (size / kind.element_size()).max(1);
+        let count = (count + alignment - 1) / alignment * alignment;
+
+        let slab_index = match self
+            .slabs
+            .iter()
+            .position(|slab| slab.kind == kind && slab.fits(count))
+        {
+            Some(slab_index) => slab_index,
+            None => {
+                self.slabs.push(Slab::new(kind));
+                self.slabs.len() - 1
+            }
+        };
+        let slab = &mut self.slabs[slab_index];
+        slab.reserve(slab.end + count);
+        let range = slab.end..slab.end + count;
+        slab.end += count;
+        SlabRange {
+            slab: slab_index,
+            range,
+        }
+    }
+
+    /// Returns the buffers of the mesh of the `allocation` and where its data starts in them.
+    pub fn get(&self, allocation: &MeshAllocation) -> Option<MeshBufferSlices> {
+        let ranges = self.allocations.get(&allocation.0.id)?;
+        let vertex_buffer = self.slabs[ranges.vertices.slab].buffer.as_ref()?;
+        let (index_buffer, first_index) = match &ranges.indices {
+            Some(indices) => (
+                Some(self.slabs[indices.slab].buffer.as_ref()?),
+                indices.range.start as u32,
+            ),
+            None => (None, 0),
+        };
+        Some(MeshBufferSlices {
+            vertex_buffer,
+            vertex_offset: ranges.vertices.range.start
+                * self.slabs[ranges.vertices.slab].kind.element_size(),
+            index_buffer,
+            first_index,
+        })
+    }
+
+    /// The sizes of the buffers of the slabs of the arena, in bytes.
+    pub fn buffer_size(&self) -> u64 {
+        self.slabs
+            .iter()
+            .map(|slab| slab.capacity * slab.kind.element_size())
+            .sum()
+    }
+
+    /// Frees the ranges of the dropped allocations, and packs the ranges of the slabs where they
+    /// made enough room.
+    fn compact(&mut self, render_device: &RenderDevice, render_queue: &RenderQueue) {
+        let relocations = self.free_dropped();
+        if relocations.is_empty() {
+            return;
+        }
+        let mut command_encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("mesh_slab_compact_encoder"),
+        });
+        for relocation in relocations {
+            let slab = &mut self.slabs[relocation.slab];
+            let buffer = slab.create_buffer(render_device, slab.capacity);
+            let old_buffer = slab.buffer.as_ref().unwrap();
+            let element_size = slab.kind.element_size();
+            for (range, start) in relocation.moves {
+                command_encoder.copy_buffer_to_buffer(
+                    old_buffer,
+                    range.start * element_size,
+                    &buffer,
+                    start * element_size,
+                    (range.end - range.start) * element_size,
+                );
+            }
+            slab.buffer = Some(buffer);
+        }
+        render_queue.submit([command_encoder.finish()]);
+    }
+
+    /// Frees the ranges of the dropped allocations, and moves the ranges of the slabs with at
+    /// least a quarter of their space freed to the start of the slab, returning the copies to do
+    /// to a new buffer for each of them.
+    fn free_dropped(&mut self) -> Vec<SlabRelocation> {
+        let freed = std::mem::take(&mut *self.freed.lock());
+        for id in freed {
+            let Some(ranges) = self.allocations.remove(&id) else {
+                continue;
+            };
+            for range in iter::once(ranges.vertices).chain(ranges.indices) {
+                self.slabs[range.slab].freed += range.range.end - range.range.start;
+            }
+        }
+
+        let mut relocations = Vec::new();
+        for (slab_index, slab) in self.slabs.iter_mut().enumerate() {
+            if slab.freed == 0 || slab.freed * 4 < slab.end {
+                continue;
+            }
+            if slab.freed == slab.end {
+                // Nothing left in the slab, which can start over with a small buffer
+                *slab = Slab::new(slab.kind);
+                continue;
+            }
+
+            let mut ranges: Vec<&mut SlabRange> = self
+                .allocations
+                .values_mut()
+                .flat_map(|ranges| iter::once(&mut ranges.vertices).chain(ranges.indices.as_mut()))
+                .filter(|range| range.slab == slab_index)
+                .collect();
+            ranges.sort_unstable_by_key(|range| range.range.start);
+
+            let used = slab.end - slab.freed;
+            if used * 4 < slab.capacity {
+                slab.capacity = (slab.capacity / 2).max(used);
+            }
+            let mut moves = Vec::with_capacity(ranges.len());
+            let mut end = 0;
+            for range in ranges {
+                let count = range.range.end - range.range.start;
+                moves.push((range.range.clone(), end));
+                range.range = end..end + count;
+                end += count;
+            }
+            slab.end = end;
+            slab.freed = 0;
+            relocations.push(SlabRelocation {
+                slab: slab_index,
+                moves,
+            });
+        }
+        relocations
+    }
+}
+
+/// Frees the ranges of the meshes unloaded this frame from the [`MeshBufferArena`], compacting
+/// its slabs.
+pub fn compact_mesh_buffers(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut arena: ResMut<MeshBufferArena>,
+) {
+    arena.compact(&render_device, &render_queue);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MeshAllocation, MeshBufferArena, SlabKind};
+    use std::ops::Range;
+    use wgpu::IndexFormat;
+
+    const VERTEX: SlabKind = SlabKind::Vertex { stride: 32 };
+    const INDEX: SlabKind = SlabKind::Index(IndexFormat::Uint16);
+
+    fn ranges(
+        arena: &MeshBufferArena,
+        allocation: &MeshAllocation,
+    ) -> ((usize, Range<u64>), Option<(usize, Range<u64>)>) {
+        let ranges = &arena.allocations[&allocation.0.id];
+        (
+            (ranges.vertices.slab, ranges.vertices.range.clone()),
+            ranges
+                .indices
+                .as_ref()
+                .map(|indices| (indices.slab, indices.range.clone())),
+        )
+    }
+
+    #[test]
+    fn slab_alignment() {
+        assert_eq!(SlabKind::Index(IndexFormat::Uint16).alignment(), 2);
+        assert_eq!(SlabKind::Index(IndexFormat::Uint32).alignment(), 1);
+        assert_eq!(SlabKind::Vertex { stride: 32 }.alignment(), 1);
+        assert_eq!(SlabKind::Vertex { stride: 6 }.alignment(), 2);
+        assert_eq!(SlabKind::Vertex { stride: 3 }.alignment(), 4);
+    }
+
+    #[test]
+    fn allocate() {
+        let mut arena = MeshBufferArena::default();
+        let a = arena.allocate_ranges((VERTEX, 32 * 3), Some((INDEX, 2 * 3)));
+        let b = arena.allocate_ranges((VERTEX, 32 * 4), None);
+        let c = arena.allocate_ranges((SlabKind::Vertex { stride: 16 }, 16 * 2), None);
+
+        // The ranges of the same kind follow each other, the index ranges being aligned to 4 bytes
+        assert_eq!(ranges(&arena, &a), ((0, 0..3), Some((1, 0..4))));
+        assert_eq!(ranges(&arena, &b), ((0, 3..7), None));
+        // Another vertex stride gets another slab
+        assert_eq!(ranges(&arena, &c), ((2, 0..2), None));
+        assert_eq!(arena.slabs[0].capacity, MeshBufferArena::MIN_SLAB_SIZE / 32);
+        assert_eq!(arena.buffer_size(), 3 * MeshBufferArena::MIN_SLAB_SIZE);
+
+        // A range larger than a slab gets a slab of its own
+        let large = arena.allocate_ranges((VERTEX, MeshBufferArena::MAX_SLAB_SIZE * 2), None);
+        let large_count = MeshBufferArena::MAX_SLAB_SIZE * 2 / 32;
+        assert_eq!(ranges(&arena, &large), ((3, 0..large_count), None));
+        assert_eq!(arena.slabs[3].capacity, large_count);
+    }
+
+    #[test]
+    fn free() {
+        let mut arena = MeshBufferArena::default();
+        let a = arena.allocate_ranges((VERTEX, 32 * 10), None);
+        let b = arena.allocate_ranges((VERTEX, 32 * 100), None);
+
+        // The ranges are only freed when every clone of the allocation is dropped
+        let a_clone = a.clone();
+        drop(a);
+        assert!(arena.free_dropped().is_empty());
+        assert_eq!(arena.allocations.len(), 2);
+        drop(a_clone);
+
+        // Freeing less than a quarter of the slab doesn't move the other ranges
+        assert!(arena.free_dropped().is_empty());
+        assert_eq!(arena.allocations.len(), 1);
+        assert_eq!(arena.slabs[0].freed, 10);
+        assert_eq!(ranges(&arena, &b), ((0, 10..110), None));
+
+        // Freeing every range of a slab empties it
+        drop(b);
+        assert!(arena.free_dropped().is_empty());
+        assert_eq!(arena.slabs[0].end, 0);
+        assert_eq!(arena.slabs[0].capacity, 0);
+        assert_eq!(arena.buffer_size(), 0);
+
+        // The empty slab is reused
+        let c = arena.allocate_ranges((VERTEX, 32), None);
+        assert_eq!(ranges(&arena, &c), ((0, 0..1), None));
+    }
+
+    #[test]
+    fn compact_relocation() {
+        let mut arena = MeshBufferArena::default();
+        let a = arena.allocate_ranges((VERTEX, 32 * 50), Some((INDEX, 2 * 6)));
+        let b = arena.allocate_ranges((VERTEX, 32 * 20), Some((INDEX, 2 * 6)));
+        let c = arena.allocate_ranges((VERTEX, 32 * 30), None);
+        drop(a);
+
+        // Half of both slabs was freed, so their remaining ranges are packed at their start
+        let mut relocations = arena.free_dropped();
+        relocations.sort_unstable_by_key(|relocation| relocation.slab);
+        assert_eq!(relocations.len(), 2);
+        assert_eq!(relocations[0].slab, 0);
+        assert_eq!(relocations[0].moves, vec![(50..70, 0), (70..100, 20)]);
+        assert_eq!(relocations[1].slab, 1);
+        assert_eq!(relocations[1].moves, vec![(6..12, 0)]);
+
+        assert_eq!(ranges(&arena, &b), ((0, 0..20), Some((1, 0..6))));
+        assert_eq!(ranges(&arena, &c), ((0, 20..50), None));
+        assert_eq!(arena.slabs[0].end, 50);
+        assert_eq!(arena.slabs[0].freed, 0);
+        // The slabs are mostly empty, so their buffers shrink
+        assert_eq!(
+            arena.slabs[0].capacity,
+            MeshBufferArena::MIN_SLAB_SIZE / 32 / 2
+        );
+
+        // New ranges go after the packed ones
+        let d = arena.allocate_ranges((VERTEX, 32), None);
+        assert_eq!(ranges(&arena, &d), ((0, 50..51), None));
+    }
+}
@@ -0,0 +1,325 @@
+use bevy_asset::Handle;
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::{BuildChildren, DespawnRecursiveExt};
+use bevy_math::{Mat4, Vec3};
+use bevy_reflect::{FromReflect, Reflect};
+use bevy_time::Time;
+use bevy_transform::components::GlobalTransform;
+
+use super::Mesh;
+use crate::{
+    camera::Camera,
+    primitives::{Aabb, WorldBounds},
+    spatial_bundle::SpatialBundle,
+};
+
+/// Switches the [`Handle<Mesh>`] of an entity between levels of detail, depending on how close it
+/// is to the cameras.
+///
+/// The levels are checked in order for each active camera each frame, and the first one whose
+/// [`LodThreshold`] is met is used, or the last one if none of them is. The entity uses the most
+/// detailed level any camera needs, so the levels should be ordered from the most detailed to the
+/// least detailed.
+///
+/// When `cross_fade_duration` isn't zero, the previous level keeps being drawn while the entity
+/// switches to another one, and both are dithered into each other over that duration, see
+/// [`LodCrossFade`]. The level doesn't change again until the cross-fade is over.
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Lod {
+    pub levels: Vec<LodLevel>,
+    /// How long switching between two levels takes, in seconds.
+    pub cross_fade_duration: f32,
+}
+
+/// A level of detail of a [`Lod`].
+#[derive(Clone, Debug, Reflect, FromReflect)]
+pub struct LodLevel {
+    pub mesh: Handle<Mesh>,
+    pub threshold: LodThreshold,
+}
+
+/// When a [`LodLevel`] can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, FromReflect)]
+pub enum LodThreshold {
+    /// While the bounding sphere of the entity covers at least this fraction of the height of
+    /// the viewport of the camera.
+    ScreenCoverage(f32),
+    /// While the center of the bounds of the entity is at most this far from the camera.
+    Distance(f32),
+}
+
+/// Added to an entity with a [`Lod`] while it cross-fades between two levels, and to the
+/// [`LodFadeOut`] entity drawing the previous level.
+///
+/// The main pass of the meshes discards their fragments in complementary dither patterns, so that
+/// each pixel is drawn by one of the two levels: the previous level is drawn everywhere when the
+/// cross-fade starts, and the new one everywhere when it ends.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct LodCrossFade {
+    /// From `0.0` when the cross-fade starts to `1.0` when it ends.
+    pub progress: f32,
+    /// Whether this entity draws the previous level.
+    pub fading_out: bool,
+}
+
+impl LodCrossFade {
+    /// The value the shaders dither the mesh with: the progress, negated for the previous level
+    /// to use the complementary pattern, and never zero, which means there is no cross-fade.
+    pub fn shader_fade(&self) -> f32 {
+        let progress = self.progress.clamp(f32::MIN_POSITIVE, 1.0);
+        if self.fading_out {
+            -progress
+        } else {
+            progress
+        }
+    }
+}
+
+/// The child entity drawing the previous level of an entity with a [`Lod`] while it
+/// cross-fades to another level.
+///
+/// It is spawned with the [`Handle<Mesh>`] of the previous level, and the renderers give it the
+/// material of its `source`. It is despawned when the cross-fade ends.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct LodFadeOut {
+    pub source: Entity,
+}
+
+/// Advances the [`LodCrossFade`]s, then selects the level of detail of each [`Lod`] for the
+/// active cameras, starting a cross-fade when it changes.
+///
+/// It runs at the start of [`CoreStage::PostUpdate`](bevy_app::CoreStage::PostUpdate), with the
+/// transforms and bounds of the previous frame, so that the [`LodFadeOut`] entities it spawns are
+/// seen by the visibility systems of the frame.
+#[allow(clippy::type_complexity)]
+pub fn update_lods(
+    mut commands: Commands,
+    time: Res<Time>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut lods: Query<
+        (
+            Entity,
+            &Lod,
+            &mut Handle<Mesh>,
+            &GlobalTransform,
+            Option<&WorldBounds>,
+            Option<&Aabb>,
+            Option<&mut LodCrossFade>,
+        ),
+        Without<LodFadeOut>,
+    >,
+    mut fade_outs: Query<(Entity, &LodFadeOut, &mut LodCrossFade)>,
+) {
+    for (fade_out_entity, fade_out, mut fade_out_cross_fade) in &mut fade_outs {
+        let Ok((entity, lod, .., Some(mut cross_fade))) = lods.get_mut(fade_out.source) else {
+            // The source was despawned or lost its `Lod`
+            if let Some(mut source) = commands.get_entity(fade_out.source) {
+                source.remove::<LodCrossFade>();
+            }
+            commands.entity(fade_out_entity).despawn_recursive();
+            continue;
+        };
+        cross_fade.progress += if lod.cross_fade_duration > 0.0 {
+            time.delta_seconds() / lod.cross_fade_duration
+        } else {
+            1.0
+        };
+        fade_out_cross_fade.progress = cross_fade.progress;
+        if cross_fade.progress >= 1.0 {
+            commands.entity(entity).remove::<LodCrossFade>();
+            commands.entity(fade_out_entity).despawn_recursive();
+        }
+    }
+
+    let views: Vec<_> = cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .map(|(camera, transform)| {
+            let projection = camera.projection_matrix();
+            LodView {
+                view_projection: projection * transform.compute_matrix().inverse(),
+                y_scale: projection.y_axis.y,
+                position: transform.translation(),
+            }
+        })
+        .collect();
+    if views.is_empty() {
+        return;
+    }
+
+    for (entity, lod, mut mesh, transform, bounds, aabb, cross_fade) in &mut lods {
+        if lod.levels.is_empty() || cross_fade.is_some() {
+            continue;
+        }
+        let (center, radius) = bounds.map_or((transform.translation(), 0.0), |bounds| {
+            (bounds.sphere.center.into(), bounds.sphere.radius)
+        });
+        let level = views
+            .iter()
+            .map(|view| select_lod_level(lod, center, radius, view))
+            .min()
+            .unwrap();
+
+        let level_mesh = &lod.levels[level].mesh;
+        if *mesh == *level_mesh {
+            continue;
+        }
+        // Meshes that aren't one of the levels yet, like when the entity is spawned, switch
+        // right away
+        let is_level = lod.levels.iter().any(|level| level.mesh == *mesh);
+        if is_level && lod.cross_fade_duration > 0.0 {
+            let mut fade_out = commands.spawn((
+                SpatialBundle::VISIBLE_IDENTITY,
+                mesh.clone(),
+                LodFadeOut { source: entity },
+                LodCrossFade {
+                    progress: 0.0,
+                    fading_out: true,
+                },
+            ));
+            // The bounds of the previous level, which would otherwise only be computed next frame
+            if let Some(aabb) = aabb {
+                fade_out.insert(aabb.clone());
+            }
+            let fade_out = fade_out.id();
+            commands
+                .entity(entity)
+                .insert(LodCrossFade::default())
+                .add_child(fade_out);
+        }
+        *mesh = level_mesh.clone();
+    }
+}
+
+/// The transforms of a camera needed to select a level of a [`Lod`].
+struct LodView {
+    view_projection: Mat4,
+    /// How much the projection scales heights from view space to clip space.
+    y_scale: f32,
+    position: Vec3,
+}
+
+/// Returns the index of the first level of the [`Lod`] whose threshold is met for a camera, or
+/// of the last level if there are none.
+fn select_lod_level(lod: &Lod, center: Vec3, radius: f32, view: &LodView) -> usize {
+    // The viewport is 2 high in normalized device coordinates, where the diameter of the sphere
+    // is 2 * radius scaled by the projection, and divided by w with perspective projections
+    let clip_w = (view.view_projection * center.extend(1.0)).w;
+    let screen_coverage = if clip_w > 0.0 {
+        radius * view.y_scale.abs() / clip_w
+    } else {
+        // The camera is inside the sphere, or the entity is behind it
+        f32::INFINITY
+    };
+    let distance = center.distance(view.position);
+
+    lod.levels
+        .iter()
+        .position(|level| match level.threshold {
+            LodThreshold::ScreenCoverage(coverage) => screen_coverage >= coverage,
+            LodThreshold::Distance(max_distance) => distance <= max_distance,
+        })
+        .unwrap_or(lod.levels.len() - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use bevy_app::prelude::*;
+    use bevy_asset::HandleId;
+    use bevy_hierarchy::Children;
+    use bevy_utils::{Duration, Instant};
+
+    use super::*;
+
+    fn advance_time(app: &mut App, seconds: f32) {
+        let mut time = app.world.resource_mut::<Time>();
+        let last_update = time.last_update().unwrap();
+        time.update_with_instant(last_update + Duration::from_secs_f32(seconds));
+    }
+
+    #[test]
+    fn lod_selection_and_cross_fade() {
+        let mut app = App::new();
+        let mut time = Time::default();
+        time.update_with_instant(Instant::now());
+        app.insert_resource(time).add_system(update_lods);
+
+        // Without a projection to compute coverages from, the levels are selected by distance
+        app.world
+            .spawn((Camera::default(), GlobalTransform::default()));
+        let meshes: Vec<Handle<Mesh>> = (0..3)
+            .map(|_| Handle::weak(HandleId::random::<Mesh>()))
+            .collect();
+        let lod = Lod {
+            levels: vec![
+                LodLevel {
+                    mesh: meshes[0].clone(),
+                    threshold: LodThreshold::Distance(10.0),
+                },
+                LodLevel {
+                    mesh: meshes[1].clone(),
+                    threshold: LodThreshold::Distance(20.0),
+                },
+                LodLevel {
+                    mesh: meshes[2].clone(),
+                    threshold: LodThreshold::Distance(0.0),
+                },
+            ],
+            cross_fade_duration: 1.0,
+        };
+        let entity = app
+            .world
+            .spawn((
+                lod,
+                Handle::<Mesh>::default(),
+                GlobalTransform::from_xyz(0.0, 0.0, -5.0),
+            ))
+            .id();
+
+        // A mesh that isn't one of the levels switches right away
+        app.update();
+        assert_eq!(app.world.get::<Handle<Mesh>>(entity), Some(&meshes[0]));
+        assert!(app.world.get::<LodCrossFade>(entity).is_none());
+        assert!(app.world.get::<Children>(entity).is_none());
+
+        // Past the last threshold, the last level is used after a cross-fade
+        *app.world.get_mut::<GlobalTransform>(entity).unwrap() =
+            GlobalTransform::from_xyz(0.0, 0.0, -50.0);
+        advance_time(&mut app, 0.0);
+        app.update();
+        assert_eq!(app.world.get::<Handle<Mesh>>(entity), Some(&meshes[2]));
+        assert_eq!(
+            app.world.get::<LodCrossFade>(entity),
+            Some(&LodCrossFade::default())
+        );
+        let fade_out = app.world.get::<Children>(entity).unwrap()[0];
+        assert_eq!(app.world.get::<Handle<Mesh>>(fade_out), Some(&meshes[0]));
+        assert_eq!(
+            app.world.get::<LodFadeOut>(fade_out).unwrap().source,
+            entity
+        );
+        assert!(app.world.get::<LodCrossFade>(fade_out).unwrap().fading_out);
+
+        // The level doesn't change again during the cross-fade
+        *app.world.get_mut::<GlobalTransform>(entity).unwrap() =
+            GlobalTransform::from_xyz(0.0, 0.0, -15.0);
+        advance_time(&mut app, 0.5);
+        app.update();
+        assert_eq!(app.world.get::<Handle<Mesh>>(entity), Some(&meshes[2]));
+        let cross_fade = app.world.get::<LodCrossFade>(fade_out).unwrap();
+        assert!((cross_fade.progress - 0.5).abs() < 1e-5);
+        assert!((cross_fade.shader_fade() + 0.5).abs() < 1e-5);
+        assert!((app.world.get::<LodCrossFade>(entity).unwrap().shader_fade() - 0.5).abs() < 1e-5);
+
+        // Once it is over, the fade-out entity is despawned and the next level is selected
+        advance_time(&mut app, 0.6);
+        app.update();
+        assert!(app.world.get_entity(fade_out).is_none());
+        assert!(app.world.get::<LodCrossFade>(entity).is_none());
+        advance_time(&mut app, 0.0);
+        app.update();
+        assert_eq!(app.world.get::<Handle<Mesh>>(entity), Some(&meshes[1]));
+        assert!(app.world.get::<LodCrossFade>(entity).is_some());
+    }
+}
@@ -1,25 +1,29 @@
 mod conversions;
+pub mod morph;
 pub mod skinning;
 pub use wgpu::PrimitiveTopology;
 
 use crate::{
+    mesh::{MeshAllocation, MeshBufferArena},
     primitives::Aabb,
     render_asset::{PrepareAssetError, RenderAsset},
-    render_resource::{Buffer, VertexBufferLayout},
-    renderer::RenderDevice,
+    render_resource::{TextureView, VertexBufferLayout},
+    renderer::{RenderDevice, RenderQueue},
 };
 use bevy_core::cast_slice;
 use bevy_derive::EnumVariantMeta;
-use bevy_ecs::system::{lifetimeless::SRes, SystemParamItem};
+use bevy_ecs::system::{
+    lifetimeless::{SRes, SResMut},
+    SystemParamItem,
+};
 use bevy_math::*;
 use bevy_reflect::TypeUuid;
+use bevy_transform::components::Transform;
 use bevy_utils::{tracing::error, Hashed};
+use morph::{MorphAttributes, MorphBuildError, MorphTargets};
 use std::{collections::BTreeMap, hash::Hash, iter::FusedIterator};
 use thiserror::Error;
-use wgpu::{
-    util::BufferInitDescriptor, BufferUsages, IndexFormat, VertexAttribute, VertexFormat,
-    VertexStepMode,
-};
+use wgpu::{IndexFormat, TextureViewDescriptor, VertexAttribute, VertexFormat, VertexStepMode};
 
 pub const INDEX_BUFFER_ASSET_INDEX: u64 = 0;
 pub const VERTEX_ATTRIBUTE_BUFFER_ID: u64 = 10;
@@ -35,6 +39,8 @@ pub struct Mesh {
     /// which allows easy stable VertexBuffers (i.e. same buffer order)
     attributes: BTreeMap<MeshVertexAttributeId, MeshAttributeData>,
     indices: Option<Indices>,
+    morph_targets: Option<MorphTargets>,
+    morph_target_names: Option<Vec<String>>,
 }
 
 /// Contains geometry in the form of a mesh.
@@ -83,6 +89,11 @@ impl Mesh {
     pub const ATTRIBUTE_JOINT_INDEX: MeshVertexAttribute =
         MeshVertexAttribute::new("Vertex_JointIndex", 6, VertexFormat::Uint16x4);
 
+    /// A second set of texture coordinates for the vertex, usually unwrapping the mesh without
+    /// overlaps to sample a lightmap. Use in conjunction with [`Mesh::insert_attribute`]
+    pub const ATTRIBUTE_UV_1: MeshVertexAttribute =
+        MeshVertexAttribute::new("Vertex_Uv_1", 7, VertexFormat::Float32x2);
+
     /// Construct a new mesh. You need to provide a [`PrimitiveTopology`] so that the
     /// renderer knows how to treat the vertex data. Most of the time this will be
     /// [`PrimitiveTopology::TriangleList`].
@@ -91,6 +102,8 @@ impl Mesh {
             primitive_topology,
             attributes: Default::default(),
             indices: None,
+            morph_targets: None,
+            morph_target_names: None,
         }
     }
 
@@ -248,6 +261,35 @@ impl Mesh {
         vertex_count.unwrap_or(0)
     }
 
+    /// Sets the morph targets of the mesh, each displacing all of its vertices in order, which are
+    /// blended by the [`MeshMorphWeights`](morph::MeshMorphWeights) of its entities.
+    ///
+    /// The vertices must be set first, as the number of displacements of each target is checked
+    /// against [`Mesh::count_vertices`].
+    pub fn set_morph_targets(
+        &mut self,
+        targets: impl IntoIterator<Item = impl IntoIterator<Item = MorphAttributes>>,
+    ) -> Result<(), MorphBuildError> {
+        self.morph_targets = Some(MorphTargets::new(targets, self.count_vertices())?);
+        Ok(())
+    }
+
+    /// Returns the morph targets of the mesh, if it has any.
+    pub fn morph_targets(&self) -> Option<&MorphTargets> {
+        self.morph_targets.as_ref()
+    }
+
+    /// Names the morph targets of the mesh, such as the expressions of a face, in the same order
+    /// as the targets and their weights.
+    pub fn set_morph_target_names(&mut self, names: Vec<String>) {
+        self.morph_target_names = Some(names);
+    }
+
+    /// Returns the names of the morph targets of the mesh, if they were set.
+    pub fn morph_target_names(&self) -> Option<&[String]> {
+        self.morph_target_names.as_deref()
+    }
+
     /// Computes and returns the vertex data of the mesh as bytes.
     /// Therefore the attributes are located in alphabetical order.
     /// This is used to transform the vertex data into a GPU friendly format.
@@ -286,49 +328,22 @@ impl Mesh {
     ///
     /// This can dramatically increase the vertex count, so make sure this is what you want.
     /// Does nothing if no [Indices] are set.
-    #[allow(clippy::match_same_arms)]
     pub fn duplicate_vertices(&mut self) {
-        fn duplicate<T: Copy>(values: &[T], indices: impl Iterator<Item = usize>) -> Vec<T> {
-            indices.map(|i| values[i]).collect()
-        }
-
         let indices = match self.indices.take() {
             Some(indices) => indices,
             None => return,
         };
+        self.remap_vertices(&indices);
+    }
 
+    /// Replaces the vertices by the ones at the indices, in order.
+    fn remap_vertices(&mut self, indices: &Indices) {
         for attributes in self.attributes.values_mut() {
-            let indices = indices.iter();
-            match &mut attributes.values {
-                VertexAttributeValues::Float32(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint32(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint32(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Float32x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint32x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint32x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Float32x3(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint32x3(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint32x3(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint32x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint32x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Float32x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint16x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Snorm16x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint16x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Unorm16x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint16x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Snorm16x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint16x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Unorm16x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint8x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Snorm8x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint8x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Unorm8x2(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Sint8x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Snorm8x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Uint8x4(vec) => *vec = duplicate(vec, indices),
-                VertexAttributeValues::Unorm8x4(vec) => *vec = duplicate(vec, indices),
-            }
+            attributes.values.remap(indices.iter());
+        }
+
+        if let Some(morph_targets) = &mut self.morph_targets {
+            morph_targets.duplicate_vertices(indices);
         }
     }
 
@@ -361,10 +376,209 @@ impl Mesh {
         self.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     }
 
+    /// Calculates the [`Mesh::ATTRIBUTE_NORMAL`] of a mesh by averaging the normals of the faces
+    /// sharing each vertex, weighted by their area.
+    ///
+    /// # Panics
+    /// Panics if [`Indices`] are not set or [`Mesh::ATTRIBUTE_POSITION`] is not of type `float3`
+    /// or if the mesh has any other topology than [`PrimitiveTopology::TriangleList`].
+    /// Consider calling [`Mesh::compute_flat_normals`] for non-indexed geometry instead.
+    pub fn compute_smooth_normals(&mut self) {
+        assert!(
+            matches!(self.primitive_topology, PrimitiveTopology::TriangleList),
+            "`compute_smooth_normals` can only work on `TriangleList`s"
+        );
+
+        let positions = self
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .expect("`Mesh::ATTRIBUTE_POSITION` vertex attributes should be of type `float3`");
+        let indices: Vec<usize> = self
+            .indices()
+            .expect("`compute_smooth_normals` can only work on indexed geometry. Consider calling `Mesh::compute_flat_normals`.")
+            .iter()
+            .collect();
+
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| Vec3::from(positions[triangle[i]]));
+            // The length of the cross product is twice the area of the face
+            let normal = (b - a).cross(c - a);
+            for &index in triangle {
+                normals[index] += normal;
+            }
+        }
+
+        let normals: Vec<[f32; 3]> = normals
+            .into_iter()
+            .map(|normal| normal.normalize_or_zero().into())
+            .collect();
+        self.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    }
+
+    /// Reverses the winding of the faces of the mesh, so that their fronts become their backs and
+    /// the other way around.
+    ///
+    /// The normals are kept, consider negating them too to turn the mesh inside out.
+    ///
+    /// # Panics
+    /// Panics if the mesh has any other topology than [`PrimitiveTopology::TriangleList`].
+    pub fn flip_winding(&mut self) {
+        assert!(
+            matches!(self.primitive_topology, PrimitiveTopology::TriangleList),
+            "`flip_winding` can only work on `TriangleList`s"
+        );
+
+        fn flip<T>(indices: &mut [T]) {
+            for triangle in indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+
+        match &mut self.indices {
+            Some(Indices::U16(indices)) => flip(indices),
+            Some(Indices::U32(indices)) => flip(indices),
+            None => {
+                let mut indices: Vec<u32> = (0..self.count_vertices() as u32).collect();
+                flip(&mut indices);
+                self.remap_vertices(&Indices::U32(indices));
+            }
+        }
+    }
+
+    /// Transforms the vertices of the mesh in place: the positions by the whole transform, and
+    /// the normals and tangents by its rotation and scale, as well as the displacements of its
+    /// morph targets.
+    ///
+    /// A scale that mirrors the mesh also flips the winding of its faces, so that they keep
+    /// facing outwards.
+    ///
+    /// # Panics
+    /// Panics if the scale mirrors a mesh of another topology than
+    /// [`PrimitiveTopology::TriangleList`].
+    pub fn transform_by(&mut self, transform: Transform) {
+        let linear = Mat3::from_quat(transform.rotation) * Mat3::from_diagonal(transform.scale);
+        // Normals are transformed by the inverse transpose to stay perpendicular to the surface
+        let normal_matrix = linear.inverse().transpose();
+
+        if let Some(VertexAttributeValues::Float32x3(positions)) =
+            self.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+        {
+            for position in positions {
+                *position = transform.transform_point(Vec3::from(*position)).into();
+            }
+        }
+        if let Some(VertexAttributeValues::Float32x3(normals)) =
+            self.attribute_mut(Mesh::ATTRIBUTE_NORMAL)
+        {
+            for normal in normals {
+                *normal = (normal_matrix * Vec3::from(*normal))
+                    .normalize_or_zero()
+                    .into();
+            }
+        }
+        let mirrored = linear.determinant() < 0.0;
+        if let Some(VertexAttributeValues::Float32x4(tangents)) =
+            self.attribute_mut(Mesh::ATTRIBUTE_TANGENT)
+        {
+            for tangent in tangents {
+                let direction = (linear * Vec4::from(*tangent).truncate()).normalize_or_zero();
+                // Mirroring changes the handedness of the tangent space
+                let handedness = if mirrored { -tangent[3] } else { tangent[3] };
+                *tangent = direction.extend(handedness).into();
+            }
+        }
+        if let Some(morph_targets) = &mut self.morph_targets {
+            morph_targets.transform_by(linear, normal_matrix);
+        }
+
+        if mirrored {
+            self.flip_winding();
+        }
+    }
+
+    /// Appends the vertices and faces of another mesh, transformed by `transform`, to this one.
+    ///
+    /// This bakes meshes that are always drawn together, such as static level geometry, into a
+    /// single mesh to draw them in one draw call. Both meshes must have the same topology, which
+    /// can't be a strip, and the same vertex attributes. If only one of them is indexed, the
+    /// other one is indexed too.
+    pub fn merge(&mut self, other: &Mesh, transform: Transform) -> Result<(), MergeMeshError> {
+        if self.primitive_topology != other.primitive_topology {
+            return Err(MergeMeshError::IncompatibleTopology(
+                self.primitive_topology,
+                other.primitive_topology,
+            ));
+        }
+        if matches!(
+            self.primitive_topology,
+            PrimitiveTopology::LineStrip | PrimitiveTopology::TriangleStrip
+        ) {
+            return Err(MergeMeshError::UnsupportedTopology(self.primitive_topology));
+        }
+        if self.morph_targets.is_some() || other.morph_targets.is_some() {
+            return Err(MergeMeshError::MorphTargets);
+        }
+        for (id, data) in &self.attributes {
+            match other.attributes.get(id) {
+                Some(other_data) if other_data.attribute.format == data.attribute.format => {}
+                Some(_) => {
+                    return Err(MergeMeshError::IncompatibleVertexAttributeFormat(
+                        data.attribute.name,
+                    ))
+                }
+                None => return Err(MergeMeshError::MissingVertexAttribute(data.attribute.name)),
+            }
+        }
+        if let Some(data) = other
+            .attributes
+            .iter()
+            .find_map(|(id, data)| (!self.attributes.contains_key(id)).then_some(data))
+        {
+            return Err(MergeMeshError::MissingVertexAttribute(data.attribute.name));
+        }
+
+        let mut other = other.clone();
+        other.transform_by(transform);
+
+        let vertex_count = self.count_vertices();
+        let other_vertex_count = other.count_vertices();
+        if self.indices.is_some() || other.indices.is_some() {
+            // Non-indexed meshes draw their vertices in order
+            let indices_of = |mesh: &Mesh, vertex_count: usize| -> Vec<usize> {
+                match mesh.indices() {
+                    Some(indices) => indices.iter().collect(),
+                    None => (0..vertex_count).collect(),
+                }
+            };
+            let other_indices = indices_of(&other, other_vertex_count);
+            let indices = indices_of(self, vertex_count)
+                .into_iter()
+                .chain(other_indices.into_iter().map(|index| index + vertex_count));
+            let fits_u16 = matches!(self.indices, None | Some(Indices::U16(_)))
+                && matches!(other.indices, None | Some(Indices::U16(_)))
+                && vertex_count + other_vertex_count <= u16::MAX as usize + 1;
+            self.indices = Some(if fits_u16 {
+                Indices::U16(indices.map(|index| index as u16).collect())
+            } else {
+                Indices::U32(indices.map(|index| index as u32).collect())
+            });
+        }
+
+        for (id, data) in &mut self.attributes {
+            let appended = data.values.extend(&other.attributes[id].values);
+            debug_assert!(appended, "the formats of the attributes were checked");
+        }
+
+        Ok(())
+    }
+
     /// Generate tangents for the mesh using the `mikktspace` algorithm.
     ///
     /// Sets the [`Mesh::ATTRIBUTE_TANGENT`] attribute if successful.
     /// Requires a [`PrimitiveTopology::TriangleList`] topology and the [`Mesh::ATTRIBUTE_POSITION`], [`Mesh::ATTRIBUTE_NORMAL`] and [`Mesh::ATTRIBUTE_UV_0`] attributes set.
+    /// The mesh may be indexed or not, such as after [`Mesh::duplicate_vertices`].
     pub fn generate_tangents(&mut self) -> Result<(), GenerateTangentsError> {
         let tangents = generate_tangents_for_mesh(self)?;
         self.insert_attribute(Mesh::ATTRIBUTE_TANGENT, tangents);
@@ -696,6 +910,67 @@ impl VertexAttributeValues {
             VertexAttributeValues::Unorm8x4(values) => cast_slice(values),
         }
     }
+
+    /// Replaces the values by the ones at the indices, in order.
+    #[allow(clippy::match_same_arms)]
+    fn remap(&mut self, indices: impl Iterator<Item = usize>) {
+        fn duplicate<T: Copy>(values: &[T], indices: impl Iterator<Item = usize>) -> Vec<T> {
+            indices.map(|i| values[i]).collect()
+        }
+
+        match self {
+            VertexAttributeValues::Float32(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint32(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint32(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Float32x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint32x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint32x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Float32x3(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint32x3(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint32x3(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint32x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint32x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Float32x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint16x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Snorm16x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint16x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Unorm16x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint16x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Snorm16x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint16x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Unorm16x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint8x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Snorm8x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint8x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Unorm8x2(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Sint8x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Snorm8x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Uint8x4(vec) => *vec = duplicate(vec, indices),
+            VertexAttributeValues::Unorm8x4(vec) => *vec = duplicate(vec, indices),
+        }
+    }
+
+    /// Appends the values of another attribute, returning `false` if their formats differ.
+    fn extend(&mut self, other: &VertexAttributeValues) -> bool {
+        macro_rules! extend {
+            ($($variant:ident),*) => {
+                match (self, other) {
+                    $((VertexAttributeValues::$variant(vec), VertexAttributeValues::$variant(other)) => {
+                        vec.extend_from_slice(other);
+                    })*
+                    _ => return false,
+                }
+            };
+        }
+
+        extend!(
+            Float32, Sint32, Uint32, Float32x2, Sint32x2, Uint32x2, Float32x3, Sint32x3, Uint32x3,
+            Sint32x4, Uint32x4, Float32x4, Sint16x2, Snorm16x2, Uint16x2, Unorm16x2, Sint16x4,
+            Snorm16x4, Uint16x4, Unorm16x4, Sint8x2, Snorm8x2, Uint8x2, Unorm8x2, Sint8x4,
+            Snorm8x4, Uint8x4, Unorm8x4
+        );
+        true
+    }
 }
 
 impl From<&VertexAttributeValues> for VertexFormat {
@@ -804,22 +1079,24 @@ impl From<&Indices> for IndexFormat {
 }
 
 /// The GPU-representation of a [`Mesh`].
-/// Consists of a vertex data buffer and an optional index data buffer.
+/// Consists of a range of vertex data and an optional range of index data in the buffers of the
+/// [`MeshBufferArena`](crate::mesh::MeshBufferArena).
 #[derive(Debug, Clone)]
 pub struct GpuMesh {
-    /// Contains all attribute data for each vertex.
-    pub vertex_buffer: Buffer,
+    /// The ranges of the vertex and index data of the mesh, which are looked up with
+    /// [`MeshBufferArena::get`](crate::mesh::MeshBufferArena::get) to draw it.
+    pub allocation: MeshAllocation,
     pub buffer_info: GpuBufferInfo,
     pub primitive_topology: PrimitiveTopology,
     pub layout: MeshVertexBufferLayout,
+    /// The texture of the [`MorphTargets`] of the mesh, if it has any.
+    pub morph_targets: Option<TextureView>,
 }
 
 /// The index/vertex buffer info of a [`GpuMesh`].
 #[derive(Debug, Clone)]
 pub enum GpuBufferInfo {
     Indexed {
-        /// Contains all index data of a mesh.
-        buffer: Buffer,
         count: u32,
         index_format: IndexFormat,
     },
@@ -831,7 +1108,11 @@ pub enum GpuBufferInfo {
 impl RenderAsset for Mesh {
     type ExtractedAsset = Mesh;
     type PreparedAsset = GpuMesh;
-    type Param = SRes<RenderDevice>;
+    type Param = (
+        SRes<RenderDevice>,
+        SRes<RenderQueue>,
+        SResMut<MeshBufferArena>,
+    );
 
     /// Clones the mesh.
     fn extract_asset(&self) -> Self::ExtractedAsset {
@@ -841,43 +1122,48 @@ impl RenderAsset for Mesh {
     /// Converts the extracted mesh a into [`GpuMesh`].
     fn prepare_asset(
         mesh: Self::ExtractedAsset,
-        render_device: &mut SystemParamItem<Self::Param>,
+        (render_device, render_queue, arena): &mut SystemParamItem<Self::Param>,
     ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
         let vertex_buffer_data = mesh.get_vertex_buffer_data();
-        let vertex_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
-            usage: BufferUsages::VERTEX,
-            label: Some("Mesh Vertex Buffer"),
-            contents: &vertex_buffer_data,
-        });
+        let mesh_vertex_buffer_layout = mesh.get_mesh_vertex_buffer_layout();
+        let indices = mesh.indices();
+        let allocation = arena.allocate(
+            render_device,
+            render_queue,
+            &vertex_buffer_data,
+            mesh_vertex_buffer_layout.layout().array_stride,
+            mesh.get_index_buffer_bytes()
+                .zip(indices.map(IndexFormat::from)),
+        );
 
-        let buffer_info = mesh.get_index_buffer_bytes().map_or(
-            GpuBufferInfo::NonIndexed {
-                vertex_count: mesh.count_vertices() as u32,
+        let buffer_info = match indices {
+            Some(indices) => GpuBufferInfo::Indexed {
+                count: indices.len() as u32,
+                index_format: indices.into(),
             },
-            |data| GpuBufferInfo::Indexed {
-                buffer: render_device.create_buffer_with_data(&BufferInitDescriptor {
-                    usage: BufferUsages::INDEX,
-                    contents: data,
-                    label: Some("Mesh Index Buffer"),
-                }),
-                count: mesh.indices().unwrap().len() as u32,
-                index_format: mesh.indices().unwrap().into(),
+            None => GpuBufferInfo::NonIndexed {
+                vertex_count: mesh.count_vertices() as u32,
             },
-        );
+        };
 
-        let mesh_vertex_buffer_layout = mesh.get_mesh_vertex_buffer_layout();
+        let morph_targets = mesh.morph_targets().map(|targets| {
+            targets
+                .create_texture(render_device, render_queue)
+                .create_view(&TextureViewDescriptor::default())
+        });
 
         Ok(GpuMesh {
-            vertex_buffer,
+            allocation,
             buffer_info,
             primitive_topology: mesh.primitive_topology(),
             layout: mesh_vertex_buffer_layout,
+            morph_targets,
         })
     }
 }
 
 struct MikktspaceGeometryHelper<'a> {
-    indices: &'a Indices,
+    indices: Option<&'a Indices>,
     positions: &'a Vec<[f32; 3]>,
     normals: &'a Vec<[f32; 3]>,
     uvs: &'a Vec<[f32; 2]>,
@@ -889,15 +1175,18 @@ impl MikktspaceGeometryHelper<'_> {
         let index_index = face * 3 + vert;
 
         match self.indices {
-            Indices::U16(indices) => indices[index_index] as usize,
-            Indices::U32(indices) => indices[index_index] as usize,
+            Some(Indices::U16(indices)) => indices[index_index] as usize,
+            Some(Indices::U32(indices)) => indices[index_index] as usize,
+            None => index_index,
         }
     }
 }
 
 impl bevy_mikktspace::Geometry for MikktspaceGeometryHelper<'_> {
     fn num_faces(&self) -> usize {
-        self.indices.len() / 3
+        self.indices
+            .map_or(self.positions.len(), |indices| indices.len())
+            / 3
     }
 
     fn num_vertices_of_face(&self, _: usize) -> usize {
@@ -927,6 +1216,9 @@ impl bevy_mikktspace::Geometry for MikktspaceGeometryHelper<'_> {
 pub enum GenerateTangentsError {
     #[error("cannot generate tangents for {0:?}")]
     UnsupportedTopology(PrimitiveTopology),
+    #[deprecated(
+        note = "tangents are generated for meshes without indices too, so this error isn't returned anymore"
+    )]
     #[error("missing indices")]
     MissingIndices,
     #[error("missing vertex attributes '{0}'")]
@@ -937,6 +1229,21 @@ pub enum GenerateTangentsError {
     MikktspaceError,
 }
 
+#[derive(thiserror::Error, Debug)]
+/// Failed to merge a mesh into another one with [`Mesh::merge`].
+pub enum MergeMeshError {
+    #[error("cannot merge a mesh with a {1:?} topology into a mesh with a {0:?} topology")]
+    IncompatibleTopology(PrimitiveTopology, PrimitiveTopology),
+    #[error("cannot merge meshes with a {0:?} topology")]
+    UnsupportedTopology(PrimitiveTopology),
+    #[error("the '{0}' vertex attribute is missing from one of the meshes")]
+    MissingVertexAttribute(&'static str),
+    #[error("the '{0}' vertex attribute has different formats in the meshes")]
+    IncompatibleVertexAttributeFormat(&'static str),
+    #[error("cannot merge meshes with morph targets")]
+    MorphTargets,
+}
+
 fn generate_tangents_for_mesh(mesh: &Mesh) -> Result<Vec<[f32; 4]>, GenerateTangentsError> {
     match mesh.primitive_topology() {
         PrimitiveTopology::TriangleList => {}
@@ -976,9 +1283,7 @@ fn generate_tangents_for_mesh(mesh: &Mesh) -> Result<Vec<[f32; 4]>, GenerateTang
             ))
         }
     };
-    let indices = mesh
-        .indices()
-        .ok_or(GenerateTangentsError::MissingIndices)?;
+    let indices = mesh.indices();
 
     let len = positions.len();
     let tangents = vec![[0., 0., 0., 0.]; len];
@@ -1004,8 +1309,16 @@ fn generate_tangents_for_mesh(mesh: &Mesh) -> Result<Vec<[f32; 4]>, GenerateTang
 
 #[cfg(test)]
 mod tests {
-    use super::Mesh;
-    use wgpu::PrimitiveTopology;
+    use super::{
+        GenerateTangentsError, Indices, MergeMeshError, Mesh, MeshVertexAttribute,
+        VertexAttributeValues,
+    };
+    use bevy_math::Vec3;
+    use bevy_transform::components::Transform;
+    use wgpu::{PrimitiveTopology, VertexFormat};
+
+    const ATTRIBUTE_CUSTOM: MeshVertexAttribute =
+        MeshVertexAttribute::new("Vertex_Custom", 988540917, VertexFormat::Float32);
 
     #[test]
     #[should_panic]
@@ -1013,4 +1326,198 @@ mod tests {
         let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
         mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0, 0.0]]);
     }
+
+    #[test]
+    fn custom_attribute_layout() {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 3]);
+        mesh.insert_attribute(ATTRIBUTE_CUSTOM, vec![1.0, 2.0, 3.0]);
+        let layout = mesh.get_mesh_vertex_buffer_layout();
+        assert!(layout.contains(ATTRIBUTE_CUSTOM));
+
+        // The attributes are mapped to the shader locations the pipeline asks for, in any order
+        let vertex_buffer_layout = layout
+            .get_layout(&[
+                ATTRIBUTE_CUSTOM.at_shader_location(0),
+                Mesh::ATTRIBUTE_POSITION.at_shader_location(7),
+            ])
+            .unwrap();
+        assert_eq!(vertex_buffer_layout.array_stride, 16);
+        let attributes = &vertex_buffer_layout.attributes;
+        assert_eq!(attributes[0].shader_location, 0);
+        assert_eq!(attributes[0].format, VertexFormat::Float32);
+        assert_eq!(attributes[0].offset, 12);
+        assert_eq!(attributes[1].shader_location, 7);
+        assert_eq!(attributes[1].format, VertexFormat::Float32x3);
+        assert_eq!(attributes[1].offset, 0);
+    }
+
+    /// Builds a quad in the XY plane facing +Z, with its UVs mapped so that U grows along +X and V
+    /// along -Y.
+    fn quad_with_uvs() -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 4]);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+        );
+        mesh.set_indices(Some(Indices::U32(vec![0, 1, 2, 0, 2, 3])));
+        mesh
+    }
+
+    fn assert_tangents_along_x(mesh: &Mesh) {
+        let Some(VertexAttributeValues::Float32x4(tangents)) =
+            mesh.attribute(Mesh::ATTRIBUTE_TANGENT)
+        else {
+            panic!("the tangents should have been generated");
+        };
+        for tangent in tangents {
+            assert!((tangent[0] - 1.0).abs() < 1e-5, "{tangent:?}");
+            assert!(
+                tangent[1].abs() < 1e-5 && tangent[2].abs() < 1e-5,
+                "{tangent:?}"
+            );
+            assert_eq!(tangent[3], 1.0);
+        }
+    }
+
+    #[test]
+    fn generate_tangents_indexed() {
+        let mut mesh = quad_with_uvs();
+        mesh.generate_tangents().unwrap();
+        assert_tangents_along_x(&mesh);
+    }
+
+    #[test]
+    fn generate_tangents_non_indexed() {
+        let mut mesh = quad_with_uvs();
+        mesh.duplicate_vertices();
+        mesh.generate_tangents().unwrap();
+        assert_eq!(mesh.count_vertices(), 6);
+        assert_tangents_along_x(&mesh);
+    }
+
+    #[test]
+    fn generate_tangents_missing_uvs() {
+        let mut mesh = quad_with_uvs();
+        mesh.remove_attribute(Mesh::ATTRIBUTE_UV_0);
+        assert!(matches!(
+            mesh.generate_tangents(),
+            Err(GenerateTangentsError::MissingVertexAttribute(_))
+        ));
+    }
+
+    fn positions(mesh: &Mesh) -> &[[f32; 3]] {
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+    }
+
+    fn normals(mesh: &Mesh) -> &[[f32; 3]] {
+        mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+    }
+
+    #[test]
+    fn merge_meshes() {
+        let mut mesh = quad_with_uvs();
+        let mut other = quad_with_uvs();
+        other.duplicate_vertices();
+        mesh.merge(&other, Transform::from_xyz(0.0, 0.0, 2.0))
+            .unwrap();
+
+        assert_eq!(mesh.count_vertices(), 10);
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(matches!(mesh.indices(), Some(Indices::U32(_))));
+        assert_eq!(positions(&mesh)[4], [0.0, 0.0, 2.0]);
+        assert_eq!(positions(&mesh)[6], [1.0, 1.0, 2.0]);
+
+        let mut missing_uvs = quad_with_uvs();
+        missing_uvs.remove_attribute(Mesh::ATTRIBUTE_UV_0);
+        assert!(matches!(
+            mesh.merge(&missing_uvs, Transform::IDENTITY),
+            Err(MergeMeshError::MissingVertexAttribute(_))
+        ));
+        assert!(matches!(
+            mesh.merge(&Mesh::new(PrimitiveTopology::LineList), Transform::IDENTITY),
+            Err(MergeMeshError::IncompatibleTopology(..))
+        ));
+    }
+
+    #[test]
+    fn transform_mesh() {
+        let mut mesh = quad_with_uvs();
+        mesh.generate_tangents().unwrap();
+        mesh.transform_by(Transform::from_xyz(1.0, 0.0, 0.0).with_scale(Vec3::new(2.0, 1.0, 1.0)));
+        assert_eq!(positions(&mesh)[2], [3.0, 1.0, 0.0]);
+        assert_eq!(normals(&mesh)[0], [0.0, 0.0, 1.0]);
+
+        // Mirroring along Z turns the quad around, which keeps facing its normal
+        mesh.transform_by(Transform::from_scale(Vec3::new(1.0, 1.0, -1.0)));
+        assert_eq!(normals(&mesh)[0], [0.0, 0.0, -1.0]);
+        let indices: Vec<usize> = mesh.indices().unwrap().iter().collect();
+        assert_eq!(indices, [0, 2, 1, 0, 3, 2]);
+        let Some(VertexAttributeValues::Float32x4(tangents)) =
+            mesh.attribute(Mesh::ATTRIBUTE_TANGENT)
+        else {
+            panic!("the tangents should have been kept");
+        };
+        assert_eq!(tangents[0], [1.0, 0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn flip_winding_non_indexed() {
+        let mut mesh = quad_with_uvs();
+        mesh.duplicate_vertices();
+        mesh.flip_winding();
+        assert_eq!(positions(&mesh)[1], [1.0, 1.0, 0.0]);
+        assert_eq!(positions(&mesh)[2], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn smooth_normals() {
+        // Two faces folded along their shared edge on the Y axis, facing +X+Z and -X+Z
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 0.0, -1.0],
+                [-1.0, 0.0, -1.0],
+            ],
+        );
+        mesh.set_indices(Some(Indices::U16(vec![0, 2, 1, 0, 1, 3])));
+        mesh.compute_smooth_normals();
+
+        let normals = normals(&mesh);
+        assert!((Vec3::from(normals[0]) - Vec3::Z).length() < 1e-5);
+        assert!((Vec3::from(normals[1]) - Vec3::Z).length() < 1e-5);
+        let side = Vec3::new(1.0, 0.0, 1.0).normalize();
+        assert!((Vec3::from(normals[2]) - side).length() < 1e-5);
+    }
+
+    #[test]
+    fn missing_attribute_layout() {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 3]);
+        let layout = mesh.get_mesh_vertex_buffer_layout();
+        assert!(!layout.contains(ATTRIBUTE_CUSTOM));
+        assert!(layout
+            .get_layout(&[ATTRIBUTE_CUSTOM.at_shader_location(0)])
+            .is_err());
+    }
 }
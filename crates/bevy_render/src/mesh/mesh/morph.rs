@@ -0,0 +1,298 @@
+use crate::{
+    render_resource::{Extent3d, Texture, TextureDimension, TextureFormat},
+    renderer::{RenderDevice, RenderQueue},
+};
+use bevy_asset::Handle;
+use bevy_core::cast_slice;
+use bevy_ecs::prelude::*;
+use bevy_hierarchy::Children;
+use bevy_math::{Mat3, Vec3};
+use bevy_reflect::Reflect;
+use thiserror::Error;
+use wgpu::{TextureDescriptor, TextureUsages};
+
+use super::{Indices, Mesh};
+
+/// The maximum number of morph targets of a [`Mesh`], and of weights of a [`MorphWeights`].
+pub const MAX_MORPH_WEIGHTS: usize = 64;
+
+/// The number of floats of the [`MorphAttributes`] of each vertex in the morph target textures.
+const COMPONENTS_PER_VERTEX: usize = 9;
+/// The width of the morph target textures, which wrap the vertices of a target over as many
+/// rows as needed. This and their height are the maximum size of 2D textures allowed by `WebGL2`.
+const MAX_TEXTURE_SIZE: usize = 2048;
+
+#[derive(Error, Clone, Debug)]
+pub enum MorphBuildError {
+    #[error("Too many morph targets or weights: {count}, the maximum is {MAX_MORPH_WEIGHTS}")]
+    TooManyTargets { count: usize },
+    #[error("Morph target {target} displaces {count} vertices, but the mesh has {vertex_count}")]
+    VertexCountMismatch {
+        target: usize,
+        count: usize,
+        vertex_count: usize,
+    },
+    #[error("Too many vertices for morph targets: {vertex_count}, the maximum is {}", MAX_TEXTURE_SIZE * MAX_TEXTURE_SIZE / COMPONENTS_PER_VERTEX)]
+    TooManyVertices { vertex_count: usize },
+}
+
+/// The displacement of a vertex of a [`Mesh`] by one of its morph targets, at a weight of `1.0`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MorphAttributes {
+    /// Added to the [`Mesh::ATTRIBUTE_POSITION`] of the vertex.
+    pub position: Vec3,
+    /// Added to the [`Mesh::ATTRIBUTE_NORMAL`] of the vertex, before it is renormalized.
+    pub normal: Vec3,
+    /// Added to the `xyz` of the [`Mesh::ATTRIBUTE_TANGENT`] of the vertex.
+    pub tangent: Vec3,
+}
+
+impl MorphAttributes {
+    pub fn new(position: Vec3, normal: Vec3, tangent: Vec3) -> Self {
+        Self {
+            position,
+            normal,
+            tangent,
+        }
+    }
+}
+
+/// The morph targets (also called blend shapes) of a [`Mesh`], which deform it by the
+/// [`MorphWeights`] of its entity, for example to animate the expressions of a face.
+///
+/// They are set with [`Mesh::set_morph_targets`], and stored in a texture on the GPU, from which
+/// the vertex shaders of the mesh blend them.
+#[derive(Clone, Debug)]
+pub struct MorphTargets {
+    /// The attributes of every vertex of the first target, then of the second one, etc.
+    attributes: Vec<MorphAttributes>,
+    vertex_count: usize,
+}
+
+impl MorphTargets {
+    /// Collects the `targets` of a mesh with this `vertex_count`, each yielding the displacement
+    /// of every vertex in order.
+    pub fn new(
+        targets: impl IntoIterator<Item = impl IntoIterator<Item = MorphAttributes>>,
+        vertex_count: usize,
+    ) -> Result<Self, MorphBuildError> {
+        if vertex_count * COMPONENTS_PER_VERTEX > MAX_TEXTURE_SIZE * MAX_TEXTURE_SIZE {
+            return Err(MorphBuildError::TooManyVertices { vertex_count });
+        }
+
+        let mut attributes = Vec::new();
+        let mut target_count = 0;
+        for (target, displacements) in targets.into_iter().enumerate() {
+            let start = attributes.len();
+            attributes.extend(displacements);
+            let count = attributes.len() - start;
+            if count != vertex_count {
+                return Err(MorphBuildError::VertexCountMismatch {
+                    target,
+                    count,
+                    vertex_count,
+                });
+            }
+            target_count += 1;
+        }
+        if target_count > MAX_MORPH_WEIGHTS {
+            return Err(MorphBuildError::TooManyTargets {
+                count: target_count,
+            });
+        }
+
+        Ok(Self {
+            attributes,
+            vertex_count,
+        })
+    }
+
+    /// The number of morph targets, which is also the number of weights they are blended with.
+    pub fn target_count(&self) -> usize {
+        if self.vertex_count == 0 {
+            0
+        } else {
+            self.attributes.len() / self.vertex_count
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// The displacements of the vertices by the target at this `index`.
+    pub fn target(&self, index: usize) -> Option<&[MorphAttributes]> {
+        let start = index * self.vertex_count;
+        self.attributes.get(start..start + self.vertex_count)
+    }
+
+    /// Duplicates the displacements of the vertices like [`Mesh::duplicate_vertices`].
+    pub(crate) fn duplicate_vertices(&mut self, indices: &Indices) {
+        self.attributes = (0..self.target_count())
+            .flat_map(|target| {
+                let displacements = self.target(target).unwrap();
+                indices.iter().map(|index| displacements[index])
+            })
+            .collect();
+        self.vertex_count = indices.len();
+    }
+
+    /// Transforms the displacements like [`Mesh::transform_by`] transforms the vertices, by the
+    /// rotation and scale of the transform and the inverse transpose of that for the normals.
+    pub(crate) fn transform_by(&mut self, linear: Mat3, normal_matrix: Mat3) {
+        // The normals of the mesh are renormalized after being transformed while the
+        // displacements can't be, so they are scaled back by the average scale instead
+        let normal_matrix = normal_matrix * linear.determinant().abs().cbrt();
+        for attributes in &mut self.attributes {
+            attributes.position = linear * attributes.position;
+            attributes.normal = normal_matrix * attributes.normal;
+            attributes.tangent = linear * attributes.tangent;
+        }
+    }
+
+    /// Creates the texture the vertex shaders read the targets from.
+    ///
+    /// It is a 2D array with a layer per target, each storing the [`MorphAttributes`] of the
+    /// vertices as consecutive floats, wrapped over rows of the width of the texture.
+    pub fn create_texture(
+        &self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+    ) -> Texture {
+        let components = (self.vertex_count * COMPONENTS_PER_VERTEX).max(1);
+        let width = components.min(MAX_TEXTURE_SIZE);
+        let height = (components + width - 1) / width;
+        let layer_size = width * height;
+
+        let mut data = vec![0.0f32; layer_size * self.target_count().max(1)];
+        for (target, layer) in (0..self.target_count()).zip(data.chunks_exact_mut(layer_size)) {
+            let floats = self.target(target).unwrap().iter().flat_map(|attributes| {
+                [attributes.position, attributes.normal, attributes.tangent]
+                    .into_iter()
+                    .flat_map(|vector| vector.to_array())
+            });
+            for (value, float) in layer.iter_mut().zip(floats) {
+                *value = float;
+            }
+        }
+
+        render_device.create_texture_with_data(
+            render_queue,
+            &TextureDescriptor {
+                label: Some("morph_targets_texture"),
+                size: Extent3d {
+                    width: width as u32,
+                    height: height as u32,
+                    depth_or_array_layers: self.target_count().max(1) as u32,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R32Float,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            },
+            cast_slice(&data),
+        )
+    }
+}
+
+/// The weights of the morph targets of the meshes of an entity, usually animated.
+///
+/// The weights are copied to the [`MeshMorphWeights`] of the children of the entity every frame
+/// they change, as models imported from glTF have one mesh entity per primitive of a mesh,
+/// whose common weights are animated on their parent.
+#[derive(Component, Debug, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct MorphWeights {
+    weights: Vec<f32>,
+}
+
+impl MorphWeights {
+    pub fn new(weights: Vec<f32>) -> Result<Self, MorphBuildError> {
+        if weights.len() > MAX_MORPH_WEIGHTS {
+            return Err(MorphBuildError::TooManyTargets {
+                count: weights.len(),
+            });
+        }
+        Ok(Self { weights })
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    /// The weights to modify, whose number can't change as it is checked by [`MorphWeights::new`].
+    pub fn weights_mut(&mut self) -> &mut [f32] {
+        &mut self.weights
+    }
+}
+
+/// The weights of the morph targets of the [`Mesh`] of an entity, which the renderer blends the
+/// targets with.
+///
+/// Each weight scales the displacement of the target at the same index, missing weights being
+/// `0.0`. Add it to entities with a mesh whose weights are set directly, otherwise it is set from
+/// the [`MorphWeights`] of the parent entity.
+#[derive(Component, Debug, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct MeshMorphWeights {
+    weights: Vec<f32>,
+}
+
+impl MeshMorphWeights {
+    pub fn new(weights: Vec<f32>) -> Result<Self, MorphBuildError> {
+        MorphWeights::new(weights).map(|MorphWeights { weights }| Self { weights })
+    }
+
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn weights_mut(&mut self) -> &mut [f32] {
+        &mut self.weights
+    }
+}
+
+/// Copies the changed [`MorphWeights`] of entities to the [`MeshMorphWeights`] of their mesh
+/// children.
+pub fn inherit_morph_weights(
+    parents: Query<(&Children, &MorphWeights), Changed<MorphWeights>>,
+    mut meshes: Query<&mut MeshMorphWeights, With<Handle<Mesh>>>,
+) {
+    for (children, parent_weights) in &parents {
+        let mut iter = meshes.iter_many_mut(children);
+        while let Some(mut mesh_weights) = iter.fetch_next() {
+            mesh_weights.weights.clear();
+            mesh_weights.weights.extend(&parent_weights.weights);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MorphAttributes, MorphBuildError, MorphTargets, MAX_MORPH_WEIGHTS};
+    use bevy_math::Vec3;
+
+    #[test]
+    fn morph_targets_per_vertex() {
+        let up = MorphAttributes::new(Vec3::Y, Vec3::ZERO, Vec3::ZERO);
+        let targets =
+            MorphTargets::new([vec![up; 3], vec![MorphAttributes::default(); 3]], 3).unwrap();
+        assert_eq!(targets.target_count(), 2);
+        assert_eq!(targets.target(0), Some(&[up; 3][..]));
+        assert_eq!(targets.target(2), None);
+
+        assert!(matches!(
+            MorphTargets::new([vec![up; 3], vec![up; 2]], 3),
+            Err(MorphBuildError::VertexCountMismatch {
+                target: 1,
+                count: 2,
+                vertex_count: 3
+            })
+        ));
+        assert!(matches!(
+            MorphTargets::new(vec![vec![up]; MAX_MORPH_WEIGHTS + 1], 1),
+            Err(MorphBuildError::TooManyTargets { .. })
+        ));
+    }
+}
@@ -9,10 +9,20 @@ use bevy_math::Mat4;
 use bevy_reflect::{Reflect, TypeUuid};
 use std::ops::Deref;
 
+/// Deforms the [`Mesh`](super::Mesh) of an entity with the transforms of its joint entities,
+/// usually the bones of a skeleton moved by an animation.
+///
+/// Each vertex follows the joints of its [`Mesh::ATTRIBUTE_JOINT_INDEX`](super::Mesh::ATTRIBUTE_JOINT_INDEX),
+/// weighted by its [`Mesh::ATTRIBUTE_JOINT_WEIGHT`](super::Mesh::ATTRIBUTE_JOINT_WEIGHT), which
+/// index into the `joints`. The skinning itself runs in the vertex shader of the mesh, with up to
+/// 256 joints per mesh.
 #[derive(Component, Debug, Default, Clone, Reflect)]
 #[reflect(Component, MapEntities)]
 pub struct SkinnedMesh {
+    /// The inverses of the transforms of the `joints` in the pose the mesh was modelled in.
     pub inverse_bindposes: Handle<SkinnedMeshInverseBindposes>,
+    /// The joint entities, whose [`GlobalTransform`](bevy_transform::components::GlobalTransform)
+    /// moves the vertices attached to them.
     pub joints: Vec<Entity>,
 }
 
@@ -26,6 +36,8 @@ impl MapEntities for SkinnedMesh {
     }
 }
 
+/// The inverse bind matrices of the joints of a [`SkinnedMesh`], in the same order as its
+/// `joints`, which can be shared by the meshes of a skeleton.
 #[derive(Debug, TypeUuid)]
 #[uuid = "b9f155a9-54ec-4026-988f-e0a03e99a76f"]
 pub struct SkinnedMeshInverseBindposes(Box<[Mat4]>);
@@ -1,14 +1,21 @@
+mod arena;
+/// Switching the mesh of entities between levels of detail.
+pub mod lod;
 #[allow(clippy::module_inception)]
 mod mesh;
 /// Generation for some primitive shape meshes.
 pub mod shape;
 
+pub use arena::*;
 pub use mesh::*;
 
-use crate::render_asset::RenderAssetPlugin;
-use bevy_app::{App, Plugin};
+use crate::{
+    render_asset::{PrepareAssetLabel, RenderAssetPlugin},
+    RenderApp, RenderStage,
+};
+use bevy_app::{App, CoreStage, Plugin};
 use bevy_asset::AddAsset;
-use bevy_ecs::entity::Entity;
+use bevy_ecs::{entity::Entity, schedule::IntoSystemDescriptor};
 
 /// Adds the [`Mesh`] as an asset and makes sure that they are extracted and prepared for the GPU.
 pub struct MeshPlugin;
@@ -19,6 +26,26 @@ impl Plugin for MeshPlugin {
             .add_asset::<skinning::SkinnedMeshInverseBindposes>()
             .register_type::<skinning::SkinnedMesh>()
             .register_type::<Vec<Entity>>()
+            .register_type::<morph::MorphWeights>()
+            .register_type::<morph::MeshMorphWeights>()
+            .register_type::<Vec<f32>>()
+            .register_type::<lod::Lod>()
+            .register_type::<lod::LodLevel>()
+            .register_type::<lod::LodThreshold>()
+            .register_type::<Vec<lod::LodLevel>>()
+            .add_system_to_stage(CoreStage::PostUpdate, morph::inherit_morph_weights)
+            // NOTE: The fade-out entities need to have been spawned before the visibility and
+            // transform systems run so add as an exclusive system
+            .add_system_to_stage(CoreStage::PostUpdate, lod::update_lods.at_start())
             .add_plugin(RenderAssetPlugin::<Mesh>::default());
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<MeshBufferArena>()
+                .add_system_to_stage(
+                    RenderStage::Prepare,
+                    compact_mesh_buffers.after(PrepareAssetLabel::AssetPrepare),
+                );
+        }
     }
 }
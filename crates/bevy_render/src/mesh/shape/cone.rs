@@ -0,0 +1,100 @@
+use crate::mesh::{Indices, Mesh};
+use bevy_math::Vec3;
+use wgpu::PrimitiveTopology;
+
+/// A cone which stands on the XZ plane, with its tip pointing up the Y axis.
+#[derive(Debug, Clone, Copy)]
+pub struct Cone {
+    /// Radius of the base in the XZ plane.
+    pub radius: f32,
+    /// Height of the cone in the Y axis, from the base to the tip.
+    pub height: f32,
+    /// The number of vertices around the base of the cone.
+    /// A higher number will make it appear more circular.
+    pub resolution: u32,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            height: 1.0,
+            resolution: 32,
+        }
+    }
+}
+
+impl From<Cone> for Mesh {
+    fn from(c: Cone) -> Self {
+        debug_assert!(c.radius > 0.0);
+        debug_assert!(c.height > 0.0);
+        debug_assert!(c.resolution > 2);
+
+        let num_vertices = (c.resolution + 1) + c.resolution + c.resolution;
+        let num_indices = (c.resolution + c.resolution - 2) * 3;
+
+        let mut positions = Vec::with_capacity(num_vertices as usize);
+        let mut normals = Vec::with_capacity(num_vertices as usize);
+        let mut uvs = Vec::with_capacity(num_vertices as usize);
+        let mut indices = Vec::with_capacity(num_indices as usize);
+
+        let step_theta = std::f32::consts::TAU / c.resolution as f32;
+        let half_height = c.height / 2.0;
+        // The normals of the sides lean up by the slope of the cone
+        let side_normal = |theta: f32| {
+            let (sin, cos) = theta.sin_cos();
+            <[f32; 3]>::from(Vec3::new(c.height * cos, c.radius, c.height * sin).normalize())
+        };
+
+        // base ring of the sides
+
+        for segment in 0..=c.resolution {
+            let theta = segment as f32 * step_theta;
+            let (sin, cos) = theta.sin_cos();
+
+            positions.push([c.radius * cos, -half_height, c.radius * sin]);
+            normals.push(side_normal(theta));
+            uvs.push([segment as f32 / c.resolution as f32, 0.0]);
+        }
+
+        // tip, with a vertex per segment so that each one gets the normal of its middle
+
+        let tip_offset = positions.len() as u32;
+        for segment in 0..c.resolution {
+            let theta = (segment as f32 + 0.5) * step_theta;
+
+            positions.push([0.0, half_height, 0.0]);
+            normals.push(side_normal(theta));
+            uvs.push([(segment as f32 + 0.5) / c.resolution as f32, 1.0]);
+        }
+
+        for segment in 0..c.resolution {
+            indices.extend_from_slice(&[segment, tip_offset + segment, segment + 1]);
+        }
+
+        // base
+
+        let base_offset = positions.len() as u32;
+        for i in 0..c.resolution {
+            let theta = i as f32 * step_theta;
+            let (sin, cos) = theta.sin_cos();
+
+            positions.push([cos * c.radius, -half_height, sin * c.radius]);
+            normals.push([0.0, -1.0, 0.0]);
+            uvs.push([0.5 * (cos + 1.0), 1.0 - 0.5 * (sin + 1.0)]);
+        }
+
+        for i in 1..(c.resolution - 1) {
+            indices.extend_from_slice(&[base_offset, base_offset + i, base_offset + i + 1]);
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.generate_tangents()
+            .expect("the cone should have everything needed to generate its tangents");
+        mesh
+    }
+}
@@ -221,16 +221,20 @@ impl From<Plane> for Mesh {
 }
 
 mod capsule;
+mod cone;
 mod cylinder;
 mod icosphere;
 mod regular_polygon;
+mod rounded_box;
 mod torus;
 mod uvsphere;
 
 pub use capsule::{Capsule, CapsuleUvProfile};
+pub use cone::Cone;
 pub use cylinder::Cylinder;
 pub use icosphere::Icosphere;
 pub use regular_polygon::{Circle, RegularPolygon};
+pub use rounded_box::RoundedBox;
 pub use torus::Torus;
 pub use uvsphere::UVSphere;
 use wgpu::PrimitiveTopology;
@@ -0,0 +1,166 @@
+use crate::mesh::{Indices, Mesh};
+use bevy_math::Vec3;
+use wgpu::PrimitiveTopology;
+
+/// A box centered at the origin whose edges and corners are rounded.
+#[derive(Debug, Clone, Copy)]
+pub struct RoundedBox {
+    /// The side lengths of the box along each axis.
+    pub size: Vec3,
+    /// The radius of the rounded edges and corners. It can't be larger than half of the smallest
+    /// side length, in which case the box is fully rounded along that axis.
+    pub radius: f32,
+    /// The number of segments of each rounded edge. A higher number will make them appear
+    /// smoother.
+    pub resolution: u32,
+}
+
+impl RoundedBox {
+    /// Creates a new rounded box centered at the origin with the supplied side lengths and radius.
+    pub fn new(size: Vec3, radius: f32) -> Self {
+        Self {
+            size,
+            radius,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for RoundedBox {
+    fn default() -> Self {
+        Self {
+            size: Vec3::ONE,
+            radius: 0.1,
+            resolution: 4,
+        }
+    }
+}
+
+impl From<RoundedBox> for Mesh {
+    fn from(b: RoundedBox) -> Self {
+        debug_assert!(b.size.min_element() > 0.0);
+        debug_assert!(b.radius > 0.0);
+        debug_assert!(b.resolution > 0);
+
+        let half_size = b.size / 2.0;
+        let radius = b.radius.min(half_size.min_element());
+        // The box the rounded surface is offset from by the radius
+        let inner = half_size - radius;
+
+        // Each face is a grid over the box, with a row of quads for the flat part and the others
+        // bending evenly around its edges. The points of the grid are projected onto the rounded
+        // surface from the closest point of the inner box, along the normal of the surface.
+        let samples = |inner_half_length: f32| {
+            let angle = |i: u32| std::f32::consts::FRAC_PI_4 * i as f32 / b.resolution as f32;
+            let mut samples = Vec::with_capacity(2 * (b.resolution as usize + 1));
+            for i in (0..=b.resolution).rev() {
+                samples.push(-inner_half_length - radius * angle(i).tan());
+            }
+            for i in 0..=b.resolution {
+                samples.push(inner_half_length + radius * angle(i).tan());
+            }
+            samples
+        };
+
+        // The normal, and the axes along which the U and V coordinates of the face grow
+        let faces = [
+            (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+            (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+            (Vec3::Z, Vec3::X, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+        ];
+
+        let side = 2 * (b.resolution + 1);
+        let num_vertices = 6 * side * side;
+        let num_indices = 6 * (side - 1) * (side - 1) * 6;
+
+        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(num_vertices as usize);
+        let mut normals: Vec<[f32; 3]> = Vec::with_capacity(num_vertices as usize);
+        let mut uvs = Vec::with_capacity(num_vertices as usize);
+        let mut indices = Vec::with_capacity(num_indices as usize);
+
+        for (normal, u_axis, v_axis) in faces {
+            let half_u = u_axis.abs().dot(half_size);
+            let half_v = v_axis.abs().dot(half_size);
+            let u_samples = samples(u_axis.abs().dot(inner));
+            let v_samples = samples(v_axis.abs().dot(inner));
+            let offset = positions.len() as u32;
+
+            for v in &v_samples {
+                for u in &u_samples {
+                    let point = normal * normal.abs().dot(half_size) + u_axis * *u + v_axis * *v;
+                    let closest = point.clamp(-inner, inner);
+                    let surface_normal = (point - closest).try_normalize().unwrap_or(normal);
+
+                    positions.push((closest + surface_normal * radius).into());
+                    normals.push(surface_normal.into());
+                    uvs.push([
+                        (u + half_u) / (2.0 * half_u),
+                        1.0 - (v + half_v) / (2.0 * half_v),
+                    ]);
+                }
+            }
+
+            for j in 0..side - 1 {
+                for i in 0..side - 1 {
+                    let a = offset + j * side + i;
+                    let b = a + 1;
+                    let c = b + side;
+                    let d = a + side;
+                    indices.extend_from_slice(&[a, b, c, a, c, d]);
+                }
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.generate_tangents()
+            .expect("the rounded box should have everything needed to generate its tangents");
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoundedBox;
+    use crate::mesh::{Mesh, VertexAttributeValues};
+    use bevy_math::Vec3;
+
+    #[test]
+    fn rounded_box_surface() {
+        let rounded_box = RoundedBox {
+            size: Vec3::new(2.0, 1.0, 0.5),
+            radius: 0.2,
+            resolution: 3,
+        };
+        let mesh = Mesh::from(rounded_box);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("the rounded box should have positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("the rounded box should have normals");
+        };
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_TANGENT).is_some());
+
+        let half_size = rounded_box.size / 2.0;
+        let inner = half_size - rounded_box.radius;
+        for (position, normal) in positions.iter().zip(normals) {
+            let (position, normal) = (Vec3::from(*position), Vec3::from(*normal));
+            assert!(position.abs().cmple(half_size + 1e-5).all(), "{position}");
+            assert!((normal.length() - 1.0).abs() < 1e-5);
+            // Every point is at the radius from the inner box, along its normal
+            let closest = position.clamp(-inner, inner);
+            assert!((position - closest).length() - rounded_box.radius < 1e-5);
+            assert!((position - closest - normal * rounded_box.radius).length() < 1e-4);
+        }
+    }
+}
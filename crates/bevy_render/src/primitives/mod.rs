@@ -1,6 +1,7 @@
-use bevy_ecs::{component::Component, reflect::ReflectComponent};
-use bevy_math::{Mat4, Vec3, Vec3A, Vec4, Vec4Swizzles};
+use bevy_ecs::{component::Component, entity::Entity, reflect::ReflectComponent};
+use bevy_math::{Affine3A, Mat4, Vec3, Vec3A, Vec4, Vec4Swizzles};
 use bevy_reflect::Reflect;
+use bevy_utils::HashMap;
 
 /// An Axis-Aligned Bounding Box
 #[derive(Component, Clone, Debug, Default, Reflect)]
@@ -46,6 +47,27 @@ impl Aabb {
     pub fn max(&self) -> Vec3A {
         self.center + self.half_extents
     }
+
+    /// The smallest sphere containing the box.
+    #[inline]
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere {
+            center: self.center,
+            radius: self.half_extents.length(),
+        }
+    }
+
+    /// The smallest axis-aligned box containing this box once transformed.
+    #[inline]
+    pub fn transformed_by(&self, transform: &Affine3A) -> Aabb {
+        let matrix = transform.matrix3;
+        Aabb {
+            center: transform.transform_point3a(self.center),
+            half_extents: matrix.x_axis.abs() * self.half_extents.x
+                + matrix.y_axis.abs() * self.half_extents.y
+                + matrix.z_axis.abs() * self.half_extents.z,
+        }
+    }
 }
 
 impl From<Sphere> for Aabb {
@@ -58,13 +80,30 @@ impl From<Sphere> for Aabb {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Reflect)]
 pub struct Sphere {
     pub center: Vec3A,
     pub radius: f32,
 }
 
 impl Sphere {
+    /// The smallest sphere containing this sphere once transformed, which is stretched along
+    /// the axis scaled the most.
+    #[inline]
+    pub fn transformed_by(&self, transform: &Affine3A) -> Sphere {
+        let matrix = transform.matrix3;
+        let max_scale = matrix
+            .x_axis
+            .length_squared()
+            .max(matrix.y_axis.length_squared())
+            .max(matrix.z_axis.length_squared())
+            .sqrt();
+        Sphere {
+            center: transform.transform_point3a(self.center),
+            radius: self.radius * max_scale,
+        }
+    }
+
     #[inline]
     pub fn intersects_obb(&self, aabb: &Aabb, local_to_world: &Mat4) -> bool {
         let aabb_center_world = *local_to_world * aabb.center.extend(1.0);
@@ -80,6 +119,33 @@ impl Sphere {
     }
 }
 
+/// The bounds of an entity in world space, computed from its [`Aabb`] and
+/// [`GlobalTransform`](bevy_transform::components::GlobalTransform).
+///
+/// It is added to and kept up to date on the entities with an [`Aabb`] during
+/// [`VisibilitySystems::UpdateWorldBounds`](crate::view::VisibilitySystems::UpdateWorldBounds),
+/// for systems that need to know where entities are in the world, such as picking or spatial
+/// queries.
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct WorldBounds {
+    /// The axis-aligned box containing the entity's transformed [`Aabb`].
+    pub aabb: Aabb,
+    /// The sphere containing the entity's transformed [`Aabb`].
+    pub sphere: Sphere,
+}
+
+impl WorldBounds {
+    /// Computes the bounds of an [`Aabb`] transformed from model to world space.
+    #[inline]
+    pub fn new(aabb: &Aabb, transform: &Affine3A) -> Self {
+        Self {
+            aabb: aabb.transformed_by(transform),
+            sphere: aabb.bounding_sphere().transformed_by(transform),
+        }
+    }
+}
+
 /// A plane defined by a unit normal and distance from the origin along the normal
 /// Any point `p` is in the plane if `n.p + d = 0`
 /// For planes defining half-spaces such as for frusta, if `n.p + d > 0` then `p` is on
@@ -173,7 +239,13 @@ impl Frustum {
     }
 
     #[inline]
-    pub fn intersects_obb(&self, aabb: &Aabb, model_to_world: &Mat4, intersect_far: bool) -> bool {
+    pub fn intersects_obb(
+        &self,
+        aabb: &Aabb,
+        model_to_world: &Mat4,
+        intersect_near: bool,
+        intersect_far: bool,
+    ) -> bool {
         let aabb_center_world = model_to_world.transform_point3a(aabb.center).extend(1.0);
         let axes = [
             Vec3A::from(model_to_world.x_axis),
@@ -181,8 +253,13 @@ impl Frustum {
             Vec3A::from(model_to_world.z_axis),
         ];
 
-        let max = if intersect_far { 6 } else { 5 };
-        for plane in &self.planes[..max] {
+        for (idx, plane) in self.planes.iter().enumerate() {
+            if idx == 4 && !intersect_near {
+                continue;
+            }
+            if idx == 5 && !intersect_far {
+                continue;
+            }
             let p_normal = Vec3A::from(plane.normal_d());
             let relative_radius = aabb.relative_radius(&p_normal, &axes);
             if plane.normal_d().dot(aabb_center_world) + relative_radius <= 0.0 {
@@ -209,6 +286,14 @@ impl CubemapFrusta {
     }
 }
 
+/// The [`Frustum`] of each shadow cascade of a directional light, for each view.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct CascadesFrusta {
+    #[reflect(ignore)]
+    pub frusta: HashMap<Entity, Vec<Frustum>>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,340 @@
+use bevy_math::{Ray, Vec3, Vec3A};
+
+use crate::{
+    mesh::{Mesh, PrimitiveTopology, VertexAttributeValues},
+    primitives::Aabb,
+};
+
+/// The maximum number of triangles in a leaf of a [`MeshBvh`].
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+/// How far outside of a triangle, in barycentric coordinates, a ray still hits it.
+const BARYCENTRIC_TOLERANCE: f32 = 1e-6;
+
+/// A bounding volume hierarchy over the triangles of a [`Mesh`], to find the triangles a ray hits
+/// without testing all of them.
+///
+/// It is a binary tree of boxes, each containing the triangles of its children, which are split
+/// in two halves along their longest axis until only a few remain in each leaf.
+#[derive(Debug, Clone)]
+pub struct MeshBvh {
+    /// The vertices of every triangle, ordered so that the triangles of each leaf are contiguous.
+    triangles: Vec<[Vec3A; 3]>,
+    /// The index of every triangle in the faces of the mesh.
+    triangle_indices: Vec<u32>,
+    /// The nodes of the tree, each followed by its first child.
+    nodes: Vec<BvhNode>,
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    min: Vec3A,
+    max: Vec3A,
+    /// The first triangle of a leaf, or the second child of a branch.
+    index: u32,
+    /// The number of triangles of a leaf, `0` for a branch.
+    triangle_count: u32,
+}
+
+/// The closest intersection of a ray with the triangles of a [`MeshBvh`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeshRayHit {
+    /// The distance from the origin of the ray to the intersection, in multiples of the length of
+    /// its direction.
+    pub distance: f32,
+    /// The index of the triangle hit in the faces of the mesh, which are defined by its
+    /// [`Indices`](crate::mesh::Indices) if it has some.
+    pub triangle: usize,
+    /// The normalized normal of the triangle hit, facing the side its vertices are
+    /// counter-clockwise from.
+    pub normal: Vec3,
+}
+
+impl MeshBvh {
+    /// Builds the hierarchy of the triangles of a mesh.
+    ///
+    /// Returns `None` if the mesh has no triangles to hit: if it has another topology than
+    /// [`PrimitiveTopology::TriangleList`], or no [`Mesh::ATTRIBUTE_POSITION`] of type `float3`.
+    pub fn new(mesh: &Mesh) -> Option<Self> {
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return None;
+        }
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+
+        let vertex = |index: usize| Vec3A::from(positions[index]);
+        let mut triangles: Vec<[Vec3A; 3]> = match mesh.indices() {
+            Some(indices) => {
+                let indices: Vec<usize> = indices.iter().collect();
+                indices
+                    .chunks_exact(3)
+                    .map(|triangle| {
+                        [
+                            vertex(triangle[0]),
+                            vertex(triangle[1]),
+                            vertex(triangle[2]),
+                        ]
+                    })
+                    .collect()
+            }
+            None => (0..positions.len() / 3)
+                .map(|triangle| {
+                    [
+                        vertex(triangle * 3),
+                        vertex(triangle * 3 + 1),
+                        vertex(triangle * 3 + 2),
+                    ]
+                })
+                .collect(),
+        };
+        if triangles.is_empty() {
+            return None;
+        }
+
+        let mut triangle_indices: Vec<u32> = (0..triangles.len() as u32).collect();
+        let mut nodes = Vec::with_capacity(2 * triangles.len() / MAX_LEAF_TRIANGLES + 1);
+        build_node(&mut triangles, &mut triangle_indices, 0, &mut nodes);
+
+        Some(Self {
+            triangles,
+            triangle_indices,
+            nodes,
+        })
+    }
+
+    /// The box containing all the triangles of the mesh.
+    pub fn aabb(&self) -> Aabb {
+        let root = &self.nodes[0];
+        Aabb::from_min_max(root.min.into(), root.max.into())
+    }
+
+    /// Finds the closest triangle the ray hits, on either of its sides.
+    ///
+    /// The direction of the ray doesn't need to be normalized, which lets rays transformed to
+    /// the space of a mesh keep measuring distances in the units of the space they come from.
+    pub fn cast_ray(&self, ray: &Ray) -> Option<MeshRayHit> {
+        let origin = Vec3A::from(ray.origin);
+        let direction = Vec3A::from(ray.direction);
+        let inverse_direction = direction.recip();
+
+        let mut closest: Option<(f32, usize)> = None;
+        let mut stack = Vec::with_capacity(32);
+        stack.push(0);
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_distance = closest.map_or(f32::INFINITY, |(distance, _)| distance);
+            match ray_box_distance(origin, inverse_direction, node.min, node.max) {
+                Some(distance) if distance <= max_distance => {}
+                _ => continue,
+            }
+
+            if node.triangle_count > 0 {
+                let first = node.index as usize;
+                for triangle in first..first + node.triangle_count as usize {
+                    if let Some(distance) =
+                        ray_triangle_distance(origin, direction, &self.triangles[triangle])
+                    {
+                        if distance < closest.map_or(f32::INFINITY, |(distance, _)| distance) {
+                            closest = Some((distance, triangle));
+                        }
+                    }
+                }
+            } else {
+                stack.push(node.index as usize);
+                stack.push(node_index + 1);
+            }
+        }
+
+        closest.map(|(distance, triangle)| {
+            let [a, b, c] = self.triangles[triangle];
+            MeshRayHit {
+                distance,
+                triangle: self.triangle_indices[triangle] as usize,
+                normal: (b - a).cross(c - a).normalize_or_zero().into(),
+            }
+        })
+    }
+}
+
+/// Appends the node containing the triangles to the tree, then its children, and returns its
+/// index.
+fn build_node(
+    triangles: &mut [[Vec3A; 3]],
+    triangle_indices: &mut [u32],
+    first_triangle: usize,
+    nodes: &mut Vec<BvhNode>,
+) -> usize {
+    let (min, max) = triangles.iter().flatten().fold(
+        (Vec3A::splat(f32::MAX), Vec3A::splat(f32::MIN)),
+        |(min, max), vertex| (min.min(*vertex), max.max(*vertex)),
+    );
+    let node_index = nodes.len();
+    nodes.push(BvhNode {
+        min,
+        max,
+        index: first_triangle as u32,
+        triangle_count: triangles.len() as u32,
+    });
+    if triangles.len() <= MAX_LEAF_TRIANGLES {
+        return node_index;
+    }
+
+    // Split the triangles in two halves along the axis their centers are spread the most on
+    let centroid = |triangle: &[Vec3A; 3]| (triangle[0] + triangle[1] + triangle[2]) / 3.0;
+    let (centroid_min, centroid_max) = triangles.iter().map(centroid).fold(
+        (Vec3A::splat(f32::MAX), Vec3A::splat(f32::MIN)),
+        |(min, max), centroid| (min.min(centroid), max.max(centroid)),
+    );
+    let extent = centroid_max - centroid_min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+
+    // The triangle indices are sorted along with the triangles
+    let mut order: Vec<usize> = (0..triangles.len()).collect();
+    let middle = triangles.len() / 2;
+    order.select_nth_unstable_by(middle, |a, b| {
+        centroid(&triangles[*a])[axis].total_cmp(&centroid(&triangles[*b])[axis])
+    });
+    let sorted_triangles: Vec<_> = order.iter().map(|i| triangles[*i]).collect();
+    let sorted_indices: Vec<_> = order.iter().map(|i| triangle_indices[*i]).collect();
+    triangles.copy_from_slice(&sorted_triangles);
+    triangle_indices.copy_from_slice(&sorted_indices);
+
+    let (left_triangles, right_triangles) = triangles.split_at_mut(middle);
+    let (left_indices, right_indices) = triangle_indices.split_at_mut(middle);
+    build_node(left_triangles, left_indices, first_triangle, nodes);
+    let right = build_node(
+        right_triangles,
+        right_indices,
+        first_triangle + middle,
+        nodes,
+    );
+    nodes[node_index].index = right as u32;
+    nodes[node_index].triangle_count = 0;
+    node_index
+}
+
+/// The distance along the ray at which it enters the box, or `0` if it starts inside it.
+#[inline]
+pub(crate) fn ray_box_distance(
+    origin: Vec3A,
+    inverse_direction: Vec3A,
+    min: Vec3A,
+    max: Vec3A,
+) -> Option<f32> {
+    let mut entry = 0.0f32;
+    let mut exit = f32::INFINITY;
+    for axis in 0..3 {
+        if inverse_direction[axis].is_infinite() {
+            // The ray is parallel to the slab, and always either inside or outside of it
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) * inverse_direction[axis];
+        let t2 = (max[axis] - origin[axis]) * inverse_direction[axis];
+        entry = entry.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+    }
+    (exit >= entry).then_some(entry)
+}
+
+/// The distance along the ray at which it hits either side of the triangle, using the
+/// Möller–Trumbore algorithm.
+#[inline]
+fn ray_triangle_distance(origin: Vec3A, direction: Vec3A, [a, b, c]: &[Vec3A; 3]) -> Option<f32> {
+    let edge_1 = *b - *a;
+    let edge_2 = *c - *a;
+    let p = direction.cross(edge_2);
+    let determinant = edge_1.dot(p);
+    if determinant.abs() < f32::EPSILON * edge_1.length() * edge_2.length() {
+        // The ray is parallel to the triangle
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+
+    let to_origin = origin - *a;
+    // The tolerance keeps rays going exactly through shared edges from slipping between the
+    // triangles due to rounding errors
+    let u = to_origin.dot(p) * inverse_determinant;
+    if !(-BARYCENTRIC_TOLERANCE..=1.0 + BARYCENTRIC_TOLERANCE).contains(&u) {
+        return None;
+    }
+    let q = to_origin.cross(edge_1);
+    let v = direction.dot(q) * inverse_determinant;
+    if v < -BARYCENTRIC_TOLERANCE || u + v > 1.0 + BARYCENTRIC_TOLERANCE {
+        return None;
+    }
+
+    let distance = edge_2.dot(q) * inverse_determinant;
+    (distance >= 0.0).then_some(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MeshBvh;
+    use crate::mesh::{shape, Mesh};
+    use bevy_math::{Ray, Vec3};
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let mesh = Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            sectors: 32,
+            stacks: 16,
+        });
+        let bvh = MeshBvh::new(&mesh).unwrap();
+
+        for i in 0..64 {
+            let angle = i as f32 * 0.37;
+            let origin = Vec3::new(angle.cos() * 3.0, (angle * 0.7).sin(), angle.sin() * 3.0);
+            let target = Vec3::new((angle * 1.3).sin() * 0.5, (angle * 0.5).cos() * 0.5, 0.0);
+            let ray = Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            };
+
+            let hit = bvh.cast_ray(&ray).unwrap();
+            // The closest point of the sphere on the ray
+            let along = -ray.origin.dot(ray.direction);
+            let to_center = (ray.get_point(along)).length_squared();
+            let expected = along - (1.0 - to_center).sqrt();
+            assert!(
+                (hit.distance - expected).abs() < 0.05,
+                "{hit:?} vs {expected}"
+            );
+            assert!(hit.normal.dot(ray.direction) < 0.0);
+        }
+
+        let miss = Ray {
+            origin: Vec3::new(0.0, 2.0, 3.0),
+            direction: Vec3::NEG_Z,
+        };
+        assert!(bvh.cast_ray(&miss).is_none());
+    }
+
+    #[test]
+    fn bvh_hits_from_inside() {
+        let mut mesh = Mesh::from(shape::Cube { size: 2.0 });
+        mesh.duplicate_vertices();
+        let bvh = MeshBvh::new(&mesh).unwrap();
+        let hit = bvh
+            .cast_ray(&Ray {
+                origin: Vec3::ZERO,
+                direction: Vec3::X,
+            })
+            .unwrap();
+        assert!((hit.distance - 1.0).abs() < 1e-5);
+        assert_eq!(hit.normal, Vec3::X);
+        assert_eq!(bvh.aabb().half_extents, Vec3::ONE.into());
+    }
+}
@@ -0,0 +1,296 @@
+//! Casting rays against the meshes and bounding boxes of entities on the CPU, to find what is
+//! under the cursor or in the line of sight of something.
+//!
+//! Rays from the cursor are created with [`Camera::viewport_to_world`](crate::camera::Camera::viewport_to_world),
+//! then cast with the [`RayCast`] system parameter.
+
+mod bvh;
+
+pub use bvh::*;
+
+use bevy_app::{App, CoreStage, Plugin};
+use bevy_asset::{AssetEvent, Assets, Handle, HandleId};
+use bevy_ecs::{prelude::*, system::SystemParam};
+use bevy_math::{Ray, Vec3, Vec3A};
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashMap;
+
+use crate::{mesh::Mesh, primitives::Aabb, view::ComputedVisibility};
+
+/// Builds the [`MeshBvh`] of meshes when rays are cast against them, and drops them when the
+/// meshes change.
+pub struct RayCastPlugin;
+
+impl Plugin for RayCastPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeshBvhs>()
+            .add_system_to_stage(CoreStage::PreUpdate, remove_changed_mesh_bvhs);
+    }
+}
+
+/// The [`MeshBvh`] of the meshes rays have been cast against with [`RayCast`], or `None` for the
+/// meshes without triangles.
+#[derive(Resource, Default)]
+pub struct MeshBvhs {
+    bvhs: HashMap<HandleId, Option<MeshBvh>>,
+}
+
+impl MeshBvhs {
+    /// Gets the hierarchy of the triangles of a mesh, building it if it isn't yet.
+    pub fn get_or_build(
+        &mut self,
+        handle: &Handle<Mesh>,
+        meshes: &Assets<Mesh>,
+    ) -> Option<&MeshBvh> {
+        if !self.bvhs.contains_key(&handle.id()) {
+            // The mesh isn't loaded yet, so there is nothing to keep
+            let mesh = meshes.get(handle)?;
+            self.bvhs.insert(handle.id(), MeshBvh::new(mesh));
+        }
+        self.bvhs[&handle.id()].as_ref()
+    }
+}
+
+/// Drops the [`MeshBvh`] of the meshes that were modified or removed, so that they are rebuilt
+/// from their new triangles.
+pub fn remove_changed_mesh_bvhs(
+    mut bvhs: ResMut<MeshBvhs>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+) {
+    for event in mesh_events.iter() {
+        match event {
+            AssetEvent::Modified { handle } | AssetEvent::Removed { handle } => {
+                bvhs.bvhs.remove(&handle.id());
+            }
+            AssetEvent::Created { .. } => {}
+        }
+    }
+}
+
+/// An intersection of a ray with an entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// The distance from the origin of the ray to the intersection.
+    pub distance: f32,
+    /// The intersection in world space.
+    pub point: Vec3,
+    /// The normalized normal of the surface hit in world space, facing the outside of the
+    /// triangle or box hit even when the ray hits it from the inside.
+    pub normal: Vec3,
+    /// The index of the triangle of the mesh hit, or `None` if the [`Aabb`] of the entity was
+    /// hit instead.
+    pub triangle: Option<usize>,
+}
+
+/// A [`SystemParam`] casting rays against the visible entities with a [`Handle<Mesh>`] or an
+/// [`Aabb`].
+///
+/// Rays hit the triangles of each mesh, using a [`MeshBvh`] built the first time a ray is cast
+/// against it, or the [`Aabb`] of the entities whose mesh has no triangles or isn't loaded yet.
+/// Entities with an [`Aabb`] are only tested further when their [`Aabb`] is hit.
+///
+/// ```
+/// # use bevy_ecs::prelude::*;
+/// # use bevy_math::{Ray, Vec3};
+/// # use bevy_render::ray_cast::RayCast;
+/// fn look_ahead(mut ray_cast: RayCast) {
+///     let ray = Ray { origin: Vec3::ZERO, direction: Vec3::NEG_Z };
+///     if let Some((entity, hit)) = ray_cast.cast_ray_closest(ray) {
+///         println!("{entity:?} is {} ahead", hit.distance);
+///     }
+/// }
+/// # bevy_ecs::system::assert_is_system(look_ahead);
+/// ```
+#[derive(SystemParam)]
+pub struct RayCast<'w, 's> {
+    meshes: Res<'w, Assets<Mesh>>,
+    bvhs: ResMut<'w, MeshBvhs>,
+    #[allow(clippy::type_complexity)]
+    targets: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static GlobalTransform,
+            Option<&'static Handle<Mesh>>,
+            Option<&'static Aabb>,
+            Option<&'static ComputedVisibility>,
+        ),
+        Or<(With<Handle<Mesh>>, With<Aabb>)>,
+    >,
+}
+
+impl<'w, 's> RayCast<'w, 's> {
+    /// Finds all the entities the ray hits, sorted from the closest to the farthest.
+    pub fn cast_ray(&mut self, ray: Ray) -> Vec<(Entity, RayHit)> {
+        let mut hits = Vec::new();
+        for target in &self.targets {
+            if let Some(hit) = cast_ray_on(ray, target, &mut self.bvhs, &self.meshes, f32::INFINITY)
+            {
+                hits.push((target.0, hit));
+            }
+        }
+        hits.sort_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Finds the closest entity the ray hits.
+    pub fn cast_ray_closest(&mut self, ray: Ray) -> Option<(Entity, RayHit)> {
+        let mut closest: Option<(Entity, RayHit)> = None;
+        for target in &self.targets {
+            let max_distance = closest.map_or(f32::INFINITY, |(_, hit)| hit.distance);
+            if let Some(hit) = cast_ray_on(ray, target, &mut self.bvhs, &self.meshes, max_distance)
+            {
+                closest = Some((target.0, hit));
+            }
+        }
+        closest
+    }
+}
+
+/// Casts the ray against a single entity, ignoring the hits farther than `max_distance`.
+fn cast_ray_on(
+    ray: Ray,
+    (_, transform, mesh, aabb, visibility): (
+        Entity,
+        &GlobalTransform,
+        Option<&Handle<Mesh>>,
+        Option<&Aabb>,
+        Option<&ComputedVisibility>,
+    ),
+    bvhs: &mut MeshBvhs,
+    meshes: &Assets<Mesh>,
+    max_distance: f32,
+) -> Option<RayHit> {
+    if visibility.map_or(false, |visibility| !visibility.is_visible_in_hierarchy()) {
+        return None;
+    }
+
+    // Keeping the transformed direction unnormalized keeps distances in world units
+    let world_to_local = transform.affine().inverse();
+    let local_ray = Ray {
+        origin: world_to_local.transform_point3(ray.origin),
+        direction: world_to_local.transform_vector3(ray.direction),
+    };
+    let local_normal_to_world = |normal: Vec3| {
+        (world_to_local.matrix3.transpose() * Vec3A::from(normal))
+            .normalize_or_zero()
+            .into()
+    };
+
+    let aabb_distance = match aabb {
+        Some(aabb) => {
+            let distance = bvh::ray_box_distance(
+                local_ray.origin.into(),
+                Vec3A::from(local_ray.direction).recip(),
+                aabb.min(),
+                aabb.max(),
+            )?;
+            if distance > max_distance {
+                return None;
+            }
+            Some(distance)
+        }
+        None => None,
+    };
+
+    if let Some(bvh) = mesh.and_then(|mesh| bvhs.get_or_build(mesh, meshes)) {
+        let hit = bvh.cast_ray(&local_ray)?;
+        return (hit.distance <= max_distance).then(|| RayHit {
+            distance: hit.distance,
+            point: ray.get_point(hit.distance),
+            normal: local_normal_to_world(hit.normal),
+            triangle: Some(hit.triangle),
+        });
+    }
+
+    let (aabb, distance) = aabb.zip(aabb_distance)?;
+    let local_point = Vec3A::from(local_ray.get_point(distance));
+    Some(RayHit {
+        distance,
+        point: ray.get_point(distance),
+        normal: local_normal_to_world(box_face_normal(local_point, aabb)),
+        triangle: None,
+    })
+}
+
+/// The normal of the face of the box closest to a point on its surface, which is the face the
+/// point is the farthest on the axis of, relative to the size of the box.
+fn box_face_normal(point: Vec3A, aabb: &Aabb) -> Vec3 {
+    let offset = (point - aabb.center) / aabb.half_extents;
+    let abs = offset.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        Vec3::X * offset.x.signum()
+    } else if abs.y >= abs.z {
+        Vec3::Y * offset.y.signum()
+    } else {
+        Vec3::Z * offset.z.signum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mesh::shape, view::ComputedVisibility};
+    use bevy_asset::AddAsset;
+    use bevy_ecs::system::SystemState;
+    use bevy_transform::components::Transform;
+
+    #[test]
+    fn ray_cast_entities() {
+        let mut app = App::new();
+        app.add_plugin(bevy_core::CorePlugin::default())
+            .add_plugin(bevy_asset::AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_plugin(RayCastPlugin);
+        let world = &mut app.world;
+        let cube = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Cube { size: 1.0 }.into());
+
+        // A cube scaled along Z, a box without a mesh behind it, and a hidden cube
+        let scaled = world
+            .spawn((
+                cube.clone(),
+                GlobalTransform::from(Transform::from_scale(Vec3::new(1.0, 1.0, 2.0))),
+            ))
+            .id();
+        let aabb = world
+            .spawn((
+                Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5)),
+                GlobalTransform::from_xyz(0.0, 0.0, -3.0),
+            ))
+            .id();
+        world.spawn((
+            cube,
+            GlobalTransform::from_xyz(0.0, 0.0, 2.0),
+            ComputedVisibility::default(),
+        ));
+
+        let mut state = SystemState::<RayCast>::new(world);
+        let mut ray_cast = state.get_mut(world);
+        let ray = Ray {
+            origin: Vec3::new(0.2, 0.1, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+
+        let hits = ray_cast.cast_ray(ray);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].0, scaled);
+        assert!((hits[0].1.distance - 4.0).abs() < 1e-5);
+        assert!((hits[0].1.point - Vec3::new(0.2, 0.1, 1.0)).length() < 1e-5);
+        assert!((hits[0].1.normal - Vec3::Z).length() < 1e-5);
+        assert!(hits[0].1.triangle.is_some());
+        assert_eq!(hits[1].0, aabb);
+        assert!((hits[1].1.distance - 7.5).abs() < 1e-5);
+        assert_eq!(hits[1].1.normal, Vec3::Z);
+        assert_eq!(hits[1].1.triangle, None);
+
+        assert_eq!(ray_cast.cast_ray_closest(ray), Some(hits[0]));
+        let miss = Ray {
+            origin: Vec3::new(0.0, 2.0, 5.0),
+            direction: Vec3::NEG_Z,
+        };
+        assert!(ray_cast.cast_ray(miss).is_empty());
+    }
+}
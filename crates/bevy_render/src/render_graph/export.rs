@@ -0,0 +1,367 @@
+use crate::render_graph::{Edge, NodeId, NodeState, RenderGraph};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Write};
+
+/// A snapshot of the configuration of a [`RenderGraph`]: its nodes, the edges between them and its
+/// sub graphs, returned by [`RenderGraph::describe`].
+///
+/// Everything is sorted by name, so that describing the same graph twice gives the same output,
+/// which can be diffed to find out what a plugin changed. It serializes to JSON with
+/// [`RenderGraphDescription::to_json`], or to the DOT language of Graphviz with
+/// [`RenderGraphDescription::to_dot`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenderGraphDescription {
+    pub nodes: Vec<NodeDescription>,
+    pub edges: Vec<EdgeDescription>,
+    pub sub_graphs: BTreeMap<String, RenderGraphDescription>,
+}
+
+/// A node of a [`RenderGraphDescription`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct NodeDescription {
+    /// The name of the node, or the name of its type if it was added without one.
+    pub name: String,
+    /// The name of the type that implements [`Node`](super::Node).
+    pub type_name: String,
+    pub inputs: Vec<SlotDescription>,
+    pub outputs: Vec<SlotDescription>,
+}
+
+/// An input or output slot of a [`NodeDescription`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct SlotDescription {
+    pub name: String,
+    /// The [`SlotType`](super::SlotType) of the slot.
+    pub slot_type: String,
+}
+
+/// An edge of a [`RenderGraphDescription`], running the `output_node` before the `input_node`.
+///
+/// The slots are set for [`Edge::SlotEdge`]s, and left empty for [`Edge::NodeEdge`]s.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct EdgeDescription {
+    pub output_node: String,
+    pub output_slot: Option<String>,
+    pub input_node: String,
+    pub input_slot: Option<String>,
+}
+
+impl RenderGraph {
+    /// Describes the nodes, edges and sub graphs of this graph, to export them with
+    /// [`RenderGraphDescription::to_json`] or [`RenderGraphDescription::to_dot`].
+    pub fn describe(&self) -> RenderGraphDescription {
+        let node_name = |id: NodeId| {
+            self.get_node_state(id)
+                .map(describe_node_name)
+                .unwrap_or_else(|_| format!("{id:?}"))
+        };
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for node_state in self.iter_nodes() {
+            nodes.push(NodeDescription {
+                name: describe_node_name(node_state),
+                type_name: node_state.type_name.to_string(),
+                inputs: node_state
+                    .input_slots
+                    .iter()
+                    .map(|slot| SlotDescription {
+                        name: slot.name.to_string(),
+                        slot_type: slot.slot_type.to_string(),
+                    })
+                    .collect(),
+                outputs: node_state
+                    .output_slots
+                    .iter()
+                    .map(|slot| SlotDescription {
+                        name: slot.name.to_string(),
+                        slot_type: slot.slot_type.to_string(),
+                    })
+                    .collect(),
+            });
+
+            // Each edge is in the output edges of its output node
+            for edge in node_state.edges.output_edges() {
+                let slot_name = |id: NodeId, index: usize, input: bool| {
+                    let node_state = self.get_node_state(id).ok()?;
+                    let slots = if input {
+                        &node_state.input_slots
+                    } else {
+                        &node_state.output_slots
+                    };
+                    Some(slots.get_slot(index)?.name.to_string())
+                };
+                edges.push(match *edge {
+                    Edge::SlotEdge {
+                        input_node,
+                        input_index,
+                        output_node,
+                        output_index,
+                    } => EdgeDescription {
+                        output_node: node_name(output_node),
+                        output_slot: slot_name(output_node, output_index, false),
+                        input_node: node_name(input_node),
+                        input_slot: slot_name(input_node, input_index, true),
+                    },
+                    Edge::NodeEdge {
+                        input_node,
+                        output_node,
+                    } => EdgeDescription {
+                        output_node: node_name(output_node),
+                        output_slot: None,
+                        input_node: node_name(input_node),
+                        input_slot: None,
+                    },
+                });
+            }
+        }
+        nodes.sort();
+        edges.sort();
+
+        RenderGraphDescription {
+            nodes,
+            edges,
+            sub_graphs: self
+                .iter_sub_graphs()
+                .map(|(name, sub_graph)| (name.to_string(), sub_graph.describe()))
+                .collect(),
+        }
+    }
+}
+
+fn describe_node_name(node_state: &NodeState) -> String {
+    node_state
+        .name
+        .as_deref()
+        .unwrap_or(node_state.type_name)
+        .to_string()
+}
+
+impl RenderGraphDescription {
+    /// Serializes the description to pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a render graph description serializes to JSON")
+    }
+
+    /// Writes the description in the DOT language, which Graphviz renders with for example
+    /// `dot -Tsvg render_graph.dot -o render_graph.svg`.
+    ///
+    /// Each sub graph is drawn in a cluster labelled with its name. The nodes list their input
+    /// slots on their left and their output slots on their right. Slot edges are drawn between
+    /// the slots they connect, and node edges are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph render_graph {\n");
+        dot.push_str("\trankdir=LR;\n");
+        dot.push_str("\tnode [shape=record];\n");
+        self.write_dot(&mut dot, "", 1);
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn write_dot(&self, dot: &mut String, path: &str, depth: usize) {
+        let indent = "\t".repeat(depth);
+        let id = |node: &str| escape_id(&format!("{path}{node}"));
+
+        for node in &self.nodes {
+            let slots = |slots: &[SlotDescription], port: char| {
+                slots
+                    .iter()
+                    .enumerate()
+                    .map(|(index, slot)| {
+                        format!(
+                            "<{port}{index}> {}: {}",
+                            escape_record(&slot.name),
+                            escape_record(&slot.slot_type)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("|")
+            };
+            let _ = writeln!(
+                dot,
+                "{indent}{} [label=\"{{{{{}}}|{}\\n{}|{{{}}}}}\"];",
+                id(&node.name),
+                slots(&node.inputs, 'i'),
+                escape_record(&node.name),
+                escape_record(&node.type_name),
+                slots(&node.outputs, 'o'),
+            );
+        }
+
+        let port = |node: &str, slot: &Option<String>, input: bool| {
+            let slots = self
+                .nodes
+                .iter()
+                .find(|description| description.name == node)
+                .map(|description| {
+                    if input {
+                        &description.inputs
+                    } else {
+                        &description.outputs
+                    }
+                });
+            slot.as_ref()
+                .and_then(|slot| slots?.iter().position(|info| &info.name == slot))
+                .map(|index| format!(":{}{index}", if input { 'i' } else { 'o' }))
+                .unwrap_or_default()
+        };
+        for edge in &self.edges {
+            let attributes = match &edge.input_slot {
+                Some(_) => "",
+                None => " [style=dashed]",
+            };
+            let _ = writeln!(
+                dot,
+                "{indent}{}{} -> {}{}{attributes};",
+                id(&edge.output_node),
+                port(&edge.output_node, &edge.output_slot, false),
+                id(&edge.input_node),
+                port(&edge.input_node, &edge.input_slot, true),
+            );
+        }
+
+        for (name, sub_graph) in &self.sub_graphs {
+            let sub_path = format!("{path}{name}/");
+            let _ = writeln!(
+                dot,
+                "{indent}subgraph {} {{",
+                escape_id(&format!("cluster_{sub_path}"))
+            );
+            let _ = writeln!(dot, "{indent}\tlabel={};", escape_id(name));
+            sub_graph.write_dot(dot, &sub_path, depth + 1);
+            let _ = writeln!(dot, "{indent}}}");
+        }
+    }
+}
+
+/// Quotes an identifier of the DOT language.
+fn escape_id(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escapes the characters that have a meaning in the label of a record node.
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        render_graph::{
+            EdgeDescription, Node, NodeRunError, RenderGraph, RenderGraphContext, SlotInfo,
+            SlotType,
+        },
+        renderer::RenderContext,
+    };
+    use bevy_ecs::world::World;
+
+    struct TestNode {
+        inputs: Vec<SlotInfo>,
+        outputs: Vec<SlotInfo>,
+    }
+
+    impl Node for TestNode {
+        fn input(&self) -> Vec<SlotInfo> {
+            self.inputs.clone()
+        }
+
+        fn output(&self) -> Vec<SlotInfo> {
+            self.outputs.clone()
+        }
+
+        fn run(
+            &self,
+            _: &mut RenderGraphContext,
+            _: &mut RenderContext,
+            _: &World,
+        ) -> Result<(), NodeRunError> {
+            Ok(())
+        }
+    }
+
+    fn test_graph() -> RenderGraph {
+        let mut graph = RenderGraph::default();
+        graph.add_node(
+            "b",
+            TestNode {
+                inputs: vec![SlotInfo::new("view", SlotType::Entity)],
+                outputs: vec![],
+            },
+        );
+        graph.add_node(
+            "a",
+            TestNode {
+                inputs: vec![],
+                outputs: vec![SlotInfo::new("view", SlotType::Entity)],
+            },
+        );
+        graph.add_node(
+            "c",
+            TestNode {
+                inputs: vec![],
+                outputs: vec![],
+            },
+        );
+        graph.add_slot_edge("a", "view", "b", "view");
+        graph.add_node_edge("b", "c");
+
+        let mut sub_graph = RenderGraph::default();
+        sub_graph.add_node(
+            "d",
+            TestNode {
+                inputs: vec![],
+                outputs: vec![],
+            },
+        );
+        graph.add_sub_graph("sub", sub_graph);
+        graph
+    }
+
+    #[test]
+    fn describe() {
+        let description = test_graph().describe();
+        let names: Vec<_> = description
+            .nodes
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(description.nodes[1].inputs[0].slot_type, "Entity");
+        assert_eq!(
+            description.edges,
+            [
+                EdgeDescription {
+                    output_node: "a".to_string(),
+                    output_slot: Some("view".to_string()),
+                    input_node: "b".to_string(),
+                    input_slot: Some("view".to_string()),
+                },
+                EdgeDescription {
+                    output_node: "b".to_string(),
+                    output_slot: None,
+                    input_node: "c".to_string(),
+                    input_slot: None,
+                },
+            ]
+        );
+        assert_eq!(description.sub_graphs["sub"].nodes[0].name, "d");
+        assert_eq!(description, test_graph().describe());
+    }
+
+    #[test]
+    fn to_dot() {
+        let dot = test_graph().describe().to_dot();
+        assert!(dot.starts_with("digraph render_graph {\n"));
+        assert!(dot.contains("\t\"a\":o0 -> \"b\":i0;\n"));
+        assert!(dot.contains("\t\"b\" -> \"c\" [style=dashed];\n"));
+        assert!(dot.contains("\tsubgraph \"cluster_sub/\" {\n"));
+        assert!(dot.contains("\t\t\"sub/d\" [label="));
+    }
+}
@@ -15,7 +15,9 @@ use super::EdgeExistence;
 /// It is a retained and stateless (nodes themselves may have their own internal state) structure,
 /// which can not be modified while it is executed by the graph runner.
 ///
-/// The `RenderGraphRunner` is responsible for executing the entire graph each frame.
+/// The `RenderGraphRunner` is responsible for executing the entire graph each frame. Nodes that
+/// don't depend on each other, and the sub graphs they run, are run in parallel, recording their
+/// commands into separate command encoders which are submitted in the order of the edges.
 ///
 /// It consists of three main components: [`Nodes`](Node), [`Edges`](Edge)
 /// and [`Slots`](super::SlotType).
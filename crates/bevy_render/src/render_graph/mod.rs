@@ -1,11 +1,13 @@
 mod context;
 mod edge;
+mod export;
 mod graph;
 mod node;
 mod node_slot;
 
 pub use context::*;
 pub use edge::*;
+pub use export::*;
 pub use graph::*;
 pub use node::*;
 pub use node_slot::*;
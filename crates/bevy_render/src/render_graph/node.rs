@@ -5,7 +5,7 @@ use crate::{
     },
     renderer::RenderContext,
 };
-use bevy_ecs::world::World;
+use bevy_ecs::{component::Component, entity::Entity, world::World};
 use bevy_utils::Uuid;
 use downcast_rs::{impl_downcast, Downcast};
 use std::{borrow::Cow, fmt::Debug};
@@ -365,3 +365,49 @@ impl Node for RunGraphOnViewNode {
         Ok(())
     }
 }
+
+/// A [`RenderGraph`](super::RenderGraph) [`Node`] that takes a view entity as input and runs the
+/// configured graph once for each of its sub views, like the views of the lights casting shadows
+/// on it, so that the nodes drawing a single view can be reused for all of them.
+///
+/// The sub views are listed by the component `C` of the view. Nothing is run for views without it.
+pub struct RunGraphOnSubViewsNode<C: Component> {
+    graph_name: Cow<'static, str>,
+    sub_views: fn(&C) -> &[Entity],
+}
+
+impl<C: Component> RunGraphOnSubViewsNode<C> {
+    pub const IN_VIEW: &'static str = "view";
+
+    /// Creates a node running the graph `graph_name` with each of the entities returned by
+    /// `sub_views` as input.
+    pub fn new<T: Into<Cow<'static, str>>>(graph_name: T, sub_views: fn(&C) -> &[Entity]) -> Self {
+        Self {
+            graph_name: graph_name.into(),
+            sub_views,
+        }
+    }
+}
+
+impl<C: Component> Node for RunGraphOnSubViewsNode<C> {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Self::IN_VIEW, SlotType::Entity)]
+    }
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        if let Some(component) = world.get::<C>(view_entity) {
+            for sub_view_entity in (self.sub_views)(component) {
+                graph.run_sub_graph(
+                    self.graph_name.clone(),
+                    vec![SlotValue::Entity(*sub_view_entity)],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::render_resource::{BindGroup, BindGroupId, Buffer, ComputePipeline, ComputePipelineId};
+use bevy_utils::tracing::trace;
+use wgpu::ComputePass;
+
+/// Tracks the current [`TrackedComputePass`] state to ensure dispatch calls are valid.
+#[derive(Debug, Default)]
+pub struct ComputeState {
+    pipeline: Option<ComputePipelineId>,
+    bind_groups: Vec<(Option<BindGroupId>, Vec<u32>)>,
+}
+
+impl ComputeState {
+    pub fn set_bind_group(
+        &mut self,
+        index: usize,
+        bind_group: BindGroupId,
+        dynamic_indices: &[u32],
+    ) {
+        if index >= self.bind_groups.len() {
+            self.bind_groups.resize(index + 1, (None, Vec::new()));
+        }
+        self.bind_groups[index].0 = Some(bind_group);
+        self.bind_groups[index].1.clear();
+        self.bind_groups[index].1.extend(dynamic_indices);
+    }
+
+    pub fn is_bind_group_set(
+        &self,
+        index: usize,
+        bind_group: BindGroupId,
+        dynamic_indices: &[u32],
+    ) -> bool {
+        if let Some(current_bind_group) = self.bind_groups.get(index) {
+            current_bind_group.0 == Some(bind_group) && dynamic_indices == current_bind_group.1
+        } else {
+            false
+        }
+    }
+
+    pub fn is_pipeline_set(&self, pipeline: ComputePipelineId) -> bool {
+        self.pipeline == Some(pipeline)
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: ComputePipelineId) {
+        self.pipeline = Some(pipeline);
+    }
+}
+
+/// A [`ComputePass`], which tracks the current pipeline state to avoid redundant state changes.
+/// It is used to set the current [`ComputePipeline`] and [`BindGroups`](BindGroup).
+/// After all requirements are specified, compute work can be dispatched.
+///
+/// Storage buffers and storage textures written by a compute pass can be read by render passes
+/// recorded later into the same [`CommandEncoder`](wgpu::CommandEncoder) (for example by
+/// subsequent render graph nodes). wgpu inserts the required synchronization between the passes.
+pub struct TrackedComputePass<'a> {
+    pass: ComputePass<'a>,
+    state: ComputeState,
+}
+
+impl<'a> TrackedComputePass<'a> {
+    /// Tracks the supplied compute pass.
+    pub fn new(pass: ComputePass<'a>) -> Self {
+        Self {
+            state: ComputeState::default(),
+            pass,
+        }
+    }
+
+    /// Sets the active [`ComputePipeline`].
+    ///
+    /// Subsequent dispatch calls will use the shader of the `pipeline`.
+    pub fn set_compute_pipeline(&mut self, pipeline: &'a ComputePipeline) {
+        trace!("set compute pipeline: {:?}", pipeline);
+        if self.state.is_pipeline_set(pipeline.id()) {
+            return;
+        }
+        self.pass.set_pipeline(pipeline);
+        self.state.set_pipeline(pipeline.id());
+    }
+
+    /// Sets the active [`BindGroup`] for a given bind group index. The bind group layout in the
+    /// active pipeline when any `dispatch()` function is called must match the layout of this `bind group`.
+    pub fn set_bind_group(
+        &mut self,
+        index: usize,
+        bind_group: &'a BindGroup,
+        dynamic_uniform_indices: &[u32],
+    ) {
+        if self
+            .state
+            .is_bind_group_set(index, bind_group.id(), dynamic_uniform_indices)
+        {
+            trace!(
+                "set bind_group {} (already set): {:?} ({:?})",
+                index,
+                bind_group,
+                dynamic_uniform_indices
+            );
+            return;
+        }
+        trace!(
+            "set bind_group {}: {:?} ({:?})",
+            index,
+            bind_group,
+            dynamic_uniform_indices
+        );
+
+        self.pass
+            .set_bind_group(index as u32, bind_group, dynamic_uniform_indices);
+        self.state
+            .set_bind_group(index, bind_group.id(), dynamic_uniform_indices);
+    }
+
+    /// Dispatches compute work operations.
+    ///
+    /// `x`, `y` and `z` denote the number of work groups to dispatch in each dimension.
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        trace!("dispatch workgroups: {} {} {}", x, y, z);
+        self.pass.dispatch_workgroups(x, y, z);
+    }
+
+    /// Dispatches compute work operations, based on the contents of the `indirect_buffer`.
+    ///
+    /// The structure expected in `indirect_buffer` is the following:
+    ///
+    /// ```rust
+    /// #[repr(C)]
+    /// struct DispatchIndirect {
+    ///     x: u32, // The number of work groups in the X dimension.
+    ///     y: u32, // The number of work groups in the Y dimension.
+    ///     z: u32, // The number of work groups in the Z dimension.
+    /// }
+    /// ```
+    pub fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &'a Buffer,
+        indirect_offset: u64,
+    ) {
+        trace!(
+            "dispatch workgroups indirect: {:?} {}",
+            indirect_buffer,
+            indirect_offset
+        );
+        self.pass
+            .dispatch_workgroups_indirect(indirect_buffer, indirect_offset);
+    }
+
+    /// Set push constant data.
+    ///
+    /// `Features::PUSH_CONSTANTS` must be enabled on the device in order to call this function.
+    pub fn set_push_constants(&mut self, offset: u32, data: &[u8]) {
+        trace!(
+            "set push constants: offset: {} data.len: {}",
+            offset,
+            data.len()
+        );
+        self.pass.set_push_constants(offset, data);
+    }
+
+    /// Insert a single debug marker.
+    ///
+    /// This is a GPU debugging feature. This has no effect on the dispatched work itself.
+    pub fn insert_debug_marker(&mut self, label: &str) {
+        trace!("insert debug marker: {}", label);
+        self.pass.insert_debug_marker(label);
+    }
+
+    /// Start a new debug group.
+    ///
+    /// Note that [`push_debug_group`] and [`pop_debug_group`] must always be called in pairs.
+    ///
+    /// This is a GPU debugging feature. This has no effect on the dispatched work itself.
+    ///
+    /// [`push_debug_group`]: TrackedComputePass::push_debug_group
+    /// [`pop_debug_group`]: TrackedComputePass::pop_debug_group
+    pub fn push_debug_group(&mut self, label: &str) {
+        trace!("push_debug_group marker: {}", label);
+        self.pass.push_debug_group(label);
+    }
+
+    /// End the current debug group.
+    ///
+    /// Note that [`push_debug_group`] and [`pop_debug_group`] must always be called in pairs.
+    ///
+    /// This is a GPU debugging feature. This has no effect on the dispatched work itself.
+    ///
+    /// [`push_debug_group`]: TrackedComputePass::push_debug_group
+    /// [`pop_debug_group`]: TrackedComputePass::pop_debug_group
+    pub fn pop_debug_group(&mut self) {
+        trace!("pop_debug_group");
+        self.pass.pop_debug_group();
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
         ShaderStages,
     },
 };
+use bevy_math::UVec2;
 use bevy_utils::tracing::trace;
 use std::ops::Range;
 use wgpu::{IndexFormat, RenderPass};
@@ -511,6 +512,18 @@ impl<'a> TrackedRenderPass<'a> {
         );
     }
 
+    /// Restrict rendering to the rectangle of the given [`Camera`](crate::camera::Camera)
+    /// [`Viewport`], clamped to a render target of `target_size`.
+    ///
+    /// Subsequent draw calls will discard any fragments outside of that viewport, which keeps
+    /// cameras sharing a render target (e.g. split screen) from drawing over each other.
+    pub fn set_camera_scissor_rect(&mut self, viewport: &Viewport, target_size: UVec2) {
+        let min = viewport.physical_position.min(target_size);
+        let max = (viewport.physical_position + viewport.physical_size).min(target_size);
+        let size = max - min;
+        self.set_scissor_rect(min.x, min.y, size.x, size.y);
+    }
+
     /// Insert a single debug marker.
     ///
     /// This is a GPU debugging feature. This has no effect on the rendering itself.
@@ -1,10 +1,17 @@
+mod compute_state;
 mod draw;
 mod draw_state;
 
+pub use compute_state::*;
 pub use draw::*;
 pub use draw_state::*;
 
-use bevy_ecs::prelude::{Component, Query};
+use crate::RenderStage;
+use bevy_app::App;
+use bevy_ecs::{
+    prelude::{Component, Query},
+    system::{Res, Resource},
+};
 
 /// A resource to collect and sort draw requests for specific [`PhaseItems`](PhaseItem).
 #[derive(Component)]
@@ -58,10 +65,57 @@ impl<I: BatchedPhaseItem> RenderPhase<I> {
     }
 }
 
-/// This system sorts all [`RenderPhases`](RenderPhase) for the [`PhaseItem`] type.
-pub fn sort_phase_system<I: PhaseItem>(mut render_phases: Query<&mut RenderPhase<I>>) {
+/// Overrides how the [`RenderPhase`]s of the [`PhaseItem`] type are sorted by the
+/// [`sort_phase_system`], in place of [`PhaseItem::sort`].
+///
+/// This lets a plugin change the order of a phase it doesn't own, for example to draw the opaque
+/// items grouped by pipeline to minimize the state changes instead of front to back. It is set with
+/// [`AddRenderPhase::set_phase_sort`].
+#[derive(Resource)]
+pub struct PhaseSorter<I: PhaseItem> {
+    pub sort: fn(&mut [I]),
+}
+
+impl<I: PhaseItem> Default for PhaseSorter<I> {
+    fn default() -> Self {
+        Self { sort: I::sort }
+    }
+}
+
+/// This system sorts all [`RenderPhases`](RenderPhase) for the [`PhaseItem`] type, with the
+/// [`PhaseSorter`] of the type if there is one.
+pub fn sort_phase_system<I: PhaseItem>(
+    sort: Option<Res<PhaseSorter<I>>>,
+    mut render_phases: Query<&mut RenderPhase<I>>,
+) {
+    let sort = sort.map_or(I::sort as fn(&mut [I]), |sort| sort.sort);
     for mut phase in &mut render_phases {
-        phase.sort();
+        sort(&mut phase.items);
+    }
+}
+
+/// Registers the [`PhaseItem`] types of custom [`RenderPhase`]s in the render app.
+pub trait AddRenderPhase {
+    /// Adds the [`DrawFunctions`] of the [`PhaseItem`] type, and sorts its [`RenderPhase`]s in the
+    /// [`RenderStage::PhaseSort`] with the [`sort_phase_system`].
+    ///
+    /// The phases still need to be added to the views, usually in the
+    /// [`RenderStage::Extract`], and drawn by a node of the render graph.
+    fn add_render_phase<I: PhaseItem>(&mut self) -> &mut Self;
+
+    /// Sorts the [`RenderPhase`]s of the [`PhaseItem`] type with `sort` instead of
+    /// [`PhaseItem::sort`].
+    fn set_phase_sort<I: PhaseItem>(&mut self, sort: fn(&mut [I])) -> &mut Self;
+}
+
+impl AddRenderPhase for App {
+    fn add_render_phase<I: PhaseItem>(&mut self) -> &mut Self {
+        self.init_resource::<DrawFunctions<I>>()
+            .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<I>)
+    }
+
+    fn set_phase_sort<I: PhaseItem>(&mut self, sort: fn(&mut [I])) -> &mut Self {
+        self.insert_resource(PhaseSorter { sort })
     }
 }
 
@@ -80,6 +134,49 @@ mod tests {
 
     use super::*;
 
+    #[derive(Debug, PartialEq)]
+    struct SortedPhaseItem(u32);
+
+    impl PhaseItem for SortedPhaseItem {
+        type SortKey = u32;
+
+        fn sort_key(&self) -> Self::SortKey {
+            self.0
+        }
+
+        fn draw_function(&self) -> DrawFunctionId {
+            unimplemented!();
+        }
+    }
+
+    #[test]
+    fn phase_sort() {
+        use bevy_ecs::{
+            schedule::{Stage, SystemStage},
+            world::World,
+        };
+
+        let mut world = World::new();
+        let view = world
+            .spawn(RenderPhase {
+                items: vec![SortedPhaseItem(2), SortedPhaseItem(0), SortedPhaseItem(1)],
+            })
+            .id();
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(sort_phase_system::<SortedPhaseItem>);
+
+        stage.run(&mut world);
+        let phase = world.get::<RenderPhase<SortedPhaseItem>>(view).unwrap();
+        assert_eq!(phase.items, [0, 1, 2].map(SortedPhaseItem));
+
+        world.insert_resource(PhaseSorter::<SortedPhaseItem> {
+            sort: |items| items.sort_by_key(|item| std::cmp::Reverse(item.0)),
+        });
+        stage.run(&mut world);
+        let phase = world.get::<RenderPhase<SortedPhaseItem>>(view).unwrap();
+        assert_eq!(phase.items, [2, 1, 0].map(SortedPhaseItem));
+    }
+
     #[test]
     fn batching() {
         #[derive(Debug, PartialEq)]
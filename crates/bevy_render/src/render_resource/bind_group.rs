@@ -137,6 +137,66 @@ impl Deref for BindGroup {
 /// | `sampler_type` = "..." | `"filtering"`, `"non_filtering"`, `"comparison"`.                       |  `"filtering"`         |
 /// | `visibility(...)`      | `all`, `none`, or a list-combination of `vertex`, `fragment`, `compute` |   `vertex`, `fragment` |
 ///
+/// * `storage(BINDING_INDEX, arguments)`
+///     * The field will be converted to a shader-compatible type using the [`ShaderType`] trait, written to a [`Buffer`], and bound as a storage buffer.
+///     * It supports an optional `read_only` parameter. Defaults to false if not present.
+///     * If the `buffer` parameter is present, the field is assumed to already be a [`Buffer`] created with [`BufferUsages::STORAGE`](crate::render_resource::BufferUsages::STORAGE),
+///     which will be bound directly instead. This is useful for data that is written by other render systems, such as the results of a compute pass.
+///
+/// | Arguments              | Values                                                                  | Default              |
+/// |------------------------|-------------------------------------------------------------------------|----------------------|
+/// | `visibility(...)`      | `all`, `none`, or a list-combination of `vertex`, `fragment`, `compute` | `vertex`, `fragment` |
+/// | `read_only`            | if present then value is true, otherwise false                          | `false`              |
+/// | `buffer`               | if present then value is true, otherwise false                          | `false`              |
+///
+/// ```
+/// # use bevy_render::{color::Color, render_resource::AsBindGroup};
+/// # use bevy_math::Vec4;
+/// #[derive(AsBindGroup)]
+/// struct CoolMaterial {
+///     #[uniform(0)]
+///     color: Color,
+///     #[storage(1, read_only)]
+///     values: Vec<Vec4>,
+/// }
+/// ```
+///
+/// In WGSL shaders, the storage binding would look like this:
+/// ```wgsl
+/// @group(1) @binding(1)
+/// var<storage> values: array<vec4<f32>>;
+/// ```
+///
+/// * `storage_texture(BINDING_INDEX, arguments)`
+///     * This field's [`Handle<Image>`](bevy_asset::Handle) will be used to look up the matching [`Texture`](crate::render_resource::Texture)
+///     GPU resource, which will be bound as a storage texture in shaders. The field will be assumed to implement [`Into<Option<Handle<Image>>>`].
+///     The [`Image`] must have been created with [`TextureUsages::STORAGE_BINDING`](crate::render_resource::TextureUsages::STORAGE_BINDING)
+///     and the format given in `image_format`. Unlike `texture` fields, a field without an image has no fallback: creating
+///     the bind group returns [`AsBindGroupError::RetryNextUpdate`] until the field has an image and it has loaded.
+///
+/// | Arguments              | Values                                                                  | Default         |
+/// |------------------------|-------------------------------------------------------------------------|-----------------|
+/// | `dimension` = "..."    | `"1d"`, `"2d"`, `"2d_array"`, `"3d"`, `"cube"`, `"cube_array"`          | `"2d"`          |
+/// | `image_format` = "..." | any [`TextureFormat`](crate::render_resource::TextureFormat) variant    | `"Rgba8Unorm"`  |
+/// | `access` = "..."       | `"read_only"`, `"write_only"`, `"read_write"`                           | `"read_write"`  |
+/// | `visibility(...)`      | `all`, `none`, or a list-combination of `vertex`, `fragment`, `compute` | `compute`       |
+///
+/// ```
+/// # use bevy_render::{render_resource::AsBindGroup, texture::Image};
+/// # use bevy_asset::Handle;
+/// #[derive(AsBindGroup)]
+/// struct CoolComputeData {
+///     #[storage_texture(0, image_format = "Rgba32Float", access = "write_only")]
+///     output: Handle<Image>,
+/// }
+/// ```
+///
+/// In WGSL shaders, the storage texture binding would look like this:
+/// ```wgsl
+/// @group(0) @binding(0)
+/// var output: texture_storage_2d<rgba32float, write>;
+/// ```
+///
 /// Note that fields without field-level binding attributes will be ignored.
 /// ```
 /// # use bevy_render::{color::Color, render_resource::AsBindGroup};
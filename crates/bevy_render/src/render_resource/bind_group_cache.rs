@@ -0,0 +1,332 @@
+use crate::{
+    render_resource::{
+        BindGroup, BindGroupLayout, BindGroupLayoutId, Buffer, BufferId, Sampler, SamplerId,
+        TextureView, TextureViewId,
+    },
+    renderer::RenderDevice,
+};
+use bevy_ecs::{prelude::ResMut, system::Resource};
+use bevy_utils::{Entry, HashMap};
+use std::{hash::Hash, sync::Mutex};
+use wgpu::{BindGroupDescriptor, BindGroupEntry, BindingResource, BufferAddress, BufferSize};
+
+/// A resource used in a bind group created through the [`BindGroupCache`].
+///
+/// Unlike [`BindingResource`], this references Bevy's resource wrappers, which carry the ids
+/// needed to recognize a bind group that has already been created.
+#[derive(Clone, Debug)]
+pub enum CachedBindingResource<'a> {
+    Buffer {
+        buffer: &'a Buffer,
+        offset: BufferAddress,
+        size: Option<BufferSize>,
+    },
+    TextureView(&'a TextureView),
+    Sampler(&'a Sampler),
+}
+
+impl<'a> CachedBindingResource<'a> {
+    /// Binds the whole `buffer`.
+    #[inline]
+    pub fn entire_buffer(buffer: &'a Buffer) -> Self {
+        CachedBindingResource::Buffer {
+            buffer,
+            offset: 0,
+            size: None,
+        }
+    }
+
+    fn id(&self) -> BindingResourceId {
+        match self {
+            CachedBindingResource::Buffer {
+                buffer,
+                offset,
+                size,
+            } => BindingResourceId::Buffer {
+                id: buffer.id(),
+                offset: *offset,
+                size: *size,
+            },
+            CachedBindingResource::TextureView(view) => BindingResourceId::TextureView(view.id()),
+            CachedBindingResource::Sampler(sampler) => BindingResourceId::Sampler(sampler.id()),
+        }
+    }
+
+    fn binding_resource(&self) -> BindingResource<'a> {
+        match *self {
+            CachedBindingResource::Buffer {
+                buffer,
+                offset,
+                size,
+            } => BindingResource::Buffer(wgpu::BufferBinding {
+                buffer,
+                offset,
+                size,
+            }),
+            CachedBindingResource::TextureView(view) => BindingResource::TextureView(view),
+            CachedBindingResource::Sampler(sampler) => BindingResource::Sampler(sampler),
+        }
+    }
+}
+
+/// A single entry of a bind group created through the [`BindGroupCache`].
+#[derive(Clone, Debug)]
+pub struct CachedBindGroupEntry<'a> {
+    pub binding: u32,
+    pub resource: CachedBindingResource<'a>,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+enum BindingResourceId {
+    Buffer {
+        id: BufferId,
+        offset: BufferAddress,
+        size: Option<BufferSize>,
+    },
+    TextureView(TextureViewId),
+    Sampler(SamplerId),
+}
+
+/// Uniquely identifies a bind group by its layout and the resources bound to it.
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+struct BindGroupCacheKey {
+    layout: BindGroupLayoutId,
+    entries: Vec<(u32, BindingResourceId)>,
+}
+
+/// Values cached by key, which remember the frame they were last used on to evict the least
+/// recently used ones.
+struct FrameCache<K, V> {
+    entries: HashMap<K, (V, u64)>,
+}
+
+impl<K, V> Default for FrameCache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> FrameCache<K, V> {
+    /// Retrieves the value of the `key`, or creates it with `create`, marking it as used on the
+    /// `frame`.
+    fn get_or_insert_with(&mut self, key: K, frame: u64, create: impl FnOnce() -> V) -> V {
+        match self.entries.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let (value, last_used_frame) = entry.get_mut();
+                *last_used_frame = frame;
+                value.clone()
+            }
+            Entry::Vacant(entry) => {
+                let value = create();
+                entry.insert((value.clone(), frame));
+                value
+            }
+        }
+    }
+
+    /// Evicts the values unused for `max_unused_frames` frames before the `frame`, then the least
+    /// recently used values until at most `capacity` remain.
+    fn evict(&mut self, frame: u64, max_unused_frames: u64, capacity: usize) {
+        self.entries
+            .retain(|_, (_, last_used_frame)| frame - *last_used_frame < max_unused_frames);
+
+        if self.entries.len() > capacity {
+            let mut last_used_frames = self
+                .entries
+                .values()
+                .map(|(_, last_used_frame)| *last_used_frame)
+                .collect::<Vec<_>>();
+            let excess = self.entries.len() - capacity;
+            last_used_frames.select_nth_unstable(excess - 1);
+            let threshold = last_used_frames[excess - 1];
+
+            // everything used before the threshold frame is evicted, then values last used on the
+            // threshold frame itself until the cache is back at capacity
+            let older = last_used_frames[..excess - 1]
+                .iter()
+                .filter(|frame| **frame < threshold)
+                .count();
+            let mut to_remove_at_threshold = excess - older;
+            self.entries.retain(|_, (_, last_used_frame)| {
+                if *last_used_frame < threshold {
+                    false
+                } else if *last_used_frame == threshold && to_remove_at_threshold > 0 {
+                    to_remove_at_threshold -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
+}
+
+/// This resource caches bind groups that are requested repeatedly (usually each frame) with the
+/// same layout and resources, so that the same GPU bind group is reused instead of being
+/// recreated.
+///
+/// Bind groups that haven't been requested for [`BindGroupCache::max_unused_frames`] frames are
+/// dropped. If the cache grows beyond [`BindGroupCache::capacity`] entries, the least recently
+/// used bind groups are evicted first.
+///
+/// Bind groups can be retrieved through a shared reference, so that render graph nodes can use
+/// the cache too.
+#[derive(Resource)]
+pub struct BindGroupCache {
+    bind_groups: Mutex<FrameCache<BindGroupCacheKey, BindGroup>>,
+    frame: u64,
+    /// The maximum number of bind groups kept in the cache.
+    pub capacity: usize,
+    /// The number of frames a bind group is kept alive without being requested.
+    pub max_unused_frames: u64,
+}
+
+impl Default for BindGroupCache {
+    fn default() -> Self {
+        Self {
+            bind_groups: Mutex::new(FrameCache::default()),
+            frame: 0,
+            capacity: 4096,
+            max_unused_frames: 3,
+        }
+    }
+}
+
+impl BindGroupCache {
+    /// Retrieves the bind group matching the `layout` and `entries`. If no matching one has been
+    /// created yet, a new [`BindGroup`] is created and cached.
+    pub fn get_or_create(
+        &self,
+        render_device: &RenderDevice,
+        label: Option<&str>,
+        layout: &BindGroupLayout,
+        entries: &[CachedBindGroupEntry],
+    ) -> BindGroup {
+        let key = BindGroupCacheKey {
+            layout: layout.id(),
+            entries: entries
+                .iter()
+                .map(|entry| (entry.binding, entry.resource.id()))
+                .collect(),
+        };
+
+        let mut bind_groups = self.bind_groups.lock().unwrap();
+        bind_groups.get_or_insert_with(key, self.frame, || {
+            let wgpu_entries = entries
+                .iter()
+                .map(|entry| BindGroupEntry {
+                    binding: entry.binding,
+                    resource: entry.resource.binding_resource(),
+                })
+                .collect::<Vec<_>>();
+            render_device.create_bind_group(&BindGroupDescriptor {
+                label,
+                layout,
+                entries: &wgpu_entries,
+            })
+        })
+    }
+
+    /// Returns the number of bind groups currently in the cache.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bind_groups.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if the cache contains no bind groups.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bind_groups.lock().unwrap().entries.is_empty()
+    }
+
+    /// Removes all bind groups from the cache.
+    pub fn clear(&mut self) {
+        self.bind_groups.get_mut().unwrap().entries.clear();
+    }
+
+    /// Updates the cache and only retains recently used bind groups.
+    pub fn update(&mut self) {
+        self.bind_groups.get_mut().unwrap().evict(
+            self.frame,
+            self.max_unused_frames,
+            self.capacity,
+        );
+        self.frame += 1;
+    }
+}
+
+/// Updates the [`BindGroupCache`] to only retain recently used bind groups.
+pub fn update_bind_group_cache_system(mut bind_group_cache: ResMut<BindGroupCache>) {
+    bind_group_cache.update();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::{
+        schedule::{Stage, SystemStage},
+        world::World,
+    };
+
+    #[test]
+    fn cache_hits() {
+        let mut cache = FrameCache::default();
+        let mut created = 0;
+        let mut get = |cache: &mut FrameCache<_, _>, key, frame| {
+            cache.get_or_insert_with(key, frame, || {
+                created += 1;
+                key * 10
+            })
+        };
+        assert_eq!(get(&mut cache, 1, 0), 10);
+        assert_eq!(get(&mut cache, 1, 0), 10);
+        assert_eq!(get(&mut cache, 2, 1), 20);
+        assert_eq!(get(&mut cache, 1, 1), 10);
+        drop(get);
+        assert_eq!(created, 2);
+        assert_eq!(cache.entries[&1].1, 1);
+    }
+
+    #[test]
+    fn unused_eviction() {
+        let mut cache = FrameCache::default();
+        cache.get_or_insert_with(1, 0, || ());
+        cache.get_or_insert_with(2, 1, || ());
+        cache.evict(2, 3, 16);
+        assert_eq!(cache.entries.len(), 2);
+        cache.evict(3, 3, 16);
+        assert!(!cache.entries.contains_key(&1));
+        assert!(cache.entries.contains_key(&2));
+        cache.evict(4, 3, 16);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn lru_eviction() {
+        let mut cache = FrameCache::default();
+        for (key, frame) in [(1, 3), (2, 0), (3, 2), (4, 1), (5, 3), (6, 2)] {
+            cache.get_or_insert_with(key, frame, || ());
+        }
+        cache.evict(3, 4, 4);
+        let mut remaining = cache.entries.keys().copied().collect::<Vec<_>>();
+        remaining.sort_unstable();
+        assert_eq!(remaining, [1, 3, 5, 6]);
+
+        // only one of the values last used on the same frame is evicted
+        cache.evict(3, 4, 3);
+        assert_eq!(cache.entries.len(), 3);
+        assert!(cache.entries.contains_key(&1) && cache.entries.contains_key(&5));
+    }
+
+    #[test]
+    fn update_system_advances_frames() {
+        let mut world = World::new();
+        world.init_resource::<BindGroupCache>();
+        let mut stage = SystemStage::single(update_bind_group_cache_system);
+        stage.run(&mut world);
+        stage.run(&mut world);
+        assert_eq!(world.resource::<BindGroupCache>().frame, 2);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     render_resource::Buffer,
-    renderer::{RenderDevice, RenderQueue},
+    renderer::{RenderDevice, RenderQueue, StagingBelt},
 };
 use bevy_core::{cast_slice, Pod};
 use wgpu::BufferUsages;
@@ -118,16 +118,39 @@ impl<T: Pod> BufferVec<T> {
     /// and the provided [`RenderQueue`](crate::renderer::RenderQueue).
     ///
     /// Before queuing the write, a [`reserve`](crate::render_resource::BufferVec::reserve) operation
-    /// is executed.
+    /// is executed, for at least twice the previous capacity when the values don't fit anymore.
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.write_with(device, |buffer, data| queue.write_buffer(buffer, 0, data));
+    }
+
+    /// Like [`write_buffer`](Self::write_buffer), but copies the data through the [`StagingBelt`],
+    /// along with the other uploads of the frame.
+    pub fn write_buffer_staged(&mut self, device: &RenderDevice, staging_belt: &StagingBelt) {
+        self.write_with(device, |buffer, data| {
+            staging_belt.write_buffer(device, buffer, 0, data);
+        });
+    }
+
+    fn write_with(&mut self, device: &RenderDevice, write: impl FnOnce(&Buffer, &[u8])) {
         if self.values.is_empty() {
             return;
         }
-        self.reserve(self.values.len(), device);
+        self.reserve(self.required_capacity(), device);
         if let Some(buffer) = &self.buffer {
             let range = 0..self.item_size * self.values.len();
             let bytes: &[u8] = cast_slice(&self.values);
-            queue.write_buffer(buffer, 0, &bytes[range]);
+            write(buffer, &bytes[range]);
+        }
+    }
+
+    /// The capacity needed for the values to fit in the buffer. Like `Vec`, the buffer at least
+    /// doubles in size when it grows.
+    fn required_capacity(&self) -> usize {
+        let len = self.values.len();
+        if len > self.capacity {
+            len.max(self.capacity * 2)
+        } else {
+            self.capacity
         }
     }
 
@@ -146,3 +169,28 @@ impl<T: Pod> Extend<T> for BufferVec<T> {
         self.values.extend(iter);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometric_growth() {
+        let mut buffer = BufferVec::<u32>::new(BufferUsages::VERTEX);
+        buffer.extend([0; 3]);
+        assert_eq!(buffer.required_capacity(), 3);
+        buffer.capacity = 3;
+
+        buffer.push(0);
+        assert_eq!(buffer.required_capacity(), 6);
+        buffer.capacity = 6;
+
+        buffer.extend([0; 9]);
+        assert_eq!(buffer.required_capacity(), 13);
+        buffer.capacity = 13;
+
+        // the buffer doesn't shrink when values are removed
+        buffer.truncate(2);
+        assert_eq!(buffer.required_capacity(), 13);
+    }
+}
@@ -4,7 +4,7 @@ use bevy_reflect::Uuid;
 use std::{borrow::Cow, ops::Deref};
 use wgpu::{
     BufferAddress, ColorTargetState, DepthStencilState, MultisampleState, PrimitiveState,
-    VertexAttribute, VertexFormat, VertexStepMode,
+    PushConstantRange, VertexAttribute, VertexFormat, VertexStepMode,
 };
 
 use super::ShaderDefVal;
@@ -99,7 +99,15 @@ pub struct RenderPipelineDescriptor {
     /// Debug label of the pipeline. This will show up in graphics debuggers for easy identification.
     pub label: Option<Cow<'static, str>>,
     /// The layout of bind groups for this pipeline.
+    ///
+    /// If `None`, the bind group layouts are derived from the reflection of the shaders, and can
+    /// be retrieved with [`PipelineCache::get_render_pipeline_bind_group_layouts`].
+    ///
+    /// [`PipelineCache::get_render_pipeline_bind_group_layouts`]: crate::render_resource::PipelineCache::get_render_pipeline_bind_group_layouts
     pub layout: Option<Vec<BindGroupLayout>>,
+    /// The push constant ranges for this pipeline.
+    /// Supply an empty vector if the pipeline doesn't use push constants.
+    pub push_constant_ranges: Vec<PushConstantRange>,
     /// The compiled vertex stage, its entry point, and the input buffers layout.
     pub vertex: VertexState,
     /// The properties of the pipeline at the primitive assembly and rasterization level.
@@ -180,7 +188,16 @@ pub struct FragmentState {
 #[derive(Clone, Debug)]
 pub struct ComputePipelineDescriptor {
     pub label: Option<Cow<'static, str>>,
+    /// The layout of bind groups for this pipeline.
+    ///
+    /// If `None`, the bind group layouts are derived from the reflection of the shader, and can
+    /// be retrieved with [`PipelineCache::get_compute_pipeline_bind_group_layouts`].
+    ///
+    /// [`PipelineCache::get_compute_pipeline_bind_group_layouts`]: crate::render_resource::PipelineCache::get_compute_pipeline_bind_group_layouts
     pub layout: Option<Vec<BindGroupLayout>>,
+    /// The push constant ranges for this pipeline.
+    /// Supply an empty vector if the pipeline doesn't use push constants.
+    pub push_constant_ranges: Vec<PushConstantRange>,
     /// The compiled shader module for this stage.
     pub shader: Handle<Shader>,
     pub shader_defs: Vec<ShaderDefVal>,
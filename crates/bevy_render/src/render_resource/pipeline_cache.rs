@@ -1,25 +1,36 @@
 use crate::{
     render_resource::{
-        AsModuleDescriptorError, BindGroupLayout, BindGroupLayoutId, ComputePipeline,
-        ComputePipelineDescriptor, ProcessShaderError, ProcessedShader,
-        RawComputePipelineDescriptor, RawFragmentState, RawRenderPipelineDescriptor,
-        RawVertexState, RenderPipeline, RenderPipelineDescriptor, Shader, ShaderImport,
-        ShaderProcessor, ShaderReflectError,
+        merge_bind_group_layout_entries, AsModuleDescriptorError, BindGroupLayout,
+        BindGroupLayoutId, BindGroupLayoutReflectError, ComputePipeline, ComputePipelineDescriptor,
+        ProcessShaderError, ProcessedShader, RawComputePipelineDescriptor, RawFragmentState,
+        RawRenderPipelineDescriptor, RawVertexState, RenderPipeline, RenderPipelineDescriptor,
+        Shader, ShaderImport, ShaderProcessor, ShaderReflectError, ShaderSourceMap,
     },
     renderer::RenderDevice,
     Extract,
 };
-use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_asset::{AssetEvent, AssetServer, Assets, Handle};
 use bevy_ecs::system::{Res, ResMut};
-use bevy_ecs::{event::EventReader, system::Resource};
+use bevy_ecs::{
+    event::{EventReader, EventWriter},
+    system::Resource,
+};
+use bevy_tasks::{AsyncComputeTaskPool, Task};
 use bevy_utils::{
     default,
     tracing::{debug, error},
     Entry, HashMap, HashSet,
 };
-use std::{hash::Hash, iter::FusedIterator, mem, ops::Deref};
+use futures_lite::future;
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow, collections::BTreeMap, hash::Hash, iter::FusedIterator, mem, ops::Deref, sync::Arc,
+};
 use thiserror::Error;
-use wgpu::{PipelineLayoutDescriptor, VertexBufferLayout as RawVertexBufferLayout};
+use wgpu::{
+    BindGroupLayoutDescriptor, BindGroupLayoutEntry, PipelineLayoutDescriptor, PushConstantRange,
+    VertexBufferLayout as RawVertexBufferLayout,
+};
 
 use crate::render_resource::resource_macros::*;
 
@@ -47,7 +58,7 @@ pub enum Pipeline {
 type CachedPipelineId = usize;
 
 /// Index of a cached render pipeline in a [`PipelineCache`].
-#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct CachedRenderPipelineId(CachedPipelineId);
 
 impl CachedRenderPipelineId {
@@ -64,9 +75,19 @@ impl CachedComputePipelineId {
     pub const INVALID: Self = CachedComputePipelineId(usize::MAX);
 }
 
+/// The id of a render or compute pipeline of a [`PipelineCache`].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum PipelineId {
+    Render(CachedRenderPipelineId),
+    Compute(CachedComputePipelineId),
+}
+
 pub struct CachedPipeline {
     pub descriptor: PipelineDescriptor,
     pub state: CachedPipelineState,
+    /// The pipeline created before one of its shaders changed, used while the pipeline is
+    /// recreated, or if recreating it failed.
+    last_good: Option<Pipeline>,
 }
 
 /// State of a cached pipeline inserted into a [`PipelineCache`].
@@ -74,6 +95,8 @@ pub struct CachedPipeline {
 pub enum CachedPipelineState {
     /// The pipeline GPU object is queued for creation.
     Queued,
+    /// The pipeline GPU object is being created on the [`AsyncComputeTaskPool`].
+    Creating(Task<Result<Pipeline, PipelineCacheError>>),
     /// The pipeline GPU object was created successfully and is available (allocated on the GPU).
     Ok(Pipeline),
     /// An error occurred while trying to create the pipeline GPU object.
@@ -97,15 +120,24 @@ impl CachedPipelineState {
             CachedPipelineState::Queued => {
                 panic!("Pipeline has not been compiled yet. It is still in the 'Queued' state.")
             }
+            CachedPipelineState::Creating(_) => {
+                panic!("Pipeline has not been compiled yet. It is still in the 'Creating' state.")
+            }
             CachedPipelineState::Err(err) => panic!("{}", err),
         }
     }
 }
 
+/// A shader processed with some shader defs, and its module.
+struct ProcessedShaderModule {
+    processed: ProcessedShader,
+    module: ErasedShaderModule,
+}
+
 #[derive(Default)]
 struct ShaderData {
     pipelines: HashSet<CachedPipelineId>,
-    processed_shaders: HashMap<Vec<ShaderDefVal>, ErasedShaderModule>,
+    processed_shaders: HashMap<Vec<ShaderDefVal>, ProcessedShaderModule>,
     resolved_imports: HashMap<ShaderImport, Handle<Shader>>,
     dependents: HashSet<Handle<Shader>>,
 }
@@ -117,6 +149,7 @@ struct ShaderCache {
     import_path_shaders: HashMap<ShaderImport, Handle<Shader>>,
     waiting_on_import: HashMap<ShaderImport, Vec<Handle<Shader>>>,
     processor: ShaderProcessor,
+    error_reporter: ShaderErrorReporter,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -196,17 +229,26 @@ impl ShaderCache {
                     "processing shader {:?}, with shader defs {:?}",
                     handle, shader_defs
                 );
-                let processed = self.processor.process(
+                let (processed, source_map) = match self.processor.process_with_source_map(
+                    handle,
                     shader,
                     &shader_defs,
                     &self.shaders,
                     &self.import_path_shaders,
-                )?;
+                ) {
+                    Ok(processed) => processed,
+                    Err(err) => {
+                        self.error_reporter.report(handle, None, &err);
+                        return Err(err.into());
+                    }
+                };
                 let module_descriptor = match processed
                     .get_module_descriptor(render_device.features())
                 {
                     Ok(module_descriptor) => module_descriptor,
                     Err(err) => {
+                        let location = error_location(&err, &processed, &source_map);
+                        self.error_reporter.report(handle, location, &err);
                         return Err(PipelineCacheError::AsModuleDescriptorError(err, processed));
                     }
                 };
@@ -214,7 +256,26 @@ impl ShaderCache {
                 render_device
                     .wgpu_device()
                     .push_error_scope(wgpu::ErrorFilter::Validation);
-                let shader_module = render_device.create_shader_module(module_descriptor);
+                let shader_module = match &processed {
+                    ProcessedShader::SpirV(source)
+                        if render_device
+                            .features()
+                            .contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH) =>
+                    {
+                        // SAFETY: the passthrough feature is only enabled when requested in the
+                        // `WgpuSettings`, which makes the SPIR-V shaders responsible for being
+                        // valid, as documented on `Shader::from_spirv`
+                        unsafe {
+                            render_device.create_shader_module_spirv(
+                                &wgpu::ShaderModuleDescriptorSpirV {
+                                    label: None,
+                                    source: wgpu::util::make_spirv_raw(source),
+                                },
+                            )
+                        }
+                    }
+                    _ => render_device.create_shader_module(module_descriptor),
+                };
                 let error = render_device.wgpu_device().pop_error_scope();
 
                 // `now_or_never` will return Some if the future is ready and None otherwise.
@@ -224,17 +285,47 @@ impl ShaderCache {
                 if let Some(Some(wgpu::Error::Validation { description, .. })) =
                     bevy_utils::futures::now_or_never(error)
                 {
-                    return Err(PipelineCacheError::CreateShaderModule(description));
+                    let err = PipelineCacheError::CreateShaderModule(description);
+                    self.error_reporter.report(handle, None, &err);
+                    return Err(err);
                 }
 
-                entry.insert(ErasedShaderModule::new(shader_module))
+                entry.insert(ProcessedShaderModule {
+                    processed,
+                    module: ErasedShaderModule::new(shader_module),
+                })
             }
         };
 
-        Ok(module.clone())
+        Ok(module.module.clone())
+    }
+
+    /// Reflects the bind group layout entries used by a shader already processed with the same
+    /// `shader_defs` by [`ShaderCache::get`].
+    fn bind_group_layout_entries(
+        &self,
+        render_device: &RenderDevice,
+        handle: &Handle<Shader>,
+        shader_defs: &[ShaderDefVal],
+    ) -> Result<BTreeMap<u32, Vec<BindGroupLayoutEntry>>, PipelineCacheError> {
+        let processed = &self
+            .data
+            .get(handle)
+            .and_then(|data| data.processed_shaders.get(shader_defs))
+            .expect("shader was processed when getting its module")
+            .processed;
+        let reflection = processed.reflect(render_device.features()).map_err(|err| {
+            PipelineCacheError::AsModuleDescriptorError(
+                AsModuleDescriptorError::ShaderReflectError(err),
+                processed.clone(),
+            )
+        })?;
+        Ok(reflection.bind_group_layout_entries()?)
     }
 
     fn clear(&mut self, handle: &Handle<Shader>) -> Vec<CachedPipelineId> {
+        // the errors may be fixed, or show up again in the pipelines recreated
+        self.error_reporter.reported.clear();
         let mut shaders_to_clear = vec![handle.clone_weak()];
         let mut pipelines_to_queue = Vec::new();
         while let Some(handle) = shaders_to_clear.pop() {
@@ -297,33 +388,190 @@ impl ShaderCache {
     }
 }
 
+/// Turns the errors found compiling shaders into [`ShaderErrorEvent`]s.
+#[derive(Default)]
+struct ShaderErrorReporter {
+    /// The asset paths of the shaders loaded from files.
+    paths: HashMap<Handle<Shader>, String>,
+    /// The errors sent since the last change to a shader, which aren't sent again for the other
+    /// pipelines using the same shaders.
+    reported: HashSet<ShaderErrorEvent>,
+    errors: ShaderErrors,
+}
+
+impl ShaderErrorReporter {
+    /// Reports an `error` of the shader `handle`, which is at the `location` returned by
+    /// [`error_location`] if it is known.
+    fn report(
+        &mut self,
+        handle: &Handle<Shader>,
+        location: Option<(&Handle<Shader>, usize, usize)>,
+        error: &dyn std::error::Error,
+    ) {
+        let shader = location.map_or(handle, |(shader, _, _)| shader);
+        let mut message = error.to_string();
+        for source in ErrorSources::of(error) {
+            message.push_str(": ");
+            message.push_str(&source.to_string());
+        }
+        let event = ShaderErrorEvent {
+            shader: shader.clone_weak(),
+            path: self.paths.get(shader).cloned(),
+            line: location.map(|(_, line, _)| line),
+            column: location.map(|(_, _, column)| column),
+            message,
+        };
+        if self.reported.insert(event.clone()) {
+            self.errors.0.lock().push(event);
+        }
+    }
+}
+
+/// Returns the shader, line and column (both starting at 1) of the first span of an `error` found
+/// in the `processed` shader, in the shader files it was processed from.
+fn error_location<'a>(
+    error: &AsModuleDescriptorError,
+    processed: &ProcessedShader,
+    source_map: &'a ShaderSourceMap,
+) -> Option<(&'a Handle<Shader>, usize, usize)> {
+    let start = match error {
+        AsModuleDescriptorError::ShaderReflectError(error) => match error {
+            ShaderReflectError::WgslParse(error) => error.labels().next()?.0.start,
+            ShaderReflectError::GlslParse(errors) => errors.first()?.meta.to_range()?.start,
+            ShaderReflectError::Validation(error) => error.spans().next()?.0.to_range()?.start,
+            ShaderReflectError::SpirVParse(_) => return None,
+        },
+        AsModuleDescriptorError::WgslConversion(_)
+        | AsModuleDescriptorError::SpirVConversion(_)
+        | AsModuleDescriptorError::InvalidSpirV => return None,
+    };
+    let source = match processed {
+        ProcessedShader::Wgsl(source) | ProcessedShader::Glsl(source, _) => source,
+        ProcessedShader::SpirV(_) => return None,
+    };
+
+    let prefix = source.get(..start)?;
+    let line = prefix.matches('\n').count();
+    let line_start = prefix.rfind('\n').map_or(0, |index| index + 1);
+    let column = prefix[line_start..].chars().count() + 1;
+    let (shader, original_line) = source_map.original_line(line)?;
+    Some((shader, original_line + 1, column))
+}
+
+type LayoutCacheKey = (Vec<BindGroupLayoutId>, Vec<PushConstantRange>);
+
 #[derive(Default)]
 struct LayoutCache {
-    layouts: HashMap<Vec<BindGroupLayoutId>, ErasedPipelineLayout>,
+    layouts: HashMap<LayoutCacheKey, ErasedPipelineLayout>,
+    /// The bind group layouts derived from shader reflection, reused by the pipelines using the
+    /// same bindings.
+    reflected_bind_group_layouts: HashMap<Vec<BindGroupLayoutEntry>, BindGroupLayout>,
 }
 
 impl LayoutCache {
+    fn get_reflected(
+        &mut self,
+        render_device: &RenderDevice,
+        entries: Vec<BindGroupLayoutEntry>,
+    ) -> BindGroupLayout {
+        self.reflected_bind_group_layouts
+            .entry(entries)
+            .or_insert_with_key(|entries| {
+                render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("reflected_bind_group_layout"),
+                    entries,
+                })
+            })
+            .clone()
+    }
+
     fn get(
         &mut self,
         render_device: &RenderDevice,
         bind_group_layouts: &[BindGroupLayout],
-    ) -> &wgpu::PipelineLayout {
-        let key = bind_group_layouts.iter().map(|l| l.id()).collect();
-        self.layouts.entry(key).or_insert_with(|| {
-            let bind_group_layouts = bind_group_layouts
-                .iter()
-                .map(|l| l.value())
-                .collect::<Vec<_>>();
-            ErasedPipelineLayout::new(render_device.create_pipeline_layout(
-                &PipelineLayoutDescriptor {
-                    bind_group_layouts: &bind_group_layouts,
-                    ..default()
-                },
-            ))
-        })
+        push_constant_ranges: Vec<PushConstantRange>,
+    ) -> ErasedPipelineLayout {
+        let bind_group_ids = bind_group_layouts.iter().map(|l| l.id()).collect();
+        self.layouts
+            .entry((bind_group_ids, push_constant_ranges))
+            .or_insert_with_key(|(_, push_constant_ranges)| {
+                let bind_group_layouts = bind_group_layouts
+                    .iter()
+                    .map(|l| l.value())
+                    .collect::<Vec<_>>();
+                ErasedPipelineLayout::new(render_device.create_pipeline_layout(
+                    &PipelineLayoutDescriptor {
+                        bind_group_layouts: &bind_group_layouts,
+                        push_constant_ranges,
+                        ..default()
+                    },
+                ))
+            })
+            .clone()
     }
 }
 
+/// Sent in the main world when a pipeline of the [`PipelineCache`] has been created, or has failed
+/// to be.
+///
+/// Pipelines waiting for a shader that isn't loaded yet aren't reported until they are created.
+/// The errors of [`wgpu`] creating the pipeline from valid shaders still go to the error handler
+/// of the device instead.
+#[derive(Debug, Clone)]
+pub struct PipelineCompilationEvent {
+    pub id: PipelineId,
+    /// The label of the descriptor of the pipeline.
+    pub label: Option<Cow<'static, str>>,
+    /// The error preventing the creation of the pipeline, if it failed.
+    pub result: Result<(), String>,
+}
+
+/// The pipelines created or failed this frame in the render world, waiting to be sent as
+/// [`PipelineCompilationEvent`]s in the main world.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct CompletedPipelines(Arc<Mutex<Vec<PipelineCompilationEvent>>>);
+
+pub(crate) fn send_pipeline_compilation_events(
+    completed: Res<CompletedPipelines>,
+    mut events: EventWriter<PipelineCompilationEvent>,
+) {
+    events.send_batch(completed.0.lock().drain(..));
+}
+
+/// Sent in the main world when a shader used by a pipeline of the [`PipelineCache`] failed to
+/// compile, for example after an error was saved in a shader file that is hot reloaded.
+///
+/// The pipelines that were created before keep using the last version of their shaders that
+/// compiled, until the error is fixed. An error is sent once, even when several pipelines use the
+/// shader, until one of the shaders changes again.
+///
+/// The location of the error is only known for the errors found by [`naga`] parsing and validating
+/// the shader. Without `debug_assertions`, WGSL shaders are only validated by [`wgpu`] instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShaderErrorEvent {
+    /// The shader the error is in, which may be a shader imported by the shader of the pipeline.
+    pub shader: Handle<Shader>,
+    /// The asset path of the shader, if it was loaded from a file.
+    pub path: Option<String>,
+    /// The line of the error in the source of the shader, starting at 1.
+    pub line: Option<usize>,
+    /// The column of the error in its line, starting at 1.
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/// The shader errors found this frame in the render world, waiting to be sent as
+/// [`ShaderErrorEvent`]s in the main world.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct ShaderErrors(Arc<Mutex<Vec<ShaderErrorEvent>>>);
+
+pub(crate) fn send_shader_error_events(
+    errors: Res<ShaderErrors>,
+    mut events: EventWriter<ShaderErrorEvent>,
+) {
+    events.send_batch(errors.0.lock().drain(..));
+}
+
 /// Cache for render and compute pipelines.
 ///
 /// The cache stores existing render and compute pipelines allocated on the GPU, as well as
@@ -332,6 +580,11 @@ impl LayoutCache {
 /// pipeline object is deferred to the [`RenderStage::Render`] stage, just before the render
 /// graph starts being processed, as this requires access to the GPU.
 ///
+/// Unless the cache is synchronous, the pipelines are then created on the
+/// [`AsyncComputeTaskPool`], so that compiling new pipelines doesn't stall the frame. They become
+/// available in a later frame, once their task has completed, and draws using them should be
+/// skipped or fall back to another pipeline until then.
+///
 /// Note that the cache do not perform automatic deduplication of identical pipelines. It is
 /// up to the user not to insert the same pipeline twice to avoid wasting GPU resources.
 ///
@@ -342,7 +595,11 @@ pub struct PipelineCache {
     shader_cache: ShaderCache,
     device: RenderDevice,
     pipelines: Vec<CachedPipeline>,
+    /// The bind group layouts derived from the shaders of the pipelines queued without a layout.
+    reflected_layouts: HashMap<CachedPipelineId, Vec<BindGroupLayout>>,
     waiting_pipelines: HashSet<CachedPipelineId>,
+    synchronous: bool,
+    completed: CompletedPipelines,
 }
 
 impl PipelineCache {
@@ -351,16 +608,37 @@ impl PipelineCache {
     }
 
     /// Create a new pipeline cache associated with the given render device.
-    pub fn new(device: RenderDevice) -> Self {
+    ///
+    /// The pipelines of a `synchronous` cache are created in [`PipelineCache::process_queue`],
+    /// instead of on the [`AsyncComputeTaskPool`].
+    pub fn new(device: RenderDevice, synchronous: bool) -> Self {
         Self {
             device,
             layout_cache: default(),
             shader_cache: default(),
             waiting_pipelines: default(),
             pipelines: default(),
+            reflected_layouts: default(),
+            // The single threaded task pool can't be polled for the completion of a task
+            synchronous: synchronous || cfg!(target_arch = "wasm32"),
+            completed: default(),
         }
     }
 
+    /// Returns whether the pipelines are created in [`PipelineCache::process_queue`], instead of
+    /// on the [`AsyncComputeTaskPool`].
+    pub fn is_synchronous(&self) -> bool {
+        self.synchronous
+    }
+
+    pub(crate) fn completed_pipelines(&self) -> CompletedPipelines {
+        self.completed.clone()
+    }
+
+    pub(crate) fn shader_errors(&self) -> ShaderErrors {
+        self.shader_cache.error_reporter.errors.clone()
+    }
+
     /// Get the state of a cached render pipeline.
     ///
     /// See [`PipelineCache::queue_render_pipeline()`].
@@ -405,6 +683,32 @@ impl PipelineCache {
         }
     }
 
+    /// Get the bind group layouts derived from the shaders of a render pipeline queued without a
+    /// [`layout`](RenderPipelineDescriptor::layout), to create the bind groups used with it.
+    ///
+    /// Returns `None` until the shaders of the pipeline have been processed, or if the pipeline
+    /// was queued with a layout.
+    #[inline]
+    pub fn get_render_pipeline_bind_group_layouts(
+        &self,
+        id: CachedRenderPipelineId,
+    ) -> Option<&[BindGroupLayout]> {
+        self.reflected_layouts.get(&id.0).map(Vec::as_slice)
+    }
+
+    /// Get the bind group layouts derived from the shader of a compute pipeline queued without a
+    /// [`layout`](ComputePipelineDescriptor::layout), to create the bind groups used with it.
+    ///
+    /// Returns `None` until the shader of the pipeline has been processed, or if the pipeline
+    /// was queued with a layout.
+    #[inline]
+    pub fn get_compute_pipeline_bind_group_layouts(
+        &self,
+        id: CachedComputePipelineId,
+    ) -> Option<&[BindGroupLayout]> {
+        self.reflected_layouts.get(&id.0).map(Vec::as_slice)
+    }
+
     /// Try to retrieve a render pipeline GPU object from a cached ID.
     ///
     /// # Returns
@@ -412,14 +716,21 @@ impl PipelineCache {
     /// This method returns a successfully created render pipeline if any, or `None` if the pipeline
     /// was not created yet or if there was an error during creation. You can check the actual creation
     /// state with [`PipelineCache::get_render_pipeline_state()`].
+    ///
+    /// While a pipeline is recreated after one of its shaders changed, or if recreating it failed,
+    /// the pipeline created before the change is returned.
     #[inline]
     pub fn get_render_pipeline(&self, id: CachedRenderPipelineId) -> Option<&RenderPipeline> {
-        if let CachedPipelineState::Ok(Pipeline::RenderPipeline(pipeline)) =
-            &self.pipelines[id.0].state
-        {
-            Some(pipeline)
-        } else {
-            None
+        match &self.pipelines[id.0] {
+            CachedPipeline {
+                state: CachedPipelineState::Ok(Pipeline::RenderPipeline(pipeline)),
+                ..
+            }
+            | CachedPipeline {
+                last_good: Some(Pipeline::RenderPipeline(pipeline)),
+                ..
+            } => Some(pipeline),
+            _ => None,
         }
     }
 
@@ -430,14 +741,21 @@ impl PipelineCache {
     /// This method returns a successfully created compute pipeline if any, or `None` if the pipeline
     /// was not created yet or if there was an error during creation. You can check the actual creation
     /// state with [`PipelineCache::get_compute_pipeline_state()`].
+    ///
+    /// While a pipeline is recreated after one of its shaders changed, or if recreating it failed,
+    /// the pipeline created before the change is returned.
     #[inline]
     pub fn get_compute_pipeline(&self, id: CachedComputePipelineId) -> Option<&ComputePipeline> {
-        if let CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)) =
-            &self.pipelines[id.0].state
-        {
-            Some(pipeline)
-        } else {
-            None
+        match &self.pipelines[id.0] {
+            CachedPipeline {
+                state: CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline)),
+                ..
+            }
+            | CachedPipeline {
+                last_good: Some(Pipeline::ComputePipeline(pipeline)),
+                ..
+            } => Some(pipeline),
+            _ => None,
         }
     }
 
@@ -462,6 +780,7 @@ impl PipelineCache {
         self.pipelines.push(CachedPipeline {
             descriptor: PipelineDescriptor::RenderPipelineDescriptor(Box::new(descriptor)),
             state: CachedPipelineState::Queued,
+            last_good: None,
         });
         self.waiting_pipelines.insert(id.0);
         id
@@ -488,6 +807,7 @@ impl PipelineCache {
         self.pipelines.push(CachedPipeline {
             descriptor: PipelineDescriptor::ComputePipelineDescriptor(Box::new(descriptor)),
             state: CachedPipelineState::Queued,
+            last_good: None,
         });
         self.waiting_pipelines.insert(id.0);
         id
@@ -496,23 +816,33 @@ impl PipelineCache {
     fn set_shader(&mut self, handle: &Handle<Shader>, shader: &Shader) {
         let pipelines_to_queue = self.shader_cache.set_shader(handle, shader.clone());
         for cached_pipeline in pipelines_to_queue {
-            self.pipelines[cached_pipeline].state = CachedPipelineState::Queued;
-            self.waiting_pipelines.insert(cached_pipeline);
+            self.requeue_pipeline(cached_pipeline);
         }
     }
 
     fn remove_shader(&mut self, shader: &Handle<Shader>) {
         let pipelines_to_queue = self.shader_cache.remove(shader);
         for cached_pipeline in pipelines_to_queue {
-            self.pipelines[cached_pipeline].state = CachedPipelineState::Queued;
-            self.waiting_pipelines.insert(cached_pipeline);
+            self.requeue_pipeline(cached_pipeline);
         }
     }
 
+    /// Queues the pipeline to be recreated with the new version of its shaders, keeping the
+    /// pipeline created from the previous version to use until then.
+    fn requeue_pipeline(&mut self, id: CachedPipelineId) {
+        let pipeline = &mut self.pipelines[id];
+        if let CachedPipelineState::Ok(created) =
+            mem::replace(&mut pipeline.state, CachedPipelineState::Queued)
+        {
+            pipeline.last_good = Some(created);
+        }
+        self.waiting_pipelines.insert(id);
+    }
+
     fn process_render_pipeline(
         &mut self,
         id: CachedPipelineId,
-        descriptor: &RenderPipelineDescriptor,
+        descriptor: RenderPipelineDescriptor,
     ) -> CachedPipelineState {
         let vertex_module = match self.shader_cache.get(
             &self.device,
@@ -526,74 +856,80 @@ impl PipelineCache {
             }
         };
 
-        let fragment_data = if let Some(fragment) = &descriptor.fragment {
-            let fragment_module = match self.shader_cache.get(
-                &self.device,
-                id,
-                &fragment.shader,
-                &fragment.shader_defs,
-            ) {
-                Ok(module) => module,
+        let fragment_module = if let Some(fragment) = &descriptor.fragment {
+            match self
+                .shader_cache
+                .get(&self.device, id, &fragment.shader, &fragment.shader_defs)
+            {
+                Ok(module) => Some(module),
                 Err(err) => {
                     return CachedPipelineState::Err(err);
                 }
-            };
-            Some((
-                fragment_module,
-                fragment.entry_point.deref(),
-                fragment.targets.as_slice(),
-            ))
-        } else {
-            None
-        };
-
-        let vertex_buffer_layouts = descriptor
-            .vertex
-            .buffers
-            .iter()
-            .map(|layout| RawVertexBufferLayout {
-                array_stride: layout.array_stride,
-                attributes: &layout.attributes,
-                step_mode: layout.step_mode,
-            })
-            .collect::<Vec<_>>();
-
-        let layout = if let Some(layout) = &descriptor.layout {
-            Some(self.layout_cache.get(&self.device, layout))
+            }
         } else {
             None
         };
 
-        let descriptor = RawRenderPipelineDescriptor {
-            multiview: None,
-            depth_stencil: descriptor.depth_stencil.clone(),
-            label: descriptor.label.as_deref(),
-            layout,
-            multisample: descriptor.multisample,
-            primitive: descriptor.primitive,
-            vertex: RawVertexState {
-                buffers: &vertex_buffer_layouts,
-                entry_point: descriptor.vertex.entry_point.deref(),
-                module: &vertex_module,
-            },
-            fragment: fragment_data
-                .as_ref()
-                .map(|(module, entry_point, targets)| RawFragmentState {
-                    entry_point,
-                    module,
-                    targets,
-                }),
+        let mut stages = vec![(&descriptor.vertex.shader, &descriptor.vertex.shader_defs)];
+        if let Some(fragment) = &descriptor.fragment {
+            stages.push((&fragment.shader, &fragment.shader_defs));
+        }
+        let layout = match self.pipeline_layout(
+            id,
+            descriptor.layout.as_deref(),
+            &descriptor.push_constant_ranges,
+            &stages,
+        ) {
+            Ok(layout) => layout,
+            Err(err) => return CachedPipelineState::Err(err),
         };
 
-        let pipeline = self.device.create_render_pipeline(&descriptor);
+        let device = self.device.clone();
+        let create = move || {
+            let vertex_buffer_layouts = descriptor
+                .vertex
+                .buffers
+                .iter()
+                .map(|layout| RawVertexBufferLayout {
+                    array_stride: layout.array_stride,
+                    attributes: &layout.attributes,
+                    step_mode: layout.step_mode,
+                })
+                .collect::<Vec<_>>();
+            let raw_descriptor = RawRenderPipelineDescriptor {
+                multiview: None,
+                depth_stencil: descriptor.depth_stencil.clone(),
+                label: descriptor.label.as_deref(),
+                layout: Some(&layout),
+                multisample: descriptor.multisample,
+                primitive: descriptor.primitive,
+                vertex: RawVertexState {
+                    buffers: &vertex_buffer_layouts,
+                    entry_point: descriptor.vertex.entry_point.deref(),
+                    module: &vertex_module,
+                },
+                fragment: descriptor
+                    .fragment
+                    .as_ref()
+                    .zip(fragment_module.as_ref())
+                    .map(|(fragment, module)| RawFragmentState {
+                        entry_point: fragment.entry_point.deref(),
+                        module,
+                        targets: &fragment.targets,
+                    }),
+            };
 
-        CachedPipelineState::Ok(Pipeline::RenderPipeline(pipeline))
+            Ok(Pipeline::RenderPipeline(
+                device.create_render_pipeline(&raw_descriptor),
+            ))
+        };
+        self.create_pipeline(create)
     }
 
     fn process_compute_pipeline(
         &mut self,
         id: CachedPipelineId,
-        descriptor: &ComputePipelineDescriptor,
+        descriptor: ComputePipelineDescriptor,
     ) -> CachedPipelineState {
         let compute_module = match self.shader_cache.get(
             &self.device,
@@ -607,22 +943,89 @@ impl PipelineCache {
             }
         };
 
-        let layout = if let Some(layout) = &descriptor.layout {
-            Some(self.layout_cache.get(&self.device, layout))
-        } else {
-            None
+        let layout = match self.pipeline_layout(
+            id,
+            descriptor.layout.as_deref(),
+            &descriptor.push_constant_ranges,
+            &[(&descriptor.shader, &descriptor.shader_defs)],
+        ) {
+            Ok(layout) => layout,
+            Err(err) => return CachedPipelineState::Err(err),
         };
 
-        let descriptor = RawComputePipelineDescriptor {
-            label: descriptor.label.as_deref(),
-            layout,
-            module: &compute_module,
-            entry_point: descriptor.entry_point.as_ref(),
+        let device = self.device.clone();
+        let create = move || {
+            let raw_descriptor = RawComputePipelineDescriptor {
+                label: descriptor.label.as_deref(),
+                layout: Some(&layout),
+                module: &compute_module,
+                entry_point: descriptor.entry_point.as_ref(),
+            };
+
+            Ok(Pipeline::ComputePipeline(
+                device.create_compute_pipeline(&raw_descriptor),
+            ))
         };
+        self.create_pipeline(create)
+    }
 
-        let pipeline = self.device.create_compute_pipeline(&descriptor);
+    /// Returns the layout of the pipeline at `id`, made of its bind group `layout` and its
+    /// `push_constant_ranges`.
+    ///
+    /// Without a bind group `layout`, the bind group layouts are derived from the reflection of
+    /// the shaders of the pipeline `stages`, which must have been processed already.
+    fn pipeline_layout(
+        &mut self,
+        id: CachedPipelineId,
+        layout: Option<&[BindGroupLayout]>,
+        push_constant_ranges: &[PushConstantRange],
+        stages: &[(&Handle<Shader>, &Vec<ShaderDefVal>)],
+    ) -> Result<ErasedPipelineLayout, PipelineCacheError> {
+        let reflected;
+        let bind_group_layouts = match layout {
+            Some(layout) => layout,
+            None => {
+                let entries = stages
+                    .iter()
+                    .map(|(shader, shader_defs)| {
+                        self.shader_cache.bind_group_layout_entries(
+                            &self.device,
+                            shader,
+                            shader_defs,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                reflected = merge_bind_group_layout_entries(entries)?
+                    .into_iter()
+                    .map(|entries| self.layout_cache.get_reflected(&self.device, entries))
+                    .collect::<Vec<_>>();
+                self.reflected_layouts.insert(id, reflected.clone());
+                &reflected
+            }
+        };
+        Ok(self.layout_cache.get(
+            &self.device,
+            bind_group_layouts,
+            push_constant_ranges.to_vec(),
+        ))
+    }
 
-        CachedPipelineState::Ok(Pipeline::ComputePipeline(pipeline))
+    /// Creates the pipeline right away if the cache is synchronous, or starts creating it on the
+    /// [`AsyncComputeTaskPool`] otherwise.
+    fn create_pipeline(
+        &self,
+        create: impl FnOnce() -> Result<Pipeline, PipelineCacheError> + Send + 'static,
+    ) -> CachedPipelineState {
+        if self.synchronous {
+            match create() {
+                Ok(pipeline) => CachedPipelineState::Ok(pipeline),
+                Err(err) => CachedPipelineState::Err(err),
+            }
+        } else {
+            CachedPipelineState::Creating(
+                AsyncComputeTaskPool::get().spawn(async move { create() }),
+            )
+        }
     }
 
     /// Process the pipeline queue and create all pending pipelines if possible.
@@ -637,41 +1040,39 @@ impl PipelineCache {
 
         for id in waiting_pipelines {
             let pipeline = &mut pipelines[id];
-            if matches!(pipeline.state, CachedPipelineState::Ok(_)) {
-                continue;
-            }
-
-            pipeline.state = match &pipeline.descriptor {
-                PipelineDescriptor::RenderPipelineDescriptor(descriptor) => {
-                    self.process_render_pipeline(id, descriptor)
-                }
-                PipelineDescriptor::ComputePipelineDescriptor(descriptor) => {
-                    self.process_compute_pipeline(id, descriptor)
-                }
-            };
-
-            if let CachedPipelineState::Err(err) = &pipeline.state {
-                match err {
-                    PipelineCacheError::ShaderNotLoaded(_)
-                    | PipelineCacheError::ShaderImportNotYetAvailable => {
-                        // retry
+            match &mut pipeline.state {
+                CachedPipelineState::Ok(_) => continue,
+                CachedPipelineState::Creating(task) => match poll_creation(task) {
+                    Some(Ok(created)) => pipeline.state = CachedPipelineState::Ok(created),
+                    Some(Err(err)) => pipeline.state = CachedPipelineState::Err(err),
+                    None => {
                         self.waiting_pipelines.insert(id);
-                    }
-                    // shader could not be processed ... retrying won't help
-                    PipelineCacheError::ProcessShaderError(err) => {
-                        error!("failed to process shader: {}", err);
-                        continue;
-                    }
-                    PipelineCacheError::AsModuleDescriptorError(err, source) => {
-                        log_shader_error(source, err);
-                        continue;
-                    }
-                    PipelineCacheError::CreateShaderModule(description) => {
-                        error!("failed to create shader module: {}", description);
                         continue;
                     }
+                },
+                CachedPipelineState::Queued | CachedPipelineState::Err(_) => {
+                    pipeline.state = match &pipeline.descriptor {
+                        PipelineDescriptor::RenderPipelineDescriptor(descriptor) => {
+                            self.process_render_pipeline(id, (**descriptor).clone())
+                        }
+                        PipelineDescriptor::ComputePipelineDescriptor(descriptor) => {
+                            self.process_compute_pipeline(id, (**descriptor).clone())
+                        }
+                    };
                 }
             }
+
+            let Some(result) = compilation_result(&pipeline.state) else {
+                self.waiting_pipelines.insert(id);
+                continue;
+            };
+            if result.is_ok() {
+                pipeline.last_good = None;
+            }
+            self.completed
+                .0
+                .lock()
+                .push(compilation_event(id, &pipeline.descriptor, result));
         }
 
         self.pipelines = pipelines;
@@ -684,21 +1085,92 @@ impl PipelineCache {
     pub(crate) fn extract_shaders(
         mut cache: ResMut<Self>,
         shaders: Extract<Res<Assets<Shader>>>,
+        asset_server: Extract<Res<AssetServer>>,
         mut events: Extract<EventReader<AssetEvent<Shader>>>,
     ) {
         for event in events.iter() {
             match event {
                 AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                    if let Some(path) = asset_server.get_handle_path(handle) {
+                        cache
+                            .shader_cache
+                            .error_reporter
+                            .paths
+                            .insert(handle.clone_weak(), path.path().display().to_string());
+                    }
                     if let Some(shader) = shaders.get(handle) {
                         cache.set_shader(handle, shader);
                     }
                 }
-                AssetEvent::Removed { handle } => cache.remove_shader(handle),
+                AssetEvent::Removed { handle } => {
+                    cache.shader_cache.error_reporter.paths.remove(handle);
+                    cache.remove_shader(handle);
+                }
+            }
+        }
+    }
+}
+
+/// Polls the task creating a pipeline, returning its result once the task has completed.
+fn poll_creation<P>(
+    task: &mut Task<Result<P, PipelineCacheError>>,
+) -> Option<Result<P, PipelineCacheError>> {
+    future::block_on(future::poll_once(task))
+}
+
+/// The result reported by the [`PipelineCompilationEvent`] of a pipeline in this `state`, or
+/// `None` if the pipeline is still waiting to be created.
+///
+/// Pipelines missing a shader keep waiting for it to be loaded, while the other errors are logged.
+fn compilation_result(state: &CachedPipelineState) -> Option<Result<(), String>> {
+    match state {
+        CachedPipelineState::Creating(_) => None,
+        CachedPipelineState::Err(err) => {
+            match err {
+                PipelineCacheError::ShaderNotLoaded(_)
+                | PipelineCacheError::ShaderImportNotYetAvailable => {
+                    // retry
+                    return None;
+                }
+                // shader could not be processed ... retrying won't help
+                PipelineCacheError::ProcessShaderError(err) => {
+                    error!("failed to process shader: {}", err);
+                }
+                PipelineCacheError::AsModuleDescriptorError(err, source) => {
+                    log_shader_error(source, err);
+                }
+                PipelineCacheError::CreateShaderModule(description) => {
+                    error!("failed to create shader module: {}", description);
+                }
+                PipelineCacheError::BindGroupLayoutReflectError(err) => {
+                    error!("failed to derive bind group layouts: {}", err);
+                }
             }
+            Some(Err(err.to_string()))
         }
+        CachedPipelineState::Ok(_) | CachedPipelineState::Queued => Some(Ok(())),
     }
 }
 
+/// The event reporting the `result` of the creation of the pipeline at `id`.
+fn compilation_event(
+    id: CachedPipelineId,
+    descriptor: &PipelineDescriptor,
+    result: Result<(), String>,
+) -> PipelineCompilationEvent {
+    let (id, label) = match descriptor {
+        PipelineDescriptor::RenderPipelineDescriptor(descriptor) => (
+            PipelineId::Render(CachedRenderPipelineId(id)),
+            descriptor.label.clone(),
+        ),
+        PipelineDescriptor::ComputePipelineDescriptor(descriptor) => (
+            PipelineId::Compute(CachedComputePipelineId(id)),
+            descriptor.label.clone(),
+        ),
+    };
+    PipelineCompilationEvent { id, label, result }
+}
+
 fn log_shader_error(source: &ProcessedShader, error: &AsModuleDescriptorError) {
     use codespan_reporting::{
         diagnostic::{Diagnostic, Label},
@@ -761,9 +1233,11 @@ fn log_shader_error(source: &ProcessedShader, error: &AsModuleDescriptorError) {
                     .with_labels(
                         error
                             .spans()
-                            .map(|(span, desc)| {
-                                Label::primary((), span.to_range().unwrap())
-                                    .with_message(desc.to_owned())
+                            .filter_map(|(span, desc)| {
+                                Some(
+                                    Label::primary((), span.to_range()?)
+                                        .with_message(desc.to_owned()),
+                                )
                             })
                             .collect(),
                     )
@@ -787,6 +1261,9 @@ fn log_shader_error(source: &ProcessedShader, error: &AsModuleDescriptorError) {
         AsModuleDescriptorError::SpirVConversion(error) => {
             error!("failed to convert shader to spirv: \n{}", error);
         }
+        AsModuleDescriptorError::InvalidSpirV => {
+            error!("failed to process shader: {}", error);
+        }
     }
 }
 
@@ -805,6 +1282,8 @@ pub enum PipelineCacheError {
     ShaderImportNotYetAvailable,
     #[error("Could not create shader module: {0}")]
     CreateShaderModule(String),
+    #[error("Could not derive the bind group layouts from the shaders: {0}")]
+    BindGroupLayoutReflectError(#[from] BindGroupLayoutReflectError),
 }
 
 struct ErrorSources<'a> {
@@ -830,3 +1309,246 @@ impl<'a> Iterator for ErrorSources<'a> {
 }
 
 impl<'a> FusedIterator for ErrorSources<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        compilation_event, compilation_result, error_location, poll_creation,
+        send_pipeline_compilation_events, CachedComputePipelineId, CachedPipelineState,
+        CompletedPipelines, PipelineCache, PipelineCacheError, PipelineCompilationEvent,
+        PipelineDescriptor, PipelineId,
+    };
+    use crate::{
+        render_resource::{
+            ComputePipelineDescriptor, FragmentState, ProcessShaderError, RenderPipelineDescriptor,
+            Shader, ShaderImport, ShaderProcessor, VertexState,
+        },
+        renderer::RenderDevice,
+    };
+    use bevy_asset::{Handle, HandleUntyped};
+    use bevy_ecs::{
+        event::Events,
+        schedule::{Stage, SystemStage},
+        world::World,
+    };
+    use bevy_reflect::TypeUuid;
+    use bevy_tasks::{Task, TaskPool};
+    use bevy_utils::HashMap;
+    use futures_lite::future;
+    use std::sync::mpsc;
+
+    fn wait_for_creation<P>(
+        task: &mut Task<Result<P, PipelineCacheError>>,
+    ) -> Result<P, PipelineCacheError> {
+        loop {
+            if let Some(result) = poll_creation(task) {
+                return result;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn creation_task_completes() {
+        let task_pool = TaskPool::new();
+        let (sender, receiver) = mpsc::channel();
+        let mut task = task_pool.spawn(async move { receiver.recv().unwrap() });
+        // the pipeline is still being created
+        assert!(poll_creation::<u32>(&mut task).is_none());
+        sender.send(Ok(1)).unwrap();
+        assert!(matches!(wait_for_creation(&mut task), Ok(1)));
+
+        let mut task = task_pool
+            .spawn(async { Err::<u32, _>(PipelineCacheError::ShaderImportNotYetAvailable) });
+        assert!(matches!(
+            wait_for_creation(&mut task),
+            Err(PipelineCacheError::ShaderImportNotYetAvailable)
+        ));
+    }
+
+    #[test]
+    fn compilation_results() {
+        let task_pool = TaskPool::new();
+        let creating = CachedPipelineState::Creating(
+            task_pool.spawn(async { Err(PipelineCacheError::ShaderImportNotYetAvailable) }),
+        );
+        assert_eq!(compilation_result(&creating), None);
+
+        // pipelines waiting for their shaders are retried
+        let not_loaded =
+            CachedPipelineState::Err(PipelineCacheError::ShaderNotLoaded(Handle::default()));
+        assert_eq!(compilation_result(&not_loaded), None);
+        let import_not_available =
+            CachedPipelineState::Err(PipelineCacheError::ShaderImportNotYetAvailable);
+        assert_eq!(compilation_result(&import_not_available), None);
+
+        let error = PipelineCacheError::ProcessShaderError(ProcessShaderError::TooManyEndIfs);
+        let message = error.to_string();
+        assert_eq!(
+            compilation_result(&CachedPipelineState::Err(error)),
+            Some(Err(message))
+        );
+    }
+
+    #[test]
+    fn compilation_events_are_sent() {
+        let descriptor =
+            PipelineDescriptor::ComputePipelineDescriptor(Box::new(ComputePipelineDescriptor {
+                label: Some("simulation".into()),
+                layout: None,
+                push_constant_ranges: Vec::new(),
+                shader: Handle::default(),
+                shader_defs: Vec::new(),
+                entry_point: "main".into(),
+            }));
+
+        let mut world = World::new();
+        let completed = CompletedPipelines::default();
+        world.insert_resource(completed.clone());
+        world.init_resource::<Events<PipelineCompilationEvent>>();
+        completed
+            .0
+            .lock()
+            .push(compilation_event(3, &descriptor, Err("error".to_string())));
+
+        let mut stage = SystemStage::single(send_pipeline_compilation_events);
+        stage.run(&mut world);
+        let events = world.resource::<Events<PipelineCompilationEvent>>();
+        let sent = events.iter_current_update_events().collect::<Vec<_>>();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].id, PipelineId::Compute(CachedComputePipelineId(3)));
+        assert_eq!(sent[0].label.as_deref(), Some("simulation"));
+        assert_eq!(sent[0].result, Err("error".to_string()));
+        assert!(completed.0.lock().is_empty());
+
+        // an event is sent once
+        stage.run(&mut world);
+        let events = world.resource::<Events<PipelineCompilationEvent>>();
+        assert_eq!(events.iter_current_update_events().count(), 1);
+    }
+
+    #[test]
+    fn error_location_in_import() {
+        #[rustfmt::skip]
+        const FOO: &str = r"
+fn foo() -> f32 {
+    return 1.0 +;
+}
+";
+        #[rustfmt::skip]
+        const INPUT: &str = r"#import FOO
+fn bar() { }
+";
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        let foo_handle = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1).typed();
+        shaders.insert(foo_handle.clone_weak(), Shader::from_wgsl(FOO));
+        import_handles.insert(
+            ShaderImport::Custom("FOO".to_string()),
+            foo_handle.clone_weak(),
+        );
+        let (processed, source_map) = ShaderProcessor::default()
+            .process_with_source_map(
+                &Handle::default(),
+                &Shader::from_wgsl(INPUT),
+                &[],
+                &shaders,
+                &import_handles,
+            )
+            .unwrap();
+        let Err(error) = processed.get_module_descriptor(wgpu::Features::empty()) else {
+            panic!("the imported shader doesn't parse");
+        };
+        assert_eq!(
+            error_location(&error, &processed, &source_map),
+            Some((&foo_handle, 3, 17))
+        );
+    }
+
+    /// Requests a device to create pipelines with, or returns `None` if there is no adapter.
+    fn request_device() -> Option<RenderDevice> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter =
+            future::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        let (device, _queue) =
+            future::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .ok()?;
+        Some(RenderDevice::from(device))
+    }
+
+    #[test]
+    fn create_pipeline_with_reflected_layout() {
+        #[rustfmt::skip]
+        const SHADER: &str = r"
+struct View {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(2) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var color_sampler: sampler;
+
+@vertex
+fn vertex(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    return view.view_proj * vec4<f32>(f32(index), 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return view.view_proj[0] * textureSample(color_texture, color_sampler, vec2<f32>(0.5, 0.5));
+}
+";
+        let Some(device) = request_device() else {
+            // no adapter to create the pipeline with
+            return;
+        };
+        let mut cache = PipelineCache::new(device.clone(), true);
+        let shader = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2).typed();
+        cache.set_shader(&shader, &Shader::from_wgsl(SHADER));
+        let id = cache.queue_render_pipeline(RenderPipelineDescriptor {
+            label: Some("reflected".into()),
+            layout: None,
+            push_constant_ranges: Vec::new(),
+            vertex: VertexState {
+                shader: shader.clone(),
+                shader_defs: Vec::new(),
+                entry_point: "vertex".into(),
+                buffers: Vec::new(),
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(FragmentState {
+                shader,
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(wgpu::TextureFormat::Rgba8Unorm.into())],
+            }),
+        });
+
+        device
+            .wgpu_device()
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        cache.process_queue();
+        let error = future::block_on(device.wgpu_device().pop_error_scope());
+        assert!(error.is_none(), "{error:?}");
+        assert!(matches!(
+            cache.get_render_pipeline_state(id),
+            CachedPipelineState::Ok(_)
+        ));
+
+        let layouts = cache.get_render_pipeline_bind_group_layouts(id).unwrap();
+        assert_eq!(layouts.len(), 3);
+        // the layouts of the same bindings are shared with other pipelines
+        let other = cache.queue_render_pipeline(cache.get_render_pipeline_descriptor(id).clone());
+        cache.process_queue();
+        let other_layouts = cache.get_render_pipeline_bind_group_layouts(other).unwrap();
+        let layouts = cache.get_render_pipeline_bind_group_layouts(id).unwrap();
+        assert!(layouts
+            .iter()
+            .zip(other_layouts)
+            .all(|(layout, other)| layout.id() == other.id()));
+    }
+}
@@ -14,11 +14,20 @@ use bevy_utils::{
 use std::{fmt::Debug, hash::Hash};
 use thiserror::Error;
 
+/// A render pipeline that can be specialized into different variants, for example by toggling
+/// shader defs, changing the sample count or the target texture format.
+///
+/// Every distinct [`Key`](SpecializedRenderPipeline::Key) produces its own
+/// [`RenderPipelineDescriptor`], which is compiled once and cached by
+/// [`SpecializedRenderPipelines`].
 pub trait SpecializedRenderPipeline {
+    /// The key uniquely identifying a variant of this pipeline.
     type Key: Clone + Hash + PartialEq + Eq;
+    /// Builds the [`RenderPipelineDescriptor`] of the variant described by the `key`.
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor;
 }
 
+/// Caches the pipelines specialized from a [`SpecializedRenderPipeline`] by key.
 #[derive(Resource)]
 pub struct SpecializedRenderPipelines<S: SpecializedRenderPipeline> {
     cache: HashMap<S::Key, CachedRenderPipelineId>,
@@ -31,6 +40,8 @@ impl<S: SpecializedRenderPipeline> Default for SpecializedRenderPipelines<S> {
 }
 
 impl<S: SpecializedRenderPipeline> SpecializedRenderPipelines<S> {
+    /// Returns the id of the pipeline specialized for the `key`. The pipeline is queued for
+    /// creation in the [`PipelineCache`] the first time a key is requested.
     pub fn specialize(
         &mut self,
         cache: &mut PipelineCache,
@@ -44,11 +55,20 @@ impl<S: SpecializedRenderPipeline> SpecializedRenderPipelines<S> {
     }
 }
 
+/// A compute pipeline that can be specialized into different variants, for example by toggling
+/// shader defs.
+///
+/// Every distinct [`Key`](SpecializedComputePipeline::Key) produces its own
+/// [`ComputePipelineDescriptor`], which is compiled once and cached by
+/// [`SpecializedComputePipelines`].
 pub trait SpecializedComputePipeline {
+    /// The key uniquely identifying a variant of this pipeline.
     type Key: Clone + Hash + PartialEq + Eq;
+    /// Builds the [`ComputePipelineDescriptor`] of the variant described by the `key`.
     fn specialize(&self, key: Self::Key) -> ComputePipelineDescriptor;
 }
 
+/// Caches the pipelines specialized from a [`SpecializedComputePipeline`] by key.
 #[derive(Resource)]
 pub struct SpecializedComputePipelines<S: SpecializedComputePipeline> {
     cache: HashMap<S::Key, CachedComputePipelineId>,
@@ -61,6 +81,8 @@ impl<S: SpecializedComputePipeline> Default for SpecializedComputePipelines<S> {
 }
 
 impl<S: SpecializedComputePipeline> SpecializedComputePipelines<S> {
+    /// Returns the id of the pipeline specialized for the `key`. The pipeline is queued for
+    /// creation in the [`PipelineCache`] the first time a key is requested.
     pub fn specialize(
         &mut self,
         cache: &mut PipelineCache,
@@ -74,8 +96,18 @@ impl<S: SpecializedComputePipeline> SpecializedComputePipelines<S> {
     }
 }
 
+/// A render pipeline used to draw meshes, specialized both by a key and by the
+/// [`MeshVertexBufferLayout`] of the mesh being drawn.
+///
+/// This lets materials enable features such as vertex colors or normal maps depending on the
+/// attributes a mesh provides, without hand-writing each shader permutation.
 pub trait SpecializedMeshPipeline {
+    /// The key uniquely identifying a variant of this pipeline for a given mesh layout.
     type Key: Clone + Hash + PartialEq + Eq;
+    /// Builds the [`RenderPipelineDescriptor`] of the variant described by the `key` and the
+    /// mesh `layout`.
+    ///
+    /// Returns an error if the `layout` is missing a vertex attribute required by the pipeline.
     fn specialize(
         &self,
         key: Self::Key,
@@ -83,6 +115,9 @@ pub trait SpecializedMeshPipeline {
     ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError>;
 }
 
+/// Caches the pipelines specialized from a [`SpecializedMeshPipeline`] by mesh layout and key.
+///
+/// Mesh layouts resolving to the same [`VertexBufferLayout`] share their pipelines.
 #[derive(Resource)]
 pub struct SpecializedMeshPipelines<S: SpecializedMeshPipeline> {
     mesh_layout_cache:
@@ -100,6 +135,8 @@ impl<S: SpecializedMeshPipeline> Default for SpecializedMeshPipelines<S> {
 }
 
 impl<S: SpecializedMeshPipeline> SpecializedMeshPipelines<S> {
+    /// Returns the id of the pipeline specialized for the `key` and mesh `layout`. The pipeline is
+    /// queued for creation in the [`PipelineCache`] the first time a combination is requested.
     #[inline]
     pub fn specialize(
         &mut self,
@@ -157,6 +194,7 @@ impl<S: SpecializedMeshPipeline> SpecializedMeshPipelines<S> {
     }
 }
 
+/// An error that occurs when specializing a [`SpecializedMeshPipeline`].
 #[derive(Error, Debug)]
 pub enum SpecializedMeshPipelineError {
     #[error(transparent)]
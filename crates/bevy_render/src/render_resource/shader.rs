@@ -1,15 +1,21 @@
 use bevy_asset::{AssetLoader, AssetPath, Handle, LoadContext, LoadedAsset};
 use bevy_reflect::{TypeUuid, Uuid};
-use bevy_utils::{tracing::error, BoxedFuture, HashMap};
+use bevy_utils::{tracing::error, BoxedFuture, HashMap, HashSet};
 use naga::back::wgsl::WriterFlags;
 use naga::valid::Capabilities;
 use naga::{valid::ModuleInfo, Module};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::{borrow::Cow, marker::Copy, ops::Deref, path::PathBuf, str::FromStr};
+use std::{
+    borrow::Cow, collections::BTreeMap, marker::Copy, num::NonZeroU32, ops::Deref, path::PathBuf,
+    str::FromStr,
+};
 use thiserror::Error;
-use wgpu::Features;
-use wgpu::{util::make_spirv, ShaderModuleDescriptor, ShaderSource};
+use wgpu::{
+    util::make_spirv, BindGroupLayoutEntry, BindingType, BufferBindingType, BufferSize, Features,
+    SamplerBindingType, ShaderModuleDescriptor, ShaderSource, ShaderStages, StorageTextureAccess,
+    TextureFormat, TextureSampleType, TextureViewDimension,
+};
 
 use super::ShaderDefVal;
 
@@ -45,6 +51,7 @@ pub struct Shader {
 }
 
 impl Shader {
+    /// Creates a shader from WGSL source code.
     pub fn from_wgsl(source: impl Into<Cow<'static, str>>) -> Shader {
         let source = source.into();
         let shader_imports = SHADER_IMPORT_PROCESSOR.get_imports_from_str(&source);
@@ -55,6 +62,8 @@ impl Shader {
         }
     }
 
+    /// Creates a shader for a single `stage` from GLSL source code, which [`naga`] translates to
+    /// WGSL once it is processed.
     pub fn from_glsl(source: impl Into<Cow<'static, str>>, stage: naga::ShaderStage) -> Shader {
         let source = source.into();
         let shader_imports = SHADER_IMPORT_PROCESSOR.get_imports_from_str(&source);
@@ -65,6 +74,12 @@ impl Shader {
         }
     }
 
+    /// Creates a shader from a SPIR-V binary, which can't use shader defs or imports.
+    ///
+    /// The SPIR-V is translated for the backend by [`wgpu`], unless the
+    /// [`SPIRV_SHADER_PASSTHROUGH`](wgpu::Features::SPIRV_SHADER_PASSTHROUGH) feature was enabled
+    /// in the [`WgpuSettings`](crate::settings::WgpuSettings), in which case it is given to the
+    /// driver as is, and must be valid for it.
     pub fn from_spirv(source: impl Into<Cow<'static, [u8]>>) -> Shader {
         Shader {
             imports: Vec::new(),
@@ -104,7 +119,7 @@ pub enum Source {
 }
 
 /// A processed [Shader]. This cannot contain preprocessor directions. It must be "ready to compile"
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum ProcessedShader {
     Wgsl(Cow<'static, str>),
     Glsl(Cow<'static, str>, naga::ShaderStage),
@@ -192,7 +207,15 @@ impl ProcessedShader {
                     let wgsl = reflection.get_wgsl()?;
                     ShaderSource::Wgsl(wgsl.into())
                 }
-                ProcessedShader::SpirV(source) => make_spirv(source),
+                ProcessedShader::SpirV(source) => {
+                    let magic_number = source
+                        .get(..4)
+                        .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+                    if source.len() % 4 != 0 || magic_number != Some(SPIRV_MAGIC_NUMBER) {
+                        return Err(AsModuleDescriptorError::InvalidSpirV);
+                    }
+                    make_spirv(source)
+                }
             },
         })
     }
@@ -206,8 +229,15 @@ pub enum AsModuleDescriptorError {
     WgslConversion(#[from] naga::back::wgsl::Error),
     #[error(transparent)]
     SpirVConversion(#[from] naga::back::spv::Error),
+    #[error(
+        "invalid SPIR-V: its size must be a multiple of 4 bytes, starting with the magic number"
+    )]
+    InvalidSpirV,
 }
 
+/// The first word of a SPIR-V module.
+const SPIRV_MAGIC_NUMBER: u32 = 0x0723_0203;
+
 pub struct ShaderReflection {
     pub module: Module,
     pub module_info: ModuleInfo,
@@ -229,6 +259,284 @@ impl ShaderReflection {
     pub fn get_wgsl(&self) -> Result<String, naga::back::wgsl::Error> {
         naga::back::wgsl::write_string(&self.module, &self.module_info, WriterFlags::EXPLICIT_TYPES)
     }
+
+    /// Derives the [`BindGroupLayoutEntry`]s of every bind group used by the shader, keyed and
+    /// sorted by bind group index, so that bind group layouts don't have to be kept in sync with
+    /// the shader by hand.
+    ///
+    /// Only bindings used by at least one entry point are returned, and their visibility is
+    /// derived from the stages of the entry points using them. Some properties cannot be
+    /// inferred from the shader and are set to a default:
+    /// - buffers never have a dynamic offset,
+    /// - float textures are assumed to be filterable and samplers to be filtering,
+    /// - the minimum binding size of buffers is the size of the bound type.
+    pub fn bind_group_layout_entries(
+        &self,
+    ) -> Result<BTreeMap<u32, Vec<BindGroupLayoutEntry>>, BindGroupLayoutReflectError> {
+        let mut bind_groups = BTreeMap::<u32, Vec<BindGroupLayoutEntry>>::new();
+        for (handle, variable) in self.module.global_variables.iter() {
+            let Some(binding) = &variable.binding else {
+                continue;
+            };
+
+            let mut visibility = ShaderStages::NONE;
+            for (index, entry_point) in self.module.entry_points.iter().enumerate() {
+                if !self.module_info.get_entry_point(index)[handle].is_empty() {
+                    visibility |= match entry_point.stage {
+                        naga::ShaderStage::Vertex => ShaderStages::VERTEX,
+                        naga::ShaderStage::Fragment => ShaderStages::FRAGMENT,
+                        naga::ShaderStage::Compute => ShaderStages::COMPUTE,
+                    };
+                }
+            }
+            if visibility.is_empty() {
+                continue;
+            }
+
+            let (ty, count) = match &self.module.types[variable.ty].inner {
+                naga::TypeInner::BindingArray { base, size } => {
+                    let count = match size {
+                        naga::ArraySize::Constant(size) => self.constant_array_length(*size),
+                        naga::ArraySize::Dynamic => None,
+                    };
+                    (*base, count)
+                }
+                _ => (variable.ty, None),
+            };
+            let ty = self.reflect_binding_type(variable.space, ty).ok_or(
+                BindGroupLayoutReflectError::UnsupportedBinding {
+                    group: binding.group,
+                    binding: binding.binding,
+                },
+            )?;
+
+            bind_groups
+                .entry(binding.group)
+                .or_default()
+                .push(BindGroupLayoutEntry {
+                    binding: binding.binding,
+                    visibility,
+                    ty,
+                    count,
+                });
+        }
+
+        for entries in bind_groups.values_mut() {
+            entries.sort_by_key(|entry| entry.binding);
+        }
+        Ok(bind_groups)
+    }
+
+    fn reflect_binding_type(
+        &self,
+        space: naga::AddressSpace,
+        ty: naga::Handle<naga::Type>,
+    ) -> Option<BindingType> {
+        let inner = &self.module.types[ty].inner;
+        match space {
+            naga::AddressSpace::Uniform => Some(BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(inner.size(&self.module.constants) as u64),
+            }),
+            naga::AddressSpace::Storage { access } => Some(BindingType::Buffer {
+                ty: BufferBindingType::Storage {
+                    read_only: !access.contains(naga::StorageAccess::STORE),
+                },
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(inner.size(&self.module.constants) as u64),
+            }),
+            naga::AddressSpace::Handle => match *inner {
+                naga::TypeInner::Sampler { comparison } => {
+                    Some(BindingType::Sampler(if comparison {
+                        SamplerBindingType::Comparison
+                    } else {
+                        SamplerBindingType::Filtering
+                    }))
+                }
+                naga::TypeInner::Image {
+                    dim,
+                    arrayed,
+                    class,
+                } => {
+                    let view_dimension = match (dim, arrayed) {
+                        (naga::ImageDimension::D1, _) => TextureViewDimension::D1,
+                        (naga::ImageDimension::D2, false) => TextureViewDimension::D2,
+                        (naga::ImageDimension::D2, true) => TextureViewDimension::D2Array,
+                        (naga::ImageDimension::D3, _) => TextureViewDimension::D3,
+                        (naga::ImageDimension::Cube, false) => TextureViewDimension::Cube,
+                        (naga::ImageDimension::Cube, true) => TextureViewDimension::CubeArray,
+                    };
+                    match class {
+                        naga::ImageClass::Sampled { kind, multi } => Some(BindingType::Texture {
+                            sample_type: match kind {
+                                naga::ScalarKind::Float => {
+                                    TextureSampleType::Float { filterable: true }
+                                }
+                                naga::ScalarKind::Sint => TextureSampleType::Sint,
+                                naga::ScalarKind::Uint => TextureSampleType::Uint,
+                                naga::ScalarKind::Bool => return None,
+                            },
+                            view_dimension,
+                            multisampled: multi,
+                        }),
+                        naga::ImageClass::Depth { multi } => Some(BindingType::Texture {
+                            sample_type: TextureSampleType::Depth,
+                            view_dimension,
+                            multisampled: multi,
+                        }),
+                        naga::ImageClass::Storage { format, access } => {
+                            Some(BindingType::StorageTexture {
+                                access: if access.contains(naga::StorageAccess::LOAD) {
+                                    if access.contains(naga::StorageAccess::STORE) {
+                                        StorageTextureAccess::ReadWrite
+                                    } else {
+                                        StorageTextureAccess::ReadOnly
+                                    }
+                                } else {
+                                    StorageTextureAccess::WriteOnly
+                                },
+                                format: storage_format_to_texture_format(format),
+                                view_dimension,
+                            })
+                        }
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn constant_array_length(&self, constant: naga::Handle<naga::Constant>) -> Option<NonZeroU32> {
+        match self.module.constants[constant].inner {
+            naga::ConstantInner::Scalar {
+                value: naga::ScalarValue::Uint(value),
+                ..
+            } => NonZeroU32::new(value as u32),
+            naga::ConstantInner::Scalar {
+                value: naga::ScalarValue::Sint(value),
+                ..
+            } => NonZeroU32::new(value as u32),
+            _ => None,
+        }
+    }
+}
+
+/// Returns how much `line` changes the depth of `{}` blocks, ignoring the braces inside `//` and
+/// `/* */` comments.
+///
+/// `comment_depth` is the number of `/* */` comments open at the start of the line, which nest
+/// like in WGSL, and is updated to the number open at its end.
+fn block_depth_change(line: &str, comment_depth: &mut usize) -> isize {
+    let mut change = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                *comment_depth += 1;
+            }
+            ('*', Some('/')) if *comment_depth > 0 => {
+                chars.next();
+                *comment_depth -= 1;
+            }
+            _ if *comment_depth > 0 => {}
+            ('/', Some('/')) => break,
+            ('{', _) => change += 1,
+            ('}', _) => change -= 1,
+            _ => {}
+        }
+    }
+    change
+}
+
+/// Merges the [`BindGroupLayoutEntry`]s reflected from the shader of each stage of a pipeline
+/// with [`ShaderReflection::bind_group_layout_entries`], into the entries of every bind group up
+/// to the last one used, indexed by bind group.
+///
+/// A binding used by several stages is visible to all of them, and must have the same type in
+/// each of them. The bind groups used by none of the stages have no entries.
+pub fn merge_bind_group_layout_entries(
+    stages: impl IntoIterator<Item = BTreeMap<u32, Vec<BindGroupLayoutEntry>>>,
+) -> Result<Vec<Vec<BindGroupLayoutEntry>>, BindGroupLayoutReflectError> {
+    let mut bind_groups = Vec::<Vec<BindGroupLayoutEntry>>::new();
+    for stage in stages {
+        for (group, entries) in stage {
+            if bind_groups.len() <= group as usize {
+                bind_groups.resize_with(group as usize + 1, Vec::new);
+            }
+            let merged = &mut bind_groups[group as usize];
+            for entry in entries {
+                match merged
+                    .iter_mut()
+                    .find(|merged| merged.binding == entry.binding)
+                {
+                    Some(merged) if merged.ty == entry.ty && merged.count == entry.count => {
+                        merged.visibility |= entry.visibility;
+                    }
+                    Some(_) => {
+                        return Err(BindGroupLayoutReflectError::MismatchedBinding {
+                            group,
+                            binding: entry.binding,
+                        });
+                    }
+                    None => merged.push(entry),
+                }
+            }
+        }
+    }
+
+    for entries in &mut bind_groups {
+        entries.sort_by_key(|entry| entry.binding);
+    }
+    Ok(bind_groups)
+}
+
+fn storage_format_to_texture_format(format: naga::StorageFormat) -> TextureFormat {
+    use naga::StorageFormat as Sf;
+    match format {
+        Sf::R8Unorm => TextureFormat::R8Unorm,
+        Sf::R8Snorm => TextureFormat::R8Snorm,
+        Sf::R8Uint => TextureFormat::R8Uint,
+        Sf::R8Sint => TextureFormat::R8Sint,
+        Sf::R16Uint => TextureFormat::R16Uint,
+        Sf::R16Sint => TextureFormat::R16Sint,
+        Sf::R16Float => TextureFormat::R16Float,
+        Sf::Rg8Unorm => TextureFormat::Rg8Unorm,
+        Sf::Rg8Snorm => TextureFormat::Rg8Snorm,
+        Sf::Rg8Uint => TextureFormat::Rg8Uint,
+        Sf::Rg8Sint => TextureFormat::Rg8Sint,
+        Sf::R32Uint => TextureFormat::R32Uint,
+        Sf::R32Sint => TextureFormat::R32Sint,
+        Sf::R32Float => TextureFormat::R32Float,
+        Sf::Rg16Uint => TextureFormat::Rg16Uint,
+        Sf::Rg16Sint => TextureFormat::Rg16Sint,
+        Sf::Rg16Float => TextureFormat::Rg16Float,
+        Sf::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+        Sf::Rgba8Snorm => TextureFormat::Rgba8Snorm,
+        Sf::Rgba8Uint => TextureFormat::Rgba8Uint,
+        Sf::Rgba8Sint => TextureFormat::Rgba8Sint,
+        Sf::Rgb10a2Unorm => TextureFormat::Rgb10a2Unorm,
+        Sf::Rg11b10Float => TextureFormat::Rg11b10Float,
+        Sf::Rg32Uint => TextureFormat::Rg32Uint,
+        Sf::Rg32Sint => TextureFormat::Rg32Sint,
+        Sf::Rg32Float => TextureFormat::Rg32Float,
+        Sf::Rgba16Uint => TextureFormat::Rgba16Uint,
+        Sf::Rgba16Sint => TextureFormat::Rgba16Sint,
+        Sf::Rgba16Float => TextureFormat::Rgba16Float,
+        Sf::Rgba32Uint => TextureFormat::Rgba32Uint,
+        Sf::Rgba32Sint => TextureFormat::Rgba32Sint,
+        Sf::Rgba32Float => TextureFormat::Rgba32Float,
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum BindGroupLayoutReflectError {
+    #[error("the type of binding {binding} in bind group {group} cannot be reflected")]
+    UnsupportedBinding { group: u32, binding: u32 },
+    #[error("binding {binding} in bind group {group} has a different type in each shader stage")]
+    MismatchedBinding { group: u32, binding: u32 },
 }
 
 #[derive(Default)]
@@ -405,12 +713,64 @@ impl Default for ShaderProcessor {
 }
 
 impl ShaderProcessor {
+    /// Processes the `shader`, evaluating conditional directives against `shader_defs` and
+    /// inlining its imports.
+    ///
+    /// Each import at module scope is inlined at most once, so a shader that is imported by several
+    /// of the processed shader's imports does not get duplicated. Imports inside a block, like the
+    /// members of a struct, are inlined every time.
     pub fn process(
         &self,
         shader: &Shader,
         shader_defs: &[ShaderDefVal],
         shaders: &HashMap<Handle<Shader>, Shader>,
         import_handles: &HashMap<ShaderImport, Handle<Shader>>,
+    ) -> Result<ProcessedShader, ProcessShaderError> {
+        self.process_with_source_map(
+            &Handle::default(),
+            shader,
+            shader_defs,
+            shaders,
+            import_handles,
+        )
+        .map(|(processed, _)| processed)
+    }
+
+    /// Processes the `shader` like [`ShaderProcessor::process`], and also returns the
+    /// [`ShaderSourceMap`] telling which shader each line of the processed shader comes from,
+    /// `handle` being the handle of the processed shader itself.
+    pub fn process_with_source_map(
+        &self,
+        handle: &Handle<Shader>,
+        shader: &Shader,
+        shader_defs: &[ShaderDefVal],
+        shaders: &HashMap<Handle<Shader>, Shader>,
+        import_handles: &HashMap<ShaderImport, Handle<Shader>>,
+    ) -> Result<(ProcessedShader, ShaderSourceMap), ProcessShaderError> {
+        let mut imported = HashSet::default();
+        let mut source_map = ShaderSourceMap::default();
+        let processed = self.process_inner(
+            handle,
+            shader,
+            shader_defs,
+            shaders,
+            import_handles,
+            &mut imported,
+            &mut source_map,
+        )?;
+        Ok((processed, source_map))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_inner(
+        &self,
+        handle: &Handle<Shader>,
+        shader: &Shader,
+        shader_defs: &[ShaderDefVal],
+        shaders: &HashMap<Handle<Shader>, Shader>,
+        import_handles: &HashMap<ShaderImport, Handle<Shader>>,
+        imported: &mut HashSet<ShaderImport>,
+        source_map: &mut ShaderSourceMap,
     ) -> Result<ProcessedShader, ProcessShaderError> {
         let shader_str = match &shader.source {
             Source::Wgsl(source) => source.deref(),
@@ -430,8 +790,12 @@ impl ShaderProcessor {
                 }
             }));
         let mut scopes = vec![true];
+        // depth of the `{}` blocks around the current line, and of the `/* */` comments open at
+        // its start
+        let mut block_depth = 0isize;
+        let mut comment_depth = 0usize;
         let mut final_string = String::new();
-        for line in shader_str.lines() {
+        for (line_index, line) in shader_str.lines().enumerate() {
             if let Some(cap) = self.ifdef_regex.captures(line) {
                 let def = cap.get(1).unwrap();
                 scopes
@@ -523,7 +887,10 @@ impl ShaderProcessor {
                         &import,
                         shader,
                         shader_defs,
+                        imported,
+                        block_depth > 0,
                         &mut final_string,
+                        source_map,
                     )?;
                 } else if let Some(cap) = SHADER_IMPORT_PROCESSOR
                     .import_custom_path_regex
@@ -536,7 +903,10 @@ impl ShaderProcessor {
                         &import,
                         shader,
                         shader_defs,
+                        imported,
+                        block_depth > 0,
                         &mut final_string,
+                        source_map,
                     )?;
                 } else if SHADER_IMPORT_PROCESSOR
                     .define_import_path_regex
@@ -563,8 +933,10 @@ impl ShaderProcessor {
                                 .to_string();
                         }
                     }
+                    block_depth += block_depth_change(&line_with_defs, &mut comment_depth);
                     final_string.push_str(&line_with_defs);
                     final_string.push('\n');
+                    source_map.lines.push((handle.clone_weak(), line_index));
                 }
             }
         }
@@ -584,6 +956,7 @@ impl ShaderProcessor {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn apply_import(
         &self,
         import_handles: &HashMap<ShaderImport, Handle<Shader>>,
@@ -591,14 +964,29 @@ impl ShaderProcessor {
         import: &ShaderImport,
         shader: &Shader,
         shader_defs: &[ShaderDefVal],
+        imported: &mut HashSet<ShaderImport>,
+        in_block: bool,
         final_string: &mut String,
+        source_map: &mut ShaderSourceMap,
     ) -> Result<(), ProcessShaderError> {
-        let imported_shader = import_handles
+        // the import has already been inlined (also guards against import cycles)
+        if !in_block && !imported.insert(import.clone()) {
+            return Ok(());
+        }
+
+        let (imported_handle, imported_shader) = import_handles
             .get(import)
-            .and_then(|handle| shaders.get(handle))
+            .and_then(|handle| Some((handle, shaders.get(handle)?)))
             .ok_or_else(|| ProcessShaderError::UnresolvedImport(import.clone()))?;
-        let imported_processed =
-            self.process(imported_shader, shader_defs, shaders, import_handles)?;
+        let imported_processed = self.process_inner(
+            imported_handle,
+            imported_shader,
+            shader_defs,
+            shaders,
+            import_handles,
+            imported,
+            source_map,
+        )?;
 
         match &shader.source {
             Source::Wgsl(_) => {
@@ -624,6 +1012,27 @@ impl ShaderProcessor {
     }
 }
 
+/// Tells which shader each line of a [`ProcessedShader`] was written in, to report the errors
+/// found in the processed shader at their place in the shader files.
+///
+/// Returned by [`ShaderProcessor::process_with_source_map`].
+#[derive(Debug, Clone, Default)]
+pub struct ShaderSourceMap {
+    lines: Vec<(Handle<Shader>, usize)>,
+}
+
+impl ShaderSourceMap {
+    /// Returns the shader the line at `line` of the processed shader comes from, with the index of
+    /// the line in the source of that shader. Both indices start at 0.
+    ///
+    /// The shader is the processed shader itself, or one of the shaders it imports.
+    pub fn original_line(&self, line: usize) -> Option<(&Handle<Shader>, usize)> {
+        self.lines
+            .get(line)
+            .map(|(handle, original_line)| (handle, *original_line))
+    }
+}
+
 /// A reference to a shader asset.
 pub enum ShaderRef {
     /// Use the "default" shader for the current context.
@@ -658,9 +1067,15 @@ mod tests {
     use bevy_reflect::TypeUuid;
     use bevy_utils::HashMap;
     use naga::ShaderStage;
+    use wgpu::{
+        BindingType, BufferBindingType, BufferSize, Features, SamplerBindingType, ShaderStages,
+        TextureSampleType, TextureViewDimension,
+    };
 
+    use super::block_depth_change;
     use crate::render_resource::{
-        ProcessShaderError, Shader, ShaderDefVal, ShaderImport, ShaderProcessor,
+        merge_bind_group_layout_entries, AsModuleDescriptorError, BindGroupLayoutReflectError,
+        ProcessShaderError, ProcessedShader, Shader, ShaderDefVal, ShaderImport, ShaderProcessor,
     };
     #[rustfmt::skip]
 const WGSL: &str = r"
@@ -1000,6 +1415,95 @@ fn bar() { }
         assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED);
     }
 
+    #[test]
+    fn process_import_source_map() {
+        #[rustfmt::skip]
+        const FOO: &str = r"
+fn foo() { }
+";
+        #[rustfmt::skip]
+        const INPUT: &str = r"#ifdef BAR
+fn bar() { }
+#endif
+#import FOO
+fn baz() { }
+";
+        let processor = ShaderProcessor::default();
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        let foo_handle = Handle::<Shader>::default();
+        shaders.insert(foo_handle.clone_weak(), Shader::from_wgsl(FOO));
+        import_handles.insert(
+            ShaderImport::Custom("FOO".to_string()),
+            foo_handle.clone_weak(),
+        );
+        let input_handle = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1).typed();
+        let (result, source_map) = processor
+            .process_with_source_map(
+                &input_handle,
+                &Shader::from_wgsl(INPUT),
+                &[],
+                &shaders,
+                &import_handles,
+            )
+            .unwrap();
+        assert_eq!(
+            result.get_wgsl_source().unwrap(),
+            "\nfn foo() { }\nfn baz() { }\n"
+        );
+        assert_eq!(source_map.original_line(0), Some((&foo_handle, 0)));
+        assert_eq!(source_map.original_line(1), Some((&foo_handle, 1)));
+        assert_eq!(source_map.original_line(2), Some((&input_handle, 4)));
+        assert_eq!(source_map.original_line(3), None);
+    }
+
+    #[test]
+    fn spirv_and_glsl_module_descriptors() {
+        #[rustfmt::skip]
+        const WGSL: &str = r"
+@compute @workgroup_size(1)
+fn main() { }
+";
+        #[rustfmt::skip]
+        const GLSL: &str = r"
+#version 450
+layout(location = 0) out vec4 o_Target;
+void main() {
+    o_Target = vec4(1.0);
+}
+";
+        let spirv = ProcessedShader::Wgsl(WGSL.into())
+            .reflect(Features::empty())
+            .unwrap()
+            .get_spirv()
+            .unwrap();
+        let spirv: Vec<u8> = spirv.iter().flat_map(|word| word.to_ne_bytes()).collect();
+        assert!(ProcessedShader::SpirV(spirv.clone().into())
+            .get_module_descriptor(Features::empty())
+            .is_ok());
+        for invalid in [&spirv[..spirv.len() - 1], &spirv[4..], &[]] {
+            assert!(matches!(
+                ProcessedShader::SpirV(invalid.to_vec().into())
+                    .get_module_descriptor(Features::empty()),
+                Err(AsModuleDescriptorError::InvalidSpirV)
+            ));
+        }
+
+        let processor = ShaderProcessor::default();
+        let glsl = processor
+            .process(
+                &Shader::from_glsl(GLSL, ShaderStage::Fragment),
+                &[],
+                &HashMap::default(),
+                &HashMap::default(),
+            )
+            .unwrap();
+        let Ok(descriptor) = glsl.get_module_descriptor(Features::empty()) else {
+            panic!("the GLSL shader doesn't translate to WGSL");
+        };
+        assert!(matches!(descriptor.source, wgpu::ShaderSource::Wgsl(_)));
+    }
+
     #[test]
     fn process_import_glsl() {
         #[rustfmt::skip]
@@ -1386,6 +1890,165 @@ fn in_main() { }
         assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED);
     }
 
+    #[test]
+    fn process_import_once() {
+        #[rustfmt::skip]
+        const BAR: &str = r"
+fn bar() { }
+";
+        #[rustfmt::skip]
+        const FOO: &str = r"
+#import BAR
+fn foo() { }
+";
+        #[rustfmt::skip]
+        const INPUT: &str = r"
+#import BAR
+#import FOO
+fn in_main() { }
+";
+        #[rustfmt::skip]
+        const EXPECTED: &str = r"
+
+fn bar() { }
+
+fn foo() { }
+fn in_main() { }
+";
+        let processor = ShaderProcessor::default();
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        {
+            let bar_handle = Handle::<Shader>::default();
+            shaders.insert(bar_handle.clone_weak(), Shader::from_wgsl(BAR));
+            import_handles.insert(
+                ShaderImport::Custom("BAR".to_string()),
+                bar_handle.clone_weak(),
+            );
+        }
+        {
+            let foo_handle = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1).typed();
+            shaders.insert(foo_handle.clone_weak(), Shader::from_wgsl(FOO));
+            import_handles.insert(
+                ShaderImport::Custom("FOO".to_string()),
+                foo_handle.clone_weak(),
+            );
+        }
+        let result = processor
+            .process(&Shader::from_wgsl(INPUT), &[], &shaders, &import_handles)
+            .unwrap();
+        assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn process_import_in_block_every_time() {
+        #[rustfmt::skip]
+        const FIELDS: &str = r"
+    a: f32,
+";
+        #[rustfmt::skip]
+        const INPUT: &str = r"
+struct Foo {
+    #import FIELDS
+};
+struct Bar {
+    #import FIELDS
+};
+";
+        #[rustfmt::skip]
+        const EXPECTED: &str = r"
+struct Foo {
+
+    a: f32,
+};
+struct Bar {
+
+    a: f32,
+};
+";
+        let processor = ShaderProcessor::default();
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        let fields_handle = Handle::<Shader>::default();
+        shaders.insert(fields_handle.clone_weak(), Shader::from_wgsl(FIELDS));
+        import_handles.insert(
+            ShaderImport::Custom("FIELDS".to_string()),
+            fields_handle.clone_weak(),
+        );
+        let result = processor
+            .process(&Shader::from_wgsl(INPUT), &[], &shaders, &import_handles)
+            .unwrap();
+        assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn process_import_after_comments_with_braces() {
+        #[rustfmt::skip]
+        const BAR: &str = r"
+fn bar() { }
+";
+        #[rustfmt::skip]
+        const FIELDS: &str = r"
+    a: f32,
+";
+        #[rustfmt::skip]
+        const INPUT: &str = r"
+// an opening brace { in a comment
+#import BAR
+/* another one {
+   /* in a nested comment } */ and { */
+#import BAR
+struct Foo { // }
+    #import FIELDS
+};
+struct Bar { /* } */
+    #import FIELDS
+};
+";
+        #[rustfmt::skip]
+        const EXPECTED: &str = r"
+// an opening brace { in a comment
+
+fn bar() { }
+/* another one {
+   /* in a nested comment } */ and { */
+struct Foo { // }
+
+    a: f32,
+};
+struct Bar { /* } */
+
+    a: f32,
+};
+";
+        let processor = ShaderProcessor::default();
+        let mut shaders = HashMap::default();
+        let mut import_handles = HashMap::default();
+        for (id, name, source) in [(1, "BAR", BAR), (2, "FIELDS", FIELDS)] {
+            let handle = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, id).typed();
+            shaders.insert(handle.clone_weak(), Shader::from_wgsl(source));
+            import_handles.insert(ShaderImport::Custom(name.to_string()), handle);
+        }
+        let result = processor
+            .process(&Shader::from_wgsl(INPUT), &[], &shaders, &import_handles)
+            .unwrap();
+        assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn block_depth_ignores_comments() {
+        let mut comment_depth = 0;
+        assert_eq!(block_depth_change("fn foo() { // }", &mut comment_depth), 1);
+        assert_eq!(block_depth_change("} /* { /* {", &mut comment_depth), -1);
+        assert_eq!(comment_depth, 2);
+        assert_eq!(block_depth_change("} */ { */ {", &mut comment_depth), 1);
+        assert_eq!(comment_depth, 0);
+        assert_eq!(
+            block_depth_change("let a = 1 / 2; }", &mut comment_depth),
+            -1
+        );
+    }
+
     #[test]
     fn process_import_in_ifdef() {
         #[rustfmt::skip]
@@ -1959,4 +2622,149 @@ fn vertex(
             .unwrap();
         assert_eq!(result.get_wgsl_source().unwrap(), EXPECTED_REPLACED);
     }
+
+    #[test]
+    fn reflect_bind_group_layout_entries() {
+        #[rustfmt::skip]
+        const WGSL: &str = r"
+struct View {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> view: View;
+
+struct Lights {
+    data: array<vec4<f32>>,
+};
+@group(1) @binding(2)
+var<storage> lights: Lights;
+@group(1) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var color_sampler: sampler;
+@group(1) @binding(3)
+var<uniform> unused: View;
+
+@vertex
+fn vertex(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return view.view_proj * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return textureSample(color_texture, color_sampler, vec2<f32>(0.5, 0.5)) * lights.data[0];
+}
+";
+        let reflection = ProcessedShader::Wgsl(WGSL.into())
+            .reflect(Features::empty())
+            .unwrap();
+        let bind_groups = reflection.bind_group_layout_entries().unwrap();
+        assert_eq!(bind_groups.len(), 2);
+
+        let view_entries = &bind_groups[&0];
+        assert_eq!(view_entries.len(), 1);
+        assert_eq!(view_entries[0].visibility, ShaderStages::VERTEX);
+        assert_eq!(
+            view_entries[0].ty,
+            BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(64),
+            }
+        );
+
+        let material_entries = &bind_groups[&1];
+        assert_eq!(
+            material_entries
+                .iter()
+                .map(|entry| entry.binding)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(material_entries
+            .iter()
+            .all(|entry| entry.visibility == ShaderStages::FRAGMENT));
+        assert_eq!(
+            material_entries[0].ty,
+            BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            material_entries[1].ty,
+            BindingType::Sampler(SamplerBindingType::Filtering)
+        );
+        assert_eq!(
+            material_entries[2].ty,
+            BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(16),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_reflected_stages() {
+        #[rustfmt::skip]
+        const VERTEX: &str = r"
+struct View {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> view: View;
+
+@vertex
+fn vertex(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return view.view_proj * vec4<f32>(position, 1.0);
+}
+";
+        #[rustfmt::skip]
+        const FRAGMENT: &str = r"
+struct View {
+    view_proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> view: View;
+@group(2) @binding(0)
+var color_texture: texture_2d<f32>;
+
+@fragment
+fn fragment() -> @location(0) vec4<f32> {
+    return view.view_proj[0] * textureLoad(color_texture, vec2<i32>(0, 0), 0);
+}
+";
+        let reflect = |source: &'static str| {
+            ProcessedShader::Wgsl(source.into())
+                .reflect(Features::empty())
+                .unwrap()
+                .bind_group_layout_entries()
+                .unwrap()
+        };
+
+        let bind_groups =
+            merge_bind_group_layout_entries([reflect(VERTEX), reflect(FRAGMENT)]).unwrap();
+        assert_eq!(bind_groups.len(), 3);
+        assert_eq!(bind_groups[0].len(), 1);
+        assert_eq!(
+            bind_groups[0][0].visibility,
+            ShaderStages::VERTEX | ShaderStages::FRAGMENT
+        );
+        assert!(bind_groups[1].is_empty());
+        assert_eq!(bind_groups[2].len(), 1);
+        assert_eq!(bind_groups[2][0].visibility, ShaderStages::FRAGMENT);
+
+        // the same binding can't have another type in another stage
+        let mut mismatched = reflect(FRAGMENT);
+        mismatched.get_mut(&0).unwrap()[0].ty = BindingType::Sampler(SamplerBindingType::Filtering);
+        assert!(matches!(
+            merge_bind_group_layout_entries([reflect(VERTEX), mismatched]),
+            Err(BindGroupLayoutReflectError::MismatchedBinding {
+                group: 0,
+                binding: 0
+            })
+        ));
+    }
 }
@@ -1,12 +1,14 @@
 #![allow(clippy::doc_markdown)]
 
-use super::Buffer;
-use crate::renderer::{RenderDevice, RenderQueue};
+use super::{Buffer, CachedBindingResource};
+use crate::renderer::{RenderDevice, RenderQueue, StagingBelt};
 use encase::{
     internal::WriteInto, DynamicStorageBuffer as DynamicStorageBufferWrapper, ShaderType,
     StorageBuffer as StorageBufferWrapper,
 };
-use wgpu::{util::BufferInitDescriptor, BindingResource, BufferBinding, BufferUsages};
+use wgpu::{
+    util::BufferInitDescriptor, BindingResource, BufferBinding, BufferDescriptor, BufferUsages,
+};
 
 /// Stores data to be transferred to the GPU and made accessible to shaders as a storage buffer.
 ///
@@ -75,6 +77,13 @@ impl<T: ShaderType + WriteInto> StorageBuffer<T> {
         ))
     }
 
+    /// The binding of the whole buffer, to create bind groups through the
+    /// [`BindGroupCache`](super::BindGroupCache).
+    #[inline]
+    pub fn cached_binding(&self) -> Option<CachedBindingResource> {
+        Some(CachedBindingResource::entire_buffer(self.buffer()?))
+    }
+
     pub fn set(&mut self, value: T) {
         self.value = value;
     }
@@ -107,6 +116,18 @@ impl<T: ShaderType + WriteInto> StorageBuffer<T> {
     /// If there is no GPU-side buffer allocated to hold the data currently stored, or if a GPU-side buffer previously
     /// allocated does not have enough capacity, a new GPU-side buffer is created.
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.write_with(device, |buffer, data| queue.write_buffer(buffer, 0, data));
+    }
+
+    /// Like [`write_buffer`](Self::write_buffer), but copies the data through the [`StagingBelt`],
+    /// along with the other uploads of the frame.
+    pub fn write_buffer_staged(&mut self, device: &RenderDevice, staging_belt: &StagingBelt) {
+        self.write_with(device, |buffer, data| {
+            staging_belt.write_buffer(device, buffer, 0, data);
+        });
+    }
+
+    fn write_with(&mut self, device: &RenderDevice, write: impl FnOnce(&Buffer, &[u8])) {
         self.scratch.write(&self.value).unwrap();
 
         let size = self.scratch.as_ref().len();
@@ -120,7 +141,7 @@ impl<T: ShaderType + WriteInto> StorageBuffer<T> {
             self.capacity = size;
             self.label_changed = false;
         } else if let Some(buffer) = &self.buffer {
-            queue.write_buffer(buffer, 0, self.scratch.as_ref());
+            write(buffer, self.scratch.as_ref());
         }
     }
 }
@@ -156,10 +177,28 @@ pub struct DynamicStorageBuffer<T: ShaderType> {
 }
 
 impl<T: ShaderType> Default for DynamicStorageBuffer<T> {
+    /// Creates a buffer aligning its values to 256 bytes, which is a valid dynamic offset on every
+    /// device.
     fn default() -> Self {
+        Self::with_alignment(256)
+    }
+}
+
+impl<T: ShaderType> DynamicStorageBuffer<T> {
+    /// Creates a buffer aligning its values to the
+    /// [`min_storage_buffer_offset_alignment`](wgpu::Limits::min_storage_buffer_offset_alignment) of the
+    /// `render_device`, which packs them tighter than the default buffer on most devices.
+    pub fn new(render_device: &RenderDevice) -> Self {
+        Self::with_alignment(render_device.limits().min_storage_buffer_offset_alignment as u64)
+    }
+
+    /// Creates a buffer aligning its values to `alignment` bytes, which needs to be a multiple of
+    /// the [`min_storage_buffer_offset_alignment`](wgpu::Limits::min_storage_buffer_offset_alignment) of
+    /// the device for the offsets returned by [`push`](Self::push) to be valid dynamic offsets.
+    pub fn with_alignment(alignment: u64) -> Self {
         Self {
             values: Vec::new(),
-            scratch: DynamicStorageBufferWrapper::new(Vec::new()),
+            scratch: DynamicStorageBufferWrapper::new_with_alignment(Vec::new(), alignment),
             buffer: None,
             capacity: 0,
             label: None,
@@ -183,6 +222,17 @@ impl<T: ShaderType + WriteInto> DynamicStorageBuffer<T> {
         }))
     }
 
+    /// The binding of a single element of the buffer, to create bind groups with a dynamic
+    /// offset through the [`BindGroupCache`](super::BindGroupCache).
+    #[inline]
+    pub fn cached_binding(&self) -> Option<CachedBindingResource> {
+        Some(CachedBindingResource::Buffer {
+            buffer: self.buffer()?,
+            offset: 0,
+            size: Some(T::min_size()),
+        })
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.values.len()
@@ -216,18 +266,37 @@ impl<T: ShaderType + WriteInto> DynamicStorageBuffer<T> {
 
     #[inline]
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.write_with(device, |buffer, data| queue.write_buffer(buffer, 0, data));
+    }
+
+    /// Like [`write_buffer`](Self::write_buffer), but copies the data through the [`StagingBelt`],
+    /// along with the other uploads of the frame.
+    #[inline]
+    pub fn write_buffer_staged(&mut self, device: &RenderDevice, staging_belt: &StagingBelt) {
+        self.write_with(device, |buffer, data| {
+            staging_belt.write_buffer(device, buffer, 0, data);
+        });
+    }
+
+    fn write_with(&mut self, device: &RenderDevice, write: impl FnOnce(&Buffer, &[u8])) {
         let size = self.scratch.as_ref().len();
 
         if self.capacity < size || self.label_changed {
-            self.buffer = Some(device.create_buffer_with_data(&BufferInitDescriptor {
+            // The buffer at least doubles in size when it grows, so that values pushed a few more
+            // at a time, frame after frame, don't need a new buffer each frame
+            if self.capacity < size {
+                self.capacity = size.max(self.capacity * 2);
+            }
+            self.buffer = Some(device.create_buffer(&BufferDescriptor {
                 label: self.label.as_deref(),
+                size: self.capacity as u64,
                 usage: BufferUsages::COPY_DST | BufferUsages::STORAGE,
-                contents: self.scratch.as_ref(),
+                mapped_at_creation: false,
             }));
-            self.capacity = size;
             self.label_changed = false;
-        } else if let Some(buffer) = &self.buffer {
-            queue.write_buffer(buffer, 0, self.scratch.as_ref());
+        }
+        if let Some(buffer) = &self.buffer {
+            write(buffer, self.scratch.as_ref());
         }
     }
 
@@ -238,3 +307,25 @@ impl<T: ShaderType + WriteInto> DynamicStorageBuffer<T> {
         self.scratch.set_offset(0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::Vec4;
+
+    #[test]
+    fn dynamic_offset_alignment() {
+        let mut buffer = DynamicStorageBuffer::<Vec4>::with_alignment(64);
+        let offsets = [Vec4::ZERO, Vec4::ONE, Vec4::X].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 64, 128]);
+
+        let mut buffer = DynamicStorageBuffer::<Vec4>::default();
+        let offsets = [Vec4::ZERO, Vec4::ONE].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 256]);
+
+        // values larger than the alignment are padded to a multiple of it
+        let mut buffer = DynamicStorageBuffer::<[Vec4; 5]>::with_alignment(64);
+        let offsets = [[Vec4::ZERO; 5], [Vec4::ONE; 5]].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 128]);
+    }
+}
@@ -1,12 +1,14 @@
 use crate::{
-    render_resource::Buffer,
-    renderer::{RenderDevice, RenderQueue},
+    render_resource::{Buffer, CachedBindingResource},
+    renderer::{RenderDevice, RenderQueue, StagingBelt},
 };
 use encase::{
     internal::WriteInto, DynamicUniformBuffer as DynamicUniformBufferWrapper, ShaderType,
     UniformBuffer as UniformBufferWrapper,
 };
-use wgpu::{util::BufferInitDescriptor, BindingResource, BufferBinding, BufferUsages};
+use wgpu::{
+    util::BufferInitDescriptor, BindingResource, BufferBinding, BufferDescriptor, BufferUsages,
+};
 
 /// Stores data to be transferred to the GPU and made accessible to shaders as a uniform buffer.
 ///
@@ -72,6 +74,13 @@ impl<T: ShaderType + WriteInto> UniformBuffer<T> {
         ))
     }
 
+    /// The binding of the whole buffer, to create bind groups through the
+    /// [`BindGroupCache`](super::BindGroupCache).
+    #[inline]
+    pub fn cached_binding(&self) -> Option<CachedBindingResource> {
+        Some(CachedBindingResource::entire_buffer(self.buffer()?))
+    }
+
     /// Set the data the buffer stores.
     pub fn set(&mut self, value: T) {
         self.value = value;
@@ -105,6 +114,18 @@ impl<T: ShaderType + WriteInto> UniformBuffer<T> {
     /// If a GPU-side buffer does not already exist for this data, such a buffer is initialized with currently
     /// available data.
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.write_with(device, |buffer, data| queue.write_buffer(buffer, 0, data));
+    }
+
+    /// Like [`write_buffer`](Self::write_buffer), but copies the data through the [`StagingBelt`],
+    /// along with the other uploads of the frame.
+    pub fn write_buffer_staged(&mut self, device: &RenderDevice, staging_belt: &StagingBelt) {
+        self.write_with(device, |buffer, data| {
+            staging_belt.write_buffer(device, buffer, 0, data);
+        });
+    }
+
+    fn write_with(&mut self, device: &RenderDevice, write: impl FnOnce(&Buffer, &[u8])) {
         self.scratch.write(&self.value).unwrap();
 
         if self.label_changed || self.buffer.is_none() {
@@ -115,7 +136,7 @@ impl<T: ShaderType + WriteInto> UniformBuffer<T> {
             }));
             self.label_changed = false;
         } else if let Some(buffer) = &self.buffer {
-            queue.write_buffer(buffer, 0, self.scratch.as_ref());
+            write(buffer, self.scratch.as_ref());
         }
     }
 }
@@ -149,10 +170,28 @@ pub struct DynamicUniformBuffer<T: ShaderType> {
 }
 
 impl<T: ShaderType> Default for DynamicUniformBuffer<T> {
+    /// Creates a buffer aligning its values to 256 bytes, which is a valid dynamic offset on every
+    /// device.
     fn default() -> Self {
+        Self::with_alignment(256)
+    }
+}
+
+impl<T: ShaderType> DynamicUniformBuffer<T> {
+    /// Creates a buffer aligning its values to the
+    /// [`min_uniform_buffer_offset_alignment`](wgpu::Limits::min_uniform_buffer_offset_alignment) of the
+    /// `render_device`, which packs them tighter than the default buffer on most devices.
+    pub fn new(render_device: &RenderDevice) -> Self {
+        Self::with_alignment(render_device.limits().min_uniform_buffer_offset_alignment as u64)
+    }
+
+    /// Creates a buffer aligning its values to `alignment` bytes, which needs to be a multiple of
+    /// the [`min_uniform_buffer_offset_alignment`](wgpu::Limits::min_uniform_buffer_offset_alignment) of
+    /// the device for the offsets returned by [`push`](Self::push) to be valid dynamic offsets.
+    pub fn with_alignment(alignment: u64) -> Self {
         Self {
             values: Vec::new(),
-            scratch: DynamicUniformBufferWrapper::new(Vec::new()),
+            scratch: DynamicUniformBufferWrapper::new_with_alignment(Vec::new(), alignment),
             buffer: None,
             capacity: 0,
             label: None,
@@ -176,6 +215,17 @@ impl<T: ShaderType + WriteInto> DynamicUniformBuffer<T> {
         }))
     }
 
+    /// The binding of a single element of the buffer, to create bind groups with a dynamic
+    /// offset through the [`BindGroupCache`](super::BindGroupCache).
+    #[inline]
+    pub fn cached_binding(&self) -> Option<CachedBindingResource> {
+        Some(CachedBindingResource::Buffer {
+            buffer: self.buffer()?,
+            offset: 0,
+            size: Some(T::min_size()),
+        })
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.values.len()
@@ -215,18 +265,37 @@ impl<T: ShaderType + WriteInto> DynamicUniformBuffer<T> {
     /// allocated does not have enough capacity, a new GPU-side buffer is created.
     #[inline]
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.write_with(device, |buffer, data| queue.write_buffer(buffer, 0, data));
+    }
+
+    /// Like [`write_buffer`](Self::write_buffer), but copies the data through the [`StagingBelt`],
+    /// along with the other uploads of the frame.
+    #[inline]
+    pub fn write_buffer_staged(&mut self, device: &RenderDevice, staging_belt: &StagingBelt) {
+        self.write_with(device, |buffer, data| {
+            staging_belt.write_buffer(device, buffer, 0, data);
+        });
+    }
+
+    fn write_with(&mut self, device: &RenderDevice, write: impl FnOnce(&Buffer, &[u8])) {
         let size = self.scratch.as_ref().len();
 
         if self.capacity < size || self.label_changed {
-            self.buffer = Some(device.create_buffer_with_data(&BufferInitDescriptor {
+            // The buffer at least doubles in size when it grows, so that values pushed a few more
+            // at a time, frame after frame, don't need a new buffer each frame
+            if self.capacity < size {
+                self.capacity = size.max(self.capacity * 2);
+            }
+            self.buffer = Some(device.create_buffer(&BufferDescriptor {
                 label: self.label.as_deref(),
+                size: self.capacity as u64,
                 usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
-                contents: self.scratch.as_ref(),
+                mapped_at_creation: false,
             }));
-            self.capacity = size;
             self.label_changed = false;
-        } else if let Some(buffer) = &self.buffer {
-            queue.write_buffer(buffer, 0, self.scratch.as_ref());
+        }
+        if let Some(buffer) = &self.buffer {
+            write(buffer, self.scratch.as_ref());
         }
     }
 
@@ -237,3 +306,25 @@ impl<T: ShaderType + WriteInto> DynamicUniformBuffer<T> {
         self.scratch.set_offset(0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::Vec4;
+
+    #[test]
+    fn dynamic_offset_alignment() {
+        let mut buffer = DynamicUniformBuffer::<Vec4>::with_alignment(64);
+        let offsets = [Vec4::ZERO, Vec4::ONE, Vec4::X].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 64, 128]);
+
+        let mut buffer = DynamicUniformBuffer::<Vec4>::default();
+        let offsets = [Vec4::ZERO, Vec4::ONE].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 256]);
+
+        // values larger than the alignment are padded to a multiple of it
+        let mut buffer = DynamicUniformBuffer::<[Vec4; 5]>::with_alignment(64);
+        let offsets = [[Vec4::ZERO; 5], [Vec4::ONE; 5]].map(|value| buffer.push(value));
+        assert_eq!(offsets, [0, 128]);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{
+    render_resource::Buffer,
+    renderer::{RenderDevice, RenderQueue},
+    RenderApp, RenderStage,
+};
+use bevy_app::{App, Plugin};
+use bevy_diagnostic::{Diagnostic, DiagnosticId, Diagnostics};
+use bevy_ecs::system::{Res, ResMut, Resource};
+use bevy_utils::{tracing::warn, FixedState, HashMap};
+use parking_lot::Mutex;
+use std::{borrow::Cow, hash::BuildHasher, sync::Arc};
+use wgpu::{BufferAsyncError, CommandEncoder};
+
+/// The maximum number of timestamps written per frame, two for each render graph node run.
+const MAX_TIMESTAMPS: u32 = 256;
+
+/// Adds diagnostics measuring how long the GPU spends on each node of the render graph, in
+/// milliseconds.
+///
+/// The diagnostics are named after the nodes, prefixed with the name of their sub graph, for example
+/// `core_3d/main_pass`. Their ids are given by [`GpuTimestampDiagnosticsPlugin::diagnostic_id`].
+/// A node run several times per frame, like the nodes of a sub graph run for each camera, measures
+/// the sum of its runs.
+///
+/// The timings are written with timestamp queries, which requires the
+/// [`WgpuFeatures::TIMESTAMP_QUERY`](crate::settings::WgpuFeatures::TIMESTAMP_QUERY) feature.
+/// It is enabled by default when the adapter supports it, or can be added to the
+/// [`WgpuSettings::optional_features`](crate::settings::WgpuSettings::optional_features) with
+/// other priorities. Nothing is measured otherwise.
+#[derive(Default)]
+pub struct GpuTimestampDiagnosticsPlugin;
+
+impl Plugin for GpuTimestampDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let measurements = GpuTimestampMeasurements::default();
+        app.insert_resource(measurements.clone())
+            .add_system(Self::diagnostic_system);
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            let render_device = render_app.world.resource::<RenderDevice>();
+            if !render_device
+                .features()
+                .contains(wgpu::Features::TIMESTAMP_QUERY)
+            {
+                warn!("GPU timestamp diagnostics require the TIMESTAMP_QUERY feature, which isn't enabled");
+                return;
+            }
+            let period = render_app
+                .world
+                .resource::<RenderQueue>()
+                .get_timestamp_period();
+            let timestamps = GpuTimestamps::new(render_device, period, measurements);
+            render_app
+                .insert_resource(timestamps)
+                .add_system_to_stage(RenderStage::Cleanup, read_gpu_timestamps);
+        }
+    }
+}
+
+impl GpuTimestampDiagnosticsPlugin {
+    /// Returns the id of the diagnostic measuring the render graph node with the given `name`.
+    pub fn diagnostic_id(name: &str) -> DiagnosticId {
+        let hash = FixedState.hash_one(name);
+        DiagnosticId::from_u128((0x2c4f_5b8e_91d3_4a67_u128 << 64) | u128::from(hash))
+    }
+
+    pub fn diagnostic_system(
+        mut diagnostics: ResMut<Diagnostics>,
+        measurements: Res<GpuTimestampMeasurements>,
+    ) {
+        for (name, duration) in measurements.0.lock().drain(..) {
+            let id = Self::diagnostic_id(&name);
+            if diagnostics.get(id).is_none() {
+                diagnostics.add(Diagnostic::new(id, name, 20).with_suffix("ms"));
+            }
+            diagnostics.add_measurement(id, || duration);
+        }
+    }
+}
+
+/// Node timings read back in the render world, waiting to be added as diagnostics.
+#[derive(Resource, Clone, Default)]
+pub struct GpuTimestampMeasurements(Arc<Mutex<Vec<(Cow<'static, str>, f64)>>>);
+
+struct PendingTimestamps {
+    names: Vec<Cow<'static, str>>,
+    buffer: Buffer,
+    map_result: Arc<Mutex<Option<Result<(), BufferAsyncError>>>>,
+}
+
+/// The names of the nodes measured this frame, the node at index `i` being measured by the
+/// timestamps `2 * i` and `2 * i + 1`.
+///
+/// Nodes recorded in parallel reserve their timestamps before running, so each node writes its
+/// own pair of timestamps however their runs overlap.
+#[derive(Default)]
+struct TimestampSlots(Mutex<Vec<Cow<'static, str>>>);
+
+impl TimestampSlots {
+    /// Reserves the pair of timestamps measuring the node named `name`, returning the index of
+    /// the first one, or `None` if all the timestamps of this frame are taken.
+    fn reserve(&self, name: Cow<'static, str>) -> Option<u32> {
+        let mut names = self.0.lock();
+        let index = names.len() as u32 * 2;
+        if index + 2 > MAX_TIMESTAMPS {
+            return None;
+        }
+        names.push(name);
+        Some(index)
+    }
+
+    /// Takes the names of the nodes measured this frame, freeing all the timestamps.
+    fn take(&self) -> Vec<Cow<'static, str>> {
+        std::mem::take(&mut *self.0.lock())
+    }
+}
+
+/// The timestamp queries written around the render graph nodes, in the render world.
+///
+/// The [`RenderGraphRunner`](super::RenderGraphRunner) writes them while running the graph when
+/// this resource exists.
+#[derive(Resource)]
+pub struct GpuTimestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: Buffer,
+    period: f32,
+    slots: TimestampSlots,
+    /// The timestamps resolved this frame, mapped once they have been submitted.
+    resolved: Mutex<Option<PendingTimestamps>>,
+    pending: Mutex<Vec<PendingTimestamps>>,
+    measurements: GpuTimestampMeasurements,
+}
+
+impl GpuTimestamps {
+    fn new(
+        render_device: &RenderDevice,
+        period: f32,
+        measurements: GpuTimestampMeasurements,
+    ) -> Self {
+        let query_set = render_device
+            .wgpu_device()
+            .create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("gpu_timestamps_query_set"),
+                ty: wgpu::QueryType::Timestamp,
+                count: MAX_TIMESTAMPS,
+            });
+        let resolve_buffer = render_device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu_timestamps_resolve_buffer"),
+            size: u64::from(MAX_TIMESTAMPS) * 8,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        Self {
+            query_set,
+            resolve_buffer,
+            period,
+            slots: TimestampSlots::default(),
+            resolved: Mutex::default(),
+            pending: Mutex::default(),
+            measurements,
+        }
+    }
+
+    /// Writes the timestamp before running the node named `name`, returning its index if there
+    /// is room left for the node this frame.
+    pub(crate) fn begin(
+        &self,
+        command_encoder: &mut CommandEncoder,
+        name: Cow<'static, str>,
+    ) -> Option<u32> {
+        let index = self.slots.reserve(name)?;
+        command_encoder.write_timestamp(&self.query_set, index);
+        Some(index)
+    }
+
+    /// Writes the timestamp after running the node started at `index`.
+    pub(crate) fn end(&self, command_encoder: &mut CommandEncoder, index: u32) {
+        command_encoder.write_timestamp(&self.query_set, index + 1);
+    }
+
+    /// Resolves the timestamps written this frame into a buffer that is read back once the frame
+    /// has been submitted.
+    pub(crate) fn resolve(
+        &self,
+        render_device: &RenderDevice,
+        command_encoder: &mut CommandEncoder,
+    ) {
+        let names = self.slots.take();
+        if names.is_empty() {
+            return;
+        }
+        let count = names.len() as u32 * 2;
+        let buffer = render_device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("gpu_timestamps_readback_buffer"),
+            size: u64::from(count) * 8,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        command_encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        command_encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &buffer,
+            0,
+            u64::from(count) * 8,
+        );
+        *self.resolved.lock() = Some(PendingTimestamps {
+            names,
+            buffer,
+            map_result: Arc::default(),
+        });
+    }
+
+    /// Starts mapping the timestamps resolved this frame, once they have been submitted.
+    pub(crate) fn map(&self, render_device: &RenderDevice) {
+        let Some(resolved) = self.resolved.lock().take() else {
+            return;
+        };
+        let map_result = resolved.map_result.clone();
+        render_device.map_buffer(
+            &resolved.buffer.slice(..),
+            wgpu::MapMode::Read,
+            move |result| {
+                *map_result.lock() = Some(result);
+            },
+        );
+        self.pending.lock().push(resolved);
+    }
+}
+
+/// Reads back the timestamps of previous frames, and hands the time spent on each node over to
+/// the main world.
+fn read_gpu_timestamps(timestamps: Res<GpuTimestamps>, render_device: Res<RenderDevice>) {
+    render_device.poll(wgpu::Maintain::Poll);
+
+    let period = f64::from(timestamps.period);
+    timestamps.pending.lock().retain(|pending| {
+        let Some(map_result) = pending.map_result.lock().take() else {
+            return true;
+        };
+        if let Err(err) = map_result {
+            warn!("Failed to read back GPU timestamps: {}", err);
+            return false;
+        }
+
+        let mut durations: HashMap<&Cow<'static, str>, f64> = HashMap::default();
+        {
+            let mapped = pending.buffer.slice(..).get_mapped_range();
+            let timestamps = mapped
+                .chunks_exact(8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .collect::<Vec<_>>();
+            for (name, pair) in pending.names.iter().zip(timestamps.chunks_exact(2)) {
+                let nanoseconds = pair[1].saturating_sub(pair[0]) as f64 * period;
+                *durations.entry(name).or_default() += nanoseconds / 1_000_000.0;
+            }
+        }
+        pending.buffer.unmap();
+
+        timestamps.measurements.0.lock().extend(
+            durations
+                .into_iter()
+                .map(|(name, duration)| (name.clone(), duration)),
+        );
+        false
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimestampSlots, MAX_TIMESTAMPS};
+    use std::borrow::Cow;
+
+    #[test]
+    fn overlapping_scopes_reserve_their_own_timestamps() {
+        let slots = TimestampSlots::default();
+        // Both nodes begin before either of them ends, like nodes recorded in parallel
+        let first = slots.reserve(Cow::Borrowed("first")).unwrap();
+        let second = slots.reserve(Cow::Borrowed("second")).unwrap();
+        assert_eq!((first, second), (0, 2));
+
+        let names = slots.take();
+        assert_eq!(names[first as usize / 2], "first");
+        assert_eq!(names[second as usize / 2], "second");
+        assert_eq!(slots.reserve(Cow::Borrowed("next frame")), Some(0));
+    }
+
+    #[test]
+    fn reserve_stops_at_max_timestamps() {
+        let slots = TimestampSlots::default();
+        for i in 0..MAX_TIMESTAMPS / 2 {
+            assert_eq!(slots.reserve(Cow::Borrowed("node")), Some(i * 2));
+        }
+        assert_eq!(slots.reserve(Cow::Borrowed("node")), None);
+        assert_eq!(slots.take().len() as u32, MAX_TIMESTAMPS / 2);
+    }
+}
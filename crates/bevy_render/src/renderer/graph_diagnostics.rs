@@ -0,0 +1,62 @@
+use crate::RenderApp;
+use bevy_app::{App, Plugin};
+use bevy_ecs::{entity::Entity, system::Resource};
+use bevy_utils::Duration;
+use parking_lot::Mutex;
+use std::{borrow::Cow, sync::Arc};
+
+/// Records the render graph nodes run each frame, to debug the order of nodes added to the graph.
+///
+/// The runs of the last rendered frame can be read from the [`RenderGraphNodeRuns`] resource of
+/// the main world. To see how the graph is configured instead, see
+/// [`RenderGraph::describe`](crate::render_graph::RenderGraph::describe).
+#[derive(Default)]
+pub struct RenderGraphDiagnosticsPlugin;
+
+impl Plugin for RenderGraphDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let node_runs = RenderGraphNodeRuns::default();
+        app.insert_resource(node_runs.clone());
+
+        if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(node_runs);
+        }
+    }
+}
+
+/// A run of a render graph node, recorded by the [`RenderGraphDiagnosticsPlugin`].
+#[derive(Debug, Clone)]
+pub struct NodeRunInfo {
+    /// The name of the sub graph the node is in, or `None` for the main render graph.
+    pub graph: Option<Cow<'static, str>>,
+    /// The name of the node, or the name of its type if it was added without one.
+    pub node: Cow<'static, str>,
+    /// The name of the type that implements [`Node`](crate::render_graph::Node).
+    pub type_name: &'static str,
+    /// The first [`Entity`] input of the node, which is the view it ran for in the sub graphs run
+    /// per view.
+    pub view: Option<Entity>,
+    /// The time spent recording the commands of the node on the CPU, excluding the sub graphs it
+    /// ran.
+    pub duration: Duration,
+}
+
+/// The render graph nodes run during the last rendered frame, shared between the main world and
+/// the render world by the [`RenderGraphDiagnosticsPlugin`].
+///
+/// The nodes are listed in the order their commands were submitted, each node followed by the
+/// nodes of the sub graphs it ran. Nodes that don't depend on each other may have been recorded
+/// in parallel.
+#[derive(Resource, Clone, Default)]
+pub struct RenderGraphNodeRuns(Arc<Mutex<Vec<NodeRunInfo>>>);
+
+impl RenderGraphNodeRuns {
+    /// Returns the nodes run during the last rendered frame.
+    pub fn last_frame(&self) -> Vec<NodeRunInfo> {
+        self.0.lock().clone()
+    }
+
+    pub(crate) fn set(&self, node_runs: Vec<NodeRunInfo>) {
+        *self.0.lock() = node_runs;
+    }
+}
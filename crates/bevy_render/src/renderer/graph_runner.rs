@@ -1,19 +1,23 @@
 use bevy_ecs::world::World;
+use bevy_tasks::{ComputeTaskPool, TaskPool};
 #[cfg(feature = "trace")]
 use bevy_utils::tracing::info_span;
-use bevy_utils::HashMap;
+use bevy_utils::{HashMap, Instant};
 use smallvec::{smallvec, SmallVec};
+use std::borrow::Cow;
 #[cfg(feature = "trace")]
 use std::ops::Deref;
-use std::{borrow::Cow, collections::VecDeque};
 use thiserror::Error;
+use wgpu::CommandBuffer;
 
 use crate::{
     render_graph::{
         Edge, NodeId, NodeRunError, NodeState, RenderGraph, RenderGraphContext, SlotLabel,
         SlotType, SlotValue,
     },
-    renderer::{RenderContext, RenderDevice},
+    renderer::{
+        GpuTimestamps, NodeRunInfo, RenderContext, RenderDevice, RenderGraphNodeRuns, StagingBelt,
+    },
 };
 
 pub(crate) struct RenderGraphRunner;
@@ -51,25 +55,65 @@ pub enum RenderGraphRunnerError {
     },
 }
 
+/// What running a graph records: the commands of its nodes and the runs of the nodes, in
+/// submission order.
+type GraphRun = (Vec<CommandBuffer>, Vec<NodeRunInfo>);
+
+/// What running a node records: its outputs, and the commands and runs of the node followed by
+/// the ones of the sub graphs it ran.
+type NodeRun = (NodeId, SmallVec<[SlotValue; 4]>, GraphRun);
+
 impl RenderGraphRunner {
+    /// Runs the `graph`, submitting the commands recorded by its nodes.
+    ///
+    /// The nodes whose dependencies have all run are run together on the [`ComputeTaskPool`],
+    /// each recording into its own command encoder, like the sub graphs they run. The command
+    /// buffers are then submitted in the order the nodes would have run one after another, which
+    /// follows the edges of the graph.
     pub fn run(
         graph: &RenderGraph,
         render_device: RenderDevice,
         queue: &wgpu::Queue,
         world: &World,
     ) -> Result<(), RenderGraphRunnerError> {
-        let command_encoder =
-            render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-        let mut render_context = RenderContext {
-            render_device,
-            command_encoder,
-        };
-
-        Self::run_graph(graph, None, &mut render_context, world, &[])?;
+        let timestamps = world.get_resource::<GpuTimestamps>();
+        let staging_belt = world.get_resource::<StagingBelt>();
+        let task_pool = ComputeTaskPool::init(TaskPool::default);
+        let (command_buffers, node_runs) = Self::run_graph(
+            graph,
+            None,
+            &render_device,
+            task_pool,
+            world,
+            timestamps,
+            &[],
+        )?;
+        let resolve_timestamps = timestamps.map(|timestamps| {
+            let mut command_encoder =
+                render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            timestamps.resolve(&render_device, &mut command_encoder);
+            command_encoder.finish()
+        });
         {
             #[cfg(feature = "trace")]
             let _span = info_span!("submit_graph_commands").entered();
-            queue.submit(vec![render_context.command_encoder.finish()]);
+            // The uploads of the prepare stage run before the render graph reads the buffers
+            let staged_commands = staging_belt.and_then(StagingBelt::finish);
+            queue.submit(
+                staged_commands
+                    .into_iter()
+                    .chain(command_buffers)
+                    .chain(resolve_timestamps),
+            );
+        }
+        if let Some(staging_belt) = staging_belt {
+            staging_belt.recall();
+        }
+        if let Some(timestamps) = timestamps {
+            timestamps.map(&render_device);
+        }
+        if let Some(runs) = world.get_resource::<RenderGraphNodeRuns>() {
+            runs.set(node_runs);
         }
         Ok(())
     }
@@ -77,10 +121,12 @@ impl RenderGraphRunner {
     fn run_graph(
         graph: &RenderGraph,
         graph_name: Option<Cow<'static, str>>,
-        render_context: &mut RenderContext,
+        render_device: &RenderDevice,
+        task_pool: &TaskPool,
         world: &World,
+        timestamps: Option<&GpuTimestamps>,
         inputs: &[SlotValue],
-    ) -> Result<(), RenderGraphRunnerError> {
+    ) -> Result<GraphRun, RenderGraphRunnerError> {
         let mut node_outputs: HashMap<NodeId, SmallVec<[SlotValue; 4]>> = HashMap::default();
         #[cfg(feature = "trace")]
         let span = if let Some(name) = &graph_name {
@@ -91,12 +137,6 @@ impl RenderGraphRunner {
         #[cfg(feature = "trace")]
         let _guard = span.enter();
 
-        // Queue up nodes without inputs, which can be run immediately
-        let mut node_queue: VecDeque<&NodeState> = graph
-            .iter_nodes()
-            .filter(|node| node.input_slots.is_empty())
-            .collect();
-
         // pass inputs into the graph
         if let Some(input_node) = graph.get_input_node() {
             let mut input_values: SmallVec<[SlotValue; 4]> = SmallVec::new();
@@ -121,107 +161,187 @@ impl RenderGraphRunner {
             }
 
             node_outputs.insert(input_node.id, input_values);
-
-            for (_, node_state) in graph.iter_node_outputs(input_node.id).expect("node exists") {
-                node_queue.push_front(node_state);
-            }
         }
 
-        'handle_node: while let Some(node_state) = node_queue.pop_back() {
-            // skip nodes that are already processed
-            if node_outputs.contains_key(&node_state.id) {
-                continue;
-            }
+        let mut command_buffers = Vec::new();
+        let mut node_runs = Vec::new();
+        loop {
+            // The nodes whose dependencies have all run, which can run in parallel
+            let mut ready: Vec<(&NodeState, SmallVec<[SlotValue; 4]>)> = Vec::new();
+            'find_ready: for node_state in graph.iter_nodes() {
+                // skip nodes that are already processed
+                if node_outputs.contains_key(&node_state.id) {
+                    continue;
+                }
+                let mut node_inputs = graph
+                    .iter_node_inputs(node_state.id)
+                    .expect("node is in graph")
+                    .peekable();
+                // Nodes with input slots are only run once something is connected to them
+                if !node_state.input_slots.is_empty() && node_inputs.peek().is_none() {
+                    continue;
+                }
 
-            let mut slot_indices_and_inputs: SmallVec<[(usize, SlotValue); 4]> = SmallVec::new();
-            // check if all dependencies have finished running
-            for (edge, input_node) in graph
-                .iter_node_inputs(node_state.id)
-                .expect("node is in graph")
-            {
-                match edge {
-                    Edge::SlotEdge {
+                let mut slot_indices_and_inputs: SmallVec<[(usize, SlotValue); 4]> =
+                    SmallVec::new();
+                // check if all dependencies have finished running
+                for (edge, input_node) in node_inputs {
+                    let Some(outputs) = node_outputs.get(&input_node.id) else {
+                        continue 'find_ready;
+                    };
+                    if let Edge::SlotEdge {
                         output_index,
                         input_index,
                         ..
-                    } => {
-                        if let Some(outputs) = node_outputs.get(&input_node.id) {
-                            slot_indices_and_inputs
-                                .push((*input_index, outputs[*output_index].clone()));
-                        } else {
-                            node_queue.push_front(node_state);
-                            continue 'handle_node;
-                        }
-                    }
-                    Edge::NodeEdge { .. } => {
-                        if !node_outputs.contains_key(&input_node.id) {
-                            node_queue.push_front(node_state);
-                            continue 'handle_node;
-                        }
+                    } = edge
+                    {
+                        slot_indices_and_inputs
+                            .push((*input_index, outputs[*output_index].clone()));
                     }
                 }
+
+                // construct final sorted input list
+                slot_indices_and_inputs.sort_by_key(|(index, _)| *index);
+                let inputs: SmallVec<[SlotValue; 4]> = slot_indices_and_inputs
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect();
+
+                if inputs.len() != node_state.input_slots.len() {
+                    return Err(RenderGraphRunnerError::MismatchedInputCount {
+                        node_name: node_state.name.clone(),
+                        slot_count: node_state.input_slots.len(),
+                        value_count: inputs.len(),
+                    });
+                }
+                ready.push((node_state, inputs));
+            }
+            if ready.is_empty() {
+                break;
             }
 
-            // construct final sorted input list
-            slot_indices_and_inputs.sort_by_key(|(index, _)| *index);
-            let inputs: SmallVec<[SlotValue; 4]> = slot_indices_and_inputs
-                .into_iter()
-                .map(|(_, value)| value)
-                .collect();
-
-            if inputs.len() != node_state.input_slots.len() {
-                return Err(RenderGraphRunnerError::MismatchedInputCount {
-                    node_name: node_state.name.clone(),
-                    slot_count: node_state.input_slots.len(),
-                    value_count: inputs.len(),
-                });
+            let graph_name = &graph_name;
+            let ready_runs = task_pool.scope(|scope| {
+                for (node_state, inputs) in ready {
+                    scope.spawn(async move {
+                        Self::run_node(
+                            graph,
+                            graph_name,
+                            node_state,
+                            &inputs,
+                            render_device,
+                            task_pool,
+                            world,
+                            timestamps,
+                        )
+                    });
+                }
+            });
+            for node_run in ready_runs {
+                let (node_id, outputs, (node_command_buffers, node_node_runs)) = node_run?;
+                node_outputs.insert(node_id, outputs);
+                command_buffers.extend(node_command_buffers);
+                node_runs.extend(node_node_runs);
             }
+        }
 
-            let mut outputs: SmallVec<[Option<SlotValue>; 4]> =
-                smallvec![None; node_state.output_slots.len()];
-            {
-                let mut context = RenderGraphContext::new(graph, node_state, &inputs, &mut outputs);
-                {
-                    #[cfg(feature = "trace")]
-                    let _span = info_span!("node", name = node_state.type_name).entered();
+        Ok((command_buffers, node_runs))
+    }
 
-                    node_state.node.run(&mut context, render_context, world)?;
-                }
+    #[allow(clippy::too_many_arguments)]
+    fn run_node(
+        graph: &RenderGraph,
+        graph_name: &Option<Cow<'static, str>>,
+        node_state: &NodeState,
+        inputs: &[SlotValue],
+        render_device: &RenderDevice,
+        task_pool: &TaskPool,
+        world: &World,
+        timestamps: Option<&GpuTimestamps>,
+    ) -> Result<NodeRun, RenderGraphRunnerError> {
+        let mut render_context = RenderContext {
+            render_device: render_device.clone(),
+            command_encoder: render_device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default()),
+        };
+        let mut outputs: SmallVec<[Option<SlotValue>; 4]> =
+            smallvec![None; node_state.output_slots.len()];
+        let mut context = RenderGraphContext::new(graph, node_state, inputs, &mut outputs);
+        let mut node_runs = Vec::with_capacity(1);
+        {
+            #[cfg(feature = "trace")]
+            let _span = info_span!("node", name = node_state.type_name).entered();
+
+            let node_name = node_state
+                .name
+                .clone()
+                .unwrap_or(Cow::Borrowed(node_state.type_name));
+            let timestamp = timestamps.and_then(|timestamps| {
+                let name = match graph_name {
+                    Some(graph_name) => Cow::Owned(format!("{graph_name}/{node_name}")),
+                    None => node_name.clone(),
+                };
+                timestamps.begin(&mut render_context.command_encoder, name)
+            });
+            let start = Instant::now();
+            node_state
+                .node
+                .run(&mut context, &mut render_context, world)?;
+            let duration = start.elapsed();
+            node_runs.push(NodeRunInfo {
+                graph: graph_name.clone(),
+                node: node_name,
+                type_name: node_state.type_name,
+                view: inputs.iter().find_map(|input| match input {
+                    SlotValue::Entity(entity) => Some(*entity),
+                    _ => None,
+                }),
+                duration,
+            });
+            if let (Some(timestamps), Some(timestamp)) = (timestamps, timestamp) {
+                timestamps.end(&mut render_context.command_encoder, timestamp);
+            }
+        }
+        let mut command_buffers = vec![render_context.command_encoder.finish()];
 
-                for run_sub_graph in context.finish() {
-                    let sub_graph = graph
-                        .get_sub_graph(&run_sub_graph.name)
-                        .expect("sub graph exists because it was validated when queued.");
+        // The sub graphs are recorded in parallel too, and submitted in the order they were queued
+        let sub_graph_runs = task_pool.scope(|scope| {
+            for run_sub_graph in context.finish() {
+                let sub_graph = graph
+                    .get_sub_graph(&run_sub_graph.name)
+                    .expect("sub graph exists because it was validated when queued.");
+                scope.spawn(async move {
                     Self::run_graph(
                         sub_graph,
                         Some(run_sub_graph.name),
-                        render_context,
+                        render_device,
+                        task_pool,
                         world,
+                        timestamps,
                         &run_sub_graph.inputs,
-                    )?;
-                }
-            }
-
-            let mut values: SmallVec<[SlotValue; 4]> = SmallVec::new();
-            for (i, output) in outputs.into_iter().enumerate() {
-                if let Some(value) = output {
-                    values.push(value);
-                } else {
-                    let empty_slot = node_state.output_slots.get_slot(i).unwrap();
-                    return Err(RenderGraphRunnerError::EmptyNodeOutputSlot {
-                        type_name: node_state.type_name,
-                        slot_index: i,
-                        slot_name: empty_slot.name.clone(),
-                    });
-                }
+                    )
+                });
             }
-            node_outputs.insert(node_state.id, values);
+        });
+        for sub_graph_run in sub_graph_runs {
+            let (sub_graph_command_buffers, sub_graph_node_runs) = sub_graph_run?;
+            command_buffers.extend(sub_graph_command_buffers);
+            node_runs.extend(sub_graph_node_runs);
+        }
 
-            for (_, node_state) in graph.iter_node_outputs(node_state.id).expect("node exists") {
-                node_queue.push_front(node_state);
+        let mut values: SmallVec<[SlotValue; 4]> = SmallVec::new();
+        for (i, output) in outputs.into_iter().enumerate() {
+            if let Some(value) = output {
+                values.push(value);
+            } else {
+                let empty_slot = node_state.output_slots.get_slot(i).unwrap();
+                return Err(RenderGraphRunnerError::EmptyNodeOutputSlot {
+                    type_name: node_state.type_name,
+                    slot_index: i,
+                    slot_name: empty_slot.name.clone(),
+                });
             }
         }
-
-        Ok(())
+        Ok((node_state.id, values, (command_buffers, node_runs)))
     }
 }
@@ -1,14 +1,20 @@
+mod gpu_timestamps;
+mod graph_diagnostics;
 mod graph_runner;
 mod render_device;
+mod staging_belt;
 
 use bevy_derive::{Deref, DerefMut};
-use bevy_utils::tracing::{error, info, info_span};
+use bevy_utils::tracing::{error, info, info_span, warn};
+pub use gpu_timestamps::*;
+pub use graph_diagnostics::*;
 pub use graph_runner::*;
 pub use render_device::*;
+pub use staging_belt::*;
 
 use crate::{
     render_graph::RenderGraph,
-    settings::{WgpuSettings, WgpuSettingsPriority},
+    settings::{AdapterSelection, WgpuSettings, WgpuSettingsPriority},
     view::{ExtractedWindows, ViewTarget},
 };
 use bevy_ecs::prelude::*;
@@ -60,6 +66,8 @@ pub fn render_system(world: &mut World) {
             world.entity_mut(view_entity).remove::<ViewTarget>();
         }
 
+        crate::view::submit_screenshots(world);
+
         let mut windows = world.resource_mut::<ExtractedWindows>();
         for window in windows.values_mut() {
             if let Some(texture_view) = window.swap_chain_texture.take() {
@@ -108,6 +116,57 @@ const GPU_NOT_FOUND_ERROR_MESSAGE: &str = if cfg!(target_os = "linux") {
     "Unable to find a GPU! Make sure you have installed required drivers!"
 };
 
+/// Lists the adapters available for the `backends`, before the renderer is initialized.
+///
+/// Their order matches the indices used by [`AdapterSelection`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn enumerate_adapters(backends: wgpu::Backends) -> Vec<AdapterInfo> {
+    Instance::new(backends)
+        .enumerate_adapters(backends)
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+/// Picks the adapter selected by the [`WgpuSettings::adapter_selection`], or the one requested
+/// by `wgpu` otherwise.
+async fn select_adapter(
+    instance: &Instance,
+    options: &WgpuSettings,
+    request_adapter_options: &RequestAdapterOptions<'_>,
+) -> Option<Adapter> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if !matches!(options.adapter_selection, AdapterSelection::Auto) {
+        // Not filtered by the surface, for the indices to match the ones of `enumerate_adapters`
+        let mut adapters: Vec<_> = instance
+            .enumerate_adapters(options.backends.unwrap_or(wgpu::Backends::all()))
+            .collect();
+        let infos: Vec<_> = adapters.iter().map(Adapter::get_info).collect();
+        if let Some(index) = options.adapter_selection.select(&infos) {
+            let adapter = adapters.swap_remove(index);
+            assert!(
+                request_adapter_options
+                    .compatible_surface
+                    .map_or(true, |surface| adapter.is_surface_supported(surface)),
+                "The adapter {:?} selected by {:?} doesn't support the primary window",
+                infos[index].name,
+                options.adapter_selection
+            );
+            return Some(adapter);
+        }
+        warn!(
+            "No adapter matches {:?} among {:?}, using the default adapter",
+            options.adapter_selection,
+            infos.iter().map(|info| &info.name).collect::<Vec<_>>()
+        );
+    }
+    #[cfg(target_arch = "wasm32")]
+    if !matches!(options.adapter_selection, AdapterSelection::Auto) {
+        warn!("Adapters can't be selected on the web, using the default adapter");
+    }
+
+    instance.request_adapter(request_adapter_options).await
+}
+
 /// Initializes the renderer by retrieving and preparing the GPU instance, device and queue
 /// for the specified backend.
 pub async fn initialize_renderer(
@@ -115,8 +174,7 @@ pub async fn initialize_renderer(
     options: &WgpuSettings,
     request_adapter_options: &RequestAdapterOptions<'_>,
 ) -> (RenderDevice, RenderQueue, RenderAdapterInfo, RenderAdapter) {
-    let adapter = instance
-        .request_adapter(request_adapter_options)
+    let adapter = select_adapter(instance, options, request_adapter_options)
         .await
         .expect(GPU_NOT_FOUND_ERROR_MESSAGE);
 
@@ -145,8 +203,12 @@ pub async fn initialize_renderer(
             // integrated GPUs.
             features -= wgpu::Features::MAPPABLE_PRIMARY_BUFFERS;
         }
+        // `SPIRV_SHADER_PASSTHROUGH` gives the SPIR-V shaders to the driver without validating
+        // them, so it is only enabled when requested explicitly.
+        features -= wgpu::Features::SPIRV_SHADER_PASSTHROUGH;
         limits = adapter.limits();
     }
+    features |= options.optional_features & adapter.features();
 
     // Enforce the disabled features
     if let Some(disabled_features) = options.disabled_features {
@@ -48,6 +48,21 @@ impl RenderDevice {
         self.device.create_shader_module(desc)
     }
 
+    /// Creates a [`ShaderModule`](wgpu::ShaderModule) from SPIR-V that is given to the driver as
+    /// is, which requires the [`SPIRV_SHADER_PASSTHROUGH`](wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// The SPIR-V is not validated, so it must be valid for the driver.
+    #[inline]
+    pub unsafe fn create_shader_module_spirv(
+        &self,
+        desc: &wgpu::ShaderModuleDescriptorSpirV,
+    ) -> wgpu::ShaderModule {
+        self.device.create_shader_module_spirv(desc)
+    }
+
     /// Check for resource cleanups and mapping callbacks.
     ///
     /// no-op on the web, device is automatically polled.
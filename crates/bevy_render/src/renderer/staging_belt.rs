@@ -0,0 +1,82 @@
+use crate::{render_resource::Buffer, renderer::RenderDevice};
+use bevy_ecs::system::Resource;
+use parking_lot::Mutex;
+use wgpu::{BufferAddress, BufferSize, CommandBuffer, CommandEncoder, CommandEncoderDescriptor};
+
+/// Uploads data to buffers through staging buffers that stay mapped between frames, instead of
+/// copying each write to its own staging allocation like
+/// [`RenderQueue::write_buffer`](wgpu::Queue::write_buffer) does.
+///
+/// The copies written during a frame are recorded into a single command encoder, submitted just
+/// before the commands of the render graph. The staging buffers are allocated in chunks of
+/// [`StagingBelt::CHUNK_SIZE`] bytes, many writes sharing a chunk, and are reused once the GPU is
+/// done copying from them.
+///
+/// The typed buffers of [`render_resource`](crate::render_resource) write through it with their
+/// `write_buffer_staged` methods, usually from systems of the
+/// [`RenderStage::Prepare`](crate::RenderStage::Prepare).
+#[derive(Resource, Default)]
+pub struct StagingBelt {
+    inner: Mutex<StagingBeltInner>,
+}
+
+struct StagingBeltInner {
+    belt: wgpu::util::StagingBelt,
+    encoder: Option<CommandEncoder>,
+}
+
+impl Default for StagingBeltInner {
+    fn default() -> Self {
+        Self {
+            belt: wgpu::util::StagingBelt::new(StagingBelt::CHUNK_SIZE),
+            encoder: None,
+        }
+    }
+}
+
+impl StagingBelt {
+    /// The size of the staging buffers, in bytes. Larger writes get a staging buffer of their size.
+    pub const CHUNK_SIZE: BufferAddress = 1 << 20;
+
+    /// Writes `data` to the `buffer` at the `offset`, before the render graph of this frame runs.
+    ///
+    /// Like [`RenderQueue::write_buffer`](wgpu::Queue::write_buffer), the `offset` and the length
+    /// of the `data` must be multiples of [`wgpu::COPY_BUFFER_ALIGNMENT`], and the `buffer` needs
+    /// the [`BufferUsages::COPY_DST`](wgpu::BufferUsages::COPY_DST) usage.
+    pub fn write_buffer(
+        &self,
+        render_device: &RenderDevice,
+        buffer: &Buffer,
+        offset: BufferAddress,
+        data: &[u8],
+    ) {
+        let Some(size) = BufferSize::new(data.len() as u64) else {
+            return;
+        };
+        let mut inner = self.inner.lock();
+        let StagingBeltInner { belt, encoder } = &mut *inner;
+        let encoder = encoder.get_or_insert_with(|| {
+            // Gets back the staging buffers the GPU is done with since the last frame
+            render_device.poll(wgpu::Maintain::Poll);
+            render_device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("staging_belt_encoder"),
+            })
+        });
+        belt.write_buffer(encoder, buffer, offset, size, render_device.wgpu_device())
+            .copy_from_slice(data);
+    }
+
+    /// Closes the staging buffers written this frame, returning the commands copying from them if
+    /// there were any. They must be submitted before [`StagingBelt::recall`] is called.
+    pub(crate) fn finish(&self) -> Option<CommandBuffer> {
+        let mut inner = self.inner.lock();
+        let encoder = inner.encoder.take()?;
+        inner.belt.finish();
+        Some(encoder.finish())
+    }
+
+    /// Maps the staging buffers again once the GPU is done with the commands submitted this frame.
+    pub(crate) fn recall(&self) {
+        self.inner.lock().belt.recall();
+    }
+}
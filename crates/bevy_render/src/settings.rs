@@ -1,6 +1,9 @@
-use std::borrow::Cow;
+use crate::render_resource::WgpuAdapterInfo;
+use std::{borrow::Cow, fmt, sync::Arc};
 
-pub use wgpu::{Backends, Features as WgpuFeatures, Limits as WgpuLimits, PowerPreference};
+pub use wgpu::{
+    Backends, DeviceType, Features as WgpuFeatures, Limits as WgpuLimits, PowerPreference,
+};
 
 /// Configures the priority used when automatically configuring the features/limits of `wgpu`.
 #[derive(Clone)]
@@ -13,8 +16,66 @@ pub enum WgpuSettingsPriority {
     WebGL2,
 }
 
+/// Selects the adapter the renderer is initialized with, among the adapters of the enabled
+/// [`Backends`]. Use [`enumerate_adapters`](crate::renderer::enumerate_adapters) to list them
+/// beforehand, in the same order.
+///
+/// Falls back to the adapter picked by `wgpu` for the
+/// [`power_preference`](WgpuSettings::power_preference), with a warning, if no adapter matches.
+/// The renderer fails to initialize if the selected adapter doesn't support the primary window.
+#[derive(Clone, Default)]
+pub enum AdapterSelection {
+    /// Lets `wgpu` pick the adapter for the [`power_preference`](WgpuSettings::power_preference).
+    #[default]
+    Auto,
+    /// The adapter at this index in the list of adapters.
+    Index(usize),
+    /// The first adapter whose name contains this string, ignoring case.
+    Name(Cow<'static, str>),
+    /// The first adapter of this type, for example [`DeviceType::DiscreteGpu`].
+    DeviceType(DeviceType),
+    /// The adapter at the index returned by the callback, given the list of adapters.
+    Custom(Arc<dyn Fn(&[WgpuAdapterInfo]) -> Option<usize> + Send + Sync>),
+}
+
+impl AdapterSelection {
+    /// Returns the index of the selected adapter in `adapters`, or `None` if
+    /// [`AdapterSelection::Auto`] or no adapter matches.
+    pub fn select(&self, adapters: &[WgpuAdapterInfo]) -> Option<usize> {
+        let index = match self {
+            AdapterSelection::Auto => None,
+            AdapterSelection::Index(index) => Some(*index),
+            AdapterSelection::Name(name) => {
+                let name = name.to_lowercase();
+                adapters
+                    .iter()
+                    .position(|adapter| adapter.name.to_lowercase().contains(&name))
+            }
+            AdapterSelection::DeviceType(device_type) => adapters
+                .iter()
+                .position(|adapter| adapter.device_type == *device_type),
+            AdapterSelection::Custom(select) => select(adapters),
+        };
+        index.filter(|index| *index < adapters.len())
+    }
+}
+
+impl fmt::Debug for AdapterSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "Auto"),
+            Self::Index(index) => f.debug_tuple("Index").field(index).finish(),
+            Self::Name(name) => f.debug_tuple("Name").field(name).finish(),
+            Self::DeviceType(device_type) => {
+                f.debug_tuple("DeviceType").field(device_type).finish()
+            }
+            Self::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
 /// Provides configuration for renderer initialization. Use [`RenderDevice::features`](crate::renderer::RenderDevice::features),
-/// [`RenderDevice::limits`](crate::renderer::RenderDevice::limits), and the [`WgpuAdapterInfo`](crate::render_resource::WgpuAdapterInfo)
+/// [`RenderDevice::limits`](crate::renderer::RenderDevice::limits), and the [`RenderAdapterInfo`](crate::renderer::RenderAdapterInfo)
 /// resource to get runtime information about the actual adapter, backend, features, and limits.
 /// NOTE: [`Backends::DX12`](Backends::DX12), [`Backends::METAL`](Backends::METAL), and
 /// [`Backends::VULKAN`](Backends::VULKAN) are enabled by default for non-web and the best choice
@@ -27,10 +88,16 @@ pub struct WgpuSettings {
     pub device_label: Option<Cow<'static, str>>,
     pub backends: Option<Backends>,
     pub power_preference: PowerPreference,
+    /// Selects the adapter to use rather than the one picked for the `power_preference`.
+    pub adapter_selection: AdapterSelection,
     pub priority: WgpuSettingsPriority,
     /// The features to ensure are enabled regardless of what the adapter/backend supports.
     /// Setting these explicitly may cause renderer initialization to fail.
     pub features: WgpuFeatures,
+    /// The features to enable only if the adapter/backend supports them, regardless of the
+    /// [`priority`](WgpuSettings::priority). Whether they were enabled can be checked with
+    /// [`RenderDevice::features`](crate::renderer::RenderDevice::features).
+    pub optional_features: WgpuFeatures,
     /// The features to ensure are disabled regardless of what the adapter/backend supports
     pub disabled_features: Option<WgpuFeatures>,
     /// The imposed limits.
@@ -69,8 +136,10 @@ impl Default for WgpuSettings {
             device_label: Default::default(),
             backends,
             power_preference: PowerPreference::HighPerformance,
+            adapter_selection: AdapterSelection::Auto,
             priority,
             features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            optional_features: wgpu::Features::empty(),
             disabled_features: None,
             limits,
             constrained_limits: None,
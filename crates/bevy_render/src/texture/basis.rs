@@ -1,7 +1,10 @@
 use basis_universal::{
     BasisTextureType, DecodeFlags, TranscodeParameters, Transcoder, TranscoderTextureFormat,
 };
-use wgpu::{AstcBlock, AstcChannel, Extent3d, TextureDimension, TextureFormat};
+use wgpu::{
+    AstcBlock, AstcChannel, Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor,
+    TextureViewDimension,
+};
 
 use super::{CompressedImageFormats, Image, TextureError};
 
@@ -123,6 +126,21 @@ pub fn basis_buffer_to_image(
             )))
         }
     };
+    // Cube maps are stored as arrays of 6 faces, which need a cube view to be sampled as such
+    let view_dimension = match texture_type {
+        BasisTextureType::TextureTypeCubemapArray if image_count > 6 => {
+            Some(TextureViewDimension::CubeArray)
+        }
+        BasisTextureType::TextureTypeCubemapArray => Some(TextureViewDimension::Cube),
+        BasisTextureType::TextureType2DArray => Some(TextureViewDimension::D2Array),
+        _ => None,
+    };
+    if view_dimension.is_some() {
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: view_dimension,
+            ..Default::default()
+        });
+    }
     image.data = transcoded;
     Ok(image)
 }
@@ -1,4 +1,4 @@
-use crate::texture::{Image, TextureFormatPixelInfo};
+use crate::texture::{Image, ImageLoaderSettings, TextureFormatPixelInfo};
 use anyhow::Result;
 use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
 use bevy_utils::BoxedFuture;
@@ -36,7 +36,7 @@ impl AssetLoader for HdrTextureLoader {
                 rgba_data.extend_from_slice(&alpha.to_ne_bytes());
             }
 
-            let texture = Image::new(
+            let mut texture = Image::new(
                 Extent3d {
                     width: info.width,
                     height: info.height,
@@ -47,6 +47,10 @@ impl AssetLoader for HdrTextureLoader {
                 format,
             );
 
+            ImageLoaderSettings::read(load_context)
+                .await?
+                .apply(&mut texture);
+
             load_context.set_default_asset(LoadedAsset::new(texture));
             Ok(())
         })
@@ -9,11 +9,14 @@ use crate::{
     render_asset::{PrepareAssetError, RenderAsset},
     render_resource::{Sampler, Texture, TextureView},
     renderer::{RenderDevice, RenderQueue},
-    texture::BevyDefault,
+    texture::{streaming::resident_mip_levels, BevyDefault, MipmapGenerator},
 };
 use bevy_asset::HandleUntyped;
 use bevy_derive::{Deref, DerefMut};
-use bevy_ecs::system::{lifetimeless::SRes, Resource, SystemParamItem};
+use bevy_ecs::system::{
+    lifetimeless::{SRes, SResMut},
+    Resource, SystemParamItem,
+};
 use bevy_math::Vec2;
 use bevy_reflect::{FromReflect, Reflect, TypeUuid};
 use std::hash::Hash;
@@ -108,6 +111,21 @@ pub struct Image {
     /// The [`ImageSampler`] to use during rendering.
     pub sampler_descriptor: ImageSampler,
     pub texture_view_descriptor: Option<wgpu::TextureViewDescriptor<'static>>,
+    /// Whether to generate the full mip chain of the texture from its first mip level when
+    /// uploading it to the GPU, which avoids aliasing when it is drawn smaller than its size.
+    ///
+    /// The image loaders set it with [`ImagePlugin::generate_mipmaps`](super::ImagePlugin::generate_mipmaps),
+    /// which can be changed per image in its [`ImageLoaderSettings`](super::ImageLoaderSettings).
+    /// It only applies to 2D images with a single mip level in a format that can be rendered to
+    /// and filtered, like the images loaded from png or jpeg files.
+    pub generate_mipmaps: bool,
+    /// The first mip level of `data` uploaded to the GPU, leaving the larger mip levels out of
+    /// video memory.
+    ///
+    /// This is set by the texture streaming (see [`TextureStreamingSettings`]) to the level the
+    /// image needs to be drawn at, and is 0 otherwise. It only applies to 2D images without a
+    /// custom texture view descriptor.
+    pub resident_mip_level: u32,
 }
 
 /// Used in [`Image`], this determines what image sampler to use when rendering. The default setting,
@@ -166,6 +184,11 @@ impl ImageSampler {
 #[derive(Resource, Debug, Clone, Deref, DerefMut)]
 pub struct DefaultImageSampler(pub(crate) Sampler);
 
+/// Whether the image loaders generate the mipmaps of the images they load without mip levels,
+/// set from [`ImagePlugin::generate_mipmaps`](super::ImagePlugin::generate_mipmaps).
+#[derive(Resource, Debug, Clone, Copy, Default, Deref, DerefMut)]
+pub struct DefaultImageMipmaps(pub bool);
+
 impl Default for Image {
     fn default() -> Self {
         let format = wgpu::TextureFormat::bevy_default();
@@ -187,6 +210,8 @@ impl Default for Image {
             },
             sampler_descriptor: ImageSampler::Default,
             texture_view_descriptor: None,
+            generate_mipmaps: false,
+            resident_mip_level: 0,
         }
     }
 }
@@ -315,6 +340,8 @@ impl Image {
     /// - `TextureFormat::R8Unorm`
     /// - `TextureFormat::Rg8Unorm`
     /// - `TextureFormat::Rgba8UnormSrgb`
+    /// - the other formats supported by [`Image::get_pixel`], whose pixels
+    ///   are converted one by one
     ///
     /// To get [`Image`] as a [`image::DynamicImage`] see:
     /// [`Image::try_into_dynamic`].
@@ -336,6 +363,7 @@ impl Image {
                 _ => None,
             })
             .map(|(dyn_img, is_srgb)| Self::from_dynamic(dyn_img, is_srgb))
+            .or_else(|| self.convert_pixels(new_format))
     }
 
     /// Load a bytes buffer in a [`Image`], according to type `image_type`, using the `image`
@@ -498,6 +526,7 @@ impl RenderAsset for Image {
         SRes<RenderDevice>,
         SRes<RenderQueue>,
         SRes<DefaultImageSampler>,
+        SResMut<MipmapGenerator>,
     );
 
     /// Clones the Image.
@@ -508,13 +537,26 @@ impl RenderAsset for Image {
     /// Converts the extracted image into a [`GpuImage`].
     fn prepare_asset(
         image: Self::ExtractedAsset,
-        (render_device, render_queue, default_sampler): &mut SystemParamItem<Self::Param>,
+        (render_device, render_queue, default_sampler, mipmap_generator): &mut SystemParamItem<
+            Self::Param,
+        >,
     ) -> Result<Self::PreparedAsset, PrepareAssetError<Self::ExtractedAsset>> {
-        let texture = render_device.create_texture_with_data(
-            render_queue,
-            &image.texture_descriptor,
-            &image.data,
-        );
+        let texture = if let Some((descriptor, data)) = resident_mip_levels(&image) {
+            render_device.create_texture_with_data(render_queue, &descriptor, &data)
+        } else if image.generate_mipmaps && MipmapGenerator::supports(&image.texture_descriptor) {
+            mipmap_generator.create_texture_with_data(
+                render_device,
+                render_queue,
+                &image.texture_descriptor,
+                &image.data,
+            )
+        } else {
+            render_device.create_texture_with_data(
+                render_queue,
+                &image.texture_descriptor,
+                &image.data,
+            )
+        };
 
         let texture_view = texture.create_view(
             image
@@ -0,0 +1,435 @@
+use crate::{
+    color::Color,
+    texture::{Image, ImageFilterMode, TextureFormatPixelInfo},
+};
+use thiserror::Error;
+use wgpu::{Extent3d, TextureFormat};
+
+/// An error that occurs when accessing or editing the pixels of an [`Image`] on the CPU.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureAccessError {
+    #[error("out of bounds (x: {x}, y: {y})")]
+    OutOfBounds { x: u32, y: u32 },
+    #[error("unsupported texture format: {0:?}")]
+    UnsupportedFormat(TextureFormat),
+    #[error("images with several mip levels can't be edited, this one has {0}")]
+    MipLevels(u32),
+}
+
+/// How the channels of a pixel are stored, for the formats supported by [`Image::get_pixel`].
+struct PixelLayout {
+    /// The index in RGBA of each stored channel.
+    channels: &'static [usize],
+    /// Whether the channels are `f32`, or `u8` normalized to 0..=1.
+    float: bool,
+    /// Whether the color is stored in the sRGB color space, or in linear space.
+    srgb: bool,
+}
+
+impl PixelLayout {
+    fn of(format: TextureFormat) -> Option<Self> {
+        let (channels, float, srgb): (&'static [usize], _, _) = match format {
+            TextureFormat::R8Unorm => (&[0], false, false),
+            TextureFormat::Rg8Unorm => (&[0, 1], false, false),
+            TextureFormat::Rgba8Unorm => (&[0, 1, 2, 3], false, false),
+            TextureFormat::Rgba8UnormSrgb => (&[0, 1, 2, 3], false, true),
+            TextureFormat::Bgra8Unorm => (&[2, 1, 0, 3], false, false),
+            TextureFormat::Bgra8UnormSrgb => (&[2, 1, 0, 3], false, true),
+            TextureFormat::R32Float => (&[0], true, false),
+            TextureFormat::Rg32Float => (&[0, 1], true, false),
+            TextureFormat::Rgba32Float => (&[0, 1, 2, 3], true, false),
+            _ => return None,
+        };
+        Some(Self {
+            channels,
+            float,
+            srgb,
+        })
+    }
+
+    /// Reads the RGBA channels of the pixel, in the color space it is stored in.
+    fn read(&self, pixel: &[u8]) -> [f32; 4] {
+        let mut rgba = [0.0, 0.0, 0.0, 1.0];
+        for (index, &channel) in self.channels.iter().enumerate() {
+            rgba[channel] = if self.float {
+                let bytes = &pixel[index * 4..index * 4 + 4];
+                f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            } else {
+                pixel[index] as f32 / 255.0
+            };
+        }
+        rgba
+    }
+
+    /// Writes the RGBA channels of the pixel, in the color space it is stored in.
+    fn write(&self, pixel: &mut [u8], rgba: [f32; 4]) {
+        for (index, &channel) in self.channels.iter().enumerate() {
+            if self.float {
+                pixel[index * 4..index * 4 + 4].copy_from_slice(&rgba[channel].to_ne_bytes());
+            } else {
+                pixel[index] = (rgba[channel].clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    fn color(&self, [r, g, b, a]: [f32; 4]) -> Color {
+        if self.srgb {
+            Color::rgba(r, g, b, a)
+        } else {
+            Color::rgba_linear(r, g, b, a)
+        }
+    }
+
+    fn channels_of(&self, color: Color) -> [f32; 4] {
+        if self.srgb {
+            color.as_rgba_f32()
+        } else {
+            color.as_linear_rgba_f32()
+        }
+    }
+}
+
+/// Editing images on the CPU.
+///
+/// These methods change the [`data`](Image::data) of the image. When they are called on an image
+/// borrowed with [`Assets::get_mut`](bevy_asset::Assets::get_mut), the image is marked as modified,
+/// and uploaded to the GPU again at the end of the frame.
+///
+/// Reading and writing colors is supported for the `R8Unorm`, `Rg8Unorm`, `Rgba8Unorm`,
+/// `Bgra8Unorm`, `R32Float`, `Rg32Float` and `Rgba32Float` formats, with the colors of the sRGB
+/// variants being in sRGB space, and the other ones in linear space. Flipping, cropping and
+/// resizing with the nearest filter is supported for all the uncompressed formats.
+impl Image {
+    /// Returns the color of the pixel at `x` and `y` of the first layer of the image.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Result<Color, TextureAccessError> {
+        let format = self.texture_descriptor.format;
+        let layout =
+            PixelLayout::of(format).ok_or(TextureAccessError::UnsupportedFormat(format))?;
+        let offset = self.pixel_offset(x, y)?;
+        let pixel = &self.data[offset..offset + format.pixel_size()];
+        Ok(layout.color(layout.read(pixel)))
+    }
+
+    /// Sets the color of the pixel at `x` and `y` of the first layer of the image.
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) -> Result<(), TextureAccessError> {
+        let format = self.texture_descriptor.format;
+        let layout =
+            PixelLayout::of(format).ok_or(TextureAccessError::UnsupportedFormat(format))?;
+        let offset = self.pixel_offset(x, y)?;
+        let pixel = &mut self.data[offset..offset + format.pixel_size()];
+        layout.write(pixel, layout.channels_of(color));
+        Ok(())
+    }
+
+    /// Sets all the pixels of the image to `color`, in all its layers and mip levels.
+    pub fn fill(&mut self, color: Color) -> Result<(), TextureAccessError> {
+        let format = self.texture_descriptor.format;
+        let layout =
+            PixelLayout::of(format).ok_or(TextureAccessError::UnsupportedFormat(format))?;
+        let mut pixel = vec![0; format.pixel_size()];
+        layout.write(&mut pixel, layout.channels_of(color));
+        for current_pixel in self.data.chunks_exact_mut(pixel.len()) {
+            current_pixel.copy_from_slice(&pixel);
+        }
+        Ok(())
+    }
+
+    /// Mirrors the image from left to right.
+    pub fn flip_horizontal(&mut self) -> Result<(), TextureAccessError> {
+        let pixel_size = self.editable_pixel_size()?;
+        let row_size = self.texture_descriptor.size.width as usize * pixel_size;
+        if row_size == 0 {
+            return Ok(());
+        }
+        for row in self.data.chunks_exact_mut(row_size) {
+            let width = row.len() / pixel_size;
+            for x in 0..width / 2 {
+                let (left, right) = row.split_at_mut((width - 1 - x) * pixel_size);
+                left[x * pixel_size..(x + 1) * pixel_size]
+                    .swap_with_slice(&mut right[..pixel_size]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Mirrors the image from top to bottom.
+    pub fn flip_vertical(&mut self) -> Result<(), TextureAccessError> {
+        let pixel_size = self.editable_pixel_size()?;
+        let size = self.texture_descriptor.size;
+        let row_size = size.width as usize * pixel_size;
+        let height = size.height as usize;
+        if row_size * height == 0 {
+            return Ok(());
+        }
+        for layer in self.data.chunks_exact_mut(row_size * height) {
+            for y in 0..height / 2 {
+                let (top, bottom) = layer.split_at_mut((height - 1 - y) * row_size);
+                top[y * row_size..(y + 1) * row_size].swap_with_slice(&mut bottom[..row_size]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the `width` by `height` pixels whose top left corner is at `x` and `y`, in each layer
+    /// of the image.
+    pub fn crop(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), TextureAccessError> {
+        let pixel_size = self.editable_pixel_size()?;
+        let size = self.texture_descriptor.size;
+        if x.saturating_add(width) > size.width || y.saturating_add(height) > size.height {
+            return Err(TextureAccessError::OutOfBounds {
+                x: x.saturating_add(width),
+                y: y.saturating_add(height),
+            });
+        }
+
+        let row_size = size.width as usize * pixel_size;
+        let cropped_row_size = width as usize * pixel_size;
+        let mut data = Vec::with_capacity(
+            cropped_row_size * height as usize * size.depth_or_array_layers as usize,
+        );
+        let layer_size = row_size * size.height as usize;
+        for layer in self.data.chunks_exact(layer_size.max(1)) {
+            for row in layer
+                .chunks_exact(row_size)
+                .skip(y as usize)
+                .take(height as usize)
+            {
+                let start = x as usize * pixel_size;
+                data.extend_from_slice(&row[start..start + cropped_row_size]);
+            }
+        }
+        self.data = data;
+        self.texture_descriptor.size = Extent3d {
+            width,
+            height,
+            ..size
+        };
+        Ok(())
+    }
+
+    /// Scales the contents of each layer of the image to `width` by `height` pixels, unlike
+    /// [`Image::resize`].
+    ///
+    /// Filtering with [`ImageFilterMode::Linear`] interpolates the stored values, so only the
+    /// formats supported by [`Image::get_pixel`] can be resized with it.
+    pub fn resize_contents(
+        &mut self,
+        width: u32,
+        height: u32,
+        filter: ImageFilterMode,
+    ) -> Result<(), TextureAccessError> {
+        let pixel_size = self.editable_pixel_size()?;
+        let format = self.texture_descriptor.format;
+        let layout = match filter {
+            ImageFilterMode::Nearest => None,
+            ImageFilterMode::Linear => {
+                Some(PixelLayout::of(format).ok_or(TextureAccessError::UnsupportedFormat(format))?)
+            }
+        };
+
+        let size = self.texture_descriptor.size;
+        let (old_width, old_height) = (size.width as usize, size.height as usize);
+        let layer_size = old_width * old_height * pixel_size;
+        let mut data = Vec::with_capacity(
+            width as usize * height as usize * pixel_size * size.depth_or_array_layers as usize,
+        );
+        if layer_size > 0 {
+            for layer in self.data.chunks_exact(layer_size) {
+                let pixel = |x: usize, y: usize| {
+                    let offset = (y * old_width + x) * pixel_size;
+                    &layer[offset..offset + pixel_size]
+                };
+                for y in 0..height as usize {
+                    // the position of the center of the pixel in the old image
+                    let old_y = ((y as f32 + 0.5) * old_height as f32 / height as f32 - 0.5)
+                        .clamp(0.0, (old_height - 1) as f32);
+                    for x in 0..width as usize {
+                        let old_x = ((x as f32 + 0.5) * old_width as f32 / width as f32 - 0.5)
+                            .clamp(0.0, (old_width - 1) as f32);
+                        match &layout {
+                            None => data.extend_from_slice(pixel(
+                                old_x.round() as usize,
+                                old_y.round() as usize,
+                            )),
+                            Some(layout) => {
+                                let (x0, y0) = (old_x as usize, old_y as usize);
+                                let (x1, y1) =
+                                    ((x0 + 1).min(old_width - 1), (y0 + 1).min(old_height - 1));
+                                let (tx, ty) = (old_x.fract(), old_y.fract());
+                                let [top_left, top_right, bottom_left, bottom_right] =
+                                    [(x0, y0), (x1, y0), (x0, y1), (x1, y1)]
+                                        .map(|(x, y)| layout.read(pixel(x, y)));
+                                let mut rgba = [0.0; 4];
+                                for (channel, value) in rgba.iter_mut().enumerate() {
+                                    let top = top_left[channel]
+                                        + (top_right[channel] - top_left[channel]) * tx;
+                                    let bottom = bottom_left[channel]
+                                        + (bottom_right[channel] - bottom_left[channel]) * tx;
+                                    *value = top + (bottom - top) * ty;
+                                }
+                                let start = data.len();
+                                data.resize(start + pixel_size, 0);
+                                layout.write(&mut data[start..], rgba);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.data = data;
+        self.data.resize(
+            width as usize * height as usize * pixel_size * size.depth_or_array_layers as usize,
+            0,
+        );
+        self.texture_descriptor.size = Extent3d {
+            width,
+            height,
+            ..size
+        };
+        Ok(())
+    }
+
+    /// Returns the offset in `data` of the pixel at `x` and `y` of the first layer.
+    fn pixel_offset(&self, x: u32, y: u32) -> Result<usize, TextureAccessError> {
+        let size = self.texture_descriptor.size;
+        if x >= size.width || y >= size.height {
+            return Err(TextureAccessError::OutOfBounds { x, y });
+        }
+        Ok((y as usize * size.width as usize + x as usize)
+            * self.texture_descriptor.format.pixel_size())
+    }
+
+    /// Returns the size of a pixel for the edits moving pixels around, which require an
+    /// uncompressed format and a single mip level.
+    fn editable_pixel_size(&self) -> Result<usize, TextureAccessError> {
+        let format = self.texture_descriptor.format;
+        if format.describe().block_dimensions != (1, 1) {
+            return Err(TextureAccessError::UnsupportedFormat(format));
+        }
+        if self.texture_descriptor.mip_level_count > 1 {
+            return Err(TextureAccessError::MipLevels(
+                self.texture_descriptor.mip_level_count,
+            ));
+        }
+        Ok(format.pixel_size())
+    }
+
+    /// Converts the pixels of the image to `new_format` one by one, for the formats supported by
+    /// [`Image::get_pixel`].
+    pub(crate) fn convert_pixels(&self, new_format: TextureFormat) -> Option<Self> {
+        let layout = PixelLayout::of(self.texture_descriptor.format)?;
+        let new_layout = PixelLayout::of(new_format)?;
+        let pixel_size = self.texture_descriptor.format.pixel_size();
+        let new_pixel_size = new_format.pixel_size();
+
+        let mut data = vec![0; self.data.len() / pixel_size * new_pixel_size];
+        for (pixel, new_pixel) in self
+            .data
+            .chunks_exact(pixel_size)
+            .zip(data.chunks_exact_mut(new_pixel_size))
+        {
+            let color = layout.color(layout.read(pixel));
+            new_layout.write(new_pixel, new_layout.channels_of(color));
+        }
+
+        let mut image = self.clone();
+        image.data = data;
+        image.texture_descriptor.format = new_format;
+        Some(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color,
+        texture::{Image, ImageFilterMode, TextureAccessError},
+    };
+    use wgpu::{Extent3d, TextureDimension, TextureFormat};
+
+    fn test_image(width: u32, height: u32, format: TextureFormat) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &vec![0; format.describe().block_size as usize],
+            format,
+        )
+    }
+
+    #[test]
+    fn pixels() {
+        let mut image = test_image(2, 2, TextureFormat::Rgba8UnormSrgb);
+        image.set_pixel(1, 0, Color::rgb_u8(255, 128, 0)).unwrap();
+        assert_eq!(&image.data[4..8], &[255, 128, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).unwrap(), Color::rgb_u8(255, 128, 0));
+        assert_eq!(
+            image.set_pixel(2, 0, Color::RED),
+            Err(TextureAccessError::OutOfBounds { x: 2, y: 0 })
+        );
+
+        let mut image = test_image(1, 1, TextureFormat::Bgra8Unorm);
+        image.fill(Color::rgba_linear(1.0, 0.0, 0.0, 1.0)).unwrap();
+        assert_eq!(image.data, [0, 0, 255, 255]);
+
+        let image = test_image(1, 1, TextureFormat::Depth32Float);
+        assert_eq!(
+            image.get_pixel(0, 0),
+            Err(TextureAccessError::UnsupportedFormat(
+                TextureFormat::Depth32Float
+            ))
+        );
+    }
+
+    #[test]
+    fn flip_and_crop() {
+        let mut image = test_image(3, 2, TextureFormat::R8Unorm);
+        image.data = vec![0, 1, 2, 3, 4, 5];
+        image.flip_horizontal().unwrap();
+        assert_eq!(image.data, [2, 1, 0, 5, 4, 3]);
+        image.flip_vertical().unwrap();
+        assert_eq!(image.data, [5, 4, 3, 2, 1, 0]);
+        image.crop(1, 0, 2, 2).unwrap();
+        assert_eq!(image.data, [4, 3, 1, 0]);
+        assert_eq!(image.texture_descriptor.size.width, 2);
+        assert_eq!(
+            image.crop(1, 1, 2, 1),
+            Err(TextureAccessError::OutOfBounds { x: 3, y: 2 })
+        );
+    }
+
+    #[test]
+    fn resize_contents() {
+        let mut image = test_image(2, 1, TextureFormat::R8Unorm);
+        image.data = vec![0, 255];
+        let mut nearest = image.clone();
+        nearest
+            .resize_contents(4, 2, ImageFilterMode::Nearest)
+            .unwrap();
+        assert_eq!(nearest.data, [0, 0, 255, 255, 0, 0, 255, 255]);
+
+        image
+            .resize_contents(4, 1, ImageFilterMode::Linear)
+            .unwrap();
+        assert_eq!(image.data, [0, 64, 191, 255]);
+    }
+
+    #[test]
+    fn convert() {
+        let mut image = test_image(1, 1, TextureFormat::Rgba8UnormSrgb);
+        image.data = vec![255, 128, 0, 255];
+        let converted = image.convert(TextureFormat::Bgra8UnormSrgb).unwrap();
+        assert_eq!(converted.data, [0, 128, 255, 255]);
+        let converted = image.convert(TextureFormat::Rgba32Float).unwrap();
+        assert_eq!(converted.data.len(), 16);
+    }
+}
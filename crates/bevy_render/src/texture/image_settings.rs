@@ -0,0 +1,308 @@
+use crate::texture::{Image, ImageSampler};
+use bevy_asset::{AssetIoError, LoadContext};
+use serde::{Deserialize, Serialize};
+use std::{num::NonZeroU8, path::PathBuf};
+use thiserror::Error;
+
+/// The settings an image file is loaded with, read from the `.meta` file next to it, which is
+/// named after the image file with `.meta` appended, like `sprites.png.meta` for `sprites.png`.
+///
+/// The `.meta` file is written in RON, and the settings it leaves out keep their default value:
+///
+/// ```ron
+/// (
+///     sampler: Some((
+///         mag_filter: Nearest,
+///         min_filter: Nearest,
+///     )),
+/// )
+/// ```
+///
+/// Images without a `.meta` file are loaded with the default settings. A change to a `.meta` file
+/// is picked up the next time its image is loaded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageLoaderSettings {
+    /// The sampler of the image, or `None` to use the default sampler of the
+    /// [`ImagePlugin`](super::ImagePlugin).
+    pub sampler: Option<ImageSamplerDescriptor>,
+    /// Whether to generate the mipmaps of the image, or `None` to follow
+    /// [`ImagePlugin::generate_mipmaps`](super::ImagePlugin::generate_mipmaps).
+    pub generate_mipmaps: Option<bool>,
+}
+
+impl ImageLoaderSettings {
+    /// Reads the settings of the image being loaded from its `.meta` file, or returns the default
+    /// settings if it has none.
+    pub async fn read(load_context: &LoadContext<'_>) -> Result<Self, ImageLoaderSettingsError> {
+        let mut path = load_context.path().as_os_str().to_owned();
+        path.push(".meta");
+        let path = PathBuf::from(path);
+
+        match load_context.read_asset_bytes(&path).await {
+            Ok(bytes) => ron::de::from_bytes(&bytes)
+                .map_err(|error| ImageLoaderSettingsError::Parse { path, error }),
+            Err(AssetIoError::NotFound(_)) => Ok(Self::default()),
+            Err(error) => Err(ImageLoaderSettingsError::Io { path, error }),
+        }
+    }
+
+    /// Applies the settings to a loaded `image`.
+    pub fn apply(&self, image: &mut Image) {
+        if let Some(sampler) = &self.sampler {
+            image.sampler_descriptor = sampler.clone().into();
+        }
+        if let Some(generate_mipmaps) = self.generate_mipmaps {
+            image.generate_mipmaps = generate_mipmaps;
+        }
+    }
+}
+
+/// An error that occurs when reading the `.meta` file of an image.
+#[derive(Error, Debug)]
+pub enum ImageLoaderSettingsError {
+    #[error("could not read the image settings {path:?}: {error}")]
+    Io { path: PathBuf, error: AssetIoError },
+    #[error("invalid image settings in {path:?}: {error}")]
+    Parse {
+        path: PathBuf,
+        error: ron::error::SpannedError,
+    },
+}
+
+/// How an [`Image`] is sampled, which can be deserialized unlike [`wgpu::SamplerDescriptor`].
+///
+/// The fields left out default to the values of [`ImageSampler::linear_descriptor`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageSamplerDescriptor {
+    /// How to sample the texture outside of its width.
+    pub address_mode_u: ImageAddressMode,
+    /// How to sample the texture outside of its height.
+    pub address_mode_v: ImageAddressMode,
+    /// How to sample the texture outside of its depth.
+    pub address_mode_w: ImageAddressMode,
+    /// How to filter the texture when it is magnified.
+    pub mag_filter: ImageFilterMode,
+    /// How to filter the texture when it is minified.
+    pub min_filter: ImageFilterMode,
+    /// How to filter between mip levels.
+    pub mipmap_filter: ImageFilterMode,
+    /// The minimum mip level to sample.
+    pub lod_min_clamp: f32,
+    /// The maximum mip level to sample.
+    pub lod_max_clamp: f32,
+    /// Makes a comparison sampler, comparing the depth of a depth texture with this function.
+    pub compare: Option<ImageCompareFunction>,
+    /// The maximum level of anisotropic filtering, which must be 1, 2, 4, 8 or 16. Values above 1
+    /// require all the filters to be [`ImageFilterMode::Linear`].
+    pub anisotropy_clamp: u8,
+    /// The color sampled outside of the texture with [`ImageAddressMode::ClampToBorder`].
+    pub border_color: Option<ImageSamplerBorderColor>,
+}
+
+impl Default for ImageSamplerDescriptor {
+    fn default() -> Self {
+        Self::linear()
+    }
+}
+
+impl ImageSamplerDescriptor {
+    /// Returns a sampler descriptor with `Linear` min, mag and mipmap filters.
+    pub fn linear() -> Self {
+        Self {
+            address_mode_u: ImageAddressMode::ClampToEdge,
+            address_mode_v: ImageAddressMode::ClampToEdge,
+            address_mode_w: ImageAddressMode::ClampToEdge,
+            mag_filter: ImageFilterMode::Linear,
+            min_filter: ImageFilterMode::Linear,
+            mipmap_filter: ImageFilterMode::Linear,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: f32::MAX,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        }
+    }
+
+    /// Returns a sampler descriptor with `Nearest` min, mag and mipmap filters.
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: ImageFilterMode::Nearest,
+            min_filter: ImageFilterMode::Nearest,
+            mipmap_filter: ImageFilterMode::Nearest,
+            ..Self::linear()
+        }
+    }
+
+    /// Returns the [`wgpu::SamplerDescriptor`] described.
+    pub fn as_wgpu(&self) -> wgpu::SamplerDescriptor<'static> {
+        wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: self.address_mode_u.into(),
+            address_mode_v: self.address_mode_v.into(),
+            address_mode_w: self.address_mode_w.into(),
+            mag_filter: self.mag_filter.into(),
+            min_filter: self.min_filter.into(),
+            mipmap_filter: self.mipmap_filter.into(),
+            lod_min_clamp: self.lod_min_clamp,
+            lod_max_clamp: self.lod_max_clamp,
+            compare: self.compare.map(Into::into),
+            anisotropy_clamp: NonZeroU8::new(self.anisotropy_clamp).filter(|clamp| clamp.get() > 1),
+            border_color: self.border_color.map(Into::into),
+        }
+    }
+}
+
+impl From<ImageSamplerDescriptor> for ImageSampler {
+    fn from(descriptor: ImageSamplerDescriptor) -> Self {
+        ImageSampler::Descriptor(descriptor.as_wgpu())
+    }
+}
+
+/// How to sample an [`Image`] outside of its edges, see [`wgpu::AddressMode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageAddressMode {
+    /// Samples the color at the nearest edge.
+    #[default]
+    ClampToEdge,
+    /// Repeats the texture.
+    Repeat,
+    /// Repeats the texture, mirrored every other time.
+    MirrorRepeat,
+    /// Samples the [`border_color`](ImageSamplerDescriptor::border_color).
+    ClampToBorder,
+}
+
+impl From<ImageAddressMode> for wgpu::AddressMode {
+    fn from(mode: ImageAddressMode) -> Self {
+        match mode {
+            ImageAddressMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+            ImageAddressMode::Repeat => wgpu::AddressMode::Repeat,
+            ImageAddressMode::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+            ImageAddressMode::ClampToBorder => wgpu::AddressMode::ClampToBorder,
+        }
+    }
+}
+
+/// How to filter an [`Image`] between its texels, see [`wgpu::FilterMode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageFilterMode {
+    /// Samples the nearest texel, which keeps pixel art sharp.
+    #[default]
+    Nearest,
+    /// Interpolates between the nearest texels.
+    Linear,
+}
+
+impl From<ImageFilterMode> for wgpu::FilterMode {
+    fn from(mode: ImageFilterMode) -> Self {
+        match mode {
+            ImageFilterMode::Nearest => wgpu::FilterMode::Nearest,
+            ImageFilterMode::Linear => wgpu::FilterMode::Linear,
+        }
+    }
+}
+
+/// The function a comparison sampler compares depths with, see [`wgpu::CompareFunction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageCompareFunction {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl From<ImageCompareFunction> for wgpu::CompareFunction {
+    fn from(function: ImageCompareFunction) -> Self {
+        match function {
+            ImageCompareFunction::Never => wgpu::CompareFunction::Never,
+            ImageCompareFunction::Less => wgpu::CompareFunction::Less,
+            ImageCompareFunction::Equal => wgpu::CompareFunction::Equal,
+            ImageCompareFunction::LessEqual => wgpu::CompareFunction::LessEqual,
+            ImageCompareFunction::Greater => wgpu::CompareFunction::Greater,
+            ImageCompareFunction::NotEqual => wgpu::CompareFunction::NotEqual,
+            ImageCompareFunction::GreaterEqual => wgpu::CompareFunction::GreaterEqual,
+            ImageCompareFunction::Always => wgpu::CompareFunction::Always,
+        }
+    }
+}
+
+/// The color sampled outside of an [`Image`] clamped to its border, see
+/// [`wgpu::SamplerBorderColor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageSamplerBorderColor {
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+    /// Transparent black for the textures with an alpha channel, and opaque black for the others.
+    Zero,
+}
+
+impl From<ImageSamplerBorderColor> for wgpu::SamplerBorderColor {
+    fn from(color: ImageSamplerBorderColor) -> Self {
+        match color {
+            ImageSamplerBorderColor::TransparentBlack => wgpu::SamplerBorderColor::TransparentBlack,
+            ImageSamplerBorderColor::OpaqueBlack => wgpu::SamplerBorderColor::OpaqueBlack,
+            ImageSamplerBorderColor::OpaqueWhite => wgpu::SamplerBorderColor::OpaqueWhite,
+            ImageSamplerBorderColor::Zero => wgpu::SamplerBorderColor::Zero,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_settings() {
+        let settings: ImageLoaderSettings = ron::from_str(
+            "(sampler: Some((mag_filter: Nearest, address_mode_u: Repeat, anisotropy_clamp: 4)))",
+        )
+        .unwrap();
+        let sampler = settings.sampler.unwrap();
+        assert_eq!(
+            sampler,
+            ImageSamplerDescriptor {
+                mag_filter: ImageFilterMode::Nearest,
+                address_mode_u: ImageAddressMode::Repeat,
+                anisotropy_clamp: 4,
+                ..ImageSamplerDescriptor::linear()
+            }
+        );
+
+        let descriptor = sampler.as_wgpu();
+        assert_eq!(descriptor.mag_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(descriptor.min_filter, wgpu::FilterMode::Linear);
+        assert_eq!(descriptor.address_mode_u, wgpu::AddressMode::Repeat);
+        assert_eq!(descriptor.anisotropy_clamp, NonZeroU8::new(4));
+        assert_eq!(
+            ImageSamplerDescriptor::linear().as_wgpu().anisotropy_clamp,
+            None
+        );
+
+        assert_eq!(
+            ron::from_str::<ImageLoaderSettings>("()").unwrap(),
+            ImageLoaderSettings::default()
+        );
+    }
+
+    #[test]
+    fn apply_generate_mipmaps() {
+        let mut image = Image {
+            generate_mipmaps: true,
+            ..Default::default()
+        };
+        ImageLoaderSettings::default().apply(&mut image);
+        assert!(image.generate_mipmaps);
+
+        let settings: ImageLoaderSettings =
+            ron::from_str("(generate_mipmaps: Some(false))").unwrap();
+        settings.apply(&mut image);
+        assert!(!image.generate_mipmaps);
+    }
+}
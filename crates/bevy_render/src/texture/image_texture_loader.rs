@@ -6,7 +6,7 @@ use thiserror::Error;
 
 use crate::{
     renderer::RenderDevice,
-    texture::{Image, ImageType, TextureError},
+    texture::{DefaultImageMipmaps, Image, ImageLoaderSettings, ImageType, TextureError},
 };
 
 use super::CompressedImageFormats;
@@ -15,6 +15,7 @@ use super::CompressedImageFormats;
 #[derive(Clone)]
 pub struct ImageTextureLoader {
     supported_compressed_formats: CompressedImageFormats,
+    generate_mipmaps: bool,
 }
 
 const FILE_EXTENSIONS: &[&str] = &[
@@ -46,7 +47,7 @@ impl AssetLoader for ImageTextureLoader {
             // use the file extension for the image type
             let ext = load_context.path().extension().unwrap().to_str().unwrap();
 
-            let dyn_img = Image::from_buffer(
+            let mut image = Image::from_buffer(
                 bytes,
                 ImageType::Extension(ext),
                 self.supported_compressed_formats,
@@ -56,8 +57,13 @@ impl AssetLoader for ImageTextureLoader {
                 error: err,
                 path: format!("{}", load_context.path().display()),
             })?;
+            image.generate_mipmaps = self.generate_mipmaps;
 
-            load_context.set_default_asset(LoadedAsset::new(dyn_img));
+            ImageLoaderSettings::read(load_context)
+                .await?
+                .apply(&mut image);
+
+            load_context.set_default_asset(LoadedAsset::new(image));
             Ok(())
         })
     }
@@ -76,6 +82,9 @@ impl FromWorld for ImageTextureLoader {
         };
         Self {
             supported_compressed_formats,
+            generate_mipmaps: world
+                .get_resource::<DefaultImageMipmaps>()
+                .map_or(false, |generate_mipmaps| **generate_mipmaps),
         }
     }
 }
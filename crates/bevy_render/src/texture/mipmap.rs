@@ -0,0 +1,275 @@
+use crate::{
+    render_resource::{BindGroupLayout, RenderPipeline, Sampler, Texture},
+    renderer::{RenderDevice, RenderQueue},
+    texture::TextureFormatPixelInfo,
+};
+use bevy_ecs::{
+    system::Resource,
+    world::{FromWorld, World},
+};
+use bevy_utils::HashMap;
+use std::num::NonZeroU32;
+use wgpu::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType};
+
+/// Returns the number of mip levels of a full mip chain for a texture of the given `size`, down
+/// to a single texel.
+pub(crate) fn mip_level_count(size: Extent3d) -> u32 {
+    32 - size.width.max(size.height).max(1).leading_zeros()
+}
+
+/// Generates the mip chains of [`Image`](super::Image)s with
+/// [`generate_mipmaps`](super::Image::generate_mipmaps) set, when they are uploaded to the GPU.
+///
+/// Each level is drawn from the previous one with a linear filter, for each layer of the image.
+#[derive(Resource)]
+pub struct MipmapGenerator {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    shader: wgpu::ShaderModule,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl FromWorld for MipmapGenerator {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("mipmap_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = render_device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("mipmap_sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let shader = render_device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mipmap_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("mipmap.wgsl").into()),
+        });
+        Self {
+            layout,
+            sampler,
+            shader,
+            pipelines: HashMap::default(),
+        }
+    }
+}
+
+impl MipmapGenerator {
+    /// Whether the mip chain of a texture with this `descriptor` can be generated, which requires
+    /// a single mip level of a 2D texture in a format that can be rendered to and filtered.
+    pub fn supports(descriptor: &TextureDescriptor) -> bool {
+        let info = descriptor.format.describe();
+        descriptor.mip_level_count == 1
+            && descriptor.sample_count == 1
+            && descriptor.dimension == TextureDimension::D2
+            && mip_level_count(descriptor.size) > 1
+            && matches!(
+                info.sample_type,
+                TextureSampleType::Float { filterable: true }
+            )
+            && info
+                .guaranteed_format_features
+                .allowed_usages
+                .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+    }
+
+    fn pipeline(&mut self, render_device: &RenderDevice, format: TextureFormat) -> &RenderPipeline {
+        let Self {
+            layout,
+            shader,
+            pipelines,
+            ..
+        } = self;
+        pipelines.entry(format).or_insert_with(|| {
+            let pipeline_layout =
+                render_device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("mipmap_pipeline_layout"),
+                    bind_group_layouts: &[layout],
+                    push_constant_ranges: &[],
+                });
+            render_device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("mipmap_pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vertex",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: "fragment",
+                    targets: &[Some(format.into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            })
+        })
+    }
+
+    /// Creates a texture with a full mip chain from the first mip level in `data`, for a
+    /// `descriptor` that is [supported](MipmapGenerator::supports).
+    ///
+    /// The levels are drawn into separate textures that are then copied to the mip levels of the
+    /// texture, as some backends can only sample the first level and layer of a texture.
+    pub fn create_texture_with_data(
+        &mut self,
+        render_device: &RenderDevice,
+        render_queue: &RenderQueue,
+        descriptor: &TextureDescriptor,
+        data: &[u8],
+    ) -> Texture {
+        let size = descriptor.size;
+        let mip_level_count = mip_level_count(size);
+        let bytes_per_row = size.width * descriptor.format.pixel_size() as u32;
+        let texture = render_device.create_texture(&TextureDescriptor {
+            mip_level_count,
+            usage: descriptor.usage | wgpu::TextureUsages::COPY_DST,
+            ..descriptor.clone()
+        });
+        render_queue.write_texture(
+            texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: NonZeroU32::new(size.height),
+            },
+            size,
+        );
+
+        let level_texture = |level: u32| {
+            render_device.create_texture(&TextureDescriptor {
+                label: Some("mipmap_level_texture"),
+                size: mip_level_size(size, level),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: descriptor.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::COPY_DST,
+            })
+        };
+        let mut command_encoder =
+            render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("mipmap_command_encoder"),
+            });
+        let pipeline = self.pipeline(render_device, descriptor.format).clone();
+        let layer_size = (bytes_per_row * size.height) as usize;
+        for (layer, layer_data) in data.chunks_exact(layer_size).enumerate() {
+            let mut source = level_texture(0);
+            render_queue.write_texture(
+                source.as_image_copy(),
+                layer_data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(bytes_per_row),
+                    rows_per_image: None,
+                },
+                mip_level_size(size, 0),
+            );
+            for level in 1..mip_level_count {
+                let target = level_texture(level);
+                let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+                let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+                let bind_group = render_device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("mipmap_bind_group"),
+                    layout: &self.layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&source_view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&self.sampler),
+                        },
+                    ],
+                });
+                {
+                    let mut pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("mipmap_pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &target_view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                                store: true,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                    });
+                    pass.set_pipeline(&pipeline);
+                    pass.set_bind_group(0, &bind_group, &[]);
+                    pass.draw(0..3, 0..1);
+                }
+                command_encoder.copy_texture_to_texture(
+                    target.as_image_copy(),
+                    wgpu::ImageCopyTexture {
+                        texture: &texture,
+                        mip_level: level,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as u32,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    mip_level_size(size, level),
+                );
+                source = target;
+            }
+        }
+        render_queue.submit([command_encoder.finish()]);
+        texture
+    }
+}
+
+/// Returns the size of a single layer of the mip `level` of a texture of the given `size`.
+fn mip_level_size(size: Extent3d, level: u32) -> Extent3d {
+    Extent3d {
+        width: (size.width >> level).max(1),
+        height: (size.height >> level).max(1),
+        depth_or_array_layers: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_mip_chain_level_count() {
+        let size = |width, height| Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        assert_eq!(mip_level_count(size(1, 1)), 1);
+        assert_eq!(mip_level_count(size(2, 1)), 2);
+        assert_eq!(mip_level_count(size(256, 256)), 9);
+        assert_eq!(mip_level_count(size(300, 17)), 9);
+        assert_eq!(mip_level_size(size(300, 17), 8), size(1, 1));
+    }
+}
@@ -2,14 +2,21 @@
 mod basis;
 #[cfg(feature = "dds")]
 mod dds;
+#[cfg(feature = "exr")]
+mod exr_texture_loader;
 mod fallback_image;
 #[cfg(feature = "hdr")]
 mod hdr_texture_loader;
 #[allow(clippy::module_inception)]
 mod image;
+mod image_manipulation;
+mod image_settings;
 mod image_texture_loader;
 #[cfg(feature = "ktx2")]
 mod ktx2;
+mod mipmap;
+pub(crate) mod readback;
+mod streaming;
 mod texture_cache;
 
 pub(crate) mod image_texture_conversion;
@@ -19,11 +26,18 @@ pub use self::image::*;
 pub use self::ktx2::*;
 #[cfg(feature = "dds")]
 pub use dds::*;
+#[cfg(feature = "exr")]
+pub use exr_texture_loader::*;
 #[cfg(feature = "hdr")]
 pub use hdr_texture_loader::*;
 
 pub use fallback_image::*;
+pub use image_manipulation::*;
+pub use image_settings::*;
 pub use image_texture_loader::*;
+pub use mipmap::MipmapGenerator;
+pub use readback::{clear_image_readbacks, ImageReadbackEvent, ImageReadbacks};
+pub use streaming::*;
 pub use texture_cache::*;
 
 use crate::{
@@ -31,14 +45,18 @@ use crate::{
     renderer::RenderDevice,
     RenderApp, RenderStage,
 };
-use bevy_app::{App, Plugin};
+use bevy_app::{App, CoreStage, Plugin};
 use bevy_asset::{AddAsset, Assets};
+use bevy_ecs::schedule::IntoSystemDescriptor;
 
 // TODO: replace Texture names with Image names?
 /// Adds the [`Image`] as an asset and makes sure that they are extracted and prepared for the GPU.
 pub struct ImagePlugin {
     /// The default image sampler to use when [`ImageSampler`] is set to `Default`.
     pub default_sampler: wgpu::SamplerDescriptor<'static>,
+    /// Whether the loaded images without mip levels generate them when uploaded to the GPU, see
+    /// [`Image::generate_mipmaps`]. Defaults to `false`.
+    pub generate_mipmaps: bool,
 }
 
 impl Default for ImagePlugin {
@@ -52,6 +70,7 @@ impl ImagePlugin {
     pub fn default_linear() -> ImagePlugin {
         ImagePlugin {
             default_sampler: ImageSampler::linear_descriptor(),
+            generate_mipmaps: false,
         }
     }
 
@@ -59,12 +78,16 @@ impl ImagePlugin {
     pub fn default_nearest() -> ImagePlugin {
         ImagePlugin {
             default_sampler: ImageSampler::nearest_descriptor(),
+            generate_mipmaps: false,
         }
     }
 }
 
 impl Plugin for ImagePlugin {
     fn build(&self, app: &mut App) {
+        // The image loaders read it when they are created
+        app.insert_resource(DefaultImageMipmaps(self.generate_mipmaps));
+
         #[cfg(any(
             feature = "png",
             feature = "dds",
@@ -83,16 +106,35 @@ impl Plugin for ImagePlugin {
             app.init_asset_loader::<HdrTextureLoader>();
         }
 
+        #[cfg(feature = "exr")]
+        {
+            app.init_asset_loader::<ExrTextureLoader>();
+        }
+
         app.add_plugin(RenderAssetPlugin::<Image>::with_prepare_asset_label(
             PrepareAssetLabel::PreAssetPrepare,
         ))
         .register_type::<Image>()
         .add_asset::<Image>()
-        .register_asset_reflect::<Image>();
+        .register_asset_reflect::<Image>()
+        .init_resource::<ImageReadbacks>()
+        .add_event::<ImageReadbackEvent>()
+        .add_system_to_stage(CoreStage::First, clear_image_readbacks)
+        .add_system_to_stage(CoreStage::PreUpdate, readback::send_readback_events)
+        .init_resource::<TextureStreamingSettings>()
+        .init_resource::<TextureStreamingRequests>()
+        .init_resource::<TextureResidency>()
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            update_texture_residency.label(TextureStreamingSystem),
+        );
         app.world
             .resource_mut::<Assets<Image>>()
             .set_untracked(DEFAULT_IMAGE_HANDLE, Image::default());
 
+        let completed_readbacks = readback::CompletedReadbacks::default();
+        app.insert_resource(completed_readbacks.clone());
+
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             let default_sampler = {
                 let device = render_app.world.resource::<RenderDevice>();
@@ -102,6 +144,12 @@ impl Plugin for ImagePlugin {
                 .insert_resource(DefaultImageSampler(default_sampler))
                 .init_resource::<TextureCache>()
                 .init_resource::<FallbackImage>()
+                .init_resource::<MipmapGenerator>()
+                .insert_resource(completed_readbacks)
+                .init_resource::<readback::ExtractedImageReadbacks>()
+                .init_resource::<readback::PendingReadbacks>()
+                .add_system_to_stage(RenderStage::Extract, readback::extract_image_readbacks)
+                .add_system_to_stage(RenderStage::Cleanup, readback::readback_images)
                 .add_system_to_stage(RenderStage::Cleanup, update_texture_cache_system);
         }
     }
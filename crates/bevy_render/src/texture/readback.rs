@@ -0,0 +1,286 @@
+use crate::{
+    render_asset::RenderAssets,
+    render_resource::Buffer,
+    renderer::{RenderDevice, RenderQueue},
+    texture::{Image, TextureFormatPixelInfo},
+    view::ScreenshotEvent,
+    Extract,
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+    event::EventWriter,
+    system::{Commands, Res, ResMut, Resource},
+};
+use bevy_utils::tracing::warn;
+use bevy_window::WindowId;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use wgpu::{
+    BufferAsyncError, CommandEncoder, Extent3d, TextureDimension, TextureFormat, TextureUsages,
+};
+
+/// Requests the contents of GPU [`Image`]s to be copied back to the CPU.
+///
+/// Each requested image is copied once the frame it was requested in has been rendered. When the
+/// data is available on the CPU, usually a frame or two later, it is sent as an
+/// [`ImageReadbackEvent`]. Only the first mip level and layer of the image are read back.
+///
+/// The texture of the image needs the [`TextureUsages::COPY_SRC`](wgpu::TextureUsages::COPY_SRC)
+/// usage. Images used as a [`RenderTarget`](crate::camera::RenderTarget) are given that usage
+/// automatically. Requests for images without it are skipped with a warning.
+#[derive(Resource, Default)]
+pub struct ImageReadbacks {
+    requests: Vec<Handle<Image>>,
+}
+
+impl ImageReadbacks {
+    /// Requests the `image` to be read back after this frame has been rendered.
+    pub fn request(&mut self, image: Handle<Image>) {
+        self.requests.push(image);
+    }
+}
+
+/// Sent when the data of an image requested through [`ImageReadbacks`] is available on the CPU.
+#[derive(Debug)]
+pub struct ImageReadbackEvent {
+    /// The handle of the image that was read back.
+    pub handle: Handle<Image>,
+    /// The contents of the image on the GPU.
+    pub image: Image,
+}
+
+/// What a readback was requested for.
+#[derive(Debug)]
+pub(crate) enum ReadbackSource {
+    Image(Handle<Image>),
+    Screenshot(WindowId),
+}
+
+/// Readbacks finished in the render world, waiting to be sent as events in the main world.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct CompletedReadbacks(Arc<Mutex<Vec<(ReadbackSource, Image)>>>);
+
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedImageReadbacks {
+    requests: Vec<Handle<Image>>,
+}
+
+/// A copy of a texture into a buffer, to be mapped and read on the CPU.
+pub(crate) struct PendingReadback {
+    source: ReadbackSource,
+    buffer: Buffer,
+    size: Extent3d,
+    format: TextureFormat,
+    padded_bytes_per_row: usize,
+    map_result: Arc<Mutex<Option<Result<(), BufferAsyncError>>>>,
+}
+
+impl PendingReadback {
+    /// Records a copy of the first mip level and layer of the `texture` into a new buffer.
+    ///
+    /// [`PendingReadback::map`] needs to be called once the copy has been submitted.
+    pub(crate) fn copy_texture(
+        source: ReadbackSource,
+        render_device: &RenderDevice,
+        command_encoder: &mut CommandEncoder,
+        texture: &wgpu::Texture,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> Self {
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let padded_bytes_per_row =
+            RenderDevice::align_copy_bytes_per_row(width as usize * format.pixel_size());
+        let buffer = render_device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback_buffer"),
+            size: (padded_bytes_per_row * height as usize) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        command_encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        Self {
+            source,
+            buffer,
+            size,
+            format,
+            padded_bytes_per_row,
+            map_result: Arc::default(),
+        }
+    }
+
+    /// Starts mapping the buffer, once the copy into it has been submitted.
+    pub(crate) fn map(&self, render_device: &RenderDevice) {
+        let map_result = self.map_result.clone();
+        render_device.map_buffer(&self.buffer.slice(..), wgpu::MapMode::Read, move |result| {
+            *map_result.lock() = Some(result);
+        });
+    }
+
+    /// Returns `None` while the buffer is being mapped, then whether the readback could be read.
+    fn finish(&self, completed: &CompletedReadbacks) -> Option<bool> {
+        let map_result = self.map_result.lock().take()?;
+        if let Err(err) = map_result {
+            warn!("Failed to read back {:?}: {}", self.source, err);
+            return Some(false);
+        }
+
+        let bytes_per_row = self.size.width as usize * self.format.pixel_size();
+        let mut data = Vec::with_capacity(bytes_per_row * self.size.height as usize);
+        {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks(self.padded_bytes_per_row) {
+                data.extend_from_slice(&row[..bytes_per_row]);
+            }
+        }
+        self.buffer.unmap();
+
+        let mut format = self.format;
+        let source = match &self.source {
+            ReadbackSource::Image(handle) => ReadbackSource::Image(handle.clone()),
+            ReadbackSource::Screenshot(window) => {
+                // swap chains are usually BGRA, which images can't be converted from
+                let rgba_format = match format {
+                    TextureFormat::Bgra8Unorm => Some(TextureFormat::Rgba8Unorm),
+                    TextureFormat::Bgra8UnormSrgb => Some(TextureFormat::Rgba8UnormSrgb),
+                    _ => None,
+                };
+                if let Some(rgba_format) = rgba_format {
+                    for pixel in data.chunks_exact_mut(4) {
+                        pixel.swap(0, 2);
+                    }
+                    format = rgba_format;
+                }
+                ReadbackSource::Screenshot(*window)
+            }
+        };
+        let image = Image::new(self.size, TextureDimension::D2, data, format);
+        completed.0.lock().push((source, image));
+        Some(true)
+    }
+}
+
+/// Readbacks whose buffer is being copied to or mapped.
+#[derive(Resource, Default)]
+pub(crate) struct PendingReadbacks(pub(crate) Vec<PendingReadback>);
+
+/// Clears the requests of the previous frame, which have been extracted to the render world.
+pub fn clear_image_readbacks(mut readbacks: ResMut<ImageReadbacks>) {
+    readbacks.requests.clear();
+}
+
+pub(crate) fn send_readback_events(
+    completed: Res<CompletedReadbacks>,
+    mut image_events: EventWriter<ImageReadbackEvent>,
+    mut screenshot_events: EventWriter<ScreenshotEvent>,
+) {
+    for (source, image) in completed.0.lock().drain(..) {
+        match source {
+            ReadbackSource::Image(handle) => {
+                image_events.send(ImageReadbackEvent { handle, image });
+            }
+            ReadbackSource::Screenshot(window) => {
+                screenshot_events.send(ScreenshotEvent { window, image });
+            }
+        }
+    }
+}
+
+pub(crate) fn extract_image_readbacks(
+    mut commands: Commands,
+    readbacks: Extract<Res<ImageReadbacks>>,
+    images: Extract<Res<Assets<Image>>>,
+) {
+    let requests = readbacks
+        .requests
+        .iter()
+        .filter(|handle| {
+            // the usages of a texture can't be queried from wgpu, so check the image instead
+            let Some(image) = images.get(handle) else {
+                return true;
+            };
+            let copyable = image
+                .texture_descriptor
+                .usage
+                .contains(TextureUsages::COPY_SRC);
+            if !copyable {
+                warn!(
+                    "Can't read back image {:?}, its texture doesn't have the COPY_SRC usage",
+                    handle
+                );
+            }
+            copyable
+        })
+        .cloned()
+        .collect();
+    commands.insert_resource(ExtractedImageReadbacks { requests });
+}
+
+/// Copies the requested images into buffers once the frame has been rendered, and hands the
+/// buffers that finished mapping over to the main world.
+pub(crate) fn readback_images(
+    extracted: Res<ExtractedImageReadbacks>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut pending: ResMut<PendingReadbacks>,
+    completed: Res<CompletedReadbacks>,
+) {
+    render_device.poll(wgpu::Maintain::Poll);
+    pending
+        .0
+        .retain(|readback| readback.finish(&completed).is_none());
+
+    if extracted.requests.is_empty() {
+        return;
+    }
+
+    let mut command_encoder =
+        render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("image_readback_command_encoder"),
+        });
+    let mut new_readbacks = Vec::new();
+    for handle in &extracted.requests {
+        let Some(gpu_image) = gpu_images.get(handle) else {
+            warn!("Can't read back image {:?}, it isn't on the GPU", handle);
+            continue;
+        };
+        if gpu_image.texture_format.describe().is_compressed() {
+            warn!(
+                "Can't read back image {:?} with a compressed format",
+                handle
+            );
+            continue;
+        }
+
+        new_readbacks.push(PendingReadback::copy_texture(
+            ReadbackSource::Image(handle.clone()),
+            &render_device,
+            &mut command_encoder,
+            &gpu_image.texture,
+            gpu_image.size.x as u32,
+            gpu_image.size.y as u32,
+            gpu_image.texture_format,
+        ));
+    }
+    render_queue.submit([command_encoder.finish()]);
+
+    for readback in new_readbacks {
+        readback.map(&render_device);
+        pending.0.push(readback);
+    }
+}
@@ -0,0 +1,502 @@
+use crate::{
+    color::SrgbColorSpace,
+    texture::{mipmap::mip_level_count, Image},
+};
+use bevy_asset::{AssetEvent, Assets, Handle, HandleId};
+use bevy_ecs::{
+    event::EventReader,
+    schedule::SystemLabel,
+    system::{Res, ResMut, Resource},
+};
+use bevy_time::Time;
+use bevy_utils::{hashbrown::hash_map::Entry, HashMap};
+use std::{borrow::Cow, cmp::Ordering};
+use wgpu::{TextureDescriptor, TextureDimension, TextureFormat, TextureUsages};
+
+/// Configures the texture streaming, which only keeps in video memory the mip levels of images
+/// that they need to be drawn at.
+///
+/// Images are streamed once their size on screen is requested through
+/// [`TextureStreamingRequests`], which the renderers of materials do for the textures of the
+/// visible meshes. The first time an image is requested, only its smallest mip level is uploaded
+/// to the GPU, and larger levels are then uploaded a level per frame up to the one matching its
+/// size on screen. The levels of images that are no longer requested are dropped after
+/// [`eviction_delay`](Self::eviction_delay).
+///
+/// Only 2D images that are not rendered to can be streamed. Images generating their mip chain on
+/// the GPU (see [`Image::generate_mipmaps`]) have it generated on the CPU instead when they are
+/// first streamed, which is only supported for 8 bit formats.
+#[derive(Resource, Clone, Debug)]
+pub struct TextureStreamingSettings {
+    /// Whether images are streamed. Defaults to `false`, which uploads all of their mip levels.
+    pub enabled: bool,
+    /// The video memory that the mip levels of the streamed images can take, in bytes. When they
+    /// need more than this, the largest levels of the images covering the least of the screen are
+    /// dropped first.
+    pub budget: u64,
+    /// How many images can have a larger mip level uploaded each frame, which spreads the cost of
+    /// the uploads over several frames.
+    pub max_uploads_per_frame: usize,
+    /// How long an image can go without being requested before all but its smallest mip level are
+    /// dropped, in seconds.
+    pub eviction_delay: f32,
+}
+
+impl Default for TextureStreamingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            budget: 512 * 1024 * 1024,
+            max_uploads_per_frame: 4,
+            eviction_delay: 2.0,
+        }
+    }
+}
+
+/// The sizes on screen that streamed images are drawn at this frame, from which the mip levels
+/// they need are chosen.
+///
+/// Requests are cleared each frame once they have been used in
+/// [`TextureStreamingSystem`], so they need to be made every frame the images are drawn.
+#[derive(Resource, Default)]
+pub struct TextureStreamingRequests {
+    screen_sizes: HashMap<HandleId, f32>,
+}
+
+impl TextureStreamingRequests {
+    /// Requests the `image` to be drawn `screen_size` pixels wide this frame. The largest size
+    /// requested for an image is kept.
+    pub fn request(&mut self, image: &Handle<Image>, screen_size: f32) {
+        let size = self.screen_sizes.entry(image.id()).or_insert(0.0);
+        *size = size.max(screen_size);
+    }
+}
+
+/// The mip levels of an image kept in video memory by the texture streaming.
+#[derive(Debug, Clone)]
+pub struct StreamedImage {
+    /// The largest size on screen the image was requested at, in pixels.
+    pub screen_size: f32,
+    /// The first mip level the image needs, given its size on screen and the budget.
+    pub desired_mip_level: u32,
+    /// The first mip level of the image in video memory, which moves towards the desired one.
+    pub resident_mip_level: u32,
+    last_requested: f32,
+    size: u32,
+    /// The bytes of the mip chain starting at each level that can be resident.
+    chain_bytes: Vec<u64>,
+}
+
+impl StreamedImage {
+    /// The bytes of video memory taken by the resident mip levels of the image.
+    pub fn resident_bytes(&self) -> u64 {
+        self.chain_bytes[self.resident_mip_level as usize]
+    }
+
+    fn smallest_mip_level(&self) -> u32 {
+        self.chain_bytes.len() as u32 - 1
+    }
+}
+
+/// The images streamed by the texture streaming, and the mip levels they keep in video memory.
+#[derive(Resource, Default, Debug)]
+pub struct TextureResidency {
+    images: HashMap<HandleId, StreamedImage>,
+    resident_bytes: u64,
+}
+
+impl TextureResidency {
+    /// Returns the streaming state of the `image`, if it is streamed.
+    pub fn get(&self, image: &Handle<Image>) -> Option<&StreamedImage> {
+        self.images.get(&image.id())
+    }
+
+    /// Iterates over the streamed images.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<Image>, &StreamedImage)> {
+        self.images
+            .iter()
+            .map(|(id, image)| (Handle::weak(*id), image))
+    }
+
+    /// The bytes of video memory taken by the resident mip levels of all the streamed images.
+    pub fn resident_bytes(&self) -> u64 {
+        self.resident_bytes
+    }
+}
+
+/// Label for [`update_texture_residency`], which runs in
+/// [`CoreStage::PostUpdate`](bevy_app::CoreStage::PostUpdate). Systems requesting images through
+/// [`TextureStreamingRequests`] need to run before it.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextureStreamingSystem;
+
+/// Chooses the mip levels of the streamed images from their requested sizes on screen and the
+/// budget, and sets the [`Image::resident_mip_level`] of the images moving towards theirs.
+pub fn update_texture_residency(
+    settings: Res<TextureStreamingSettings>,
+    time: Res<Time>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut requests: ResMut<TextureStreamingRequests>,
+    mut residency: ResMut<TextureResidency>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let residency = &mut *residency;
+    if !settings.enabled {
+        requests.screen_sizes.clear();
+        // Upload all the mip levels of the images streamed before the streaming was disabled
+        for (id, _) in residency.images.drain() {
+            if let Some(image) = images.get_mut(&Handle::weak(id)) {
+                image.resident_mip_level = 0;
+            }
+        }
+        residency.resident_bytes = 0;
+        image_events.clear();
+        return;
+    }
+
+    for event in image_events.iter() {
+        match event {
+            AssetEvent::Removed { handle } => {
+                residency.images.remove(&handle.id());
+            }
+            // Images replaced by other ones start over the next time they are requested
+            AssetEvent::Modified { handle } => {
+                if let (Some(image), Some(streamed)) =
+                    (images.get(handle), residency.images.get(&handle.id()))
+                {
+                    if image.resident_mip_level != streamed.resident_mip_level
+                        || image.data.len() as u64 != streamed.chain_bytes[0]
+                    {
+                        residency.images.remove(&handle.id());
+                    }
+                }
+            }
+            AssetEvent::Created { .. } => {}
+        }
+    }
+
+    let now = time.elapsed_seconds();
+    for (id, screen_size) in requests.screen_sizes.drain() {
+        let handle = Handle::weak(id);
+        let streamed = match residency.images.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let Some(image) = images.get(&handle) else {
+                    continue;
+                };
+                let mut streamed = StreamedImage {
+                    screen_size,
+                    desired_mip_level: 0,
+                    resident_mip_level: 0,
+                    last_requested: now,
+                    size: 0,
+                    chain_bytes: Vec::new(),
+                };
+                let generates_mip_chain =
+                    image.generate_mipmaps && image.texture_descriptor.mip_level_count == 1;
+                if generates_mip_chain || smallest_resident_mip_level(image) > 0 {
+                    // Only the smallest level is uploaded at first, the other ones are streamed in
+                    let image = images.get_mut(&handle).unwrap();
+                    if generates_mip_chain {
+                        generate_mip_chain(image);
+                    }
+                    image.resident_mip_level = smallest_resident_mip_level(image);
+                    streamed.resident_mip_level = image.resident_mip_level;
+                }
+                let image = images.get(&handle).unwrap();
+                let descriptor = &image.texture_descriptor;
+                let layers = descriptor.size.depth_or_array_layers as u64;
+                streamed.size = descriptor.size.width.max(descriptor.size.height);
+                streamed.chain_bytes = (0..=smallest_resident_mip_level(image))
+                    .map(|level| mip_chain_bytes(descriptor, level) as u64 * layers)
+                    .collect();
+                entry.insert(streamed)
+            }
+        };
+        streamed.screen_size = screen_size;
+        streamed.last_requested = now;
+    }
+
+    // The mip levels the images are drawn at, where each texel covers about a pixel
+    let mut desired_bytes = 0;
+    for streamed in residency.images.values_mut() {
+        streamed.desired_mip_level = if now - streamed.last_requested > settings.eviction_delay {
+            streamed.smallest_mip_level()
+        } else {
+            desired_mip_level(streamed.size, streamed.screen_size)
+                .min(streamed.smallest_mip_level())
+        };
+        desired_bytes += streamed.chain_bytes[streamed.desired_mip_level as usize];
+    }
+
+    // Over the budget, a level is dropped from each image in turn, starting with the ones covering
+    // the least of the screen, until the images fit
+    if desired_bytes > settings.budget {
+        let mut by_screen_size: Vec<_> = residency.images.values_mut().collect();
+        by_screen_size.sort_by(|a, b| a.screen_size.total_cmp(&b.screen_size));
+        let mut dropped = true;
+        while dropped && desired_bytes > settings.budget {
+            dropped = false;
+            for streamed in &mut by_screen_size {
+                if desired_bytes <= settings.budget {
+                    break;
+                }
+                let level = streamed.desired_mip_level as usize;
+                if level + 1 < streamed.chain_bytes.len() {
+                    desired_bytes -= streamed.chain_bytes[level] - streamed.chain_bytes[level + 1];
+                    streamed.desired_mip_level += 1;
+                    dropped = true;
+                }
+            }
+        }
+    }
+
+    // Levels are dropped right away, but larger ones are uploaded a level per frame, for the
+    // images drawn the largest first
+    let mut uploads = Vec::new();
+    for (id, streamed) in &mut residency.images {
+        match streamed.desired_mip_level.cmp(&streamed.resident_mip_level) {
+            Ordering::Greater => {
+                streamed.resident_mip_level = streamed.desired_mip_level;
+                if let Some(image) = images.get_mut(&Handle::weak(*id)) {
+                    image.resident_mip_level = streamed.resident_mip_level;
+                }
+            }
+            Ordering::Less => uploads.push((*id, streamed.screen_size)),
+            Ordering::Equal => {}
+        }
+    }
+    uploads.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    for (id, _) in uploads.into_iter().take(settings.max_uploads_per_frame) {
+        let streamed = residency.images.get_mut(&id).unwrap();
+        streamed.resident_mip_level -= 1;
+        if let Some(image) = images.get_mut(&Handle::weak(id)) {
+            image.resident_mip_level = streamed.resident_mip_level;
+        }
+    }
+    residency.resident_bytes = residency
+        .images
+        .values()
+        .map(StreamedImage::resident_bytes)
+        .sum();
+}
+
+/// The first mip level of an image `size` texels wide to draw it `screen_size` pixels wide.
+fn desired_mip_level(size: u32, screen_size: f32) -> u32 {
+    (size as f32 / screen_size.max(1.0)).log2().max(0.0) as u32
+}
+
+/// The smallest mip level of the `image` that can be its first resident level, which is 0 for
+/// images that can't be streamed.
+pub(crate) fn smallest_resident_mip_level(image: &Image) -> u32 {
+    let descriptor = &image.texture_descriptor;
+    if descriptor.dimension != TextureDimension::D2
+        || descriptor.sample_count != 1
+        || descriptor
+            .usage
+            .intersects(TextureUsages::RENDER_ATTACHMENT | TextureUsages::STORAGE_BINDING)
+        || image.texture_view_descriptor.is_some()
+        || image.data.len()
+            != mip_chain_bytes(descriptor, 0) * descriptor.size.depth_or_array_layers as usize
+    {
+        return 0;
+    }
+    // The first level of compressed textures needs to be a whole number of blocks
+    let (block_width, block_height) = descriptor.format.describe().block_dimensions;
+    (0..descriptor.mip_level_count)
+        .take_while(|&level| {
+            let size = descriptor.mip_level_size(level).unwrap();
+            size.width % block_width as u32 == 0 && size.height % block_height as u32 == 0
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+/// The bytes of a layer of the mip chain of a 2D texture, starting at `first_mip_level`.
+fn mip_chain_bytes(descriptor: &TextureDescriptor, first_mip_level: u32) -> usize {
+    let info = descriptor.format.describe();
+    (first_mip_level..descriptor.mip_level_count)
+        .map(|level| {
+            let size = descriptor
+                .mip_level_size(level)
+                .unwrap()
+                .physical_size(descriptor.format);
+            let width_blocks = size.width / info.block_dimensions.0 as u32;
+            let height_blocks = size.height / info.block_dimensions.1 as u32;
+            (width_blocks * height_blocks) as usize * info.block_size as usize
+        })
+        .sum()
+}
+
+/// The descriptor and data of the resident mip levels of the `image`, when it doesn't upload all
+/// of them.
+pub(crate) fn resident_mip_levels(
+    image: &Image,
+) -> Option<(TextureDescriptor<'static>, Cow<'_, [u8]>)> {
+    let first_mip_level = image
+        .resident_mip_level
+        .min(smallest_resident_mip_level(image));
+    if first_mip_level == 0 {
+        return None;
+    }
+
+    let descriptor = &image.texture_descriptor;
+    let layer_bytes = mip_chain_bytes(descriptor, 0);
+    let skipped_bytes = layer_bytes - mip_chain_bytes(descriptor, first_mip_level);
+    let data = if descriptor.size.depth_or_array_layers == 1 {
+        Cow::Borrowed(&image.data[skipped_bytes..])
+    } else {
+        // The data is laid out layer by layer, each with all of its mip levels
+        Cow::Owned(
+            image
+                .data
+                .chunks_exact(layer_bytes)
+                .flat_map(|layer| &layer[skipped_bytes..])
+                .copied()
+                .collect(),
+        )
+    };
+    Some((
+        TextureDescriptor {
+            size: descriptor.mip_level_size(first_mip_level).unwrap(),
+            mip_level_count: descriptor.mip_level_count - first_mip_level,
+            ..descriptor.clone()
+        },
+        data,
+    ))
+}
+
+/// Replaces the single mip level of the `image` with its full mip chain, averaging each texel of a
+/// level from four texels of the level above. Returns `false` for formats other than the 8 bit
+/// ones, which are left alone.
+fn generate_mip_chain(image: &mut Image) -> bool {
+    let srgb = match image.texture_descriptor.format {
+        TextureFormat::R8Unorm
+        | TextureFormat::Rg8Unorm
+        | TextureFormat::Rgba8Unorm
+        | TextureFormat::Bgra8Unorm => false,
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => true,
+        _ => return false,
+    };
+    let descriptor = &image.texture_descriptor;
+    let channels = descriptor.format.describe().block_size as usize;
+    let mip_level_count = mip_level_count(descriptor.size);
+    let layer_bytes = mip_chain_bytes(descriptor, 0);
+    if descriptor.dimension != TextureDimension::D2
+        || image.data.len() != layer_bytes * descriptor.size.depth_or_array_layers as usize
+    {
+        return false;
+    }
+
+    // Colors are averaged in linear space, but alpha is always linear
+    let to_linear = |channel: usize, value: u8| {
+        let value = value as f32 / 255.0;
+        if srgb && channel < 3 {
+            value.nonlinear_to_linear_srgb()
+        } else {
+            value
+        }
+    };
+    let from_linear = |channel: usize, value: f32| {
+        let value = if srgb && channel < 3 {
+            value.linear_to_nonlinear_srgb()
+        } else {
+            value
+        };
+        (value * 255.0).round() as u8
+    };
+
+    let mut data = Vec::new();
+    for layer in image.data.chunks_exact(layer_bytes) {
+        let mut level = layer.to_vec();
+        let mut size = (descriptor.size.width, descriptor.size.height);
+        data.extend_from_slice(&level);
+        for _ in 1..mip_level_count {
+            let next_size = ((size.0 / 2).max(1), (size.1 / 2).max(1));
+            let mut next_level =
+                Vec::with_capacity((next_size.0 * next_size.1) as usize * channels);
+            for y in 0..next_size.1 {
+                for x in 0..next_size.0 {
+                    for channel in 0..channels {
+                        let mut sum = 0.0;
+                        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                            let sx = (x * 2 + dx).min(size.0 - 1);
+                            let sy = (y * 2 + dy).min(size.1 - 1);
+                            let index = (sy * size.0 + sx) as usize * channels + channel;
+                            sum += to_linear(channel, level[index]);
+                        }
+                        next_level.push(from_linear(channel, sum / 4.0));
+                    }
+                }
+            }
+            data.extend_from_slice(&next_level);
+            level = next_level;
+            size = next_size;
+        }
+    }
+
+    image.data = data;
+    image.texture_descriptor.mip_level_count = mip_level_count;
+    image.generate_mipmaps = false;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::Extent3d;
+
+    fn image(width: u32, height: u32, layers: u32) -> Image {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: layers,
+            },
+            TextureDimension::D2,
+            &[255, 0, 0, 255],
+            TextureFormat::Rgba8Unorm,
+        );
+        image.generate_mipmaps = true;
+        assert!(generate_mip_chain(&mut image));
+        image
+    }
+
+    #[test]
+    fn generated_mip_chain() {
+        let image = image(8, 4, 1);
+        assert_eq!(image.texture_descriptor.mip_level_count, 4);
+        // 8x4, 4x2, 2x1 and 1x1 texels
+        assert_eq!(image.data.len(), (32 + 8 + 2 + 1) * 4);
+        assert_eq!(image.data[image.data.len() - 4..], [255, 0, 0, 255]);
+        assert_eq!(smallest_resident_mip_level(&image), 3);
+    }
+
+    #[test]
+    fn resident_mip_levels_of_layers() {
+        let mut image = image(4, 4, 2);
+        assert!(resident_mip_levels(&image).is_none());
+
+        image.resident_mip_level = 1;
+        let (descriptor, data) = resident_mip_levels(&image).unwrap();
+        assert_eq!(descriptor.size.width, 2);
+        assert_eq!(descriptor.size.height, 2);
+        assert_eq!(descriptor.size.depth_or_array_layers, 2);
+        assert_eq!(descriptor.mip_level_count, 2);
+        // 2x2 and 1x1 texels for each layer
+        assert_eq!(data.len(), (4 + 1) * 4 * 2);
+
+        // Levels past the smallest one are clamped to it
+        image.resident_mip_level = 10;
+        let (descriptor, _) = resident_mip_levels(&image).unwrap();
+        assert_eq!(descriptor.mip_level_count, 1);
+    }
+
+    #[test]
+    fn desired_mip_levels() {
+        assert_eq!(desired_mip_level(1024, 2000.0), 0);
+        assert_eq!(desired_mip_level(1024, 1024.0), 0);
+        assert_eq!(desired_mip_level(1024, 500.0), 1);
+        assert_eq!(desired_mip_level(1024, 0.0), 10);
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
 };
 use bevy_ecs::{prelude::ResMut, system::Resource};
 use bevy_utils::{Entry, HashMap};
+use parking_lot::Mutex;
 use wgpu::{TextureDescriptor, TextureViewDescriptor};
 
 /// The internal representation of a [`CachedTexture`] used to track whether it was recently used
@@ -26,20 +27,39 @@ pub struct CachedTexture {
 
 /// This resource caches textures that are created repeatedly in the rendering process and
 /// are only required for one frame.
-#[derive(Resource, Default)]
+///
+/// Textures are keyed by their descriptor: each texture is handed out once per frame, and is
+/// reused by the requests with the same descriptor in the next frames. Textures that haven't been
+/// requested for [`max_unused_frames`](Self::max_unused_frames) are freed, such as the ones of
+/// the previous size of a resized window.
+///
+/// Textures can be requested from systems with a [`ResMut<TextureCache>`], and from render graph
+/// nodes through the [`World`](bevy_ecs::world::World) they run with.
+#[derive(Resource)]
 pub struct TextureCache {
-    textures: HashMap<wgpu::TextureDescriptor<'static>, Vec<CachedTextureMeta>>,
+    textures: Mutex<HashMap<wgpu::TextureDescriptor<'static>, Vec<CachedTextureMeta>>>,
+    /// The number of frames a texture can go unrequested before it is freed. Defaults to 3.
+    pub max_unused_frames: usize,
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self {
+            textures: Default::default(),
+            max_unused_frames: 3,
+        }
+    }
 }
 
 impl TextureCache {
     /// Retrieves a texture that matches the `descriptor`. If no matching one is found a new
     /// [`CachedTexture`] is created.
     pub fn get(
-        &mut self,
+        &self,
         render_device: &RenderDevice,
         descriptor: TextureDescriptor<'static>,
     ) -> CachedTexture {
-        match self.textures.entry(descriptor) {
+        match self.textures.lock().entry(descriptor) {
             Entry::Occupied(mut entry) => {
                 for texture in entry.get_mut().iter_mut() {
                     if !texture.taken {
@@ -82,16 +102,31 @@ impl TextureCache {
         }
     }
 
+    /// Returns the number of textures in the cache, whether they were requested this frame or
+    /// not.
+    pub fn len(&self) -> usize {
+        self.textures.lock().values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` if the cache holds no textures.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Updates the cache and only retains recently used textures.
     pub fn update(&mut self) {
-        for textures in self.textures.values_mut() {
+        let max_unused_frames = self.max_unused_frames;
+        self.textures.get_mut().retain(|_, textures| {
             for texture in textures.iter_mut() {
                 texture.frames_since_last_use += 1;
                 texture.taken = false;
             }
 
-            textures.retain(|texture| texture.frames_since_last_use < 3);
-        }
+            textures.retain(|texture| texture.frames_since_last_use < max_unused_frames);
+            // The descriptors of textures that are no longer used, like the previous sizes of
+            // resized targets, are forgotten too
+            !textures.is_empty()
+        });
     }
 }
 
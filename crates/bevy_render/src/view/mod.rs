@@ -5,22 +5,23 @@ pub use visibility::*;
 pub use window::*;
 
 use crate::{
-    camera::ExtractedCamera,
+    camera::{ExtractedCamera, TemporalJitter},
     extract_resource::{ExtractResource, ExtractResourcePlugin},
     prelude::Image,
     rangefinder::ViewRangefinder3d,
     render_asset::RenderAssets,
     render_resource::{DynamicUniformBuffer, ShaderType, Texture, TextureView},
-    renderer::{RenderDevice, RenderQueue},
+    renderer::{RenderDevice, StagingBelt},
     texture::{BevyDefault, TextureCache},
     RenderApp, RenderStage,
 };
 use bevy_app::{App, Plugin};
+use bevy_asset::Handle;
 use bevy_ecs::prelude::*;
-use bevy_math::{Mat4, UVec4, Vec3, Vec4};
-use bevy_reflect::Reflect;
+use bevy_math::{Mat4, UVec4, Vec2, Vec3, Vec4};
+use bevy_reflect::{std_traits::ReflectDefault, Reflect};
 use bevy_transform::components::GlobalTransform;
-use bevy_utils::HashMap;
+use bevy_utils::{tracing::warn, HashMap};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use wgpu::{
     Color, Extent3d, Operations, RenderPassColorAttachment, TextureDescriptor, TextureDimension,
@@ -31,7 +32,8 @@ pub struct ViewPlugin;
 
 impl Plugin for ViewPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<ComputedVisibility>()
+        app.register_type::<ColorGrading>()
+            .register_type::<ComputedVisibility>()
             .register_type::<ComputedVisibilityFlags>()
             .register_type::<Msaa>()
             .register_type::<RenderLayers>()
@@ -64,7 +66,7 @@ impl Plugin for ViewPlugin {
 ///     .insert_resource(Msaa { samples: 4 })
 ///     .run();
 /// ```
-#[derive(Resource, Clone, ExtractResource, Reflect)]
+#[derive(Resource, Clone, Reflect)]
 #[reflect(Resource)]
 pub struct Msaa {
     /// The number of samples to run for Multi-Sample Anti-Aliasing. Higher numbers result in
@@ -83,6 +85,41 @@ impl Default for Msaa {
     }
 }
 
+impl Msaa {
+    /// The sample counts the renderer can currently create multisampled attachments with.
+    pub const SUPPORTED_SAMPLE_COUNTS: [u32; 2] = [1, 4];
+
+    /// Returns `true` if more than one sample is taken per pixel.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.samples > 1
+    }
+}
+
+impl ExtractResource for Msaa {
+    type Source = Self;
+
+    /// Extracts the [`Msaa`] configuration, replacing an unsupported sample count with the
+    /// closest supported one so that all pipelines and attachments agree on the sample count.
+    fn extract_resource(source: &Self::Source) -> Self {
+        if Self::SUPPORTED_SAMPLE_COUNTS.contains(&source.samples) {
+            return source.clone();
+        }
+
+        let samples = Self::SUPPORTED_SAMPLE_COUNTS
+            .into_iter()
+            .min_by_key(|supported| supported.abs_diff(source.samples))
+            .unwrap();
+        warn!(
+            "Msaa sample count {} is not supported, using {} instead. Supported sample counts: {:?}",
+            source.samples,
+            samples,
+            Self::SUPPORTED_SAMPLE_COUNTS
+        );
+        Self { samples }
+    }
+}
+
 #[derive(Component)]
 pub struct ExtractedView {
     pub projection: Mat4,
@@ -99,9 +136,53 @@ impl ExtractedView {
     }
 }
 
+/// The color grading applied to the output of a [`Camera`](crate::camera::Camera), along with its
+/// tonemapping.
+///
+/// This allows grading a camera, for example for a day and night cycle or an underwater effect,
+/// without a custom full screen shader. The exposure, contrast and saturation are applied to the
+/// HDR colors before they are tonemapped, then the [`lut`](Self::lut) is applied to the tonemapped
+/// colors.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component, Default)]
+pub struct ColorGrading {
+    /// The exposure offset, in stops. Each stop doubles the brightness of the colors.
+    ///
+    /// Defaults to `0.0`.
+    pub exposure: f32,
+    /// The contrast, applied around middle grey so that it keeps the same brightness. Values
+    /// below `1.0` flatten the colors, and values above `1.0` push them away from middle grey.
+    ///
+    /// Defaults to `1.0`.
+    pub contrast: f32,
+    /// The saturation, where `0.0` is greyscale and values above `1.0` make colors more vivid.
+    ///
+    /// Defaults to `1.0`.
+    pub saturation: f32,
+    /// A 3D lookup table remapping the tonemapped colors, indexed by their sRGB encoded red, green
+    /// and blue. The image must have a [`TextureDimension::D3`] and a filterable format.
+    ///
+    /// The lookup table is applied by the tonemapping node, so it is only used by cameras with
+    /// [`hdr`](crate::camera::Camera::hdr) enabled and tonemapping enabled.
+    pub lut: Option<Handle<Image>>,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            lut: None,
+        }
+    }
+}
+
 #[derive(Clone, ShaderType)]
+#[shader_type(uniform)]
 pub struct ViewUniform {
     view_proj: Mat4,
+    unjittered_view_proj: Mat4,
     inverse_view_proj: Mat4,
     view: Mat4,
     inverse_view: Mat4,
@@ -110,13 +191,23 @@ pub struct ViewUniform {
     world_position: Vec3,
     // viewport(x_origin, y_origin, width, height)
     viewport: Vec4,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: Vec3,
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource)]
 pub struct ViewUniforms {
     pub uniforms: DynamicUniformBuffer<ViewUniform>,
 }
 
+impl FromWorld for ViewUniforms {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            uniforms: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct ViewUniformOffset {
     pub offset: u32,
@@ -235,19 +326,32 @@ pub struct ViewDepthTexture {
 fn prepare_view_uniforms(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
-    render_queue: Res<RenderQueue>,
+    staging_belt: Res<StagingBelt>,
     mut view_uniforms: ResMut<ViewUniforms>,
-    views: Query<(Entity, &ExtractedView)>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        Option<&TemporalJitter>,
+        Option<&ColorGrading>,
+    )>,
 ) {
     view_uniforms.uniforms.clear();
-    for (entity, camera) in &views {
-        let projection = camera.projection;
+    let default_color_grading = ColorGrading::default();
+    for (entity, camera, temporal_jitter, color_grading) in &views {
+        let unjittered_projection = camera.projection;
+        let mut projection = unjittered_projection;
+        if let Some(temporal_jitter) = temporal_jitter {
+            let viewport_size = Vec2::new(camera.viewport.z as f32, camera.viewport.w as f32);
+            temporal_jitter.jitter_projection(&mut projection, viewport_size);
+        }
         let inverse_projection = projection.inverse();
         let view = camera.transform.compute_matrix();
         let inverse_view = view.inverse();
+        let color_grading = color_grading.unwrap_or(&default_color_grading);
         let view_uniforms = ViewUniformOffset {
             offset: view_uniforms.uniforms.push(ViewUniform {
                 view_proj: projection * inverse_view,
+                unjittered_view_proj: unjittered_projection * inverse_view,
                 inverse_view_proj: view * inverse_projection,
                 view,
                 inverse_view,
@@ -255,6 +359,11 @@ fn prepare_view_uniforms(
                 inverse_projection,
                 world_position: camera.transform.translation(),
                 viewport: camera.viewport.as_vec4(),
+                color_grading: Vec3::new(
+                    color_grading.exposure,
+                    color_grading.contrast,
+                    color_grading.saturation,
+                ),
             }),
         };
 
@@ -263,7 +372,7 @@ fn prepare_view_uniforms(
 
     view_uniforms
         .uniforms
-        .write_buffer(&render_device, &render_queue);
+        .write_buffer_staged(&render_device, &staging_belt);
 }
 
 #[derive(Clone)]
@@ -280,7 +389,7 @@ fn prepare_view_targets(
     images: Res<RenderAssets<Image>>,
     msaa: Res<Msaa>,
     render_device: Res<RenderDevice>,
-    mut texture_cache: ResMut<TextureCache>,
+    texture_cache: Res<TextureCache>,
     cameras: Query<(Entity, &ExtractedCamera, &ExtractedView)>,
 ) {
     let mut textures = HashMap::default();
@@ -334,7 +443,7 @@ fn prepare_view_targets(
                                     },
                                 )
                                 .default_view,
-                            sampled: (msaa.samples > 1).then(|| {
+                            sampled: msaa.is_enabled().then(|| {
                                 texture_cache
                                     .get(
                                         &render_device,
@@ -364,3 +473,20 @@ fn prepare_view_targets(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Msaa;
+    use crate::extract_resource::ExtractResource;
+
+    #[test]
+    fn extract_msaa_sample_count() {
+        let extract = |samples| Msaa::extract_resource(&Msaa { samples }).samples;
+        assert_eq!(extract(1), 1);
+        assert_eq!(extract(4), 4);
+        assert_eq!(extract(0), 1);
+        assert_eq!(extract(2), 1);
+        assert_eq!(extract(3), 4);
+        assert_eq!(extract(8), 4);
+    }
+}
@@ -3,29 +3,30 @@ mod render_layers;
 pub use render_layers::*;
 
 use bevy_app::{CoreStage, Plugin};
-use bevy_asset::{Assets, Handle};
+use bevy_asset::{AssetEvent, Assets, Handle};
 use bevy_ecs::prelude::*;
 use bevy_hierarchy::{Children, Parent};
 use bevy_reflect::Reflect;
 use bevy_reflect::{std_traits::ReflectDefault, FromReflect};
 use bevy_transform::components::GlobalTransform;
 use bevy_transform::TransformSystem;
+use bevy_utils::HashSet;
 use std::cell::Cell;
 use thread_local::ThreadLocal;
 
 use crate::{
-    camera::{
-        camera_system, Camera, CameraProjection, OrthographicProjection, PerspectiveProjection,
-        Projection,
-    },
+    camera::{Camera, CameraProjection},
     mesh::Mesh,
-    primitives::{Aabb, Frustum, Sphere},
+    primitives::{Aabb, Frustum, Sphere, WorldBounds},
 };
 
 /// User indication of whether an entity is visible. Propagates down the entity hierarchy.
 
 /// If an entity is hidden in this way,  all [`Children`] (and all of their children and so on) will also be hidden.
 /// This is done by setting the values of their [`ComputedVisibility`] component.
+///
+/// Propagation starts at entities without a [`Parent`], or whose parent has no [`Visibility`] and
+/// [`ComputedVisibility`].
 #[derive(Component, Clone, Reflect, FromReflect, Debug)]
 #[reflect(Component, Default)]
 pub struct Visibility {
@@ -161,8 +162,7 @@ pub struct NoFrustumCulling;
 /// This component is intended to be attached to the same entity as the [`Camera`] and
 /// the [`Frustum`] defining the view.
 ///
-/// Currently this component is ignored by the sprite renderer, so sprite rendering
-/// is not optimized per view.
+/// Only entities sharing a layer with the view's [`RenderLayers`] are visible from it.
 #[derive(Clone, Component, Default, Debug, Reflect)]
 #[reflect(Component)]
 pub struct VisibleEntities {
@@ -187,8 +187,11 @@ impl VisibleEntities {
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum VisibilitySystems {
     CalculateBounds,
-    UpdateOrthographicFrusta,
-    UpdatePerspectiveFrusta,
+    /// Label for the [`update_world_bounds()`] system keeping the [`WorldBounds`] of entities
+    /// up to date.
+    UpdateWorldBounds,
+    /// Label for the [`update_frusta()`] systems of all the projections, added by the
+    /// [`CameraProjectionPlugin`](crate::camera::CameraProjectionPlugin)s.
     UpdateProjectionFrusta,
     VisibilityPropagate,
     /// Label for the [`check_visibility()`] system updating each frame the [`ComputedVisibility`]
@@ -208,32 +211,9 @@ impl Plugin for VisibilityPlugin {
         )
         .add_system_to_stage(
             CoreStage::PostUpdate,
-            update_frusta::<OrthographicProjection>
-                .label(UpdateOrthographicFrusta)
-                .after(camera_system::<OrthographicProjection>)
-                .after(TransformSystem::TransformPropagate)
-                // We assume that no camera will have more than one projection component,
-                // so these systems will run independently of one another.
-                // FIXME: Add an archetype invariant for this https://github.com/bevyengine/bevy/issues/1481.
-                .ambiguous_with(update_frusta::<PerspectiveProjection>)
-                .ambiguous_with(update_frusta::<Projection>),
-        )
-        .add_system_to_stage(
-            CoreStage::PostUpdate,
-            update_frusta::<PerspectiveProjection>
-                .label(UpdatePerspectiveFrusta)
-                .after(camera_system::<PerspectiveProjection>)
-                .after(TransformSystem::TransformPropagate)
-                // We assume that no camera will have more than one projection component,
-                // so these systems will run independently of one another.
-                // FIXME: Add an archetype invariant for this https://github.com/bevyengine/bevy/issues/1481.
-                .ambiguous_with(update_frusta::<Projection>),
-        )
-        .add_system_to_stage(
-            CoreStage::PostUpdate,
-            update_frusta::<Projection>
-                .label(UpdateProjectionFrusta)
-                .after(camera_system::<Projection>)
+            update_world_bounds
+                .label(UpdateWorldBounds)
+                .after(CalculateBounds)
                 .after(TransformSystem::TransformPropagate),
         )
         .add_system_to_stage(
@@ -245,8 +225,6 @@ impl Plugin for VisibilityPlugin {
             check_visibility
                 .label(CheckVisibility)
                 .after(CalculateBounds)
-                .after(UpdateOrthographicFrusta)
-                .after(UpdatePerspectiveFrusta)
                 .after(UpdateProjectionFrusta)
                 .after(VisibilityPropagate)
                 .after(TransformSystem::TransformPropagate),
@@ -254,10 +232,21 @@ impl Plugin for VisibilityPlugin {
     }
 }
 
+/// Computes the [`Aabb`] of mesh entities that don't have one yet.
+///
+/// The [`Aabb`] is recomputed when the entity's [`Handle<Mesh>`] is changed after it was added,
+/// or when its [`Mesh`] asset is modified. An [`Aabb`] inserted together with the handle is kept
+/// until then.
 pub fn calculate_bounds(
     mut commands: Commands,
     meshes: Res<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
     without_aabb: Query<(Entity, &Handle<Mesh>), (Without<Aabb>, Without<NoFrustumCulling>)>,
+    changed_mesh: Query<
+        (Entity, &Handle<Mesh>, ChangeTrackers<Handle<Mesh>>),
+        (With<Aabb>, Changed<Handle<Mesh>>, Without<NoFrustumCulling>),
+    >,
+    with_aabb: Query<(Entity, &Handle<Mesh>), (With<Aabb>, Without<NoFrustumCulling>)>,
 ) {
     for (entity, mesh_handle) in &without_aabb {
         if let Some(mesh) = meshes.get(mesh_handle) {
@@ -266,6 +255,61 @@ pub fn calculate_bounds(
             }
         }
     }
+
+    let mut update_bounds = |entity: Entity, mesh_handle: &Handle<Mesh>| {
+        if let Some(aabb) = meshes.get(mesh_handle).and_then(Mesh::compute_aabb) {
+            commands.entity(entity).insert(aabb);
+        } else {
+            commands.entity(entity).remove::<Aabb>();
+        }
+    };
+
+    for (entity, mesh_handle, mesh_tracker) in &changed_mesh {
+        if !mesh_tracker.is_added() {
+            update_bounds(entity, mesh_handle);
+        }
+    }
+
+    let modified_meshes: HashSet<_> = mesh_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle),
+            _ => None,
+        })
+        .collect();
+    if modified_meshes.is_empty() {
+        return;
+    }
+    for (entity, mesh_handle) in &with_aabb {
+        if modified_meshes.contains(mesh_handle) {
+            update_bounds(entity, mesh_handle);
+        }
+    }
+}
+
+/// Adds the [`WorldBounds`] of the entities with an [`Aabb`], updates them when either their
+/// [`Aabb`] or [`GlobalTransform`] changes, and removes them from the entities that lost their
+/// [`Aabb`].
+pub fn update_world_bounds(
+    mut commands: Commands,
+    without_bounds: Query<(Entity, &Aabb, &GlobalTransform), Without<WorldBounds>>,
+    mut changed_bounds: Query<
+        (&Aabb, &GlobalTransform, &mut WorldBounds),
+        Or<(Changed<Aabb>, Changed<GlobalTransform>)>,
+    >,
+    without_aabb: Query<Entity, (With<WorldBounds>, Without<Aabb>)>,
+) {
+    for (entity, aabb, transform) in &without_bounds {
+        commands
+            .entity(entity)
+            .insert(WorldBounds::new(aabb, &transform.affine()));
+    }
+    for (aabb, transform, mut bounds) in &mut changed_bounds {
+        *bounds = WorldBounds::new(aabb, &transform.affine());
+    }
+    for entity in &without_aabb {
+        commands.entity(entity).remove::<WorldBounds>();
+    }
 }
 
 pub fn update_frusta<T: Component + CameraProjection + Send + Sync + 'static>(
@@ -295,6 +339,8 @@ fn visibility_propagate_system(
     >,
     mut visibility_query: Query<(&Visibility, &mut ComputedVisibility, &Parent)>,
     children_query: Query<&Children, (With<Parent>, With<Visibility>, With<ComputedVisibility>)>,
+    child_query: Query<(Entity, &Parent), (With<Visibility>, With<ComputedVisibility>)>,
+    visibility_parent_query: Query<(), (With<Visibility>, With<ComputedVisibility>)>,
 ) {
     for (children, visibility, mut computed_visibility, entity) in root_query.iter_mut() {
         // reset "view" visibility here ... if this entity should be drawn a future system should set this to true
@@ -311,6 +357,20 @@ fn visibility_propagate_system(
             }
         }
     }
+
+    // Entities whose parent doesn't take part in visibility propagation are the root of their
+    // own visibility hierarchy.
+    for (entity, parent) in &child_query {
+        if !visibility_parent_query.contains(parent.get()) {
+            let _ = propagate_recursive(
+                true,
+                &mut visibility_query,
+                &children_query,
+                entity,
+                parent.get(),
+            );
+        }
+    }
 }
 
 fn propagate_recursive(
@@ -401,7 +461,7 @@ pub fn check_visibility(
                         return;
                     }
                     // If we have an aabb, do aabb-based frustum culling
-                    if !frustum.intersects_obb(model_aabb, &model, false) {
+                    if !frustum.intersects_obb(model_aabb, &model, true, false) {
                         return;
                     }
                 }
@@ -449,7 +509,12 @@ mod test {
 
     use super::*;
 
+    use bevy_asset::AddAsset;
     use bevy_hierarchy::BuildWorldChildren;
+    use bevy_math::{Quat, Vec3};
+    use bevy_transform::components::Transform;
+
+    use crate::mesh::shape;
 
     #[test]
     fn visibility_propagation() {
@@ -578,4 +643,183 @@ mod test {
             "child's invisibility propagates down to grandchild"
         );
     }
+
+    #[test]
+    fn bounds_follow_mesh_changes() {
+        let mut app = App::new();
+        app.add_plugin(bevy_core::CorePlugin::default())
+            .add_plugin(bevy_asset::AssetPlugin::default())
+            .add_asset::<Mesh>()
+            .add_system_to_stage(CoreStage::PostUpdate, calculate_bounds);
+
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let small = meshes.add(shape::Cube { size: 1.0 }.into());
+        let large = meshes.add(shape::Cube { size: 4.0 }.into());
+        let entity = app.world.spawn(small.clone()).id();
+        let custom = app
+            .world
+            .spawn((small.clone(), Aabb::from_min_max(Vec3::ZERO, Vec3::ONE)))
+            .id();
+        let half_extents =
+            |app: &App| Vec3::from(app.world.get::<Aabb>(entity).unwrap().half_extents);
+
+        app.update();
+        assert_eq!(half_extents(&app), Vec3::splat(0.5));
+        // An Aabb inserted with the mesh is kept
+        assert_eq!(
+            app.world.get::<Aabb>(custom).unwrap().center,
+            Vec3::splat(0.5).into()
+        );
+
+        // Changing the handle recomputes the bounds
+        *app.world.get_mut::<Handle<Mesh>>(entity).unwrap() = large;
+        app.update();
+        assert_eq!(half_extents(&app), Vec3::splat(2.0));
+
+        // Modifying the mesh asset recomputes the bounds of the entities using it
+        *app.world.get_mut::<Handle<Mesh>>(entity).unwrap() = small.clone();
+        app.update();
+        *app.world
+            .resource_mut::<Assets<Mesh>>()
+            .get_mut(&small)
+            .unwrap() = shape::Cube { size: 2.0 }.into();
+        // The asset event is sent at the end of this frame, and handled in the next one
+        app.update();
+        app.update();
+        assert_eq!(half_extents(&app), Vec3::splat(1.0));
+    }
+
+    #[test]
+    fn world_bounds_follow_transforms() {
+        let mut app = App::new();
+        app.add_system(update_world_bounds);
+
+        let aabb = Aabb::from_min_max(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        let transform = Transform::from_xyz(0.0, 0.0, 5.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+            .with_scale(Vec3::splat(2.0));
+        let entity = app
+            .world
+            .spawn((aabb, GlobalTransform::from(transform)))
+            .id();
+        let bounds = |app: &App| app.world.get::<WorldBounds>(entity).cloned();
+
+        app.update();
+        let world_bounds = bounds(&app).unwrap();
+        // The box is rotated to stand along the Y axis, and its center moves with it
+        assert!((Vec3::from(world_bounds.aabb.center) - Vec3::new(-1.0, 0.0, 5.0)).length() < 1e-5);
+        assert!(
+            (Vec3::from(world_bounds.aabb.half_extents) - Vec3::new(1.0, 2.0, 0.0)).length() < 1e-5
+        );
+        assert!((world_bounds.sphere.radius - 2.0 * 1.25f32.sqrt()).abs() < 1e-5);
+
+        *app.world.get_mut::<GlobalTransform>(entity).unwrap() =
+            GlobalTransform::from_xyz(3.0, 0.0, 0.0);
+        app.update();
+        assert_eq!(
+            Vec3::from(bounds(&app).unwrap().aabb.center),
+            Vec3::new(3.0, 0.5, 0.0)
+        );
+
+        app.world.entity_mut(entity).remove::<Aabb>();
+        app.update();
+        assert!(bounds(&app).is_none());
+    }
+
+    #[test]
+    fn visibility_propagation_without_visible_parent() {
+        let mut app = App::new();
+        app.add_system(visibility_propagate_system);
+
+        let parent = app.world.spawn_empty().id();
+        let child = app
+            .world
+            .spawn((Visibility::default(), ComputedVisibility::default()))
+            .id();
+        let hidden_child = app
+            .world
+            .spawn((
+                Visibility { is_visible: false },
+                ComputedVisibility::default(),
+            ))
+            .id();
+        let grandchild = app
+            .world
+            .spawn((Visibility::default(), ComputedVisibility::default()))
+            .id();
+        app.world
+            .entity_mut(parent)
+            .push_children(&[child, hidden_child]);
+        app.world
+            .entity_mut(hidden_child)
+            .push_children(&[grandchild]);
+
+        app.update();
+
+        let is_visible_in_hierarchy = |entity: Entity| {
+            app.world
+                .get::<ComputedVisibility>(entity)
+                .unwrap()
+                .is_visible_in_hierarchy()
+        };
+        assert!(is_visible_in_hierarchy(child));
+        assert!(!is_visible_in_hierarchy(hidden_child));
+        assert!(!is_visible_in_hierarchy(grandchild));
+    }
+
+    #[test]
+    fn visible_entities_respect_render_layers() {
+        let mut app = App::new();
+        app.add_plugin(bevy_core::CorePlugin::default())
+            .add_system(visibility_propagate_system.label(VisibilitySystems::VisibilityPropagate))
+            .add_system(check_visibility.after(VisibilitySystems::VisibilityPropagate));
+
+        let main_camera = app
+            .world
+            .spawn((
+                Camera::default(),
+                Frustum::default(),
+                VisibleEntities::default(),
+            ))
+            .id();
+        let minimap_camera = app
+            .world
+            .spawn((
+                Camera::default(),
+                Frustum::default(),
+                VisibleEntities::default(),
+                RenderLayers::layer(2),
+            ))
+            .id();
+        let default_layer = app.world.spawn(VisibilityBundle::default()).id();
+        let minimap_layer = app
+            .world
+            .spawn((VisibilityBundle::default(), RenderLayers::layer(2)))
+            .id();
+        let both_layers = app
+            .world
+            .spawn((VisibilityBundle::default(), RenderLayers::layer(0).with(2)))
+            .id();
+
+        app.update();
+
+        let visible_entities = |camera: Entity| {
+            let mut entities = app
+                .world
+                .get::<VisibleEntities>(camera)
+                .unwrap()
+                .entities
+                .clone();
+            entities.sort();
+            entities
+        };
+        assert_eq!(
+            visible_entities(main_camera),
+            vec![default_layer, both_layers]
+        );
+        assert_eq!(
+            visible_entities(minimap_camera),
+            vec![minimap_layer, both_layers]
+        );
+    }
 }
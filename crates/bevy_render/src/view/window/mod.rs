@@ -3,15 +3,23 @@ use crate::{
     renderer::{RenderAdapter, RenderDevice, RenderInstance},
     Extract, RenderApp, RenderStage,
 };
-use bevy_app::{App, Plugin};
+use bevy_app::{App, CoreStage, Plugin};
 use bevy_ecs::prelude::*;
-use bevy_utils::{tracing::debug, HashMap, HashSet};
+use bevy_utils::{
+    tracing::{debug, warn},
+    HashMap, HashSet,
+};
 use bevy_window::{
     CompositeAlphaMode, PresentMode, RawHandleWrapper, WindowClosed, WindowId, Windows,
 };
 use std::ops::{Deref, DerefMut};
 use wgpu::TextureFormat;
 
+mod screenshot;
+
+pub(crate) use screenshot::submit_screenshots;
+pub use screenshot::{clear_screenshots, ScreenshotEvent, Screenshots};
+
 /// Token to ensure a system runs on the main thread.
 #[derive(Resource, Default)]
 pub struct NonSendMarker;
@@ -25,15 +33,29 @@ pub enum WindowSystem {
 
 impl Plugin for WindowRenderPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<Screenshots>()
+            .add_event::<ScreenshotEvent>()
+            .add_system_to_stage(CoreStage::First, clear_screenshots);
+
         if let Ok(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app
                 .init_resource::<ExtractedWindows>()
                 .init_resource::<WindowSurfaces>()
                 .init_resource::<NonSendMarker>()
+                .init_resource::<screenshot::ExtractedScreenshots>()
+                .init_resource::<screenshot::ScreenshotTargets>()
+                .init_resource::<screenshot::ScreenshotPipelines>()
                 .add_system_to_stage(RenderStage::Extract, extract_windows)
+                .add_system_to_stage(RenderStage::Extract, screenshot::extract_screenshots)
                 .add_system_to_stage(
                     RenderStage::Prepare,
                     prepare_windows.label(WindowSystem::Prepare),
+                )
+                .add_system_to_stage(
+                    RenderStage::Prepare,
+                    screenshot::prepare_screenshots
+                        .after(prepare_windows)
+                        .label(WindowSystem::Prepare),
                 );
         }
     }
@@ -73,14 +95,14 @@ impl DerefMut for ExtractedWindows {
 
 fn extract_windows(
     mut extracted_windows: ResMut<ExtractedWindows>,
+    mut window_surfaces: ResMut<WindowSurfaces>,
     mut closed: Extract<EventReader<WindowClosed>>,
     windows: Extract<Res<Windows>>,
 ) {
     for window in windows.iter() {
-        let (new_width, new_height) = (
-            window.physical_width().max(1),
-            window.physical_height().max(1),
-        );
+        // A size of zero, for example while the window is minimized, is kept so that
+        // `prepare_windows` skips the surface until the window is restored.
+        let (new_width, new_height) = (window.physical_width(), window.physical_height());
         let new_present_mode = window.present_mode();
 
         let mut extracted_window =
@@ -127,6 +149,7 @@ fn extract_windows(
     }
     for closed_window in closed.iter() {
         extracted_windows.remove(&closed_window.id);
+        window_surfaces.remove(&closed_window.id);
     }
 }
 
@@ -142,8 +165,22 @@ pub struct WindowSurfaces {
     configured_windows: HashSet<WindowId>,
 }
 
+impl WindowSurfaces {
+    /// Drops the surface of a closed window, so that it gets recreated if a window with the same
+    /// id is opened again.
+    fn remove(&mut self, window_id: &WindowId) {
+        self.surfaces.remove(window_id);
+        self.configured_windows.remove(window_id);
+    }
+}
+
 /// Creates and (re)configures window surfaces, and obtains a swapchain texture for rendering.
 ///
+/// Outdated and lost surfaces are reconfigured, or created again the next frame if that fails,
+/// and the frames timing out are skipped. A lost [`RenderDevice`] isn't recovered from: wgpu 0.14
+/// doesn't report it, and panics in `Queue::submit` and `Device::poll` once it happens, before
+/// any system could re-create the pipelines and bind groups.
+///
 /// NOTE: `get_current_texture` in `prepare_windows` can take a long time if the GPU workload is
 /// the performance bottleneck. This can be seen in profiles as multiple prepare-stage systems all
 /// taking an unusually long time to complete, and all finishing at about the same time as the
@@ -180,6 +217,12 @@ pub fn prepare_windows(
         .filter(|x| x.raw_handle.is_some())
     {
         let window_surfaces = window_surfaces.deref_mut();
+        if window.physical_width == 0 || window.physical_height == 0 {
+            // Surfaces can't be configured with a size of zero, for example while the window is
+            // minimized. Configure it again once the window is restored.
+            window_surfaces.configured_windows.remove(&window.id);
+            continue;
+        }
         let surface_data = window_surfaces
             .surfaces
             .entry(window.id)
@@ -242,34 +285,51 @@ pub fn prepare_windows(
         let surface = &surface_data.surface;
         if not_already_configured || window.size_changed || window.present_mode_changed {
             render_device.configure_surface(surface, &surface_configuration);
-            let frame = surface
-                .get_current_texture()
-                .expect("Error configuring surface");
-            window.swap_chain_texture = Some(TextureView::from(frame));
-        } else {
-            match surface.get_current_texture() {
-                Ok(frame) => {
-                    window.swap_chain_texture = Some(TextureView::from(frame));
-                }
-                Err(wgpu::SurfaceError::Outdated) => {
-                    render_device.configure_surface(surface, &surface_configuration);
-                    let frame = surface
-                        .get_current_texture()
-                        .expect("Error reconfiguring surface");
-                    window.swap_chain_texture = Some(TextureView::from(frame));
-                }
-                #[cfg(target_os = "linux")]
-                Err(wgpu::SurfaceError::Timeout) if may_erroneously_timeout() => {
-                    bevy_utils::tracing::trace!(
-                        "Couldn't get swap chain texture. This is probably a quirk \
-                        of your Linux GPU driver, so it can be safely ignored."
-                    );
-                }
-                Err(err) => {
-                    panic!("Couldn't get swap chain texture, operation unrecoverable: {err}");
+        }
+        let mut surface_lost = false;
+        match surface.get_current_texture() {
+            Ok(frame) => {
+                window.swap_chain_texture = Some(TextureView::from(frame));
+            }
+            // The surface no longer matches the window, for example while it is resized
+            // rapidly, or its swap chain has been dropped by the driver.
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                render_device.configure_surface(surface, &surface_configuration);
+                match surface.get_current_texture() {
+                    Ok(frame) => {
+                        window.swap_chain_texture = Some(TextureView::from(frame));
+                    }
+                    Err(err) => {
+                        warn!(
+                            "Couldn't get swap chain texture of window {:?} after reconfiguring \
+                            its surface, recreating it next frame: {err}",
+                            window.id
+                        );
+                        surface_lost = true;
+                    }
                 }
             }
+            #[cfg(target_os = "linux")]
+            Err(wgpu::SurfaceError::Timeout) if may_erroneously_timeout() => {
+                bevy_utils::tracing::trace!(
+                    "Couldn't get swap chain texture. This is probably a quirk \
+                    of your Linux GPU driver, so it can be safely ignored."
+                );
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                warn!(
+                    "Timed out getting swap chain texture of window {:?}, skipping the frame",
+                    window.id
+                );
+            }
+            Err(err @ wgpu::SurfaceError::OutOfMemory) => {
+                panic!("Couldn't get swap chain texture, operation unrecoverable: {err}");
+            }
         };
+        if surface_lost {
+            window_surfaces.remove(&window.id);
+            continue;
+        }
         window.swap_chain_texture_format = Some(surface_data.format);
     }
 }
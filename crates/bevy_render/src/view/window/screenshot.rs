@@ -0,0 +1,262 @@
+use crate::{
+    render_resource::{BindGroupLayout, RenderPipeline, Texture, TextureView},
+    renderer::{RenderDevice, RenderQueue},
+    texture::readback::{PendingReadback, PendingReadbacks, ReadbackSource},
+    view::ExtractedWindows,
+    Extract,
+};
+use bevy_ecs::prelude::*;
+use bevy_utils::{tracing::warn, HashMap};
+use bevy_window::WindowId;
+use wgpu::TextureFormat;
+
+/// Requests screenshots of windows.
+///
+/// A screenshot is taken of the frame rendered after the request. When its data is available on
+/// the CPU, usually a frame or two later, it is sent as a [`ScreenshotEvent`].
+#[derive(Resource, Default)]
+pub struct Screenshots {
+    requests: Vec<WindowId>,
+}
+
+impl Screenshots {
+    /// Requests a screenshot of the `window` once this frame has been rendered.
+    pub fn request(&mut self, window: WindowId) {
+        if !self.requests.contains(&window) {
+            self.requests.push(window);
+        }
+    }
+}
+
+/// Sent when a screenshot requested through [`Screenshots`] is available on the CPU.
+#[derive(Debug)]
+pub struct ScreenshotEvent {
+    /// The window the screenshot was taken of.
+    pub window: WindowId,
+    /// The rendered frame. Screenshots of windows using a BGRA swap chain format are converted
+    /// to RGBA.
+    pub image: crate::texture::Image,
+}
+
+/// Clears the requests of the previous frame, which have been extracted to the render world.
+pub fn clear_screenshots(mut screenshots: ResMut<Screenshots>) {
+    screenshots.requests.clear();
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct ExtractedScreenshots {
+    requests: Vec<WindowId>,
+}
+
+pub(crate) fn extract_screenshots(mut commands: Commands, screenshots: Extract<Res<Screenshots>>) {
+    commands.insert_resource(ExtractedScreenshots {
+        requests: screenshots.requests.clone(),
+    });
+}
+
+/// The offscreen texture a window renders to while a screenshot is taken of it.
+///
+/// Swap chain textures usually can't be copied from, so the frame is rendered to this texture,
+/// which is then copied to a buffer and drawn to the swap chain texture.
+struct ScreenshotTarget {
+    texture: Texture,
+    view: TextureView,
+    swap_chain_texture: TextureView,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct ScreenshotTargets(HashMap<WindowId, ScreenshotTarget>);
+
+/// The pipelines drawing screenshot targets to the swap chain texture, for each swap chain
+/// format.
+#[derive(Resource, Default)]
+pub(crate) struct ScreenshotPipelines {
+    layout: Option<BindGroupLayout>,
+    pipelines: HashMap<TextureFormat, RenderPipeline>,
+}
+
+impl ScreenshotPipelines {
+    fn layout(&mut self, render_device: &RenderDevice) -> &BindGroupLayout {
+        self.layout.get_or_insert_with(|| {
+            render_device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("screenshot_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                }],
+            })
+        })
+    }
+
+    fn prepare(&mut self, render_device: &RenderDevice, format: TextureFormat) {
+        if self.pipelines.contains_key(&format) {
+            return;
+        }
+        let layout = self.layout(render_device).clone();
+        let shader = render_device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("screenshot_shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("screenshot.wgsl").into()),
+        });
+        let pipeline_layout =
+            render_device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("screenshot_pipeline_layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = render_device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("screenshot_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vertex",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fragment",
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        self.pipelines.insert(format, pipeline);
+    }
+}
+
+/// Makes the windows a screenshot was requested of render to a [`ScreenshotTarget`] this frame.
+pub(crate) fn prepare_screenshots(
+    extracted: Res<ExtractedScreenshots>,
+    mut windows: ResMut<ExtractedWindows>,
+    mut targets: ResMut<ScreenshotTargets>,
+    mut pipelines: ResMut<ScreenshotPipelines>,
+    render_device: Res<RenderDevice>,
+) {
+    targets.0.clear();
+    for window_id in &extracted.requests {
+        let Some(window) = windows.get_mut(window_id) else {
+            warn!(
+                "Can't take a screenshot of window {:?}, it doesn't exist",
+                window_id
+            );
+            continue;
+        };
+        let (Some(swap_chain_texture), Some(format)) = (
+            window.swap_chain_texture.take(),
+            window.swap_chain_texture_format,
+        ) else {
+            continue;
+        };
+
+        pipelines.prepare(&render_device, format);
+        let texture = render_device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screenshot_texture"),
+            size: wgpu::Extent3d {
+                width: window.physical_width,
+                height: window.physical_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        window.swap_chain_texture = Some(view.clone());
+        targets.0.insert(
+            *window_id,
+            ScreenshotTarget {
+                texture,
+                view,
+                swap_chain_texture,
+                format,
+                width: window.physical_width,
+                height: window.physical_height,
+            },
+        );
+    }
+}
+
+/// Copies the rendered screenshot targets to buffers and draws them to the swap chain textures of
+/// their windows, which they are given back before being presented.
+pub(crate) fn submit_screenshots(world: &mut World) {
+    let mut targets = std::mem::take(&mut world.resource_mut::<ScreenshotTargets>().0);
+    if targets.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world, mut pipelines: Mut<ScreenshotPipelines>| {
+        let render_device = world.resource::<RenderDevice>().clone();
+        let layout = pipelines.layout(&render_device).clone();
+        let mut command_encoder =
+            render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("screenshot_command_encoder"),
+            });
+        let mut readbacks = Vec::new();
+        for (window_id, target) in &targets {
+            let bind_group = render_device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("screenshot_bind_group"),
+                layout: &layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                }],
+            });
+            {
+                let mut pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("screenshot_pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &target.swap_chain_texture,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                pass.set_pipeline(&pipelines.pipelines[&target.format]);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.draw(0..3, 0..1);
+            }
+            readbacks.push(PendingReadback::copy_texture(
+                ReadbackSource::Screenshot(*window_id),
+                &render_device,
+                &mut command_encoder,
+                &target.texture,
+                target.width,
+                target.height,
+                target.format,
+            ));
+        }
+        world
+            .resource::<RenderQueue>()
+            .submit([command_encoder.finish()]);
+
+        let mut pending = world.resource_mut::<PendingReadbacks>();
+        for readback in readbacks {
+            readback.map(&render_device);
+            pending.0.push(readback);
+        }
+    });
+
+    let mut windows = world.resource_mut::<ExtractedWindows>();
+    for (window_id, target) in targets.drain() {
+        if let Some(window) = windows.get_mut(&window_id) {
+            window.swap_chain_texture = Some(target.swap_chain_texture);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::TextureAtlasSprite;
+use bevy_ecs::prelude::*;
+use bevy_reflect::{std_traits::ReflectDefault, FromReflect, Reflect};
+use bevy_time::Time;
+
+/// A flipbook animation, playing the textures `first..=last` of the
+/// [`TextureAtlas`](crate::TextureAtlas) of an entity by setting the index of its
+/// [`TextureAtlasSprite`] at a fixed frame rate.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component, Default)]
+pub struct SpriteAnimation {
+    /// The index in the atlas of the first frame of the animation
+    pub first: usize,
+    /// The index in the atlas of the last frame of the animation
+    pub last: usize,
+    /// The number of frames played per second
+    pub fps: f32,
+    /// What to play once the last frame was played
+    pub mode: SpriteAnimationMode,
+    /// Stops advancing the frames, without going back to the first one
+    pub paused: bool,
+    /// The current frame, from `0` for `first`
+    frame: usize,
+    /// The seconds elapsed since the current frame is shown
+    elapsed: f32,
+    /// Whether a [`SpriteAnimationMode::PingPong`] animation is playing backwards
+    reversed: bool,
+    finished: bool,
+}
+
+/// What a [`SpriteAnimation`] plays once its last frame was played.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum SpriteAnimationMode {
+    /// Stays on the last frame.
+    Once,
+    /// Plays again from the first frame.
+    #[default]
+    Loop,
+    /// Plays backwards to the first frame, then forwards again.
+    PingPong,
+}
+
+/// Sent when a [`SpriteAnimation`] completes: once when a [`SpriteAnimationMode::Once`]
+/// animation finishes, and at the end of each cycle of the animations looping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteAnimationCompleted {
+    /// The entity of the animation
+    pub entity: Entity,
+}
+
+impl Default for SpriteAnimation {
+    fn default() -> Self {
+        Self::new(0, 0, 10.0)
+    }
+}
+
+impl SpriteAnimation {
+    /// Creates an animation looping over the textures `first..=last` of the atlas, at `fps`
+    /// frames per second.
+    pub fn new(first: usize, last: usize, fps: f32) -> Self {
+        Self {
+            first,
+            last,
+            fps,
+            mode: SpriteAnimationMode::Loop,
+            paused: false,
+            frame: 0,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    /// Sets what the animation plays once its last frame was played.
+    pub fn with_mode(mut self, mode: SpriteAnimationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the index in the atlas of the current frame.
+    pub fn index(&self) -> usize {
+        self.first + self.frame
+    }
+
+    /// Returns `true` once a [`SpriteAnimationMode::Once`] animation played its last frame.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Plays the animation again from its first frame.
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.reversed = false;
+        self.finished = false;
+    }
+
+    /// Advances the animation by `seconds`, returning the number of times it completed.
+    pub fn tick(&mut self, seconds: f32) -> usize {
+        if self.paused || self.finished || self.fps <= 0.0 {
+            return 0;
+        }
+        let frame_duration = 1.0 / self.fps;
+        let mut completed = 0;
+        self.elapsed += seconds;
+        while self.elapsed >= frame_duration && !self.finished {
+            self.elapsed -= frame_duration;
+            if self.advance() {
+                completed += 1;
+            }
+        }
+        completed
+    }
+
+    /// Moves to the next frame, returning `true` if the animation completed.
+    fn advance(&mut self) -> bool {
+        let last_frame = self.last.saturating_sub(self.first);
+        match self.mode {
+            SpriteAnimationMode::Once if self.frame < last_frame => self.frame += 1,
+            SpriteAnimationMode::Once => {
+                self.finished = true;
+                return true;
+            }
+            SpriteAnimationMode::Loop if self.frame < last_frame => self.frame += 1,
+            SpriteAnimationMode::Loop => {
+                self.frame = 0;
+                return true;
+            }
+            SpriteAnimationMode::PingPong if last_frame == 0 => return true,
+            SpriteAnimationMode::PingPong if self.reversed => {
+                self.frame -= 1;
+                if self.frame == 0 {
+                    self.reversed = false;
+                    return true;
+                }
+            }
+            SpriteAnimationMode::PingPong => {
+                self.frame += 1;
+                self.reversed = self.frame == last_frame;
+            }
+        }
+        false
+    }
+}
+
+/// System advancing the [`SpriteAnimation`]s and setting the index of their
+/// [`TextureAtlasSprite`].
+pub fn animate_sprites(
+    time: Res<Time>,
+    mut completed_events: EventWriter<SpriteAnimationCompleted>,
+    mut animations: Query<(Entity, &mut SpriteAnimation, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut animation, mut sprite) in &mut animations {
+        for _ in 0..animation.tick(time.delta_seconds()) {
+            completed_events.send(SpriteAnimationCompleted { entity });
+        }
+        let index = animation.index();
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(animation: &mut SpriteAnimation, frames: usize) -> (Vec<usize>, usize) {
+        let mut completed = 0;
+        let indices = (0..frames)
+            .map(|_| {
+                completed += animation.tick(1.0 / animation.fps);
+                animation.index()
+            })
+            .collect();
+        (indices, completed)
+    }
+
+    #[test]
+    fn modes() {
+        let mut animation = SpriteAnimation::new(2, 4, 10.0);
+        assert_eq!(play(&mut animation, 4), (vec![3, 4, 2, 3], 1));
+
+        let mut animation = SpriteAnimation::new(2, 4, 10.0).with_mode(SpriteAnimationMode::Once);
+        assert_eq!(play(&mut animation, 4), (vec![3, 4, 4, 4], 1));
+        assert!(animation.is_finished());
+        animation.restart();
+        assert_eq!(animation.index(), 2);
+
+        let mut animation =
+            SpriteAnimation::new(2, 4, 10.0).with_mode(SpriteAnimationMode::PingPong);
+        assert_eq!(play(&mut animation, 6), (vec![3, 4, 3, 2, 3, 4], 1));
+    }
+
+    #[test]
+    fn several_frames_per_tick() {
+        let mut animation = SpriteAnimation::new(0, 3, 10.0);
+        assert_eq!(animation.tick(0.55), 1);
+        assert_eq!(animation.index(), 1);
+        animation.paused = true;
+        assert_eq!(animation.tick(1.0), 0);
+        assert_eq!(animation.index(), 1);
+    }
+}
@@ -1,30 +1,44 @@
+mod animation;
 mod bundle;
 mod dynamic_texture_atlas_builder;
+mod light2d;
 mod mesh2d;
 mod render;
 mod sprite;
 mod texture_atlas;
 mod texture_atlas_builder;
+mod texture_slice;
+mod tilemap;
 
 pub mod collide_aabb;
 
 pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
+        animation::{SpriteAnimation, SpriteAnimationMode},
         bundle::{SpriteBundle, SpriteSheetBundle},
+        light2d::{
+            AmbientLight2d, LightOccluder2d, PointLight2d, PointLight2dBundle, SpriteNormalMap,
+        },
         sprite::Sprite,
         texture_atlas::{TextureAtlas, TextureAtlasSprite},
+        texture_slice::{BorderRect, ImageScaleMode, SliceScaleMode, TextureSlicer},
+        tilemap::{TileMap, TileMapBundle},
         ColorMaterial, ColorMesh2dBundle, TextureAtlasBuilder,
     };
 }
 
+pub use animation::*;
 pub use bundle::*;
 pub use dynamic_texture_atlas_builder::*;
+pub use light2d::*;
 pub use mesh2d::*;
 pub use render::*;
 pub use sprite::*;
 pub use texture_atlas::*;
 pub use texture_atlas_builder::*;
+pub use texture_slice::*;
+pub use tilemap::*;
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Assets, HandleUntyped};
@@ -46,6 +60,10 @@ pub const SPRITE_SHADER_HANDLE: HandleUntyped =
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum SpriteSystem {
     ExtractSprites,
+    /// Label for the [`animate_sprites`] system.
+    AnimateSprites,
+    /// Label for the [`update_tile_map_chunks`] system.
+    UpdateTileMapChunks,
 }
 
 impl Plugin for SpritePlugin {
@@ -57,7 +75,19 @@ impl Plugin for SpritePlugin {
             .register_asset_reflect::<TextureAtlas>()
             .register_type::<Sprite>()
             .register_type::<Anchor>()
+            .register_type::<ImageScaleMode>()
             .register_type::<Mesh2dHandle>()
+            .register_type::<SpriteAnimation>()
+            .register_type::<SpriteAnimationMode>()
+            .add_event::<SpriteAnimationCompleted>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                animate_sprites.label(SpriteSystem::AnimateSprites),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_tile_map_chunks.label(SpriteSystem::UpdateTileMapChunks),
+            )
             .add_plugin(Mesh2dRenderPlugin)
             .add_plugin(ColorMaterialPlugin);
 
@@ -77,5 +107,8 @@ impl Plugin for SpritePlugin {
                 .add_system_to_stage(RenderStage::Extract, render::extract_sprite_events)
                 .add_system_to_stage(RenderStage::Queue, queue_sprites);
         };
+
+        // The lighting uses the layouts of the `SpritePipeline`
+        app.add_plugin(Light2dPlugin);
     }
 }
@@ -0,0 +1,129 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct View {
+    view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+    // viewport(x_origin, y_origin, width, height)
+    viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
+};
+
+struct PointLight2d {
+    // position (x, y), height and radius
+    position_height_radius: vec4<f32>,
+    // color multiplied by the intensity, and 1.0 in w if the light casts shadows
+    color_shadows: vec4<f32>,
+};
+
+struct Lights2d {
+    ambient: vec4<f32>,
+    point_lights: array<PointLight2d, 32u>,
+    // start (x, y) and end (x, y) of the edges of the occluders
+    edges: array<vec4<f32>, 256u>,
+    // the range of edges of two occluders per element
+    occluders: array<vec4<u32>, 32u>,
+    point_light_count: u32,
+    occluder_count: u32,
+};
+
+@group(0) @binding(0)
+var main_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var normal_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var<uniform> view: View;
+@group(0) @binding(3)
+var<uniform> lights: Lights2d;
+
+fn cross_2d(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+// Whether the segments from a to b and from c to d intersect
+fn segments_intersect(a: vec2<f32>, b: vec2<f32>, c: vec2<f32>, d: vec2<f32>) -> bool {
+    let ab = b - a;
+    let cd = d - c;
+    let denominator = cross_2d(ab, cd);
+    if (abs(denominator) < 1e-6) {
+        return false;
+    }
+    let ac = c - a;
+    let t = cross_2d(ac, cd) / denominator;
+    let u = cross_2d(ac, ab) / denominator;
+    return t >= 0.0 && t <= 1.0 && u >= 0.0 && u <= 1.0;
+}
+
+// Whether going from the position to the light goes through an occluder, entering and leaving it.
+// Crossing a single edge of an occluder means the position or the light is inside of it, which
+// doesn't cast a shadow.
+fn is_in_shadow(position: vec2<f32>, light_position: vec2<f32>) -> bool {
+    for (var i = 0u; i < lights.occluder_count; i = i + 1u) {
+        let ranges = lights.occluders[i / 2u];
+        var range = ranges.xy;
+        if (i % 2u == 1u) {
+            range = ranges.zw;
+        }
+        var crossings = 0u;
+        for (var edge = range.x; edge < range.y; edge = edge + 1u) {
+            let segment = lights.edges[edge];
+            if (segments_intersect(position, light_position, segment.xy, segment.zw)) {
+                crossings = crossings + 1u;
+            }
+        }
+        if (crossings >= 2u) {
+            return true;
+        }
+    }
+    return false;
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let color = textureLoad(main_texture, pixel, 0);
+
+    // Only light the viewport of the view
+    let viewport_uv = (in.position.xy - view.viewport.xy) / view.viewport.zw;
+    if (any(viewport_uv < vec2<f32>(0.0)) || any(viewport_uv > vec2<f32>(1.0))) {
+        return color;
+    }
+    let ndc = vec2<f32>(viewport_uv.x * 2.0 - 1.0, 1.0 - viewport_uv.y * 2.0);
+    let world_position = view.inverse_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+    let position = world_position.xy / world_position.w;
+
+    // The alpha of the normal texture is 0.0 where there is no normal map
+    let encoded_normal = textureLoad(normal_texture, pixel, 0);
+    let normal = encoded_normal.xyz * 2.0 - 1.0;
+
+    var light = lights.ambient.rgb;
+    for (var i = 0u; i < lights.point_light_count; i = i + 1u) {
+        let point_light = lights.point_lights[i];
+        let light_position = point_light.position_height_radius.xy;
+        let radius = point_light.position_height_radius.w;
+        let to_light = light_position - position;
+        let distance = length(to_light);
+        if (distance >= radius) {
+            continue;
+        }
+        var attenuation = 1.0 - distance / radius;
+        attenuation = attenuation * attenuation;
+        if (encoded_normal.a > 0.0) {
+            let height = max(point_light.position_height_radius.z, 1e-4);
+            let light_direction = normalize(vec3<f32>(to_light, height));
+            attenuation = attenuation * max(dot(normal, light_direction), 0.0);
+        }
+        if (point_light.color_shadows.w > 0.0 && is_in_shadow(position, light_position)) {
+            continue;
+        }
+        light = light + point_light.color_shadows.rgb * attenuation;
+    }
+
+    return vec4<f32>(color.rgb * light, color.a);
+}
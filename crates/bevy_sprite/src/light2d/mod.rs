@@ -0,0 +1,204 @@
+mod node;
+mod render;
+
+pub use node::Light2dNode;
+pub use render::*;
+
+use bevy_app::prelude::*;
+use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_core_pipeline::core_2d;
+use bevy_ecs::{prelude::*, schedule::IntoSystemDescriptor};
+use bevy_math::Vec2;
+use bevy_reflect::{std_traits::ReflectDefault, Reflect, TypeUuid};
+use bevy_render::{
+    color::Color,
+    extract_component::ExtractComponentPlugin,
+    prelude::{ComputedVisibility, Image, Visibility},
+    render_graph::RenderGraph,
+    render_resource::{Shader, SpecializedRenderPipelines},
+    RenderApp, RenderStage,
+};
+use bevy_transform::components::{GlobalTransform, Transform};
+
+pub const LIGHT_2D_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7533127303165751136);
+pub const SPRITE_NORMAL_MAP_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1404244826697500226);
+
+/// Lights the 2d cameras with an [`AmbientLight2d`] with the [`PointLight2d`]s, taking the
+/// [`SpriteNormalMap`]s and the shadows of the [`LightOccluder2d`]s into account.
+///
+/// The lighting is composited over the main pass of the camera, before bloom and tonemapping.
+/// All the pixels rendered by the camera are lit, so the lit color of a pixel is its color
+/// multiplied by the ambient light plus the light of the point lights reaching it.
+pub struct Light2dPlugin;
+
+impl Plugin for Light2dPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            LIGHT_2D_SHADER_HANDLE,
+            "light2d.wgsl",
+            Shader::from_wgsl
+        );
+        load_internal_asset!(
+            app,
+            SPRITE_NORMAL_MAP_SHADER_HANDLE,
+            "sprite_normal_map.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.register_type::<PointLight2d>()
+            .register_type::<AmbientLight2d>()
+            .register_type::<LightOccluder2d>()
+            .register_type::<SpriteNormalMap>()
+            .add_plugin(ExtractComponentPlugin::<AmbientLight2d>::default());
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .init_resource::<ExtractedLights2d>()
+            .init_resource::<ExtractedSpriteNormalMaps>()
+            .init_resource::<Light2dMeta>()
+            .init_resource::<Light2dPipeline>()
+            .init_resource::<SpecializedRenderPipelines<Light2dPipeline>>()
+            .init_resource::<SpriteNormalMapPipeline>()
+            .add_system_to_stage(RenderStage::Extract, extract_lights_2d)
+            .add_system_to_stage(RenderStage::Extract, extract_sprite_normal_maps)
+            .add_system_to_stage(RenderStage::Prepare, prepare_lights_2d)
+            .add_system_to_stage(
+                RenderStage::Queue,
+                queue_sprite_normal_maps.after(crate::queue_sprites),
+            );
+
+        let light_2d_node = Light2dNode::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        let draw_2d_graph = graph.get_sub_graph_mut(core_2d::graph::NAME).unwrap();
+        draw_2d_graph.add_node(core_2d::graph::node::LIGHT_2D, light_2d_node);
+        draw_2d_graph.add_slot_edge(
+            draw_2d_graph.input_node().id,
+            core_2d::graph::input::VIEW_ENTITY,
+            core_2d::graph::node::LIGHT_2D,
+            Light2dNode::IN_VIEW,
+        );
+        // MAIN_PASS -> LIGHT_2D -> BLOOM
+        draw_2d_graph.add_node_edge(
+            core_2d::graph::node::MAIN_PASS,
+            core_2d::graph::node::LIGHT_2D,
+        );
+        draw_2d_graph.add_node_edge(core_2d::graph::node::LIGHT_2D, core_2d::graph::node::BLOOM);
+    }
+}
+
+/// Enables 2d lighting for the [`Camera2d`](bevy_core_pipeline::core_2d::Camera2d) it is added
+/// to, lighting everything the camera renders with this light and the [`PointLight2d`]s.
+///
+/// Without any point light, the colors rendered by the camera are multiplied by the ambient
+/// light, so a `brightness` of `1.0` with a white light leaves them unchanged.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component, Default)]
+pub struct AmbientLight2d {
+    pub color: Color,
+    /// Multiplies the color of the light
+    pub brightness: f32,
+}
+
+impl Default for AmbientLight2d {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            brightness: 0.1,
+        }
+    }
+}
+
+/// A light emitted from a point in every direction of the 2d plane, fading out until its
+/// [`radius`](Self::radius).
+///
+/// The point lights only light the cameras with an [`AmbientLight2d`].
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component, Default)]
+pub struct PointLight2d {
+    pub color: Color,
+    /// Multiplies the color of the light
+    pub intensity: f32,
+    /// The distance at which the light fades out, in world units
+    pub radius: f32,
+    /// The height of the light above the plane of the sprites, in world units, setting the angle
+    /// the sprites with a [`SpriteNormalMap`] are lit from
+    pub height: f32,
+    /// Whether the [`LightOccluder2d`]s cast shadows from this light
+    pub shadows_enabled: bool,
+}
+
+impl Default for PointLight2d {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            intensity: 1.0,
+            radius: 200.0,
+            height: 50.0,
+            shadows_enabled: false,
+        }
+    }
+}
+
+/// A bundle for a [`PointLight2d`].
+#[derive(Bundle, Clone, Debug, Default)]
+pub struct PointLight2dBundle {
+    pub point_light: PointLight2d,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// Enables or disables the light
+    pub visibility: Visibility,
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// A closed polygon casting shadows from the [`PointLight2d`]s with shadows enabled.
+///
+/// The points are in the local space of the entity, which also needs a [`GlobalTransform`]. A
+/// pixel is in the shadow of an occluder when going from the pixel to the light goes through the
+/// occluder, so the pixels inside of an occluder are still lit, like the sprite of a wall.
+///
+/// At most [`MAX_LIGHT_OCCLUDERS_2D`] occluders with [`MAX_LIGHT_OCCLUDER_2D_EDGES`] edges in all
+/// cast shadows.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct LightOccluder2d {
+    /// The corners of the polygon, with an edge between each point and the next one, and between
+    /// the last point and the first one
+    pub points: Vec<Vec2>,
+}
+
+impl LightOccluder2d {
+    /// Creates an occluder shaped like a rectangle of the given `size`, centered on the entity.
+    pub fn rectangle(size: Vec2) -> Self {
+        let half_size = size / 2.0;
+        Self {
+            points: vec![
+                Vec2::new(-half_size.x, -half_size.y),
+                Vec2::new(half_size.x, -half_size.y),
+                Vec2::new(half_size.x, half_size.y),
+                Vec2::new(-half_size.x, half_size.y),
+            ],
+        }
+    }
+}
+
+/// A normal map for the [`Sprite`](crate::Sprite) or [`TextureAtlasSprite`](crate::TextureAtlasSprite)
+/// of the entity, sampled with the same UVs as its texture, which the [`PointLight2d`]s light
+/// depending on the angle they reach each pixel from.
+///
+/// The red and green channels of the normal map point to the right and to the top of the
+/// texture, and the blue channel out of the screen. The image must be in a linear format like
+/// [`TextureFormat::Rgba8Unorm`](bevy_render::render_resource::TextureFormat::Rgba8Unorm), and
+/// its pixels with an alpha below `0.5` are lit like the sprites without a normal map, from
+/// every angle.
+///
+/// The normal maps are drawn in the order of the sprites, so a sprite without a normal map drawn
+/// over a sprite with one is lit with the normals of the sprite below it.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct SpriteNormalMap(pub Handle<Image>);
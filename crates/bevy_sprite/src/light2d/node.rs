@@ -0,0 +1,172 @@
+use super::{
+    Light2dMeta, Light2dPipeline, SpriteNormalMapPipeline, ViewLight2dPipeline,
+    ViewLight2dUniformOffset, ViewSpriteNormalTexture,
+};
+use crate::ImageBindGroups;
+use bevy_asset::Handle;
+use bevy_ecs::prelude::*;
+use bevy_ecs::query::QueryState;
+use bevy_render::{
+    color::Color,
+    render_graph::{Node, NodeRunError, RenderGraphContext, SlotInfo, SlotType},
+    render_resource::{
+        BindGroupDescriptor, BindGroupEntry, BindingResource, LoadOp, Operations, PipelineCache,
+        RenderPassColorAttachment, RenderPassDescriptor,
+    },
+    renderer::RenderContext,
+    view::{ExtractedView, ViewTarget, ViewUniformOffset, ViewUniforms},
+};
+
+/// Draws the sprite normal maps of a view, then composites the 2d lighting over its main texture.
+pub struct Light2dNode {
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            &'static ViewUniformOffset,
+            &'static ViewLight2dUniformOffset,
+            &'static ViewLight2dPipeline,
+            &'static ViewSpriteNormalTexture,
+        ),
+        With<ExtractedView>,
+    >,
+}
+
+impl Light2dNode {
+    pub const IN_VIEW: &'static str = "view";
+
+    pub fn new(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for Light2dNode {
+    fn input(&self) -> Vec<SlotInfo> {
+        vec![SlotInfo::new(Light2dNode::IN_VIEW, SlotType::Entity)]
+    }
+
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph.get_input_entity(Self::IN_VIEW)?;
+        let Ok((target, view_uniform_offset, lights_uniform_offset, pipeline, normal_texture)) =
+            self.query.get_manual(world, view_entity)
+        else {
+            return Ok(());
+        };
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let light_2d_meta = world.resource::<Light2dMeta>();
+        let (
+            Some(normal_map_pipeline),
+            Some(light_2d_pipeline),
+            Some(view_binding),
+            Some(lights_binding),
+        ) = (
+            pipeline_cache
+                .get_render_pipeline(world.resource::<SpriteNormalMapPipeline>().pipeline_id),
+            pipeline_cache.get_render_pipeline(pipeline.pipeline_id),
+            world.resource::<ViewUniforms>().uniforms.binding(),
+            light_2d_meta.lights.binding(),
+        )
+        else {
+            return Ok(());
+        };
+
+        {
+            let pass_descriptor = RenderPassDescriptor {
+                label: Some("sprite_normal_map_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &normal_texture.texture.default_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::NONE.into()),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            };
+            let mut render_pass = render_context
+                .command_encoder
+                .begin_render_pass(&pass_descriptor);
+
+            if let (Some(vertex_buffer), Some(view_bind_group)) = (
+                light_2d_meta.vertex_buffer(),
+                light_2d_meta.view_bind_group.as_ref(),
+            ) {
+                let image_bind_groups = world.resource::<ImageBindGroups>();
+                render_pass.set_pipeline(normal_map_pipeline);
+                render_pass.set_bind_group(0, view_bind_group, &[view_uniform_offset.offset]);
+                render_pass.set_vertex_buffer(0, *vertex_buffer.slice(..));
+                for batch in &light_2d_meta.batches {
+                    if let Some(bind_group) = image_bind_groups
+                        .values
+                        .get(&Handle::weak(batch.normal_map))
+                    {
+                        render_pass.set_bind_group(1, bind_group, &[]);
+                        render_pass.draw(batch.vertices.clone(), 0..1);
+                    }
+                }
+            }
+        }
+
+        let post_process = target.post_process_write();
+        let bind_group = render_context
+            .render_device
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("light_2d_bind_group"),
+                layout: &world.resource::<Light2dPipeline>().layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(post_process.source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(
+                            &normal_texture.texture.default_view,
+                        ),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: view_binding,
+                    },
+                    BindGroupEntry {
+                        binding: 3,
+                        resource: lights_binding,
+                    },
+                ],
+            });
+
+        let pass_descriptor = RenderPassDescriptor {
+            label: Some("light_2d_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        };
+        let mut render_pass = render_context
+            .command_encoder
+            .begin_render_pass(&pass_descriptor);
+
+        render_pass.set_pipeline(light_2d_pipeline);
+        render_pass.set_bind_group(
+            0,
+            &bind_group,
+            &[view_uniform_offset.offset, lights_uniform_offset.offset],
+        );
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
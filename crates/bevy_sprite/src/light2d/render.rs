@@ -0,0 +1,551 @@
+use std::ops::Range;
+
+use super::{
+    AmbientLight2d, LightOccluder2d, PointLight2d, SpriteNormalMap, LIGHT_2D_SHADER_HANDLE,
+    SPRITE_NORMAL_MAP_SHADER_HANDLE,
+};
+use crate::{ExtractedSprites, ImageBindGroups, SpritePipeline, QUAD_INDICES};
+use bevy_asset::{Handle, HandleId};
+use bevy_core_pipeline::{
+    core_2d::Camera2d, fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+};
+use bevy_ecs::{prelude::*, query::QueryItem};
+use bevy_log::warn;
+use bevy_math::{UVec2, UVec4, Vec2, Vec4};
+use bevy_render::{
+    camera::ExtractedCamera,
+    extract_component::ExtractComponent,
+    prelude::{ComputedVisibility, Image},
+    render_asset::RenderAssets,
+    render_resource::*,
+    renderer::{RenderDevice, RenderQueue},
+    texture::{BevyDefault, CachedTexture, TextureCache},
+    view::{ExtractedView, ViewTarget, ViewUniform, ViewUniforms},
+    Extract,
+};
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashMap;
+use bytemuck::{Pod, Zeroable};
+
+/// The maximum number of [`PointLight2d`]s lighting the views.
+pub const MAX_POINT_LIGHTS_2D: usize = 32;
+/// The maximum number of [`LightOccluder2d`]s casting shadows.
+pub const MAX_LIGHT_OCCLUDERS_2D: usize = 64;
+/// The maximum number of edges of all the [`LightOccluder2d`]s casting shadows.
+pub const MAX_LIGHT_OCCLUDER_2D_EDGES: usize = 256;
+
+impl ExtractComponent for AmbientLight2d {
+    type Query = &'static Self;
+    type Filter = With<Camera2d>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<Self::Query>) -> Option<Self> {
+        Some(*item)
+    }
+}
+
+#[derive(Clone, Copy, Default, ShaderType)]
+pub struct GpuPointLight2d {
+    // position (x, y), height and radius
+    position_height_radius: Vec4,
+    // linear color multiplied by the intensity, and 1.0 in w if the light casts shadows
+    color_shadows: Vec4,
+}
+
+#[derive(Clone, ShaderType)]
+pub struct GpuLights2d {
+    ambient: Vec4,
+    point_lights: [GpuPointLight2d; MAX_POINT_LIGHTS_2D],
+    // start (x, y) and end (x, y) of the edges of the occluders
+    edges: [Vec4; MAX_LIGHT_OCCLUDER_2D_EDGES],
+    // the range of edges of two occluders per element
+    occluders: [UVec4; MAX_LIGHT_OCCLUDERS_2D / 2],
+    point_light_count: u32,
+    occluder_count: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct ExtractedLights2d {
+    pub point_lights: Vec<GpuPointLight2d>,
+    pub edges: Vec<Vec4>,
+    /// The range in `edges` of each occluder
+    pub occluders: Vec<UVec2>,
+}
+
+pub fn extract_lights_2d(
+    mut extracted_lights: ResMut<ExtractedLights2d>,
+    mut warned_truncation: Local<bool>,
+    point_lights: Extract<Query<(&PointLight2d, &GlobalTransform, Option<&ComputedVisibility>)>>,
+    occluders: Extract<
+        Query<(
+            &LightOccluder2d,
+            &GlobalTransform,
+            Option<&ComputedVisibility>,
+        )>,
+    >,
+) {
+    let is_visible = |visibility: Option<&ComputedVisibility>| {
+        visibility.map_or(true, ComputedVisibility::is_visible_in_hierarchy)
+    };
+    let ExtractedLights2d {
+        point_lights: extracted_point_lights,
+        edges,
+        occluders: extracted_occluders,
+    } = &mut *extracted_lights;
+    extracted_point_lights.clear();
+    edges.clear();
+    extracted_occluders.clear();
+    let mut truncated = false;
+
+    for (point_light, transform, visibility) in &point_lights {
+        if !is_visible(visibility) {
+            continue;
+        }
+        if extracted_point_lights.len() == MAX_POINT_LIGHTS_2D {
+            truncated = true;
+            break;
+        }
+        let color = Vec4::from_slice(&point_light.color.as_linear_rgba_f32()).truncate()
+            * point_light.intensity;
+        extracted_point_lights.push(GpuPointLight2d {
+            position_height_radius: transform
+                .translation()
+                .truncate()
+                .extend(point_light.height)
+                .extend(point_light.radius),
+            color_shadows: color.extend(if point_light.shadows_enabled {
+                1.0
+            } else {
+                0.0
+            }),
+        });
+    }
+
+    for (occluder, transform, visibility) in &occluders {
+        if !is_visible(visibility) || occluder.points.len() < 2 {
+            continue;
+        }
+        if extracted_occluders.len() == MAX_LIGHT_OCCLUDERS_2D
+            || edges.len() + occluder.points.len() > MAX_LIGHT_OCCLUDER_2D_EDGES
+        {
+            truncated = true;
+            break;
+        }
+        let points: Vec<Vec2> = occluder
+            .points
+            .iter()
+            .map(|point| transform.transform_point(point.extend(0.0)).truncate())
+            .collect();
+        let start = edges.len() as u32;
+        for (i, &point) in points.iter().enumerate() {
+            let next = points[(i + 1) % points.len()];
+            edges.push(Vec4::new(point.x, point.y, next.x, next.y));
+        }
+        extracted_occluders.push(UVec2::new(start, edges.len() as u32));
+    }
+
+    if truncated && !*warned_truncation {
+        warn!(
+            "Too many 2d point lights or light occluders, only {MAX_POINT_LIGHTS_2D} point lights \
+            and {MAX_LIGHT_OCCLUDERS_2D} occluders with {MAX_LIGHT_OCCLUDER_2D_EDGES} edges are used"
+        );
+        *warned_truncation = true;
+    }
+}
+
+/// The normal maps of the sprites extracted in [`ExtractedSprites`].
+#[derive(Resource, Default)]
+pub struct ExtractedSpriteNormalMaps {
+    pub normal_maps: HashMap<Entity, HandleId>,
+}
+
+pub fn extract_sprite_normal_maps(
+    mut extracted_normal_maps: ResMut<ExtractedSpriteNormalMaps>,
+    normal_maps: Extract<Query<(Entity, &ComputedVisibility, &SpriteNormalMap)>>,
+) {
+    extracted_normal_maps.normal_maps.clear();
+    for (entity, visibility, normal_map) in &normal_maps {
+        if visibility.is_visible() {
+            extracted_normal_maps
+                .normal_maps
+                .insert(entity, normal_map.0.id());
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct NormalMappedSpriteVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    // the world directions of the x and y axes of the texture
+    pub axes: [f32; 4],
+}
+
+/// Sprites drawn in a row with the same normal map.
+pub struct NormalMapBatch {
+    pub vertices: Range<u32>,
+    pub normal_map: HandleId,
+}
+
+#[derive(Resource)]
+pub struct Light2dMeta {
+    pub lights: DynamicUniformBuffer<GpuLights2d>,
+    vertices: BufferVec<NormalMappedSpriteVertex>,
+    pub batches: Vec<NormalMapBatch>,
+    pub view_bind_group: Option<BindGroup>,
+}
+
+impl FromWorld for Light2dMeta {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            lights: DynamicUniformBuffer::new(world.resource::<RenderDevice>()),
+            vertices: BufferVec::new(BufferUsages::VERTEX),
+            batches: Vec::new(),
+            view_bind_group: None,
+        }
+    }
+}
+
+impl Light2dMeta {
+    pub fn vertex_buffer(&self) -> Option<&Buffer> {
+        self.vertices.buffer()
+    }
+}
+
+/// The pipeline drawing the [`SpriteNormalMap`]s into the [`ViewSpriteNormalTexture`].
+#[derive(Resource)]
+pub struct SpriteNormalMapPipeline {
+    pub pipeline_id: CachedRenderPipelineId,
+}
+
+impl SpriteNormalMapPipeline {
+    pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+}
+
+impl FromWorld for SpriteNormalMapPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let sprite_pipeline = world.resource::<SpritePipeline>();
+        let layout = vec![
+            sprite_pipeline.view_layout.clone(),
+            sprite_pipeline.material_layout.clone(),
+        ];
+        let vertex_layout = VertexBufferLayout::from_vertex_formats(
+            VertexStepMode::Vertex,
+            vec![
+                // position
+                VertexFormat::Float32x3,
+                // uv
+                VertexFormat::Float32x2,
+                // axes
+                VertexFormat::Float32x4,
+            ],
+        );
+        let pipeline_id =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("sprite_normal_map_pipeline".into()),
+                    layout: Some(layout),
+                    push_constant_ranges: Vec::new(),
+                    vertex: VertexState {
+                        shader: SPRITE_NORMAL_MAP_SHADER_HANDLE.typed(),
+                        shader_defs: Vec::new(),
+                        entry_point: "vertex".into(),
+                        buffers: vec![vertex_layout],
+                    },
+                    fragment: Some(FragmentState {
+                        shader: SPRITE_NORMAL_MAP_SHADER_HANDLE.typed(),
+                        shader_defs: Vec::new(),
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: Self::TEXTURE_FORMAT,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                });
+        SpriteNormalMapPipeline { pipeline_id }
+    }
+}
+
+/// The pipeline compositing the lighting over the main texture of the views.
+#[derive(Resource)]
+pub struct Light2dPipeline {
+    pub layout: BindGroupLayout,
+}
+
+impl FromWorld for Light2dPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let uniform_entry = |binding, min_binding_size| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: Some(min_binding_size),
+            },
+            count: None,
+        };
+        let layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("light_2d_bind_group_layout"),
+                    entries: &[
+                        // main texture
+                        texture_entry(0),
+                        // normal texture
+                        texture_entry(1),
+                        uniform_entry(2, ViewUniform::min_size()),
+                        uniform_entry(3, GpuLights2d::min_size()),
+                    ],
+                });
+
+        Light2dPipeline { layout }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Light2dPipelineKey {
+    texture_format: TextureFormat,
+}
+
+impl SpecializedRenderPipeline for Light2dPipeline {
+    type Key = Light2dPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        RenderPipelineDescriptor {
+            label: Some("light_2d_pipeline".into()),
+            layout: Some(vec![self.layout.clone()]),
+            push_constant_ranges: Vec::new(),
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: LIGHT_2D_SHADER_HANDLE.typed(),
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: key.texture_format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ViewLight2dUniformOffset {
+    pub offset: u32,
+}
+
+#[derive(Component)]
+pub struct ViewLight2dPipeline {
+    pub pipeline_id: CachedRenderPipelineId,
+}
+
+/// The texture the [`SpriteNormalMap`]s of a view are drawn into, with the normals in world space
+/// encoded in its color and an alpha of `1.0` where there is a normal map.
+#[derive(Component)]
+pub struct ViewSpriteNormalTexture {
+    pub texture: CachedTexture,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_lights_2d(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    texture_cache: Res<TextureCache>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<Light2dPipeline>>,
+    light_2d_pipeline: Res<Light2dPipeline>,
+    mut light_2d_meta: ResMut<Light2dMeta>,
+    extracted_lights: Res<ExtractedLights2d>,
+    views: Query<(Entity, &ExtractedView, &ExtractedCamera, &AmbientLight2d)>,
+) {
+    light_2d_meta.lights.clear();
+
+    let mut lights = GpuLights2d {
+        ambient: Vec4::ZERO,
+        point_lights: [GpuPointLight2d::default(); MAX_POINT_LIGHTS_2D],
+        edges: [Vec4::ZERO; MAX_LIGHT_OCCLUDER_2D_EDGES],
+        occluders: [UVec4::ZERO; MAX_LIGHT_OCCLUDERS_2D / 2],
+        point_light_count: extracted_lights.point_lights.len() as u32,
+        occluder_count: extracted_lights.occluders.len() as u32,
+    };
+    lights.point_lights[..extracted_lights.point_lights.len()]
+        .copy_from_slice(&extracted_lights.point_lights);
+    lights.edges[..extracted_lights.edges.len()].copy_from_slice(&extracted_lights.edges);
+    for (i, range) in extracted_lights.occluders.iter().enumerate() {
+        let occluders = &mut lights.occluders[i / 2];
+        if i % 2 == 0 {
+            occluders.x = range.x;
+            occluders.y = range.y;
+        } else {
+            occluders.z = range.x;
+            occluders.w = range.y;
+        }
+    }
+
+    for (entity, view, camera, ambient_light) in &views {
+        let Some(physical_target_size) = camera.physical_target_size else {
+            continue;
+        };
+        lights.ambient =
+            Vec4::from_slice(&ambient_light.color.as_linear_rgba_f32()) * ambient_light.brightness;
+
+        let normal_texture = texture_cache.get(
+            &render_device,
+            TextureDescriptor {
+                label: Some("view_sprite_normal_texture"),
+                size: Extent3d {
+                    width: physical_target_size.x,
+                    height: physical_target_size.y,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: SpriteNormalMapPipeline::TEXTURE_FORMAT,
+                usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            },
+        );
+        let pipeline_id = pipelines.specialize(
+            &mut pipeline_cache,
+            &light_2d_pipeline,
+            Light2dPipelineKey {
+                texture_format: if view.hdr {
+                    ViewTarget::TEXTURE_FORMAT_HDR
+                } else {
+                    TextureFormat::bevy_default()
+                },
+            },
+        );
+
+        commands.entity(entity).insert((
+            ViewLight2dUniformOffset {
+                offset: light_2d_meta.lights.push(lights.clone()),
+            },
+            ViewLight2dPipeline { pipeline_id },
+            ViewSpriteNormalTexture {
+                texture: normal_texture,
+            },
+        ));
+    }
+
+    light_2d_meta
+        .lights
+        .write_buffer(&render_device, &render_queue);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn queue_sprite_normal_maps(
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut light_2d_meta: ResMut<Light2dMeta>,
+    view_uniforms: Res<ViewUniforms>,
+    sprite_pipeline: Res<SpritePipeline>,
+    mut image_bind_groups: ResMut<ImageBindGroups>,
+    gpu_images: Res<RenderAssets<Image>>,
+    extracted_sprites: Res<ExtractedSprites>,
+    extracted_normal_maps: Res<ExtractedSpriteNormalMaps>,
+) {
+    let light_2d_meta = &mut *light_2d_meta;
+    light_2d_meta.vertices.clear();
+    light_2d_meta.batches.clear();
+
+    let Some(view_binding) = view_uniforms.uniforms.binding() else {
+        return;
+    };
+    light_2d_meta.view_bind_group = Some(render_device.create_bind_group(&BindGroupDescriptor {
+        entries: &[BindGroupEntry {
+            binding: 0,
+            resource: view_binding,
+        }],
+        label: Some("sprite_normal_map_view_bind_group"),
+        layout: &sprite_pipeline.view_layout,
+    }));
+
+    // The sprites were sorted by z when queued, so the normal maps are drawn in the same order
+    for extracted_sprite in &extracted_sprites.sprites {
+        let Some(&normal_map) = extracted_normal_maps
+            .normal_maps
+            .get(&extracted_sprite.entity)
+        else {
+            continue;
+        };
+        let (Some(gpu_image), Some(gpu_normal_map)) = (
+            gpu_images.get(&Handle::weak(extracted_sprite.image_handle_id)),
+            gpu_images.get(&Handle::weak(normal_map)),
+        ) else {
+            continue;
+        };
+        image_bind_groups
+            .values
+            .entry(Handle::weak(normal_map))
+            .or_insert_with(|| {
+                render_device.create_bind_group(&BindGroupDescriptor {
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&gpu_normal_map.texture_view),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: BindingResource::Sampler(&gpu_normal_map.sampler),
+                        },
+                    ],
+                    label: Some("sprite_material_bind_group"),
+                    layout: &sprite_pipeline.material_layout,
+                })
+            });
+
+        let (positions, uvs) = extracted_sprite.quad(gpu_image.size);
+        // The x axis of the texture goes left when flipped horizontally, and its y axis goes down
+        // when flipped vertically, the v coordinate of the UVs going down the texture
+        let matrix = extracted_sprite.transform.affine().matrix3;
+        let flip = |flip: bool| if flip { -1.0 } else { 1.0 };
+        let x_axis = matrix.x_axis.truncate().normalize_or_zero() * flip(extracted_sprite.flip_x);
+        let y_axis = matrix.y_axis.truncate().normalize_or_zero() * flip(extracted_sprite.flip_y);
+        let axes = [x_axis.x, x_axis.y, y_axis.x, y_axis.y];
+
+        let start = light_2d_meta.vertices.len() as u32;
+        for i in QUAD_INDICES {
+            light_2d_meta.vertices.push(NormalMappedSpriteVertex {
+                position: positions[i],
+                uv: uvs[i].into(),
+                axes,
+            });
+        }
+        let end = light_2d_meta.vertices.len() as u32;
+
+        match light_2d_meta.batches.last_mut() {
+            Some(batch) if batch.normal_map == normal_map => batch.vertices.end = end,
+            _ => light_2d_meta.batches.push(NormalMapBatch {
+                vertices: start..end,
+                normal_map,
+            }),
+        }
+    }
+
+    light_2d_meta
+        .vertices
+        .write_buffer(&render_device, &render_queue);
+}
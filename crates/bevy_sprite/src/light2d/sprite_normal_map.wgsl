@@ -0,0 +1,56 @@
+struct View {
+    view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
+    inverse_view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+    // viewport(x_origin, y_origin, width, height)
+    viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    // the world directions of the x and y axes of the texture
+    @location(1) axes: vec4<f32>,
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vertex(
+    @location(0) vertex_position: vec3<f32>,
+    @location(1) vertex_uv: vec2<f32>,
+    @location(2) vertex_axes: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vertex_uv;
+    out.axes = vertex_axes;
+    out.position = view.view_proj * vec4<f32>(vertex_position, 1.0);
+    return out;
+}
+
+@group(1) @binding(0)
+var normal_map: texture_2d<f32>;
+@group(1) @binding(1)
+var normal_map_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let sampled = textureSample(normal_map, normal_map_sampler, in.uv);
+    if (sampled.a < 0.5) {
+        discard;
+    }
+    let texture_normal = sampled.rgb * 2.0 - 1.0;
+    let normal = normalize(vec3<f32>(
+        texture_normal.x * in.axes.xy + texture_normal.y * in.axes.zw,
+        texture_normal.z,
+    ));
+    return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+}
@@ -325,9 +325,14 @@ pub fn queue_material2d_meshes<M: Material2d>(
         let mut view_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples)
             | Mesh2dPipelineKey::from_hdr(view.hdr);
 
-        if let Some(Tonemapping::Enabled { deband_dither }) = tonemapping {
+        if let Some(Tonemapping::Enabled {
+            deband_dither,
+            method,
+        }) = tonemapping
+        {
             if !view.hdr {
                 view_key |= Mesh2dPipelineKey::TONEMAP_IN_SHADER;
+                view_key |= Mesh2dPipelineKey::from_tonemapping_method(*method);
 
                 if *deband_dither {
                     view_key |= Mesh2dPipelineKey::DEBAND_DITHER;
@@ -361,6 +366,10 @@ pub fn queue_material2d_meshes<M: Material2d>(
                                 continue;
                             }
                         };
+                        // The pipeline may still be created in the background
+                        if pipeline_cache.get_render_pipeline(pipeline_id).is_none() {
+                            continue;
+                        }
 
                         let mesh_z = mesh2d_uniform.transform.w_axis.z;
                         transparent_phase.add(Transparent2d {
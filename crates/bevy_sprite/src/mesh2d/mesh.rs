@@ -1,5 +1,6 @@
 use bevy_app::Plugin;
 use bevy_asset::{load_internal_asset, Handle, HandleUntyped};
+use bevy_core_pipeline::tonemapping::TonemappingMethod;
 use bevy_ecs::{
     prelude::*,
     system::{lifetimeless::*, SystemParamItem, SystemState},
@@ -9,7 +10,7 @@ use bevy_reflect::{Reflect, TypeUuid};
 use bevy_render::{
     extract_component::{ComponentUniforms, DynamicUniformIndex, UniformComponentPlugin},
     globals::{GlobalsBuffer, GlobalsUniform},
-    mesh::{GpuBufferInfo, Mesh, MeshVertexBufferLayout},
+    mesh::{GpuBufferInfo, Mesh, MeshBufferArena, MeshVertexBufferLayout},
     render_asset::RenderAssets,
     render_phase::{EntityRenderCommand, RenderCommandResult, TrackedRenderPass},
     render_resource::*,
@@ -291,6 +292,10 @@ bitflags::bitflags! {
         const DEBAND_DITHER               = (1 << 2);
         const MSAA_RESERVED_BITS          = Self::MSAA_MASK_BITS << Self::MSAA_SHIFT_BITS;
         const PRIMITIVE_TOPOLOGY_RESERVED_BITS = Self::PRIMITIVE_TOPOLOGY_MASK_BITS << Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS;
+        const TONEMAP_METHOD_RESERVED_BITS = Self::TONEMAP_METHOD_MASK_BITS << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD     = 0 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD_LUMINANCE = 1 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_ACES         = 2 << Self::TONEMAP_METHOD_SHIFT_BITS;
     }
 }
 
@@ -299,6 +304,9 @@ impl Mesh2dPipelineKey {
     const MSAA_SHIFT_BITS: u32 = 32 - Self::MSAA_MASK_BITS.count_ones();
     const PRIMITIVE_TOPOLOGY_MASK_BITS: u32 = 0b111;
     const PRIMITIVE_TOPOLOGY_SHIFT_BITS: u32 = Self::MSAA_SHIFT_BITS - 3;
+    const TONEMAP_METHOD_MASK_BITS: u32 = 0b11;
+    const TONEMAP_METHOD_SHIFT_BITS: u32 =
+        Self::PRIMITIVE_TOPOLOGY_SHIFT_BITS - Self::TONEMAP_METHOD_MASK_BITS.count_ones();
 
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
         let msaa_bits =
@@ -314,6 +322,27 @@ impl Mesh2dPipelineKey {
         }
     }
 
+    pub fn from_tonemapping_method(method: TonemappingMethod) -> Self {
+        match method {
+            TonemappingMethod::Reinhard => Mesh2dPipelineKey::TONEMAP_METHOD_REINHARD,
+            TonemappingMethod::ReinhardLuminance => {
+                Mesh2dPipelineKey::TONEMAP_METHOD_REINHARD_LUMINANCE
+            }
+            TonemappingMethod::Aces => Mesh2dPipelineKey::TONEMAP_METHOD_ACES,
+        }
+    }
+
+    pub fn tonemapping_method(&self) -> TonemappingMethod {
+        let method = self.intersection(Mesh2dPipelineKey::TONEMAP_METHOD_RESERVED_BITS);
+        if method == Mesh2dPipelineKey::TONEMAP_METHOD_REINHARD {
+            TonemappingMethod::Reinhard
+        } else if method == Mesh2dPipelineKey::TONEMAP_METHOD_ACES {
+            TonemappingMethod::Aces
+        } else {
+            TonemappingMethod::ReinhardLuminance
+        }
+    }
+
     pub fn msaa_samples(&self) -> u32 {
         1 << ((self.bits >> Self::MSAA_SHIFT_BITS) & Self::MSAA_MASK_BITS)
     }
@@ -377,6 +406,7 @@ impl SpecializedMeshPipeline for Mesh2dPipeline {
 
         if key.contains(Mesh2dPipelineKey::TONEMAP_IN_SHADER) {
             shader_defs.push("TONEMAP_IN_SHADER".into());
+            shader_defs.push(key.tonemapping_method().shader_def().into());
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
             if key.contains(Mesh2dPipelineKey::DEBAND_DITHER) {
@@ -409,6 +439,7 @@ impl SpecializedMeshPipeline for Mesh2dPipeline {
                 })],
             }),
             layout: Some(vec![self.view_layout.clone(), self.mesh_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             primitive: PrimitiveState {
                 front_face: FrontFace::Ccw,
                 cull_mode: Some(Face::Back),
@@ -438,18 +469,20 @@ pub fn queue_mesh2d_bind_group(
     mut commands: Commands,
     mesh2d_pipeline: Res<Mesh2dPipeline>,
     render_device: Res<RenderDevice>,
+    bind_group_cache: Res<BindGroupCache>,
     mesh2d_uniforms: Res<ComponentUniforms<Mesh2dUniform>>,
 ) {
-    if let Some(binding) = mesh2d_uniforms.uniforms().binding() {
+    if let Some(binding) = mesh2d_uniforms.uniforms().cached_binding() {
         commands.insert_resource(Mesh2dBindGroup {
-            value: render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[BindGroupEntry {
+            value: bind_group_cache.get_or_create(
+                &render_device,
+                Some("mesh2d_bind_group"),
+                &mesh2d_pipeline.mesh_layout,
+                &[CachedBindGroupEntry {
                     binding: 0,
                     resource: binding,
                 }],
-                label: Some("mesh2d_bind_group"),
-                layout: &mesh2d_pipeline.mesh_layout,
-            }),
+            ),
         });
     }
 }
@@ -462,30 +495,32 @@ pub struct Mesh2dViewBindGroup {
 pub fn queue_mesh2d_view_bind_groups(
     mut commands: Commands,
     render_device: Res<RenderDevice>,
+    bind_group_cache: Res<BindGroupCache>,
     mesh2d_pipeline: Res<Mesh2dPipeline>,
     view_uniforms: Res<ViewUniforms>,
     views: Query<Entity, With<ExtractedView>>,
     globals_buffer: Res<GlobalsBuffer>,
 ) {
     if let (Some(view_binding), Some(globals)) = (
-        view_uniforms.uniforms.binding(),
-        globals_buffer.buffer.binding(),
+        view_uniforms.uniforms.cached_binding(),
+        globals_buffer.buffer.cached_binding(),
     ) {
         for entity in &views {
-            let view_bind_group = render_device.create_bind_group(&BindGroupDescriptor {
-                entries: &[
-                    BindGroupEntry {
+            let view_bind_group = bind_group_cache.get_or_create(
+                &render_device,
+                Some("mesh2d_view_bind_group"),
+                &mesh2d_pipeline.view_layout,
+                &[
+                    CachedBindGroupEntry {
                         binding: 0,
                         resource: view_binding.clone(),
                     },
-                    BindGroupEntry {
+                    CachedBindGroupEntry {
                         binding: 1,
                         resource: globals.clone(),
                     },
                 ],
-                label: Some("mesh2d_view_bind_group"),
-                layout: &mesh2d_pipeline.view_layout,
-            });
+            );
 
             commands.entity(entity).insert(Mesh2dViewBindGroup {
                 value: view_bind_group,
@@ -536,33 +571,42 @@ impl<const I: usize> EntityRenderCommand for SetMesh2dBindGroup<I> {
 
 pub struct DrawMesh2d;
 impl EntityRenderCommand for DrawMesh2d {
-    type Param = (SRes<RenderAssets<Mesh>>, SQuery<Read<Mesh2dHandle>>);
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SRes<MeshBufferArena>,
+        SQuery<Read<Mesh2dHandle>>,
+    );
     #[inline]
     fn render<'w>(
         _view: Entity,
         item: Entity,
-        (meshes, mesh2d_query): SystemParamItem<'w, '_, Self::Param>,
+        (meshes, mesh_buffers, mesh2d_query): SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         let mesh_handle = &mesh2d_query.get(item).unwrap().0;
-        if let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) {
-            pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
-            match &gpu_mesh.buffer_info {
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_handle) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(slices) = mesh_buffers.into_inner().get(&gpu_mesh.allocation) else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_vertex_buffer(0, slices.vertex_slice());
+        match (&gpu_mesh.buffer_info, slices.index_buffer) {
+            (
                 GpuBufferInfo::Indexed {
-                    buffer,
                     index_format,
                     count,
-                } => {
-                    pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                    pass.draw_indexed(0..*count, 0, 0..1);
-                }
-                GpuBufferInfo::NonIndexed { vertex_count } => {
-                    pass.draw(0..*vertex_count, 0..1);
-                }
+                },
+                Some(index_buffer),
+            ) => {
+                pass.set_index_buffer(index_buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(slices.first_index..slices.first_index + count, 0, 0..1);
             }
-            RenderCommandResult::Success
-        } else {
-            RenderCommandResult::Failure
+            (GpuBufferInfo::NonIndexed { vertex_count }, _) => {
+                pass.draw(0..*vertex_count, 0..1);
+            }
+            _ => return RenderCommandResult::Failure,
         }
+        RenderCommandResult::Success
     }
 }
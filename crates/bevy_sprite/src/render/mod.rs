@@ -2,10 +2,13 @@ use std::cmp::Ordering;
 
 use crate::{
     texture_atlas::{TextureAtlas, TextureAtlasSprite},
-    Sprite, SPRITE_SHADER_HANDLE,
+    ImageScaleMode, Sprite, SPRITE_SHADER_HANDLE,
 };
 use bevy_asset::{AssetEvent, Assets, Handle, HandleId};
-use bevy_core_pipeline::{core_2d::Transparent2d, tonemapping::Tonemapping};
+use bevy_core_pipeline::{
+    core_2d::Transparent2d,
+    tonemapping::{Tonemapping, TonemappingMethod},
+};
 use bevy_ecs::{
     prelude::*,
     system::{lifetimeless::*, SystemParamItem, SystemState},
@@ -30,7 +33,7 @@ use bevy_render::{
     },
     Extract,
 };
-use bevy_transform::components::GlobalTransform;
+use bevy_transform::components::{GlobalTransform, Transform};
 use bevy_utils::FloatOrd;
 use bevy_utils::HashMap;
 use bytemuck::{Pod, Zeroable};
@@ -38,8 +41,8 @@ use fixedbitset::FixedBitSet;
 
 #[derive(Resource)]
 pub struct SpritePipeline {
-    view_layout: BindGroupLayout,
-    material_layout: BindGroupLayout,
+    pub(crate) view_layout: BindGroupLayout,
+    pub(crate) material_layout: BindGroupLayout,
     pub dummy_white_gpu_image: GpuImage,
 }
 
@@ -153,12 +156,19 @@ bitflags::bitflags! {
         const TONEMAP_IN_SHADER           = (1 << 2);
         const DEBAND_DITHER               = (1 << 3);
         const MSAA_RESERVED_BITS          = Self::MSAA_MASK_BITS << Self::MSAA_SHIFT_BITS;
+        const TONEMAP_METHOD_RESERVED_BITS = Self::TONEMAP_METHOD_MASK_BITS << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD     = 0 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_REINHARD_LUMINANCE = 1 << Self::TONEMAP_METHOD_SHIFT_BITS;
+        const TONEMAP_METHOD_ACES         = 2 << Self::TONEMAP_METHOD_SHIFT_BITS;
     }
 }
 
 impl SpritePipelineKey {
     const MSAA_MASK_BITS: u32 = 0b111;
     const MSAA_SHIFT_BITS: u32 = 32 - Self::MSAA_MASK_BITS.count_ones();
+    const TONEMAP_METHOD_MASK_BITS: u32 = 0b11;
+    const TONEMAP_METHOD_SHIFT_BITS: u32 =
+        Self::MSAA_SHIFT_BITS - Self::TONEMAP_METHOD_MASK_BITS.count_ones();
 
     pub fn from_msaa_samples(msaa_samples: u32) -> Self {
         let msaa_bits =
@@ -185,6 +195,27 @@ impl SpritePipelineKey {
             SpritePipelineKey::NONE
         }
     }
+
+    pub fn from_tonemapping_method(method: TonemappingMethod) -> Self {
+        match method {
+            TonemappingMethod::Reinhard => SpritePipelineKey::TONEMAP_METHOD_REINHARD,
+            TonemappingMethod::ReinhardLuminance => {
+                SpritePipelineKey::TONEMAP_METHOD_REINHARD_LUMINANCE
+            }
+            TonemappingMethod::Aces => SpritePipelineKey::TONEMAP_METHOD_ACES,
+        }
+    }
+
+    pub fn tonemapping_method(&self) -> TonemappingMethod {
+        let method = self.intersection(SpritePipelineKey::TONEMAP_METHOD_RESERVED_BITS);
+        if method == SpritePipelineKey::TONEMAP_METHOD_REINHARD {
+            TonemappingMethod::Reinhard
+        } else if method == SpritePipelineKey::TONEMAP_METHOD_ACES {
+            TonemappingMethod::Aces
+        } else {
+            TonemappingMethod::ReinhardLuminance
+        }
+    }
 }
 
 impl SpecializedRenderPipeline for SpritePipeline {
@@ -213,6 +244,7 @@ impl SpecializedRenderPipeline for SpritePipeline {
 
         if key.contains(SpritePipelineKey::TONEMAP_IN_SHADER) {
             shader_defs.push("TONEMAP_IN_SHADER".into());
+            shader_defs.push(key.tonemapping_method().shader_def().into());
 
             // Debanding is tied to tonemapping in the shader, cannot run without it.
             if key.contains(SpritePipelineKey::DEBAND_DITHER) {
@@ -243,6 +275,7 @@ impl SpecializedRenderPipeline for SpritePipeline {
                 })],
             }),
             layout: Some(vec![self.view_layout.clone(), self.material_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             primitive: PrimitiveState {
                 front_face: FrontFace::Ccw,
                 cull_mode: None,
@@ -280,6 +313,45 @@ pub struct ExtractedSprite {
     pub anchor: Vec2,
 }
 
+impl ExtractedSprite {
+    /// Returns the positions and UVs of the corners of the quad of the sprite, given the size of
+    /// its image.
+    pub(crate) fn quad(&self, image_size: Vec2) -> ([[f32; 3]; 4], [Vec2; 4]) {
+        let mut uvs = QUAD_UVS;
+        if self.flip_x {
+            uvs = [uvs[1], uvs[0], uvs[3], uvs[2]];
+        }
+        if self.flip_y {
+            uvs = [uvs[3], uvs[2], uvs[1], uvs[0]];
+        }
+
+        // By default, the size of the quad is the size of the texture
+        let mut quad_size = image_size;
+
+        // If a rect is specified, adjust UVs and the size of the quad
+        if let Some(rect) = self.rect {
+            let rect_size = rect.size();
+            for uv in &mut uvs {
+                *uv = (rect.min + *uv * rect_size) / image_size;
+            }
+            quad_size = rect_size;
+        }
+
+        // Override the size if a custom one is specified
+        if let Some(custom_size) = self.custom_size {
+            quad_size = custom_size;
+        }
+
+        // Apply size and global transform
+        let positions = QUAD_VERTEX_POSITIONS.map(|quad_pos| {
+            self.transform
+                .transform_point(((quad_pos - self.anchor) * quad_size).extend(0.))
+                .into()
+        });
+        (positions, uvs)
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct ExtractedSprites {
     pub sprites: Vec<ExtractedSprite>,
@@ -316,6 +388,7 @@ pub fn extract_sprite_events(
 pub fn extract_sprites(
     mut extracted_sprites: ResMut<ExtractedSprites>,
     texture_atlases: Extract<Res<Assets<TextureAtlas>>>,
+    images: Extract<Res<Assets<Image>>>,
     sprite_query: Extract<
         Query<(
             Entity,
@@ -323,6 +396,7 @@ pub fn extract_sprites(
             &Sprite,
             &GlobalTransform,
             &Handle<Image>,
+            Option<&ImageScaleMode>,
         )>,
     >,
     atlas_query: Extract<
@@ -336,10 +410,44 @@ pub fn extract_sprites(
     >,
 ) {
     extracted_sprites.sprites.clear();
-    for (entity, visibility, sprite, transform, handle) in sprite_query.iter() {
+    for (entity, visibility, sprite, transform, handle, scale_mode) in sprite_query.iter() {
         if !visibility.is_visible() {
             continue;
         }
+        if let Some(scale_mode) = scale_mode {
+            // The slices are positioned from the size of the image, so it must be loaded
+            let Some(image) = images.get(handle) else {
+                continue;
+            };
+            let rect = sprite.rect.unwrap_or(Rect {
+                min: Vec2::ZERO,
+                max: image.size(),
+            });
+            let render_size = sprite.custom_size.unwrap_or_else(|| rect.size());
+            let anchor = sprite.anchor.as_vec() * render_size;
+            for slice in scale_mode.compute_slices(rect, render_size) {
+                let mut offset = slice.offset;
+                if sprite.flip_x {
+                    offset.x = -offset.x;
+                }
+                if sprite.flip_y {
+                    offset.y = -offset.y;
+                }
+                extracted_sprites.sprites.push(ExtractedSprite {
+                    entity,
+                    color: sprite.color,
+                    transform: transform
+                        .mul_transform(Transform::from_translation((offset - anchor).extend(0.))),
+                    rect: Some(slice.texture_rect),
+                    custom_size: Some(slice.draw_size),
+                    flip_x: sprite.flip_x,
+                    flip_y: sprite.flip_y,
+                    image_handle_id: handle.id(),
+                    anchor: Vec2::ZERO,
+                });
+            }
+            continue;
+        }
         // PERF: we don't check in this function that the `Image` asset is ready, since it should be in most cases and hashing the handle is expensive
         extracted_sprites.sprites.push(ExtractedSprite {
             entity,
@@ -409,7 +517,7 @@ impl Default for SpriteMeta {
     }
 }
 
-const QUAD_INDICES: [usize; 6] = [0, 2, 3, 0, 1, 2];
+pub(crate) const QUAD_INDICES: [usize; 6] = [0, 2, 3, 0, 1, 2];
 
 const QUAD_VERTEX_POSITIONS: [Vec2; 4] = [
     Vec2::new(-0.5, -0.5),
@@ -433,7 +541,7 @@ pub struct SpriteBatch {
 
 #[derive(Resource, Default)]
 pub struct ImageBindGroups {
-    values: HashMap<Handle<Image>, BindGroup>,
+    pub(crate) values: HashMap<Handle<Image>, BindGroup>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -494,8 +602,6 @@ pub fn queue_sprites(
         let mut index = 0;
         let mut colored_index = 0;
 
-        // FIXME: VisibleEntities is ignored
-
         let extracted_sprites = &mut extracted_sprites.sprites;
         // Sort sprites by z for correct transparency and then by handle to improve batching
         // NOTE: This can be done independent of views by reasonably assuming that all 2D views look along the negative-z axis in world space
@@ -514,9 +620,14 @@ pub fn queue_sprites(
 
         for (mut transparent_phase, visible_entities, view, tonemapping) in &mut views {
             let mut view_key = SpritePipelineKey::from_hdr(view.hdr) | msaa_key;
-            if let Some(Tonemapping::Enabled { deband_dither }) = tonemapping {
+            if let Some(Tonemapping::Enabled {
+                deband_dither,
+                method,
+            }) = tonemapping
+            {
                 if !view.hdr {
                     view_key |= SpritePipelineKey::TONEMAP_IN_SHADER;
+                    view_key |= SpritePipelineKey::from_tonemapping_method(*method);
 
                     if *deband_dither {
                         view_key |= SpritePipelineKey::DEBAND_DITHER;
@@ -595,41 +706,7 @@ pub fn queue_sprites(
                 }
 
                 // Calculate vertex data for this item
-
-                let mut uvs = QUAD_UVS;
-                if extracted_sprite.flip_x {
-                    uvs = [uvs[1], uvs[0], uvs[3], uvs[2]];
-                }
-                if extracted_sprite.flip_y {
-                    uvs = [uvs[3], uvs[2], uvs[1], uvs[0]];
-                }
-
-                // By default, the size of the quad is the size of the texture
-                let mut quad_size = current_image_size;
-
-                // If a rect is specified, adjust UVs and the size of the quad
-                if let Some(rect) = extracted_sprite.rect {
-                    let rect_size = rect.size();
-                    for uv in &mut uvs {
-                        *uv = (rect.min + *uv * rect_size) / current_image_size;
-                    }
-                    quad_size = rect_size;
-                }
-
-                // Override the size if a custom one is specified
-                if let Some(custom_size) = extracted_sprite.custom_size {
-                    quad_size = custom_size;
-                }
-
-                // Apply size and global transform
-                let positions = QUAD_VERTEX_POSITIONS.map(|quad_pos| {
-                    extracted_sprite
-                        .transform
-                        .transform_point(
-                            ((quad_pos - extracted_sprite.anchor) * quad_size).extend(0.),
-                        )
-                        .into()
-                });
+                let (positions, uvs) = extracted_sprite.quad(current_image_size);
 
                 // These items will be sorted by depth with other phase items
                 let sort_key = FloatOrd(extracted_sprite.transform.translation().z);
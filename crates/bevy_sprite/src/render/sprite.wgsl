@@ -4,6 +4,7 @@
 
 struct View {
     view_proj: mat4x4<f32>,
+    unjittered_view_proj: mat4x4<f32>,
     inverse_view_proj: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
@@ -12,6 +13,8 @@ struct View {
     world_position: vec3<f32>,
     // viewport(x_origin, y_origin, width, height)
     viewport: vec4<f32>,
+    // color_grading(exposure, contrast, saturation)
+    color_grading: vec3<f32>,
 };
 @group(0) @binding(0)
 var<uniform> view: View;
@@ -54,7 +57,7 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
 #endif
 
 #ifdef TONEMAP_IN_SHADER
-    color = vec4<f32>(reinhard_luminance(color.rgb), color.a);
+    color = vec4<f32>(tonemap(color_grade(color.rgb, view.color_grading)), color.a);
 #endif
 
     return color;
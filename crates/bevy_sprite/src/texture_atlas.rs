@@ -20,6 +20,8 @@ pub struct TextureAtlas {
     /// The specific areas of the atlas where each texture can be found
     pub textures: Vec<Rect>,
     pub texture_handles: Option<HashMap<Handle<Image>, usize>>,
+    /// The index of the textures added with a name, see [`TextureAtlas::add_named_texture`]
+    pub texture_names: Option<HashMap<String, usize>>,
 }
 
 #[derive(Component, Debug, Clone, Reflect)]
@@ -64,6 +66,7 @@ impl TextureAtlas {
             texture,
             size: dimensions,
             texture_handles: None,
+            texture_names: None,
             textures: Vec::new(),
         }
     }
@@ -113,6 +116,7 @@ impl TextureAtlas {
             textures: sprites,
             texture,
             texture_handles: None,
+            texture_names: None,
         }
     }
 
@@ -128,6 +132,18 @@ impl TextureAtlas {
         self.textures.len() - 1
     }
 
+    /// Add a sprite to the list of textures in the `TextureAtlas` under a `name`, which
+    /// [`TextureAtlas::get_named_texture_index`] looks its index up with. Adding another texture
+    /// with the same name replaces the index of the name.
+    /// returns an index to the texture which can be used with `TextureAtlasSprite`
+    pub fn add_named_texture(&mut self, name: impl Into<String>, rect: Rect) -> usize {
+        let index = self.add_texture(rect);
+        self.texture_names
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), index);
+        index
+    }
+
     /// How many textures are in the `TextureAtlas`
     pub fn len(&self) -> usize {
         self.textures.len()
@@ -142,4 +158,11 @@ impl TextureAtlas {
             .as_ref()
             .and_then(|texture_handles| texture_handles.get(texture).cloned())
     }
+
+    /// Returns the index of the texture added with [`TextureAtlas::add_named_texture`] under `name`
+    pub fn get_named_texture_index(&self, name: &str) -> Option<usize> {
+        self.texture_names
+            .as_ref()
+            .and_then(|texture_names| texture_names.get(name).cloned())
+    }
 }
@@ -235,6 +235,7 @@ impl TextureAtlasBuilder {
             texture: textures.add(atlas_texture),
             textures: texture_rects,
             texture_handles: Some(texture_handles),
+            texture_names: None,
         })
     }
 }
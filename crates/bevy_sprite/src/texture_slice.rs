@@ -0,0 +1,310 @@
+use bevy_ecs::component::Component;
+use bevy_math::{Rect, Vec2};
+use bevy_reflect::{FromReflect, Reflect};
+
+/// How the image of a [`Sprite`](crate::Sprite) or of a UI image is scaled to the size it is drawn
+/// at, instead of being stretched.
+#[derive(Component, Debug, Clone, PartialEq, Reflect, FromReflect)]
+pub enum ImageScaleMode {
+    /// Slices the image in 9 parts, keeping its corners unscaled, see [`TextureSlicer`].
+    Sliced(TextureSlicer),
+    /// Repeats the image along the tiled axes, stretching it along the others.
+    Tiled {
+        /// Repeats the image horizontally
+        tile_x: bool,
+        /// Repeats the image vertically
+        tile_y: bool,
+        /// The scale of each repetition of the image
+        stretch_value: f32,
+    },
+}
+
+impl ImageScaleMode {
+    /// Returns the slices of the `rect` of a texture, in pixels, drawing it at `render_size`.
+    pub fn compute_slices(&self, rect: Rect, render_size: Vec2) -> Vec<TextureSlice> {
+        match self {
+            ImageScaleMode::Sliced(slicer) => slicer.compute_slices(rect, render_size),
+            ImageScaleMode::Tiled {
+                tile_x,
+                tile_y,
+                stretch_value,
+            } => TextureSlice {
+                texture_rect: rect,
+                draw_size: render_size,
+                offset: Vec2::ZERO,
+            }
+            .tiled(*stretch_value, (*tile_x, *tile_y)),
+        }
+    }
+}
+
+/// The size of the borders of a rectangle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect, FromReflect)]
+pub struct BorderRect {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+impl BorderRect {
+    /// Creates borders of the same size on every side.
+    pub const fn square(value: f32) -> Self {
+        Self {
+            left: value,
+            right: value,
+            top: value,
+            bottom: value,
+        }
+    }
+
+    /// Creates borders of the `horizontal` size on the left and right, and of the `vertical` size
+    /// on the top and bottom.
+    pub const fn rectangle(horizontal: f32, vertical: f32) -> Self {
+        Self {
+            left: horizontal,
+            right: horizontal,
+            top: vertical,
+            bottom: vertical,
+        }
+    }
+}
+
+/// How the sides and the center of a sliced texture fill their part of the drawn size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect, FromReflect)]
+pub enum SliceScaleMode {
+    /// Stretches the slice to its drawn size.
+    #[default]
+    Stretch,
+    /// Repeats the slice, each repetition being scaled by `stretch_value`, the last repetition
+    /// being cut to fit.
+    Tile { stretch_value: f32 },
+}
+
+/// Slices a texture in 9 parts, also called 9-patch, so it can be drawn at any size without
+/// smearing its borders.
+///
+/// The corners are drawn at the size of the texture, the left and right sides are only scaled
+/// vertically, the top and bottom sides only horizontally, and the center in both directions.
+/// When the drawn size is smaller than the borders, the corners are scaled down to fit.
+#[derive(Debug, Clone, PartialEq, Reflect, FromReflect)]
+pub struct TextureSlicer {
+    /// The size of the borders of the texture, in pixels
+    pub border: BorderRect,
+    /// How the center fills its area
+    pub center_scale_mode: SliceScaleMode,
+    /// How the sides fill their area
+    pub sides_scale_mode: SliceScaleMode,
+    /// The maximum scale of the corners
+    pub max_corner_scale: f32,
+}
+
+impl Default for TextureSlicer {
+    fn default() -> Self {
+        Self {
+            border: BorderRect::default(),
+            center_scale_mode: SliceScaleMode::default(),
+            sides_scale_mode: SliceScaleMode::default(),
+            max_corner_scale: 1.0,
+        }
+    }
+}
+
+impl TextureSlicer {
+    /// Returns the slices of the `rect` of a texture, in pixels, drawing it at `render_size`.
+    ///
+    /// The slices with no area are left out.
+    pub fn compute_slices(&self, rect: Rect, render_size: Vec2) -> Vec<TextureSlice> {
+        let BorderRect {
+            left,
+            right,
+            top,
+            bottom,
+        } = self.border;
+        let corner_scale = self
+            .max_corner_scale
+            .min(render_size.x / (left + right))
+            .min(render_size.y / (top + bottom));
+
+        // The edges of the columns and rows, from the left and the top, in the texture and in
+        // the drawn area
+        let texture_columns = [
+            rect.min.x,
+            rect.min.x + left,
+            rect.max.x - right,
+            rect.max.x,
+        ];
+        let texture_rows = [
+            rect.min.y,
+            rect.min.y + top,
+            rect.max.y - bottom,
+            rect.max.y,
+        ];
+        let draw_columns = [
+            0.0,
+            left * corner_scale,
+            render_size.x - right * corner_scale,
+            render_size.x,
+        ];
+        let draw_rows = [
+            0.0,
+            top * corner_scale,
+            render_size.y - bottom * corner_scale,
+            render_size.y,
+        ];
+
+        let mut slices = Vec::new();
+        for row in 0..3 {
+            for column in 0..3 {
+                let texture_rect = Rect::new(
+                    texture_columns[column],
+                    texture_rows[row],
+                    texture_columns[column + 1],
+                    texture_rows[row + 1],
+                );
+                let draw_min = Vec2::new(draw_columns[column], draw_rows[row]);
+                let draw_max = Vec2::new(draw_columns[column + 1], draw_rows[row + 1]);
+                let draw_size = draw_max - draw_min;
+                if texture_rect.is_empty() || draw_size.x <= 0.0 || draw_size.y <= 0.0 {
+                    continue;
+                }
+                let center = (draw_min + draw_max) / 2.0 - render_size / 2.0;
+                let slice = TextureSlice {
+                    texture_rect,
+                    draw_size,
+                    // The rows go down
+                    offset: Vec2::new(center.x, -center.y),
+                };
+                let (scale_mode, tiling) = match (column, row) {
+                    (1, 1) => (self.center_scale_mode, (true, true)),
+                    (1, _) => (self.sides_scale_mode, (true, false)),
+                    (_, 1) => (self.sides_scale_mode, (false, true)),
+                    _ => (SliceScaleMode::Stretch, (false, false)),
+                };
+                match scale_mode {
+                    SliceScaleMode::Stretch => slices.push(slice),
+                    SliceScaleMode::Tile { stretch_value } => {
+                        // The tiles of the sides keep the scale of the corners across them
+                        let stretch_value = if tiling == (true, true) {
+                            stretch_value
+                        } else {
+                            stretch_value * corner_scale
+                        };
+                        slices.extend(slice.tiled(stretch_value, tiling));
+                    }
+                }
+            }
+        }
+        slices
+    }
+}
+
+/// A part of a texture drawn at a given size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureSlice {
+    /// The area of the texture drawn, in pixels
+    pub texture_rect: Rect,
+    /// The size the slice is drawn at
+    pub draw_size: Vec2,
+    /// The offset of the center of the slice from the center of the drawn texture, with `y`
+    /// going up
+    pub offset: Vec2,
+}
+
+impl TextureSlice {
+    /// Splits the slice in tiles repeating its texture along the tiled axes, each tile being the
+    /// texture scaled by `stretch_value`, starting from the top left of the slice.
+    ///
+    /// The tiles of the last column and row are cut to fit in the slice.
+    pub fn tiled(self, stretch_value: f32, (tile_x, tile_y): (bool, bool)) -> Vec<TextureSlice> {
+        let texture_size = self.texture_rect.size();
+        let tile_size = Vec2::new(
+            if tile_x {
+                texture_size.x * stretch_value
+            } else {
+                self.draw_size.x
+            },
+            if tile_y {
+                texture_size.y * stretch_value
+            } else {
+                self.draw_size.y
+            },
+        );
+        if tile_size.x <= 0.0 || tile_size.y <= 0.0 {
+            return vec![self];
+        }
+
+        let top_left = self.offset + Vec2::new(-self.draw_size.x, self.draw_size.y) / 2.0;
+        let mut tiles = Vec::new();
+        let mut y = 0.0;
+        while y < self.draw_size.y {
+            let height = tile_size.y.min(self.draw_size.y - y);
+            let mut x = 0.0;
+            while x < self.draw_size.x {
+                let width = tile_size.x.min(self.draw_size.x - x);
+                let size = Vec2::new(width, height);
+                let min = self.texture_rect.min;
+                tiles.push(TextureSlice {
+                    texture_rect: Rect {
+                        min,
+                        max: min + texture_size * size / tile_size,
+                    },
+                    draw_size: size,
+                    offset: top_left + Vec2::new(x + width / 2.0, -y - height / 2.0),
+                });
+                x += width;
+            }
+            y += height;
+        }
+        tiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nine_slices() {
+        let slicer = TextureSlicer {
+            border: BorderRect::square(10.0),
+            ..Default::default()
+        };
+        let slices = slicer.compute_slices(Rect::new(0.0, 0.0, 30.0, 30.0), Vec2::new(100.0, 50.0));
+        assert_eq!(slices.len(), 9);
+
+        // The top left corner keeps its size
+        assert_eq!(slices[0].texture_rect, Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(slices[0].draw_size, Vec2::splat(10.0));
+        assert_eq!(slices[0].offset, Vec2::new(-45.0, 20.0));
+        // The top side is only stretched horizontally
+        assert_eq!(slices[1].draw_size, Vec2::new(80.0, 10.0));
+        assert_eq!(slices[1].offset, Vec2::new(0.0, 20.0));
+        // The center is stretched in both directions
+        assert_eq!(slices[4].texture_rect, Rect::new(10.0, 10.0, 20.0, 20.0));
+        assert_eq!(slices[4].draw_size, Vec2::new(80.0, 30.0));
+        assert_eq!(slices[4].offset, Vec2::ZERO);
+
+        // The corners shrink when the drawn size is smaller than the borders
+        let slices = slicer.compute_slices(Rect::new(0.0, 0.0, 30.0, 30.0), Vec2::new(10.0, 40.0));
+        assert_eq!(slices[0].draw_size, Vec2::splat(5.0));
+        assert_eq!(slices.len(), 6);
+    }
+
+    #[test]
+    fn tiles() {
+        let slice = TextureSlice {
+            texture_rect: Rect::new(10.0, 0.0, 20.0, 10.0),
+            draw_size: Vec2::new(25.0, 10.0),
+            offset: Vec2::ZERO,
+        };
+        let tiles = slice.tiled(1.0, (true, false));
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(tiles[0].offset, Vec2::new(-7.5, 0.0));
+        assert_eq!(tiles[1].texture_rect, slice.texture_rect);
+        // The last tile is cut
+        assert_eq!(tiles[2].draw_size, Vec2::new(5.0, 10.0));
+        assert_eq!(tiles[2].texture_rect, Rect::new(10.0, 0.0, 15.0, 10.0));
+        assert_eq!(tiles[2].offset, Vec2::new(10.0, 0.0));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{ColorMaterial, Mesh2dHandle, TextureAtlas};
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_ecs::{
+    bundle::Bundle,
+    component::Component,
+    entity::Entity,
+    event::EventReader,
+    query::ChangeTrackers,
+    system::{Commands, Query, Res, ResMut},
+};
+use bevy_hierarchy::{BuildChildren, Children, DespawnRecursiveExt};
+use bevy_math::{UVec2, Vec2};
+use bevy_render::{
+    mesh::{Indices, Mesh},
+    primitives::Aabb,
+    render_resource::PrimitiveTopology,
+    view::{ComputedVisibility, Visibility},
+};
+use bevy_transform::components::{GlobalTransform, Transform};
+use bevy_utils::{HashMap, HashSet};
+
+/// A grid of tiles drawn with the textures of the [`TextureAtlas`] of its entity.
+///
+/// The tile at `(0, 0)` is at the origin of the [`Transform`] of the map, and the `x` and `y` of
+/// the tiles go right and up. The tiles are drawn in chunks of [`TileMap::CHUNK_SIZE`] by
+/// [`TileMap::CHUNK_SIZE`] tiles, each with its own mesh, and only the chunks whose tiles changed
+/// are built again.
+///
+/// Each tile map is a single layer, the layers of a level are tile maps ordered by the `z` of
+/// their [`Transform`].
+#[derive(Component, Debug, Clone, Default)]
+pub struct TileMap {
+    size: UVec2,
+    tile_size: Vec2,
+    /// The index in the atlas of each tile, row by row from the bottom
+    tiles: Vec<Option<usize>>,
+    /// The position of the chunks whose mesh must be built again
+    dirty_chunks: HashSet<UVec2>,
+    /// The material of the chunks, or `None` if it must be created again from the atlas
+    material: Option<Handle<ColorMaterial>>,
+}
+
+impl TileMap {
+    /// The width and height of the chunks, in tiles.
+    pub const CHUNK_SIZE: u32 = 32;
+
+    /// Creates an empty map of `size` tiles, each `tile_size` wide in world units.
+    pub fn new(size: UVec2, tile_size: Vec2) -> Self {
+        Self {
+            size,
+            tile_size,
+            tiles: vec![None; size.x as usize * size.y as usize],
+            dirty_chunks: HashSet::default(),
+            material: None,
+        }
+    }
+
+    /// The width and height of the map, in tiles.
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// The width and height of a tile, in world units.
+    pub fn tile_size(&self) -> Vec2 {
+        self.tile_size
+    }
+
+    /// Returns the index in the atlas of the tile at `position`, or `None` if there is no tile
+    /// there.
+    pub fn get(&self, position: UVec2) -> Option<usize> {
+        if position.x >= self.size.x || position.y >= self.size.y {
+            return None;
+        }
+        self.tiles[self.tile_index(position)]
+    }
+
+    /// Sets the index in the atlas of the tile at `position`, or removes it with `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `position` is outside of the map.
+    pub fn set(&mut self, position: UVec2, tile: Option<usize>) {
+        assert!(
+            position.x < self.size.x && position.y < self.size.y,
+            "the tile {position} is outside of the map of size {}",
+            self.size
+        );
+        let index = self.tile_index(position);
+        if self.tiles[index] != tile {
+            self.tiles[index] = tile;
+            self.dirty_chunks.insert(position / Self::CHUNK_SIZE);
+        }
+    }
+
+    /// Sets all the tiles of the map to `tile`.
+    pub fn fill(&mut self, tile: Option<usize>) {
+        self.tiles.fill(tile);
+        self.mark_all_dirty();
+    }
+
+    fn tile_index(&self, position: UVec2) -> usize {
+        position.y as usize * self.size.x as usize + position.x as usize
+    }
+
+    fn mark_all_dirty(&mut self) {
+        let chunks = (self.size + Self::CHUNK_SIZE - 1) / Self::CHUNK_SIZE;
+        for y in 0..chunks.y {
+            for x in 0..chunks.x {
+                self.dirty_chunks.insert(UVec2::new(x, y));
+            }
+        }
+    }
+
+    /// Builds the mesh of the tiles of the chunk at `chunk`, or returns `None` if it has no tiles.
+    fn chunk_mesh(&self, chunk: UVec2, atlas: &TextureAtlas) -> Option<Mesh> {
+        let min = chunk * Self::CHUNK_SIZE;
+        let max = (min + Self::CHUNK_SIZE).min(self.size);
+
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let Some(rect) = self
+                    .get(UVec2::new(x, y))
+                    .and_then(|tile| atlas.textures.get(tile))
+                else {
+                    continue;
+                };
+                let position_min = Vec2::new(x as f32, y as f32) * self.tile_size;
+                let position_max = position_min + self.tile_size;
+                let uv_min = rect.min / atlas.size;
+                let uv_max = rect.max / atlas.size;
+
+                let start = positions.len() as u32;
+                positions.extend([
+                    [position_min.x, position_min.y, 0.0],
+                    [position_max.x, position_min.y, 0.0],
+                    [position_max.x, position_max.y, 0.0],
+                    [position_min.x, position_max.y, 0.0],
+                ]);
+                // The textures of the atlas go down from their top left corner
+                uvs.extend([
+                    [uv_min.x, uv_max.y],
+                    [uv_max.x, uv_max.y],
+                    [uv_max.x, uv_min.y],
+                    [uv_min.x, uv_min.y],
+                ]);
+                indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+            }
+        }
+        if indices.is_empty() {
+            return None;
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        Some(mesh)
+    }
+}
+
+/// A chunk of a [`TileMap`], spawned as a child of the map by [`update_tile_map_chunks`] to draw
+/// its tiles.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TileMapChunk {
+    /// The position of the chunk in the map, in chunks
+    pub position: UVec2,
+}
+
+/// A Bundle of components for drawing a [`TileMap`] layer
+#[derive(Bundle, Clone, Default)]
+pub struct TileMapBundle {
+    pub tile_map: TileMap,
+    /// A handle to the texture atlas that holds the tile images
+    pub texture_atlas: Handle<TextureAtlas>,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    /// User indication of whether an entity is visible
+    pub visibility: Visibility,
+    /// Algorithmically-computed indication of whether an entity is visible and should be extracted for rendering
+    pub computed_visibility: ComputedVisibility,
+}
+
+/// System building the meshes of the chunks of the [`TileMap`]s whose tiles changed, and of all
+/// the chunks of the maps whose [`TextureAtlas`] changed.
+#[allow(clippy::type_complexity)]
+pub fn update_tile_map_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut texture_atlas_events: EventReader<AssetEvent<TextureAtlas>>,
+    mut tile_maps: Query<(
+        Entity,
+        &mut TileMap,
+        &Handle<TextureAtlas>,
+        ChangeTrackers<Handle<TextureAtlas>>,
+        &GlobalTransform,
+        Option<&Children>,
+    )>,
+    chunks: Query<(&TileMapChunk, &Mesh2dHandle)>,
+) {
+    let changed_atlases: HashSet<_> = texture_atlas_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => Some(handle.id()),
+            AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+
+    for (entity, mut tile_map, atlas_handle, atlas_tracker, transform, children) in &mut tile_maps {
+        if tile_map.material.is_none()
+            || atlas_tracker.is_changed()
+            || changed_atlases.contains(&atlas_handle.id())
+        {
+            tile_map.material = None;
+            tile_map.mark_all_dirty();
+        }
+        if tile_map.dirty_chunks.is_empty() {
+            continue;
+        }
+        let Some(atlas) = texture_atlases.get(atlas_handle) else {
+            continue;
+        };
+        let material = tile_map
+            .material
+            .get_or_insert_with(|| materials.add(atlas.texture.clone().into()))
+            .clone();
+
+        let mut existing_chunks: HashMap<_, _> = children
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| {
+                let (chunk, mesh) = chunks.get(child).ok()?;
+                Some((chunk.position, (child, mesh.0.clone_weak())))
+            })
+            .collect();
+        for position in std::mem::take(&mut tile_map.dirty_chunks) {
+            match (
+                tile_map.chunk_mesh(position, atlas),
+                existing_chunks.remove(&position),
+            ) {
+                (Some(mesh), Some((chunk_entity, mesh_handle))) => {
+                    if let Some(chunk_mesh) = meshes.get_mut(&mesh_handle) {
+                        *chunk_mesh = mesh;
+                    }
+                    commands.entity(chunk_entity).insert(material.clone());
+                }
+                (Some(mesh), None) => {
+                    // The bounds of the whole chunk, which stay the same as its tiles change
+                    let min = (position * TileMap::CHUNK_SIZE).as_vec2() * tile_map.tile_size;
+                    let max = min + TileMap::CHUNK_SIZE as f32 * tile_map.tile_size;
+                    let chunk_entity = commands
+                        .spawn((
+                            TileMapChunk { position },
+                            Mesh2dHandle(meshes.add(mesh)),
+                            material.clone(),
+                            Transform::default(),
+                            // Set right away so that the chunk isn't drawn at the origin for a
+                            // frame
+                            *transform,
+                            Visibility::default(),
+                            ComputedVisibility::default(),
+                            Aabb::from_min_max(min.extend(0.0), max.extend(0.0)),
+                        ))
+                        .id();
+                    commands.entity(entity).add_child(chunk_entity);
+                }
+                (None, Some((chunk_entity, _))) => {
+                    commands.entity(chunk_entity).despawn_recursive();
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::Rect;
+    use bevy_render::mesh::VertexAttributeValues;
+
+    #[test]
+    fn dirty_chunks() {
+        let mut tile_map = TileMap::new(UVec2::new(40, 10), Vec2::splat(16.0));
+        tile_map.set(UVec2::new(35, 2), Some(1));
+        tile_map.set(UVec2::new(36, 3), Some(1));
+        assert_eq!(tile_map.get(UVec2::new(35, 2)), Some(1));
+        assert_eq!(tile_map.get(UVec2::new(0, 0)), None);
+        assert_eq!(tile_map.get(UVec2::new(40, 0)), None);
+        assert_eq!(
+            tile_map.dirty_chunks.iter().collect::<Vec<_>>(),
+            [&UVec2::new(1, 0)]
+        );
+
+        tile_map.fill(None);
+        assert_eq!(tile_map.dirty_chunks.len(), 2);
+    }
+
+    #[test]
+    fn chunk_mesh() {
+        let mut atlas = TextureAtlas::new_empty(Handle::default(), Vec2::new(32.0, 16.0));
+        atlas.add_texture(Rect::new(0.0, 0.0, 16.0, 16.0));
+        atlas.add_texture(Rect::new(16.0, 0.0, 32.0, 16.0));
+
+        let mut tile_map = TileMap::new(UVec2::new(40, 10), Vec2::splat(2.0));
+        assert!(tile_map.chunk_mesh(UVec2::ZERO, &atlas).is_none());
+        tile_map.set(UVec2::new(1, 0), Some(1));
+        // Tiles missing from the atlas are skipped
+        tile_map.set(UVec2::new(2, 0), Some(2));
+
+        let mesh = tile_map.chunk_mesh(UVec2::ZERO, &atlas).unwrap();
+        assert_eq!(mesh.indices().unwrap().len(), 6);
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("the chunk mesh has no positions");
+        };
+        assert_eq!(positions[0], [2.0, 0.0, 0.0]);
+        assert_eq!(positions[2], [4.0, 2.0, 0.0]);
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0)
+        else {
+            panic!("the chunk mesh has no uvs");
+        };
+        assert_eq!(uvs[0], [0.5, 1.0]);
+        assert_eq!(uvs[2], [1.0, 0.0]);
+    }
+}
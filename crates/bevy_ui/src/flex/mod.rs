@@ -1,6 +1,6 @@
 mod convert;
 
-use crate::{CalculatedSize, Node, Style, UiScale};
+use crate::{CalculatedSize, Node, ScrollPosition, Style, UiScale};
 use bevy_ecs::{
     entity::Entity,
     event::EventReader,
@@ -229,7 +229,9 @@ pub fn flex_node_system(
         (With<Node>, Changed<CalculatedSize>),
     >,
     children_query: Query<(Entity, &Children), (With<Node>, Changed<Children>)>,
+    full_children_query: Query<&Children, With<Node>>,
     removed_children: RemovedComponents<Children>,
+    mut scroll_position_query: Query<(Entity, &Style, &mut ScrollPosition), With<Node>>,
     mut node_transform_query: Query<(Entity, &mut Node, &mut Transform, Option<&Parent>)>,
     removed_nodes: RemovedComponents<Node>,
 ) {
@@ -291,6 +293,32 @@ pub fn flex_node_system(
 
     let to_logical = |v| (physical_to_logical_factor * v as f64) as f32;
 
+    // clamp the scroll positions to the content overflowing their node
+    for (entity, _, mut scroll_position) in &mut scroll_position_query {
+        let Ok(layout) = flex_surface.get_layout(entity) else {
+            continue;
+        };
+        let mut content_size = Vec2::ZERO;
+        for child in full_children_query.get(entity).into_iter().flatten() {
+            if let Ok(child_layout) = flex_surface.get_layout(*child) {
+                content_size = content_size.max(Vec2::new(
+                    to_logical(child_layout.location.x + child_layout.size.width),
+                    to_logical(child_layout.location.y + child_layout.size.height),
+                ));
+            }
+        }
+        let node_size = Vec2::new(
+            to_logical(layout.size.width),
+            to_logical(layout.size.height),
+        );
+        let max_offset = (content_size - node_size).max(Vec2::ZERO);
+        let offset = scroll_position.offset.clamp(Vec2::ZERO, max_offset);
+        // only trigger change detection when the new value is different
+        if scroll_position.offset != offset {
+            scroll_position.offset = offset;
+        }
+    }
+
     // PERF: try doing this incrementally
     for (entity, mut node, mut transform, parent) in &mut node_transform_query {
         let layout = flex_surface.get_layout(entity).unwrap();
@@ -310,6 +338,12 @@ pub fn flex_node_system(
                 new_position.x -= to_logical(parent_layout.size.width / 2.0);
                 new_position.y -= to_logical(parent_layout.size.height / 2.0);
             }
+            if let Ok((_, style, scroll_position)) = scroll_position_query.get(**parent) {
+                if style.overflow.is_scrollable() {
+                    new_position.x -= scroll_position.offset.x;
+                    new_position.y -= scroll_position.offset.y;
+                }
+            }
         }
         // only trigger change detection when the new value is different
         if transform.translation != new_position {
@@ -317,3 +351,94 @@ pub fn flex_node_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::{
+        event::Events,
+        query::With,
+        schedule::{Schedule, Stage, SystemStage},
+        system::{CommandQueue, Commands},
+        world::World,
+    };
+    use bevy_hierarchy::{BuildChildren, Parent};
+    use bevy_math::Vec2;
+    use bevy_transform::components::Transform;
+    use bevy_window::{Window, WindowDescriptor, WindowId, WindowScaleFactorChanged, Windows};
+
+    use crate::{
+        node_bundles::NodeBundle, FlexDirection, Overflow, ScrollPosition, Size, Style, UiScale,
+        Val,
+    };
+
+    use super::{flex_node_system, FlexSurface};
+
+    /// Tests that the scroll position of a node is clamped to its overflowing content, and
+    /// offsets its children.
+    #[test]
+    fn test_scroll_position() {
+        let mut world = World::default();
+        let mut windows = Windows::default();
+        windows.add(Window::new(
+            WindowId::primary(),
+            &WindowDescriptor::default(),
+            400,
+            400,
+            1.0,
+            None,
+            None,
+        ));
+        world.insert_resource(windows);
+        world.init_resource::<UiScale>();
+        world.init_resource::<FlexSurface>();
+        world.init_resource::<Events<WindowScaleFactorChanged>>();
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let list = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    size: Size::new(Val::Px(100.0), Val::Px(100.0)),
+                    overflow: Overflow::Scroll,
+                    ..Default::default()
+                },
+                scroll_position: ScrollPosition {
+                    offset: Vec2::new(10.0, 500.0),
+                },
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                for _ in 0..5 {
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            flex_shrink: 0.0,
+                            size: Size::new(Val::Px(100.0), Val::Px(60.0)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    });
+                }
+            })
+            .id();
+        queue.apply(&mut world);
+
+        let mut schedule = Schedule::default();
+        let mut update_stage = SystemStage::parallel();
+        update_stage.add_system(flex_node_system);
+        schedule.add_stage("update", update_stage);
+        schedule.run(&mut world);
+
+        // the content overflows the list by 200 pixels vertically, and not horizontally
+        let scroll_position = world.get::<ScrollPosition>(list).unwrap();
+        assert_eq!(scroll_position.offset, Vec2::new(0.0, 200.0));
+
+        let mut children = world.query_filtered::<&Transform, With<Parent>>();
+        let mut positions: Vec<f32> = children
+            .iter(&world)
+            .map(|transform| transform.translation.y)
+            .collect();
+        positions.sort_by(f32::total_cmp);
+        assert_eq!(positions, vec![-220.0, -160.0, -100.0, -40.0, 20.0]);
+    }
+}
@@ -110,28 +110,7 @@ pub fn ui_focus_system(
     let mouse_clicked =
         mouse_button_input.just_pressed(MouseButton::Left) || touches_input.any_just_pressed();
 
-    let is_ui_disabled =
-        |camera_ui| matches!(camera_ui, Some(&UiCameraConfig { show_ui: false, .. }));
-
-    let cursor_position = camera
-        .iter()
-        .filter(|(_, camera_ui)| !is_ui_disabled(*camera_ui))
-        .filter_map(|(camera, _)| {
-            if let RenderTarget::Window(window_id) = camera.target {
-                Some(window_id)
-            } else {
-                None
-            }
-        })
-        .filter_map(|window_id| windows.get(window_id))
-        .filter(|window| window.is_focused())
-        .find_map(|window| {
-            window.cursor_position().map(|mut cursor_pos| {
-                cursor_pos.y = window.height() - cursor_pos.y;
-                cursor_pos
-            })
-        })
-        .or_else(|| touches_input.first_pressed_position());
+    let cursor_position = ui_cursor_position(&camera, &windows, &touches_input);
 
     // prepare an iterator that contains all the nodes that have the cursor in their rect,
     // from the top node to the bottom one. this will also reset the interaction to `None`
@@ -232,3 +211,34 @@ pub fn ui_focus_system(
         }
     }
 }
+
+/// Returns the position of the cursor in the window of a camera rendering the UI, in the UI
+/// coordinates going down from the top left corner of the window, or of the first touch.
+pub(crate) fn ui_cursor_position(
+    camera: &Query<(&Camera, Option<&UiCameraConfig>)>,
+    windows: &Windows,
+    touches_input: &Touches,
+) -> Option<Vec2> {
+    let is_ui_disabled =
+        |camera_ui| matches!(camera_ui, Some(&UiCameraConfig { show_ui: false, .. }));
+
+    camera
+        .iter()
+        .filter(|(_, camera_ui)| !is_ui_disabled(*camera_ui))
+        .filter_map(|(camera, _)| {
+            if let RenderTarget::Window(window_id) = camera.target {
+                Some(window_id)
+            } else {
+                None
+            }
+        })
+        .filter_map(|window_id| windows.get(window_id))
+        .filter(|window| window.is_focused())
+        .find_map(|window| {
+            window.cursor_position().map(|mut cursor_pos| {
+                cursor_pos.y = window.height() - cursor_pos.y;
+                cursor_pos
+            })
+        })
+        .or_else(|| touches_input.first_pressed_position())
+}
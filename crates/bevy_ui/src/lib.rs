@@ -6,6 +6,7 @@ mod flex;
 mod focus;
 mod geometry;
 mod render;
+mod scroll;
 mod stack;
 mod ui_node;
 
@@ -19,6 +20,7 @@ pub use flex::*;
 pub use focus::*;
 pub use geometry::*;
 pub use render::*;
+pub use scroll::*;
 pub use ui_node::*;
 
 #[doc(hidden)]
@@ -55,6 +57,9 @@ pub enum UiSystem {
     Flex,
     /// After this label, input interactions with UI entities have been updated for this frame
     Focus,
+    /// After this label, the [`ScrollPosition`]s scrolled by the user have been updated for this
+    /// frame
+    Scroll,
     /// After this label, the [`UiStack`] resource has been updated
     Stack,
 }
@@ -97,6 +102,7 @@ impl Plugin for UiPlugin {
             .register_type::<Option<f32>>()
             .register_type::<Overflow>()
             .register_type::<PositionType>()
+            .register_type::<ScrollPosition>()
             .register_type::<Size>()
             .register_type::<UiRect>()
             .register_type::<Style>()
@@ -108,6 +114,10 @@ impl Plugin for UiPlugin {
                 CoreStage::PreUpdate,
                 ui_focus_system.label(UiSystem::Focus).after(InputSystem),
             )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                ui_scroll_system.label(UiSystem::Scroll).after(InputSystem),
+            )
             // add these stages to front because these must run before transform update systems
             .add_system_to_stage(
                 CoreStage::PostUpdate,
@@ -17,14 +17,14 @@ use bevy_render::{
     color::Color,
     render_asset::RenderAssets,
     render_graph::{RenderGraph, RunGraphOnViewNode, SlotInfo, SlotType},
-    render_phase::{sort_phase_system, AddRenderCommand, DrawFunctions, RenderPhase},
+    render_phase::{AddRenderCommand, AddRenderPhase, DrawFunctions, RenderPhase},
     render_resource::*,
     renderer::{RenderDevice, RenderQueue},
     texture::Image,
     view::{ComputedVisibility, ExtractedView, ViewUniforms},
     Extract, RenderApp, RenderStage,
 };
-use bevy_sprite::{SpriteAssetEvents, TextureAtlas};
+use bevy_sprite::{ImageScaleMode, SpriteAssetEvents, TextureAtlas};
 use bevy_text::{Text, TextLayoutInfo};
 use bevy_transform::components::GlobalTransform;
 use bevy_utils::FloatOrd;
@@ -69,7 +69,7 @@ pub fn build_ui_render(app: &mut App) {
         .init_resource::<UiImageBindGroups>()
         .init_resource::<UiMeta>()
         .init_resource::<ExtractedUiNodes>()
-        .init_resource::<DrawFunctions<TransparentUi>>()
+        .add_render_phase::<TransparentUi>()
         .add_render_command::<TransparentUi, DrawUi>()
         .add_system_to_stage(
             RenderStage::Extract,
@@ -88,8 +88,7 @@ pub fn build_ui_render(app: &mut App) {
             extract_text_uinodes.after(RenderUiSystem::ExtractNode),
         )
         .add_system_to_stage(RenderStage::Prepare, prepare_uinodes)
-        .add_system_to_stage(RenderStage::Queue, queue_uinodes)
-        .add_system_to_stage(RenderStage::PhaseSort, sort_phase_system::<TransparentUi>);
+        .add_system_to_stage(RenderStage::Queue, queue_uinodes);
 
     // Render graph
     let ui_graph_2d = get_ui_graph(render_app);
@@ -176,7 +175,6 @@ pub struct ExtractedUiNode {
     pub clip: Option<Rect>,
     pub flip_x: bool,
     pub flip_y: bool,
-    pub scale_factor: f32,
 }
 
 #[derive(Resource, Default)]
@@ -188,7 +186,6 @@ pub fn extract_uinodes(
     mut extracted_uinodes: ResMut<ExtractedUiNodes>,
     images: Extract<Res<Assets<Image>>>,
     ui_stack: Extract<Res<UiStack>>,
-    windows: Extract<Res<Windows>>,
     uinode_query: Extract<
         Query<(
             &Node,
@@ -197,13 +194,13 @@ pub fn extract_uinodes(
             Option<&UiImage>,
             &ComputedVisibility,
             Option<&CalculatedClip>,
+            Option<&ImageScaleMode>,
         )>,
     >,
 ) {
-    let scale_factor = windows.scale_factor(WindowId::primary()) as f32;
     extracted_uinodes.uinodes.clear();
     for (stack_index, entity) in ui_stack.uinodes.iter().enumerate() {
-        if let Ok((uinode, transform, color, maybe_image, visibility, clip)) =
+        if let Ok((uinode, transform, color, maybe_image, visibility, clip, scale_mode)) =
             uinode_query.get(*entity)
         {
             if !visibility.is_visible() {
@@ -215,14 +212,50 @@ pub fn extract_uinodes(
                 (DEFAULT_IMAGE_HANDLE.typed().clone_weak(), false, false)
             };
             // Skip loading images
-            if !images.contains(&image) {
+            let Some(texture) = images.get(&image) else {
                 continue;
-            }
+            };
             // Skip completely transparent nodes
             if color.0.a() == 0.0 {
                 continue;
             }
 
+            if let (Some(scale_mode), Some(_)) = (scale_mode, maybe_image) {
+                let image_size = texture.size();
+                let image_rect = Rect {
+                    min: Vec2::ZERO,
+                    max: image_size,
+                };
+                let transform = transform.compute_matrix();
+                for slice in scale_mode.compute_slices(image_rect, uinode.calculated_size) {
+                    // The slices are offset with y going up, while it goes down in the UI
+                    let mut offset = Vec2::new(slice.offset.x, -slice.offset.y);
+                    if flip_x {
+                        offset.x = -offset.x;
+                    }
+                    if flip_y {
+                        offset.y = -offset.y;
+                    }
+                    // The quad of the node is the size of its rect, which is the size of the
+                    // slice in the texture here
+                    let scale = slice.draw_size / slice.texture_rect.size();
+                    extracted_uinodes.uinodes.push(ExtractedUiNode {
+                        stack_index,
+                        transform: transform
+                            * Mat4::from_translation(offset.extend(0.0))
+                            * Mat4::from_scale(scale.extend(1.0)),
+                        background_color: color.0,
+                        rect: slice.texture_rect,
+                        image: image.clone_weak(),
+                        atlas_size: Some(image_size),
+                        clip: clip.map(|clip| clip.clip),
+                        flip_x,
+                        flip_y,
+                    });
+                }
+                continue;
+            }
+
             extracted_uinodes.uinodes.push(ExtractedUiNode {
                 stack_index,
                 transform: transform.compute_matrix(),
@@ -236,7 +269,6 @@ pub fn extract_uinodes(
                 clip: clip.map(|clip| clip.clip),
                 flip_x,
                 flip_y,
-                scale_factor,
             });
         }
     }
@@ -364,7 +396,6 @@ pub fn extract_text_uinodes(
                     clip: clip.map(|clip| clip.clip),
                     flip_x: false,
                     flip_y: false,
-                    scale_factor,
                 });
             }
         }
@@ -498,22 +529,28 @@ pub fn prepare_uinodes(
         }
 
         let atlas_extent = extracted_uinode.atlas_size.unwrap_or(uinode_rect.max);
+        // The texture pixels per unit of the node, moving the UVs along with the clipped corners
+        let texture_scale = uinode_rect.size()
+            / transformed_rect_size
+                .truncate()
+                .abs()
+                .max(Vec2::splat(f32::EPSILON));
         let mut uvs = [
             Vec2::new(
-                uinode_rect.min.x + positions_diff[0].x * extracted_uinode.scale_factor,
-                uinode_rect.min.y + positions_diff[0].y * extracted_uinode.scale_factor,
+                uinode_rect.min.x + positions_diff[0].x * texture_scale.x,
+                uinode_rect.min.y + positions_diff[0].y * texture_scale.y,
             ),
             Vec2::new(
-                uinode_rect.max.x + positions_diff[1].x * extracted_uinode.scale_factor,
-                uinode_rect.min.y + positions_diff[1].y * extracted_uinode.scale_factor,
+                uinode_rect.max.x + positions_diff[1].x * texture_scale.x,
+                uinode_rect.min.y + positions_diff[1].y * texture_scale.y,
             ),
             Vec2::new(
-                uinode_rect.max.x + positions_diff[2].x * extracted_uinode.scale_factor,
-                uinode_rect.max.y + positions_diff[2].y * extracted_uinode.scale_factor,
+                uinode_rect.max.x + positions_diff[2].x * texture_scale.x,
+                uinode_rect.max.y + positions_diff[2].y * texture_scale.y,
             ),
             Vec2::new(
-                uinode_rect.min.x + positions_diff[3].x * extracted_uinode.scale_factor,
-                uinode_rect.max.y + positions_diff[3].y * extracted_uinode.scale_factor,
+                uinode_rect.min.x + positions_diff[3].x * texture_scale.x,
+                uinode_rect.max.y + positions_diff[3].y * texture_scale.y,
             ),
         ]
         .map(|pos| pos / atlas_extent);
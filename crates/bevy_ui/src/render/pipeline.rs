@@ -103,6 +103,7 @@ impl SpecializedRenderPipeline for UiPipeline {
                 })],
             }),
             layout: Some(vec![self.view_layout.clone(), self.image_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             primitive: PrimitiveState {
                 front_face: FrontFace::Ccw,
                 cull_mode: None,
@@ -0,0 +1,124 @@
+use crate::{
+    camera_config::UiCameraConfig, focus::ui_cursor_position, CalculatedClip, Node, Overflow,
+    ScrollPosition, Style, UiStack,
+};
+use bevy_ecs::{
+    entity::Entity,
+    event::EventReader,
+    query::WorldQuery,
+    system::{Local, Query, Res},
+};
+use bevy_input::{
+    mouse::{MouseButton, MouseScrollUnit, MouseWheel},
+    touch::Touches,
+    Input,
+};
+use bevy_math::Vec2;
+use bevy_render::{camera::Camera, view::ComputedVisibility};
+use bevy_transform::components::GlobalTransform;
+use bevy_window::Windows;
+
+/// The distance scrolled by a line of a [`MouseWheel`] event, in logical pixels
+pub const SCROLL_LINE_HEIGHT: f32 = 20.0;
+
+/// The node dragged by [`ui_scroll_system`]
+#[derive(Default)]
+pub struct ScrollState {
+    dragged: Option<(Entity, Vec2)>,
+}
+
+/// Main query for [`ui_scroll_system`]
+#[derive(WorldQuery)]
+#[world_query(mutable)]
+pub struct ScrollNodeQuery {
+    entity: Entity,
+    node: &'static Node,
+    style: &'static Style,
+    global_transform: &'static GlobalTransform,
+    scroll_position: &'static mut ScrollPosition,
+    calculated_clip: Option<&'static CalculatedClip>,
+    computed_visibility: Option<&'static ComputedVisibility>,
+}
+
+/// The system that scrolls the nodes with an [`Overflow::Scroll`] and a [`ScrollPosition`] under
+/// the cursor, with the mouse wheel or by dragging their content with the left mouse button or a
+/// touch.
+///
+/// Only the top node under the cursor is scrolled.
+#[allow(clippy::too_many_arguments)]
+pub fn ui_scroll_system(
+    mut state: Local<ScrollState>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    camera: Query<(&Camera, Option<&UiCameraConfig>)>,
+    windows: Res<Windows>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    touches_input: Res<Touches>,
+    ui_stack: Res<UiStack>,
+    mut node_query: Query<ScrollNodeQuery>,
+) {
+    let wheel_delta: Vec2 = mouse_wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => Vec2::new(event.x, event.y) * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => Vec2::new(event.x, event.y),
+        })
+        .sum();
+
+    let cursor_position = ui_cursor_position(&camera, &windows, &touches_input);
+    let pressed =
+        mouse_button_input.pressed(MouseButton::Left) || touches_input.iter().next().is_some();
+    let just_pressed =
+        mouse_button_input.just_pressed(MouseButton::Left) || touches_input.any_just_pressed();
+
+    let Some(cursor_position) = cursor_position.filter(|_| pressed || wheel_delta != Vec2::ZERO)
+    else {
+        state.dragged = None;
+        return;
+    };
+
+    // the content follows the cursor while it is dragged
+    if let Some((entity, last_position)) = state.dragged.filter(|_| pressed && !just_pressed) {
+        if let Ok(mut node) = node_query.get_mut(entity) {
+            node.scroll_position.offset -= cursor_position - last_position;
+            state.dragged = Some((entity, cursor_position));
+        } else {
+            state.dragged = None;
+        }
+        return;
+    }
+    state.dragged = None;
+
+    // find the top scrollable node under the cursor
+    let hovered_node = ui_stack.uinodes.iter().rev().find(|entity| {
+        let Ok(node) = node_query.get(**entity) else {
+            return false;
+        };
+        let is_visible = node
+            .computed_visibility
+            .map_or(true, ComputedVisibility::is_visible);
+        if node.style.overflow != Overflow::Scroll || !is_visible {
+            return false;
+        }
+        let position = node.global_transform.translation().truncate();
+        let extents = node.node.size() / 2.0;
+        let mut min = position - extents;
+        let mut max = position + extents;
+        if let Some(clip) = node.calculated_clip {
+            min = Vec2::max(min, clip.clip.min);
+            max = Vec2::min(max, clip.clip.max);
+        }
+        (min.x..max.x).contains(&cursor_position.x) && (min.y..max.y).contains(&cursor_position.y)
+    });
+    let Some(&entity) = hovered_node else {
+        return;
+    };
+
+    let mut node = node_query.get_mut(entity).unwrap();
+    if wheel_delta != Vec2::ZERO {
+        // scrolling the wheel up moves the content down
+        node.scroll_position.offset -= wheel_delta;
+    }
+    if just_pressed {
+        state.dragged = Some((entity, cursor_position));
+    }
+}
@@ -403,8 +403,26 @@ pub enum Overflow {
     /// Show overflowing items
     #[default]
     Visible,
-    /// Hide overflowing items
+    /// Hide overflowing items, the [`ScrollPosition`] of the node being ignored
+    Clip,
+    /// Hide overflowing items, which can be scrolled into view with the [`ScrollPosition`] of the
+    /// node
     Hidden,
+    /// Hide overflowing items, which can be scrolled into view with the [`ScrollPosition`] of the
+    /// node, or by the user with the mouse wheel and by dragging the content
+    Scroll,
+}
+
+impl Overflow {
+    /// Whether the overflowing items are hidden
+    pub fn is_clipped(&self) -> bool {
+        !matches!(self, Overflow::Visible)
+    }
+
+    /// Whether the children of the node are offset by its [`ScrollPosition`]
+    pub fn is_scrollable(&self) -> bool {
+        matches!(self, Overflow::Hidden | Overflow::Scroll)
+    }
 }
 
 /// The strategy used to position this node
@@ -458,6 +476,9 @@ impl From<Color> for BackgroundColor {
 }
 
 /// The 2D texture displayed for this UI node
+///
+/// The texture is stretched to the size of the node, unless the node also has an
+/// [`ImageScaleMode`](bevy_sprite::ImageScaleMode) slicing or tiling it.
 #[derive(Component, Clone, Debug, Reflect)]
 #[reflect(Component, Default)]
 pub struct UiImage {
@@ -494,6 +515,18 @@ impl From<Handle<Image>> for UiImage {
     }
 }
 
+/// The scroll offset of the content of a node with a scrollable [`Overflow`], in logical pixels.
+///
+/// The offset goes from the top left corner of the node to the top left corner of the visible
+/// part of its content, so increasing `offset.y` scrolls the children up. It is clamped to the
+/// size of the content overflowing the node when the layout is computed.
+#[derive(Component, Default, Copy, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component, Default)]
+pub struct ScrollPosition {
+    /// The offset of the content
+    pub offset: Vec2,
+}
+
 /// The calculated clip of the node
 #[derive(Component, Default, Copy, Clone, Debug, Reflect)]
 #[reflect(Component)]
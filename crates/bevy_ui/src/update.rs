@@ -1,6 +1,6 @@
 //! This module contains systems that update the UI when something changes
 
-use crate::{CalculatedClip, Overflow, Style};
+use crate::{CalculatedClip, Style};
 
 use super::Node;
 use bevy_ecs::{
@@ -55,13 +55,12 @@ fn update_clipping(
     }
 
     // Calculate new clip for its children
-    let children_clip = match style.overflow {
-        Overflow::Visible => clip,
-        Overflow::Hidden => {
-            let node_center = global_transform.translation().truncate();
-            let node_rect = Rect::from_center_size(node_center, node.calculated_size);
-            Some(clip.map_or(node_rect, |c| c.intersect(node_rect)))
-        }
+    let children_clip = if style.overflow.is_clipped() {
+        let node_center = global_transform.translation().truncate();
+        let node_rect = Rect::from_center_size(node_center, node.calculated_size);
+        Some(clip.map_or(node_rect, |c| c.intersect(node_rect)))
+    } else {
+        clip
     };
 
     if let Ok(children) = children_query.get(entity) {
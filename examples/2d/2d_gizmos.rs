@@ -0,0 +1,40 @@
+//! Draws debug lines and shapes in 2D with [`Gizmos`].
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(draw_gizmos)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+}
+
+fn draw_gizmos(mut gizmos: Gizmos, time: Res<Time>) {
+    let t = time.elapsed_seconds();
+
+    gizmos.line_2d(
+        Vec2::new(-300.0, -200.0),
+        Vec2::new(300.0, -200.0),
+        Color::WHITE,
+    );
+    gizmos.rect_2d(
+        Vec2::new(-150.0, 0.0),
+        t / 2.0,
+        Vec2::splat(150.0),
+        Color::YELLOW,
+    );
+    gizmos.circle_2d(Vec2::new(150.0, 0.0), 75.0, Color::CYAN);
+    // A ray going around the circle
+    gizmos.ray_2d(
+        Vec2::new(150.0, 0.0),
+        Vec2::from_angle(t * PI / 2.0) * 75.0,
+        Color::RED,
+    );
+}
@@ -0,0 +1,131 @@
+//! Lights sprites with 2d point lights, shading sprites with a normal map and casting shadows
+//! from light occluders.
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(move_light)
+        .run();
+}
+
+#[derive(Component)]
+struct MovingLight;
+
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // The ambient light enables the 2d lighting for the camera
+    commands.spawn((
+        Camera2dBundle::default(),
+        AmbientLight2d {
+            brightness: 0.05,
+            ..default()
+        },
+    ));
+
+    // The floor, lit without a normal map
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.6, 0.6, 0.7),
+            custom_size: Some(Vec2::new(1200.0, 800.0)),
+            ..default()
+        },
+        ..default()
+    });
+
+    // Domes, shaded by the lights with their normal map
+    let dome_normal_map = images.add(dome_normal_map(64));
+    for x in [-300.0, -100.0, 100.0, 300.0] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.9, 0.8, 0.5),
+                    custom_size: Some(Vec2::splat(80.0)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, 150.0, 1.0),
+                ..default()
+            },
+            SpriteNormalMap(dome_normal_map.clone()),
+        ));
+    }
+
+    // Walls, casting shadows
+    for (x, y, size) in [
+        (-200.0, -100.0, Vec2::new(40.0, 160.0)),
+        (200.0, -150.0, Vec2::new(160.0, 40.0)),
+    ] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.3, 0.3, 0.3),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, 1.0),
+                ..default()
+            },
+            LightOccluder2d::rectangle(size),
+        ));
+    }
+
+    commands.spawn((
+        PointLight2dBundle {
+            point_light: PointLight2d {
+                radius: 500.0,
+                shadows_enabled: true,
+                ..default()
+            },
+            ..default()
+        },
+        MovingLight,
+    ));
+    commands.spawn(PointLight2dBundle {
+        point_light: PointLight2d {
+            color: Color::rgb(1.0, 0.3, 0.1),
+            intensity: 0.8,
+            radius: 300.0,
+            ..default()
+        },
+        transform: Transform::from_xyz(450.0, -250.0, 0.0),
+        ..default()
+    });
+}
+
+/// Creates the normal map of a half sphere, the normals pointing out of its surface.
+fn dome_normal_map(size: u32) -> Image {
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            // The rows of an image go down, while the green channel of a normal map points up
+            let position =
+                Vec2::new(x as f32 + 0.5, size as f32 - y as f32 - 0.5) / size as f32 * 2.0 - 1.0;
+            let normal = position.extend((1.0 - position.length_squared()).max(0.0).sqrt());
+            let alpha = if position.length() <= 1.0 { 255 } else { 0 };
+            let encoded = (normal.normalize() * 0.5 + 0.5) * 255.0;
+            data.extend([encoded.x as u8, encoded.y as u8, encoded.z as u8, alpha]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        // Normal maps are linear
+        TextureFormat::Rgba8Unorm,
+    )
+}
+
+fn move_light(time: Res<Time>, mut lights: Query<&mut Transform, With<MovingLight>>) {
+    let t = time.elapsed_seconds() * 0.5;
+    for mut transform in &mut lights {
+        transform.translation = Vec3::new(t.cos() * 400.0, t.sin() * 250.0, 0.0);
+    }
+}
@@ -0,0 +1,131 @@
+//! Draws debug lines with [`Gizmos`], to visualize the bounding boxes of meshes and a raycast
+//! without creating meshes for them.
+
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, render::primitives::Aabb};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .add_system(draw_gizmos)
+        .add_system(update_config)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotate;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 3.0, 7.0).looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 10.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+            transform: Transform::from_xyz(-1.5, 0.75, 0.0),
+            ..default()
+        },
+        Rotate,
+    ));
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Torus::default())),
+            material: materials.add(Color::rgb(0.6, 0.7, 0.8).into()),
+            transform: Transform::from_xyz(1.5, 0.75, 0.0),
+            ..default()
+        },
+        Rotate,
+    ));
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn rotate(mut query: Query<&mut Transform, With<Rotate>>, time: Res<Time>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() / 2.0);
+        transform.rotate_x(time.delta_seconds() / 3.0);
+    }
+}
+
+fn draw_gizmos(
+    mut gizmos: Gizmos,
+    meshes: Query<(&Aabb, &GlobalTransform), With<Rotate>>,
+    time: Res<Time>,
+) {
+    // The bounding boxes of the meshes, which are used for their frustum culling
+    for (aabb, transform) in &meshes {
+        gizmos.aabb(aabb, transform, Color::YELLOW);
+        gizmos.axes(*transform, 1.0);
+    }
+
+    // A ray sweeping over the ground, and a sphere where it hits the ground
+    let angle = time.elapsed_seconds() * PI / 4.0;
+    let start = Vec3::new(0.0, 3.0, 0.0);
+    let hit = Vec3::new(3.0 * angle.cos(), 0.0, 3.0 * angle.sin());
+    gizmos.ray(start, hit - start, Color::RED);
+    gizmos.sphere(hit, Quat::IDENTITY, 0.2, Color::RED);
+    gizmos.circle(Vec3::ZERO, Vec3::Y, 3.0, Color::WHITE);
+}
+
+fn update_config(
+    mut config: ResMut<GizmoConfig>,
+    mut text: Query<&mut Text>,
+    keycode: Res<Input<KeyCode>>,
+) {
+    if keycode.just_pressed(KeyCode::D) {
+        config.depth_test = !config.depth_test;
+    }
+    if keycode.just_pressed(KeyCode::Space) {
+        config.enabled = !config.enabled;
+    }
+
+    text.single_mut().sections[0].value = format!(
+        "Gizmos: {} (Space)\nDepth test: {} (D)",
+        if config.enabled { "on" } else { "off" },
+        if config.depth_test { "on" } else { "off" },
+    );
+}
@@ -20,7 +20,7 @@ fn main() {
 #[derive(Component)]
 struct Shape;
 
-const X_EXTENT: f32 = 14.5;
+const X_EXTENT: f32 = 18.0;
 
 fn setup(
     mut commands: Commands,
@@ -36,9 +36,11 @@ fn setup(
     let shapes = [
         meshes.add(shape::Cube::default().into()),
         meshes.add(shape::Box::default().into()),
+        meshes.add(shape::RoundedBox::default().into()),
         meshes.add(shape::Capsule::default().into()),
         meshes.add(shape::Torus::default().into()),
         meshes.add(shape::Cylinder::default().into()),
+        meshes.add(shape::Cone::default().into()),
         meshes.add(shape::Icosphere::default().try_into().unwrap()),
         meshes.add(shape::UVSphere::default().into()),
     ];
@@ -1,6 +1,9 @@
 //! Illustrates bloom configuration using HDR and emissive materials.
 
-use bevy::{core_pipeline::bloom::BloomSettings, prelude::*};
+use bevy::{
+    core_pipeline::bloom::{BloomCompositeMode, BloomSettings},
+    prelude::*,
+};
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
@@ -114,6 +117,10 @@ fn update_bloom_settings(
     text.push_str(&format!("Knee: {}\n", bloom_settings.knee));
     text.push_str(&format!("Scale: {}\n", bloom_settings.scale));
     text.push_str(&format!("Intensity: {}\n", bloom_settings.intensity));
+    text.push_str(&format!(
+        "Composite mode: {:?}\n",
+        bloom_settings.composite_mode
+    ));
 
     text.push_str("\n\n");
 
@@ -123,6 +130,7 @@ fn update_bloom_settings(
     text.push_str("E/R - Knee\n");
     text.push_str("A/S - Scale\n");
     text.push_str("D/F - Intensity\n");
+    text.push_str("C   - Composite mode\n");
 
     let dt = time.delta_seconds();
 
@@ -153,6 +161,13 @@ fn update_bloom_settings(
     if keycode.pressed(KeyCode::F) {
         bloom_settings.intensity += dt;
     }
+
+    if keycode.just_pressed(KeyCode::C) {
+        bloom_settings.composite_mode = match bloom_settings.composite_mode {
+            BloomCompositeMode::EnergyConserving => BloomCompositeMode::Additive,
+            BloomCompositeMode::Additive => BloomCompositeMode::EnergyConserving,
+        };
+    }
 }
 
 #[derive(Component)]
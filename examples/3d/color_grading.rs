@@ -0,0 +1,167 @@
+//! Illustrates color grading a camera, with its exposure, contrast and saturation, and a 3D
+//! lookup table remapping its colors.
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::ColorGrading,
+    },
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(update_color_grading)
+        .run();
+}
+
+/// The color grading of each time of day, and the key selecting it.
+#[derive(Resource)]
+struct ColorGradingPresets(Vec<(KeyCode, &'static str, ColorGrading)>);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    let presets = vec![
+        (KeyCode::Key1, "Day", ColorGrading::default()),
+        (
+            KeyCode::Key2,
+            "Night",
+            ColorGrading {
+                exposure: -2.0,
+                contrast: 1.2,
+                saturation: 0.4,
+                lut: Some(images.add(tint_lut(Vec3::new(0.7, 0.85, 1.2)))),
+            },
+        ),
+        (
+            KeyCode::Key3,
+            "Underwater",
+            ColorGrading {
+                exposure: -0.5,
+                contrast: 0.8,
+                saturation: 0.7,
+                lut: Some(images.add(tint_lut(Vec3::new(0.5, 1.0, 1.1)))),
+            },
+        ),
+    ];
+
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                // The lookup table is applied by the tonemapping node, which requires HDR
+                hdr: true,
+                ..default()
+            },
+            transform: Transform::from_xyz(-2.5, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        presets[0].2.clone(),
+    ));
+    commands.insert_resource(ColorGradingPresets(presets));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 10.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    let colors = [Color::RED, Color::GREEN, Color::BLUE, Color::YELLOW];
+    for (i, color) in colors.into_iter().enumerate() {
+        commands.spawn(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            material: materials.add(color.into()),
+            transform: Transform::from_xyz(i as f32 * 1.5 - 2.25, 0.5, 0.0),
+            ..default()
+        });
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::ZYX, 0.0, 0.5, -0.8)),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+/// Creates a lookup table multiplying the colors by `tint`.
+///
+/// Lookup tables are usually authored in an image editor instead, by grading a screenshot along
+/// with an identity lookup table.
+fn tint_lut(tint: Vec3) -> Image {
+    const SIZE: u32 = 16;
+    let mut data = Vec::with_capacity((SIZE * SIZE * SIZE * 4) as usize);
+    for b in 0..SIZE {
+        for g in 0..SIZE {
+            for r in 0..SIZE {
+                let color = Vec3::new(r as f32, g as f32, b as f32) / (SIZE - 1) as f32 * tint;
+                let color = color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+                data.extend([color.x as u8, color.y as u8, color.z as u8, 255]);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: SIZE,
+        },
+        TextureDimension::D3,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn update_color_grading(
+    mut camera: Query<&mut ColorGrading>,
+    mut text: Query<&mut Text>,
+    presets: Res<ColorGradingPresets>,
+    keycode: Res<Input<KeyCode>>,
+) {
+    let mut color_grading = camera.single_mut();
+    for (key, _, preset) in &presets.0 {
+        if keycode.just_pressed(*key) {
+            *color_grading = preset.clone();
+        }
+    }
+
+    let mut text = text.single_mut();
+    let text = &mut text.sections[0].value;
+    text.clear();
+    for (index, (_, name, preset)) in presets.0.iter().enumerate() {
+        let selected = if preset.lut == color_grading.lut {
+            "(*)"
+        } else {
+            "( )"
+        };
+        text.push_str(&format!("{selected} {name} ({})\n", index + 1));
+    }
+}
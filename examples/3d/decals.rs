@@ -0,0 +1,102 @@
+//! Projects decals onto the meshes of a scene, without modifying them: a logo blended over the
+//! corner of a cube and the ground, and dark splats multiplied with the surfaces under them.
+
+use std::f32::consts::PI;
+
+use bevy::{core_pipeline::prepass::DepthPrepass, prelude::*};
+
+fn main() {
+    App::new()
+        // The depth prepass the decals are projected with isn't multisampled
+        .insert_resource(Msaa { samples: 1 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(move_logo)
+        .run();
+}
+
+#[derive(Component)]
+struct Logo;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut decal_materials: ResMut<Assets<DecalMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 10.0 })),
+        material: materials.add(Color::rgb(0.8, 0.8, 0.75).into()),
+        ..default()
+    });
+    let cube = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    for (x, z) in [(0.0, 0.0), (-2.0, -1.5), (1.5, -2.0)] {
+        commands.spawn(PbrBundle {
+            mesh: cube.clone(),
+            material: materials.add(Color::rgb(0.4, 0.5, 0.7).into()),
+            transform: Transform::from_xyz(x, 0.5, z),
+            ..default()
+        });
+    }
+
+    // The logo is projected downwards, onto the top of the cube and the ground around it, and
+    // fades out on the sides of the cube, which are parallel to its projection
+    commands.spawn((
+        DecalBundle {
+            material: decal_materials.add(asset_server.load("branding/icon.png").into()),
+            transform: Transform::from_xyz(0.5, 0.5, 0.5).with_scale(Vec3::new(2.0, 1.5, 2.0)),
+            ..default()
+        },
+        Logo,
+    ));
+
+    // Splats projected sideways onto the cubes, and downwards onto the ground
+    let splat = decal_materials.add(DecalMaterial {
+        base_color: Color::rgb(0.4, 0.05, 0.05),
+        blend_mode: DecalBlendMode::Multiply,
+        ..default()
+    });
+    for transform in [
+        Transform::from_xyz(-2.0, 0.6, -1.0)
+            .with_rotation(Quat::from_rotation_x(PI / 2.0))
+            .with_scale(Vec3::splat(0.6)),
+        Transform::from_xyz(1.0, 0.4, -2.0)
+            .with_rotation(Quat::from_rotation_z(PI / 2.0))
+            .with_scale(Vec3::splat(0.5)),
+        Transform::from_xyz(-1.0, 0.0, 1.5).with_scale(Vec3::new(1.2, 0.5, 0.8)),
+    ] {
+        commands.spawn(DecalBundle {
+            material: splat.clone(),
+            transform,
+            ..default()
+        });
+    }
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-3.0, 4.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // The decals are only drawn by the cameras with a depth prepass
+        DepthPrepass,
+    ));
+}
+
+fn move_logo(time: Res<Time>, mut logos: Query<&mut Transform, With<Logo>>) {
+    for mut transform in &mut logos {
+        let t = time.elapsed_seconds() * 0.5;
+        transform.translation.x = t.cos();
+        transform.translation.z = t.sin();
+        transform.rotation = Quat::from_rotation_y(-t);
+    }
+}
@@ -0,0 +1,106 @@
+//! Illustrates depth of field, which blurs the parts of the scene that are out of focus.
+
+use bevy::{
+    core_pipeline::dof::{DepthOfFieldBundle, DepthOfFieldSettings},
+    prelude::*,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(update_dof_settings)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 1.0, 4.0)
+                .looking_at(Vec3::new(0.0, 0.5, -4.0), Vec3::Y),
+            ..default()
+        },
+        DepthOfFieldBundle::default(),
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 50.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    // A row of cubes going away from the camera, one every two meters
+    let mesh = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    for i in 0..10 {
+        let x = if i % 2 == 0 { -1.0 } else { 1.0 };
+        commands.spawn(PbrBundle {
+            mesh: mesh.clone(),
+            material: materials.add(Color::hsl(i as f32 * 36.0, 0.8, 0.5).into()),
+            transform: Transform::from_xyz(x, 0.5, 2.0 - i as f32 * 2.0),
+            ..default()
+        });
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::ZYX, 0.0, 0.5, -0.8)),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn update_dof_settings(
+    mut camera: Query<&mut DepthOfFieldSettings>,
+    mut text: Query<&mut Text>,
+    keycode: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let mut settings = camera.single_mut();
+    let dt = time.delta_seconds();
+
+    if keycode.pressed(KeyCode::Up) {
+        settings.focal_distance += 4.0 * dt;
+    }
+    if keycode.pressed(KeyCode::Down) {
+        settings.focal_distance = (settings.focal_distance - 4.0 * dt).max(0.5);
+    }
+    if keycode.pressed(KeyCode::Right) {
+        settings.aperture_f_stops *= 1.0 + dt;
+    }
+    if keycode.pressed(KeyCode::Left) {
+        settings.aperture_f_stops = (settings.aperture_f_stops / (1.0 + dt)).max(0.1);
+    }
+
+    let mut text = text.single_mut();
+    text.sections[0].value = format!(
+        "Focal distance: {:.1}m (Up/Down)\nAperture: f/{:.2} (Left/Right)",
+        settings.focal_distance, settings.aperture_f_stops,
+    );
+}
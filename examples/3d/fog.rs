@@ -0,0 +1,137 @@
+//! Illustrates fog, which fades the meshes into the horizon as they get further from the camera.
+
+use bevy::{pbr::HeightFog, prelude::*};
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(FOG_COLOR))
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(update_fog_settings)
+        .run();
+}
+
+const FOG_COLOR: Color = Color::rgb(0.6, 0.65, 0.75);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 2.0, 8.0)
+                .looking_at(Vec3::new(0.0, 1.0, -10.0), Vec3::Y),
+            ..default()
+        },
+        FogSettings {
+            color: FOG_COLOR,
+            falloff: FogFalloff::Linear {
+                start: 5.0,
+                end: 40.0,
+            },
+            ..default()
+        },
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 200.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    // Rows of pillars going away from the camera, on both sides of a path
+    let mesh = meshes.add(Mesh::from(shape::Box::new(1.0, 4.0, 1.0)));
+    let material = materials.add(Color::rgb(0.8, 0.7, 0.6).into());
+    for i in 0..20 {
+        for x in [-3.0, 3.0] {
+            commands.spawn(PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(x, 2.0, -(i as f32) * 4.0),
+                ..default()
+            });
+        }
+    }
+    // A beacon which stays visible through the fog
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 2.0,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(1.0, 0.3, 0.1),
+            unlit: true,
+            fog_enabled: false,
+            ..default()
+        }),
+        transform: Transform::from_xyz(0.0, 8.0, -80.0),
+        ..default()
+    });
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::ZYX, 0.0, 0.5, -0.8)),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn update_fog_settings(
+    mut camera: Query<&mut FogSettings>,
+    mut text: Query<&mut Text>,
+    keycode: Res<Input<KeyCode>>,
+) {
+    let mut fog_settings = camera.single_mut();
+
+    if keycode.just_pressed(KeyCode::Space) {
+        fog_settings.falloff = match fog_settings.falloff {
+            FogFalloff::Linear { .. } => FogFalloff::Exponential { density: 0.05 },
+            FogFalloff::Exponential { .. } => FogFalloff::ExponentialSquared { density: 0.04 },
+            FogFalloff::ExponentialSquared { .. } => FogFalloff::Linear {
+                start: 5.0,
+                end: 40.0,
+            },
+        };
+    }
+    if keycode.just_pressed(KeyCode::H) {
+        fog_settings.height_fog = match fog_settings.height_fog {
+            Some(_) => None,
+            None => Some(HeightFog::default()),
+        };
+    }
+
+    let mut text = text.single_mut();
+    text.sections[0].value = format!(
+        "Falloff: {:?} (Space)\nHeight fog: {} (H)",
+        fog_settings.falloff,
+        if fog_settings.height_fog.is_some() {
+            "on"
+        } else {
+            "off"
+        },
+    );
+}
@@ -1,9 +1,14 @@
-//! This examples compares MSAA (Multi-Sample Anti-Aliasing) and FXAA (Fast Approximate Anti-Aliasing).
+//! This examples compares MSAA (Multi-Sample Anti-Aliasing), FXAA (Fast Approximate Anti-Aliasing)
+//! and TAA (Temporal Anti-Aliasing).
 
 use std::f32::consts::PI;
 
 use bevy::{
-    core_pipeline::fxaa::{Fxaa, Sensitivity},
+    core_pipeline::{
+        fxaa::{Fxaa, Sensitivity},
+        taa::TemporalAntiAliasBundle,
+    },
+    pbr::CascadeShadowConfigBuilder,
     prelude::*,
     render::{
         render_resource::{Extent3d, SamplerDescriptor, TextureDimension, TextureFormat},
@@ -33,6 +38,7 @@ fn setup(
     println!("1 - NO AA");
     println!("2 - MSAA 4");
     println!("3 - FXAA (default)");
+    println!("4 - TAA");
 
     println!("Threshold:");
     println!("6 - LOW");
@@ -70,18 +76,8 @@ fn setup(
     });
 
     // light
-    const HALF_SIZE: f32 = 2.0;
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
-            shadow_projection: OrthographicProjection {
-                left: -HALF_SIZE,
-                right: HALF_SIZE,
-                bottom: -HALF_SIZE,
-                top: HALF_SIZE,
-                near: -10.0 * HALF_SIZE,
-                far: 10.0 * HALF_SIZE,
-                ..default()
-            },
             shadows_enabled: true,
             ..default()
         },
@@ -91,6 +87,12 @@ fn setup(
             PI * -0.15,
             PI * -0.15,
         )),
+        cascade_shadow_config: CascadeShadowConfigBuilder {
+            maximum_distance: 3.0,
+            first_cascade_far_bound: 0.9,
+            ..default()
+        }
+        .into(),
         ..default()
     });
 
@@ -108,17 +110,34 @@ fn setup(
         .insert(Fxaa::default());
 }
 
-fn toggle_fxaa(keys: Res<Input<KeyCode>>, mut query: Query<&mut Fxaa>, mut msaa: ResMut<Msaa>) {
+fn toggle_fxaa(
+    keys: Res<Input<KeyCode>>,
+    mut query: Query<(Entity, &mut Fxaa)>,
+    mut msaa: ResMut<Msaa>,
+    mut commands: Commands,
+) {
     let set_no_aa = keys.just_pressed(KeyCode::Key1);
     let set_msaa = keys.just_pressed(KeyCode::Key2);
     let set_fxaa = keys.just_pressed(KeyCode::Key3);
+    let set_taa = keys.just_pressed(KeyCode::Key4);
     let fxaa_low = keys.just_pressed(KeyCode::Key6);
     let fxaa_med = keys.just_pressed(KeyCode::Key7);
     let fxaa_high = keys.just_pressed(KeyCode::Key8);
     let fxaa_ultra = keys.just_pressed(KeyCode::Key9);
     let fxaa_extreme = keys.just_pressed(KeyCode::Key0);
     let set_fxaa = set_fxaa | fxaa_low | fxaa_med | fxaa_high | fxaa_ultra | fxaa_extreme;
-    for mut fxaa in &mut query {
+    for (entity, mut fxaa) in &mut query {
+        if set_no_aa | set_msaa | set_fxaa {
+            commands.entity(entity).remove::<TemporalAntiAliasBundle>();
+        }
+        if set_taa {
+            fxaa.enabled = false;
+            msaa.samples = 1;
+            commands
+                .entity(entity)
+                .insert(TemporalAntiAliasBundle::default());
+            info!("TAA");
+        }
         if set_msaa {
             fxaa.enabled = false;
             msaa.samples = 4;
@@ -0,0 +1,111 @@
+//! Highlights the mesh under the cursor, found by reading back the entity the [`PickingPass`] of
+//! the camera draws there.
+
+use bevy::{
+    pbr::{GpuPicking, Pick, PickingPass},
+    prelude::*,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .add_system(highlight_hovered)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotate;
+
+#[derive(Resource)]
+struct Materials {
+    normal: Handle<StandardMaterial>,
+    hovered: Handle<StandardMaterial>,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 3.0, 7.0)
+                .looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
+            ..default()
+        },
+        PickingPass,
+    ));
+
+    let normal = materials.add(Color::rgb(0.8, 0.7, 0.6).into());
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 10.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    let shapes = [
+        Mesh::from(shape::Torus::default()),
+        Mesh::from(shape::Cube::default()),
+        Mesh::from(shape::Capsule::default()),
+    ];
+    for (i, mesh) in shapes.into_iter().enumerate() {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(mesh),
+                material: normal.clone(),
+                transform: Transform::from_xyz(i as f32 * 2.5 - 2.5, 1.0, 0.0),
+                ..default()
+            },
+            Rotate,
+        ));
+    }
+    commands.insert_resource(Materials {
+        normal,
+        hovered: materials.add(Color::rgb(1.0, 0.4, 0.2).into()),
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+}
+
+fn rotate(mut query: Query<&mut Transform, With<Rotate>>, time: Res<Time>) {
+    for mut transform in &mut query {
+        transform.rotate_x(time.delta_seconds() / 3.0);
+    }
+}
+
+fn highlight_hovered(
+    camera: Query<Entity, With<PickingPass>>,
+    windows: Res<Windows>,
+    mut picking: ResMut<GpuPicking>,
+    // The pick waiting to be read back, if any
+    mut pending: Local<Option<Pick>>,
+    materials: Res<Materials>,
+    mut shapes: Query<(Entity, &mut Handle<StandardMaterial>), With<Rotate>>,
+) {
+    if let Some(hovered) = pending.as_ref().and_then(|pick| pick.try_get()) {
+        for (entity, mut material) in &mut shapes {
+            *material = if Some(entity) == hovered {
+                materials.hovered.clone()
+            } else {
+                materials.normal.clone()
+            };
+        }
+        *pending = None;
+    }
+
+    // Picks take a frame or two to be read back, so only one is requested at a time
+    if pending.is_none() {
+        if let Some(cursor_position) = windows.get_primary().and_then(|w| w.cursor_position()) {
+            *pending = Some(picking.pick(camera.single(), cursor_position));
+        }
+    }
+}
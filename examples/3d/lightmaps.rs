@@ -0,0 +1,142 @@
+//! Lights a floor with a lightmap baked into a texture when the example starts, sampled with the
+//! second set of UVs of its mesh, so the soft shadow of the cube standing on it costs nothing to
+//! render.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        // The ambient light lights the cube, which isn't lightmapped
+        .insert_resource(AmbientLight {
+            brightness: 0.3,
+            ..default()
+        })
+        .add_startup_system(setup)
+        .add_system(orbit_camera)
+        .run();
+}
+
+const FLOOR_SIZE: f32 = 10.0;
+const LIGHTMAP_SIZE: u32 = 128;
+const CUBE_SIZE: f32 = 1.5;
+/// The spherical light baked into the lightmap.
+const LIGHT_POSITION: Vec3 = Vec3::new(-2.5, 3.0, -1.5);
+const LIGHT_RADIUS: f32 = 0.6;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // The UVs of a plane already unwrap it without overlaps, so they are reused for the lightmap
+    let mut floor = Mesh::from(shape::Plane { size: FLOOR_SIZE });
+    let uvs = floor.attribute(Mesh::ATTRIBUTE_UV_0).unwrap().clone();
+    floor.insert_attribute(Mesh::ATTRIBUTE_UV_1, uvs);
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(floor),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.8, 0.8, 0.75),
+            perceptual_roughness: 0.9,
+            lightmap_texture: Some(images.add(bake_floor_lightmap())),
+            lightmap_intensity: 1.2,
+            ..default()
+        }),
+        ..default()
+    });
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: CUBE_SIZE })),
+        material: materials.add(Color::rgb(0.8, 0.3, 0.2).into()),
+        transform: Transform::from_xyz(0.0, CUBE_SIZE / 2.0, 0.0),
+        ..default()
+    });
+
+    // The baked light is only drawn, it isn't a light of the scene
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: LIGHT_RADIUS,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            emissive: Color::rgb(1.0, 0.95, 0.8),
+            ..default()
+        }),
+        transform: Transform::from_translation(LIGHT_POSITION),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle::default());
+}
+
+/// A tiny light baker: the light of the spherical light reaching each texel of the floor, from
+/// points spread over the light so that the shadow of the cube is soft.
+fn bake_floor_lightmap() -> Image {
+    // Points spread over the light with the golden angle
+    const LIGHT_SAMPLES: u32 = 32;
+    let light_samples: Vec<Vec3> = (0..LIGHT_SAMPLES)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / LIGHT_SAMPLES as f32;
+            let angle = i as f32 * PI * (3.0 - 5f32.sqrt());
+            let radius = (1.0 - y * y).sqrt();
+            LIGHT_POSITION + Vec3::new(angle.cos() * radius, y, angle.sin() * radius) * LIGHT_RADIUS
+        })
+        .collect();
+
+    let mut data = Vec::with_capacity((LIGHTMAP_SIZE * LIGHTMAP_SIZE * 4) as usize);
+    for i in 0..LIGHTMAP_SIZE * LIGHTMAP_SIZE {
+        // The UV of the texel on the plane, whose V goes from its +Z edge to its -Z edge
+        let u = ((i % LIGHTMAP_SIZE) as f32 + 0.5) / LIGHTMAP_SIZE as f32;
+        let v = ((i / LIGHTMAP_SIZE) as f32 + 0.5) / LIGHTMAP_SIZE as f32;
+        let position = Vec3::new((u - 0.5) * FLOOR_SIZE, 0.0, (0.5 - v) * FLOOR_SIZE);
+
+        let mut light = 0.0;
+        for sample in &light_samples {
+            let to_light = *sample - position;
+            if !hits_cube(position, to_light) {
+                let distance_squared = to_light.length_squared();
+                light += to_light.y / distance_squared.sqrt() / distance_squared;
+            }
+        }
+        let light = light * 14.0 / LIGHT_SAMPLES as f32;
+        let [r, g, b] = [1.0, 0.92, 0.8].map(|tint: f32| ((light * tint).min(1.0) * 255.0) as u8);
+        data.extend([r, g, b, 255]);
+    }
+
+    Image::new(
+        Extent3d {
+            width: LIGHTMAP_SIZE,
+            height: LIGHTMAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        // The light is stored in linear color, unlike the colors of the other textures
+        TextureFormat::Rgba8Unorm,
+    )
+}
+
+/// Whether the segment from `origin` to `origin + direction` goes through the cube.
+fn hits_cube(origin: Vec3, direction: Vec3) -> bool {
+    let half_size = CUBE_SIZE / 2.0;
+    let min = Vec3::new(-half_size, 0.0, -half_size);
+    let max = Vec3::new(half_size, CUBE_SIZE, half_size);
+    let (t0, t1) = ((min - origin) / direction, (max - origin) / direction);
+    let enter = t0.min(t1).max_element();
+    let exit = t0.max(t1).min_element();
+    enter <= exit && exit > 0.0 && enter < 1.0
+}
+
+fn orbit_camera(time: Res<Time>, mut cameras: Query<&mut Transform, With<Camera>>) {
+    let angle = time.elapsed_seconds() * 0.2;
+    for mut transform in &mut cameras {
+        *transform = Transform::from_xyz(angle.cos() * 9.0, 5.0, angle.sin() * 9.0)
+            .looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y);
+    }
+}
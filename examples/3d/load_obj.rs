@@ -0,0 +1,38 @@
+//! Loads and renders an OBJ file, with the materials of its MTL file, as a scene.
+
+use bevy::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotates;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(1.2, 1.2, 1.8).looking_at(Vec3::new(0.0, 0.3, 0.0), Vec3::Y),
+        ..default()
+    });
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 0.5).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    commands.spawn((
+        SceneBundle {
+            scene: asset_server.load("models/pyramid/pyramid.obj#Scene"),
+            ..default()
+        },
+        Rotates,
+    ));
+}
+
+fn rotate(mut query: Query<&mut Transform, With<Rotates>>, time: Res<Time>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds() * 0.5);
+    }
+}
@@ -0,0 +1,79 @@
+//! Switches the meshes of spheres between levels of detail with a [`Lod`], depending on how much
+//! of the screen they cover, and cross-fades between the levels as the camera moves.
+
+use bevy::{
+    prelude::*,
+    render::mesh::lod::{Lod, LodLevel, LodThreshold},
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(move_camera)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // From the most detailed to the least detailed, the last one being used when the spheres
+    // cover less than a tenth of the height of the screen
+    let levels: Vec<_> = [(5, 0.4), (2, 0.1), (0, 0.0)]
+        .into_iter()
+        .map(|(subdivisions, coverage)| LodLevel {
+            mesh: meshes.add(
+                Mesh::try_from(shape::Icosphere {
+                    radius: 1.0,
+                    subdivisions,
+                })
+                .unwrap(),
+            ),
+            threshold: LodThreshold::ScreenCoverage(coverage),
+        })
+        .collect();
+
+    let material = materials.add(Color::rgb(0.8, 0.7, 0.6).into());
+    for x in -2..=2 {
+        for z in -4..=0 {
+            commands.spawn((
+                PbrBundle {
+                    mesh: levels[0].mesh.clone(),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(x as f32 * 3.0, 1.0, z as f32 * 3.0),
+                    ..default()
+                },
+                Lod {
+                    levels: levels.clone(),
+                    cross_fade_duration: 0.5,
+                },
+            ));
+        }
+    }
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 40.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::XYZ, -1.0, 0.6, 0.0)),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle::default());
+}
+
+/// Moves the camera towards the spheres and away from them.
+fn move_camera(time: Res<Time>, mut cameras: Query<&mut Transform, With<Camera>>) {
+    let distance = 12.0 + 10.0 * (time.elapsed_seconds() * 0.4).sin();
+    for mut transform in &mut cameras {
+        *transform = Transform::from_xyz(0.0, distance * 0.4, distance)
+            .looking_at(Vec3::new(0.0, 0.0, -6.0), Vec3::Y);
+    }
+}
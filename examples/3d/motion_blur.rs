@@ -0,0 +1,131 @@
+//! Illustrates motion blur, which blurs the meshes along their motion on screen.
+
+use bevy::{
+    core_pipeline::motion_blur::{MotionBlurBundle, MotionBlurSettings},
+    prelude::*,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(orbit_spheres)
+        .add_system(update_motion_blur_settings)
+        .run();
+}
+
+#[derive(Component)]
+struct Orbit {
+    radius: f32,
+    speed: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 6.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        MotionBlurBundle::default(),
+    ));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 20.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    let mesh = meshes.add(
+        shape::Icosphere {
+            radius: 0.5,
+            subdivisions: 4,
+        }
+        .try_into()
+        .unwrap(),
+    );
+    for i in 0..4 {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(Color::hsl(i as f32 * 90.0, 0.8, 0.5).into()),
+                ..default()
+            },
+            Orbit {
+                radius: 1.5 + i as f32,
+                speed: 4.0 - i as f32 * 0.5,
+            },
+        ));
+    }
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::ZYX, 0.0, 0.5, -0.8)),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn orbit_spheres(mut spheres: Query<(&mut Transform, &Orbit)>, time: Res<Time>) {
+    for (mut transform, orbit) in &mut spheres {
+        let angle = time.elapsed_seconds() * orbit.speed;
+        transform.translation =
+            Vec3::new(angle.cos() * orbit.radius, 0.5, angle.sin() * orbit.radius);
+    }
+}
+
+fn update_motion_blur_settings(
+    mut camera: Query<&mut MotionBlurSettings>,
+    mut text: Query<&mut Text>,
+    keycode: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let mut settings = camera.single_mut();
+    let dt = time.delta_seconds();
+
+    if keycode.pressed(KeyCode::Up) {
+        settings.shutter_angle = (settings.shutter_angle + dt).min(1.0);
+    }
+    if keycode.pressed(KeyCode::Down) {
+        settings.shutter_angle = (settings.shutter_angle - dt).max(0.0);
+    }
+    if keycode.just_pressed(KeyCode::Right) {
+        settings.samples += 1;
+    }
+    if keycode.just_pressed(KeyCode::Left) {
+        settings.samples = settings.samples.saturating_sub(1).max(1);
+    }
+
+    let mut text = text.single_mut();
+    text.sections[0].value = format!(
+        "Shutter angle: {:.0}° (Up/Down)\nSamples: {} (Left/Right)",
+        settings.shutter_angle * 360.0,
+        settings.samples,
+    );
+}
@@ -0,0 +1,104 @@
+//! Simulates particles on the GPU with [`ParticleEmitter`]s: a fountain falling back onto the
+//! ground, and smoke fading out softly where it crosses the meshes around it.
+
+use std::f32::consts::PI;
+
+use bevy::{core_pipeline::prepass::DepthPrepass, prelude::*};
+
+fn main() {
+    App::new()
+        // The depth prepass soft particles are faded with isn't multisampled
+        .insert_resource(Msaa { samples: 1 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(rotate_fountain)
+        .run();
+}
+
+#[derive(Component)]
+struct Fountain;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 20.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+        transform: Transform::from_xyz(2.5, 0.5, 0.0),
+        ..default()
+    });
+
+    // The fountain is tilted, and rotated by `rotate_fountain`, while gravity stays downwards
+    commands.spawn((
+        ParticleEmitterBundle {
+            emitter: ParticleEmitter {
+                capacity: 4096,
+                rate: 1024.0,
+                lifetime: 2.5,
+                velocity: Vec3::new(0.0, 6.0, 0.0),
+                velocity_randomness: 0.8,
+                acceleration: Vec3::new(0.0, -9.81, 0.0),
+                start_size: 0.08,
+                end_size: 0.04,
+                start_color: Color::rgb(0.4, 0.7, 1.0),
+                end_color: Color::rgba(0.8, 0.9, 1.0, 0.0),
+                ..default()
+            },
+            transform: Transform::from_xyz(-1.5, 0.0, 0.0)
+                .with_rotation(Quat::from_rotation_z(PI / 12.0)),
+            ..default()
+        },
+        Fountain,
+    ));
+
+    // Large, slow particles behind the cube, softened where they cross it and the ground
+    commands.spawn(ParticleEmitterBundle {
+        emitter: ParticleEmitter {
+            capacity: 256,
+            rate: 48.0,
+            lifetime: 5.0,
+            spawn_radius: 0.5,
+            velocity: Vec3::new(0.2, 0.6, 0.0),
+            velocity_randomness: 0.2,
+            start_size: 0.8,
+            end_size: 2.0,
+            start_color: Color::rgba(0.5, 0.5, 0.5, 0.5),
+            end_color: Color::rgba(0.8, 0.8, 0.8, 0.0),
+            soft_distance: 0.5,
+            ..default()
+        },
+        transform: Transform::from_xyz(3.0, 0.2, -1.0),
+        ..default()
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 4.0, 10.0)
+                .looking_at(Vec3::new(0.0, 2.0, 0.0), Vec3::Y),
+            ..default()
+        },
+        DepthPrepass,
+    ));
+}
+
+fn rotate_fountain(time: Res<Time>, mut fountains: Query<&mut Transform, With<Fountain>>) {
+    for mut transform in &mut fountains {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
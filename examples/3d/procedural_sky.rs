@@ -0,0 +1,149 @@
+//! Illustrates a procedural sky, which follows the direction of the sun through the day and can
+//! light the scene as an environment map.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(move_sun)
+        .add_system(update_sky)
+        .run();
+}
+
+/// The elevation of the sun above the horizon, in radians.
+#[derive(Resource)]
+struct SunElevation(f32);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 1.0, 5.0)
+                .looking_at(Vec3::new(0.0, 1.5, 0.0), Vec3::Y),
+            ..default()
+        },
+        ProceduralSky {
+            bake_environment_map: true,
+            ..default()
+        },
+    ));
+    // The environment map of the sky replaces the ambient light
+    commands.insert_resource(AmbientLight {
+        brightness: 0.0,
+        ..default()
+    });
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 50.0 })),
+        material: materials.add(Color::rgb(0.4, 0.4, 0.4).into()),
+        ..default()
+    });
+    // Spheres from smooth metal to rough plastic, which reflect the sky more or less sharply
+    let mesh = meshes.add(Mesh::from(shape::UVSphere {
+        radius: 0.5,
+        ..default()
+    }));
+    for i in 0..5 {
+        let t = i as f32 / 4.0;
+        commands.spawn(PbrBundle {
+            mesh: mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                metallic: 1.0 - t,
+                perceptual_roughness: t.max(0.05),
+                ..default()
+            }),
+            transform: Transform::from_xyz(i as f32 * 1.2 - 2.4, 0.5, 0.0),
+            ..default()
+        });
+    }
+
+    commands.insert_resource(SunElevation(0.5));
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 100000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn move_sun(
+    mut sun_elevation: ResMut<SunElevation>,
+    mut light: Query<&mut Transform, With<DirectionalLight>>,
+    keycode: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    if keycode.pressed(KeyCode::Up) {
+        sun_elevation.0 = (sun_elevation.0 + 0.3 * dt).min(PI / 2.0);
+    }
+    if keycode.pressed(KeyCode::Down) {
+        sun_elevation.0 = (sun_elevation.0 - 0.3 * dt).max(-0.2);
+    }
+
+    // The sun rises behind the spheres, on the left of the camera
+    light.single_mut().rotation = Quat::from_euler(EulerRot::YXZ, 0.6, -sun_elevation.0, 0.0);
+}
+
+fn update_sky(
+    mut sky: Query<&mut ProceduralSky>,
+    mut text: Query<&mut Text>,
+    sun_elevation: Res<SunElevation>,
+    keycode: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let mut sky = sky.single_mut();
+    let dt = time.delta_seconds();
+
+    if keycode.pressed(KeyCode::Right) {
+        sky.turbidity = (sky.turbidity + 2.0 * dt).min(10.0);
+    }
+    if keycode.pressed(KeyCode::Left) {
+        sky.turbidity = (sky.turbidity - 2.0 * dt).max(2.0);
+    }
+    if keycode.just_pressed(KeyCode::Space) {
+        sky.bake_environment_map = !sky.bake_environment_map;
+    }
+
+    let mut text = text.single_mut();
+    text.sections[0].value = format!(
+        "Sun elevation: {:.0}° (Up/Down)\nTurbidity: {:.1} (Left/Right)\nEnvironment map: {} (Space)",
+        sun_elevation.0.to_degrees(),
+        sky.turbidity,
+        if sky.bake_environment_map {
+            "on"
+        } else {
+            "off"
+        },
+    );
+}
@@ -0,0 +1,89 @@
+//! Casts a ray from the cursor against the meshes of the scene with [`RayCast`], and draws where
+//! it hits them and the normal of their surface there.
+
+use bevy::{prelude::*, render::ray_cast::RayCast};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(rotate)
+        .add_system(draw_cursor_hit)
+        .run();
+}
+
+#[derive(Component)]
+struct Rotate;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 3.0, 7.0).looking_at(Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
+        ..default()
+    });
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 10.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..default()
+    });
+    let shapes = [
+        Mesh::from(shape::Torus::default()),
+        Mesh::try_from(shape::Icosphere::default()).unwrap(),
+        Mesh::from(shape::Cone::default()),
+    ];
+    for (i, mesh) in shapes.into_iter().enumerate() {
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(mesh),
+                material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+                transform: Transform::from_xyz(i as f32 * 2.5 - 2.5, 1.0, 0.0),
+                ..default()
+            },
+            Rotate,
+        ));
+    }
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+}
+
+fn rotate(mut query: Query<&mut Transform, With<Rotate>>, time: Res<Time>) {
+    for mut transform in &mut query {
+        transform.rotate_x(time.delta_seconds() / 3.0);
+    }
+}
+
+fn draw_cursor_hit(
+    camera: Query<(&Camera, &GlobalTransform)>,
+    windows: Res<Windows>,
+    mut ray_cast: RayCast,
+    mut gizmos: Gizmos,
+) {
+    let (camera, camera_transform) = camera.single();
+    let Some(cursor_position) = windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    // The ray going through the cursor from the camera
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor_position) else {
+        return;
+    };
+
+    if let Some((_, hit)) = ray_cast.cast_ray_closest(ray) {
+        gizmos.circle(hit.point, hit.normal, 0.1, Color::WHITE);
+        gizmos.ray(hit.point, hit.normal * 0.5, Color::RED);
+    }
+}
@@ -0,0 +1,253 @@
+//! Lights the shiny meshes of a room with a [`ReflectionProbe`] capturing its walls, and draws a
+//! mirror on the floor from the image of a camera mirroring the main camera with a
+//! [`PlanarReflection`].
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        view::RenderLayers,
+    },
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugin(MaterialPlugin::<MirrorMaterial>::default())
+        .add_startup_system(setup)
+        .add_system(orbit_camera)
+        .add_system(move_cube)
+        .run();
+}
+
+/// The room the probe captures, centered on the origin above the floor.
+const ROOM_SIZE: Vec3 = Vec3::new(10.0, 4.0, 10.0);
+
+#[derive(Component)]
+struct MainCamera;
+
+#[derive(Component)]
+struct MovingCube;
+
+fn setup(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut mirror_materials: ResMut<Assets<MirrorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // The floor, ceiling and walls of the room, each of its own color
+    let walls = [
+        (
+            Vec3::new(0.0, 0.0, 0.0),
+            Quat::IDENTITY,
+            Color::rgb(0.7, 0.7, 0.7),
+        ),
+        (
+            Vec3::new(0.0, ROOM_SIZE.y, 0.0),
+            Quat::from_rotation_x(PI),
+            Color::WHITE,
+        ),
+        (
+            Vec3::new(-ROOM_SIZE.x / 2.0, ROOM_SIZE.y / 2.0, 0.0),
+            Quat::from_rotation_z(-PI / 2.0),
+            Color::rgb(0.8, 0.2, 0.2),
+        ),
+        (
+            Vec3::new(ROOM_SIZE.x / 2.0, ROOM_SIZE.y / 2.0, 0.0),
+            Quat::from_rotation_z(PI / 2.0),
+            Color::rgb(0.2, 0.7, 0.2),
+        ),
+        (
+            Vec3::new(0.0, ROOM_SIZE.y / 2.0, -ROOM_SIZE.z / 2.0),
+            Quat::from_rotation_x(PI / 2.0),
+            Color::rgb(0.2, 0.3, 0.8),
+        ),
+        (
+            Vec3::new(0.0, ROOM_SIZE.y / 2.0, ROOM_SIZE.z / 2.0),
+            Quat::from_rotation_x(-PI / 2.0),
+            Color::rgb(0.8, 0.7, 0.2),
+        ),
+    ];
+    let wall = meshes.add(Mesh::from(shape::Plane { size: 1.0 }));
+    for (translation, rotation, color) in walls {
+        commands.spawn(PbrBundle {
+            mesh: wall.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                perceptual_roughness: 0.8,
+                ..default()
+            }),
+            transform: Transform {
+                translation,
+                rotation,
+                scale: ROOM_SIZE.max_element() * Vec3::ONE,
+            },
+            ..default()
+        });
+    }
+
+    // Shiny meshes reflecting the walls
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 1.0,
+            ..default()
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            metallic: 1.0,
+            perceptual_roughness: 0.1,
+            ..default()
+        }),
+        transform: Transform::from_xyz(-1.5, 1.0, 0.0),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.5 })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.9, 0.8, 0.6),
+            metallic: 1.0,
+            perceptual_roughness: 0.3,
+            ..default()
+        }),
+        transform: Transform::from_xyz(2.0, 0.75, -1.5).with_rotation(Quat::from_rotation_y(0.5)),
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 0.5 })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::BLACK,
+                emissive: Color::rgb(1.0, 0.4, 0.1),
+                ..default()
+            }),
+            ..default()
+        },
+        MovingCube,
+    ));
+
+    // The probe fills the room, so the reflections are projected onto its walls. The moving cube
+    // is captured again a few times per second
+    commands.spawn(ReflectionProbeBundle {
+        reflection_probe: ReflectionProbe {
+            refresh: ReflectionProbeRefresh::Interval(0.25),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, ROOM_SIZE.y / 2.0, 0.0).with_scale(ROOM_SIZE),
+        ..default()
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1500.0,
+            range: 20.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_xyz(0.0, ROOM_SIZE.y - 0.5, 0.0),
+        ..default()
+    });
+
+    let main_camera = commands
+        .spawn((
+            Camera3dBundle::default(),
+            // The mirror is only drawn by the main camera, which can't render into the image it
+            // samples
+            RenderLayers::from_layers(&[0, 1]),
+            MainCamera,
+        ))
+        .id();
+
+    // The reflection is rendered into an image of the size of the window
+    let window = windows.primary();
+    let size = Extent3d {
+        width: window.physical_width(),
+        height: window.physical_height(),
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    let reflection = images.add(image);
+
+    let mirror = commands
+        .spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(Mesh::from(shape::Plane { size: 3.0 })),
+                material: mirror_materials.add(MirrorMaterial {
+                    reflection: reflection.clone(),
+                }),
+                transform: Transform::from_xyz(1.0, 0.01, 2.0),
+                ..default()
+            },
+            RenderLayers::layer(1),
+        ))
+        .id();
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                // Rendered before the main camera, which samples its image
+                priority: -1,
+                target: RenderTarget::Image(reflection),
+                ..default()
+            },
+            ..default()
+        },
+        PlanarReflection {
+            source: main_camera,
+            plane: mirror,
+        },
+    ));
+}
+
+/// Draws the image of the camera mirroring the main camera, at the screen-space position of its
+/// pixels.
+#[derive(AsBindGroup, TypeUuid, Debug, Clone)]
+#[uuid = "a6a7d1d4-3a0e-4f1b-9f5e-64c0c8f6a9c2"]
+struct MirrorMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    reflection: Handle<Image>,
+}
+
+impl Material for MirrorMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/planar_reflection.wgsl".into()
+    }
+}
+
+fn orbit_camera(time: Res<Time>, mut cameras: Query<&mut Transform, With<MainCamera>>) {
+    let angle = time.elapsed_seconds() * 0.2;
+    for mut transform in &mut cameras {
+        *transform = Transform::from_xyz(angle.cos() * 4.0, 2.5, angle.sin() * 4.0)
+            .looking_at(Vec3::new(0.0, 0.8, 0.0), Vec3::Y);
+    }
+}
+
+fn move_cube(time: Res<Time>, mut cubes: Query<&mut Transform, With<MovingCube>>) {
+    let t = time.elapsed_seconds();
+    for mut transform in &mut cubes {
+        transform.translation = Vec3::new((t * 0.7).cos() * 3.0, 2.5, (t * 0.7).sin() * 3.0);
+        transform.rotation = Quat::from_rotation_y(t);
+    }
+}
@@ -2,7 +2,11 @@
 
 use std::f32::consts::PI;
 
-use bevy::{input::mouse::MouseMotion, prelude::*};
+use bevy::{
+    input::mouse::MouseMotion,
+    pbr::{CascadeDebugVisualization, CascadeShadowConfigBuilder, ShadowFilteringMethod},
+    prelude::*,
+};
 
 fn main() {
     println!(
@@ -15,7 +19,9 @@ fn main() {
     1/2    - decrease/increase point light depth bias
     3/4    - decrease/increase point light normal bias
     5/6    - decrease/increase direction light depth bias
-    7/8    - decrease/increase direction light normal bias"
+    7/8    - decrease/increase direction light normal bias
+    F      - switch shadow filtering method
+    C      - show the cascades of the directional light"
     );
     App::new()
         .add_plugins(DefaultPlugins)
@@ -23,6 +29,8 @@ fn main() {
         .add_system(adjust_point_light_biases)
         .add_system(toggle_light)
         .add_system(adjust_directional_light_biases)
+        .add_system(toggle_shadow_filtering_method)
+        .add_system(toggle_cascade_debug_visualization)
         .add_system(camera_controller)
         .run();
 }
@@ -69,15 +77,6 @@ fn setup(
     commands.spawn(DirectionalLightBundle {
         directional_light: DirectionalLight {
             illuminance: 100000.0,
-            shadow_projection: OrthographicProjection {
-                left: -0.35,
-                right: 500.35,
-                bottom: -0.1,
-                top: 5.0,
-                near: -5.0,
-                far: 5.0,
-                ..default()
-            },
             shadow_depth_bias: 0.0,
             shadow_normal_bias: 0.0,
             shadows_enabled: true,
@@ -89,6 +88,12 @@ fn setup(
             PI / 2.,
             -PI / 4.,
         )),
+        cascade_shadow_config: CascadeShadowConfigBuilder {
+            first_cascade_far_bound: 5.0,
+            maximum_distance: spawn_plane_depth,
+            ..default()
+        }
+        .into(),
         ..default()
     });
 
@@ -100,6 +105,7 @@ fn setup(
             ..default()
         },
         CameraController::default(),
+        ShadowFilteringMethod::Hardware2x2,
     ));
 
     for z_i32 in -spawn_plane_depth as i32..=0 {
@@ -146,6 +152,46 @@ fn toggle_light(
     }
 }
 
+fn toggle_shadow_filtering_method(
+    input: Res<Input<KeyCode>>,
+    mut filter_methods: Query<&mut ShadowFilteringMethod>,
+) {
+    if input.just_pressed(KeyCode::F) {
+        for mut filter_method in &mut filter_methods {
+            *filter_method = match *filter_method {
+                ShadowFilteringMethod::Hardware2x2 => {
+                    println!("Using ShadowFilteringMethod::Castano13");
+                    ShadowFilteringMethod::Castano13
+                }
+                ShadowFilteringMethod::Castano13 => {
+                    println!("Using ShadowFilteringMethod::Hardware2x2");
+                    ShadowFilteringMethod::Hardware2x2
+                }
+            };
+        }
+    }
+}
+
+fn toggle_cascade_debug_visualization(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    cameras: Query<(Entity, Option<&CascadeDebugVisualization>), With<Camera>>,
+) {
+    if input.just_pressed(KeyCode::C) {
+        for (camera, visualization) in &cameras {
+            if visualization.is_some() {
+                println!("Hiding the cascades");
+                commands
+                    .entity(camera)
+                    .remove::<CascadeDebugVisualization>();
+            } else {
+                println!("Showing the cascades");
+                commands.entity(camera).insert(CascadeDebugVisualization);
+            }
+        }
+    }
+}
+
 fn adjust_point_light_biases(input: Res<Input<KeyCode>>, mut query: Query<&mut PointLight>) {
     let depth_bias_step_size = 0.01;
     let normal_bias_step_size = 0.1;
@@ -0,0 +1,157 @@
+//! Illustrates screen space ambient occlusion, which darkens the creases and corners of a scene.
+
+use bevy::{
+    pbr::{ScreenSpaceAmbientOcclusionBundle, ScreenSpaceAmbientOcclusionQualityLevel},
+    prelude::*,
+};
+
+fn main() {
+    App::new()
+        .insert_resource(AmbientLight {
+            brightness: 5.0,
+            ..default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(update_ssao_settings)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(-2.0, 2.0, -2.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        // Adds the settings along with the depth and normal prepasses the occlusion is computed
+        // from
+        ScreenSpaceAmbientOcclusionBundle::default(),
+    ));
+
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.5, 0.5, 0.5),
+        perceptual_roughness: 1.0,
+        reflectance: 0.0,
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: material.clone(),
+        transform: Transform::from_xyz(0.0, 0.0, 1.0),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: material.clone(),
+        transform: Transform::from_xyz(0.0, -1.0, 0.0),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material,
+        transform: Transform::from_xyz(1.0, 0.0, 0.0),
+        ..default()
+    });
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::UVSphere {
+            radius: 0.4,
+            sectors: 72,
+            stacks: 36,
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.4, 0.4, 0.4),
+            perceptual_roughness: 1.0,
+            reflectance: 0.0,
+            ..default()
+        }),
+        ..default()
+    });
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 3000.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(
+            EulerRot::ZYX,
+            0.0,
+            -2.5,
+            -std::f32::consts::FRAC_PI_4,
+        )),
+        ..default()
+    });
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 26.0,
+                color: Color::BLACK,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+fn update_ssao_settings(
+    camera: Query<(Entity, Option<&ScreenSpaceAmbientOcclusionSettings>), With<Camera>>,
+    mut text: Query<&mut Text>,
+    mut commands: Commands,
+    keycode: Res<Input<KeyCode>>,
+) {
+    let (camera_entity, ssao_settings) = camera.single();
+
+    let levels = [
+        (KeyCode::Key1, ScreenSpaceAmbientOcclusionQualityLevel::Low),
+        (
+            KeyCode::Key2,
+            ScreenSpaceAmbientOcclusionQualityLevel::Medium,
+        ),
+        (KeyCode::Key3, ScreenSpaceAmbientOcclusionQualityLevel::High),
+        (
+            KeyCode::Key4,
+            ScreenSpaceAmbientOcclusionQualityLevel::Ultra,
+        ),
+    ];
+    for (key, quality_level) in levels {
+        if keycode.just_pressed(key) {
+            commands
+                .entity(camera_entity)
+                .insert(ScreenSpaceAmbientOcclusionSettings { quality_level });
+        }
+    }
+    if keycode.just_pressed(KeyCode::Space) {
+        commands
+            .entity(camera_entity)
+            .remove::<ScreenSpaceAmbientOcclusionSettings>();
+    }
+
+    let current_level = ssao_settings.map(|settings| settings.quality_level);
+    let selected = |selected: bool| if selected { "(*)" } else { "( )" };
+    let mut text = text.single_mut();
+    let text = &mut text.sections[0].value;
+    *text = format!("{} SSAO Off (Space)\n", selected(current_level.is_none()));
+    for (index, (_, quality_level)) in levels.iter().enumerate() {
+        text.push_str(&format!(
+            "{} SSAO {:?} ({})\n",
+            selected(current_level == Some(*quality_level)),
+            quality_level,
+            index + 1
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+//! Generates a [`Terrain`] from a procedural heightmap, textured with a splat map blending sand,
+//! grass, rock and snow, while the camera flies over it and its chunks follow the camera.
+
+use std::f32::consts::PI;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{
+            AddressMode, Extent3d, SamplerDescriptor, TextureDimension, TextureFormat,
+        },
+        texture::ImageSampler,
+    },
+};
+
+const HEIGHTMAP_SIZE: u32 = 257;
+const LAYER_SIZE: u32 = 64;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(fly_camera)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<TerrainMaterial>>,
+) {
+    let heights = heights();
+    let heightmap = Image::new(
+        Extent3d {
+            width: HEIGHTMAP_SIZE,
+            height: HEIGHTMAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        heights
+            .iter()
+            .flat_map(|height| height.to_le_bytes())
+            .collect(),
+        TextureFormat::R32Float,
+    );
+
+    // Sand near the bottom, rock on the steep slopes, and snow near the top
+    let mut splat_map = Vec::with_capacity(heights.len() * 4);
+    for (i, height) in heights.iter().enumerate() {
+        let (x, y) = (i as u32 % HEIGHTMAP_SIZE, i as u32 / HEIGHTMAP_SIZE);
+        let next = |dx: u32, dy: u32| {
+            let (x, y) = (
+                (x + dx).min(HEIGHTMAP_SIZE - 1),
+                (y + dy).min(HEIGHTMAP_SIZE - 1),
+            );
+            heights[(y * HEIGHTMAP_SIZE + x) as usize]
+        };
+        let steepness = ((next(1, 0) - height).abs() + (next(0, 1) - height).abs()) * 15.0;
+        let sand = 1.0 - (height * 8.0).clamp(0.0, 1.0);
+        let snow = ((height - 0.6) * 8.0).clamp(0.0, 1.0);
+        let rock = steepness.clamp(0.0, 1.0) * (1.0 - snow);
+        let grass = (1.0 - sand - snow - rock).max(0.0);
+        splat_map.extend([sand, grass, rock, snow].map(|weight| (weight * 255.0) as u8));
+    }
+    let splat_map = Image::new(
+        Extent3d {
+            width: HEIGHTMAP_SIZE,
+            height: HEIGHTMAP_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        splat_map,
+        TextureFormat::Rgba8Unorm,
+    );
+
+    commands.spawn(TerrainBundle {
+        terrain: Terrain {
+            heightmap: images.add(heightmap),
+            size: Vec2::splat(200.0),
+            height: 30.0,
+            max_depth: 5,
+            ..default()
+        },
+        material: materials.add(TerrainMaterial {
+            splat_map: images.add(splat_map),
+            layers: images.add(layers()),
+            layer_size: 4.0,
+            ..default()
+        }),
+        ..default()
+    });
+
+    commands.spawn(DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            illuminance: 20000.0,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            PI / 4.0,
+            -PI / 5.0,
+            0.0,
+        )),
+        ..default()
+    });
+    commands.spawn((
+        Camera3dBundle::default(),
+        FogSettings {
+            color: Color::rgb(0.6, 0.7, 0.8),
+            falloff: FogFalloff::Linear {
+                start: 50.0,
+                end: 200.0,
+            },
+            ..default()
+        },
+    ));
+    commands.insert_resource(ClearColor(Color::rgb(0.6, 0.7, 0.8)));
+}
+
+/// Hills made of a few sine waves, from `0.0` to `1.0`.
+fn heights() -> Vec<f32> {
+    (0..HEIGHTMAP_SIZE * HEIGHTMAP_SIZE)
+        .map(|i| {
+            let x = (i % HEIGHTMAP_SIZE) as f32 / (HEIGHTMAP_SIZE - 1) as f32 * 2.0 * PI;
+            let y = (i / HEIGHTMAP_SIZE) as f32 / (HEIGHTMAP_SIZE - 1) as f32 * 2.0 * PI;
+            let hills = (x * 1.5).sin() * (y * 2.0).cos() * 0.5
+                + (x * 4.0 + y * 3.0).sin() * 0.2
+                + (x * 11.0).cos() * (y * 13.0).sin() * 0.05;
+            (hills * 0.6 + 0.45).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// An array texture with a noisy layer for each of the colors of the splat map, which repeats
+/// across the terrain.
+fn layers() -> Image {
+    let colors = [
+        Color::rgb(0.76, 0.7, 0.5),
+        Color::rgb(0.25, 0.45, 0.15),
+        Color::rgb(0.4, 0.38, 0.36),
+        Color::rgb(0.95, 0.95, 0.97),
+    ];
+    let mut data = Vec::new();
+    for color in colors {
+        for i in 0..LAYER_SIZE * LAYER_SIZE {
+            let (x, y) = (i % LAYER_SIZE, i / LAYER_SIZE);
+            // A cheap hash of the texel, tiling seamlessly
+            let noise =
+                ((x.wrapping_mul(73_856_093) ^ y.wrapping_mul(19_349_663)) % 256) as f32 / 255.0;
+            let shade = 0.85 + noise * 0.3;
+            let [r, g, b, _] = color.as_rgba_f32();
+            data.extend(
+                [r * shade, g * shade, b * shade].map(|channel| (channel.min(1.0) * 255.0) as u8),
+            );
+            data.push(255);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: LAYER_SIZE,
+            height: LAYER_SIZE,
+            depth_or_array_layers: colors.len() as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        ..ImageSampler::linear_descriptor()
+    });
+    image
+}
+
+fn fly_camera(
+    time: Res<Time>,
+    images: Res<Assets<Image>>,
+    terrains: Query<&Terrain>,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+) {
+    let terrain = terrains.single();
+    let Some(heightmap) = images.get(&terrain.heightmap) else {
+        return;
+    };
+    let t = time.elapsed_seconds() * 0.1;
+    let position = Vec2::new(t.cos(), t.sin()) * 60.0;
+    let ahead = Vec2::new((t + 0.2).cos(), (t + 0.2).sin()) * 60.0;
+    let ground = terrain.height_at(heightmap, position).unwrap_or(0.0);
+    for mut transform in &mut cameras {
+        *transform = Transform::from_xyz(position.x, ground + 8.0, position.y)
+            .looking_at(Vec3::new(ahead.x, ground + 2.0, ahead.y), Vec3::Y);
+    }
+}
@@ -0,0 +1,146 @@
+//! Streams the textures of a long row of panels, which only keep in video memory the mip levels
+//! they are drawn at, under a budget small enough for the far ones to get blurrier.
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{TextureResidency, TextureStreamingSettings},
+    },
+};
+
+fn main() {
+    App::new()
+        .insert_resource(TextureStreamingSettings {
+            enabled: true,
+            budget: 4 * 1024 * 1024,
+            ..default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(move_camera)
+        .add_system(update_text)
+        .run();
+}
+
+const PANELS: usize = 24;
+const PANEL_SPACING: f32 = 3.0;
+const TEXTURE_SIZE: u32 = 1024;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    let panel = meshes.add(Mesh::from(shape::Quad::new(Vec2::splat(2.0))));
+    for i in 0..PANELS {
+        let color = Color::hsl(i as f32 * 360.0 / PANELS as f32, 0.7, 0.5);
+        let side = if i % 2 == 0 { -1.5 } else { 1.5 };
+        commands.spawn(PbrBundle {
+            mesh: panel.clone(),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(images.add(checkerboard(color))),
+                unlit: true,
+                ..default()
+            }),
+            // Facing the middle of the row
+            transform: Transform::from_xyz(side, 1.0, -(i as f32) * PANEL_SPACING)
+                .looking_at(Vec3::new(2.0 * side, 1.0, -(i as f32) * PANEL_SPACING), Vec3::Y),
+            ..default()
+        });
+    }
+
+    commands.spawn(Camera3dBundle::default());
+
+    commands.spawn(
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+    );
+}
+
+/// A checkerboard whose mip chain is generated from its first level, like images loaded from png
+/// files.
+fn checkerboard(color: Color) -> Image {
+    let [r, g, b, _] = color.as_rgba_f32().map(|channel| (channel * 255.0) as u8);
+    let mut data = Vec::with_capacity((TEXTURE_SIZE * TEXTURE_SIZE * 4) as usize);
+    for y in 0..TEXTURE_SIZE {
+        for x in 0..TEXTURE_SIZE {
+            // Thin lines between the squares, which flicker without the smaller mip levels
+            let line = x % 64 < 2 || y % 64 < 2;
+            let texel = if line {
+                [255, 255, 255, 255]
+            } else if (x / 64 + y / 64) % 2 == 0 {
+                [r, g, b, 255]
+            } else {
+                [r / 3, g / 3, b / 3, 255]
+            };
+            data.extend(texel);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.generate_mipmaps = true;
+    image
+}
+
+fn move_camera(time: Res<Time>, mut cameras: Query<&mut Transform, With<Camera>>) {
+    // Back and forth along the row
+    let length = PANELS as f32 * PANEL_SPACING;
+    let z = (1.0 - (time.elapsed_seconds() * 0.1).cos()) / 2.0 * -length + 4.0;
+    for mut transform in &mut cameras {
+        *transform =
+            Transform::from_xyz(0.0, 1.0, z).looking_at(Vec3::new(0.0, 1.0, z - 1.0), Vec3::Y);
+    }
+}
+
+fn update_text(
+    settings: Res<TextureStreamingSettings>,
+    residency: Res<TextureResidency>,
+    mut texts: Query<&mut Text>,
+) {
+    let mut levels = [0; 11];
+    for (_, image) in residency.iter() {
+        levels[(image.resident_mip_level as usize).min(levels.len() - 1)] += 1;
+    }
+    let levels: Vec<_> = levels
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(level, count)| format!("{count} from level {level}"))
+        .collect();
+
+    for mut text in &mut texts {
+        text.sections[0].value = format!(
+            "Resident: {:.2} MiB of {:.2} MiB\nTextures: {}",
+            residency.resident_bytes() as f32 / (1024.0 * 1024.0),
+            settings.budget as f32 / (1024.0 * 1024.0),
+            levels.join(", ")
+        );
+    }
+}
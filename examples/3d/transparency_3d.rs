@@ -75,6 +75,25 @@ fn setup(
         transform: Transform::from_xyz(0.0, 0.5, 0.0),
         ..default()
     });
+    // additive sphere, uses `alpha_mode: Add`
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(
+            Mesh::try_from(shape::Icosphere {
+                radius: 0.5,
+                subdivisions: 3,
+            })
+            .unwrap(),
+        ),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.9, 0.4, 0.1, 0.0),
+            // Add only brightens what is behind the object, like a glowing effect.
+            alpha_mode: AlphaMode::Add,
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(1.5, 0.5, 0.5),
+        ..default()
+    });
     // opaque sphere
     commands.spawn(PbrBundle {
         mesh: meshes.add(
@@ -111,6 +130,7 @@ fn setup(
 /// - `Mask(f32)`: Object appears when the alpha value goes above the mask's threshold, disappears
 ///                when the alpha value goes back below the threshold.
 /// - `Blend`: Object fades in and out smoothly.
+/// - `Add`: Object brightens what is behind it more and more, without ever hiding it.
 pub fn fade_transparency(time: Res<Time>, mut materials: ResMut<Assets<StandardMaterial>>) {
     let alpha = (time.elapsed_seconds().sin() / 2.0) + 0.5;
     for (_, material) in materials.iter_mut() {
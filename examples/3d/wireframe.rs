@@ -13,6 +13,7 @@ fn main() {
                 features: WgpuFeatures::POLYGON_MODE_LINE,
                 ..default()
             },
+            ..default()
         }))
         .add_plugin(WireframePlugin)
         .add_startup_system(setup)
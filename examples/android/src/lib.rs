@@ -17,6 +17,7 @@ fn main() {
                 priority: WgpuSettingsPriority::Compatibility,
                 ..default()
             },
+            ..default()
         }))
         .add_startup_system(setup)
         .run();
@@ -0,0 +1,86 @@
+//! Deforms a sphere with morph targets defined in code, blended by animated weights.
+
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::mesh::{
+        morph::{MeshMorphWeights, MorphAttributes},
+        VertexAttributeValues,
+    },
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(animate_weights)
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 1.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    let mut mesh = Mesh::from(shape::UVSphere {
+        radius: 1.0,
+        sectors: 64,
+        stacks: 32,
+    });
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        unreachable!();
+    };
+    let positions: Vec<Vec3> = positions.iter().copied().map(Vec3::from).collect();
+
+    // The first target stretches the sphere vertically, and the second one grows ridges around
+    // its vertical axis. Only the positions are displaced, so the normals stay the sphere's.
+    let stretch = positions
+        .iter()
+        .map(|position| MorphAttributes {
+            position: Vec3::new(0.0, position.y, 0.0),
+            ..default()
+        })
+        .collect::<Vec<_>>();
+    let ridges = positions
+        .iter()
+        .map(|position| {
+            let angle = position.z.atan2(position.x);
+            MorphAttributes {
+                position: *position * (angle * 4.0).cos().powi(2) * 0.3,
+                ..default()
+            }
+        })
+        .collect::<Vec<_>>();
+    mesh.set_morph_targets([stretch, ridges]).unwrap();
+
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(Color::rgb(0.8, 0.5, 0.3).into()),
+            ..default()
+        },
+        MeshMorphWeights::new(vec![0.0, 0.0]).unwrap(),
+    ));
+}
+
+/// Blends the targets in and out at different rates.
+fn animate_weights(mut morph_weights: Query<&mut MeshMorphWeights>, time: Res<Time>) {
+    let t = time.elapsed_seconds();
+    for mut morph_weights in &mut morph_weights {
+        let weights = morph_weights.weights_mut();
+        weights[0] = (t * TAU / 4.0).sin() * 0.5 + 0.5;
+        weights[1] = (t * TAU / 3.0).sin() * 0.5 + 0.5;
+    }
+}
@@ -0,0 +1,108 @@
+//! Renders a scene without a window into an image, reads it back to the CPU and saves it as a
+//! png file. This is useful to generate thumbnails on a server or to compare renders in tests.
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{ImageReadbackEvent, ImageReadbacks},
+    },
+    utils::Duration,
+    winit::WinitPlugin,
+};
+
+// The number of frames rendered before reading the image back, so that the assets are loaded.
+const FRAMES_BEFORE_READBACK: u32 = 10;
+
+fn main() {
+    App::new()
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    add_primary_window: false,
+                    // there are no windows, which would otherwise exit the app right away
+                    exit_on_all_closed: false,
+                    ..default()
+                })
+                .disable::<WinitPlugin>(),
+        )
+        .add_plugin(ScheduleRunnerPlugin)
+        .add_startup_system(setup)
+        .add_system(request_readback)
+        .add_system(save_readback)
+        .run();
+}
+
+#[derive(Resource)]
+struct RenderedImage(Handle<Image>);
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // The image the camera renders to. It is given the texture usages needed to render to it and
+    // read it back automatically.
+    let image = images.add(Image::new_fill(
+        Extent3d {
+            width: 512,
+            height: 512,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    commands.insert_resource(RenderedImage(image.clone()));
+
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+        ..default()
+    });
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(image),
+            ..default()
+        },
+        transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn request_readback(
+    mut frames: Local<u32>,
+    rendered_image: Res<RenderedImage>,
+    mut readbacks: ResMut<ImageReadbacks>,
+) {
+    *frames += 1;
+    if *frames == FRAMES_BEFORE_READBACK {
+        readbacks.request(rendered_image.0.clone());
+    }
+}
+
+fn save_readback(
+    mut readback_events: EventReader<ImageReadbackEvent>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    for event in readback_events.iter() {
+        match event.image.clone().try_into_dynamic() {
+            Ok(image) => match image.save("headless_render.png") {
+                Ok(()) => info!("Saved the rendered image to headless_render.png"),
+                Err(err) => error!("Failed to save the rendered image: {err}"),
+            },
+            Err(err) => error!("Failed to convert the rendered image: {err}"),
+        }
+        app_exit_events.send(AppExit);
+    }
+}
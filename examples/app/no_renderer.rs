@@ -16,6 +16,7 @@ fn main() {
                 backends: None,
                 ..default()
             },
+            ..default()
         }))
         .run();
 }
@@ -9,6 +9,7 @@ use bevy::{
         extract_resource::{ExtractResource, ExtractResourcePlugin},
         render_asset::RenderAssets,
         render_graph::{self, RenderGraph},
+        render_phase::TrackedComputePass,
         render_resource::*,
         renderer::{RenderContext, RenderDevice},
         RenderApp, RenderStage,
@@ -141,6 +142,7 @@ impl FromWorld for GameOfLifePipeline {
         let init_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label: None,
             layout: Some(vec![texture_bind_group_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             shader: shader.clone(),
             shader_defs: vec![],
             entry_point: Cow::from("init"),
@@ -148,6 +150,7 @@ impl FromWorld for GameOfLifePipeline {
         let update_pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
             label: None,
             layout: Some(vec![texture_bind_group_layout.clone()]),
+            push_constant_ranges: Vec::new(),
             shader,
             shader_defs: vec![],
             entry_point: Cow::from("update"),
@@ -214,9 +217,11 @@ impl render_graph::Node for GameOfLifeNode {
         let pipeline_cache = world.resource::<PipelineCache>();
         let pipeline = world.resource::<GameOfLifePipeline>();
 
-        let mut pass = render_context
-            .command_encoder
-            .begin_compute_pass(&ComputePassDescriptor::default());
+        let mut pass = TrackedComputePass::new(
+            render_context
+                .command_encoder
+                .begin_compute_pass(&ComputePassDescriptor::default()),
+        );
 
         pass.set_bind_group(0, texture_bind_group, &[]);
 
@@ -227,14 +232,14 @@ impl render_graph::Node for GameOfLifeNode {
                 let init_pipeline = pipeline_cache
                     .get_compute_pipeline(pipeline.init_pipeline)
                     .unwrap();
-                pass.set_pipeline(init_pipeline);
+                pass.set_compute_pipeline(init_pipeline);
                 pass.dispatch_workgroups(SIZE.0 / WORKGROUP_SIZE, SIZE.1 / WORKGROUP_SIZE, 1);
             }
             GameOfLifeState::Update => {
                 let update_pipeline = pipeline_cache
                     .get_compute_pipeline(pipeline.update_pipeline)
                     .unwrap();
-                pass.set_pipeline(update_pipeline);
+                pass.set_compute_pipeline(update_pipeline);
                 pass.dispatch_workgroups(SIZE.0 / WORKGROUP_SIZE, SIZE.1 / WORKGROUP_SIZE, 1);
             }
         }
@@ -10,7 +10,7 @@ use bevy::{
     prelude::*,
     render::{
         extract_component::{ExtractComponent, ExtractComponentPlugin},
-        mesh::{GpuBufferInfo, MeshVertexBufferLayout},
+        mesh::{GpuBufferInfo, MeshBufferArena, MeshVertexBufferLayout},
         render_asset::RenderAssets,
         render_phase::{
             AddRenderCommand, DrawFunctions, EntityRenderCommand, RenderCommandResult, RenderPhase,
@@ -128,6 +128,7 @@ fn queue_custom(
                     pipeline,
                     draw_function: draw_custom,
                     distance: rangefinder.distance(&mesh_uniform.transform),
+                    batch_range: None,
                 });
             }
         }
@@ -226,6 +227,7 @@ pub struct DrawMeshInstanced;
 impl EntityRenderCommand for DrawMeshInstanced {
     type Param = (
         SRes<RenderAssets<Mesh>>,
+        SRes<MeshBufferArena>,
         SQuery<Read<Handle<Mesh>>>,
         SQuery<Read<InstanceBuffer>>,
     );
@@ -233,7 +235,11 @@ impl EntityRenderCommand for DrawMeshInstanced {
     fn render<'w>(
         _view: Entity,
         item: Entity,
-        (meshes, mesh_query, instance_buffer_query): SystemParamItem<'w, '_, Self::Param>,
+        (meshes, mesh_buffers, mesh_query, instance_buffer_query): SystemParamItem<
+            'w,
+            '_,
+            Self::Param,
+        >,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
         let mesh_handle = mesh_query.get(item).unwrap();
@@ -243,22 +249,34 @@ impl EntityRenderCommand for DrawMeshInstanced {
             Some(gpu_mesh) => gpu_mesh,
             None => return RenderCommandResult::Failure,
         };
+        // The mesh is stored in buffers shared with other meshes, from these offsets
+        let slices = match mesh_buffers.into_inner().get(&gpu_mesh.allocation) {
+            Some(slices) => slices,
+            None => return RenderCommandResult::Failure,
+        };
 
-        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(0, slices.vertex_slice());
         pass.set_vertex_buffer(1, instance_buffer.buffer.slice(..));
 
-        match &gpu_mesh.buffer_info {
-            GpuBufferInfo::Indexed {
-                buffer,
-                index_format,
-                count,
-            } => {
-                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
-                pass.draw_indexed(0..*count, 0, 0..instance_buffer.length as u32);
+        match (&gpu_mesh.buffer_info, slices.index_buffer) {
+            (
+                GpuBufferInfo::Indexed {
+                    index_format,
+                    count,
+                },
+                Some(index_buffer),
+            ) => {
+                pass.set_index_buffer(index_buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(
+                    slices.first_index..slices.first_index + count,
+                    0,
+                    0..instance_buffer.length as u32,
+                );
             }
-            GpuBufferInfo::NonIndexed { vertex_count } => {
+            (GpuBufferInfo::NonIndexed { vertex_count }, _) => {
                 pass.draw(0..*vertex_count, 0..instance_buffer.length as u32);
             }
+            _ => return RenderCommandResult::Failure,
         }
         RenderCommandResult::Success
     }
@@ -9,18 +9,29 @@
 //!
 //! To start the demo using the spherical layout run
 //! `cargo run --example many_cubes --release sphere`
+//!
+//! Add `indirect` to the arguments to draw the meshes with indirect draw calls.
 
 use std::f64::consts::PI;
 
 use bevy::{
     diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
     math::{DVec2, DVec3},
+    pbr::MeshDrawMode,
     prelude::*,
+    render::renderer::GpuTimestampDiagnosticsPlugin,
     window::PresentMode,
 };
 
 fn main() {
+    let draw_mode = if std::env::args().any(|arg| arg == "indirect") {
+        MeshDrawMode::Indirect
+    } else {
+        MeshDrawMode::Direct
+    };
+
     App::new()
+        .insert_resource(draw_mode)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
                 present_mode: PresentMode::AutoNoVsync,
@@ -29,6 +40,7 @@ fn main() {
             ..default()
         }))
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(GpuTimestampDiagnosticsPlugin)
         .add_plugin(LogDiagnosticsPlugin::default())
         .add_startup_system(setup)
         .add_system(move_camera)
@@ -0,0 +1,48 @@
+//! Saves a screenshot of the window when pressing the space bar.
+
+use bevy::{
+    prelude::*,
+    render::view::{ScreenshotEvent, Screenshots},
+    window::WindowId,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup)
+        .add_system(request_screenshot)
+        .add_system(save_screenshot)
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.25, 0.25, 0.75),
+            custom_size: Some(Vec2::new(200.0, 100.0)),
+            ..default()
+        },
+        ..default()
+    });
+}
+
+fn request_screenshot(input: Res<Input<KeyCode>>, mut screenshots: ResMut<Screenshots>) {
+    if input.just_pressed(KeyCode::Space) {
+        screenshots.request(WindowId::primary());
+    }
+}
+
+fn save_screenshot(mut screenshot_events: EventReader<ScreenshotEvent>, mut count: Local<u32>) {
+    for event in screenshot_events.iter() {
+        let path = format!("screenshot-{}.png", *count);
+        *count += 1;
+        match event.image.clone().try_into_dynamic() {
+            Ok(image) => match image.save(&path) {
+                Ok(()) => info!("Saved a screenshot to {path}"),
+                Err(err) => error!("Failed to save the screenshot: {err}"),
+            },
+            Err(err) => error!("Failed to convert the screenshot: {err}"),
+        }
+    }
+}